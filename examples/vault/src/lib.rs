@@ -0,0 +1,234 @@
+//! An example vault program built on `solana_contracts`
+//!
+//! Each user's deposits sit in the associated token account of a PDA of
+//! this program, the user's vault authority. Deposits are plain transfers
+//! the user signs; withdrawals are signed by the vault authority through
+//! `solana_contracts::cpi::transfer_with_seed`.
+
+use solana_contracts::{associated::get_associated_token_address, cpi, instruction};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+/// Seed of a user's vault authority, followed by the user's address.
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Derives `user`'s vault authority and its bump seed.
+pub fn get_vault_authority(user: &Pubkey, vault_program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, user.as_ref()], vault_program_id)
+}
+
+/// Derives `user`'s vault token account for `mint`: the associated token
+/// account of the user's vault authority.
+pub fn get_vault_address(
+    user: &Pubkey,
+    mint: &Pubkey,
+    vault_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Pubkey {
+    let authority = get_vault_authority(user, vault_program_id).0;
+    get_associated_token_address(&authority, mint, token_program_id)
+}
+
+/// Instructions supported by the vault program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VaultInstruction {
+    /// Moves tokens from the user's token account into their vault, creating
+    /// the vault token account first if it doesn't exist.
+    ///
+    ///   0. `[writable]` The user's token account.
+    ///   1. `[writable]` The user's vault token account.
+    ///   2. `[writable, signer]` The user, funding the vault token account.
+    ///   3. `[]` The user's vault authority.
+    ///   4. `[]` The token mint.
+    ///   5. `[]` System program.
+    ///   6. `[]` The token program.
+    Deposit { amount: u64 },
+    /// Moves tokens from the user's vault back to a token account.
+    ///
+    ///   0. `[writable]` The destination token account.
+    ///   1. `[writable]` The user's vault token account.
+    ///   2. `[signer]` The user.
+    ///   3. `[]` The user's vault authority.
+    ///   4. `[]` The token program.
+    Withdraw { amount: u64 },
+}
+
+impl VaultInstruction {
+    /// Unpacks a tag, 0 to deposit or 1 to withdraw, then the amount as 8
+    /// little-endian bytes.
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, amount) = input
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let amount = amount
+            .try_into()
+            .map(u64::from_le_bytes)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        match tag {
+            0 => Ok(Self::Deposit { amount }),
+            1 => Ok(Self::Withdraw { amount }),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let (tag, amount) = match self {
+            Self::Deposit { amount } => (0, amount),
+            Self::Withdraw { amount } => (1, amount),
+        };
+        let mut buf = vec![tag];
+        buf.extend_from_slice(&amount.to_le_bytes());
+        buf
+    }
+}
+
+/// Creates a `Deposit` instruction.
+pub fn deposit(
+    vault_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    user_token_pubkey: &Pubkey,
+    user_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *vault_program_id,
+        accounts: vec![
+            AccountMeta::new(*user_token_pubkey, false),
+            AccountMeta::new(
+                get_vault_address(user_pubkey, mint_pubkey, vault_program_id, token_program_id),
+                false,
+            ),
+            AccountMeta::new(*user_pubkey, true),
+            AccountMeta::new_readonly(get_vault_authority(user_pubkey, vault_program_id).0, false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program_id, false),
+        ],
+        data: VaultInstruction::Deposit { amount }.pack(),
+    }
+}
+
+/// Creates a `Withdraw` instruction.
+pub fn withdraw(
+    vault_program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    destination_pubkey: &Pubkey,
+    user_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *vault_program_id,
+        accounts: vec![
+            AccountMeta::new(*destination_pubkey, false),
+            AccountMeta::new(
+                get_vault_address(user_pubkey, mint_pubkey, vault_program_id, token_program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*user_pubkey, true),
+            AccountMeta::new_readonly(get_vault_authority(user_pubkey, vault_program_id).0, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+        ],
+        data: VaultInstruction::Withdraw { amount }.pack(),
+    }
+}
+
+/// Processes a [`VaultInstruction`].
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    match VaultInstruction::unpack(input)? {
+        VaultInstruction::Deposit { amount } => process_deposit(program_id, accounts, amount),
+        VaultInstruction::Withdraw { amount } => process_withdraw(program_id, accounts, amount),
+    }
+}
+
+fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_token = next_account_info(account_info_iter)?;
+    let vault_token = next_account_info(account_info_iter)?;
+    let user = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    // Only the user's own vault takes deposits, so nothing is stranded in an
+    // account the user can't withdraw from.
+    if get_vault_authority(user.key, program_id).0 != *authority.key
+        || get_associated_token_address(authority.key, mint.key, token_program.key)
+            != *vault_token.key
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if vault_token.lamports() == 0 {
+        invoke(
+            &instruction::create_associated_account(
+                token_program.key,
+                user.key,
+                authority.key,
+                mint.key,
+            )?,
+            &[
+                user.clone(),
+                vault_token.clone(),
+                authority.clone(),
+                mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    }
+
+    // The user already signed this instruction, so no seeds are needed.
+    cpi::transfer(
+        cpi::CpiAccounts {
+            source: user_token,
+            destination: vault_token,
+            authority: user,
+            token_program,
+        },
+        amount,
+        &[],
+    )
+}
+
+fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let destination = next_account_info(account_info_iter)?;
+    let vault_token = next_account_info(account_info_iter)?;
+    let user = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !user.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    // The token program rejects the transfer unless the vault authority owns
+    // `vault_token`, and `transfer_with_seed` unless `authority` is the user's.
+    cpi::transfer_with_seed(
+        cpi::CpiAccounts {
+            source: vault_token,
+            destination,
+            authority,
+            token_program,
+        },
+        amount,
+        program_id,
+        &[VAULT_SEED, user.key.as_ref()],
+    )
+}
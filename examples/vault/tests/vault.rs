@@ -0,0 +1,285 @@
+use solana_contracts::{error::TokenError, instruction, Mint, Token};
+use solana_program::{
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    hash::Hash,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use vault::{get_vault_address, get_vault_authority, VAULT_SEED};
+
+struct Env {
+    banks_client: BanksClient,
+    payer: Keypair,
+    recent_blockhash: Hash,
+    token_program_id: Pubkey,
+    vault_program_id: Pubkey,
+    mint: Keypair,
+}
+
+impl Env {
+    /// Starts a bank running both programs, with a mint the payer mints.
+    async fn start() -> Self {
+        let token_program_id = Pubkey::new_unique();
+        let vault_program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_contracts",
+            token_program_id,
+            processor!(solana_contracts::process_instruction),
+        );
+        program_test.add_program(
+            "vault",
+            vault_program_id,
+            processor!(vault::process_instruction),
+        );
+        let (banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut env = Env {
+            banks_client,
+            payer,
+            recent_blockhash,
+            token_program_id,
+            vault_program_id,
+            mint: Keypair::new(),
+        };
+
+        let mint = env.mint.insecure_clone();
+        env.create_account(&mint, Mint::LEN).await;
+        let initialize = instruction::initialize_mint(
+            &token_program_id,
+            &mint.pubkey(),
+            &env.payer.pubkey(),
+            None,
+            6,
+            None,
+        )
+        .unwrap();
+        env.process(&[initialize], &[]).await.unwrap();
+        env
+    }
+
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.recent_blockhash,
+        );
+        self.banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|e| e.unwrap())
+    }
+
+    async fn create_account(&mut self, account: &Keypair, len: usize) {
+        let rent = self.banks_client.get_rent().await.unwrap();
+        let create = system_instruction::create_account(
+            &self.payer.pubkey(),
+            &account.pubkey(),
+            rent.minimum_balance(len),
+            len as u64,
+            &self.token_program_id,
+        );
+        self.process(&[create], &[account]).await.unwrap();
+    }
+
+    /// Creates a token account of the mint holding `amount`.
+    async fn create_token_account(&mut self, owner: &Pubkey, amount: u64) -> Pubkey {
+        let account = Keypair::new();
+        self.create_account(&account, Token::LEN).await;
+        let token_program_id = self.token_program_id;
+        let initialize = instruction::initialize_account(
+            &token_program_id,
+            &account.pubkey(),
+            &self.mint.pubkey(),
+            owner,
+        )
+        .unwrap();
+        let mint = instruction::mint_tokens(
+            &token_program_id,
+            &self.mint.pubkey(),
+            &account.pubkey(),
+            &self.payer.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.process(&[initialize, mint], &[]).await.unwrap();
+        account.pubkey()
+    }
+
+    /// Creates a user holding `amount` in a funded wallet and token account.
+    async fn create_user(&mut self, amount: u64) -> (Keypair, Pubkey) {
+        let user = Keypair::new();
+        let fund =
+            system_instruction::transfer(&self.payer.pubkey(), &user.pubkey(), 1_000_000_000);
+        self.process(&[fund], &[]).await.unwrap();
+        let token = self.create_token_account(&user.pubkey(), amount).await;
+        (user, token)
+    }
+
+    fn deposit(&self, user_token: &Pubkey, user: &Pubkey, amount: u64) -> Instruction {
+        vault::deposit(
+            &self.vault_program_id,
+            &self.token_program_id,
+            user_token,
+            user,
+            &self.mint.pubkey(),
+            amount,
+        )
+    }
+
+    fn withdraw(&self, destination: &Pubkey, user: &Pubkey, amount: u64) -> Instruction {
+        vault::withdraw(
+            &self.vault_program_id,
+            &self.token_program_id,
+            destination,
+            user,
+            &self.mint.pubkey(),
+            amount,
+        )
+    }
+
+    fn vault_address(&self, user: &Pubkey) -> Pubkey {
+        get_vault_address(
+            user,
+            &self.mint.pubkey(),
+            &self.vault_program_id,
+            &self.token_program_id,
+        )
+    }
+
+    async fn token(&mut self, account: &Pubkey) -> Token {
+        let account = self
+            .banks_client
+            .get_account(*account)
+            .await
+            .unwrap()
+            .unwrap();
+        Token::unpack(&account.data).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn vault_deposits_and_withdraws_through_cpi() {
+    let mut env = Env::start().await;
+    let (user, user_token) = env.create_user(100).await;
+    let vault_token = env.vault_address(&user.pubkey());
+
+    // The first deposit creates the vault token account.
+    let deposit = env.deposit(&user_token, &user.pubkey(), 60);
+    env.process(&[deposit], &[&user]).await.unwrap();
+    let vault = env.token(&vault_token).await;
+    assert_eq!(
+        vault.owner,
+        get_vault_authority(&user.pubkey(), &env.vault_program_id).0
+    );
+    assert_eq!(vault.amount, 60);
+    assert_eq!(env.token(&user_token).await.amount, 40);
+
+    let deposit = env.deposit(&user_token, &user.pubkey(), 15);
+    env.process(&[deposit], &[&user]).await.unwrap();
+    assert_eq!(env.token(&vault_token).await.amount, 75);
+
+    let withdraw = env.withdraw(&user_token, &user.pubkey(), 25);
+    env.process(&[withdraw], &[&user]).await.unwrap();
+    assert_eq!(env.token(&user_token).await.amount, 50);
+    assert_eq!(env.token(&vault_token).await.amount, 50);
+}
+
+#[tokio::test]
+async fn vault_authority_only_signs_for_its_own_user() {
+    let mut env = Env::start().await;
+    let (user, user_token) = env.create_user(100).await;
+    let (thief, thief_token) = env.create_user(0).await;
+    let deposit = env.deposit(&user_token, &user.pubkey(), 60);
+    env.process(&[deposit], &[&user]).await.unwrap();
+
+    // The thief's own vault authority signs, but doesn't own the user's vault.
+    let vault_token = env.vault_address(&user.pubkey());
+    let mut withdraw = env.withdraw(&thief_token, &thief.pubkey(), 60);
+    withdraw.accounts[1].pubkey = vault_token;
+    assert_eq!(
+        env.process(&[withdraw], &[&thief]).await.unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TokenError::OwnerMismatch as u32)
+        )
+    );
+    assert_eq!(env.token(&vault_token).await.amount, 60);
+}
+
+#[tokio::test]
+async fn vault_rejects_an_authority_that_is_not_the_user_pda() {
+    let mut env = Env::start().await;
+    let (user, user_token) = env.create_user(100).await;
+    let deposit = env.deposit(&user_token, &user.pubkey(), 50);
+    env.process(&[deposit], &[&user]).await.unwrap();
+
+    let mut withdraw = env.withdraw(&user_token, &user.pubkey(), 50);
+    withdraw.accounts[3].pubkey = Pubkey::new_unique();
+    assert_eq!(
+        env.process(&[withdraw], &[&user]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
+
+#[tokio::test]
+async fn vault_only_takes_deposits_into_the_user_vault() {
+    let mut env = Env::start().await;
+    let (user, user_token) = env.create_user(100).await;
+    let other = env.create_token_account(&Pubkey::new_unique(), 0).await;
+
+    let mut deposit = env.deposit(&user_token, &user.pubkey(), 10);
+    deposit.accounts[1].pubkey = other;
+    assert_eq!(
+        env.process(&[deposit], &[&user]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+    assert_eq!(env.token(&user_token).await.amount, 100);
+}
+
+#[test]
+fn transfer_with_seed_derives_the_authority() {
+    let token_program_id = Pubkey::new_unique();
+    let vault_program_id = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let (source, destination) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let (transfer, bump_seed) = instruction::transfer_with_seed(
+        &token_program_id,
+        &source,
+        &destination,
+        &vault_program_id,
+        &[VAULT_SEED, user.as_ref()],
+        7,
+    )
+    .unwrap();
+    let (authority, expected_bump_seed) = get_vault_authority(&user, &vault_program_id);
+    assert_eq!(bump_seed, expected_bump_seed);
+    assert_eq!(
+        transfer,
+        instruction::transfer_tokens(&token_program_id, &source, &destination, &authority, &[], 7)
+            .unwrap()
+    );
+}
+
+/// Links the vault's entrypoint into a binary with the token program's
+/// processor: with `no-entrypoint`, the token program exports no
+/// `entrypoint` symbol of its own to clash with the vault's.
+#[cfg(not(feature = "no-entrypoint"))]
+#[test]
+fn the_vault_links_its_own_entrypoint_beside_the_token_program() {
+    let entrypoint: unsafe extern "C" fn(*mut u8) -> u64 = vault::entrypoint;
+    let _ = (entrypoint, solana_contracts::process_instruction);
+}
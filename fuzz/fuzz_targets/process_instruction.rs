@@ -0,0 +1,107 @@
+//! Feeds arbitrary instruction data and synthetic accounts into the
+//! processor. Any outcome other than a panic is fine.
+//!
+//! Run with `cargo +nightly fuzz run process_instruction` from the
+//! repository root.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use solana_contracts::{
+    process_instruction,
+    test_utils::{account_infos, AccountFixture},
+    Escrow, Mint, Multisig, Token, TokenMetadata,
+};
+use solana_program::{account_info::AccountInfo, program_pack::Pack, pubkey::Pubkey};
+
+const MAX_ACCOUNTS: usize = 8;
+
+const PROGRAM_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+
+#[derive(Arbitrary, Debug)]
+enum FuzzOwner {
+    Program,
+    System,
+    Account(u8),
+}
+
+/// Data lengths of the program's accounts, so the fuzzer doesn't have to
+/// stumble on them; the leading bytes of `data` fill the buffer.
+#[derive(Arbitrary, Debug)]
+enum FuzzDataLen {
+    Token,
+    Mint,
+    Multisig,
+    Metadata,
+    Escrow,
+    Raw,
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzAccount {
+    owner: FuzzOwner,
+    lamports: u64,
+    data_len: FuzzDataLen,
+    data: Vec<u8>,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzInput {
+    accounts: Vec<FuzzAccount>,
+    /// Order in which `accounts` are passed; repeats pass the same account
+    /// twice.
+    indices: Vec<u8>,
+    instruction_data: Vec<u8>,
+}
+
+fn key(index: usize) -> Pubkey {
+    Pubkey::new_from_array([index as u8 + 1; 32])
+}
+
+fuzz_target!(|input: FuzzInput| {
+    let num_accounts = input.accounts.len().min(MAX_ACCOUNTS);
+    if num_accounts == 0 {
+        let _ = process_instruction(&PROGRAM_ID, &[], &input.instruction_data);
+        return;
+    }
+    let mut accounts: Vec<AccountFixture> = input
+        .accounts
+        .into_iter()
+        .take(num_accounts)
+        .enumerate()
+        .map(|(index, account)| {
+            let owner = match account.owner {
+                FuzzOwner::Program => PROGRAM_ID,
+                FuzzOwner::System => Pubkey::default(),
+                FuzzOwner::Account(owner) => key(owner as usize % num_accounts),
+            };
+            let mut data = account.data;
+            match account.data_len {
+                FuzzDataLen::Token => data.resize(Token::LEN, 0),
+                FuzzDataLen::Mint => data.resize(Mint::LEN, 0),
+                FuzzDataLen::Multisig => data.resize(Multisig::LEN, 0),
+                FuzzDataLen::Metadata => data.resize(TokenMetadata::LEN, 0),
+                FuzzDataLen::Escrow => data.resize(Escrow::LEN, 0),
+                FuzzDataLen::Raw => {}
+            }
+            AccountFixture {
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+                ..AccountFixture::new(key(index))
+                    .owner(owner)
+                    .lamports(account.lamports)
+                    .data(data)
+            }
+        })
+        .collect();
+    let infos = account_infos(&mut accounts);
+    let ordered: Vec<AccountInfo> = input
+        .indices
+        .iter()
+        .map(|index| infos[*index as usize % infos.len()].clone())
+        .collect();
+    let _ = process_instruction(&PROGRAM_ID, &ordered, &input.instruction_data);
+});
@@ -0,0 +1,24 @@
+#!/usr/bin/env bash
+# Checks that the crate compiles, warning-free, in each supported feature
+# combination: the program, the program as a library of another program,
+# and off-chain clients with and without the optional formats.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+combinations=(
+    ""
+    "--features no-entrypoint"
+    "--no-default-features"
+    "--no-default-features --features client"
+    "--no-default-features --features client,idl,anchor,serde,borsh"
+    "--features client"
+    "--features bytemuck"
+    "--all-features"
+)
+
+for features in "${combinations[@]}"; do
+    echo "cargo check --lib ${features}"
+    # shellcheck disable=SC2086
+    RUSTFLAGS="${RUSTFLAGS:-} -D warnings" cargo check --quiet --lib ${features}
+done
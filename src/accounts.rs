@@ -0,0 +1,203 @@
+//! Typed loading and validation of instruction accounts
+//!
+//! Handlers take their accounts as a slice in the order their instruction
+//! documents. The checks here fail with a [`ProgramError`] naming what was
+//! wrong, so an account passed in the wrong position or owned by another
+//! program is rejected before its data is read, and a short slice fails with
+//! `NotEnoughAccountKeys` rather than a panic.
+//!
+//! The `*Accounts` structs load the fixed accounts of one instruction,
+//! checking what can be checked without reading state: that the slice is
+//! long enough, and the writability and owner of each account. Handlers then
+//! read the state of those accounts with the `load_*` functions, and check
+//! single signers with [`load_signer`].
+
+use crate::{
+    error::TokenError,
+    log::log_debug,
+    processor::cmp_pubkeys,
+    state::{Mint, Token},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+
+/// Fails with `DuplicateAccount` if `account` is any of `others`, for roles
+/// that can't share an account.
+pub fn check_distinct(account: &AccountInfo, others: &[&AccountInfo]) -> ProgramResult {
+    if others
+        .iter()
+        .any(|other| cmp_pubkeys(account.key, other.key))
+    {
+        return Err(TokenError::DuplicateAccount.into());
+    }
+    Ok(())
+}
+
+/// Fails with `AccountNotWritable`, logging which `role` was passed
+/// read-only, unless `account` is writable.
+pub fn check_writable(account: &AccountInfo, role: &str) -> ProgramResult {
+    if !account.is_writable {
+        log_debug!("The {} account {} is not writable", role, account.key);
+        return Err(TokenError::AccountNotWritable.into());
+    }
+    Ok(())
+}
+
+/// Fails with `IncorrectProgramId`, logging the account, unless `account`
+/// is owned by `program_id`. Every account whose data is read as program
+/// state must pass this first: anyone can give an account of another
+/// program bytes that unpack as a valid token account or mint.
+pub fn check_account_owner(program_id: &Pubkey, account: &AccountInfo) -> ProgramResult {
+    if !cmp_pubkeys(account.owner, program_id) {
+        log_debug!(
+            "The account {} is owned by {}, not this program",
+            account.key,
+            account.owner
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Fails with `MissingRequiredSignature`, logging which `role` didn't sign,
+/// unless `account` signed the transaction.
+pub fn check_is_signer(account: &AccountInfo, role: &str) -> ProgramResult {
+    if !account.is_signer {
+        log_debug!("The {} account {} did not sign", role, account.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Checks that `payer` can fund `lamports` of rent: that it signed, is
+/// writable and holds them, failing with `InsufficientFunds` and logging
+/// the shortfall otherwise. The payer is any account of the caller's,
+/// separate from the owner, which then needn't sign or hold lamports.
+pub fn check_payer(payer: &AccountInfo, lamports: u64) -> ProgramResult {
+    check_is_signer(payer, "payer")?;
+    check_writable(payer, "payer")?;
+    if payer.lamports() < lamports {
+        log_debug!(
+            "The payer {} holds {} lamports of the {} needed",
+            payer.key,
+            payer.lamports(),
+            lamports
+        );
+        return Err(ProgramError::InsufficientFunds);
+    }
+    Ok(())
+}
+
+/// Returns `info` if it signed the transaction.
+pub fn load_signer<'a, 'info>(
+    info: &'a AccountInfo<'info>,
+) -> Result<&'a AccountInfo<'info>, ProgramError> {
+    check_is_signer(info, "signer")?;
+    Ok(info)
+}
+
+/// Unpacks `info` as an initialized mint owned by `program_id`.
+pub fn load_mint<'a, 'info>(
+    info: &'a AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<(Mint, &'a AccountInfo<'info>), ProgramError> {
+    check_account_owner(program_id, info)?;
+    let mint = Mint::unpack(&info.try_borrow_data()?)?;
+    Ok((mint, info))
+}
+
+/// Unpacks `info` as an initialized token account owned by `program_id`.
+pub fn load_token_account<'a, 'info>(
+    info: &'a AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<(Token, &'a AccountInfo<'info>), ProgramError> {
+    check_account_owner(program_id, info)?;
+    let token = Token::unpack(&info.try_borrow_data()?)?;
+    Ok((token, info))
+}
+
+/// Accounts of `MintTokens` and `MintToChecked`.
+pub struct MintToAccounts<'a, 'info> {
+    pub mint: &'a AccountInfo<'info>,
+    pub destination: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    /// Multisignature signers, then any allowlist account.
+    pub remaining: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> MintToAccounts<'a, 'info> {
+    pub fn load(
+        accounts: &'a [AccountInfo<'info>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let mint = next_account_info(account_info_iter)?;
+        let destination = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
+
+        check_writable(mint, "mint")?;
+        check_writable(destination, "destination")?;
+
+        check_account_owner(program_id, mint)?;
+        check_account_owner(program_id, destination)?;
+
+        Ok(MintToAccounts {
+            mint,
+            destination,
+            authority,
+            remaining: account_info_iter.as_slice(),
+        })
+    }
+}
+
+/// Accounts of `TransferTokens` and, with its `mint`, `TransferChecked`.
+pub struct TransferAccounts<'a, 'info> {
+    pub source: &'a AccountInfo<'info>,
+    /// Present only for `TransferChecked`. Not checked here: the mint of
+    /// wrapped SOL isn't a program account.
+    pub mint: Option<&'a AccountInfo<'info>>,
+    pub destination: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    /// Multisignature signers, then any royalty, allowlist, memo and
+    /// transfer hook accounts.
+    pub remaining: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> TransferAccounts<'a, 'info> {
+    /// Loads the accounts of `TransferChecked` if `checked`, or of
+    /// `TransferTokens` otherwise.
+    pub fn load(
+        accounts: &'a [AccountInfo<'info>],
+        program_id: &Pubkey,
+        checked: bool,
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let source = next_account_info(account_info_iter)?;
+        let mint = if checked {
+            Some(next_account_info(account_info_iter)?)
+        } else {
+            None
+        };
+        let destination = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
+
+        check_writable(source, "source")?;
+        check_writable(destination, "destination")?;
+
+        check_account_owner(program_id, source)?;
+        check_account_owner(program_id, destination)?;
+
+        Ok(TransferAccounts {
+            source,
+            mint,
+            destination,
+            authority,
+            remaining: account_info_iter.as_slice(),
+        })
+    }
+}
@@ -0,0 +1,18 @@
+//! Allowlist account address derivation
+
+use solana_program::pubkey::Pubkey;
+
+/// Seed prefix of allowlist account addresses.
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist";
+
+/// Derives the allowlist account address for a mint.
+pub fn get_allowlist_address(mint_address: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_allowlist_address_and_bump_seed(mint_address, program_id).0
+}
+
+pub(crate) fn get_allowlist_address_and_bump_seed(
+    mint_address: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ALLOWLIST_SEED, &mint_address.to_bytes()], program_id)
+}
@@ -0,0 +1,232 @@
+//! Anchor-style instruction discriminators
+//!
+//! With the `anchor` feature, [`TokenInstruction::unpack`] also accepts data
+//! that starts with an Anchor discriminator, the first eight bytes of
+//! `sha256("global:<name>")` for the instruction's snake-case name, followed
+//! by its arguments in borsh. Borsh encodes every argument as the legacy
+//! layout does, except that the `TransferBatch` amounts, `Claim` proof and
+//! `InitializeMintWithAllocations` amounts have a `u32` count.
+//!
+//! The discriminator takes precedence: data whose first eight bytes are a
+//! discriminator is parsed as that instruction, even if its first byte is a
+//! legacy tag. Legacy data only collides with a discriminator when its
+//! arguments spell one out, such as a `MintTokens` amount whose low seven
+//! bytes continue `cancel_pending_owner`'s, and such data fails to unpack
+//! since the Anchor form leaves bytes over. Everything else, including data
+//! shorter than eight bytes, is parsed by tag as before.
+
+use crate::instruction::TokenInstruction;
+
+/// Each instruction's name and discriminator, indexed by legacy tag.
+pub const DISCRIMINATORS: [(&str, [u8; 8]); 90] = [
+    ("mint_tokens", [59, 132, 24, 246, 122, 39, 8, 243]),
+    ("transfer_tokens", [54, 180, 238, 175, 74, 85, 126, 188]),
+    ("burn", [116, 110, 29, 56, 107, 219, 42, 93]),
+    ("initialize_mint", [209, 42, 195, 4, 129, 85, 209, 44]),
+    ("approve", [69, 74, 217, 36, 115, 117, 97, 76]),
+    ("revoke", [170, 23, 31, 34, 133, 173, 93, 242]),
+    ("initialize_account", [74, 115, 99, 93, 197, 69, 103, 7]),
+    ("close_account", [125, 255, 149, 14, 110, 34, 72, 24]),
+    ("freeze_account", [253, 75, 82, 133, 167, 238, 43, 130]),
+    ("thaw_account", [115, 152, 79, 213, 213, 169, 184, 35]),
+    ("initialize_account2", [8, 182, 149, 144, 185, 31, 209, 105]),
+    ("set_authority", [133, 250, 37, 21, 110, 163, 26, 121]),
+    ("transfer_checked", [119, 250, 202, 24, 253, 135, 244, 121]),
+    ("mint_to_checked", [229, 236, 36, 240, 118, 225, 45, 125]),
+    ("burn_checked", [198, 121, 200, 102, 120, 208, 155, 178]),
+    (
+        "create_associated_account",
+        [169, 148, 65, 117, 203, 119, 96, 50],
+    ),
+    ("sync_native", [155, 219, 36, 36, 239, 128, 21, 65]),
+    ("initialize_multisig", [220, 130, 117, 21, 27, 227, 78, 213]),
+    ("transfer_batch", [114, 216, 40, 75, 132, 196, 172, 29]),
+    ("create_metadata", [30, 35, 117, 134, 196, 139, 44, 25]),
+    ("update_metadata", [170, 182, 43, 239, 97, 78, 225, 186]),
+    (
+        "initialize_transfer_fee_config",
+        [189, 189, 171, 33, 115, 17, 231, 2],
+    ),
+    (
+        "harvest_withheld_tokens",
+        [240, 7, 1, 129, 106, 109, 61, 38],
+    ),
+    (
+        "withdraw_withheld_tokens",
+        [223, 93, 178, 161, 107, 231, 169, 22],
+    ),
+    (
+        "initialize_non_transferable_mint",
+        [242, 68, 44, 126, 194, 231, 206, 200],
+    ),
+    ("lock_account", [96, 230, 27, 93, 190, 236, 88, 0]),
+    ("initialize_escrow", [243, 160, 77, 153, 11, 92, 48, 209]),
+    ("exchange", [47, 3, 27, 97, 215, 236, 219, 144]),
+    ("cancel_escrow", [156, 203, 54, 179, 38, 72, 33, 21]),
+    (
+        "initialize_pause_config",
+        [122, 21, 123, 223, 26, 70, 49, 226],
+    ),
+    ("pause", [211, 22, 221, 251, 74, 121, 193, 47]),
+    ("resume", [1, 166, 51, 170, 127, 32, 141, 206]),
+    (
+        "initialize_permanent_delegate",
+        [98, 200, 9, 70, 17, 203, 130, 60],
+    ),
+    ("propose_owner", [90, 57, 141, 110, 196, 241, 172, 39]),
+    ("accept_owner", [176, 23, 41, 28, 23, 111, 8, 4]),
+    ("cancel_pending_owner", [0, 54, 96, 117, 94, 141, 217, 249]),
+    ("get_account_balance", [244, 97, 181, 222, 199, 68, 28, 94]),
+    ("get_mint_supply", [64, 201, 242, 224, 179, 26, 80, 112]),
+    ("amount_to_ui_amount", [160, 145, 200, 98, 242, 156, 30, 90]),
+    ("ui_amount_to_amount", [173, 243, 64, 4, 103, 31, 56, 52]),
+    ("require_memo", [180, 183, 89, 224, 12, 216, 101, 152]),
+    ("unrequire_memo", [255, 235, 63, 178, 107, 175, 72, 207]),
+    (
+        "initialize_transfer_hook",
+        [22, 166, 19, 247, 193, 62, 186, 143],
+    ),
+    (
+        "initialize_default_account_state",
+        [175, 89, 119, 112, 150, 203, 202, 198],
+    ),
+    (
+        "set_default_account_state",
+        [166, 75, 152, 106, 149, 101, 212, 30],
+    ),
+    (
+        "initialize_immutable_owner",
+        [141, 50, 15, 44, 195, 247, 34, 60],
+    ),
+    (
+        "initialize_interest_bearing_mint",
+        [95, 0, 96, 123, 180, 250, 63, 131],
+    ),
+    ("update_rate", [24, 225, 53, 189, 72, 212, 225, 178]),
+    (
+        "initialize_mint_close_authority",
+        [117, 167, 56, 158, 201, 160, 209, 109],
+    ),
+    ("close_mint", [149, 251, 157, 212, 65, 181, 235, 129]),
+    ("migrate_account", [177, 228, 60, 125, 13, 116, 44, 84]),
+    (
+        "initialize_royalty",
+        [240, 108, 174, 152, 126, 199, 89, 184],
+    ),
+    ("initialize_allowlist", [77, 102, 38, 154, 54, 54, 58, 100]),
+    ("add_to_allowlist", [149, 143, 78, 134, 241, 244, 7, 56]),
+    (
+        "remove_from_allowlist",
+        [45, 46, 214, 56, 189, 77, 242, 227],
+    ),
+    (
+        "initialize_min_transfer_amount",
+        [96, 63, 133, 230, 39, 169, 165, 80],
+    ),
+    (
+        "update_min_transfer_amount",
+        [67, 128, 122, 63, 135, 213, 110, 167],
+    ),
+    (
+        "transfer_account_ownership",
+        [49, 114, 199, 133, 137, 108, 123, 99],
+    ),
+    ("create_distribution", [237, 83, 162, 50, 49, 6, 28, 115]),
+    ("claim", [62, 198, 214, 193, 213, 159, 108, 210]),
+    ("initialize_mint2", [95, 108, 198, 210, 72, 243, 143, 235]),
+    ("initialize_account3", [23, 142, 140, 135, 21, 160, 133, 64]),
+    ("initialize_multisig2", [81, 239, 73, 39, 27, 148, 2, 146]),
+    ("freeze_batch", [176, 184, 96, 95, 38, 136, 33, 35]),
+    (
+        "initialize_mint_with_allocations",
+        [229, 2, 31, 164, 9, 133, 96, 119],
+    ),
+    (
+        "initialize_activity_tracking",
+        [224, 63, 64, 43, 4, 21, 213, 24],
+    ),
+    ("transfer_with_fee", [140, 41, 159, 221, 159, 13, 211, 126]),
+    (
+        "create_token_account",
+        [147, 241, 123, 100, 244, 132, 174, 118],
+    ),
+    (
+        "initialize_transfer_cooldown",
+        [89, 238, 178, 121, 77, 38, 31, 171],
+    ),
+    (
+        "initialize_holder_count",
+        [191, 183, 7, 141, 155, 222, 247, 51],
+    ),
+    ("permit_transfer", [122, 159, 165, 218, 223, 2, 80, 182]),
+    ("create_vesting", [135, 184, 171, 156, 197, 162, 246, 44]),
+    ("claim_vested", [208, 190, 166, 114, 203, 225, 140, 208]),
+    ("repair_owner", [112, 72, 119, 17, 12, 214, 211, 143]),
+    ("recover_nested", [8, 190, 201, 58, 200, 121, 210, 143]),
+    ("two_party_swap", [97, 135, 145, 57, 153, 236, 142, 245]),
+    (
+        "create_associated_account_idempotent",
+        [37, 1, 240, 232, 240, 15, 198, 181],
+    ),
+    (
+        "initialize_recovery_authority",
+        [223, 207, 218, 101, 33, 201, 60, 176],
+    ),
+    (
+        "initialize_metadata_pointer",
+        [34, 177, 129, 129, 200, 224, 68, 147],
+    ),
+    (
+        "update_metadata_pointer",
+        [145, 195, 69, 58, 113, 250, 35, 222],
+    ),
+    ("create_converter", [55, 47, 183, 67, 3, 25, 126, 191]),
+    ("convert", [122, 80, 212, 208, 92, 200, 34, 161]),
+    ("set_minimum_balance", [16, 49, 254, 150, 9, 236, 72, 89]),
+    ("snapshot_balance", [103, 80, 86, 114, 5, 1, 234, 53]),
+    ("close_snapshot", [10, 135, 141, 56, 1, 123, 162, 186]),
+    (
+        "transfer_with_relayer_fee",
+        [46, 104, 121, 40, 227, 179, 229, 231],
+    ),
+    ("verify_ownership", [7, 115, 200, 80, 34, 133, 174, 184]),
+    ("set_outflow_limit", [228, 89, 251, 114, 101, 140, 200, 123]),
+    ("add_minter", [75, 86, 218, 40, 219, 6, 141, 29]),
+    ("remove_minter", [241, 69, 84, 16, 164, 232, 131, 79]),
+];
+
+/// The legacy tag of the instruction `prefix` is the discriminator of, if
+/// any.
+pub fn tag(prefix: &[u8]) -> Option<u8> {
+    let prefix: [u8; 8] = prefix.get(..8)?.try_into().ok()?;
+    DISCRIMINATORS
+        .iter()
+        .position(|(_, discriminator)| *discriminator == prefix)
+        .map(|tag| tag as u8)
+}
+
+/// Packs `instruction` in the Anchor layout.
+pub fn pack(instruction: &TokenInstruction) -> Vec<u8> {
+    let legacy = instruction.pack();
+    let (&tag, args) = legacy.split_first().unwrap();
+    let mut buf = Vec::with_capacity(legacy.len() + 10);
+    buf.extend_from_slice(&DISCRIMINATORS[tag as usize].1);
+    match instruction {
+        TokenInstruction::TransferBatch { amounts } => {
+            buf.extend_from_slice(&(amounts.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&args[1..]);
+        }
+        TokenInstruction::Claim { proof, .. } => {
+            buf.extend_from_slice(&args[..16]);
+            buf.extend_from_slice(&(proof.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&args[17..]);
+        }
+        TokenInstruction::InitializeMintWithAllocations { amounts, .. } => {
+            buf.push(args[0]);
+            buf.extend_from_slice(&(amounts.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&args[2..]);
+        }
+        _ => buf.extend_from_slice(args),
+    }
+    buf
+}
@@ -0,0 +1,27 @@
+//! Associated token account address derivation
+
+use solana_program::pubkey::Pubkey;
+
+/// Derives the canonical token account address for a wallet and mint.
+pub fn get_associated_token_address(
+    wallet_address: &Pubkey,
+    token_mint_address: &Pubkey,
+    program_id: &Pubkey,
+) -> Pubkey {
+    get_associated_token_address_and_bump_seed(wallet_address, token_mint_address, program_id).0
+}
+
+pub(crate) fn get_associated_token_address_and_bump_seed(
+    wallet_address: &Pubkey,
+    token_mint_address: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            &wallet_address.to_bytes(),
+            &program_id.to_bytes(),
+            &token_mint_address.to_bytes(),
+        ],
+        program_id,
+    )
+}
@@ -0,0 +1,15 @@
+//! Writes the account fixtures of `solana_contracts::fixtures` and their
+//! manifest to the directory given, `fixtures/` by default.
+
+use solana_contracts::fixtures;
+use std::{fs, path::PathBuf};
+
+fn main() -> std::io::Result<()> {
+    let dir = PathBuf::from(std::env::args().nth(1).unwrap_or_else(|| "fixtures".into()));
+    fs::create_dir_all(&dir)?;
+    for (name, contents) in fixtures::files() {
+        fs::write(dir.join(&name), contents)?;
+        println!("wrote {}", dir.join(name).display());
+    }
+    Ok(())
+}
@@ -0,0 +1,21 @@
+//! Converter authority address derivation
+//!
+//! A converter's authority is the mint authority of the mint it converts
+//! to, so `Convert` can mint with `invoke_signed` and nobody else can.
+
+use solana_program::pubkey::Pubkey;
+
+/// Seed prefix of converter authority addresses.
+pub const CONVERTER_SEED: &[u8] = b"converter";
+
+/// Derives the address that mints the target tokens of a converter.
+pub fn get_converter_authority(converter_address: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_converter_authority_and_bump_seed(converter_address, program_id).0
+}
+
+pub(crate) fn get_converter_authority_and_bump_seed(
+    converter_address: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONVERTER_SEED, &converter_address.to_bytes()], program_id)
+}
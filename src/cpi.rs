@@ -0,0 +1,193 @@
+//! Helpers for other programs to call this program through CPI
+//!
+//! Each helper builds its instruction with the matching builder in
+//! [`crate::instruction`] and invokes `token_program`. With empty
+//! `signer_seeds` the authority must already have signed the calling
+//! instruction; otherwise the seeds sign for an authority that is a PDA of
+//! the calling program.
+
+use crate::instruction;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Accounts of a [`transfer`].
+pub struct CpiAccounts<'a, 'info> {
+    pub source: &'a AccountInfo<'info>,
+    pub destination: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+}
+
+/// Accounts of a [`transfer_checked`].
+pub struct TransferCheckedAccounts<'a, 'info> {
+    pub source: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub destination: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+}
+
+/// Accounts of a [`mint_to`].
+pub struct MintToAccounts<'a, 'info> {
+    pub mint: &'a AccountInfo<'info>,
+    pub destination: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+}
+
+/// Accounts of a [`burn`].
+pub struct BurnAccounts<'a, 'info> {
+    pub account: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+}
+
+/// Transfers `amount` tokens with a `TransferTokens` instruction.
+pub fn transfer(accounts: CpiAccounts, amount: u64, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    let instruction = instruction::transfer_tokens(
+        accounts.token_program.key,
+        accounts.source.key,
+        accounts.destination.key,
+        accounts.authority.key,
+        &[],
+        amount,
+    )?;
+    invoke_maybe_signed(
+        &instruction,
+        &[
+            accounts.source.clone(),
+            accounts.destination.clone(),
+            accounts.authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+/// Transfers `amount` tokens out of an account owned by the calling
+/// program's PDA at `seeds`, signing for it. Fails with `InvalidSeeds` unless
+/// `accounts.authority` is that PDA.
+pub fn transfer_with_seed(
+    accounts: CpiAccounts,
+    amount: u64,
+    program_id: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let (instruction, bump_seed) = instruction::transfer_with_seed(
+        accounts.token_program.key,
+        accounts.source.key,
+        accounts.destination.key,
+        program_id,
+        seeds,
+        amount,
+    )?;
+    if instruction.accounts[2].pubkey != *accounts.authority.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let bump_seed = [bump_seed];
+    let mut signer_seeds = seeds.to_vec();
+    signer_seeds.push(&bump_seed);
+    invoke_signed(
+        &instruction,
+        &[
+            accounts.source.clone(),
+            accounts.destination.clone(),
+            accounts.authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        &[&signer_seeds],
+    )
+}
+
+/// Transfers `amount` tokens with a `TransferChecked` instruction.
+pub fn transfer_checked(
+    accounts: TransferCheckedAccounts,
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::transfer_checked(
+        accounts.token_program.key,
+        accounts.source.key,
+        accounts.mint.key,
+        accounts.destination.key,
+        accounts.authority.key,
+        &[],
+        amount,
+        decimals,
+    )?;
+    invoke_maybe_signed(
+        &instruction,
+        &[
+            accounts.source.clone(),
+            accounts.mint.clone(),
+            accounts.destination.clone(),
+            accounts.authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+/// Mints `amount` new tokens with a `MintTokens` instruction.
+pub fn mint_to(accounts: MintToAccounts, amount: u64, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    let instruction = instruction::mint_tokens(
+        accounts.token_program.key,
+        accounts.mint.key,
+        accounts.destination.key,
+        accounts.authority.key,
+        &[],
+        amount,
+    )?;
+    invoke_maybe_signed(
+        &instruction,
+        &[
+            accounts.mint.clone(),
+            accounts.destination.clone(),
+            accounts.authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+/// Burns `amount` tokens with a `Burn` instruction.
+pub fn burn(accounts: BurnAccounts, amount: u64, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    let instruction = instruction::burn(
+        accounts.token_program.key,
+        accounts.account.key,
+        accounts.mint.key,
+        accounts.authority.key,
+        &[],
+        amount,
+    )?;
+    invoke_maybe_signed(
+        &instruction,
+        &[
+            accounts.account.clone(),
+            accounts.mint.clone(),
+            accounts.authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+fn invoke_maybe_signed(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    if signer_seeds.is_empty() {
+        invoke(instruction, account_infos)
+    } else {
+        invoke_signed(instruction, account_infos, signer_seeds)
+    }
+}
@@ -0,0 +1,167 @@
+//! Decoding of fetched account data for off-chain consumers
+//!
+//! Services that read accounts over RPC get their raw data, in either the
+//! legacy, unversioned layout or the current one. [`parse_token_account`]
+//! and [`parse_mint`] tell the two apart by length, like the program does,
+//! and return a view with plain `Option`s in place of `COption`s. Malformed
+//! data of any length is an error, never a panic.
+
+use crate::{
+    extension::{
+        self, ExtensionType, MetadataPointer, MintSettings, Minters, MAX_ACCOUNT_LEN, MAX_MINT_LEN,
+    },
+    state::{AccountState, Mint, Token, TransferFee},
+};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use thiserror::Error;
+
+/// Why account data couldn't be decoded.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum DecodeError {
+    #[error("Account data of {0} bytes is not of the expected account type")]
+    UnexpectedLength(usize),
+    #[error("Account data is malformed")]
+    InvalidData,
+    #[error("The account is not initialized")]
+    Uninitialized,
+}
+
+/// Which layout decoded account data was in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Layout {
+    /// The unversioned layout, from before `MigrateAccount`.
+    Legacy,
+    /// The [`ACCOUNT_VERSION`](crate::state::ACCOUNT_VERSION) layout.
+    Current,
+}
+
+/// Settings a token account copied from its mint when it was initialized.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TokenAccountFlags {
+    pub non_transferable: bool,
+    pub pausable: bool,
+    pub transfer_hook: bool,
+    pub royalty: bool,
+    pub allowlist: bool,
+    pub min_transfer_amount: bool,
+    pub activity_tracking: bool,
+    pub cooldown: bool,
+    pub holder_count: bool,
+}
+
+/// A decoded token account.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenAccountView {
+    pub layout: Layout,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub delegate: Option<Pubkey>,
+    pub delegated_amount: u64,
+    pub state: AccountState,
+    /// The rent-exempt reserve of an account that wraps SOL.
+    pub is_native: Option<u64>,
+    /// Transfer fees withheld in the account, if its mint charges them.
+    pub withheld_amount: Option<u64>,
+    pub pending_owner: Option<Pubkey>,
+    pub flags: TokenAccountFlags,
+    /// The extensions the account holds, in the order they were enabled.
+    pub extensions: Vec<ExtensionType>,
+}
+
+/// A decoded mint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MintView {
+    pub layout: Layout,
+    pub mint_authority: Option<Pubkey>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub freeze_authority: Option<Pubkey>,
+    pub max_supply: Option<u64>,
+    pub transfer_fee: Option<TransferFee>,
+    /// Fees harvested from token accounts and not yet withdrawn.
+    pub withheld_amount: u64,
+    pub non_transferable: bool,
+    pub paused: bool,
+    /// Where the mint's metadata lives instead of its metadata account,
+    /// which a mint with a pointer never has.
+    pub metadata_pointer: Option<MetadataPointer>,
+    /// Keys besides the mint authority that may mint.
+    pub minters: Vec<Pubkey>,
+}
+
+/// Decodes the data of an initialized token account.
+pub fn parse_token_account(data: &[u8]) -> Result<TokenAccountView, DecodeError> {
+    let (layout, extensions, settings) = match data.len() {
+        Token::LEGACY_LEN => (Layout::Legacy, Vec::new(), None),
+        Token::LEN..=MAX_ACCOUNT_LEN => (
+            Layout::Current,
+            extension::get_extension_types(data).map_err(|_| DecodeError::InvalidData)?,
+            extension::get_extension::<MintSettings>(data).map_err(|_| DecodeError::InvalidData)?,
+        ),
+        len => return Err(DecodeError::UnexpectedLength(len)),
+    };
+    let token = Token::unpack_any_version(data).map_err(|_| DecodeError::InvalidData)?;
+    if token.state == AccountState::Uninitialized {
+        return Err(DecodeError::Uninitialized);
+    }
+    let settings = settings.unwrap_or_default();
+    Ok(TokenAccountView {
+        layout,
+        mint: token.mint,
+        owner: token.owner,
+        amount: token.amount,
+        delegate: token.delegate.into(),
+        delegated_amount: token.delegated_amount,
+        state: token.state,
+        is_native: token.is_native.into(),
+        withheld_amount: token.withheld_amount.into(),
+        pending_owner: token.pending_owner.into(),
+        flags: TokenAccountFlags {
+            non_transferable: token.is_non_transferable,
+            pausable: token.is_pausable,
+            transfer_hook: token.has_transfer_hook,
+            royalty: settings.royalty,
+            allowlist: settings.enforce_allowlist,
+            min_transfer_amount: settings.min_transfer_amount,
+            activity_tracking: settings.activity_tracking,
+            cooldown: extensions.contains(&ExtensionType::Cooldown),
+            holder_count: settings.holder_count,
+        },
+        extensions,
+    })
+}
+
+/// Decodes the data of an initialized mint.
+pub fn parse_mint(data: &[u8]) -> Result<MintView, DecodeError> {
+    let (layout, minters, metadata_pointer) = match data.len() {
+        Mint::LEGACY_LEN => (Layout::Legacy, None, None),
+        Mint::LEN..=MAX_MINT_LEN => (
+            Layout::Current,
+            extension::get_mint_extension::<Minters>(data).map_err(|_| DecodeError::InvalidData)?,
+            extension::get_mint_extension::<MetadataPointer>(data)
+                .map_err(|_| DecodeError::InvalidData)?,
+        ),
+        len => return Err(DecodeError::UnexpectedLength(len)),
+    };
+    let mint = Mint::unpack_any_version(data).map_err(|_| DecodeError::InvalidData)?;
+    if !mint.is_initialized {
+        return Err(DecodeError::Uninitialized);
+    }
+    Ok(MintView {
+        layout,
+        mint_authority: mint.mint_authority.into(),
+        supply: mint.supply,
+        decimals: mint.decimals,
+        freeze_authority: mint.freeze_authority.into(),
+        max_supply: mint.max_supply.into(),
+        transfer_fee: mint.transfer_fee.into(),
+        withheld_amount: mint.withheld_amount,
+        non_transferable: mint.non_transferable,
+        paused: mint.paused,
+        metadata_pointer,
+        minters: minters
+            .map(|minters| minters.minters.iter().map(|minter| minter.minter).collect())
+            .unwrap_or_default(),
+    })
+}
@@ -0,0 +1,79 @@
+//! Instruction deprecation table
+//!
+//! Before unpacking an instruction, the processor looks its tag up in a
+//! [`DeprecationTable`], so that changing what a tag does is never silent
+//! for clients that still send it. A tag either passes through to its
+//! handler as usual, is handled by a compatibility handler in its place,
+//! after a log of the tag that replaces it, or fails with
+//! `DeprecatedInstruction`, logging the same.
+//!
+//! [`RELEASE`] is the table this build dispatches with. A sunset is staged
+//! by editing it over releases: the tag goes from `PassThrough` to `Compat`
+//! for at least one release, then to `Deprecated`. With the `program`
+//! feature, `Processor::process_with_deprecations` dispatches with any other
+//! table, for tests of the next release's.
+
+use crate::{instruction::MAX_TAG, processor::Processor};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+/// Handles an instruction of a tag in compatibility, given the same program
+/// id, accounts and instruction data as the processor.
+pub type CompatHandler = fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult;
+
+/// What the processor does with instructions of one tag.
+#[derive(Clone, Copy, Debug)]
+pub enum Disposition {
+    /// Dispatched to the tag's handler as usual.
+    PassThrough,
+    /// Handled by `handler` instead of the tag's own handler, after logging
+    /// `replacement`, the tag clients should move to.
+    Compat {
+        replacement: u8,
+        handler: CompatHandler,
+    },
+    /// Fails with `DeprecatedInstruction` without being unpacked, logging
+    /// `replacement`.
+    Deprecated { replacement: u8 },
+}
+
+/// The [`Disposition`] of every tag up to [`MAX_TAG`].
+#[derive(Clone, Copy, Debug)]
+pub struct DeprecationTable([Disposition; MAX_TAG as usize + 1]);
+
+impl DeprecationTable {
+    /// Every tag passes through.
+    pub const PASS_THROUGH: Self = Self([Disposition::PassThrough; MAX_TAG as usize + 1]);
+
+    /// This table with `tag` set to `disposition`. Tags above [`MAX_TAG`]
+    /// are never valid, so they can't be set and stay passed through.
+    pub const fn with(mut self, tag: u8, disposition: Disposition) -> Self {
+        assert!(tag <= MAX_TAG, "tag above MAX_TAG");
+        self.0[tag as usize] = disposition;
+        self
+    }
+
+    /// The disposition of `tag`.
+    pub const fn get(&self, tag: u8) -> Disposition {
+        if tag > MAX_TAG {
+            return Disposition::PassThrough;
+        }
+        self.0[tag as usize]
+    }
+}
+
+/// `MintToChecked`, which replaces `MintTokens` (tag 0).
+pub const MINT_TOKENS_REPLACEMENT: u8 = 13;
+/// `TransferChecked`, which replaces `TransferTokens` (tag 1).
+pub const TRANSFER_TOKENS_REPLACEMENT: u8 = 12;
+
+/// The table this release dispatches with. `MintTokens` and `TransferTokens`
+/// will be sunset in favour of their checked variants. `MintTokens`, which
+/// no longer makes the mint the owner of the account it credits, is in
+/// compatibility; every other tag passes through.
+pub const RELEASE: DeprecationTable = DeprecationTable::PASS_THROUGH.with(
+    0,
+    Disposition::Compat {
+        replacement: MINT_TOKENS_REPLACEMENT,
+        handler: Processor::process_mint_tokens_compat,
+    },
+);
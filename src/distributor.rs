@@ -0,0 +1,75 @@
+//! Merkle distribution address derivation and proofs
+//!
+//! A distribution commits to its claims with the root of a merkle tree whose
+//! leaves are [`claim_leaf`]s. A claimant proves their leaf with the sibling
+//! hashes on its path to the root; [`hash_pair`] orders each pair before
+//! hashing, so a proof doesn't record which side each sibling is on.
+
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+/// Seed prefix of distributor authority addresses.
+pub const DISTRIBUTOR_SEED: &[u8] = b"distributor";
+/// Seed prefix of claim status addresses.
+pub const CLAIM_SEED: &[u8] = b"claim";
+
+/// Derives the address that owns the vault of a distributor.
+pub fn get_distributor_authority(distributor_address: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_distributor_authority_and_bump_seed(distributor_address, program_id).0
+}
+
+pub(crate) fn get_distributor_authority_and_bump_seed(
+    distributor_address: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[DISTRIBUTOR_SEED, &distributor_address.to_bytes()],
+        program_id,
+    )
+}
+
+/// Derives the address of the claim status recording that leaf `index` of
+/// a distributor has been claimed.
+pub fn get_claim_address(distributor_address: &Pubkey, index: u64, program_id: &Pubkey) -> Pubkey {
+    get_claim_address_and_bump_seed(distributor_address, index, program_id).0
+}
+
+pub(crate) fn get_claim_address_and_bump_seed(
+    distributor_address: &Pubkey,
+    index: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            CLAIM_SEED,
+            &distributor_address.to_bytes(),
+            &index.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// The leaf entitling `claimant` to `amount` tokens as claim `index`.
+pub fn claim_leaf(index: u64, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        &[0],
+        &index.to_le_bytes(),
+        claimant.as_ref(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// The parent of two nodes. The prefix byte keeps a parent from passing for
+/// a leaf.
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[&[1], low, high]).to_bytes()
+}
+
+/// Whether `proof` leads from `leaf` to `root`.
+pub fn verify_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(&node, sibling))
+        == *root
+}
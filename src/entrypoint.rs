@@ -0,0 +1,21 @@
+//! Program entrypoint
+
+use crate::{error::TokenError, processor::Processor};
+use solana_program::{
+    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult,
+    program_error::PrintProgramError, pubkey::Pubkey,
+};
+
+entrypoint!(program_entrypoint);
+
+fn program_entrypoint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if let Err(error) = Processor::process(program_id, accounts, instruction_data) {
+        error.print::<TokenError>();
+        return Err(error);
+    }
+    Ok(())
+}
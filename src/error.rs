@@ -0,0 +1,217 @@
+//! Error types
+
+use num_derive::FromPrimitive;
+use solana_program::{
+    decode_error::DecodeError,
+    msg,
+    program_error::{PrintProgramError, ProgramError},
+};
+use thiserror::Error;
+
+/// A [`TokenError`] as clients see it: the code the program fails with,
+/// and the variant's name and message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ErrorCode {
+    pub code: u32,
+    pub name: &'static str,
+    pub message: &'static str,
+}
+
+/// Declares `TokenError` and [`ERRORS`] from one list, so the table can't
+/// miss a variant or disagree with its message.
+macro_rules! token_errors {
+    ($(#[error($message:literal)] $variant:ident,)*) => {
+        /// Errors that may be returned by the token program.
+        ///
+        /// Codes are the variants' positions, so variants are only ever
+        /// appended; `tests/errors.rs` holds them to `idl/errors.json`.
+        #[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+        pub enum TokenError {
+            $(#[error($message)] $variant,)*
+        }
+
+        /// Every [`TokenError`], in order of code.
+        pub const ERRORS: &[ErrorCode] = &[$(ErrorCode {
+            code: TokenError::$variant as u32,
+            name: stringify!($variant),
+            message: $message,
+        },)*];
+    };
+}
+
+token_errors! {
+    #[error("Lamport balance below rent-exempt threshold")]
+    NotRentExempt,
+    #[error("Insufficient funds")]
+    InsufficientFunds,
+    #[error("Owner does not match")]
+    OwnerMismatch,
+    #[error("Account not associated with this Mint")]
+    MintMismatch,
+    #[error("Account is frozen")]
+    AccountFrozen,
+    #[error("Operation overflowed")]
+    Overflow,
+    #[error("Fixed supply")]
+    FixedSupply,
+    #[error("Non-native account can only be closed if its balance is zero")]
+    NonZeroBalance,
+    #[error("Invalid account state for operation")]
+    InvalidState,
+    #[error("This token mint cannot freeze accounts")]
+    MintCannotFreeze,
+    #[error("Invalid instruction")]
+    InvalidInstruction,
+    #[error("Account does not support specified authority type")]
+    AuthorityTypeNotSupported,
+    #[error("The provided decimals value different from the Mint decimals")]
+    MintDecimalsMismatch,
+    #[error("Invalid number of provided signers")]
+    InvalidNumberOfProvidedSigners,
+    #[error("Invalid number of required signers")]
+    InvalidNumberOfRequiredSigners,
+    #[error("Mint would exceed its maximum supply")]
+    SupplyCapExceeded,
+    #[error("Metadata name, symbol or uri is longer than its maximum length")]
+    MetadataTooLong,
+    #[error("Transfer fee basis points exceed the maximum")]
+    TransferFeeExceedsMaximum,
+    #[error("Transfers of this mint must include the mint account")]
+    MintRequiredForTransfer,
+    #[error("Account has withheld transfer fees")]
+    AccountHasWithheldTransferFees,
+    #[error("Tokens of this mint cannot be transferred")]
+    NonTransferable,
+    #[error("Account is locked until a later time")]
+    AccountLocked,
+    #[error("Account lock can only be extended")]
+    LockCannotBeShortened,
+    #[error("Offered amount does not match the escrow's expected amount")]
+    EscrowAmountMismatch,
+    #[error("Mint is paused")]
+    MintPaused,
+    #[error("This token mint cannot be paused")]
+    MintCannotPause,
+    #[error("Transfers into this account must be preceded by a memo")]
+    MemoRequired,
+    #[error("A mint's transfer hook can't be this program")]
+    InvalidTransferHook,
+    #[error("The account owner can't be changed")]
+    ImmutableOwner,
+    #[error("Timestamp precedes the mint's last rate update")]
+    InvalidTimestamp,
+    #[error("Mint can only be closed if its supply is zero")]
+    MintHasSupply,
+    #[error("Royalty basis points exceed the maximum")]
+    RoyaltyExceedsMaximum,
+    #[error("The mint's royalty destination account was not provided")]
+    RoyaltyAccountMissing,
+    #[error("The destination account's owner is not on the mint's allowlist")]
+    NotAllowlisted,
+    #[error("The mint's allowlist account was not provided")]
+    AllowlistAccountMissing,
+    #[error("The allowlist holds its maximum number of owners")]
+    AllowlistFull,
+    #[error("Transfer amount is below the mint's minimum")]
+    AmountBelowMinimum,
+    #[error("The same account was passed in two roles that must be distinct")]
+    DuplicateAccount,
+    #[error("An account the instruction modifies was not passed as writable")]
+    AccountNotWritable,
+    #[error("The merkle proof does not match the distribution")]
+    InvalidMerkleProof,
+    #[error("This distribution leaf has already been claimed")]
+    AlreadyClaimed,
+    #[error("Arithmetic overflowed or divided by zero")]
+    MathOverflow,
+    #[error("The instruction is reserved for a newer version of the program")]
+    UnsupportedInstruction,
+    #[error("The account sent too recently to send again")]
+    CooldownActive,
+    #[error("The permit is missing, malformed or for other accounts")]
+    InvalidPermit,
+    #[error("The permit has expired")]
+    PermitExpired,
+    #[error("The permit's nonce has already been used")]
+    PermitNonceUsed,
+    #[error("Only accounts owned by their mint's address can have their owner repaired")]
+    OwnerNotRepairable,
+    #[error("More accounts were passed than the instruction's counts call for")]
+    TooManyAccounts,
+    #[error("The instruction lists more items than it allows")]
+    TooManyItems,
+    #[error("The account is of another type than expected")]
+    InvalidAccountType,
+    #[error("The mint's metadata pointer is set, so it can't have metadata here")]
+    MetadataPointerSet,
+    #[error("The debit would take the account below its minimum balance")]
+    BelowMinimumBalance,
+    #[error("The snapshot's retention period hasn't passed")]
+    SnapshotRetained,
+    #[error("The instruction is deprecated; its replacement is logged")]
+    DeprecatedInstruction,
+    #[error("The debit exceeds the account's outflow limit for one instruction")]
+    OutflowLimitExceeded,
+    #[error("The outflow limit's timelock can only be extended")]
+    TimelockCannotBeShortened,
+    #[error("The key is not one of the mint's minters")]
+    NotAMinter,
+    #[error("The mint already has the most minters it can list")]
+    TooManyMinters,
+    #[error("The mint exceeds the minter's cap for its current window")]
+    MinterCapExceeded,
+}
+
+impl From<TokenError> for ProgramError {
+    fn from(e: TokenError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for TokenError {
+    fn type_of() -> &'static str {
+        "TokenError"
+    }
+}
+
+impl PrintProgramError for TokenError {
+    fn print<E>(&self)
+    where
+        E: 'static
+            + std::error::Error
+            + DecodeError<E>
+            + PrintProgramError
+            + num_traits::FromPrimitive,
+    {
+        msg!("Error: {}", self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::FromPrimitive;
+
+    #[test]
+    fn token_errors_become_custom_program_errors_by_code() {
+        assert_eq!(
+            ProgramError::from(TokenError::NotRentExempt),
+            ProgramError::Custom(0)
+        );
+        for error in ERRORS {
+            let variant = TokenError::from_u32(error.code).unwrap();
+            assert_eq!(
+                ProgramError::from(variant.clone()),
+                ProgramError::Custom(error.code)
+            );
+            assert_eq!(
+                <TokenError as DecodeError<TokenError>>::decode_custom_error_to_enum(error.code),
+                Some(variant)
+            );
+        }
+        assert_eq!(
+            <TokenError as DecodeError<TokenError>>::type_of(),
+            "TokenError"
+        );
+    }
+}
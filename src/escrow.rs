@@ -0,0 +1,18 @@
+//! Escrow authority address derivation
+
+use solana_program::pubkey::Pubkey;
+
+/// Seed prefix of escrow authority addresses.
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+/// Derives the address that owns the deposit account of an escrow.
+pub fn get_escrow_authority(escrow_address: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_escrow_authority_and_bump_seed(escrow_address, program_id).0
+}
+
+pub(crate) fn get_escrow_authority_and_bump_seed(
+    escrow_address: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ESCROW_SEED, &escrow_address.to_bytes()], program_id)
+}
@@ -0,0 +1,509 @@
+//! Structured events emitted through `sol_log_data`
+//!
+//! Each event is logged as a single data field: a one-byte discriminator
+//! followed by the event's fixed-size little-endian payload. The runtime
+//! renders it as a base64 `Program data:` log line, which off-chain indexers
+//! can decode with [`TokenEvent::unpack`], or dispatch on by first reading
+//! its [`EventDiscriminator`].
+
+use crate::{instruction::AuthorityType, state::AccountState};
+use num_traits::FromPrimitive;
+use solana_program::{
+    log::sol_log_data, program_error::ProgramError, program_option::COption, pubkey::Pubkey,
+};
+
+/// The first byte of each logged event, naming its [`TokenEvent`] variant.
+/// Values are never reused.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventDiscriminator {
+    Mint = 0,
+    Transfer = 1,
+    Clawback = 2,
+    AuthorityChanged = 3,
+    ConfigChanged = 4,
+    Burn = 5,
+    RecoveryUsed = 6,
+    OwnershipProved = 7,
+    MinterUsed = 8,
+}
+
+impl EventDiscriminator {
+    pub fn from_u8(discriminator: u8) -> Option<Self> {
+        match discriminator {
+            0 => Some(Self::Mint),
+            1 => Some(Self::Transfer),
+            2 => Some(Self::Clawback),
+            3 => Some(Self::AuthorityChanged),
+            4 => Some(Self::ConfigChanged),
+            5 => Some(Self::Burn),
+            6 => Some(Self::RecoveryUsed),
+            7 => Some(Self::OwnershipProved),
+            8 => Some(Self::MinterUsed),
+            _ => None,
+        }
+    }
+}
+
+/// Tokens were minted into `destination`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MintEvent {
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+/// Tokens moved from `source` to `destination`, authorized by `authority`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferEvent {
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+}
+
+/// The mint's permanent delegate moved or burned `amount` tokens out of
+/// `source`, regardless of the account's owner.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClawbackEvent {
+    pub mint: Pubkey,
+    pub source: Pubkey,
+    pub permanent_delegate: Pubkey,
+    pub amount: u64,
+}
+
+/// `authority` burned `amount` tokens out of `source`. A permanent
+/// delegate's burn also logs a [`ClawbackEvent`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BurnEvent {
+    pub mint: Pubkey,
+    pub source: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+}
+
+/// An authority of a mint or token account was replaced, by `SetAuthority`
+/// or by a pending owner accepting ownership.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthorityChangedEvent {
+    pub account: Pubkey,
+    pub authority_type: AuthorityType,
+    pub old: COption<Pubkey>,
+    pub new: COption<Pubkey>,
+}
+
+/// A setting of a mint or token account that an authority controls.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigChange {
+    /// The token account was frozen.
+    Frozen,
+    /// The token account was thawed.
+    Thawed,
+    /// The mint was paused.
+    Paused,
+    /// The mint was resumed.
+    Resumed,
+    /// The mint's interest rate, in basis points, was updated.
+    InterestRate(i16),
+    /// The state new accounts of the mint start in was set.
+    DefaultAccountState(AccountState),
+    /// The mint's minimum transfer amount was updated.
+    MinTransferAmount(u64),
+}
+
+/// What a mint's recovery authority did.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecoveryAction {
+    /// A token account of the mint was thawed.
+    Thaw = 0,
+    /// The mint was resumed.
+    Resume = 1,
+}
+
+/// The mint's recovery authority acted in place of its freeze or pause
+/// authority. Logged after the [`ConfigChangedEvent`] for the same change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecoveryUsedEvent {
+    pub account: Pubkey,
+    pub recovery_authority: Pubkey,
+    pub action: RecoveryAction,
+}
+
+/// `owner` proved it controls `token_account` by signing a
+/// `VerifyOwnership` with the verifier's `nonce`, at `unix_timestamp`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnershipProvedEvent {
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub nonce: u64,
+    pub unix_timestamp: i64,
+}
+
+/// One of the mint's minters, rather than its mint authority, minted
+/// `amount` tokens. Logged after the [`MintEvent`] for the same mint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MinterUsedEvent {
+    pub mint: Pubkey,
+    pub minter: Pubkey,
+    pub amount: u64,
+}
+
+/// `authority` changed a setting of `account`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigChangedEvent {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    pub change: ConfigChange,
+}
+
+/// Events emitted by the token program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenEvent {
+    /// Payload: `mint (32) | destination (32) | amount (8)`.
+    Mint(MintEvent),
+    /// Payload: `source (32) | destination (32) | authority (32) | amount (8)`.
+    Transfer(TransferEvent),
+    /// Payload: `mint (32) | source (32) | permanent_delegate (32) | amount (8)`.
+    Clawback(ClawbackEvent),
+    /// Payload: `account (32) | authority_type (1) | old (1 + 32) | new (1 + 32)`;
+    /// each authority is a tag, 1 if it is set, then the key, zeroed if not.
+    AuthorityChanged(AuthorityChangedEvent),
+    /// Payload: `account (32) | authority (32) | change (1) | value (8)`; the
+    /// value is the change's argument, widened to 8 bytes, or zero.
+    ConfigChanged(ConfigChangedEvent),
+    /// Payload: `mint (32) | source (32) | authority (32) | amount (8)`.
+    Burn(BurnEvent),
+    /// Payload: `account (32) | recovery_authority (32) | action (1)`.
+    RecoveryUsed(RecoveryUsedEvent),
+    /// Payload: `token_account (32) | owner (32) | nonce (8) | unix_timestamp (8)`.
+    OwnershipProved(OwnershipProvedEvent),
+    /// Payload: `mint (32) | minter (32) | amount (8)`.
+    MinterUsed(MinterUsedEvent),
+}
+
+impl TokenEvent {
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input.split_first().ok_or(ProgramError::InvalidArgument)?;
+        let discriminator =
+            EventDiscriminator::from_u8(tag).ok_or(ProgramError::InvalidArgument)?;
+
+        Ok(match discriminator {
+            EventDiscriminator::Mint => {
+                let (mint, rest) = unpack_pubkey(rest)?;
+                let (destination, rest) = unpack_pubkey(rest)?;
+                let amount = unpack_amount(rest)?;
+                Self::Mint(MintEvent {
+                    mint,
+                    destination,
+                    amount,
+                })
+            }
+            EventDiscriminator::Transfer => {
+                let (source, rest) = unpack_pubkey(rest)?;
+                let (destination, rest) = unpack_pubkey(rest)?;
+                let (authority, rest) = unpack_pubkey(rest)?;
+                let amount = unpack_amount(rest)?;
+                Self::Transfer(TransferEvent {
+                    source,
+                    destination,
+                    authority,
+                    amount,
+                })
+            }
+            EventDiscriminator::Clawback => {
+                let (mint, rest) = unpack_pubkey(rest)?;
+                let (source, rest) = unpack_pubkey(rest)?;
+                let (permanent_delegate, rest) = unpack_pubkey(rest)?;
+                let amount = unpack_amount(rest)?;
+                Self::Clawback(ClawbackEvent {
+                    mint,
+                    source,
+                    permanent_delegate,
+                    amount,
+                })
+            }
+            EventDiscriminator::AuthorityChanged => {
+                let (account, rest) = unpack_pubkey(rest)?;
+                let (&authority_type, rest) =
+                    rest.split_first().ok_or(ProgramError::InvalidArgument)?;
+                let authority_type = AuthorityType::from_u8(authority_type)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                let (old, rest) = unpack_pubkey_option(rest)?;
+                let (new, rest) = unpack_pubkey_option(rest)?;
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                Self::AuthorityChanged(AuthorityChangedEvent {
+                    account,
+                    authority_type,
+                    old,
+                    new,
+                })
+            }
+            EventDiscriminator::ConfigChanged => {
+                let (account, rest) = unpack_pubkey(rest)?;
+                let (authority, rest) = unpack_pubkey(rest)?;
+                let (&kind, rest) = rest.split_first().ok_or(ProgramError::InvalidArgument)?;
+                let value = unpack_amount(rest)?;
+                let change = match (kind, value) {
+                    (0, 0) => ConfigChange::Frozen,
+                    (1, 0) => ConfigChange::Thawed,
+                    (2, 0) => ConfigChange::Paused,
+                    (3, 0) => ConfigChange::Resumed,
+                    (4, value) => ConfigChange::InterestRate(
+                        i16::try_from(value as i64).map_err(|_| ProgramError::InvalidArgument)?,
+                    ),
+                    (5, value) => ConfigChange::DefaultAccountState(
+                        u8::try_from(value)
+                            .ok()
+                            .and_then(AccountState::from_u8)
+                            .ok_or(ProgramError::InvalidArgument)?,
+                    ),
+                    (6, value) => ConfigChange::MinTransferAmount(value),
+                    _ => return Err(ProgramError::InvalidArgument),
+                };
+                Self::ConfigChanged(ConfigChangedEvent {
+                    account,
+                    authority,
+                    change,
+                })
+            }
+            EventDiscriminator::Burn => {
+                let (mint, rest) = unpack_pubkey(rest)?;
+                let (source, rest) = unpack_pubkey(rest)?;
+                let (authority, rest) = unpack_pubkey(rest)?;
+                let amount = unpack_amount(rest)?;
+                Self::Burn(BurnEvent {
+                    mint,
+                    source,
+                    authority,
+                    amount,
+                })
+            }
+            EventDiscriminator::RecoveryUsed => {
+                let (account, rest) = unpack_pubkey(rest)?;
+                let (recovery_authority, rest) = unpack_pubkey(rest)?;
+                let action = match rest {
+                    [0] => RecoveryAction::Thaw,
+                    [1] => RecoveryAction::Resume,
+                    _ => return Err(ProgramError::InvalidArgument),
+                };
+                Self::RecoveryUsed(RecoveryUsedEvent {
+                    account,
+                    recovery_authority,
+                    action,
+                })
+            }
+            EventDiscriminator::OwnershipProved => {
+                let (token_account, rest) = unpack_pubkey(rest)?;
+                let (owner, rest) = unpack_pubkey(rest)?;
+                if rest.len() != 16 {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                let (nonce, unix_timestamp) = rest.split_at(8);
+                Self::OwnershipProved(OwnershipProvedEvent {
+                    token_account,
+                    owner,
+                    nonce: unpack_amount(nonce)?,
+                    unix_timestamp: unpack_amount(unix_timestamp)? as i64,
+                })
+            }
+            EventDiscriminator::MinterUsed => {
+                let (mint, rest) = unpack_pubkey(rest)?;
+                let (minter, rest) = unpack_pubkey(rest)?;
+                let amount = unpack_amount(rest)?;
+                Self::MinterUsed(MinterUsedEvent {
+                    mint,
+                    minter,
+                    amount,
+                })
+            }
+        })
+    }
+
+    /// The discriminator [`TokenEvent::pack`] starts with.
+    pub fn discriminator(&self) -> EventDiscriminator {
+        match self {
+            Self::Mint(_) => EventDiscriminator::Mint,
+            Self::Transfer(_) => EventDiscriminator::Transfer,
+            Self::Clawback(_) => EventDiscriminator::Clawback,
+            Self::AuthorityChanged(_) => EventDiscriminator::AuthorityChanged,
+            Self::ConfigChanged(_) => EventDiscriminator::ConfigChanged,
+            Self::Burn(_) => EventDiscriminator::Burn,
+            Self::RecoveryUsed(_) => EventDiscriminator::RecoveryUsed,
+            Self::OwnershipProved(_) => EventDiscriminator::OwnershipProved,
+            Self::MinterUsed(_) => EventDiscriminator::MinterUsed,
+        }
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = vec![self.discriminator() as u8];
+        match self {
+            Self::Mint(event) => {
+                buf.extend_from_slice(event.mint.as_ref());
+                buf.extend_from_slice(event.destination.as_ref());
+                buf.extend_from_slice(&event.amount.to_le_bytes());
+            }
+            Self::Transfer(event) => {
+                buf.extend_from_slice(event.source.as_ref());
+                buf.extend_from_slice(event.destination.as_ref());
+                buf.extend_from_slice(event.authority.as_ref());
+                buf.extend_from_slice(&event.amount.to_le_bytes());
+            }
+            Self::Clawback(event) => {
+                buf.extend_from_slice(event.mint.as_ref());
+                buf.extend_from_slice(event.source.as_ref());
+                buf.extend_from_slice(event.permanent_delegate.as_ref());
+                buf.extend_from_slice(&event.amount.to_le_bytes());
+            }
+            Self::AuthorityChanged(event) => {
+                buf.extend_from_slice(event.account.as_ref());
+                buf.push(event.authority_type.into_u8());
+                pack_pubkey_option(&event.old, &mut buf);
+                pack_pubkey_option(&event.new, &mut buf);
+            }
+            Self::ConfigChanged(event) => {
+                buf.extend_from_slice(event.account.as_ref());
+                buf.extend_from_slice(event.authority.as_ref());
+                let (kind, value) = match event.change {
+                    ConfigChange::Frozen => (0, 0),
+                    ConfigChange::Thawed => (1, 0),
+                    ConfigChange::Paused => (2, 0),
+                    ConfigChange::Resumed => (3, 0),
+                    ConfigChange::InterestRate(rate_bps) => (4, rate_bps as i64 as u64),
+                    ConfigChange::DefaultAccountState(state) => (5, state as u64),
+                    ConfigChange::MinTransferAmount(amount) => (6, amount),
+                };
+                buf.push(kind);
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+            Self::Burn(event) => {
+                buf.extend_from_slice(event.mint.as_ref());
+                buf.extend_from_slice(event.source.as_ref());
+                buf.extend_from_slice(event.authority.as_ref());
+                buf.extend_from_slice(&event.amount.to_le_bytes());
+            }
+            Self::RecoveryUsed(event) => {
+                buf.extend_from_slice(event.account.as_ref());
+                buf.extend_from_slice(event.recovery_authority.as_ref());
+                buf.push(event.action as u8);
+            }
+            Self::OwnershipProved(event) => {
+                buf.extend_from_slice(event.token_account.as_ref());
+                buf.extend_from_slice(event.owner.as_ref());
+                buf.extend_from_slice(&event.nonce.to_le_bytes());
+                buf.extend_from_slice(&event.unix_timestamp.to_le_bytes());
+            }
+            Self::MinterUsed(event) => {
+                buf.extend_from_slice(event.mint.as_ref());
+                buf.extend_from_slice(event.minter.as_ref());
+                buf.extend_from_slice(&event.amount.to_le_bytes());
+            }
+        }
+        buf
+    }
+
+    /// Logs the packed event.
+    pub fn emit(&self) {
+        sol_log_data(&[&self.pack()]);
+    }
+}
+
+/// Decodes the events `program_id` logged, in order, from the log messages
+/// of a transaction. Only `Program data:` lines logged while `program_id`
+/// was the innermost program running count, so no other program in the
+/// transaction can log an event in its name; lines that don't decode are
+/// skipped. Events of a failed transaction describe nothing that happened:
+/// callers check the transaction succeeded.
+#[cfg(feature = "client")]
+pub fn events_from_logs(log_messages: &[String], program_id: &Pubkey) -> Vec<TokenEvent> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let program_id = program_id.to_string();
+    let mut invoked = Vec::new();
+    let mut events = Vec::new();
+    for line in log_messages {
+        if let Some(data) = line.strip_prefix("Program data: ") {
+            if invoked.last() == Some(&program_id.as_str()) {
+                let event = STANDARD
+                    .decode(data)
+                    .ok()
+                    .map(|data| TokenEvent::unpack(&data));
+                if let Some(Ok(event)) = event {
+                    events.push(event);
+                }
+            }
+        } else if let Some((id, status)) = line
+            .strip_prefix("Program ")
+            .and_then(|line| line.split_once(' '))
+        {
+            if status.starts_with("invoke [") {
+                invoked.push(id);
+            } else if status == "success" || status.starts_with("failed") {
+                invoked.pop();
+            }
+        }
+    }
+    events
+}
+
+/// The `OwnershipProved` event `program_id` logged for `token_account` and
+/// `nonce`, as [`events_from_logs`] reads them, if any. Its `owner` is the
+/// account's owner when the proof was made.
+#[cfg(feature = "client")]
+pub fn find_ownership_proof(
+    log_messages: &[String],
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    nonce: u64,
+) -> Option<OwnershipProvedEvent> {
+    events_from_logs(log_messages, program_id)
+        .into_iter()
+        .find_map(|event| match event {
+            TokenEvent::OwnershipProved(proof)
+                if proof.token_account == *token_account && proof.nonce == nonce =>
+            {
+                Some(proof)
+            }
+            _ => None,
+        })
+}
+
+fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+    if input.len() < 32 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (key, rest) = input.split_at(32);
+    Ok((Pubkey::new_from_array(key.try_into().unwrap()), rest))
+}
+
+fn unpack_pubkey_option(input: &[u8]) -> Result<(COption<Pubkey>, &[u8]), ProgramError> {
+    let (&tag, rest) = input.split_first().ok_or(ProgramError::InvalidArgument)?;
+    let (key, rest) = unpack_pubkey(rest)?;
+    match tag {
+        0 if key == Pubkey::default() => Ok((COption::None, rest)),
+        1 => Ok((COption::Some(key), rest)),
+        _ => Err(ProgramError::InvalidArgument),
+    }
+}
+
+fn pack_pubkey_option(key: &COption<Pubkey>, buf: &mut Vec<u8>) {
+    match key {
+        COption::Some(key) => {
+            buf.push(1);
+            buf.extend_from_slice(key.as_ref());
+        }
+        COption::None => {
+            buf.push(0);
+            buf.extend_from_slice(&[0; 32]);
+        }
+    }
+}
+
+fn unpack_amount(input: &[u8]) -> Result<u64, ProgramError> {
+    let amount = input
+        .try_into()
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    Ok(u64::from_le_bytes(amount))
+}
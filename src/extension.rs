@@ -0,0 +1,1111 @@
+//! Optional token account and mint extensions, stored after the base layout
+//!
+//! A token account's data is `base (Token::LEN) | entry*`, where each entry
+//! is `type (2) | length (2) | value (length)`, little-endian. Accounts only
+//! grow by an entry when its extension is first enabled, so an account
+//! holds zero, some or all extensions, each at most once and in the order
+//! they were enabled.
+//!
+//! Mints hold entries of the same format after `Mint::LEN`, numbered by
+//! [`MintExtensionType`] instead.
+
+use crate::{
+    error::TokenError,
+    math,
+    state::{pack_coption_key, unpack_coption_key, Mint, Multisig, Token},
+};
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use solana_program::{
+    program_error::ProgramError, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+};
+use std::ops::Range;
+
+/// Size of an entry's `type | length` header.
+pub const HEADER_LEN: usize = 4;
+
+/// Size of the entries of every extension together.
+pub const MAX_EXTENSIONS_LEN: usize = 9 * HEADER_LEN
+    + MemoTransfer::LEN
+    + AccountLock::LEN
+    + PermitNonce::LEN
+    + MinimumBalance::LEN
+    + CloseAuthority::LEN
+    + OutflowLimit::LEN
+    + MintSettings::LEN
+    + Cooldown::LEN;
+
+/// Largest size of a token account, holding every extension.
+pub const MAX_ACCOUNT_LEN: usize = Token::LEN + MAX_EXTENSIONS_LEN;
+
+/// Size of the entries of every mint extension together.
+pub const MAX_MINT_EXTENSIONS_LEN: usize = 10 * HEADER_LEN
+    + Minters::LEN
+    + MetadataPointer::LEN
+    + RecoveryAuthority::LEN
+    + VestingReserve::LEN
+    + HolderCount::LEN
+    + TransferCooldown::LEN
+    + MintActivity::LEN
+    + MinTransferAmount::LEN
+    + AllowlistEnforcement::LEN
+    + Royalty::LEN;
+
+/// Largest size of a mint, holding every mint extension.
+pub const MAX_MINT_LEN: usize = Mint::LEN + MAX_MINT_EXTENSIONS_LEN;
+
+/// Extension types; an entry of any other type makes the account invalid.
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+pub enum ExtensionType {
+    /// [`MemoTransfer`]
+    MemoTransfer = 1,
+    /// [`AccountLock`]
+    AccountLock = 2,
+    /// [`ImmutableOwner`]
+    ImmutableOwner = 3,
+    /// [`PermitNonce`]
+    PermitNonce = 4,
+    /// [`MinimumBalance`]
+    MinimumBalance = 5,
+    /// [`CloseAuthority`]
+    CloseAuthority = 6,
+    /// [`OutflowLimit`]
+    OutflowLimit = 7,
+    /// [`MintSettings`]
+    MintSettings = 8,
+    /// [`Cooldown`]
+    Cooldown = 9,
+}
+
+/// Mint extension types; an entry of any other type makes the mint invalid.
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+pub enum MintExtensionType {
+    /// [`Minters`]
+    Minters = 1,
+    /// [`MetadataPointer`]
+    MetadataPointer = 2,
+    /// [`RecoveryAuthority`]
+    RecoveryAuthority = 3,
+    /// [`VestingReserve`]
+    VestingReserve = 4,
+    /// [`HolderCount`]
+    HolderCount = 5,
+    /// [`TransferCooldown`]
+    TransferCooldown = 6,
+    /// [`MintActivity`]
+    MintActivity = 7,
+    /// [`MinTransferAmount`]
+    MinTransferAmount = 8,
+    /// [`AllowlistEnforcement`]
+    AllowlistEnforcement = 9,
+    /// [`Royalty`]
+    Royalty = 10,
+}
+
+/// A typed extension value with a fixed packed length.
+pub trait Extension: Sized {
+    const TYPE: ExtensionType;
+    const LEN: usize;
+
+    fn pack_into_slice(&self, dst: &mut [u8]);
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError>;
+}
+
+/// [`Extension`] for mints.
+pub trait MintExtension: Sized {
+    const TYPE: MintExtensionType;
+    const LEN: usize;
+
+    fn pack_into_slice(&self, dst: &mut [u8]);
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError>;
+}
+
+/// Whether incoming transfers must be immediately preceded by a memo;
+/// toggled by the owner with `RequireMemo`/`UnrequireMemo`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoTransfer {
+    pub require_incoming_transfer_memos: bool,
+}
+
+impl Extension for MemoTransfer {
+    const TYPE: ExtensionType = ExtensionType::MemoTransfer;
+    const LEN: usize = 1;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.require_incoming_transfer_memos as u8;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let require_incoming_transfer_memos = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(MemoTransfer {
+            require_incoming_transfer_memos,
+        })
+    }
+}
+
+/// Unix timestamp before which the account can't send or burn tokens; set
+/// with `LockAccount`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AccountLock {
+    pub lock_until: i64,
+}
+
+impl Extension for AccountLock {
+    const TYPE: ExtensionType = ExtensionType::AccountLock;
+    const LEN: usize = 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.lock_until.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(AccountLock {
+            lock_until: i64::from_le_bytes(src.try_into().unwrap()),
+        })
+    }
+}
+
+/// The account's owner can never change; enabled with
+/// `InitializeImmutableOwner` before the account is initialized.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImmutableOwner;
+
+impl Extension for ImmutableOwner {
+    const TYPE: ExtensionType = ExtensionType::ImmutableOwner;
+    const LEN: usize = 0;
+
+    fn pack_into_slice(&self, _dst: &mut [u8]) {}
+
+    fn unpack_from_slice(_src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(ImmutableOwner)
+    }
+}
+
+/// The lowest nonce a `PermitTransfer` from the account may still use;
+/// added by the first one.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PermitNonce {
+    pub next_nonce: u64,
+}
+
+impl Extension for PermitNonce {
+    const TYPE: ExtensionType = ExtensionType::PermitNonce;
+    const LEN: usize = 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.next_nonce.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(PermitNonce {
+            next_nonce: u64::from_le_bytes(src.try_into().unwrap()),
+        })
+    }
+}
+
+/// The balance below which the account's debits need its enforcement
+/// authority's signature too; set with `SetMinimumBalance`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MinimumBalance {
+    pub minimum_balance: u64,
+    pub enforcement_authority: Pubkey,
+}
+
+impl Extension for MinimumBalance {
+    const TYPE: ExtensionType = ExtensionType::MinimumBalance;
+    const LEN: usize = 40;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0..8].copy_from_slice(&self.minimum_balance.to_le_bytes());
+        dst[8..40].copy_from_slice(self.enforcement_authority.as_ref());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(MinimumBalance {
+            minimum_balance: u64::from_le_bytes(src[0..8].try_into().unwrap()),
+            enforcement_authority: Pubkey::new_from_array(src[8..40].try_into().unwrap()),
+        })
+    }
+}
+
+/// A key besides the owner that may close the account once it is empty,
+/// but can't move or burn its tokens; set with `SetAuthority`. Revoking it
+/// leaves the entry, unset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CloseAuthority {
+    pub close_authority: COption<Pubkey>,
+}
+
+impl Extension for CloseAuthority {
+    const TYPE: ExtensionType = ExtensionType::CloseAuthority;
+    const LEN: usize = 36;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_coption_key(&self.close_authority, dst.try_into().unwrap());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(CloseAuthority {
+            close_authority: unpack_coption_key(src.try_into().unwrap())?,
+        })
+    }
+}
+
+/// The most a single instruction may debit from the account, whoever signs
+/// it; set by the owner with `SetOutflowLimit`. A looser limit waits out
+/// `timelock_slots` as the pending one, which applies from
+/// `pending_effective_slot`; a `pending_effective_slot` of zero means none
+/// is pending.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OutflowLimit {
+    pub max_outflow_per_instruction: u64,
+    pub timelock_slots: u64,
+    pub pending_max_outflow_per_instruction: u64,
+    pub pending_effective_slot: u64,
+}
+
+impl OutflowLimit {
+    /// The limit in force at `slot`, which is the pending one once its
+    /// timelock has passed.
+    pub fn max_outflow_at(&self, slot: u64) -> u64 {
+        if self.pending_effective_slot != 0 && slot >= self.pending_effective_slot {
+            self.pending_max_outflow_per_instruction
+        } else {
+            self.max_outflow_per_instruction
+        }
+    }
+}
+
+impl Extension for OutflowLimit {
+    const TYPE: ExtensionType = ExtensionType::OutflowLimit;
+    const LEN: usize = 32;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0..8].copy_from_slice(&self.max_outflow_per_instruction.to_le_bytes());
+        dst[8..16].copy_from_slice(&self.timelock_slots.to_le_bytes());
+        dst[16..24].copy_from_slice(&self.pending_max_outflow_per_instruction.to_le_bytes());
+        dst[24..32].copy_from_slice(&self.pending_effective_slot.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(src[offset..offset + 8].try_into().unwrap());
+        Ok(OutflowLimit {
+            max_outflow_per_instruction: u64_at(0),
+            timelock_slots: u64_at(8),
+            pending_max_outflow_per_instruction: u64_at(16),
+            pending_effective_slot: u64_at(24),
+        })
+    }
+}
+
+/// The settings of its mint that apply to the account, copied from the mint
+/// when the account is initialized, if it has any. Transfers out of the
+/// account fail without the mint if it [`requires_mint`](Self::requires_mint).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MintSettings {
+    /// The mint counts its holders, so transfers must include it, writable.
+    pub holder_count: bool,
+    /// The mint tracks its activity, so transfers must include it, writable.
+    pub activity_tracking: bool,
+    /// The mint has a minimum transfer amount, so transfers must include it.
+    pub min_transfer_amount: bool,
+    /// Tokens can only be sent or minted to the account while its owner is
+    /// on the mint's [`Allowlist`](crate::state::Allowlist).
+    pub enforce_allowlist: bool,
+    /// The mint has a royalty, so transfers must include it to pay it.
+    pub royalty: bool,
+}
+
+impl MintSettings {
+    /// Whether transfers out of the account must include its mint.
+    pub fn requires_mint(&self) -> bool {
+        self.holder_count || self.activity_tracking || self.min_transfer_amount || self.royalty
+    }
+}
+
+impl Extension for MintSettings {
+    const TYPE: ExtensionType = ExtensionType::MintSettings;
+    const LEN: usize = 5;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.holder_count as u8;
+        dst[1] = self.activity_tracking as u8;
+        dst[2] = self.min_transfer_amount as u8;
+        dst[3] = self.enforce_allowlist as u8;
+        dst[4] = self.royalty as u8;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let flag = |byte: u8| match byte {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ProgramError::InvalidAccountData),
+        };
+        Ok(MintSettings {
+            holder_count: flag(src[0])?,
+            activity_tracking: flag(src[1])?,
+            min_transfer_amount: flag(src[2])?,
+            enforce_allowlist: flag(src[3])?,
+            royalty: flag(src[4])?,
+        })
+    }
+}
+
+/// Slot of the account's last outgoing transfer, zero if it has sent none;
+/// added when the account is initialized if its mint has a
+/// [`TransferCooldown`], which transfers out of it must then include.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Cooldown {
+    pub last_outgoing_slot: u64,
+}
+
+impl Extension for Cooldown {
+    const TYPE: ExtensionType = ExtensionType::Cooldown;
+    const LEN: usize = 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.last_outgoing_slot.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Cooldown {
+            last_outgoing_slot: u64::from_le_bytes(src.try_into().unwrap()),
+        })
+    }
+}
+
+/// Most keys a mint's [`Minters`] can list.
+pub const MAX_MINTERS: usize = 8;
+
+/// A key that may mint tokens besides the mint authority, and what it has
+/// minted in its current window.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Minter {
+    pub minter: Pubkey,
+    /// The most it may mint per window of `window_slots`; a `window_slots`
+    /// of zero means it is uncapped.
+    pub max_per_window: u64,
+    pub window_slots: u64,
+    pub window_start_slot: u64,
+    pub minted_in_window: u64,
+}
+
+impl Minter {
+    pub const LEN: usize = 64;
+
+    /// Records `amount` minted at `slot`, starting a new window first if
+    /// the current one has ended. Returns `false`, recording nothing, if
+    /// that would take the window over its cap.
+    pub fn record_mint(&mut self, amount: u64, slot: u64) -> bool {
+        if self.window_slots == 0 {
+            return true;
+        }
+        let (mut start, mut minted) = (self.window_start_slot, self.minted_in_window);
+        if slot >= start.saturating_add(self.window_slots) {
+            (start, minted) = (slot, 0);
+        }
+        match minted.checked_add(amount) {
+            Some(minted) if minted <= self.max_per_window => {
+                (self.window_start_slot, self.minted_in_window) = (start, minted);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Keys besides the mint authority that may sign `MintTokens` and
+/// `MintToChecked`, up to [`MAX_MINTERS`] of them; managed by the mint
+/// authority with `AddMinter` and `RemoveMinter`. Removing the last one
+/// leaves the entry, empty.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Minters {
+    pub minters: Vec<Minter>,
+}
+
+impl MintExtension for Minters {
+    const TYPE: MintExtensionType = MintExtensionType::Minters;
+    const LEN: usize = 1 + MAX_MINTERS * Minter::LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst.fill(0);
+        dst[0] = self.minters.len() as u8;
+        for (minter, dst) in self
+            .minters
+            .iter()
+            .zip(dst[1..].chunks_exact_mut(Minter::LEN))
+        {
+            dst[0..32].copy_from_slice(minter.minter.as_ref());
+            dst[32..40].copy_from_slice(&minter.max_per_window.to_le_bytes());
+            dst[40..48].copy_from_slice(&minter.window_slots.to_le_bytes());
+            dst[48..56].copy_from_slice(&minter.window_start_slot.to_le_bytes());
+            dst[56..64].copy_from_slice(&minter.minted_in_window.to_le_bytes());
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let count = src[0] as usize;
+        if count > MAX_MINTERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let minters = src[1..]
+            .chunks_exact(Minter::LEN)
+            .take(count)
+            .map(|src| {
+                let u64_at =
+                    |offset: usize| u64::from_le_bytes(src[offset..offset + 8].try_into().unwrap());
+                Minter {
+                    minter: Pubkey::new_from_array(src[0..32].try_into().unwrap()),
+                    max_per_window: u64_at(32),
+                    window_slots: u64_at(40),
+                    window_start_slot: u64_at(48),
+                    minted_in_window: u64_at(56),
+                }
+            })
+            .collect();
+        Ok(Minters { minters })
+    }
+}
+
+/// Where a mint's metadata lives, if not in the metadata account this
+/// program derives for it, such as with a metadata program; set before the
+/// mint is initialized, and repointed or cleared by `authority` with
+/// `UpdateMetadataPointer`. A mint with a pointer can't have that account
+/// created.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MetadataPointer {
+    pub authority: COption<Pubkey>,
+    pub metadata_address: COption<Pubkey>,
+}
+
+impl MintExtension for MetadataPointer {
+    const TYPE: MintExtensionType = MintExtensionType::MetadataPointer;
+    const LEN: usize = 72;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_coption_key(&self.authority, (&mut dst[0..36]).try_into().unwrap());
+        pack_coption_key(
+            &self.metadata_address,
+            (&mut dst[36..72]).try_into().unwrap(),
+        );
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(MetadataPointer {
+            authority: unpack_coption_key(src[0..36].try_into().unwrap())?,
+            metadata_address: unpack_coption_key(src[36..72].try_into().unwrap())?,
+        })
+    }
+}
+
+/// A key that may thaw the mint's accounts and resume the mint in place of
+/// a lost freeze or pause authority, and do nothing else; set before the
+/// mint is initialized, and never changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RecoveryAuthority {
+    pub recovery_authority: Pubkey,
+}
+
+impl MintExtension for RecoveryAuthority {
+    const TYPE: MintExtensionType = MintExtensionType::RecoveryAuthority;
+    const LEN: usize = 32;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(self.recovery_authority.as_ref());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(RecoveryAuthority {
+            recovery_authority: Pubkey::new_from_array(src.try_into().unwrap()),
+        })
+    }
+}
+
+/// Tokens owed to the mint's vesting schedules and not yet claimed, which
+/// count against its `max_supply` as if already minted; added by the
+/// mint's first `CreateVesting`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VestingReserve {
+    pub reserved: u64,
+}
+
+impl MintExtension for VestingReserve {
+    const TYPE: MintExtensionType = MintExtensionType::VestingReserve;
+    const LEN: usize = 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.reserved.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(VestingReserve {
+            reserved: u64::from_le_bytes(src.try_into().unwrap()),
+        })
+    }
+}
+
+/// Number of the mint's token accounts with a nonzero balance; enabled
+/// before the mint is initialized.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HolderCount {
+    pub holders: u64,
+}
+
+impl MintExtension for HolderCount {
+    const TYPE: MintExtensionType = MintExtensionType::HolderCount;
+    const LEN: usize = 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.holders.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(HolderCount {
+            holders: u64::from_le_bytes(src.try_into().unwrap()),
+        })
+    }
+}
+
+/// Slots an account must wait after sending before it can send again; set
+/// before the mint is initialized.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransferCooldown {
+    pub cooldown_slots: u64,
+}
+
+impl MintExtension for TransferCooldown {
+    const TYPE: MintExtensionType = MintExtensionType::TransferCooldown;
+    const LEN: usize = 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.cooldown_slots.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(TransferCooldown {
+            cooldown_slots: u64::from_le_bytes(src.try_into().unwrap()),
+        })
+    }
+}
+
+/// How often and how recently a mint was used, for activity signals that
+/// don't need its transactions scanned; enabled before the mint is
+/// initialized.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MintActivity {
+    /// Successful transfers between two distinct accounts.
+    pub transfer_count: u64,
+    /// Unix timestamp of the last transfer, mint or burn.
+    pub last_activity_unix: i64,
+}
+
+impl MintActivity {
+    /// Stamps the activity with `unix_timestamp`, counting a transfer if
+    /// `is_transfer`.
+    pub fn record(&mut self, unix_timestamp: i64, is_transfer: bool) -> Result<(), TokenError> {
+        if is_transfer {
+            self.transfer_count = self
+                .transfer_count
+                .checked_add(1)
+                .ok_or(TokenError::Overflow)?;
+        }
+        self.last_activity_unix = unix_timestamp;
+        Ok(())
+    }
+}
+
+impl MintExtension for MintActivity {
+    const TYPE: MintExtensionType = MintExtensionType::MintActivity;
+    const LEN: usize = 16;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0..8].copy_from_slice(&self.transfer_count.to_le_bytes());
+        dst[8..16].copy_from_slice(&self.last_activity_unix.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(MintActivity {
+            transfer_count: u64::from_le_bytes(src[0..8].try_into().unwrap()),
+            last_activity_unix: i64::from_le_bytes(src[8..16].try_into().unwrap()),
+        })
+    }
+}
+
+/// Smallest amount a transfer may move, unless it empties the source; set
+/// before the mint is initialized, after which the mint authority can
+/// change it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MinTransferAmount {
+    pub min_transfer_amount: u64,
+}
+
+impl MintExtension for MinTransferAmount {
+    const TYPE: MintExtensionType = MintExtensionType::MinTransferAmount;
+    const LEN: usize = 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.min_transfer_amount.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(MinTransferAmount {
+            min_transfer_amount: u64::from_le_bytes(src.try_into().unwrap()),
+        })
+    }
+}
+
+/// The mint's token accounts can only receive tokens while their owner is on
+/// its [`Allowlist`](crate::state::Allowlist); added with
+/// `InitializeAllowlist` before the mint is initialized.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AllowlistEnforcement;
+
+impl MintExtension for AllowlistEnforcement {
+    const TYPE: MintExtensionType = MintExtensionType::AllowlistEnforcement;
+    const LEN: usize = 0;
+
+    fn pack_into_slice(&self, _dst: &mut [u8]) {}
+
+    fn unpack_from_slice(_src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(AllowlistEnforcement)
+    }
+}
+
+/// A creator's share of `basis_points` / 10_000 of each transferred amount,
+/// rounded down, credited to the `destination` token account; set before
+/// the mint is initialized.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Royalty {
+    pub basis_points: u16,
+    pub destination: Pubkey,
+}
+
+impl Royalty {
+    /// Largest accepted `basis_points`, the whole transfer.
+    pub const MAX_BASIS_POINTS: u16 = math::MAX_BASIS_POINTS;
+
+    /// Returns the share of a transfer of `amount` paid to the creator.
+    pub fn calculate_royalty(&self, amount: u64) -> Option<u64> {
+        math::multiply_by_bps_floor(amount, self.basis_points).ok()
+    }
+}
+
+impl MintExtension for Royalty {
+    const TYPE: MintExtensionType = MintExtensionType::Royalty;
+    const LEN: usize = 34;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0..2].copy_from_slice(&self.basis_points.to_le_bytes());
+        dst[2..34].copy_from_slice(self.destination.as_ref());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Royalty {
+            basis_points: u16::from_le_bytes(src[0..2].try_into().unwrap()),
+            destination: Pubkey::new_from_array(src[2..34].try_into().unwrap()),
+        })
+    }
+}
+
+/// Returns the type and value range of every entry in `tlv`, the data after
+/// a token account's base layout. Fails with `InvalidAccountData` on an
+/// unknown or repeated type, or an entry that doesn't fit or has the wrong
+/// length.
+fn entries(tlv: &[u8]) -> Entries<ExtensionType> {
+    parse_entries(tlv, |raw| {
+        ExtensionType::from_u16(raw)
+            .map(|extension_type| (extension_type, value_len(extension_type)))
+    })
+}
+
+/// [`entries`] for the data after a mint's base layout.
+fn mint_entries(tlv: &[u8]) -> Entries<MintExtensionType> {
+    parse_entries(tlv, |raw| {
+        MintExtensionType::from_u16(raw).map(|extension_type| match extension_type {
+            MintExtensionType::Minters => (extension_type, Minters::LEN),
+            MintExtensionType::MetadataPointer => (extension_type, MetadataPointer::LEN),
+            MintExtensionType::RecoveryAuthority => (extension_type, RecoveryAuthority::LEN),
+            MintExtensionType::VestingReserve => (extension_type, VestingReserve::LEN),
+            MintExtensionType::HolderCount => (extension_type, HolderCount::LEN),
+            MintExtensionType::TransferCooldown => (extension_type, TransferCooldown::LEN),
+            MintExtensionType::MintActivity => (extension_type, MintActivity::LEN),
+            MintExtensionType::MinTransferAmount => (extension_type, MinTransferAmount::LEN),
+            MintExtensionType::AllowlistEnforcement => (extension_type, AllowlistEnforcement::LEN),
+            MintExtensionType::Royalty => (extension_type, Royalty::LEN),
+        })
+    })
+}
+
+/// Each entry's type and value range.
+type Entries<T> = Result<Vec<(T, Range<usize>)>, ProgramError>;
+
+/// Parses entries whose raw type `kind` maps to a type and its value's
+/// length.
+fn parse_entries<T: Copy + PartialEq>(
+    tlv: &[u8],
+    kind: impl Fn(u16) -> Option<(T, usize)>,
+) -> Entries<T> {
+    let mut entries: Vec<(T, Range<usize>)> = Vec::new();
+    let mut offset = 0;
+    while offset < tlv.len() {
+        let header = tlv
+            .get(offset..offset + HEADER_LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let (extension_type, value_len) = kind(u16::from_le_bytes([header[0], header[1]]))
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let len = u16::from_le_bytes([header[2], header[3]]) as usize;
+        let value = offset + HEADER_LEN..offset + HEADER_LEN + len;
+        if len != value_len
+            || value.end > tlv.len()
+            || entries.iter().any(|(seen, _)| *seen == extension_type)
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset = value.end;
+        entries.push((extension_type, value));
+    }
+    Ok(entries)
+}
+
+fn value_len(extension_type: ExtensionType) -> usize {
+    match extension_type {
+        ExtensionType::MemoTransfer => MemoTransfer::LEN,
+        ExtensionType::AccountLock => AccountLock::LEN,
+        ExtensionType::ImmutableOwner => ImmutableOwner::LEN,
+        ExtensionType::PermitNonce => PermitNonce::LEN,
+        ExtensionType::MinimumBalance => MinimumBalance::LEN,
+        ExtensionType::CloseAuthority => CloseAuthority::LEN,
+        ExtensionType::OutflowLimit => OutflowLimit::LEN,
+        ExtensionType::MintSettings => MintSettings::LEN,
+        ExtensionType::Cooldown => Cooldown::LEN,
+    }
+}
+
+fn tlv(data: &[u8]) -> Result<&[u8], ProgramError> {
+    data.get(Token::LEN..)
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+/// Checks that the data after a token account's base layout is a valid
+/// sequence of entries.
+pub fn validate(data: &[u8]) -> Result<(), ProgramError> {
+    entries(tlv(data)?).map(|_| ())
+}
+
+/// Returns the types of the extensions a token account holds, in order.
+pub fn get_extension_types(data: &[u8]) -> Result<Vec<ExtensionType>, ProgramError> {
+    Ok(entries(tlv(data)?)?
+        .into_iter()
+        .map(|(extension_type, _)| extension_type)
+        .collect())
+}
+
+/// Returns a token account's `T` extension, if it holds one.
+pub fn get_extension<T: Extension>(data: &[u8]) -> Result<Option<T>, ProgramError> {
+    let tlv = tlv(data)?;
+    entries(tlv)?
+        .into_iter()
+        .find(|(extension_type, _)| *extension_type == T::TYPE)
+        .map(|(_, value)| T::unpack_from_slice(&tlv[value]))
+        .transpose()
+}
+
+/// Returns the length the account must have to hold a `T` extension: its
+/// current length if it already holds one, or that plus a new entry.
+pub fn len_with<T: Extension>(data: &[u8]) -> Result<usize, ProgramError> {
+    if get_extension::<T>(data)?.is_some() {
+        Ok(data.len())
+    } else {
+        Ok(data.len() + HEADER_LEN + T::LEN)
+    }
+}
+
+/// Writes `extension` into a token account, overwriting the existing `T`
+/// entry, or else filling an entry's worth of zeroed space at the end of
+/// the data, as left by growing the account to [`len_with`].
+pub fn init_extension<T: Extension>(data: &mut [u8], extension: &T) -> Result<(), ProgramError> {
+    let tlv = data
+        .get_mut(Token::LEN..)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    init_entry(tlv, entries, T::TYPE, T::TYPE as u16, T::LEN, |dst| {
+        extension.pack_into_slice(dst)
+    })
+}
+
+/// Checks that the data after a mint's base layout is a valid sequence of
+/// entries.
+pub fn validate_mint(data: &[u8]) -> Result<(), ProgramError> {
+    mint_entries(mint_tlv(data)?).map(|_| ())
+}
+
+/// Returns a mint's `T` extension, if it holds one.
+pub fn get_mint_extension<T: MintExtension>(data: &[u8]) -> Result<Option<T>, ProgramError> {
+    let tlv = mint_tlv(data)?;
+    mint_entries(tlv)?
+        .into_iter()
+        .find(|(extension_type, _)| *extension_type == T::TYPE)
+        .map(|(_, value)| T::unpack_from_slice(&tlv[value]))
+        .transpose()
+}
+
+/// [`len_with`] for mints.
+pub fn mint_len_with<T: MintExtension>(data: &[u8]) -> Result<usize, ProgramError> {
+    if get_mint_extension::<T>(data)?.is_some() {
+        Ok(data.len())
+    } else {
+        Ok(data.len() + HEADER_LEN + T::LEN)
+    }
+}
+
+/// [`init_extension`] for mints, grown to [`mint_len_with`].
+pub fn init_mint_extension<T: MintExtension>(
+    data: &mut [u8],
+    extension: &T,
+) -> Result<(), ProgramError> {
+    let tlv = data
+        .get_mut(Mint::LEN..)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    init_entry(tlv, mint_entries, T::TYPE, T::TYPE as u16, T::LEN, |dst| {
+        extension.pack_into_slice(dst)
+    })
+}
+
+fn mint_tlv(data: &[u8]) -> Result<&[u8], ProgramError> {
+    data.get(Mint::LEN..)
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+fn init_entry<T: Copy + PartialEq>(
+    tlv: &mut [u8],
+    entries: fn(&[u8]) -> Entries<T>,
+    extension_type: T,
+    raw_type: u16,
+    len: usize,
+    pack: impl FnOnce(&mut [u8]),
+) -> Result<(), ProgramError> {
+    // Data that still ends in zeroed space doesn't parse as a whole.
+    if let Some((_, value)) = entries(tlv)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|(found, _)| *found == extension_type)
+    {
+        pack(&mut tlv[value]);
+        return Ok(());
+    }
+
+    let start = tlv
+        .len()
+        .checked_sub(HEADER_LEN + len)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    entries(&tlv[..start])?;
+    let entry = &mut tlv[start..];
+    if entry.iter().any(|&byte| byte != 0) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    entry[0..2].copy_from_slice(&raw_type.to_le_bytes());
+    entry[2..4].copy_from_slice(&(len as u16).to_le_bytes());
+    pack(&mut entry[HEADER_LEN..]);
+    Ok(())
+}
+
+// Token accounts are told apart from mints and multisigs by their account
+// type; the lengths no longer overlap only so that untyped version 2 token
+// accounts can't be mistaken for the other types during migration.
+const _: () = assert!(MAX_ACCOUNT_LEN < Multisig::LEN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::{coption, pubkey};
+    use proptest::{collection::vec, prelude::*};
+
+    prop_compose! {
+        fn minter()(
+            minter in pubkey(),
+            max_per_window in any::<u64>(),
+            window_slots in any::<u64>(),
+            window_start_slot in any::<u64>(),
+            minted_in_window in any::<u64>(),
+        ) -> Minter {
+            Minter { minter, max_per_window, window_slots, window_start_slot, minted_in_window }
+        }
+    }
+
+    /// Grows `data` to hold `extension` and writes it, as the processor does.
+    fn add<T: Extension>(data: &mut Vec<u8>, extension: &T) {
+        let len = len_with::<T>(data).unwrap();
+        data.resize(len, 0);
+        init_extension(data, extension).unwrap();
+    }
+
+    /// [`add`] for mints.
+    fn add_to_mint<T: MintExtension>(data: &mut Vec<u8>, extension: &T) {
+        let len = mint_len_with::<T>(data).unwrap();
+        data.resize(len, 0);
+        init_mint_extension(data, extension).unwrap();
+    }
+
+    proptest! {
+        #[test]
+        fn extensions_round_trip(
+            lock_until in any::<i64>(),
+            next_nonce in any::<u64>(),
+            minimum_balance in any::<u64>(),
+            enforcement_authority in pubkey(),
+            close_authority in coption(pubkey()),
+            require_incoming_transfer_memos in any::<bool>(),
+            holder_count in any::<bool>(),
+            activity_tracking in any::<bool>(),
+            min_transfer_amount in any::<bool>(),
+            enforce_allowlist in any::<bool>(),
+            royalty in any::<bool>(),
+            last_outgoing_slot in any::<u64>(),
+        ) {
+            let lock = AccountLock { lock_until };
+            let nonce = PermitNonce { next_nonce };
+            let minimum = MinimumBalance { minimum_balance, enforcement_authority };
+            let close = CloseAuthority { close_authority };
+            let memo = MemoTransfer { require_incoming_transfer_memos };
+            let settings = MintSettings {
+                holder_count,
+                activity_tracking,
+                min_transfer_amount,
+                enforce_allowlist,
+                royalty,
+            };
+            let cooldown = Cooldown { last_outgoing_slot };
+
+            let mut data = vec![0; Token::LEN];
+            add(&mut data, &settings);
+            add(&mut data, &cooldown);
+            add(&mut data, &lock);
+            add(&mut data, &nonce);
+            add(&mut data, &ImmutableOwner);
+            add(&mut data, &minimum);
+            add(&mut data, &close);
+            prop_assert_eq!(get_extension::<AccountLock>(&data), Ok(Some(lock)));
+            prop_assert_eq!(get_extension::<PermitNonce>(&data), Ok(Some(nonce)));
+            prop_assert_eq!(get_extension::<MinimumBalance>(&data), Ok(Some(minimum)));
+            prop_assert_eq!(get_extension::<CloseAuthority>(&data), Ok(Some(close)));
+            prop_assert_eq!(get_extension::<MintSettings>(&data), Ok(Some(settings)));
+            prop_assert_eq!(get_extension::<Cooldown>(&data), Ok(Some(cooldown)));
+            prop_assert_eq!(get_extension::<MemoTransfer>(&data), Ok(None));
+
+            // Rewriting an entry leaves the account's length and the other
+            // entries alone.
+            let len = data.len();
+            let lock = AccountLock { lock_until: !lock_until };
+            add(&mut data, &lock);
+            add(&mut data, &memo);
+            prop_assert_eq!(data.len(), len + HEADER_LEN + MemoTransfer::LEN);
+            prop_assert_eq!(get_extension::<AccountLock>(&data), Ok(Some(lock)));
+            prop_assert_eq!(get_extension::<MemoTransfer>(&data), Ok(Some(memo)));
+            prop_assert_eq!(get_extension::<PermitNonce>(&data), Ok(Some(nonce)));
+            prop_assert_eq!(
+                get_extension_types(&data),
+                Ok(vec![
+                    ExtensionType::MintSettings,
+                    ExtensionType::Cooldown,
+                    ExtensionType::AccountLock,
+                    ExtensionType::PermitNonce,
+                    ExtensionType::ImmutableOwner,
+                    ExtensionType::MinimumBalance,
+                    ExtensionType::CloseAuthority,
+                    ExtensionType::MemoTransfer,
+                ])
+            );
+        }
+
+        #[test]
+        fn mint_extensions_round_trip(
+            minters in vec(minter(), 0..=MAX_MINTERS),
+            authority in coption(pubkey()),
+            metadata_address in coption(pubkey()),
+            recovery_authority in pubkey(),
+            reserved in any::<u64>(),
+            holders in any::<u64>(),
+            cooldown_slots in any::<u64>(),
+            transfer_count in any::<u64>(),
+            last_activity_unix in any::<i64>(),
+            min_transfer_amount in any::<u64>(),
+            basis_points in any::<u16>(),
+            destination in pubkey(),
+        ) {
+            let minters = Minters { minters };
+            let pointer = MetadataPointer { authority, metadata_address };
+            let recovery = RecoveryAuthority { recovery_authority };
+            let reserve = VestingReserve { reserved };
+            let count = HolderCount { holders };
+            let cooldown = TransferCooldown { cooldown_slots };
+            let activity = MintActivity { transfer_count, last_activity_unix };
+            let minimum = MinTransferAmount { min_transfer_amount };
+            let royalty = Royalty { basis_points, destination };
+            let mut data = vec![0; Mint::LEN];
+            add_to_mint(&mut data, &pointer);
+            prop_assert_eq!(get_mint_extension::<Minters>(&data), Ok(None));
+            add_to_mint(&mut data, &recovery);
+            add_to_mint(&mut data, &reserve);
+            add_to_mint(&mut data, &count);
+            add_to_mint(&mut data, &cooldown);
+            add_to_mint(&mut data, &activity);
+            add_to_mint(&mut data, &minimum);
+            add_to_mint(&mut data, &AllowlistEnforcement);
+            add_to_mint(&mut data, &royalty);
+            add_to_mint(&mut data, &minters);
+            prop_assert_eq!(data.len(), MAX_MINT_LEN);
+            prop_assert_eq!(get_mint_extension::<Minters>(&data), Ok(Some(minters)));
+            prop_assert_eq!(get_mint_extension::<MetadataPointer>(&data), Ok(Some(pointer)));
+            prop_assert_eq!(get_mint_extension::<RecoveryAuthority>(&data), Ok(Some(recovery)));
+            prop_assert_eq!(get_mint_extension::<VestingReserve>(&data), Ok(Some(reserve)));
+            prop_assert_eq!(get_mint_extension::<HolderCount>(&data), Ok(Some(count)));
+            prop_assert_eq!(get_mint_extension::<TransferCooldown>(&data), Ok(Some(cooldown)));
+            prop_assert_eq!(get_mint_extension::<MintActivity>(&data), Ok(Some(activity)));
+            prop_assert_eq!(get_mint_extension::<MinTransferAmount>(&data), Ok(Some(minimum)));
+            prop_assert_eq!(
+                get_mint_extension::<AllowlistEnforcement>(&data),
+                Ok(Some(AllowlistEnforcement))
+            );
+            prop_assert_eq!(get_mint_extension::<Royalty>(&data), Ok(Some(royalty)));
+        }
+
+        #[test]
+        fn corrupted_entries_never_panic(tail in vec(any::<u8>(), 0..MAX_MINT_EXTENSIONS_LEN + 8)) {
+            let mut data = vec![0; Token::LEN];
+            data.extend_from_slice(&tail[..tail.len().min(MAX_EXTENSIONS_LEN + 8)]);
+            let _ = validate(&data);
+            let _ = get_extension_types(&data);
+            let _ = get_extension::<MemoTransfer>(&data);
+            let _ = get_extension::<AccountLock>(&data);
+            let _ = get_extension::<MinimumBalance>(&data);
+            let _ = get_extension::<CloseAuthority>(&data);
+            let _ = get_extension::<OutflowLimit>(&data);
+            let _ = get_extension::<MintSettings>(&data);
+            let _ = get_extension::<Cooldown>(&data);
+            let _ = init_extension(&mut data, &PermitNonce { next_nonce: 1 });
+
+            let mut data = vec![0; Mint::LEN];
+            data.extend_from_slice(&tail);
+            let _ = validate_mint(&data);
+            let _ = get_mint_extension::<Minters>(&data);
+            let _ = get_mint_extension::<MetadataPointer>(&data);
+            let _ = get_mint_extension::<RecoveryAuthority>(&data);
+            let _ = get_mint_extension::<VestingReserve>(&data);
+            let _ = get_mint_extension::<HolderCount>(&data);
+            let _ = get_mint_extension::<TransferCooldown>(&data);
+            let _ = get_mint_extension::<MintActivity>(&data);
+            let _ = get_mint_extension::<MinTransferAmount>(&data);
+            let _ = get_mint_extension::<AllowlistEnforcement>(&data);
+            let _ = get_mint_extension::<Royalty>(&data);
+            let _ = init_mint_extension(&mut data, &Minters::default());
+        }
+    }
+}
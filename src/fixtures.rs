@@ -0,0 +1,254 @@
+//! Canned account data for integration tests outside this crate
+//!
+//! [`fixtures`] packs one account of each shape integrators most often need
+//! to load into their own test validators: a mint with a transfer fee, a
+//! frozen token account of it, a multisig, a token account in the legacy
+//! layout, and a malformed token account that every read must reject. Keys
+//! are derived from fixed names with [`fixture_key`], so the bytes only
+//! change when a layout does. The `generate-fixtures` binary writes
+//! [`files`] to a directory, `fixtures/` by default:
+//!
+//! ```text
+//! cargo run --features client --bin generate-fixtures -- fixtures
+//! ```
+//!
+//! `tests/fixtures.rs` compares them with the committed copies. Every
+//! account is owned by the token program, under whatever id it is deployed.
+
+use crate::{
+    extension::HEADER_LEN,
+    instruction::MAX_SIGNERS,
+    state::{AccountState, Mint, Multisig, Token, TransferFee, ACCOUNT_VERSION},
+};
+use solana_program::{
+    hash::hashv, program_option::COption, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use std::fmt::Write;
+
+/// Name of the manifest [`files`] lists the fixtures in.
+pub const MANIFEST: &str = "manifest.json";
+
+/// One account's data, and what it was built from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fixture {
+    /// The data is written to `{name}.bin`.
+    pub name: &'static str,
+    /// The account type the data is, or is meant to look like.
+    pub account_type: &'static str,
+    pub address: Pubkey,
+    pub data: Vec<u8>,
+    /// Whether `Pack` reads `data` as an `account_type`.
+    pub valid: bool,
+    /// What the account was built with: names and JSON values.
+    pub parameters: Vec<(&'static str, String)>,
+}
+
+/// A key derived from `name`, the same on every run.
+pub fn fixture_key(name: &str) -> Pubkey {
+    Pubkey::new_from_array(hashv(&[b"solana_contracts fixture ", name.as_bytes()]).to_bytes())
+}
+
+/// Every fixture, in the order the manifest lists them.
+pub fn fixtures() -> Vec<Fixture> {
+    let mint = fixture_key("mint");
+    let mint_authority = fixture_key("mint_authority");
+    let freeze_authority = fixture_key("freeze_authority");
+    let transfer_fee_authority = fixture_key("transfer_fee_authority");
+    let owner = fixture_key("owner");
+    let transfer_fee = TransferFee {
+        basis_points: 50,
+        maximum_fee: 5_000,
+    };
+
+    let fee_mint = Mint {
+        mint_authority: COption::Some(mint_authority),
+        supply: 1_000_000,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::Some(freeze_authority),
+        transfer_fee_authority: COption::Some(transfer_fee_authority),
+        transfer_fee: COption::Some(transfer_fee),
+        ..Mint::default()
+    };
+    let frozen = Token {
+        mint,
+        owner,
+        amount: 250_000,
+        state: AccountState::Frozen,
+        withheld_amount: COption::Some(125),
+        ..Token::default()
+    };
+
+    let signers: Vec<Pubkey> = ["signer_0", "signer_1", "signer_2"]
+        .iter()
+        .map(|name| fixture_key(name))
+        .collect();
+    let mut multisig = Multisig {
+        m: 2,
+        n: signers.len() as u8,
+        is_initialized: true,
+        signers: [Pubkey::default(); MAX_SIGNERS],
+    };
+    multisig.signers[..signers.len()].copy_from_slice(&signers);
+
+    // The unversioned layout: `amount (8) | mint (32) | owner (32)`.
+    let legacy_amount = 42_000u64;
+    let mut legacy = Vec::with_capacity(Token::LEGACY_LEN);
+    legacy.extend_from_slice(&legacy_amount.to_le_bytes());
+    legacy.extend_from_slice(mint.as_ref());
+    legacy.extend_from_slice(owner.as_ref());
+
+    // A valid account followed by an extension entry of an unknown type.
+    let unknown_extension_type = u16::MAX;
+    let mut malformed = pack(Token {
+        mint,
+        owner,
+        amount: 1,
+        state: AccountState::Initialized,
+        ..Token::default()
+    });
+    malformed.extend_from_slice(&unknown_extension_type.to_le_bytes());
+    malformed.extend_from_slice(&[0; HEADER_LEN - 2]);
+
+    vec![
+        Fixture {
+            name: "mint_with_transfer_fee",
+            account_type: "Mint",
+            address: mint,
+            data: pack(fee_mint.clone()),
+            valid: true,
+            parameters: vec![
+                ("mint_authority", key(&mint_authority)),
+                ("freeze_authority", key(&freeze_authority)),
+                ("transfer_fee_authority", key(&transfer_fee_authority)),
+                ("supply", fee_mint.supply.to_string()),
+                ("decimals", fee_mint.decimals.to_string()),
+                (
+                    "transfer_fee_basis_points",
+                    transfer_fee.basis_points.to_string(),
+                ),
+                ("maximum_fee", transfer_fee.maximum_fee.to_string()),
+            ],
+        },
+        Fixture {
+            name: "frozen_token_account",
+            account_type: "Token",
+            address: fixture_key("frozen_token_account"),
+            data: pack(frozen.clone()),
+            valid: true,
+            parameters: vec![
+                ("mint", key(&mint)),
+                ("owner", key(&owner)),
+                ("amount", frozen.amount.to_string()),
+                ("state", string("Frozen")),
+                ("withheld_amount", 125.to_string()),
+            ],
+        },
+        Fixture {
+            name: "multisig_2_of_3",
+            account_type: "Multisig",
+            address: fixture_key("multisig"),
+            data: pack(multisig.clone()),
+            valid: true,
+            parameters: vec![
+                ("m", multisig.m.to_string()),
+                ("n", multisig.n.to_string()),
+                (
+                    "signers",
+                    format!(
+                        "[{}]",
+                        signers.iter().map(key).collect::<Vec<_>>().join(", ")
+                    ),
+                ),
+            ],
+        },
+        Fixture {
+            name: "legacy_token_account",
+            account_type: "Token",
+            address: fixture_key("legacy_token_account"),
+            data: legacy,
+            // Only `Token::unpack_any_version` and `MigrateAccount` read it.
+            valid: false,
+            parameters: vec![
+                ("layout", string("legacy")),
+                ("mint", key(&mint)),
+                ("owner", key(&owner)),
+                ("amount", legacy_amount.to_string()),
+            ],
+        },
+        Fixture {
+            name: "malformed_token_account",
+            account_type: "Token",
+            address: fixture_key("malformed_token_account"),
+            data: malformed,
+            valid: false,
+            parameters: vec![
+                ("unknown_extension_type", unknown_extension_type.to_string()),
+                ("expected_error", string("InvalidAccountData")),
+            ],
+        },
+    ]
+}
+
+/// Renders the manifest of `fixtures` as pretty-printed JSON, ending in a
+/// newline. Lamports are the rent-exempt minimum for each length.
+pub fn manifest_json(fixtures: &[Fixture]) -> String {
+    let rent = Rent::default();
+    let mut json = String::new();
+    write!(
+        json,
+        "{{\n  \"account_version\": {ACCOUNT_VERSION},\n  \"fixtures\": ["
+    )
+    .unwrap();
+    for (i, fixture) in fixtures.iter().enumerate() {
+        json.push_str(if i == 0 { "\n" } else { ",\n" });
+        write!(
+            json,
+            "    {{\n      \"name\": {},\n      \"file\": \"{}.bin\",\n      \
+             \"account_type\": {},\n      \"address\": {},\n      \"data_len\": {},\n      \
+             \"lamports\": {},\n      \"valid\": {},\n      \"parameters\": {{",
+            string(fixture.name),
+            fixture.name,
+            string(fixture.account_type),
+            key(&fixture.address),
+            fixture.data.len(),
+            rent.minimum_balance(fixture.data.len()),
+            fixture.valid,
+        )
+        .unwrap();
+        for (j, (name, value)) in fixture.parameters.iter().enumerate() {
+            json.push_str(if j == 0 { "\n" } else { ",\n" });
+            write!(json, "        {}: {value}", string(name)).unwrap();
+        }
+        json.push_str("\n      }\n    }");
+    }
+    json.push_str("\n  ]\n}\n");
+    json
+}
+
+/// The file names and contents the fixtures are written as: one `.bin`
+/// file each, then the [`MANIFEST`].
+pub fn files() -> Vec<(String, Vec<u8>)> {
+    let fixtures = fixtures();
+    let mut files: Vec<_> = fixtures
+        .iter()
+        .map(|fixture| (format!("{}.bin", fixture.name), fixture.data.clone()))
+        .collect();
+    files.push((MANIFEST.to_string(), manifest_json(&fixtures).into_bytes()));
+    files
+}
+
+fn pack<T: Pack>(value: T) -> Vec<u8> {
+    let mut data = vec![0; T::LEN];
+    value.pack_into_slice(&mut data);
+    data
+}
+
+fn key(key: &Pubkey) -> String {
+    string(&key.to_string())
+}
+
+/// `value` as a JSON string; fixture names and parameters need no escapes.
+fn string(value: &str) -> String {
+    format!("\"{value}\"")
+}
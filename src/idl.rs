@@ -0,0 +1,1533 @@
+//! Machine-readable description of the instruction set
+//!
+//! [`instructions`] lists every [`TokenInstruction`] with its accounts, in
+//! the order the processor reads them, and its arguments, in the order they
+//! are packed. [`to_json`] renders it, with the error codes, in the shank
+//! IDL format that client generators read. Accounts that only some calls
+//! pass, such as multisignature signers, are described by
+//! `remainingAccounts` rather than listed.
+
+use crate::{error::ERRORS, instruction::TokenInstruction};
+use std::fmt::Write;
+
+/// An account an instruction reads, at a fixed position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IdlAccount {
+    pub name: &'static str,
+    pub is_mut: bool,
+    pub is_signer: bool,
+    pub desc: &'static str,
+}
+
+/// The encoding of an instruction argument.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IdlType {
+    U8,
+    U16,
+    I16,
+    U64,
+    I64,
+    PublicKey,
+    /// A `u32` byte length, then UTF-8 bytes.
+    String,
+    /// A one-byte tag, then the value if the tag is 1.
+    Option(&'static IdlType),
+    /// A one-byte count, then the values.
+    Vec(&'static IdlType),
+    /// A fixed number of values, without a count.
+    Array(&'static IdlType, usize),
+    /// One of [`TYPES`], packed as a single byte.
+    Defined(&'static str),
+}
+
+/// An instruction argument, packed after the discriminant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IdlArg {
+    pub name: &'static str,
+    pub ty: IdlType,
+}
+
+/// One instruction of the program.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IdlInstruction {
+    /// The [`TokenInstruction`] variant.
+    pub name: &'static str,
+    /// The first byte of the instruction data.
+    pub discriminant: u8,
+    pub accounts: &'static [IdlAccount],
+    pub args: &'static [IdlArg],
+    /// The accounts that may follow the fixed ones, if any.
+    pub remaining_accounts: Option<&'static str>,
+}
+
+/// A one-byte enum used as an argument.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IdlEnum {
+    pub name: &'static str,
+    pub variants: &'static [&'static str],
+}
+
+/// The enums [`IdlType::Defined`] refers to, their variants in order of
+/// their byte value.
+pub const TYPES: &[IdlEnum] = &[
+    IdlEnum {
+        name: "AuthorityType",
+        variants: &[
+            "MintTokens",
+            "FreezeAccount",
+            "AccountOwner",
+            "TransferFeeConfig",
+            "CloseMint",
+            "CloseAccount",
+        ],
+    },
+    IdlEnum {
+        name: "AccountState",
+        variants: &["Uninitialized", "Initialized", "Frozen"],
+    },
+];
+
+const fn readonly(name: &'static str, desc: &'static str) -> IdlAccount {
+    IdlAccount {
+        name,
+        is_mut: false,
+        is_signer: false,
+        desc,
+    }
+}
+
+const fn writable(name: &'static str, desc: &'static str) -> IdlAccount {
+    IdlAccount {
+        name,
+        is_mut: true,
+        is_signer: false,
+        desc,
+    }
+}
+
+const fn signer(name: &'static str, desc: &'static str) -> IdlAccount {
+    IdlAccount {
+        name,
+        is_mut: false,
+        is_signer: true,
+        desc,
+    }
+}
+
+const fn writable_signer(name: &'static str, desc: &'static str) -> IdlAccount {
+    IdlAccount {
+        name,
+        is_mut: true,
+        is_signer: true,
+        desc,
+    }
+}
+
+const fn arg(name: &'static str, ty: IdlType) -> IdlArg {
+    IdlArg { name, ty }
+}
+
+const SIGNERS: Option<&str> =
+    Some("M signer accounts, if the authority is a multisignature account.");
+const SIGNERS_THEN_PAYER: Option<&str> = Some(
+    "M signer accounts, if the authority is a multisignature account; then, when the account \
+     grows, a writable, signing payer and the system program.",
+);
+const INITIALIZE_ACCOUNT_PAYER: Option<&str> = Some(
+    "When the mint's settings grow the account by a `MintSettings` or `Cooldown` extension, \
+     a writable, signing payer and the system program.",
+);
+const TRANSFER_ACCOUNTS: Option<&str> = Some(
+    "M signer accounts, if the authority is a multisignature account; the instructions sysvar, \
+     if a destination requires a memo; the allowlist account, if the mint enforces one; the \
+     source's enforcement authority, signing, if the debit takes it below its minimum balance.",
+);
+
+const BURN_REMAINING_ACCOUNTS: Option<&str> = Some(
+    "M signer accounts, if the authority is a multisignature account; the account's \
+     enforcement authority, signing, if the burn takes it below its minimum balance.",
+);
+
+const PUBKEY_OPTION: IdlType = IdlType::Option(&IdlType::PublicKey);
+const HASH: IdlType = IdlType::Array(&IdlType::U8, 32);
+
+const MINT_TO_ACCOUNTS: &[IdlAccount] = &[
+    writable("mint", "The mint."),
+    writable("account", "The token account to credit."),
+    signer(
+        "mintAuthority",
+        "The mint authority or a minter, or a multisignature account.",
+    ),
+];
+const BURN_ACCOUNTS: &[IdlAccount] = &[
+    writable("account", "The token account."),
+    writable("mint", "The mint."),
+    signer(
+        "owner",
+        "The token account owner or delegate, or a multisignature account.",
+    ),
+];
+const FREEZE_ACCOUNTS: &[IdlAccount] = &[
+    writable("account", "The token account."),
+    readonly("mint", "The account's mint."),
+    signer(
+        "freezeAuthority",
+        "The mint freeze authority, or a multisignature account.",
+    ),
+];
+const CONFIGURE_MINT: &[IdlAccount] = &[writable("mint", "The mint to configure, uninitialized.")];
+const EXTEND_MINT: &[IdlAccount] = &[
+    writable("mint", "The mint to configure, uninitialized."),
+    writable_signer("payer", "The payer topping the mint up to rent exemption."),
+    readonly("systemProgram", "System program."),
+];
+const PAUSE_ACCOUNTS: &[IdlAccount] = &[
+    writable("mint", "The mint."),
+    signer(
+        "pauseAuthority",
+        "The pause authority, or a multisignature account.",
+    ),
+];
+const OWNER_ACCOUNTS: &[IdlAccount] = &[
+    writable("account", "The token account."),
+    signer(
+        "owner",
+        "The token account owner, or a multisignature account.",
+    ),
+];
+const METADATA_FIELDS: &[IdlArg] = &[
+    arg("name", IdlType::String),
+    arg("symbol", IdlType::String),
+    arg("uri", IdlType::String),
+];
+const MINTER_ACCOUNTS: &[IdlAccount] = &[
+    writable("mint", "The mint."),
+    signer(
+        "mintAuthority",
+        "The mint authority, or a multisignature account.",
+    ),
+];
+const ALLOWLIST_ACCOUNTS: &[IdlAccount] = &[
+    writable("allowlist", "The allowlist account."),
+    signer(
+        "authority",
+        "The allowlist authority, or a multisignature account.",
+    ),
+];
+
+const INSTRUCTIONS: &[IdlInstruction] = &[
+    IdlInstruction {
+        name: "MintTokens",
+        discriminant: 0,
+        accounts: MINT_TO_ACCOUNTS,
+        args: &[arg("amount", IdlType::U64)],
+        remaining_accounts: Some(
+            "M signer accounts, if the authority is a multisignature account; the allowlist \
+             account, if the mint enforces one.",
+        ),
+    },
+    IdlInstruction {
+        name: "TransferTokens",
+        discriminant: 1,
+        accounts: &[
+            writable("source", "The source account."),
+            writable("destination", "The destination account."),
+            signer(
+                "authority",
+                "The source account owner or delegate, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("amount", IdlType::U64)],
+        remaining_accounts: TRANSFER_ACCOUNTS,
+    },
+    IdlInstruction {
+        name: "Burn",
+        discriminant: 2,
+        accounts: BURN_ACCOUNTS,
+        args: &[arg("amount", IdlType::U64)],
+        remaining_accounts: BURN_REMAINING_ACCOUNTS,
+    },
+    IdlInstruction {
+        name: "InitializeMint",
+        discriminant: 3,
+        accounts: &[
+            writable("mint", "The mint to initialize."),
+            readonly("rent", "Rent sysvar."),
+        ],
+        args: &[
+            arg("decimals", IdlType::U8),
+            arg("mintAuthority", IdlType::PublicKey),
+            arg("freezeAuthority", PUBKEY_OPTION),
+            arg("maxSupply", IdlType::Option(&IdlType::U64)),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "Approve",
+        discriminant: 4,
+        accounts: &[
+            writable("source", "The source account."),
+            readonly("delegate", "The delegate."),
+            signer(
+                "owner",
+                "The source account owner, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("amount", IdlType::U64)],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "Revoke",
+        discriminant: 5,
+        accounts: &[
+            writable("source", "The source account."),
+            signer(
+                "owner",
+                "The source account owner, or a multisignature account.",
+            ),
+        ],
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "InitializeAccount",
+        discriminant: 6,
+        accounts: &[
+            writable("account", "The token account to initialize."),
+            readonly("mint", "The mint this account will hold."),
+            readonly("owner", "The new account's owner."),
+            readonly("rent", "Rent sysvar."),
+        ],
+        args: &[],
+        remaining_accounts: INITIALIZE_ACCOUNT_PAYER,
+    },
+    IdlInstruction {
+        name: "CloseAccount",
+        discriminant: 7,
+        accounts: &[
+            writable("account", "The token account to close."),
+            writable("destination", "The destination for the account's lamports."),
+            signer(
+                "owner",
+                "The token account owner or close authority, or a multisignature account.",
+            ),
+        ],
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "FreezeAccount",
+        discriminant: 8,
+        accounts: FREEZE_ACCOUNTS,
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "ThawAccount",
+        discriminant: 9,
+        accounts: FREEZE_ACCOUNTS,
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "InitializeAccount2",
+        discriminant: 10,
+        accounts: &[
+            writable("account", "The token account to initialize."),
+            readonly("mint", "The mint this account will hold."),
+            readonly("owner", "The new account's owner."),
+        ],
+        args: &[],
+        remaining_accounts: INITIALIZE_ACCOUNT_PAYER,
+    },
+    IdlInstruction {
+        name: "SetAuthority",
+        discriminant: 11,
+        accounts: &[
+            writable("owned", "The mint or token account to change."),
+            signer(
+                "authority",
+                "The current authority, or a multisignature account.",
+            ),
+        ],
+        args: &[
+            arg("authorityType", IdlType::Defined("AuthorityType")),
+            arg("newAuthority", PUBKEY_OPTION),
+        ],
+        remaining_accounts: SIGNERS_THEN_PAYER,
+    },
+    IdlInstruction {
+        name: "TransferChecked",
+        discriminant: 12,
+        accounts: &[
+            writable("source", "The source account."),
+            readonly("mint", "The token mint."),
+            writable("destination", "The destination account."),
+            signer(
+                "authority",
+                "The source account owner or delegate, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("amount", IdlType::U64), arg("decimals", IdlType::U8)],
+        remaining_accounts: Some(
+            "M signer accounts, if the authority is a multisignature account; the instructions \
+             sysvar, if the destination requires a memo; the writable royalty destination, if \
+             the mint has a royalty; the allowlist account, if the mint enforces one; then the \
+             hook program and its extra accounts, if the mint has a transfer hook.",
+        ),
+    },
+    IdlInstruction {
+        name: "MintToChecked",
+        discriminant: 13,
+        accounts: MINT_TO_ACCOUNTS,
+        args: &[arg("amount", IdlType::U64), arg("decimals", IdlType::U8)],
+        remaining_accounts: Some(
+            "M signer accounts, if the authority is a multisignature account; the allowlist \
+             account, if the mint enforces one.",
+        ),
+    },
+    IdlInstruction {
+        name: "BurnChecked",
+        discriminant: 14,
+        accounts: BURN_ACCOUNTS,
+        args: &[arg("amount", IdlType::U64), arg("decimals", IdlType::U8)],
+        remaining_accounts: BURN_REMAINING_ACCOUNTS,
+    },
+    IdlInstruction {
+        name: "CreateAssociatedAccount",
+        discriminant: 15,
+        accounts: &[
+            writable_signer("funding", "The funding account."),
+            writable(
+                "associatedAccount",
+                "The associated token account to create.",
+            ),
+            readonly("wallet", "The wallet that will own the new account."),
+            readonly("mint", "The token mint."),
+            readonly("systemProgram", "System program."),
+        ],
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "SyncNative",
+        discriminant: 16,
+        accounts: &[writable("account", "The native token account.")],
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeMultisig",
+        discriminant: 17,
+        accounts: &[
+            writable("multisig", "The multisignature account to initialize."),
+            readonly("rent", "Rent sysvar."),
+        ],
+        args: &[arg("m", IdlType::U8)],
+        remaining_accounts: Some("The N signer accounts, where 1 <= N <= 11."),
+    },
+    IdlInstruction {
+        name: "TransferBatch",
+        discriminant: 18,
+        accounts: &[
+            writable("source", "The source account."),
+            signer(
+                "authority",
+                "The source account owner or delegate, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("amounts", IdlType::Vec(&IdlType::U64))],
+        remaining_accounts: Some(
+            "The N writable destination accounts, in the order of amounts; then M signer \
+             accounts, if the authority is a multisignature account; the instructions sysvar, \
+             if a destination requires a memo; the allowlist account, if the mint enforces one.",
+        ),
+    },
+    IdlInstruction {
+        name: "CreateMetadata",
+        discriminant: 19,
+        accounts: &[
+            writable_signer("funding", "The funding account."),
+            writable("metadata", "The metadata account to create."),
+            readonly("mint", "The mint."),
+            signer(
+                "mintAuthority",
+                "The mint authority, or a multisignature account.",
+            ),
+            readonly("updateAuthority", "The metadata update authority."),
+            readonly("systemProgram", "System program."),
+        ],
+        args: METADATA_FIELDS,
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "UpdateMetadata",
+        discriminant: 20,
+        accounts: &[
+            writable("metadata", "The metadata account."),
+            signer(
+                "updateAuthority",
+                "The update authority, or a multisignature account.",
+            ),
+        ],
+        args: METADATA_FIELDS,
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "InitializeTransferFeeConfig",
+        discriminant: 21,
+        accounts: CONFIGURE_MINT,
+        args: &[
+            arg("transferFeeAuthority", PUBKEY_OPTION),
+            arg("transferFeeBasisPoints", IdlType::U16),
+            arg("maximumFee", IdlType::U64),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "HarvestWithheldTokens",
+        discriminant: 22,
+        accounts: &[writable("mint", "The mint.")],
+        args: &[],
+        remaining_accounts: Some("The writable token accounts to harvest from."),
+    },
+    IdlInstruction {
+        name: "WithdrawWithheldTokens",
+        discriminant: 23,
+        accounts: &[
+            writable("mint", "The mint."),
+            writable("destination", "The destination account."),
+            signer(
+                "authority",
+                "The transfer fee authority, or a multisignature account.",
+            ),
+        ],
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "InitializeNonTransferableMint",
+        discriminant: 24,
+        accounts: CONFIGURE_MINT,
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "LockAccount",
+        discriminant: 25,
+        accounts: OWNER_ACCOUNTS,
+        args: &[arg("lockUntil", IdlType::I64)],
+        remaining_accounts: SIGNERS_THEN_PAYER,
+    },
+    IdlInstruction {
+        name: "InitializeEscrow",
+        discriminant: 26,
+        accounts: &[
+            signer("maker", "The maker."),
+            writable("makerDeposit", "The maker's token account to deposit from."),
+            writable("deposit", "The deposit account."),
+            writable("escrow", "The escrow account, uninitialized."),
+            readonly(
+                "makerReceive",
+                "The maker's token account that will receive the payment.",
+            ),
+            readonly("tokenProgram", "This program, for the deposit transfer."),
+        ],
+        args: &[
+            arg("amount", IdlType::U64),
+            arg("expectedAmount", IdlType::U64),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "Exchange",
+        discriminant: 27,
+        accounts: &[
+            signer("taker", "The taker."),
+            writable("takerPay", "The taker's token account to pay from."),
+            writable(
+                "takerReceive",
+                "The taker's token account to receive the deposit.",
+            ),
+            writable("deposit", "The deposit account."),
+            writable(
+                "makerReceive",
+                "The maker's token account receiving the payment.",
+            ),
+            writable(
+                "maker",
+                "The maker, receiving the closed accounts' lamports.",
+            ),
+            writable("escrow", "The escrow account."),
+            readonly("escrowAuthority", "The escrow authority."),
+            readonly("tokenProgram", "This program, for the transfers."),
+        ],
+        args: &[
+            arg("amount", IdlType::U64),
+            arg("expectedAmount", IdlType::U64),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "CancelEscrow",
+        discriminant: 28,
+        accounts: &[
+            writable_signer("maker", "The maker."),
+            writable("deposit", "The deposit account."),
+            writable("makerRefund", "The maker's token account to refund."),
+            writable("escrow", "The escrow account."),
+            readonly("escrowAuthority", "The escrow authority."),
+            readonly("tokenProgram", "This program, for the refund transfer."),
+        ],
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializePauseConfig",
+        discriminant: 29,
+        accounts: CONFIGURE_MINT,
+        args: &[arg("pauseAuthority", IdlType::PublicKey)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "Pause",
+        discriminant: 30,
+        accounts: PAUSE_ACCOUNTS,
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "Resume",
+        discriminant: 31,
+        accounts: PAUSE_ACCOUNTS,
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "InitializePermanentDelegate",
+        discriminant: 32,
+        accounts: CONFIGURE_MINT,
+        args: &[arg("delegate", IdlType::PublicKey)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "ProposeOwner",
+        discriminant: 33,
+        accounts: OWNER_ACCOUNTS,
+        args: &[arg("newOwner", IdlType::PublicKey)],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "AcceptOwner",
+        discriminant: 34,
+        accounts: &[
+            writable("account", "The token account."),
+            signer(
+                "pendingOwner",
+                "The pending owner, or a multisignature account.",
+            ),
+        ],
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "CancelPendingOwner",
+        discriminant: 35,
+        accounts: OWNER_ACCOUNTS,
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "GetAccountBalance",
+        discriminant: 36,
+        accounts: &[readonly("account", "The token account.")],
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "GetMintSupply",
+        discriminant: 37,
+        accounts: &[readonly("mint", "The mint.")],
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "AmountToUiAmount",
+        discriminant: 38,
+        accounts: &[readonly("mint", "The mint.")],
+        args: &[arg("amount", IdlType::U64)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "UiAmountToAmount",
+        discriminant: 39,
+        accounts: &[readonly("mint", "The mint.")],
+        args: &[arg("uiAmount", IdlType::String)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "RequireMemo",
+        discriminant: 40,
+        accounts: OWNER_ACCOUNTS,
+        args: &[],
+        remaining_accounts: SIGNERS_THEN_PAYER,
+    },
+    IdlInstruction {
+        name: "UnrequireMemo",
+        discriminant: 41,
+        accounts: OWNER_ACCOUNTS,
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "InitializeTransferHook",
+        discriminant: 42,
+        accounts: CONFIGURE_MINT,
+        args: &[arg("programId", IdlType::PublicKey)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeDefaultAccountState",
+        discriminant: 43,
+        accounts: CONFIGURE_MINT,
+        args: &[arg("state", IdlType::Defined("AccountState"))],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "SetDefaultAccountState",
+        discriminant: 44,
+        accounts: &[
+            writable("mint", "The mint."),
+            signer(
+                "freezeAuthority",
+                "The mint freeze authority, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("state", IdlType::Defined("AccountState"))],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "InitializeImmutableOwner",
+        discriminant: 45,
+        accounts: &[
+            writable("account", "The token account to configure, uninitialized."),
+            writable_signer(
+                "payer",
+                "The payer topping the account up to rent exemption.",
+            ),
+            readonly("systemProgram", "System program."),
+        ],
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeInterestBearingMint",
+        discriminant: 46,
+        accounts: CONFIGURE_MINT,
+        args: &[
+            arg("rateAuthority", PUBKEY_OPTION),
+            arg("rateBps", IdlType::I16),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "UpdateRate",
+        discriminant: 47,
+        accounts: &[
+            writable("mint", "The mint."),
+            signer(
+                "rateAuthority",
+                "The mint rate authority, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("rateBps", IdlType::I16)],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "InitializeMintCloseAuthority",
+        discriminant: 48,
+        accounts: CONFIGURE_MINT,
+        args: &[arg("closeAuthority", PUBKEY_OPTION)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "CloseMint",
+        discriminant: 49,
+        accounts: &[
+            writable("mint", "The mint to close."),
+            writable("destination", "The destination for the mint's lamports."),
+            signer(
+                "closeAuthority",
+                "The mint close authority, or a multisignature account.",
+            ),
+        ],
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "MigrateAccount",
+        discriminant: 50,
+        accounts: &[
+            writable("account", "The token account or mint to migrate."),
+            writable_signer("payer", "The payer."),
+            readonly("systemProgram", "System program."),
+        ],
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeRoyalty",
+        discriminant: 51,
+        accounts: EXTEND_MINT,
+        args: &[
+            arg("basisPoints", IdlType::U16),
+            arg("destination", IdlType::PublicKey),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeAllowlist",
+        discriminant: 52,
+        accounts: &[
+            writable_signer(
+                "funding",
+                "The funding account, also topping the mint up to rent exemption.",
+            ),
+            writable("allowlist", "The allowlist account to create."),
+            writable("mint", "The mint to configure, uninitialized."),
+            readonly("systemProgram", "System program."),
+        ],
+        args: &[arg("authority", IdlType::PublicKey)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "AddToAllowlist",
+        discriminant: 53,
+        accounts: ALLOWLIST_ACCOUNTS,
+        args: &[arg("owner", IdlType::PublicKey)],
+        remaining_accounts: Some(
+            "M signer accounts, if the authority is a multisignature account; then a writable, \
+             signing payer and the system program.",
+        ),
+    },
+    IdlInstruction {
+        name: "RemoveFromAllowlist",
+        discriminant: 54,
+        accounts: ALLOWLIST_ACCOUNTS,
+        args: &[arg("owner", IdlType::PublicKey)],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "InitializeMinTransferAmount",
+        discriminant: 55,
+        accounts: EXTEND_MINT,
+        args: &[arg("minTransferAmount", IdlType::U64)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "UpdateMinTransferAmount",
+        discriminant: 56,
+        accounts: &[
+            writable("mint", "The mint."),
+            signer(
+                "mintAuthority",
+                "The mint authority, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("minTransferAmount", IdlType::U64)],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "TransferAccountOwnership",
+        discriminant: 57,
+        accounts: &[
+            writable("account", "The token account."),
+            signer("owner", "The current owner, or a multisignature account."),
+            signer("newOwner", "The new owner."),
+        ],
+        args: &[],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "CreateDistribution",
+        discriminant: 58,
+        accounts: &[
+            signer("creator", "The creator, owner of the funding account."),
+            writable("source", "The creator's token account to fund from."),
+            writable("vault", "The vault."),
+            writable("distributor", "The distributor account, uninitialized."),
+            readonly("tokenProgram", "This program, for the funding transfer."),
+        ],
+        args: &[arg("merkleRoot", HASH), arg("totalClaimable", IdlType::U64)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "Claim",
+        discriminant: 59,
+        accounts: &[
+            writable_signer("claimant", "The claimant, paying for the claim status."),
+            writable("destination", "The token account to receive the tokens."),
+            writable("distributor", "The distributor account."),
+            writable("vault", "The vault."),
+            readonly("distributorAuthority", "The distributor authority."),
+            writable(
+                "claimStatus",
+                "The claim status, `get_claim_address` of the distributor and `index`.",
+            ),
+            readonly("systemProgram", "The system program."),
+            readonly("tokenProgram", "This program, for the payout transfer."),
+        ],
+        args: &[
+            arg("index", IdlType::U64),
+            arg("amount", IdlType::U64),
+            arg("proof", IdlType::Vec(&HASH)),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeMint2",
+        discriminant: 60,
+        accounts: &[writable("mint", "The mint to initialize.")],
+        args: &[
+            arg("decimals", IdlType::U8),
+            arg("mintAuthority", IdlType::PublicKey),
+            arg("freezeAuthority", PUBKEY_OPTION),
+            arg("maxSupply", IdlType::Option(&IdlType::U64)),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeAccount3",
+        discriminant: 61,
+        accounts: &[
+            writable("account", "The token account to initialize."),
+            readonly("mint", "The mint this account will hold."),
+        ],
+        args: &[arg("owner", IdlType::PublicKey)],
+        remaining_accounts: INITIALIZE_ACCOUNT_PAYER,
+    },
+    IdlInstruction {
+        name: "InitializeMultisig2",
+        discriminant: 62,
+        accounts: &[writable(
+            "multisig",
+            "The multisignature account to initialize.",
+        )],
+        args: &[arg("m", IdlType::U8)],
+        remaining_accounts: Some("The N signer accounts, where 1 <= N <= 11."),
+    },
+    IdlInstruction {
+        name: "FreezeBatch",
+        discriminant: 63,
+        accounts: &[
+            readonly("mint", "The mint."),
+            signer(
+                "freezeAuthority",
+                "The mint freeze authority, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("count", IdlType::U8)],
+        remaining_accounts: Some(
+            "The N writable accounts to freeze, where 1 <= N <= 24; then M signer accounts, if \
+             the authority is a multisignature account.",
+        ),
+    },
+    IdlInstruction {
+        name: "InitializeMintWithAllocations",
+        discriminant: 64,
+        accounts: &[
+            writable("mint", "The mint to initialize."),
+            signer("mintAuthority", "The mint authority."),
+            writable_signer("payer", "The funding account for the new accounts."),
+            readonly("systemProgram", "System program."),
+        ],
+        args: &[
+            arg("decimals", IdlType::U8),
+            arg("amounts", IdlType::Vec(&IdlType::U64)),
+            arg("freezeAuthority", PUBKEY_OPTION),
+            arg("maxSupply", IdlType::Option(&IdlType::U64)),
+        ],
+        remaining_accounts: Some(
+            "For each of the N amounts, where 1 <= N <= 16, the writable associated token \
+             account of a wallet, then the wallet.",
+        ),
+    },
+    IdlInstruction {
+        name: "InitializeActivityTracking",
+        discriminant: 65,
+        accounts: EXTEND_MINT,
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "TransferWithFee",
+        discriminant: 66,
+        accounts: &[
+            writable("source", "The source account."),
+            writable("destination", "The destination account."),
+            writable(
+                "feeAccount",
+                "The fee account, distinct from the other two.",
+            ),
+            signer(
+                "authority",
+                "The source account owner or delegate, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("amount", IdlType::U64), arg("fee", IdlType::U64)],
+        remaining_accounts: TRANSFER_ACCOUNTS,
+    },
+    IdlInstruction {
+        name: "CreateTokenAccount",
+        discriminant: 67,
+        accounts: &[
+            writable_signer("funding", "The funding account."),
+            writable(
+                "account",
+                "The token account to create, a signer if it is a keypair address.",
+            ),
+            readonly("mint", "The mint this account will hold."),
+            readonly("systemProgram", "System program."),
+        ],
+        args: &[
+            arg("owner", IdlType::PublicKey),
+            arg("seed", IdlType::Option(&IdlType::U64)),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeTransferCooldown",
+        discriminant: 68,
+        accounts: EXTEND_MINT,
+        args: &[arg("cooldownSlots", IdlType::U64)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeHolderCount",
+        discriminant: 69,
+        accounts: EXTEND_MINT,
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "PermitTransfer",
+        discriminant: 70,
+        accounts: &[
+            writable("source", "The source account."),
+            writable("destination", "The destination account."),
+            readonly("instructions", "Instructions sysvar."),
+            writable_signer("payer", "The payer, for the source account's growth."),
+            readonly("systemProgram", "System program."),
+        ],
+        args: &[],
+        remaining_accounts: Some("The allowlist account, if the mint enforces one."),
+    },
+    IdlInstruction {
+        name: "CreateVesting",
+        discriminant: 71,
+        accounts: &[
+            writable_signer(
+                "funding",
+                "The funding account, which also tops the mint up to rent exemption.",
+            ),
+            writable("vesting", "The vesting schedule to create."),
+            writable("mint", "The mint."),
+            readonly("beneficiary", "The beneficiary token account."),
+            signer(
+                "mintAuthority",
+                "The mint authority, or a multisignature account.",
+            ),
+            readonly("systemProgram", "System program."),
+        ],
+        args: &[
+            arg("totalAmount", IdlType::U64),
+            arg("startTs", IdlType::I64),
+            arg("endTs", IdlType::I64),
+        ],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "ClaimVested",
+        discriminant: 72,
+        accounts: &[
+            writable("vesting", "The vesting schedule."),
+            writable("mint", "The mint."),
+            writable("beneficiary", "The beneficiary token account."),
+        ],
+        args: &[],
+        remaining_accounts: Some("The allowlist account, if the mint enforces one."),
+    },
+    IdlInstruction {
+        name: "RepairOwner",
+        discriminant: 73,
+        accounts: &[
+            writable("account", "The token account to repair."),
+            readonly("mint", "The account's mint."),
+            signer(
+                "mintAuthority",
+                "The mint authority, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("owner", IdlType::PublicKey)],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "RecoverNested",
+        discriminant: 74,
+        accounts: &[
+            writable("nested", "The nested associated token account."),
+            readonly("nestedMint", "The nested account's mint."),
+            writable(
+                "destination",
+                "The wallet's associated token account for that mint.",
+            ),
+            readonly(
+                "ownerAssociatedAccount",
+                "The wallet's associated token account that owns the nested one.",
+            ),
+            readonly("ownerMint", "That account's mint."),
+            writable_signer("wallet", "The wallet."),
+        ],
+        args: &[],
+        remaining_accounts: Some(
+            "The instructions sysvar, if the destination requires a memo, and the allowlist account, if the mint enforces one.",
+        ),
+    },
+    IdlInstruction {
+        name: "TwoPartySwap",
+        discriminant: 75,
+        accounts: &[
+            writable("sourceA", "Owner A's source account."),
+            writable("destinationA", "The account receiving `amount_a`."),
+            signer("ownerA", "Owner A."),
+            writable("sourceB", "Owner B's source account."),
+            writable("destinationB", "The account receiving `amount_b`."),
+            signer("ownerB", "Owner B."),
+        ],
+        args: &[
+            arg("amountA", IdlType::U64),
+            arg("amountB", IdlType::U64),
+        ],
+        remaining_accounts: Some(
+            "The instructions sysvar, if a destination requires a memo, and the allowlist accounts of mints that enforce one.",
+        ),
+    },
+    IdlInstruction {
+        name: "CreateAssociatedAccountIdempotent",
+        discriminant: 76,
+        accounts: &[
+            writable_signer("funding", "The funding account."),
+            writable(
+                "associatedAccount",
+                "The associated token account to create.",
+            ),
+            readonly("wallet", "The wallet that will own the new account."),
+            readonly("mint", "The token mint."),
+            readonly("systemProgram", "System program."),
+        ],
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeRecoveryAuthority",
+        discriminant: 77,
+        accounts: EXTEND_MINT,
+        args: &[arg("recoveryAuthority", IdlType::PublicKey)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "InitializeMetadataPointer",
+        discriminant: 78,
+        accounts: EXTEND_MINT,
+        args: &[
+            arg("authority", PUBKEY_OPTION),
+            arg("metadataAddress", PUBKEY_OPTION),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "UpdateMetadataPointer",
+        discriminant: 79,
+        accounts: &[
+            writable("mint", "The mint."),
+            signer(
+                "authority",
+                "The metadata pointer authority, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("metadataAddress", PUBKEY_OPTION)],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "CreateConverter",
+        discriminant: 80,
+        accounts: &[
+            writable("converter", "The converter account, uninitialized."),
+            readonly("fromMint", "The mint to convert from."),
+            readonly("toMint", "The mint to convert to."),
+        ],
+        args: &[
+            arg("numerator", IdlType::U64),
+            arg("denominator", IdlType::U64),
+        ],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "Convert",
+        discriminant: 81,
+        accounts: &[
+            signer("owner", "The owner of the source account."),
+            writable("source", "The token account to convert from."),
+            writable("fromMint", "The mint to convert from."),
+            writable(
+                "destination",
+                "The token account to receive the converted tokens.",
+            ),
+            writable("toMint", "The mint to convert to."),
+            readonly("converter", "The converter."),
+            readonly("converterAuthority", "The converter authority."),
+            readonly("tokenProgram", "This program, for the burn and the mint."),
+        ],
+        args: &[arg("amount", IdlType::U64)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "SetMinimumBalance",
+        discriminant: 82,
+        accounts: OWNER_ACCOUNTS,
+        args: &[
+            arg("minimumBalance", IdlType::U64),
+            arg("enforcementAuthority", IdlType::PublicKey),
+        ],
+        remaining_accounts: Some(
+            "M signer accounts, if the owner is a multisignature account; the current enforcement \
+             authority, signing, if the change lowers the minimum or names another authority; \
+             then, when the account grows, a writable, signing payer and the system program.",
+        ),
+    },
+    IdlInstruction {
+        name: "SnapshotBalance",
+        discriminant: 83,
+        accounts: &[
+            writable_signer("payer", "The payer, who funds the snapshot."),
+            writable("snapshot", "The snapshot account, not yet created."),
+            readonly("account", "The token account."),
+            readonly("systemProgram", "System program."),
+        ],
+        args: &[arg("snapshotId", IdlType::U64)],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "CloseSnapshot",
+        discriminant: 84,
+        accounts: &[
+            writable("snapshot", "The snapshot account."),
+            writable("destination", "The destination account."),
+            signer("payer", "The payer of the snapshot."),
+        ],
+        args: &[],
+        remaining_accounts: None,
+    },
+    IdlInstruction {
+        name: "TransferWithRelayerFee",
+        discriminant: 85,
+        accounts: &[
+            writable("source", "The source account."),
+            readonly("mint", "The token mint."),
+            writable("destination", "The destination account."),
+            writable(
+                "relayerAccount",
+                "The relayer's token account, distinct from the other two.",
+            ),
+            signer(
+                "authority",
+                "The source account owner or delegate, or a multisignature account.",
+            ),
+        ],
+        args: &[
+            arg("amount", IdlType::U64),
+            arg("relayerFee", IdlType::U64),
+        ],
+        remaining_accounts: TRANSFER_ACCOUNTS,
+    },
+    IdlInstruction {
+        name: "VerifyOwnership",
+        discriminant: 86,
+        accounts: &[
+            readonly("account", "The token account."),
+            signer(
+                "owner",
+                "The token account owner, or a multisignature account.",
+            ),
+        ],
+        args: &[arg("nonce", IdlType::U64)],
+        remaining_accounts: SIGNERS,
+    },
+    IdlInstruction {
+        name: "SetOutflowLimit",
+        discriminant: 87,
+        accounts: OWNER_ACCOUNTS,
+        args: &[
+            arg("maxOutflowPerInstruction", IdlType::U64),
+            arg("timelockSlots", IdlType::U64),
+        ],
+        remaining_accounts: SIGNERS_THEN_PAYER,
+    },
+    IdlInstruction {
+        name: "AddMinter",
+        discriminant: 88,
+        accounts: MINTER_ACCOUNTS,
+        args: &[
+            arg("minter", IdlType::PublicKey),
+            arg("maxPerWindow", IdlType::U64),
+            arg("windowSlots", IdlType::U64),
+        ],
+        remaining_accounts: SIGNERS_THEN_PAYER,
+    },
+    IdlInstruction {
+        name: "RemoveMinter",
+        discriminant: 89,
+        accounts: MINTER_ACCOUNTS,
+        args: &[arg("minter", IdlType::PublicKey)],
+        remaining_accounts: SIGNERS,
+    },
+];
+
+/// Every instruction, in order of discriminant.
+pub fn instructions() -> &'static [IdlInstruction] {
+    INSTRUCTIONS
+}
+
+/// Returns the description of `instruction`.
+pub fn instruction(instruction: &TokenInstruction) -> &'static IdlInstruction {
+    // Exhaustive, so a new variant doesn't compile without an entry.
+    let name = match instruction {
+        TokenInstruction::MintTokens { .. } => "MintTokens",
+        TokenInstruction::TransferTokens { .. } => "TransferTokens",
+        TokenInstruction::Burn { .. } => "Burn",
+        TokenInstruction::InitializeMint { .. } => "InitializeMint",
+        TokenInstruction::Approve { .. } => "Approve",
+        TokenInstruction::Revoke => "Revoke",
+        TokenInstruction::InitializeAccount => "InitializeAccount",
+        TokenInstruction::CloseAccount => "CloseAccount",
+        TokenInstruction::FreezeAccount => "FreezeAccount",
+        TokenInstruction::ThawAccount => "ThawAccount",
+        TokenInstruction::InitializeAccount2 => "InitializeAccount2",
+        TokenInstruction::SetAuthority { .. } => "SetAuthority",
+        TokenInstruction::TransferChecked { .. } => "TransferChecked",
+        TokenInstruction::MintToChecked { .. } => "MintToChecked",
+        TokenInstruction::BurnChecked { .. } => "BurnChecked",
+        TokenInstruction::CreateAssociatedAccount => "CreateAssociatedAccount",
+        TokenInstruction::SyncNative => "SyncNative",
+        TokenInstruction::InitializeMultisig { .. } => "InitializeMultisig",
+        TokenInstruction::TransferBatch { .. } => "TransferBatch",
+        TokenInstruction::CreateMetadata { .. } => "CreateMetadata",
+        TokenInstruction::UpdateMetadata { .. } => "UpdateMetadata",
+        TokenInstruction::InitializeTransferFeeConfig { .. } => "InitializeTransferFeeConfig",
+        TokenInstruction::HarvestWithheldTokens => "HarvestWithheldTokens",
+        TokenInstruction::WithdrawWithheldTokens => "WithdrawWithheldTokens",
+        TokenInstruction::InitializeNonTransferableMint => "InitializeNonTransferableMint",
+        TokenInstruction::LockAccount { .. } => "LockAccount",
+        TokenInstruction::InitializeEscrow { .. } => "InitializeEscrow",
+        TokenInstruction::Exchange { .. } => "Exchange",
+        TokenInstruction::CancelEscrow => "CancelEscrow",
+        TokenInstruction::InitializePauseConfig { .. } => "InitializePauseConfig",
+        TokenInstruction::Pause => "Pause",
+        TokenInstruction::Resume => "Resume",
+        TokenInstruction::InitializePermanentDelegate { .. } => "InitializePermanentDelegate",
+        TokenInstruction::ProposeOwner { .. } => "ProposeOwner",
+        TokenInstruction::AcceptOwner => "AcceptOwner",
+        TokenInstruction::CancelPendingOwner => "CancelPendingOwner",
+        TokenInstruction::GetAccountBalance => "GetAccountBalance",
+        TokenInstruction::GetMintSupply => "GetMintSupply",
+        TokenInstruction::AmountToUiAmount { .. } => "AmountToUiAmount",
+        TokenInstruction::UiAmountToAmount { .. } => "UiAmountToAmount",
+        TokenInstruction::RequireMemo => "RequireMemo",
+        TokenInstruction::UnrequireMemo => "UnrequireMemo",
+        TokenInstruction::InitializeTransferHook { .. } => "InitializeTransferHook",
+        TokenInstruction::InitializeDefaultAccountState { .. } => "InitializeDefaultAccountState",
+        TokenInstruction::SetDefaultAccountState { .. } => "SetDefaultAccountState",
+        TokenInstruction::InitializeImmutableOwner => "InitializeImmutableOwner",
+        TokenInstruction::InitializeInterestBearingMint { .. } => "InitializeInterestBearingMint",
+        TokenInstruction::UpdateRate { .. } => "UpdateRate",
+        TokenInstruction::InitializeMintCloseAuthority { .. } => "InitializeMintCloseAuthority",
+        TokenInstruction::CloseMint => "CloseMint",
+        TokenInstruction::MigrateAccount => "MigrateAccount",
+        TokenInstruction::InitializeRoyalty { .. } => "InitializeRoyalty",
+        TokenInstruction::InitializeAllowlist { .. } => "InitializeAllowlist",
+        TokenInstruction::AddToAllowlist { .. } => "AddToAllowlist",
+        TokenInstruction::RemoveFromAllowlist { .. } => "RemoveFromAllowlist",
+        TokenInstruction::InitializeMinTransferAmount { .. } => "InitializeMinTransferAmount",
+        TokenInstruction::UpdateMinTransferAmount { .. } => "UpdateMinTransferAmount",
+        TokenInstruction::TransferAccountOwnership => "TransferAccountOwnership",
+        TokenInstruction::CreateDistribution { .. } => "CreateDistribution",
+        TokenInstruction::Claim { .. } => "Claim",
+        TokenInstruction::InitializeMint2 { .. } => "InitializeMint2",
+        TokenInstruction::InitializeAccount3 { .. } => "InitializeAccount3",
+        TokenInstruction::InitializeMultisig2 { .. } => "InitializeMultisig2",
+        TokenInstruction::FreezeBatch { .. } => "FreezeBatch",
+        TokenInstruction::InitializeMintWithAllocations { .. } => "InitializeMintWithAllocations",
+        TokenInstruction::InitializeActivityTracking => "InitializeActivityTracking",
+        TokenInstruction::TransferWithFee { .. } => "TransferWithFee",
+        TokenInstruction::CreateTokenAccount { .. } => "CreateTokenAccount",
+        TokenInstruction::InitializeTransferCooldown { .. } => "InitializeTransferCooldown",
+        TokenInstruction::InitializeHolderCount => "InitializeHolderCount",
+        TokenInstruction::PermitTransfer => "PermitTransfer",
+        TokenInstruction::CreateVesting { .. } => "CreateVesting",
+        TokenInstruction::ClaimVested => "ClaimVested",
+        TokenInstruction::RepairOwner { .. } => "RepairOwner",
+        TokenInstruction::RecoverNested => "RecoverNested",
+        TokenInstruction::TwoPartySwap { .. } => "TwoPartySwap",
+        TokenInstruction::CreateAssociatedAccountIdempotent => "CreateAssociatedAccountIdempotent",
+        TokenInstruction::InitializeRecoveryAuthority { .. } => "InitializeRecoveryAuthority",
+        TokenInstruction::InitializeMetadataPointer { .. } => "InitializeMetadataPointer",
+        TokenInstruction::UpdateMetadataPointer { .. } => "UpdateMetadataPointer",
+        TokenInstruction::CreateConverter { .. } => "CreateConverter",
+        TokenInstruction::Convert { .. } => "Convert",
+        TokenInstruction::SetMinimumBalance { .. } => "SetMinimumBalance",
+        TokenInstruction::SnapshotBalance { .. } => "SnapshotBalance",
+        TokenInstruction::CloseSnapshot => "CloseSnapshot",
+        TokenInstruction::TransferWithRelayerFee { .. } => "TransferWithRelayerFee",
+        TokenInstruction::VerifyOwnership { .. } => "VerifyOwnership",
+        TokenInstruction::SetOutflowLimit { .. } => "SetOutflowLimit",
+        TokenInstruction::AddMinter { .. } => "AddMinter",
+        TokenInstruction::RemoveMinter { .. } => "RemoveMinter",
+    };
+    INSTRUCTIONS
+        .iter()
+        .find(|entry| entry.name == name)
+        .expect("every instruction is described")
+}
+
+/// Renders the IDL as pretty-printed JSON, ending in a newline.
+pub fn to_json() -> String {
+    let mut json = String::new();
+    json.push_str("{\n  \"version\": \"");
+    json.push_str(env!("CARGO_PKG_VERSION"));
+    json.push_str("\",\n  \"name\": \"solana_contracts\",\n  \"instructions\": [");
+    for (i, instruction) in INSTRUCTIONS.iter().enumerate() {
+        json.push_str(if i == 0 { "\n" } else { ",\n" });
+        write_instruction(&mut json, instruction);
+    }
+    json.push_str("\n  ],\n  \"types\": [");
+    for (i, idl_enum) in TYPES.iter().enumerate() {
+        json.push_str(if i == 0 { "\n" } else { ",\n" });
+        write!(
+            json,
+            "    {{\n      \"name\": {},\n      \"type\": {{\n        \"kind\": \"enum\",\n        \
+             \"variants\": [",
+            quote(idl_enum.name)
+        )
+        .unwrap();
+        for (j, variant) in idl_enum.variants.iter().enumerate() {
+            json.push_str(if j == 0 { "\n" } else { ",\n" });
+            write!(json, "          {{ \"name\": {} }}", quote(variant)).unwrap();
+        }
+        json.push_str("\n        ]\n      }\n    }");
+    }
+    json.push_str("\n  ],\n  \"errors\": [");
+    for (i, error) in ERRORS.iter().enumerate() {
+        json.push_str(if i == 0 { "\n" } else { ",\n" });
+        write!(
+            json,
+            "    {{\n      \"code\": {},\n      \"name\": {},\n      \"msg\": {}\n    }}",
+            error.code,
+            quote(error.name),
+            quote(error.message)
+        )
+        .unwrap();
+    }
+    json.push_str("\n  ],\n  \"metadata\": {\n    \"origin\": \"shank\"\n  }\n}\n");
+    json
+}
+
+fn write_instruction(json: &mut String, instruction: &IdlInstruction) {
+    write!(
+        json,
+        "    {{\n      \"name\": {},\n      \"accounts\": [",
+        quote(instruction.name)
+    )
+    .unwrap();
+    for (i, account) in instruction.accounts.iter().enumerate() {
+        json.push_str(if i == 0 { "\n" } else { ",\n" });
+        write!(
+            json,
+            "        {{\n          \"name\": {},\n          \"isMut\": {},\n          \
+             \"isSigner\": {},\n          \"desc\": {}\n        }}",
+            quote(account.name),
+            account.is_mut,
+            account.is_signer,
+            quote(account.desc)
+        )
+        .unwrap();
+    }
+    json.push_str(if instruction.accounts.is_empty() {
+        "],\n      \"args\": ["
+    } else {
+        "\n      ],\n      \"args\": ["
+    });
+    for (i, arg) in instruction.args.iter().enumerate() {
+        json.push_str(if i == 0 { "\n" } else { ",\n" });
+        write!(
+            json,
+            "        {{\n          \"name\": {},\n          \"type\": {}\n        }}",
+            quote(arg.name),
+            type_json(&arg.ty)
+        )
+        .unwrap();
+    }
+    json.push_str(if instruction.args.is_empty() {
+        "],\n"
+    } else {
+        "\n      ],\n"
+    });
+    if let Some(remaining_accounts) = instruction.remaining_accounts {
+        writeln!(
+            json,
+            "      \"remainingAccounts\": {},",
+            quote(remaining_accounts)
+        )
+        .unwrap();
+    }
+    write!(
+        json,
+        "      \"discriminant\": {{\n        \"type\": \"u8\",\n        \"value\": {}\n      }}\n    }}",
+        instruction.discriminant
+    )
+    .unwrap();
+}
+
+fn type_json(ty: &IdlType) -> String {
+    match ty {
+        IdlType::U8 => quote("u8"),
+        IdlType::U16 => quote("u16"),
+        IdlType::I16 => quote("i16"),
+        IdlType::U64 => quote("u64"),
+        IdlType::I64 => quote("i64"),
+        IdlType::PublicKey => quote("publicKey"),
+        IdlType::String => quote("string"),
+        IdlType::Option(inner) => format!("{{ \"option\": {} }}", type_json(inner)),
+        IdlType::Vec(inner) => format!(
+            "{{ \"vec\": {}, \"lengthPrefix\": \"u8\" }}",
+            type_json(inner)
+        ),
+        IdlType::Array(inner, len) => format!("{{ \"array\": [{}, {}] }}", type_json(inner), len),
+        IdlType::Defined(name) => format!("{{ \"defined\": {} }}", quote(name)),
+    }
+}
+
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
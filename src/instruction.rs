@@ -0,0 +1,5015 @@
+//! Instruction types
+//!
+//! Instruction data is a one-byte tag, or an Anchor discriminator, followed
+//! by the instruction's arguments, and [`TokenInstruction::unpack`] rejects
+//! data with bytes left over after them. Earlier versions ignored trailing
+//! bytes, so clients that pad instruction data now fail with
+//! `InvalidInstructionData`. No instruction takes extra bytes; the only
+//! shorter form accepted is `InitializeMint` or `InitializeMint2` without
+//! its optional `max_supply`, which older clients leave out.
+
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::get_return_data,
+    program_error::ProgramError,
+    program_option::COption,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+use crate::{
+    allowlist::get_allowlist_address,
+    associated::get_associated_token_address,
+    converter::get_converter_authority,
+    distributor::{get_claim_address, get_distributor_authority},
+    error::TokenError,
+    escrow::get_escrow_authority,
+    metadata::get_metadata_address,
+    seeded::get_seeded_token_address,
+    snapshot::get_snapshot_address,
+    state::{AccountState, TokenMetadata},
+    vesting::get_vesting_address,
+};
+use num_traits::FromPrimitive;
+use std::mem::size_of;
+
+/// Minimum number of multisignature signers (min N)
+pub const MIN_SIGNERS: usize = 1;
+/// Maximum number of multisignature signers (max N)
+pub const MAX_SIGNERS: usize = 11;
+/// Amount that stands for the source account's entire current balance in
+/// `TransferTokens`, `Burn` and their checked variants
+pub const AMOUNT_ALL: u64 = u64::MAX;
+/// Maximum number of destinations in a single `TransferBatch`
+pub const MAX_TRANSFER_BATCH: usize = 16;
+/// Maximum number of sibling hashes in a `Claim` proof
+pub const MAX_CLAIM_PROOF_LEN: usize = 32;
+/// Maximum number of token accounts in a single `FreezeBatch`
+pub const MAX_FREEZE_BATCH: usize = 24;
+/// Maximum number of allocations in a single `InitializeMintWithAllocations`
+pub const MAX_ALLOCATIONS: usize = 16;
+/// Largest tag reserved for instructions. Tags above the last one this
+/// version defines, up to this, fail with `UnsupportedInstruction`, as
+/// instructions of a newer version; tags above it are never valid.
+pub const MAX_TAG: u8 = 127;
+
+/// Instructions supported by the token program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenInstruction {
+    /// Mints `amount` new tokens into an initialized token account. Any of
+    /// the mint's minters, added with `AddMinter`, may sign in place of the
+    /// mint authority, within its cap.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[writable]` The token account to credit.
+    ///   2. `[signer]` The mint authority or a minter, or a multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    ///
+    /// If the mint enforces an allowlist, its allowlist account must follow
+    /// the signer accounts.
+    MintTokens { amount: u64 },
+    /// Moves `amount` tokens between two token accounts. An `amount` of
+    /// [`AMOUNT_ALL`] moves the source's whole balance, which may be zero.
+    /// The destination must already be initialized; tokens are never
+    /// credited to an account without an owner. If it requires a memo, the
+    /// instructions sysvar must follow the signer accounts; if its mint
+    /// enforces an allowlist, so must the allowlist account; and if the
+    /// debit takes the source below its minimum balance, so must its
+    /// enforcement authority, signing.
+    ///
+    ///   0. `[writable]` The source account.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[signer]` The source account owner or delegate, or a multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    TransferTokens { amount: u64 },
+    /// Destroys `amount` tokens held by a token account. An `amount` of
+    /// [`AMOUNT_ALL`] burns the whole balance, which may be zero. A delegate
+    /// burns out of its allowance, like it transfers. A burn that takes the
+    /// account below its minimum balance needs its enforcement authority to
+    /// sign too, after the signer accounts.
+    ///
+    ///   0. `[writable]` The token account.
+    ///   1. `[writable]` The mint.
+    ///   2. `[signer]` The token account owner or delegate, or a multisignature
+    ///      account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    Burn { amount: u64 },
+    /// Initializes a new mint. A `max_supply`, if given, can't be changed
+    /// afterwards; older clients that omit it create an uncapped mint.
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    ///   1. `[]` Rent sysvar.
+    InitializeMint {
+        decimals: u8,
+        mint_authority: Pubkey,
+        freeze_authority: COption<Pubkey>,
+        max_supply: COption<u64>,
+    },
+    /// Allows a delegate to transfer up to `amount` tokens from the source
+    /// account on behalf of its owner.
+    ///
+    ///   0. `[writable]` The source account.
+    ///   1. `[]` The delegate.
+    ///   2. `[signer]` The source account owner, or a multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    Approve { amount: u64 },
+    /// Revokes the source account's delegate, if any.
+    ///
+    ///   0. `[writable]` The source account.
+    ///   1. `[signer]` The source account owner, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    Revoke,
+    /// Initializes a new token account with a zero balance. Accounts of the
+    /// native mint are marked native and start with their lamports above the
+    /// rent-exempt reserve as balance.
+    ///
+    ///   0. `[writable]` The token account to initialize.
+    ///   1. `[]` The mint this account will hold.
+    ///   2. `[]` The new account's owner.
+    ///   3. `[]` Rent sysvar.
+    ///   4. `[writable, signer]` The payer topping the account up to rent
+    ///      exemption, if its mint's settings grow it by a `MintSettings`
+    ///      or `Cooldown` extension.
+    ///   5. `[]` The system program, likewise.
+    InitializeAccount,
+    /// Closes an empty token account, moving its lamports to the destination.
+    /// Native accounts may be closed with a balance, unwrapping it.
+    ///
+    ///   0. `[writable]` The token account to close.
+    ///   1. `[writable]` The destination for the account's lamports.
+    ///   2. `[signer]` The token account owner or close authority, or a
+    ///      multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    CloseAccount,
+    /// Freezes an initialized account using the mint's freeze authority.
+    ///
+    ///   0. `[writable]` The account to freeze.
+    ///   1. `[]` The account's mint.
+    ///   2. `[signer]` The mint freeze authority, or a multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    FreezeAccount,
+    /// Thaws a frozen account using the mint's freeze authority, or its
+    /// recovery authority.
+    ///
+    ///   0. `[writable]` The account to thaw.
+    ///   1. `[]` The account's mint.
+    ///   2. `[signer]` The mint freeze or recovery authority, or a
+    ///      multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    ThawAccount,
+    /// Like `InitializeAccount`, but reads rent from the `Rent` sysvar
+    /// syscall instead of requiring the sysvar account.
+    ///
+    ///   0. `[writable]` The token account to initialize.
+    ///   1. `[]` The mint this account will hold.
+    ///   2. `[]` The new account's owner.
+    ///   3. `[writable, signer]` The payer topping the account up to rent
+    ///      exemption, if its mint's settings grow it by a `MintSettings`
+    ///      or `Cooldown` extension.
+    ///   4. `[]` The system program, likewise.
+    InitializeAccount2,
+    /// Sets a new authority of a mint or account. Setting the mint authority
+    /// to `None` permanently fixes the supply. Changing an account owner this
+    /// way takes effect at once and drops any pending owner; `ProposeOwner`
+    /// and `AcceptOwner` are the safer path. An account's close authority is
+    /// set, replaced or revoked by its owner, never by itself; the first one
+    /// grows the account by a `CloseAuthority` extension.
+    ///
+    ///   0. `[writable]` The mint or token account to change.
+    ///   1. `[signer]` The current authority, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    ///
+    /// When an account grows the signers are followed by a `[writable,
+    /// signer]` payer, which tops it up to rent exemption, and the system
+    /// program.
+    SetAuthority {
+        authority_type: AuthorityType,
+        new_authority: COption<Pubkey>,
+    },
+    /// Like `TransferTokens`, but also requires the mint and its decimals so
+    /// the caller can't move a different asset by mistake.
+    ///
+    ///   0. `[writable]` The source account.
+    ///   1. `[]` The token mint.
+    ///   2. `[writable]` The destination account.
+    ///   3. `[signer]` The source account owner or delegate, the mint's permanent
+    ///      delegate, or a multisignature account.
+    ///   4. ..4+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    ///
+    /// If the mint has a royalty, its `[writable]` destination token account
+    /// must follow the signer accounts, and receives its share of `amount`;
+    /// so must the allowlist account if the mint enforces one.
+    /// If the mint has a transfer hook, the hook program and the extra
+    /// accounts it needs follow, in that order; see
+    /// [`crate::transfer_hook::add_hook_accounts`].
+    /// If the mint tracks its activity or counts its holders, it must be
+    /// writable.
+    TransferChecked { amount: u64, decimals: u8 },
+    /// Like `MintTokens`, but also checks `decimals` against the mint.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[writable]` The token account to credit.
+    ///   2. `[signer]` The mint authority or a minter, or a multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    MintToChecked { amount: u64, decimals: u8 },
+    /// Like `Burn`, but also checks `decimals` against the mint.
+    ///
+    ///   0. `[writable]` The token account.
+    ///   1. `[writable]` The mint.
+    ///   2. `[signer]` The token account owner or delegate, the mint's permanent
+    ///      delegate, or a multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    BurnChecked { amount: u64, decimals: u8 },
+    /// Creates and initializes the associated token account for a wallet and
+    /// mint at the address returned by `get_associated_token_address`. The
+    /// funding account may be anyone's: the wallet neither signs nor pays.
+    ///
+    ///   0. `[writable, signer]` The funding account.
+    ///   1. `[writable]` The associated token account to create.
+    ///   2. `[]` The wallet that will own the new account.
+    ///   3. `[]` The token mint.
+    ///   4. `[]` System program.
+    CreateAssociatedAccount,
+    /// Sets a native account's balance to its lamports above the rent-exempt
+    /// reserve, picking up lamports transferred to it directly.
+    ///
+    ///   0. `[writable]` The native token account.
+    SyncNative,
+    /// Initializes a multisignature account with N provided signers, M of
+    /// which must sign to act as an authority.
+    ///
+    ///   0. `[writable]` The multisignature account to initialize.
+    ///   1. `[]` Rent sysvar.
+    ///   2. ..2+N `[]` The signer accounts, must equal to N where 1 <= N <=
+    ///      11.
+    InitializeMultisig {
+        /// The number of signers (M) required to validate this multisignature
+        /// account.
+        m: u8,
+    },
+    /// Moves tokens from one source account to several destinations, one
+    /// amount per destination. Either every credit is applied or none is,
+    /// and every destination must already be initialized. If any requires a
+    /// memo, the instructions sysvar must follow the signer accounts; if the
+    /// mint enforces an allowlist, so must the allowlist account, and if the
+    /// debit takes the source below its minimum balance, so must its
+    /// enforcement authority. Any other account fails with `TooManyAccounts`.
+    ///
+    ///   0. `[writable]` The source account.
+    ///   1. `[signer]` The source account owner or delegate, or a multisignature account.
+    ///   2. ..2+N `[writable]` The N destination accounts, in the order of `amounts`.
+    ///   3. ..3+N+M `[signer]` M signer accounts, if the authority is a
+    ///      multisignature account.
+    TransferBatch { amounts: Vec<u64> },
+    /// Creates the metadata account of a mint at the address returned by
+    /// `get_metadata_address`.
+    ///
+    ///   0. `[writable, signer]` The funding account.
+    ///   1. `[writable]` The metadata account to create.
+    ///   2. `[]` The mint.
+    ///   3. `[signer]` The mint authority, or a multisignature account.
+    ///   4. `[]` The metadata update authority.
+    ///   5. `[]` System program.
+    ///   6. ..6+M `[signer]` M signer accounts, if the mint authority is a
+    ///      multisignature account.
+    CreateMetadata {
+        name: String,
+        symbol: String,
+        uri: String,
+    },
+    /// Replaces the name, symbol and uri of a metadata account.
+    ///
+    ///   0. `[writable]` The metadata account.
+    ///   1. `[signer]` The update authority, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    UpdateMetadata {
+        name: String,
+        symbol: String,
+        uri: String,
+    },
+    /// Configures a transfer fee on a mint before `InitializeMint`. Transfers
+    /// of the mint must then use `TransferChecked`; the fee is withheld on the
+    /// destination account.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    InitializeTransferFeeConfig {
+        transfer_fee_authority: COption<Pubkey>,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    },
+    /// Moves fees withheld on token accounts to their mint. Anyone may call
+    /// this.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. ..1+N `[writable]` The token accounts to harvest from.
+    HarvestWithheldTokens,
+    /// Moves all fees harvested to the mint into a token account.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[signer]` The transfer fee authority, or a multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    WithdrawWithheldTokens,
+    /// Makes a mint non-transferable before `InitializeMint`. Its tokens can
+    /// still be minted, burned and closed out, but every transfer fails.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    InitializeNonTransferableMint,
+    /// Prevents the account from sending or burning tokens before the unix
+    /// timestamp `lock_until`. An existing lock can be extended but not
+    /// shortened; the account can still receive tokens while locked. The
+    /// first lock grows the account by an `AccountLock` extension.
+    ///
+    ///   0. `[writable]` The token account to lock.
+    ///   1. `[signer]` The token account owner, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    ///
+    /// When the account grows the signers are followed by a `[writable,
+    /// signer]` payer, which tops it up to rent exemption, and the system
+    /// program.
+    LockAccount { lock_until: i64 },
+    /// Opens an escrow offering `amount` tokens of the maker's mint for
+    /// `expected_amount` tokens of another mint. The deposit account must
+    /// already be initialized, owned by `get_escrow_authority` of the escrow
+    /// account.
+    ///
+    ///   0. `[signer]` The maker.
+    ///   1. `[writable]` The maker's token account to deposit from.
+    ///   2. `[writable]` The deposit account.
+    ///   3. `[writable]` The escrow account, uninitialized.
+    ///   4. `[]` The maker's token account that will receive the payment.
+    ///   5. `[]` This program, for the deposit transfer.
+    InitializeEscrow { amount: u64, expected_amount: u64 },
+    /// Pays `amount` tokens to the maker and releases the deposit to the
+    /// taker, closing the deposit and escrow accounts. `amount` must equal
+    /// the escrow's expected amount, and `expected_amount` the deposit it
+    /// recorded when opened; the deposit must still hold exactly that much.
+    ///
+    ///   0. `[signer]` The taker.
+    ///   1. `[writable]` The taker's token account to pay from.
+    ///   2. `[writable]` The taker's token account to receive the deposit.
+    ///   3. `[writable]` The deposit account.
+    ///   4. `[writable]` The maker's token account receiving the payment.
+    ///   5. `[writable]` The maker, receiving the closed accounts' lamports.
+    ///   6. `[writable]` The escrow account.
+    ///   7. `[]` The escrow authority.
+    ///   8. `[]` This program, for the transfers.
+    Exchange { amount: u64, expected_amount: u64 },
+    /// Returns the deposit to the maker and closes the deposit and escrow
+    /// accounts.
+    ///
+    ///   0. `[writable, signer]` The maker.
+    ///   1. `[writable]` The deposit account.
+    ///   2. `[writable]` The maker's token account to refund.
+    ///   3. `[writable]` The escrow account.
+    ///   4. `[]` The escrow authority.
+    ///   5. `[]` This program, for the refund transfer.
+    CancelEscrow,
+    /// Gives a mint a pause authority before `InitializeMint`. Token accounts
+    /// of a pausable mint can only send with `TransferChecked`.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    InitializePauseConfig { pause_authority: Pubkey },
+    /// Pauses a mint: transfers, mint-to and burns fail until it is resumed.
+    /// Empty accounts can still be closed.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The pause authority, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    Pause,
+    /// Resumes a paused mint, using its pause authority or its recovery
+    /// authority.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The pause or recovery authority, or a multisignature
+    ///      account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    Resume,
+    /// Gives a mint a permanent delegate before `InitializeMint`. The
+    /// delegate can move or burn tokens from any account of the mint with
+    /// `TransferChecked` or `BurnChecked`, which check the mint, and can't be
+    /// changed later. `TransferTokens` and `Burn` don't accept it.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    InitializePermanentDelegate { delegate: Pubkey },
+    /// Proposes `new_owner` as the account's owner, replacing any earlier
+    /// proposal. Ownership only changes once the candidate accepts.
+    ///
+    ///   0. `[writable]` The token account.
+    ///   1. `[signer]` The current owner, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the owner is a multisignature
+    ///      account.
+    ProposeOwner { new_owner: Pubkey },
+    /// Makes the pending owner the account's owner and clears any delegate.
+    ///
+    ///   0. `[writable]` The token account.
+    ///   1. `[signer]` The pending owner, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the pending owner is a
+    ///      multisignature account.
+    AcceptOwner,
+    /// Withdraws a pending owner proposal.
+    ///
+    ///   0. `[writable]` The token account.
+    ///   1. `[signer]` The current owner, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the owner is a multisignature
+    ///      account.
+    CancelPendingOwner,
+    /// Returns the account's `amount` as 8 little-endian bytes of return
+    /// data; see [`get_returned_amount`].
+    ///
+    ///   0. `[]` The token account.
+    GetAccountBalance,
+    /// Returns the mint's `supply` as 8 little-endian bytes of return data;
+    /// see [`get_returned_amount`].
+    ///
+    ///   0. `[]` The mint.
+    GetMintSupply,
+    /// Returns `amount` formatted with the mint's decimals, as UTF-8 return
+    /// data; see [`crate::amount_to_ui_amount`]. For an interest-bearing mint
+    /// the amount includes interest accrued up to the current clock.
+    ///
+    ///   0. `[]` The mint.
+    AmountToUiAmount { amount: u64 },
+    /// Returns `ui_amount` parsed with the mint's decimals, as 8
+    /// little-endian bytes of return data; see
+    /// [`crate::try_ui_amount_into_amount`]. For an interest-bearing mint
+    /// accrued interest is taken out first, rounding down.
+    ///
+    ///   0. `[]` The mint.
+    UiAmountToAmount { ui_amount: String },
+    /// Requires every transfer into the account to be immediately preceded,
+    /// in the same transaction, by a memo program instruction. Transfers
+    /// must then also pass the instructions sysvar. The first time it grows
+    /// the account by a `MemoTransfer` extension.
+    ///
+    ///   0. `[writable]` The token account.
+    ///   1. `[signer]` The account owner, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the owner is a multisignature
+    ///      account.
+    ///
+    /// When the account grows the signers are followed by a `[writable,
+    /// signer]` payer, which tops it up to rent exemption, and the system
+    /// program.
+    RequireMemo,
+    /// Stops requiring memos on incoming transfers.
+    ///
+    ///   0. `[writable]` The token account.
+    ///   1. `[signer]` The account owner, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the owner is a multisignature
+    ///      account.
+    UnrequireMemo,
+    /// Gives a mint a transfer hook before `InitializeMint`. Every
+    /// `TransferChecked` of the mint then invokes `program_id` with
+    /// [`crate::transfer_hook::execute`], and other transfers are rejected.
+    /// The hook can't be changed later.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    InitializeTransferHook { program_id: Pubkey },
+    /// Sets the state new token accounts of a mint start in, before
+    /// `InitializeMint`. `state` is `Initialized` or `Frozen`; a `Frozen`
+    /// default requires the mint to be initialized with a freeze authority.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    InitializeDefaultAccountState { state: AccountState },
+    /// Changes the state new token accounts of the mint start in. Existing
+    /// accounts keep their state.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The mint freeze authority, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    SetDefaultAccountState { state: AccountState },
+    /// Makes a token account's owner immutable, before `InitializeAccount`.
+    /// `SetAuthority` on the owner and `ProposeOwner` then always fail. The
+    /// `ImmutableOwner` extension it grows the account by can't be removed.
+    ///
+    ///   0. `[writable]` The token account to configure.
+    ///   1. `[writable, signer]` The payer topping the account up to rent
+    ///      exemption.
+    ///   2. `[]` The system program.
+    InitializeImmutableOwner,
+    /// Makes a mint interest-bearing before `InitializeMint`: UI amounts of
+    /// the mint grow continuously at an annual `rate_bps`, starting from the
+    /// current clock. Raw balances and transfers are unaffected.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    InitializeInterestBearingMint {
+        rate_authority: COption<Pubkey>,
+        rate_bps: i16,
+    },
+    /// Changes an interest-bearing mint's rate. Interest up to now accrues
+    /// at the old rate.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The mint rate authority, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    UpdateRate { rate_bps: i16 },
+    /// Gives a mint a close authority before `InitializeMint`. The authority
+    /// can be changed later with `SetAuthority`.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    InitializeMintCloseAuthority { close_authority: COption<Pubkey> },
+    /// Closes a mint whose supply is zero, moving its lamports to the
+    /// destination. Token accounts of the mint can't be created afterwards.
+    ///
+    ///   0. `[writable]` The mint to close.
+    ///   1. `[writable]` The destination for the mint's lamports.
+    ///   2. `[signer]` The mint close authority, or a multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    CloseMint,
+    /// Rewrites an account of an older layout in the current one: a token
+    /// account or mint of the legacy, unversioned layout, or an account of
+    /// any type written before account types. The account grows to its
+    /// type's current size, and the payer funds the extra rent. Anyone may
+    /// call this.
+    ///
+    ///   0. `[writable]` The token account or mint to migrate.
+    ///   1. `[writable, signer]` The payer.
+    ///   2. `[]` The system program.
+    MigrateAccount,
+    /// Gives a mint a royalty before `InitializeMint`: `basis_points` /
+    /// 10_000 of every checked transfer, rounded down, goes to the
+    /// `destination` token account of the mint instead of the recipient.
+    /// Token accounts of the mint then need `TransferChecked`. The royalty
+    /// can't be changed later. The mint grows by a `Royalty` extension.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    ///   1. `[writable, signer]` The payer topping the mint up to rent
+    ///      exemption.
+    ///   2. `[]` The system program.
+    InitializeRoyalty {
+        basis_points: u16,
+        destination: Pubkey,
+    },
+    /// Creates the allowlist account of a mint, at the address returned by
+    /// `get_allowlist_address`, before `InitializeMint`. Tokens of the mint
+    /// can then only be minted or transferred to token accounts whose owner
+    /// the `authority` has added to the list; the allowlist account must
+    /// follow the signer accounts of those instructions. The mint grows by
+    /// an `AllowlistEnforcement` extension.
+    ///
+    ///   0. `[writable, signer]` The funding account, also topping the mint
+    ///      up to rent exemption.
+    ///   1. `[writable]` The allowlist account to create.
+    ///   2. `[writable]` The mint to configure.
+    ///   3. `[]` System program.
+    InitializeAllowlist { authority: Pubkey },
+    /// Adds an owner to a mint's allowlist, growing the account. Adding an
+    /// owner already on the list does nothing.
+    ///
+    ///   0. `[writable]` The allowlist account.
+    ///   1. `[signer]` The allowlist authority, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    ///
+    /// The payer, `[writable, signer]`, which funds the extra rent, and the
+    /// system program follow the signer accounts.
+    AddToAllowlist { owner: Pubkey },
+    /// Removes an owner from a mint's allowlist, shrinking the account. Its
+    /// token accounts can no longer receive tokens.
+    ///
+    ///   0. `[writable]` The allowlist account.
+    ///   1. `[signer]` The allowlist authority, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    RemoveFromAllowlist { owner: Pubkey },
+    /// Gives a mint a minimum transfer amount before `InitializeMint`.
+    /// Transfers of less, or of zero, fail unless they empty the source
+    /// account. Token accounts of the mint then need `TransferChecked`. The
+    /// mint grows by a `MinTransferAmount` extension.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    ///   1. `[writable, signer]` The payer topping the mint up to rent
+    ///      exemption.
+    ///   2. `[]` The system program.
+    InitializeMinTransferAmount { min_transfer_amount: u64 },
+    /// Changes the minimum transfer amount of a mint that has one.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The mint authority, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    UpdateMinTransferAmount { min_transfer_amount: u64 },
+    /// Hands a token account to a new owner who signs alongside the current
+    /// one, clearing any delegate and pending owner. Fails for frozen
+    /// accounts and accounts with an immutable owner.
+    ///
+    ///   0. `[writable]` The token account.
+    ///   1. `[signer]` The current owner, or a multisignature account.
+    ///   2. `[signer]` The new owner.
+    ///   3. ..3+M `[signer]` M signer accounts, if the current owner is a
+    ///      multisignature account.
+    TransferAccountOwnership,
+    /// Opens a merkle distribution of `total_claimable` tokens, moving them
+    /// from the creator's account into the vault. The vault must already be
+    /// initialized, owned by `get_distributor_authority` of the distributor
+    /// account.
+    ///
+    ///   0. `[signer]` The creator, owner of the funding account.
+    ///   1. `[writable]` The creator's token account to fund from.
+    ///   2. `[writable]` The vault.
+    ///   3. `[writable]` The distributor account, uninitialized.
+    ///   4. `[]` This program, for the funding transfer.
+    CreateDistribution {
+        merkle_root: [u8; 32],
+        total_claimable: u64,
+    },
+    /// Pays out leaf `index` of a distribution, entitling the claimant to
+    /// `amount` tokens, once `proof` leads from its `claim_leaf` to the
+    /// merkle root. The claim status account, created here, rejects a
+    /// second claim of the same leaf.
+    ///
+    ///   0. `[writable, signer]` The claimant, paying for the claim status.
+    ///   1. `[writable]` The token account to receive the tokens.
+    ///   2. `[writable]` The distributor account.
+    ///   3. `[writable]` The vault.
+    ///   4. `[]` The distributor authority.
+    ///   5. `[writable]` The claim status, `get_claim_address` of the
+    ///      distributor and `index`.
+    ///   6. `[]` The system program.
+    ///   7. `[]` This program, for the payout transfer.
+    Claim {
+        index: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    },
+    /// Like `InitializeMint`, but reads rent from the `Rent` sysvar syscall
+    /// instead of requiring the sysvar account.
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeMint2 {
+        decimals: u8,
+        mint_authority: Pubkey,
+        freeze_authority: COption<Pubkey>,
+        max_supply: COption<u64>,
+    },
+    /// Like `InitializeAccount2`, but takes the owner as an argument instead
+    /// of an account.
+    ///
+    ///   0. `[writable]` The token account to initialize.
+    ///   1. `[]` The mint this account will hold.
+    ///   2. `[writable, signer]` The payer topping the account up to rent
+    ///      exemption, if its mint's settings grow it by a `MintSettings`
+    ///      or `Cooldown` extension.
+    ///   3. `[]` The system program, likewise.
+    InitializeAccount3 { owner: Pubkey },
+    /// Like `InitializeMultisig`, but reads rent from the `Rent` sysvar
+    /// syscall instead of requiring the sysvar account.
+    ///
+    ///   0. `[writable]` The multisignature account to initialize.
+    ///   1. ..1+N `[]` The signer accounts, must equal to N where 1 <= N <=
+    ///      11.
+    InitializeMultisig2 {
+        /// The number of signers (M) required to validate this multisignature
+        /// account.
+        m: u8,
+    },
+    /// Freezes several accounts of one mint at once, e.g. every account a
+    /// compromised key controls, using the mint's freeze authority.
+    ///
+    /// Fails without freezing any of them if one isn't an account of the
+    /// mint. Accounts that are already frozen are left as they are, so a
+    /// batch can be resent with more accounts added. Accounts beyond the N
+    /// to freeze and the signers fail with `TooManyAccounts`.
+    ///
+    ///   0. `[]` The mint.
+    ///   1. `[signer]` The mint freeze authority, or a multisignature account.
+    ///   2. ..2+N `[writable]` The N accounts to freeze, where 1 <= N <= 24.
+    ///   3. 2+N..2+N+M `[signer]` M signer accounts, if the authority is a
+    ///      multisignature account.
+    FreezeBatch {
+        /// The number of accounts to freeze (N).
+        count: u8,
+    },
+    /// Launches a token in one instruction: initializes a mint, like
+    /// `InitializeMint2`, then creates the associated token account of each
+    /// wallet and credits it its amount. The mint starts with the total of
+    /// the amounts as its supply, which must fit any `max_supply`. Fails
+    /// without creating anything if any allocation can't be made.
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    ///   1. `[signer]` The mint authority.
+    ///   2. `[writable, signer]` The funding account for the new accounts.
+    ///   3. `[]` System program.
+    ///   4. 4+2i `[writable]` The associated token account of wallet i, for
+    ///      each of the N allocations, where 1 <= N <= 16.
+    ///   5. 5+2i `[]` Wallet i, which will own it.
+    InitializeMintWithAllocations {
+        decimals: u8,
+        freeze_authority: COption<Pubkey>,
+        max_supply: COption<u64>,
+        /// The amount credited to each allocation, in order.
+        amounts: Vec<u64>,
+    },
+    /// Makes a mint record its activity before `InitializeMint`: transfers
+    /// count towards its `transfer_count`, and transfers, mints and burns
+    /// stamp its `last_activity_unix`. Token accounts of the mint then need
+    /// `TransferChecked`, with the mint writable. The activity is kept in a
+    /// `MintActivity` extension the mint grows by.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    ///   1. `[writable, signer]` The payer topping the mint up to rent
+    ///      exemption.
+    ///   2. `[]` The system program.
+    InitializeActivityTracking,
+    /// Moves `amount` to a destination and `fee` to a fee account chosen by
+    /// the caller, debiting the source by both. Checked and applied like a
+    /// `TransferBatch` of the two, so it can't move tokens of mints that
+    /// need `TransferChecked`.
+    ///
+    ///   0. `[writable]` The source account.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[writable]` The fee account, distinct from the other two.
+    ///   3. `[signer]` The source account owner or delegate, or a multisignature account.
+    ///   4. ..4+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    TransferWithFee { amount: u64, fee: u64 },
+    /// Creates and initializes a token account in one instruction, funding
+    /// its rent-exempt reserve from the payer. Without a `seed` the account
+    /// is a keypair address, which must sign; with one it is the address
+    /// returned by `get_seeded_token_address` for the owner, mint and seed.
+    ///
+    /// An address that already holds lamports is topped up to the reserve
+    /// rather than created; for the native mint, lamports above the reserve
+    /// become the wrapped amount. As for `CreateAssociatedAccount`, the
+    /// owner neither signs nor pays.
+    ///
+    ///   0. `[writable, signer]` The funding account.
+    ///   1. `[writable]` The token account to create, a signer if it is a
+    ///      keypair address.
+    ///   2. `[]` The mint this account will hold.
+    ///   3. `[]` System program.
+    CreateTokenAccount { owner: Pubkey, seed: COption<u64> },
+    /// Gives a mint a transfer cooldown before `InitializeMint`: after an
+    /// account sends, it can't send again until `cooldown_slots` slots have
+    /// passed. Receiving, minting and burning are unaffected. Token accounts
+    /// of the mint then need `TransferChecked`, and each grows by a
+    /// `Cooldown` extension recording its last send. The mint grows by a
+    /// `TransferCooldown` extension.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    ///   1. `[writable, signer]` The payer topping the mint up to rent
+    ///      exemption.
+    ///   2. `[]` The system program.
+    InitializeTransferCooldown { cooldown_slots: u64 },
+    /// Makes a mint count its holders before `InitializeMint`: `holders` is
+    /// the number of its token accounts with a nonzero balance, kept by
+    /// mints, burns, transfers and fee withdrawals. Token accounts of the
+    /// mint then need `TransferChecked`, with the mint writable; accounts
+    /// can only be closed empty, so closing never changes the count. The
+    /// count is kept in a `HolderCount` extension the mint grows by.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    ///   1. `[writable, signer]` The payer topping the mint up to rent
+    ///      exemption.
+    ///   2. `[]` The system program.
+    InitializeHolderCount,
+    /// Moves tokens from a source account as a `permit::Permit`, signed
+    /// off-chain by the source's owner, allows; anyone can submit it. The
+    /// instruction immediately before must be the ed25519 program's check
+    /// of the owner's signature over the packed permit, as built by
+    /// `permit::new_ed25519_instruction`. Each nonce is used at most once,
+    /// and only in increasing order: the source account grows by an entry
+    /// recording the next one on its first permit. Token accounts of mints
+    /// that need `TransferChecked` can't be the source, nor can accounts
+    /// requiring memos be the destination; if the mint enforces an
+    /// allowlist, its allowlist account must follow the system program.
+    ///
+    ///   0. `[writable]` The source account.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[]` Instructions sysvar.
+    ///   3. `[writable, signer]` The payer, for the source account's growth.
+    ///   4. `[]` System program.
+    PermitTransfer,
+    /// Creates the vesting schedule of a beneficiary token account at the
+    /// address returned by `vesting::get_vesting_address`: `total_amount`
+    /// tokens vest linearly from `start_ts` to `end_ts`, and nothing before
+    /// `start_ts`. The total is reserved against the mint's supply cap until
+    /// claimed, so the mint authority can't later mint it elsewhere; the
+    /// first schedule grows the mint by a `VestingReserve` extension to
+    /// hold it.
+    ///
+    ///   0. `[writable, signer]` The funding account, which also tops the
+    ///      mint up to rent exemption.
+    ///   1. `[writable]` The vesting schedule to create.
+    ///   2. `[writable]` The mint.
+    ///   3. `[]` The beneficiary token account.
+    ///   4. `[signer]` The mint authority, or a multisignature account.
+    ///   5. `[]` System program.
+    ///   6. ..6+M `[signer]` M signer accounts, if the authority is a
+    ///      multisignature account.
+    CreateVesting {
+        total_amount: u64,
+        start_ts: i64,
+        end_ts: i64,
+    },
+    /// Mints to the beneficiary whatever its vesting schedule has vested
+    /// since the last claim; anyone can submit it, and it does nothing if
+    /// nothing new has vested. If the mint enforces an allowlist, the
+    /// allowlist account must follow.
+    ///
+    ///   0. `[writable]` The vesting schedule.
+    ///   1. `[writable]` The mint.
+    ///   2. `[writable]` The beneficiary token account.
+    ClaimVested,
+    /// Gives a token account its real `owner` if it records its mint's
+    /// address as the owner, as accounts created by the original
+    /// `MintTokens` did; no one can sign for those. Legacy-layout accounts
+    /// must first be rewritten with `MigrateAccount`.
+    ///
+    ///   0. `[writable]` The token account to repair.
+    ///   1. `[]` The account's mint.
+    ///   2. `[signer]` The mint authority, or a multisignature account.
+    ///   3. ..3+M `[signer]` M signer accounts, if the authority is a
+    ///      multisignature account.
+    RepairOwner { owner: Pubkey },
+    /// Recovers tokens sent to the associated token account of one of a
+    /// wallet's own associated token accounts, which nobody can sign for:
+    /// moves the whole balance to the wallet's associated token account for
+    /// that mint and closes the nested account, refunding its lamports to
+    /// the wallet. Every address must derive from the wallet and the two
+    /// mints. Mints whose transfers need the mint can't be recovered, as
+    /// with `TransferBatch`.
+    ///
+    ///   0. `[writable]` The nested associated token account.
+    ///   1. `[]` The nested account's mint.
+    ///   2. `[writable]` The wallet's associated token account for that mint.
+    ///   3. `[]` The wallet's associated token account that owns account 0.
+    ///   4. `[]` That account's mint.
+    ///   5. `[writable, signer]` The wallet.
+    RecoverNested,
+    /// Swaps tokens between two owners who both sign the transaction, with
+    /// no escrow: `amount_a` moves from `source_a` to `destination_a` and
+    /// `amount_b` from `source_b` to `destination_b`, or nothing moves.
+    /// Each leg is checked like a `TransferBatch` leg, so mints whose
+    /// transfers need the mint can't be swapped. If a destination requires
+    /// a memo, the instructions sysvar must follow the accounts; if a mint
+    /// enforces an allowlist, so must the allowlist account.
+    ///
+    ///   0. `[writable]` Owner A's source account.
+    ///   1. `[writable]` The account receiving `amount_a`.
+    ///   2. `[signer]` Owner A.
+    ///   3. `[writable]` Owner B's source account.
+    ///   4. `[writable]` The account receiving `amount_b`.
+    ///   5. `[signer]` Owner B.
+    TwoPartySwap { amount_a: u64, amount_b: u64 },
+    /// Like `CreateAssociatedAccount`, but does nothing if the associated
+    /// token account already exists, so it can precede every transfer to a
+    /// wallet. An existing account of another mint or owner is an error.
+    ///
+    ///   0. `[writable, signer]` The funding account.
+    ///   1. `[writable]` The associated token account to create.
+    ///   2. `[]` The wallet that will own the new account.
+    ///   3. `[]` The token mint.
+    ///   4. `[]` System program.
+    CreateAssociatedAccountIdempotent,
+    /// Gives a mint a recovery authority before `InitializeMint`: a key
+    /// that can thaw accounts with `ThawAccount` and resume the mint with
+    /// `Resume` if the freeze or pause authority is lost, but do nothing
+    /// else. It can't be changed later, and grows the mint by a
+    /// `RecoveryAuthority` extension.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    ///   1. `[writable, signer]` The payer topping the mint up to rent
+    ///      exemption.
+    ///   2. `[]` The system program.
+    InitializeRecoveryAuthority { recovery_authority: Pubkey },
+    /// Points a mint at metadata kept outside this program before
+    /// `InitializeMint`. The mint's metadata account can't then be created
+    /// with `CreateMetadata`: the pointer takes precedence, even once
+    /// cleared. The `MetadataPointer` extension it grows the mint by can't
+    /// be removed.
+    ///
+    ///   0. `[writable]` The mint to configure.
+    ///   1. `[writable, signer]` The payer topping the mint up to rent
+    ///      exemption.
+    ///   2. `[]` The system program.
+    InitializeMetadataPointer {
+        authority: COption<Pubkey>,
+        metadata_address: COption<Pubkey>,
+    },
+    /// Repoints a mint's metadata pointer, or clears it to `None`.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The metadata pointer authority, or a multisignature
+    ///      account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    UpdateMetadataPointer { metadata_address: COption<Pubkey> },
+    /// Opens a converter from one mint to another at a fixed rate of
+    /// `numerator / denominator`. The rate must be nonzero, and converting
+    /// the source mint's maximum supply, or its supply if uncapped, must
+    /// not overflow. Conversions fail until the target mint's mint
+    /// authority is `get_converter_authority` of the converter.
+    ///
+    ///   0. `[writable]` The converter account, uninitialized.
+    ///   1. `[]` The mint to convert from.
+    ///   2. `[]` The mint to convert to.
+    CreateConverter { numerator: u64, denominator: u64 },
+    /// Converts up to `amount` tokens in one step: burns them from the
+    /// owner's account and mints `numerator / denominator` as many of the
+    /// target mint to the destination, rounded down. Only as much as the
+    /// minted tokens take is burned; the remainder stays unconverted.
+    ///
+    ///   0. `[signer]` The owner of the source account.
+    ///   1. `[writable]` The token account to convert from.
+    ///   2. `[writable]` The mint to convert from.
+    ///   3. `[writable]` The token account to receive the converted tokens.
+    ///   4. `[writable]` The mint to convert to.
+    ///   5. `[]` The converter.
+    ///   6. `[]` The converter authority.
+    ///   7. `[]` This program, for the burn and the mint.
+    Convert { amount: u64 },
+    /// Sets the balance below which the account's transfers and burns fail
+    /// with `BelowMinimumBalance`, unless `enforcement_authority` also signs
+    /// them, passed as an extra account after any signers. The owner can
+    /// raise the minimum alone; lowering or clearing it, to zero, or naming
+    /// another authority while it is set also takes the current enforcement
+    /// authority's signature. The first minimum grows the account by a
+    /// `MinimumBalance` extension.
+    ///
+    ///   0. `[writable]` The token account.
+    ///   1. `[signer]` The token account owner, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the owner is a multisignature
+    ///      account.
+    ///
+    /// The current enforcement authority follows the signers, as a signer,
+    /// when the change needs it. When the account grows, they are followed
+    /// by a `[writable, signer]` payer, which tops it up to rent exemption,
+    /// and the system program.
+    SetMinimumBalance {
+        minimum_balance: u64,
+        enforcement_authority: Pubkey,
+    },
+    /// Records the token account's balance and owner as of the current slot
+    /// in a new account at `snapshot::get_snapshot_address` of the token
+    /// account and `snapshot_id`. Anyone can take a snapshot of any account.
+    /// It never changes once written, and only its payer can close it.
+    ///
+    ///   0. `[writable, signer]` The payer, who funds the snapshot.
+    ///   1. `[writable]` The snapshot account, not yet created.
+    ///   2. `[]` The token account.
+    ///   3. `[]` The system program.
+    SnapshotBalance { snapshot_id: u64 },
+    /// Closes a snapshot, sending its lamports to the destination. Fails
+    /// with `SnapshotRetained` until `snapshot::SNAPSHOT_RETENTION_SLOTS`
+    /// have passed since its slot.
+    ///
+    ///   0. `[writable]` The snapshot account.
+    ///   1. `[writable]` The destination account.
+    ///   2. `[signer]` The payer of the snapshot.
+    CloseSnapshot,
+    /// Moves `amount` to a destination and `relayer_fee` to the token
+    /// account of a relayer that paid the transaction fee, debiting the
+    /// source by both, so owners without lamports can still transact. All
+    /// three accounts must be of the given mint. The fee may be zero.
+    /// Checked and applied like two `TransferChecked`s, so the mint's
+    /// transfer fee, pause, hook and royalty apply to both; the relayer fee
+    /// is exempt from a cooldown or minimum transfer amount the first
+    /// movement has already met. The accounts those need follow the
+    /// signers, as for `TransferChecked`, and the mint must be writable if
+    /// it tracks activity or holders.
+    ///
+    ///   0. `[writable]` The source account.
+    ///   1. `[]` The token mint.
+    ///   2. `[writable]` The destination account.
+    ///   3. `[writable]` The relayer's token account, distinct from the
+    ///      other two.
+    ///   4. `[signer]` The source account owner or delegate, or a multisignature account.
+    ///   5. ..5+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    TransferWithRelayerFee { amount: u64, relayer_fee: u64 },
+    /// Proves control of a token account without moving funds: checks that
+    /// its owner signed, then logs an `OwnershipProved` event with `nonce`,
+    /// which a verifier picks to tie the proof to its challenge, and the
+    /// current unix timestamp. Writes nothing, so frozen accounts can still
+    /// be proven. Verifiers read the event back with
+    /// `event::find_ownership_proof`.
+    ///
+    ///   0. `[]` The token account.
+    ///   1. `[signer]` The token account owner, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the owner is a multisignature
+    ///      account.
+    VerifyOwnership { nonce: u64 },
+    /// Caps what a single transfer, batch transfer or burn may debit from
+    /// the account, whoever signs it, at `max_outflow_per_instruction`;
+    /// debits over it fail with `OutflowLimitExceeded`. A lower limit
+    /// applies at once and cancels any pending one. A higher one only
+    /// applies once the current `timelock_slots` have passed, replacing any
+    /// pending one; `u64::MAX` lifts the cap. The timelock can't be zero and
+    /// only grows, failing with `TimelockCannotBeShortened` otherwise. The
+    /// first limit grows the account by an `OutflowLimit` extension.
+    ///
+    ///   0. `[writable]` The token account.
+    ///   1. `[signer]` The token account owner, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the owner is a multisignature
+    ///      account.
+    ///
+    /// When the account grows the signers are followed by a `[writable,
+    /// signer]` payer, which tops it up to rent exemption, and the system
+    /// program.
+    SetOutflowLimit {
+        max_outflow_per_instruction: u64,
+        timelock_slots: u64,
+    },
+    /// Lets `minter` sign `MintTokens` and `MintToChecked` for the mint, at
+    /// most `max_per_window` tokens per `window_slots` slots. Its first
+    /// window starts when it is added, and each later one at its first
+    /// mint after the last one ended. A `window_slots`
+    /// of zero leaves it uncapped, and `max_per_window` must then be zero
+    /// too. Adding a listed minter replaces its cap and starts a new
+    /// window. A mint lists
+    /// up to `extension::MAX_MINTERS`; the first minter grows it by a
+    /// `Minters` extension. Minters can no longer mint once the mint
+    /// authority is unset.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The mint authority, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    ///
+    /// When the mint grows the signers are followed by a `[writable,
+    /// signer]` payer, which tops it up to rent exemption, and the system
+    /// program.
+    AddMinter {
+        minter: Pubkey,
+        max_per_window: u64,
+        window_slots: u64,
+    },
+    /// Removes `minter` from the mint's minters, failing with `NotAMinter`
+    /// if it isn't one.
+    ///
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The mint authority, or a multisignature account.
+    ///   2. ..2+M `[signer]` M signer accounts, if the authority is a multisignature
+    ///      account.
+    RemoveMinter { minter: Pubkey },
+}
+
+/// Specifies the authority type for `SetAuthority` instructions.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuthorityType {
+    /// Authority to mint new tokens
+    MintTokens,
+    /// Authority to freeze any account associated with the mint
+    FreezeAccount,
+    /// Owner of a given token account
+    AccountOwner,
+    /// Authority to withdraw withheld transfer fees
+    TransferFeeConfig,
+    /// Authority to close the mint
+    CloseMint,
+    /// Authority besides the owner to close an empty token account
+    CloseAccount,
+}
+
+impl AuthorityType {
+    pub(crate) fn into_u8(self) -> u8 {
+        self as u8
+    }
+
+    pub(crate) fn from_u8(index: u8) -> Result<Self, ProgramError> {
+        match index {
+            0 => Ok(AuthorityType::MintTokens),
+            1 => Ok(AuthorityType::FreezeAccount),
+            2 => Ok(AuthorityType::AccountOwner),
+            3 => Ok(AuthorityType::TransferFeeConfig),
+            4 => Ok(AuthorityType::CloseMint),
+            5 => Ok(AuthorityType::CloseAccount),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+impl TokenInstruction {
+    /// Unpacks instruction data in the legacy or, with the `anchor` feature,
+    /// the Anchor layout. Fails with `UnsupportedInstruction` for a tag
+    /// reserved by [`MAX_TAG`], or `InvalidInstructionData` for any other
+    /// unknown tag, malformed arguments or bytes left over after them.
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (instruction, rest) = Self::unpack_with_rest(input)?;
+        if !rest.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(instruction)
+    }
+
+    /// The tag of the instruction in `input`, read from its Anchor
+    /// discriminator, with the `anchor` feature, or its first byte, without
+    /// unpacking its arguments.
+    pub fn tag(input: &[u8]) -> Option<u8> {
+        #[cfg(feature = "anchor")]
+        if let Some(tag) = crate::anchor::tag(input) {
+            return Some(tag);
+        }
+        input.first().copied()
+    }
+
+    /// Unpacks an instruction, returning the bytes after its arguments.
+    fn unpack_with_rest(input: &[u8]) -> Result<(Self, &[u8]), ProgramError> {
+        #[cfg(feature = "anchor")]
+        if let Some(tag) = crate::anchor::tag(input) {
+            let rest = &input[8..];
+            if tag == 18 {
+                let (count, rest) = Self::unpack_u32(rest)?;
+                return Self::unpack_transfer_batch(count as usize, rest);
+            }
+            if tag == 59 {
+                let (index, rest) = Self::unpack_u64(rest)?;
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (count, rest) = Self::unpack_u32(rest)?;
+                return Self::unpack_claim(index, amount, count as usize, rest);
+            }
+            if tag == 64 {
+                let (&decimals, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (count, rest) = Self::unpack_u32(rest)?;
+                return Self::unpack_allocations(decimals, count as usize, rest);
+            }
+            return Self::unpack_args(tag, rest);
+        }
+        let (&tag, rest) = input
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        Self::unpack_args(tag, rest)
+    }
+
+    fn unpack_args(tag: u8, rest: &[u8]) -> Result<(Self, &[u8]), ProgramError> {
+        Ok(match tag {
+            0 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::MintTokens { amount }, rest)
+            }
+            1 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::TransferTokens { amount }, rest)
+            }
+            2 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::Burn { amount }, rest)
+            }
+            3 | 60 => {
+                let (&decimals, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (mint_authority, rest) = Self::unpack_pubkey(rest)?;
+                let (freeze_authority, rest) = Self::unpack_pubkey_option(rest)?;
+                let (max_supply, rest) = if rest.is_empty() {
+                    (COption::None, rest)
+                } else {
+                    Self::unpack_u64_option(rest)?
+                };
+                (
+                    if tag == 3 {
+                        Self::InitializeMint {
+                            decimals,
+                            mint_authority,
+                            freeze_authority,
+                            max_supply,
+                        }
+                    } else {
+                        Self::InitializeMint2 {
+                            decimals,
+                            mint_authority,
+                            freeze_authority,
+                            max_supply,
+                        }
+                    },
+                    rest,
+                )
+            }
+            4 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::Approve { amount }, rest)
+            }
+            5 => (Self::Revoke, rest),
+            6 => (Self::InitializeAccount, rest),
+            7 => (Self::CloseAccount, rest),
+            8 => (Self::FreezeAccount, rest),
+            9 => (Self::ThawAccount, rest),
+            10 => (Self::InitializeAccount2, rest),
+            11 => {
+                let (&authority_type, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let authority_type = AuthorityType::from_u8(authority_type)?;
+                let (new_authority, rest) = Self::unpack_pubkey_option(rest)?;
+                (
+                    Self::SetAuthority {
+                        authority_type,
+                        new_authority,
+                    },
+                    rest,
+                )
+            }
+            12..=14 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (&decimals, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                (
+                    match tag {
+                        12 => Self::TransferChecked { amount, decimals },
+                        13 => Self::MintToChecked { amount, decimals },
+                        _ => Self::BurnChecked { amount, decimals },
+                    },
+                    rest,
+                )
+            }
+            15 => (Self::CreateAssociatedAccount, rest),
+            16 => (Self::SyncNative, rest),
+            17 | 62 => {
+                let (&m, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                (
+                    if tag == 17 {
+                        Self::InitializeMultisig { m }
+                    } else {
+                        Self::InitializeMultisig2 { m }
+                    },
+                    rest,
+                )
+            }
+            63 => {
+                let (&count, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                if count == 0 || count as usize > MAX_FREEZE_BATCH {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                (Self::FreezeBatch { count }, rest)
+            }
+            64 => {
+                let (&decimals, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (&count, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                return Self::unpack_allocations(decimals, count as usize, rest);
+            }
+            65 => (Self::InitializeActivityTracking, rest),
+            66 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (fee, rest) = Self::unpack_u64(rest)?;
+                (Self::TransferWithFee { amount, fee }, rest)
+            }
+            67 => {
+                let (owner, rest) = Self::unpack_pubkey(rest)?;
+                let (seed, rest) = Self::unpack_u64_option(rest)?;
+                (Self::CreateTokenAccount { owner, seed }, rest)
+            }
+            68 => {
+                let (cooldown_slots, rest) = Self::unpack_u64(rest)?;
+                (Self::InitializeTransferCooldown { cooldown_slots }, rest)
+            }
+            69 => (Self::InitializeHolderCount, rest),
+            70 => (Self::PermitTransfer, rest),
+            71 => {
+                let (total_amount, rest) = Self::unpack_u64(rest)?;
+                let (start_ts, rest) = Self::unpack_u64(rest)?;
+                let (end_ts, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::CreateVesting {
+                        total_amount,
+                        start_ts: start_ts as i64,
+                        end_ts: end_ts as i64,
+                    },
+                    rest,
+                )
+            }
+            72 => (Self::ClaimVested, rest),
+            73 => {
+                let (owner, rest) = Self::unpack_pubkey(rest)?;
+                (Self::RepairOwner { owner }, rest)
+            }
+            74 => (Self::RecoverNested, rest),
+            75 => {
+                let (amount_a, rest) = Self::unpack_u64(rest)?;
+                let (amount_b, rest) = Self::unpack_u64(rest)?;
+                (Self::TwoPartySwap { amount_a, amount_b }, rest)
+            }
+            76 => (Self::CreateAssociatedAccountIdempotent, rest),
+            77 => {
+                let (recovery_authority, rest) = Self::unpack_pubkey(rest)?;
+                (
+                    Self::InitializeRecoveryAuthority { recovery_authority },
+                    rest,
+                )
+            }
+            78 => {
+                let (authority, rest) = Self::unpack_pubkey_option(rest)?;
+                let (metadata_address, rest) = Self::unpack_pubkey_option(rest)?;
+                (
+                    Self::InitializeMetadataPointer {
+                        authority,
+                        metadata_address,
+                    },
+                    rest,
+                )
+            }
+            79 => {
+                let (metadata_address, rest) = Self::unpack_pubkey_option(rest)?;
+                (Self::UpdateMetadataPointer { metadata_address }, rest)
+            }
+            80 => {
+                let (numerator, rest) = Self::unpack_u64(rest)?;
+                let (denominator, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::CreateConverter {
+                        numerator,
+                        denominator,
+                    },
+                    rest,
+                )
+            }
+            81 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::Convert { amount }, rest)
+            }
+            82 => {
+                let (minimum_balance, rest) = Self::unpack_u64(rest)?;
+                let (enforcement_authority, rest) = Self::unpack_pubkey(rest)?;
+                (
+                    Self::SetMinimumBalance {
+                        minimum_balance,
+                        enforcement_authority,
+                    },
+                    rest,
+                )
+            }
+            83 => {
+                let (snapshot_id, rest) = Self::unpack_u64(rest)?;
+                (Self::SnapshotBalance { snapshot_id }, rest)
+            }
+            84 => (Self::CloseSnapshot, rest),
+            85 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (relayer_fee, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::TransferWithRelayerFee {
+                        amount,
+                        relayer_fee,
+                    },
+                    rest,
+                )
+            }
+            86 => {
+                let (nonce, rest) = Self::unpack_u64(rest)?;
+                (Self::VerifyOwnership { nonce }, rest)
+            }
+            87 => {
+                let (max_outflow_per_instruction, rest) = Self::unpack_u64(rest)?;
+                let (timelock_slots, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::SetOutflowLimit {
+                        max_outflow_per_instruction,
+                        timelock_slots,
+                    },
+                    rest,
+                )
+            }
+            88 => {
+                let (minter, rest) = Self::unpack_pubkey(rest)?;
+                let (max_per_window, rest) = Self::unpack_u64(rest)?;
+                let (window_slots, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::AddMinter {
+                        minter,
+                        max_per_window,
+                        window_slots,
+                    },
+                    rest,
+                )
+            }
+            89 => {
+                let (minter, rest) = Self::unpack_pubkey(rest)?;
+                (Self::RemoveMinter { minter }, rest)
+            }
+            18 => {
+                let (&count, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                return Self::unpack_transfer_batch(count as usize, rest);
+            }
+            19 | 20 => {
+                let (name, rest) = Self::unpack_string(rest)?;
+                let (symbol, rest) = Self::unpack_string(rest)?;
+                let (uri, rest) = Self::unpack_string(rest)?;
+                TokenMetadata::check_lengths(&name, &symbol, &uri)?;
+                (
+                    if tag == 19 {
+                        Self::CreateMetadata { name, symbol, uri }
+                    } else {
+                        Self::UpdateMetadata { name, symbol, uri }
+                    },
+                    rest,
+                )
+            }
+            21 => {
+                let (transfer_fee_authority, rest) = Self::unpack_pubkey_option(rest)?;
+                if rest.len() < 2 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let (basis_points, rest) = rest.split_at(2);
+                let transfer_fee_basis_points =
+                    u16::from_le_bytes(basis_points.try_into().unwrap());
+                let (maximum_fee, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::InitializeTransferFeeConfig {
+                        transfer_fee_authority,
+                        transfer_fee_basis_points,
+                        maximum_fee,
+                    },
+                    rest,
+                )
+            }
+            22 => (Self::HarvestWithheldTokens, rest),
+            23 => (Self::WithdrawWithheldTokens, rest),
+            24 => (Self::InitializeNonTransferableMint, rest),
+            25 => {
+                let (lock_until, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::LockAccount {
+                        lock_until: lock_until as i64,
+                    },
+                    rest,
+                )
+            }
+            26 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (expected_amount, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::InitializeEscrow {
+                        amount,
+                        expected_amount,
+                    },
+                    rest,
+                )
+            }
+            27 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (expected_amount, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::Exchange {
+                        amount,
+                        expected_amount,
+                    },
+                    rest,
+                )
+            }
+            28 => (Self::CancelEscrow, rest),
+            29 => {
+                let (pause_authority, rest) = Self::unpack_pubkey(rest)?;
+                (Self::InitializePauseConfig { pause_authority }, rest)
+            }
+            30 => (Self::Pause, rest),
+            31 => (Self::Resume, rest),
+            32 => {
+                let (delegate, rest) = Self::unpack_pubkey(rest)?;
+                (Self::InitializePermanentDelegate { delegate }, rest)
+            }
+            33 => {
+                let (new_owner, rest) = Self::unpack_pubkey(rest)?;
+                (Self::ProposeOwner { new_owner }, rest)
+            }
+            34 => (Self::AcceptOwner, rest),
+            35 => (Self::CancelPendingOwner, rest),
+            36 => (Self::GetAccountBalance, rest),
+            37 => (Self::GetMintSupply, rest),
+            38 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::AmountToUiAmount { amount }, rest)
+            }
+            39 => {
+                let (ui_amount, rest) = Self::unpack_string(rest)?;
+                (Self::UiAmountToAmount { ui_amount }, rest)
+            }
+            40 => (Self::RequireMemo, rest),
+            41 => (Self::UnrequireMemo, rest),
+            42 => {
+                let (program_id, rest) = Self::unpack_pubkey(rest)?;
+                (Self::InitializeTransferHook { program_id }, rest)
+            }
+            43 | 44 => {
+                let (&state, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let state =
+                    AccountState::from_u8(state).ok_or(ProgramError::InvalidInstructionData)?;
+                (
+                    match tag {
+                        43 => Self::InitializeDefaultAccountState { state },
+                        _ => Self::SetDefaultAccountState { state },
+                    },
+                    rest,
+                )
+            }
+            45 => (Self::InitializeImmutableOwner, rest),
+            46 => {
+                let (rate_authority, rest) = Self::unpack_pubkey_option(rest)?;
+                let (rate_bps, rest) = Self::unpack_i16(rest)?;
+                (
+                    Self::InitializeInterestBearingMint {
+                        rate_authority,
+                        rate_bps,
+                    },
+                    rest,
+                )
+            }
+            47 => {
+                let (rate_bps, rest) = Self::unpack_i16(rest)?;
+                (Self::UpdateRate { rate_bps }, rest)
+            }
+            48 => {
+                let (close_authority, rest) = Self::unpack_pubkey_option(rest)?;
+                (Self::InitializeMintCloseAuthority { close_authority }, rest)
+            }
+            49 => (Self::CloseMint, rest),
+            50 => (Self::MigrateAccount, rest),
+            51 => {
+                if rest.len() < 2 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let (basis_points, rest) = rest.split_at(2);
+                let basis_points = u16::from_le_bytes(basis_points.try_into().unwrap());
+                let (destination, rest) = Self::unpack_pubkey(rest)?;
+                (
+                    Self::InitializeRoyalty {
+                        basis_points,
+                        destination,
+                    },
+                    rest,
+                )
+            }
+            52..=54 => {
+                let (key, rest) = Self::unpack_pubkey(rest)?;
+                (
+                    match tag {
+                        52 => Self::InitializeAllowlist { authority: key },
+                        53 => Self::AddToAllowlist { owner: key },
+                        _ => Self::RemoveFromAllowlist { owner: key },
+                    },
+                    rest,
+                )
+            }
+            55 | 56 => {
+                let (min_transfer_amount, rest) = Self::unpack_u64(rest)?;
+                (
+                    if tag == 55 {
+                        Self::InitializeMinTransferAmount {
+                            min_transfer_amount,
+                        }
+                    } else {
+                        Self::UpdateMinTransferAmount {
+                            min_transfer_amount,
+                        }
+                    },
+                    rest,
+                )
+            }
+            57 => (Self::TransferAccountOwnership, rest),
+            58 => {
+                let (merkle_root, rest) = Self::unpack_hash(rest)?;
+                let (total_claimable, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::CreateDistribution {
+                        merkle_root,
+                        total_claimable,
+                    },
+                    rest,
+                )
+            }
+            59 => {
+                let (index, rest) = Self::unpack_u64(rest)?;
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (&count, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                return Self::unpack_claim(index, amount, count as usize, rest);
+            }
+            61 => {
+                let (owner, rest) = Self::unpack_pubkey(rest)?;
+                (Self::InitializeAccount3 { owner }, rest)
+            }
+            tag if tag <= MAX_TAG => return Err(TokenError::UnsupportedInstruction.into()),
+            _ => return Err(ProgramError::InvalidInstructionData),
+        })
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        match self {
+            Self::MintTokens { amount } => {
+                buf.push(0);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::TransferTokens { amount } => {
+                buf.push(1);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::Burn { amount } => {
+                buf.push(2);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::InitializeMint {
+                decimals,
+                mint_authority,
+                freeze_authority,
+                max_supply,
+            } => {
+                buf.push(3);
+                buf.push(*decimals);
+                buf.extend_from_slice(mint_authority.as_ref());
+                Self::pack_pubkey_option(freeze_authority, &mut buf);
+                Self::pack_u64_option(max_supply, &mut buf);
+            }
+            Self::Approve { amount } => {
+                buf.push(4);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::Revoke => buf.push(5),
+            Self::InitializeAccount => buf.push(6),
+            Self::CloseAccount => buf.push(7),
+            Self::FreezeAccount => buf.push(8),
+            Self::ThawAccount => buf.push(9),
+            Self::InitializeAccount2 => buf.push(10),
+            Self::SetAuthority {
+                authority_type,
+                new_authority,
+            } => {
+                buf.push(11);
+                buf.push(authority_type.into_u8());
+                Self::pack_pubkey_option(new_authority, &mut buf);
+            }
+            Self::TransferChecked { amount, decimals } => {
+                buf.push(12);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(*decimals);
+            }
+            Self::MintToChecked { amount, decimals } => {
+                buf.push(13);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(*decimals);
+            }
+            Self::BurnChecked { amount, decimals } => {
+                buf.push(14);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(*decimals);
+            }
+            Self::CreateAssociatedAccount => buf.push(15),
+            Self::SyncNative => buf.push(16),
+            Self::InitializeMultisig { m } => {
+                buf.push(17);
+                buf.push(*m);
+            }
+            Self::TransferBatch { amounts } => {
+                buf.push(18);
+                buf.push(amounts.len() as u8);
+                for amount in amounts {
+                    buf.extend_from_slice(&amount.to_le_bytes());
+                }
+            }
+            Self::CreateMetadata { name, symbol, uri } => {
+                buf.push(19);
+                Self::pack_string(name, &mut buf);
+                Self::pack_string(symbol, &mut buf);
+                Self::pack_string(uri, &mut buf);
+            }
+            Self::UpdateMetadata { name, symbol, uri } => {
+                buf.push(20);
+                Self::pack_string(name, &mut buf);
+                Self::pack_string(symbol, &mut buf);
+                Self::pack_string(uri, &mut buf);
+            }
+            Self::InitializeTransferFeeConfig {
+                transfer_fee_authority,
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => {
+                buf.push(21);
+                Self::pack_pubkey_option(transfer_fee_authority, &mut buf);
+                buf.extend_from_slice(&transfer_fee_basis_points.to_le_bytes());
+                buf.extend_from_slice(&maximum_fee.to_le_bytes());
+            }
+            Self::HarvestWithheldTokens => buf.push(22),
+            Self::WithdrawWithheldTokens => buf.push(23),
+            Self::InitializeNonTransferableMint => buf.push(24),
+            Self::LockAccount { lock_until } => {
+                buf.push(25);
+                buf.extend_from_slice(&lock_until.to_le_bytes());
+            }
+            Self::InitializeEscrow {
+                amount,
+                expected_amount,
+            } => {
+                buf.push(26);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&expected_amount.to_le_bytes());
+            }
+            Self::Exchange {
+                amount,
+                expected_amount,
+            } => {
+                buf.push(27);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&expected_amount.to_le_bytes());
+            }
+            Self::CancelEscrow => buf.push(28),
+            Self::InitializePauseConfig { pause_authority } => {
+                buf.push(29);
+                buf.extend_from_slice(pause_authority.as_ref());
+            }
+            Self::Pause => buf.push(30),
+            Self::Resume => buf.push(31),
+            Self::InitializePermanentDelegate { delegate } => {
+                buf.push(32);
+                buf.extend_from_slice(delegate.as_ref());
+            }
+            Self::ProposeOwner { new_owner } => {
+                buf.push(33);
+                buf.extend_from_slice(new_owner.as_ref());
+            }
+            Self::AcceptOwner => buf.push(34),
+            Self::CancelPendingOwner => buf.push(35),
+            Self::GetAccountBalance => buf.push(36),
+            Self::GetMintSupply => buf.push(37),
+            Self::AmountToUiAmount { amount } => {
+                buf.push(38);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::UiAmountToAmount { ui_amount } => {
+                buf.push(39);
+                Self::pack_string(ui_amount, &mut buf);
+            }
+            Self::RequireMemo => buf.push(40),
+            Self::UnrequireMemo => buf.push(41),
+            Self::InitializeTransferHook { program_id } => {
+                buf.push(42);
+                buf.extend_from_slice(program_id.as_ref());
+            }
+            Self::InitializeDefaultAccountState { state } => {
+                buf.push(43);
+                buf.push(*state as u8);
+            }
+            Self::SetDefaultAccountState { state } => {
+                buf.push(44);
+                buf.push(*state as u8);
+            }
+            Self::InitializeImmutableOwner => buf.push(45),
+            Self::InitializeInterestBearingMint {
+                rate_authority,
+                rate_bps,
+            } => {
+                buf.push(46);
+                Self::pack_pubkey_option(rate_authority, &mut buf);
+                buf.extend_from_slice(&rate_bps.to_le_bytes());
+            }
+            Self::UpdateRate { rate_bps } => {
+                buf.push(47);
+                buf.extend_from_slice(&rate_bps.to_le_bytes());
+            }
+            Self::InitializeMintCloseAuthority { close_authority } => {
+                buf.push(48);
+                Self::pack_pubkey_option(close_authority, &mut buf);
+            }
+            Self::CloseMint => buf.push(49),
+            Self::MigrateAccount => buf.push(50),
+            Self::InitializeRoyalty {
+                basis_points,
+                destination,
+            } => {
+                buf.push(51);
+                buf.extend_from_slice(&basis_points.to_le_bytes());
+                buf.extend_from_slice(destination.as_ref());
+            }
+            Self::InitializeAllowlist { authority } => {
+                buf.push(52);
+                buf.extend_from_slice(authority.as_ref());
+            }
+            Self::AddToAllowlist { owner } => {
+                buf.push(53);
+                buf.extend_from_slice(owner.as_ref());
+            }
+            Self::RemoveFromAllowlist { owner } => {
+                buf.push(54);
+                buf.extend_from_slice(owner.as_ref());
+            }
+            Self::InitializeMinTransferAmount {
+                min_transfer_amount,
+            } => {
+                buf.push(55);
+                buf.extend_from_slice(&min_transfer_amount.to_le_bytes());
+            }
+            Self::UpdateMinTransferAmount {
+                min_transfer_amount,
+            } => {
+                buf.push(56);
+                buf.extend_from_slice(&min_transfer_amount.to_le_bytes());
+            }
+            Self::TransferAccountOwnership => buf.push(57),
+            Self::CreateDistribution {
+                merkle_root,
+                total_claimable,
+            } => {
+                buf.push(58);
+                buf.extend_from_slice(merkle_root);
+                buf.extend_from_slice(&total_claimable.to_le_bytes());
+            }
+            Self::Claim {
+                index,
+                amount,
+                proof,
+            } => {
+                buf.push(59);
+                buf.extend_from_slice(&index.to_le_bytes());
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(proof.len() as u8);
+                for node in proof {
+                    buf.extend_from_slice(node);
+                }
+            }
+            Self::InitializeMint2 {
+                decimals,
+                mint_authority,
+                freeze_authority,
+                max_supply,
+            } => {
+                buf.push(60);
+                buf.push(*decimals);
+                buf.extend_from_slice(mint_authority.as_ref());
+                Self::pack_pubkey_option(freeze_authority, &mut buf);
+                Self::pack_u64_option(max_supply, &mut buf);
+            }
+            Self::InitializeAccount3 { owner } => {
+                buf.push(61);
+                buf.extend_from_slice(owner.as_ref());
+            }
+            Self::InitializeMultisig2 { m } => {
+                buf.push(62);
+                buf.push(*m);
+            }
+            Self::FreezeBatch { count } => {
+                buf.push(63);
+                buf.push(*count);
+            }
+            Self::InitializeMintWithAllocations {
+                decimals,
+                freeze_authority,
+                max_supply,
+                amounts,
+            } => {
+                buf.push(64);
+                buf.push(*decimals);
+                buf.push(amounts.len() as u8);
+                for amount in amounts {
+                    buf.extend_from_slice(&amount.to_le_bytes());
+                }
+                Self::pack_pubkey_option(freeze_authority, &mut buf);
+                Self::pack_u64_option(max_supply, &mut buf);
+            }
+            Self::InitializeActivityTracking => buf.push(65),
+            Self::TransferWithFee { amount, fee } => {
+                buf.push(66);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&fee.to_le_bytes());
+            }
+            Self::CreateTokenAccount { owner, seed } => {
+                buf.push(67);
+                buf.extend_from_slice(owner.as_ref());
+                Self::pack_u64_option(seed, &mut buf);
+            }
+            Self::InitializeTransferCooldown { cooldown_slots } => {
+                buf.push(68);
+                buf.extend_from_slice(&cooldown_slots.to_le_bytes());
+            }
+            Self::InitializeHolderCount => buf.push(69),
+            Self::PermitTransfer => buf.push(70),
+            Self::CreateVesting {
+                total_amount,
+                start_ts,
+                end_ts,
+            } => {
+                buf.push(71);
+                buf.extend_from_slice(&total_amount.to_le_bytes());
+                buf.extend_from_slice(&start_ts.to_le_bytes());
+                buf.extend_from_slice(&end_ts.to_le_bytes());
+            }
+            Self::ClaimVested => buf.push(72),
+            Self::RepairOwner { owner } => {
+                buf.push(73);
+                buf.extend_from_slice(owner.as_ref());
+            }
+            Self::RecoverNested => buf.push(74),
+            Self::TwoPartySwap { amount_a, amount_b } => {
+                buf.push(75);
+                buf.extend_from_slice(&amount_a.to_le_bytes());
+                buf.extend_from_slice(&amount_b.to_le_bytes());
+            }
+            Self::CreateAssociatedAccountIdempotent => buf.push(76),
+            Self::InitializeRecoveryAuthority { recovery_authority } => {
+                buf.push(77);
+                buf.extend_from_slice(recovery_authority.as_ref());
+            }
+            Self::InitializeMetadataPointer {
+                ref authority,
+                ref metadata_address,
+            } => {
+                buf.push(78);
+                Self::pack_pubkey_option(authority, &mut buf);
+                Self::pack_pubkey_option(metadata_address, &mut buf);
+            }
+            Self::UpdateMetadataPointer {
+                ref metadata_address,
+            } => {
+                buf.push(79);
+                Self::pack_pubkey_option(metadata_address, &mut buf);
+            }
+            Self::CreateConverter {
+                numerator,
+                denominator,
+            } => {
+                buf.push(80);
+                buf.extend_from_slice(&numerator.to_le_bytes());
+                buf.extend_from_slice(&denominator.to_le_bytes());
+            }
+            Self::Convert { amount } => {
+                buf.push(81);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::SetMinimumBalance {
+                minimum_balance,
+                enforcement_authority,
+            } => {
+                buf.push(82);
+                buf.extend_from_slice(&minimum_balance.to_le_bytes());
+                buf.extend_from_slice(enforcement_authority.as_ref());
+            }
+            Self::SnapshotBalance { snapshot_id } => {
+                buf.push(83);
+                buf.extend_from_slice(&snapshot_id.to_le_bytes());
+            }
+            Self::CloseSnapshot => buf.push(84),
+            Self::TransferWithRelayerFee {
+                amount,
+                relayer_fee,
+            } => {
+                buf.push(85);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&relayer_fee.to_le_bytes());
+            }
+            Self::VerifyOwnership { nonce } => {
+                buf.push(86);
+                buf.extend_from_slice(&nonce.to_le_bytes());
+            }
+            Self::SetOutflowLimit {
+                max_outflow_per_instruction,
+                timelock_slots,
+            } => {
+                buf.push(87);
+                buf.extend_from_slice(&max_outflow_per_instruction.to_le_bytes());
+                buf.extend_from_slice(&timelock_slots.to_le_bytes());
+            }
+            Self::AddMinter {
+                minter,
+                max_per_window,
+                window_slots,
+            } => {
+                buf.push(88);
+                buf.extend_from_slice(minter.as_ref());
+                buf.extend_from_slice(&max_per_window.to_le_bytes());
+                buf.extend_from_slice(&window_slots.to_le_bytes());
+            }
+            Self::RemoveMinter { minter } => {
+                buf.push(89);
+                buf.extend_from_slice(minter.as_ref());
+            }
+        }
+        buf
+    }
+
+    fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+        if input.len() < 8 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (amount, rest) = input.split_at(8);
+        Ok((u64::from_le_bytes(amount.try_into().unwrap()), rest))
+    }
+
+    #[cfg(feature = "anchor")]
+    fn unpack_u32(input: &[u8]) -> Result<(u32, &[u8]), ProgramError> {
+        if input.len() < 4 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (value, rest) = input.split_at(4);
+        Ok((u32::from_le_bytes(value.try_into().unwrap()), rest))
+    }
+
+    fn unpack_i16(input: &[u8]) -> Result<(i16, &[u8]), ProgramError> {
+        if input.len() < 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (value, rest) = input.split_at(2);
+        Ok((i16::from_le_bytes(value.try_into().unwrap()), rest))
+    }
+
+    fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+        if input.len() < 32 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (key, rest) = input.split_at(32);
+        Ok((Pubkey::new_from_array(key.try_into().unwrap()), rest))
+    }
+
+    fn unpack_hash(input: &[u8]) -> Result<([u8; 32], &[u8]), ProgramError> {
+        if input.len() < 32 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (hash, rest) = input.split_at(32);
+        Ok((hash.try_into().unwrap(), rest))
+    }
+
+    fn unpack_pubkey_option(input: &[u8]) -> Result<(COption<Pubkey>, &[u8]), ProgramError> {
+        match input.split_first() {
+            Some((&0, rest)) => Ok((COption::None, rest)),
+            Some((&1, rest)) => {
+                let (key, rest) = Self::unpack_pubkey(rest)?;
+                Ok((COption::Some(key), rest))
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    fn unpack_u64_option(input: &[u8]) -> Result<(COption<u64>, &[u8]), ProgramError> {
+        match input.split_first() {
+            Some((&0, rest)) => Ok((COption::None, rest)),
+            Some((&1, rest)) => {
+                let (value, rest) = Self::unpack_u64(rest)?;
+                Ok((COption::Some(value), rest))
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    fn pack_u64_option(value: &COption<u64>, buf: &mut Vec<u8>) {
+        match *value {
+            COption::Some(ref value) => {
+                buf.push(1);
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+            COption::None => buf.push(0),
+        }
+    }
+
+    fn unpack_transfer_batch(count: usize, mut rest: &[u8]) -> Result<(Self, &[u8]), ProgramError> {
+        if count == 0 || count > MAX_TRANSFER_BATCH {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let mut amounts = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (amount, next) = Self::unpack_u64(rest)?;
+            amounts.push(amount);
+            rest = next;
+        }
+        Ok((Self::TransferBatch { amounts }, rest))
+    }
+
+    /// Unpacks the `InitializeMintWithAllocations` arguments that follow
+    /// its decimals and count.
+    fn unpack_allocations(
+        decimals: u8,
+        count: usize,
+        mut rest: &[u8],
+    ) -> Result<(Self, &[u8]), ProgramError> {
+        if count == 0 || count > MAX_ALLOCATIONS {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let mut amounts = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (amount, next) = Self::unpack_u64(rest)?;
+            amounts.push(amount);
+            rest = next;
+        }
+        let (freeze_authority, rest) = Self::unpack_pubkey_option(rest)?;
+        let (max_supply, rest) = Self::unpack_u64_option(rest)?;
+        Ok((
+            Self::InitializeMintWithAllocations {
+                decimals,
+                freeze_authority,
+                max_supply,
+                amounts,
+            },
+            rest,
+        ))
+    }
+
+    fn unpack_claim(
+        index: u64,
+        amount: u64,
+        count: usize,
+        mut rest: &[u8],
+    ) -> Result<(Self, &[u8]), ProgramError> {
+        if count > MAX_CLAIM_PROOF_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let mut proof = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (node, next) = Self::unpack_hash(rest)?;
+            proof.push(node);
+            rest = next;
+        }
+        Ok((
+            Self::Claim {
+                index,
+                amount,
+                proof,
+            },
+            rest,
+        ))
+    }
+
+    fn unpack_string(input: &[u8]) -> Result<(String, &[u8]), ProgramError> {
+        if input.len() < 4 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (len, rest) = input.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+        if rest.len() < len {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (bytes, rest) = rest.split_at(len);
+        let value =
+            String::from_utf8(bytes.to_vec()).map_err(|_| ProgramError::InvalidInstructionData)?;
+        Ok((value, rest))
+    }
+
+    fn pack_string(value: &str, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn pack_pubkey_option(value: &COption<Pubkey>, buf: &mut Vec<u8>) {
+        match *value {
+            COption::Some(ref key) => {
+                buf.push(1);
+                buf.extend_from_slice(key.as_ref());
+            }
+            COption::None => buf.push(0),
+        }
+    }
+}
+
+/// With the `borsh` feature, instructions (de)serialize to exactly the bytes
+/// of [`TokenInstruction::pack`] and [`TokenInstruction::unpack`]. Since
+/// instruction data has no length prefix, deserializing consumes the whole
+/// reader.
+#[cfg(feature = "borsh")]
+impl borsh::BorshSerialize for TokenInstruction {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        writer.write_all(&self.pack())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for TokenInstruction {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let mut input = Vec::new();
+        reader.read_to_end(&mut input)?;
+        Self::unpack(&input).map_err(|error| {
+            borsh::io::Error::new(borsh::io::ErrorKind::InvalidData, error.to_string())
+        })
+    }
+}
+
+/// Creates an `InitializeMint` instruction.
+pub fn initialize_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    freeze_authority_pubkey: Option<&Pubkey>,
+    decimals: u8,
+    max_supply: Option<u64>,
+) -> Result<Instruction, ProgramError> {
+    let freeze_authority = freeze_authority_pubkey.cloned().into();
+    let data = TokenInstruction::InitializeMint {
+        decimals,
+        mint_authority: *mint_authority_pubkey,
+        freeze_authority,
+        max_supply: max_supply.into(),
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeMint2` instruction.
+pub fn initialize_mint2(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    freeze_authority_pubkey: Option<&Pubkey>,
+    decimals: u8,
+    max_supply: Option<u64>,
+) -> Result<Instruction, ProgramError> {
+    let freeze_authority = freeze_authority_pubkey.cloned().into();
+    let data = TokenInstruction::InitializeMint2 {
+        decimals,
+        mint_authority: *mint_authority_pubkey,
+        freeze_authority,
+        max_supply: max_supply.into(),
+    }
+    .pack();
+
+    let accounts = vec![AccountMeta::new(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeMintWithAllocations` instruction crediting each
+/// wallet of `allocations` its amount, in its associated token account.
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_mint_with_allocations(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+    freeze_authority_pubkey: Option<&Pubkey>,
+    decimals: u8,
+    max_supply: Option<u64>,
+    allocations: &[(&Pubkey, u64)],
+) -> Result<Instruction, ProgramError> {
+    if allocations.is_empty() || allocations.len() > MAX_ALLOCATIONS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let data = TokenInstruction::InitializeMintWithAllocations {
+        decimals,
+        freeze_authority: freeze_authority_pubkey.cloned().into(),
+        max_supply: max_supply.into(),
+        amounts: allocations.iter().map(|(_, amount)| *amount).collect(),
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*mint_authority_pubkey, true),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for (wallet_pubkey, _) in allocations {
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(wallet_pubkey, mint_pubkey, token_program_id),
+            false,
+        ));
+        accounts.push(AccountMeta::new_readonly(**wallet_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeAccount` instruction.
+pub fn initialize_account(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeAccount.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeAccount2` instruction.
+pub fn initialize_account2(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeAccount2.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeAccount3` instruction.
+pub fn initialize_account3(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeAccount3 {
+        owner: *owner_pubkey,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `MintTokens` instruction.
+pub fn mint_tokens(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::MintTokens { amount }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*mint_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `MintToChecked` instruction.
+pub fn mint_to_checked(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::MintToChecked { amount, decimals }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*mint_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `TransferTokens` instruction.
+pub fn transfer_tokens(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::TransferTokens { amount }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `TransferTokens` instruction from a source account owned by
+/// the PDA of `authority_program_id` at `seeds`, returning it with the PDA's
+/// bump seed. Only `authority_program_id` can sign it, with `invoke_signed`
+/// and `seeds` followed by the bump seed; see [`crate::cpi::transfer_with_seed`].
+pub fn transfer_with_seed(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_program_id: &Pubkey,
+    seeds: &[&[u8]],
+    amount: u64,
+) -> Result<(Instruction, u8), ProgramError> {
+    let (authority_pubkey, bump_seed) = Pubkey::find_program_address(seeds, authority_program_id);
+    let instruction = transfer_tokens(
+        token_program_id,
+        source_pubkey,
+        destination_pubkey,
+        &authority_pubkey,
+        &[],
+        amount,
+    )?;
+    Ok((instruction, bump_seed))
+}
+
+/// Creates a `TransferChecked` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::TransferChecked { amount, decimals }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `Burn` instruction.
+pub fn burn(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::Burn { amount }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `BurnChecked` instruction.
+pub fn burn_checked(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::BurnChecked { amount, decimals }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `Approve` instruction.
+pub fn approve(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    delegate_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::Approve { amount }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new_readonly(*delegate_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `Revoke` instruction.
+pub fn revoke(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::Revoke.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CloseAccount` instruction.
+pub fn close_account(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::CloseAccount.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `FreezeAccount` instruction.
+pub fn freeze_account(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    freeze_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::FreezeAccount.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(*freeze_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `ThawAccount` instruction.
+pub fn thaw_account(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    freeze_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::ThawAccount.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(*freeze_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `FreezeBatch` instruction.
+pub fn freeze_batch(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    freeze_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    account_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    if account_pubkeys.is_empty() || account_pubkeys.len() > MAX_FREEZE_BATCH {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let data = TokenInstruction::FreezeBatch {
+        count: account_pubkeys.len() as u8,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(*freeze_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for account_pubkey in account_pubkeys.iter() {
+        accounts.push(AccountMeta::new(**account_pubkey, false));
+    }
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `SetAuthority` instruction.
+pub fn set_authority(
+    token_program_id: &Pubkey,
+    owned_pubkey: &Pubkey,
+    new_authority_pubkey: Option<&Pubkey>,
+    authority_type: AuthorityType,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let new_authority = new_authority_pubkey.cloned().into();
+    let data = TokenInstruction::SetAuthority {
+        authority_type,
+        new_authority,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*owned_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `SetAuthority` instruction that sets, replaces or, with
+/// `None`, revokes the close authority of a token account, with
+/// `payer_pubkey` to fund the account if it grows.
+pub fn set_close_authority(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    new_close_authority_pubkey: Option<&Pubkey>,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    payer_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = set_authority(
+        token_program_id,
+        account_pubkey,
+        new_close_authority_pubkey,
+        AuthorityType::CloseAccount,
+        owner_pubkey,
+        signer_pubkeys,
+    )?;
+    instruction
+        .accounts
+        .push(AccountMeta::new(*payer_pubkey, true));
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(system_program::id(), false));
+    Ok(instruction)
+}
+
+/// Creates a `CreateAssociatedAccount` instruction.
+pub fn create_associated_account(
+    token_program_id: &Pubkey,
+    funding_pubkey: &Pubkey,
+    wallet_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let associated_pubkey =
+        get_associated_token_address(wallet_pubkey, mint_pubkey, token_program_id);
+    let data = TokenInstruction::CreateAssociatedAccount.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*funding_pubkey, true),
+        AccountMeta::new(associated_pubkey, false),
+        AccountMeta::new_readonly(*wallet_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `SyncNative` instruction.
+pub fn sync_native(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::SyncNative.pack();
+
+    let accounts = vec![AccountMeta::new(*account_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeMultisig` instruction.
+pub fn initialize_multisig(
+    token_program_id: &Pubkey,
+    multisig_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    m: u8,
+) -> Result<Instruction, ProgramError> {
+    if !is_valid_signer_index(m as usize)
+        || !is_valid_signer_index(signer_pubkeys.len())
+        || m as usize > signer_pubkeys.len()
+    {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let data = TokenInstruction::InitializeMultisig { m }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*multisig_pubkey, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeMultisig2` instruction.
+pub fn initialize_multisig2(
+    token_program_id: &Pubkey,
+    multisig_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    m: u8,
+) -> Result<Instruction, ProgramError> {
+    if !is_valid_signer_index(m as usize)
+        || !is_valid_signer_index(signer_pubkeys.len())
+        || m as usize > signer_pubkeys.len()
+    {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let data = TokenInstruction::InitializeMultisig2 { m }.pack();
+
+    let mut accounts = vec![AccountMeta::new(*multisig_pubkey, false)];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `TransferBatch` instruction.
+pub fn transfer_batch(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    transfers: &[(&Pubkey, u64)],
+) -> Result<Instruction, ProgramError> {
+    if transfers.is_empty() || transfers.len() > MAX_TRANSFER_BATCH {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let amounts = transfers.iter().map(|(_, amount)| *amount).collect();
+    let data = TokenInstruction::TransferBatch { amounts }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for (destination_pubkey, _) in transfers.iter() {
+        accounts.push(AccountMeta::new(**destination_pubkey, false));
+    }
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `TransferWithFee` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_with_fee(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    fee_account_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+    fee: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::TransferWithFee { amount, fee }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*fee_account_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CreateMetadata` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn create_metadata(
+    token_program_id: &Pubkey,
+    funding_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    update_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Result<Instruction, ProgramError> {
+    TokenMetadata::check_lengths(name, symbol, uri)?;
+    let metadata_pubkey = get_metadata_address(mint_pubkey, token_program_id);
+    let data = TokenInstruction::CreateMetadata {
+        name: name.to_string(),
+        symbol: symbol.to_string(),
+        uri: uri.to_string(),
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*funding_pubkey, true),
+        AccountMeta::new(metadata_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(*mint_authority_pubkey, signer_pubkeys.is_empty()),
+        AccountMeta::new_readonly(*update_authority_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `UpdateMetadata` instruction.
+pub fn update_metadata(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    update_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Result<Instruction, ProgramError> {
+    TokenMetadata::check_lengths(name, symbol, uri)?;
+    let metadata_pubkey = get_metadata_address(mint_pubkey, token_program_id);
+    let data = TokenInstruction::UpdateMetadata {
+        name: name.to_string(),
+        symbol: symbol.to_string(),
+        uri: uri.to_string(),
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(metadata_pubkey, false),
+        AccountMeta::new_readonly(*update_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeTransferFeeConfig` instruction.
+pub fn initialize_transfer_fee_config(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    transfer_fee_authority_pubkey: Option<&Pubkey>,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeTransferFeeConfig {
+        transfer_fee_authority: transfer_fee_authority_pubkey.cloned().into(),
+        transfer_fee_basis_points,
+        maximum_fee,
+    }
+    .pack();
+
+    let accounts = vec![AccountMeta::new(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `HarvestWithheldTokens` instruction.
+pub fn harvest_withheld_tokens(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    source_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::HarvestWithheldTokens.pack();
+
+    let mut accounts = vec![AccountMeta::new(*mint_pubkey, false)];
+    for source_pubkey in source_pubkeys.iter() {
+        accounts.push(AccountMeta::new(**source_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `WithdrawWithheldTokens` instruction.
+pub fn withdraw_withheld_tokens(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::WithdrawWithheldTokens.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeNonTransferableMint` instruction.
+pub fn initialize_non_transferable_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeNonTransferableMint.pack();
+
+    let accounts = vec![AccountMeta::new(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `LockAccount` instruction.
+pub fn lock_account(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    payer_pubkey: &Pubkey,
+    lock_until: i64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::LockAccount { lock_until }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+    accounts.push(AccountMeta::new(*payer_pubkey, true));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeEscrow` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_escrow(
+    token_program_id: &Pubkey,
+    maker_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    deposit_pubkey: &Pubkey,
+    escrow_pubkey: &Pubkey,
+    maker_receive_pubkey: &Pubkey,
+    amount: u64,
+    expected_amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeEscrow {
+        amount,
+        expected_amount,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*maker_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*deposit_pubkey, false),
+        AccountMeta::new(*escrow_pubkey, false),
+        AccountMeta::new_readonly(*maker_receive_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `Exchange` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn exchange(
+    token_program_id: &Pubkey,
+    taker_pubkey: &Pubkey,
+    taker_source_pubkey: &Pubkey,
+    taker_receive_pubkey: &Pubkey,
+    deposit_pubkey: &Pubkey,
+    maker_receive_pubkey: &Pubkey,
+    maker_pubkey: &Pubkey,
+    escrow_pubkey: &Pubkey,
+    amount: u64,
+    expected_amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::Exchange {
+        amount,
+        expected_amount,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*taker_pubkey, true),
+        AccountMeta::new(*taker_source_pubkey, false),
+        AccountMeta::new(*taker_receive_pubkey, false),
+        AccountMeta::new(*deposit_pubkey, false),
+        AccountMeta::new(*maker_receive_pubkey, false),
+        AccountMeta::new(*maker_pubkey, false),
+        AccountMeta::new(*escrow_pubkey, false),
+        AccountMeta::new_readonly(get_escrow_authority(escrow_pubkey, token_program_id), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CancelEscrow` instruction.
+pub fn cancel_escrow(
+    token_program_id: &Pubkey,
+    maker_pubkey: &Pubkey,
+    deposit_pubkey: &Pubkey,
+    refund_pubkey: &Pubkey,
+    escrow_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::CancelEscrow.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*maker_pubkey, true),
+        AccountMeta::new(*deposit_pubkey, false),
+        AccountMeta::new(*refund_pubkey, false),
+        AccountMeta::new(*escrow_pubkey, false),
+        AccountMeta::new_readonly(get_escrow_authority(escrow_pubkey, token_program_id), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CreateDistribution` instruction.
+pub fn create_distribution(
+    token_program_id: &Pubkey,
+    creator_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    vault_pubkey: &Pubkey,
+    distributor_pubkey: &Pubkey,
+    merkle_root: [u8; 32],
+    total_claimable: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::CreateDistribution {
+        merkle_root,
+        total_claimable,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*creator_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*vault_pubkey, false),
+        AccountMeta::new(*distributor_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `Claim` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn claim(
+    token_program_id: &Pubkey,
+    claimant_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    distributor_pubkey: &Pubkey,
+    vault_pubkey: &Pubkey,
+    index: u64,
+    amount: u64,
+    proof: &[[u8; 32]],
+) -> Result<Instruction, ProgramError> {
+    if proof.len() > MAX_CLAIM_PROOF_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let data = TokenInstruction::Claim {
+        index,
+        amount,
+        proof: proof.to_vec(),
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*claimant_pubkey, true),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*distributor_pubkey, false),
+        AccountMeta::new(*vault_pubkey, false),
+        AccountMeta::new_readonly(
+            get_distributor_authority(distributor_pubkey, token_program_id),
+            false,
+        ),
+        AccountMeta::new(
+            get_claim_address(distributor_pubkey, index, token_program_id),
+            false,
+        ),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializePauseConfig` instruction.
+pub fn initialize_pause_config(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    pause_authority_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializePauseConfig {
+        pause_authority: *pause_authority_pubkey,
+    }
+    .pack();
+
+    let accounts = vec![AccountMeta::new(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `Pause` instruction.
+pub fn pause(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    pause_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::Pause.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*pause_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `Resume` instruction.
+pub fn resume(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    pause_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::Resume.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*pause_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializePermanentDelegate` instruction.
+pub fn initialize_permanent_delegate(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    delegate_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializePermanentDelegate {
+        delegate: *delegate_pubkey,
+    }
+    .pack();
+
+    let accounts = vec![AccountMeta::new(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeTransferHook` instruction.
+pub fn initialize_transfer_hook(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    hook_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeTransferHook {
+        program_id: *hook_program_id,
+    }
+    .pack();
+
+    let accounts = vec![AccountMeta::new(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeDefaultAccountState` instruction.
+pub fn initialize_default_account_state(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    state: AccountState,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeDefaultAccountState { state }.pack();
+
+    let accounts = vec![AccountMeta::new(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `SetDefaultAccountState` instruction.
+pub fn set_default_account_state(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    freeze_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    state: AccountState,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::SetDefaultAccountState { state }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*freeze_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeImmutableOwner` instruction.
+pub fn initialize_immutable_owner(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeImmutableOwner.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeInterestBearingMint` instruction.
+pub fn initialize_interest_bearing_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    rate_authority_pubkey: Option<&Pubkey>,
+    rate_bps: i16,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeInterestBearingMint {
+        rate_authority: rate_authority_pubkey.cloned().into(),
+        rate_bps,
+    }
+    .pack();
+
+    let accounts = vec![AccountMeta::new(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `UpdateRate` instruction.
+pub fn update_rate(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    rate_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    rate_bps: i16,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::UpdateRate { rate_bps }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*rate_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeMintCloseAuthority` instruction.
+pub fn initialize_mint_close_authority(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    close_authority_pubkey: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeMintCloseAuthority {
+        close_authority: close_authority_pubkey.cloned().into(),
+    }
+    .pack();
+
+    let accounts = vec![AccountMeta::new(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CloseMint` instruction.
+pub fn close_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    close_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::CloseMint.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*close_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `MigrateAccount` instruction.
+pub fn migrate_account(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::MigrateAccount.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeRoyalty` instruction.
+pub fn initialize_royalty(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+    basis_points: u16,
+    destination_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeRoyalty {
+        basis_points,
+        destination: *destination_pubkey,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeAllowlist` instruction for the allowlist account
+/// of `mint_pubkey`.
+pub fn initialize_allowlist(
+    token_program_id: &Pubkey,
+    funding_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeAllowlist {
+        authority: *authority_pubkey,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*funding_pubkey, true),
+        AccountMeta::new(get_allowlist_address(mint_pubkey, token_program_id), false),
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `AddToAllowlist` instruction for the allowlist account of
+/// `mint_pubkey`.
+pub fn add_to_allowlist(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    payer_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::AddToAllowlist {
+        owner: *owner_pubkey,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(get_allowlist_address(mint_pubkey, token_program_id), false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+    accounts.push(AccountMeta::new(*payer_pubkey, true));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `RemoveFromAllowlist` instruction for the allowlist account of
+/// `mint_pubkey`.
+pub fn remove_from_allowlist(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    owner_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::RemoveFromAllowlist {
+        owner: *owner_pubkey,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(get_allowlist_address(mint_pubkey, token_program_id), false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeMinTransferAmount` instruction.
+pub fn initialize_min_transfer_amount(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+    min_transfer_amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeMinTransferAmount {
+        min_transfer_amount,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `UpdateMinTransferAmount` instruction.
+pub fn update_min_transfer_amount(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    min_transfer_amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::UpdateMinTransferAmount {
+        min_transfer_amount,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*mint_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeActivityTracking` instruction.
+pub fn initialize_activity_tracking(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeActivityTracking.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `ProposeOwner` instruction.
+pub fn propose_owner(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    new_owner_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::ProposeOwner {
+        new_owner: *new_owner_pubkey,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `AcceptOwner` instruction.
+pub fn accept_owner(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    pending_owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::AcceptOwner.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*pending_owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CancelPendingOwner` instruction.
+pub fn cancel_pending_owner(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::CancelPendingOwner.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `TransferAccountOwnership` instruction.
+pub fn transfer_account_ownership(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    new_owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::TransferAccountOwnership.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+        AccountMeta::new_readonly(*new_owner_pubkey, true),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `RequireMemo` instruction.
+pub fn require_memo(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    payer_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::RequireMemo.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+    accounts.push(AccountMeta::new(*payer_pubkey, true));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `UnrequireMemo` instruction.
+pub fn unrequire_memo(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::UnrequireMemo.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `GetAccountBalance` instruction.
+pub fn get_account_balance(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::GetAccountBalance.pack();
+
+    let accounts = vec![AccountMeta::new_readonly(*account_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `GetMintSupply` instruction.
+pub fn get_mint_supply(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::GetMintSupply.pack();
+
+    let accounts = vec![AccountMeta::new_readonly(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `AmountToUiAmount` instruction.
+pub fn amount_to_ui_amount(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::AmountToUiAmount { amount }.pack();
+
+    let accounts = vec![AccountMeta::new_readonly(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `UiAmountToAmount` instruction.
+pub fn ui_amount_to_amount(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    ui_amount: &str,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::UiAmountToAmount {
+        ui_amount: ui_amount.to_string(),
+    }
+    .pack();
+
+    let accounts = vec![AccountMeta::new_readonly(*mint_pubkey, false)];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CreateTokenAccount` instruction for a keypair address, which
+/// must sign the transaction.
+pub fn create_token_account(
+    token_program_id: &Pubkey,
+    funding_pubkey: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::CreateTokenAccount {
+        owner: *owner_pubkey,
+        seed: COption::None,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*funding_pubkey, true),
+        AccountMeta::new(*account_pubkey, true),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CreateTokenAccount` instruction for the address returned by
+/// `get_seeded_token_address`.
+pub fn create_seeded_token_account(
+    token_program_id: &Pubkey,
+    funding_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    seed: u64,
+) -> Result<Instruction, ProgramError> {
+    let account_pubkey =
+        get_seeded_token_address(owner_pubkey, mint_pubkey, seed, token_program_id);
+    let data = TokenInstruction::CreateTokenAccount {
+        owner: *owner_pubkey,
+        seed: COption::Some(seed),
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*funding_pubkey, true),
+        AccountMeta::new(account_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeTransferCooldown` instruction.
+pub fn initialize_transfer_cooldown(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+    cooldown_slots: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeTransferCooldown { cooldown_slots }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeHolderCount` instruction.
+pub fn initialize_holder_count(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeHolderCount.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `PermitTransfer` instruction, to follow the
+/// `permit::new_ed25519_instruction` of its permit.
+pub fn permit_transfer(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::PermitTransfer.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CreateVesting` instruction for the vesting schedule of
+/// `beneficiary_pubkey`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_vesting(
+    token_program_id: &Pubkey,
+    funding_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    beneficiary_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    total_amount: u64,
+    start_ts: i64,
+    end_ts: i64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::CreateVesting {
+        total_amount,
+        start_ts,
+        end_ts,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*funding_pubkey, true),
+        AccountMeta::new(
+            get_vesting_address(mint_pubkey, beneficiary_pubkey, token_program_id),
+            false,
+        ),
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*beneficiary_pubkey, false),
+        AccountMeta::new_readonly(*mint_authority_pubkey, signer_pubkeys.is_empty()),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `ClaimVested` instruction for the vesting schedule of
+/// `beneficiary_pubkey`.
+pub fn claim_vested(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    beneficiary_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::ClaimVested.pack();
+
+    let accounts = vec![
+        AccountMeta::new(
+            get_vesting_address(mint_pubkey, beneficiary_pubkey, token_program_id),
+            false,
+        ),
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*beneficiary_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `RepairOwner` instruction.
+pub fn repair_owner(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    owner_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::RepairOwner {
+        owner: *owner_pubkey,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(*mint_authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `RecoverNested` instruction, deriving every address from the
+/// wallet and the two mints.
+pub fn recover_nested(
+    token_program_id: &Pubkey,
+    wallet_pubkey: &Pubkey,
+    owner_mint_pubkey: &Pubkey,
+    nested_mint_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let owner_associated_pubkey =
+        get_associated_token_address(wallet_pubkey, owner_mint_pubkey, token_program_id);
+    let nested_pubkey = get_associated_token_address(
+        &owner_associated_pubkey,
+        nested_mint_pubkey,
+        token_program_id,
+    );
+    let destination_pubkey =
+        get_associated_token_address(wallet_pubkey, nested_mint_pubkey, token_program_id);
+    let data = TokenInstruction::RecoverNested.pack();
+
+    let accounts = vec![
+        AccountMeta::new(nested_pubkey, false),
+        AccountMeta::new_readonly(*nested_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(owner_associated_pubkey, false),
+        AccountMeta::new_readonly(*owner_mint_pubkey, false),
+        AccountMeta::new(*wallet_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `TwoPartySwap` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn two_party_swap(
+    token_program_id: &Pubkey,
+    source_a_pubkey: &Pubkey,
+    destination_a_pubkey: &Pubkey,
+    owner_a_pubkey: &Pubkey,
+    source_b_pubkey: &Pubkey,
+    destination_b_pubkey: &Pubkey,
+    owner_b_pubkey: &Pubkey,
+    amount_a: u64,
+    amount_b: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::TwoPartySwap { amount_a, amount_b }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*source_a_pubkey, false),
+        AccountMeta::new(*destination_a_pubkey, false),
+        AccountMeta::new_readonly(*owner_a_pubkey, true),
+        AccountMeta::new(*source_b_pubkey, false),
+        AccountMeta::new(*destination_b_pubkey, false),
+        AccountMeta::new_readonly(*owner_b_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CreateAssociatedAccountIdempotent` instruction.
+pub fn create_associated_account_idempotent(
+    token_program_id: &Pubkey,
+    funding_pubkey: &Pubkey,
+    wallet_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction =
+        create_associated_account(token_program_id, funding_pubkey, wallet_pubkey, mint_pubkey)?;
+    instruction.data = TokenInstruction::CreateAssociatedAccountIdempotent.pack();
+    Ok(instruction)
+}
+
+/// Creates an `InitializeRecoveryAuthority` instruction.
+pub fn initialize_recovery_authority(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+    recovery_authority_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeRecoveryAuthority {
+        recovery_authority: *recovery_authority_pubkey,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeMetadataPointer` instruction.
+pub fn initialize_metadata_pointer(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+    authority_pubkey: Option<&Pubkey>,
+    metadata_address: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::InitializeMetadataPointer {
+        authority: authority_pubkey.cloned().into(),
+        metadata_address: metadata_address.cloned().into(),
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `UpdateMetadataPointer` instruction.
+pub fn update_metadata_pointer(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    metadata_address: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::UpdateMetadataPointer {
+        metadata_address: metadata_address.cloned().into(),
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CreateConverter` instruction.
+pub fn create_converter(
+    token_program_id: &Pubkey,
+    converter_pubkey: &Pubkey,
+    from_mint_pubkey: &Pubkey,
+    to_mint_pubkey: &Pubkey,
+    numerator: u64,
+    denominator: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::CreateConverter {
+        numerator,
+        denominator,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*converter_pubkey, false),
+        AccountMeta::new_readonly(*from_mint_pubkey, false),
+        AccountMeta::new_readonly(*to_mint_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `Convert` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn convert(
+    token_program_id: &Pubkey,
+    owner_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    from_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    to_mint_pubkey: &Pubkey,
+    converter_pubkey: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::Convert { amount }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*owner_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*from_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*to_mint_pubkey, false),
+        AccountMeta::new_readonly(*converter_pubkey, false),
+        AccountMeta::new_readonly(
+            get_converter_authority(converter_pubkey, token_program_id),
+            false,
+        ),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `SetMinimumBalance` instruction, signed by
+/// `current_authority_pubkey` too when the change needs it.
+#[allow(clippy::too_many_arguments)]
+pub fn set_minimum_balance(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    payer_pubkey: &Pubkey,
+    minimum_balance: u64,
+    enforcement_authority_pubkey: &Pubkey,
+    current_authority_pubkey: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::SetMinimumBalance {
+        minimum_balance,
+        enforcement_authority: *enforcement_authority_pubkey,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+    if let Some(current_authority_pubkey) = current_authority_pubkey {
+        accounts.push(AccountMeta::new_readonly(*current_authority_pubkey, true));
+    }
+    accounts.push(AccountMeta::new(*payer_pubkey, true));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `SnapshotBalance` instruction.
+pub fn snapshot_balance(
+    token_program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    account_pubkey: &Pubkey,
+    snapshot_id: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::SnapshotBalance { snapshot_id }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new(
+            get_snapshot_address(account_pubkey, snapshot_id, token_program_id),
+            false,
+        ),
+        AccountMeta::new_readonly(*account_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CloseSnapshot` instruction.
+pub fn close_snapshot(
+    token_program_id: &Pubkey,
+    snapshot_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::CloseSnapshot.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*snapshot_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*payer_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `TransferWithRelayerFee` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_with_relayer_fee(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    relayer_account_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+    relayer_fee: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::TransferWithRelayerFee {
+        amount,
+        relayer_fee,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*relayer_account_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `VerifyOwnership` instruction.
+pub fn verify_ownership(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    nonce: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::VerifyOwnership { nonce }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*account_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `SetOutflowLimit` instruction.
+pub fn set_outflow_limit(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    payer_pubkey: &Pubkey,
+    max_outflow_per_instruction: u64,
+    timelock_slots: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::SetOutflowLimit {
+        max_outflow_per_instruction,
+        timelock_slots,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*account_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+    accounts.push(AccountMeta::new(*payer_pubkey, true));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `AddMinter` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn add_minter(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    payer_pubkey: &Pubkey,
+    minter_pubkey: &Pubkey,
+    max_per_window: u64,
+    window_slots: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::AddMinter {
+        minter: *minter_pubkey,
+        max_per_window,
+        window_slots,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+    accounts.push(AccountMeta::new(*payer_pubkey, true));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `RemoveMinter` instruction.
+pub fn remove_minter(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    minter_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = TokenInstruction::RemoveMinter {
+        minter: *minter_pubkey,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*mint_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, signer_pubkeys.is_empty()),
+    ];
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Decodes the amount written by `GetAccountBalance`, `GetMintSupply` or
+/// `UiAmountToAmount`.
+pub fn decode_returned_amount(data: &[u8]) -> Result<u64, ProgramError> {
+    let amount = data
+        .try_into()
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok(u64::from_le_bytes(amount))
+}
+
+/// Reads the amount returned by a `GetAccountBalance` or `GetMintSupply`
+/// just invoked through CPI, checking that it came from `token_program_id`.
+pub fn get_returned_amount(token_program_id: &Pubkey) -> Result<u64, ProgramError> {
+    match get_return_data() {
+        Some((program_id, data)) if program_id == *token_program_id => {
+            decode_returned_amount(&data)
+        }
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// Utility function that checks index is between `MIN_SIGNERS` and `MAX_SIGNERS`.
+pub fn is_valid_signer_index(index: usize) -> bool {
+    (MIN_SIGNERS..=MAX_SIGNERS).contains(&index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::{account_state, coption, pubkey};
+    use proptest::{collection::vec, prelude::*};
+
+    fn authority_type() -> impl Strategy<Value = AuthorityType> {
+        prop_oneof![
+            Just(AuthorityType::MintTokens),
+            Just(AuthorityType::FreezeAccount),
+            Just(AuthorityType::AccountOwner),
+            Just(AuthorityType::TransferFeeConfig),
+            Just(AuthorityType::CloseMint),
+            Just(AuthorityType::CloseAccount),
+        ]
+    }
+
+    fn metadata_fields() -> impl Strategy<Value = (String, String, String)> {
+        (".{0,8}", "[A-Z]{0,10}", "[ -~]{0,200}")
+    }
+
+    fn instruction() -> impl Strategy<Value = TokenInstruction> {
+        use TokenInstruction::*;
+        prop_oneof![
+            any::<u64>().prop_map(|amount| MintTokens { amount }),
+            any::<u64>().prop_map(|amount| TransferTokens { amount }),
+            any::<u64>().prop_map(|amount| Burn { amount }),
+            (
+                any::<u8>(),
+                pubkey(),
+                coption(pubkey()),
+                coption(any::<u64>())
+            )
+                .prop_map(
+                    |(decimals, mint_authority, freeze_authority, max_supply)| {
+                        InitializeMint {
+                            decimals,
+                            mint_authority,
+                            freeze_authority,
+                            max_supply,
+                        }
+                    }
+                ),
+            any::<u64>().prop_map(|amount| Approve { amount }),
+            Just(Revoke),
+            Just(InitializeAccount),
+            Just(CloseAccount),
+            Just(FreezeAccount),
+            Just(ThawAccount),
+            Just(InitializeAccount2),
+            (authority_type(), coption(pubkey())).prop_map(|(authority_type, new_authority)| {
+                SetAuthority {
+                    authority_type,
+                    new_authority,
+                }
+            }),
+            (any::<u64>(), any::<u8>())
+                .prop_map(|(amount, decimals)| TransferChecked { amount, decimals }),
+            (any::<u64>(), any::<u8>())
+                .prop_map(|(amount, decimals)| MintToChecked { amount, decimals }),
+            (any::<u64>(), any::<u8>())
+                .prop_map(|(amount, decimals)| BurnChecked { amount, decimals }),
+            Just(CreateAssociatedAccount),
+            Just(SyncNative),
+            any::<u8>().prop_map(|m| InitializeMultisig { m }),
+            vec(any::<u64>(), 1..=MAX_TRANSFER_BATCH).prop_map(|amounts| TransferBatch { amounts }),
+            metadata_fields().prop_map(|(name, symbol, uri)| CreateMetadata { name, symbol, uri }),
+            metadata_fields().prop_map(|(name, symbol, uri)| UpdateMetadata { name, symbol, uri }),
+            (coption(pubkey()), any::<u16>(), any::<u64>()).prop_map(
+                |(transfer_fee_authority, transfer_fee_basis_points, maximum_fee)| {
+                    InitializeTransferFeeConfig {
+                        transfer_fee_authority,
+                        transfer_fee_basis_points,
+                        maximum_fee,
+                    }
+                }
+            ),
+            Just(HarvestWithheldTokens),
+            Just(WithdrawWithheldTokens),
+            Just(InitializeNonTransferableMint),
+            any::<i64>().prop_map(|lock_until| LockAccount { lock_until }),
+            (any::<u64>(), any::<u64>()).prop_map(|(amount, expected_amount)| InitializeEscrow {
+                amount,
+                expected_amount,
+            }),
+            (any::<u64>(), any::<u64>()).prop_map(|(amount, expected_amount)| Exchange {
+                amount,
+                expected_amount,
+            }),
+            Just(CancelEscrow),
+            pubkey().prop_map(|pause_authority| InitializePauseConfig { pause_authority }),
+            Just(Pause),
+            Just(Resume),
+            pubkey().prop_map(|delegate| InitializePermanentDelegate { delegate }),
+            pubkey().prop_map(|new_owner| ProposeOwner { new_owner }),
+            Just(AcceptOwner),
+            Just(CancelPendingOwner),
+            Just(GetAccountBalance),
+            Just(GetMintSupply),
+            any::<u64>().prop_map(|amount| AmountToUiAmount { amount }),
+            any::<String>().prop_map(|ui_amount| UiAmountToAmount { ui_amount }),
+            Just(RequireMemo),
+            Just(UnrequireMemo),
+            pubkey().prop_map(|program_id| InitializeTransferHook { program_id }),
+            account_state().prop_map(|state| InitializeDefaultAccountState { state }),
+            account_state().prop_map(|state| SetDefaultAccountState { state }),
+            Just(InitializeImmutableOwner),
+            (coption(pubkey()), any::<i16>()).prop_map(|(rate_authority, rate_bps)| {
+                InitializeInterestBearingMint {
+                    rate_authority,
+                    rate_bps,
+                }
+            }),
+            any::<i16>().prop_map(|rate_bps| UpdateRate { rate_bps }),
+            coption(pubkey())
+                .prop_map(|close_authority| InitializeMintCloseAuthority { close_authority }),
+            Just(CloseMint),
+            Just(MigrateAccount),
+            (any::<u16>(), pubkey()).prop_map(|(basis_points, destination)| InitializeRoyalty {
+                basis_points,
+                destination,
+            }),
+            pubkey().prop_map(|authority| InitializeAllowlist { authority }),
+            pubkey().prop_map(|owner| AddToAllowlist { owner }),
+            pubkey().prop_map(|owner| RemoveFromAllowlist { owner }),
+            any::<u64>().prop_map(|min_transfer_amount| InitializeMinTransferAmount {
+                min_transfer_amount
+            }),
+            any::<u64>().prop_map(|min_transfer_amount| UpdateMinTransferAmount {
+                min_transfer_amount
+            }),
+            Just(TransferAccountOwnership),
+            (any::<[u8; 32]>(), any::<u64>()).prop_map(|(merkle_root, total_claimable)| {
+                CreateDistribution {
+                    merkle_root,
+                    total_claimable,
+                }
+            }),
+            (
+                any::<u64>(),
+                any::<u64>(),
+                vec(any::<[u8; 32]>(), 0..=MAX_CLAIM_PROOF_LEN)
+            )
+                .prop_map(|(index, amount, proof)| Claim {
+                    index,
+                    amount,
+                    proof
+                }),
+            (
+                any::<u8>(),
+                pubkey(),
+                coption(pubkey()),
+                coption(any::<u64>())
+            )
+                .prop_map(
+                    |(decimals, mint_authority, freeze_authority, max_supply)| {
+                        InitializeMint2 {
+                            decimals,
+                            mint_authority,
+                            freeze_authority,
+                            max_supply,
+                        }
+                    }
+                ),
+            pubkey().prop_map(|owner| InitializeAccount3 { owner }),
+            any::<u8>().prop_map(|m| InitializeMultisig2 { m }),
+            (1..=MAX_FREEZE_BATCH as u8).prop_map(|count| FreezeBatch { count }),
+            (
+                any::<u8>(),
+                coption(pubkey()),
+                coption(any::<u64>()),
+                vec(any::<u64>(), 1..=MAX_ALLOCATIONS)
+            )
+                .prop_map(|(decimals, freeze_authority, max_supply, amounts)| {
+                    InitializeMintWithAllocations {
+                        decimals,
+                        freeze_authority,
+                        max_supply,
+                        amounts,
+                    }
+                }),
+            Just(InitializeActivityTracking),
+            (any::<u64>(), any::<u64>()).prop_map(|(amount, fee)| TransferWithFee { amount, fee }),
+            (pubkey(), coption(any::<u64>()))
+                .prop_map(|(owner, seed)| CreateTokenAccount { owner, seed }),
+            any::<u64>().prop_map(|cooldown_slots| InitializeTransferCooldown { cooldown_slots }),
+            Just(InitializeHolderCount),
+            Just(PermitTransfer),
+            (any::<u64>(), any::<i64>(), any::<i64>()).prop_map(
+                |(total_amount, start_ts, end_ts)| {
+                    CreateVesting {
+                        total_amount,
+                        start_ts,
+                        end_ts,
+                    }
+                }
+            ),
+            Just(ClaimVested),
+            pubkey().prop_map(|owner| RepairOwner { owner }),
+            Just(RecoverNested),
+            (any::<u64>(), any::<u64>())
+                .prop_map(|(amount_a, amount_b)| TwoPartySwap { amount_a, amount_b }),
+            Just(CreateAssociatedAccountIdempotent),
+            pubkey()
+                .prop_map(|recovery_authority| InitializeRecoveryAuthority { recovery_authority }),
+            (coption(pubkey()), coption(pubkey())).prop_map(|(authority, metadata_address)| {
+                InitializeMetadataPointer {
+                    authority,
+                    metadata_address,
+                }
+            }),
+            coption(pubkey())
+                .prop_map(|metadata_address| UpdateMetadataPointer { metadata_address }),
+            (any::<u64>(), any::<u64>()).prop_map(|(numerator, denominator)| CreateConverter {
+                numerator,
+                denominator,
+            }),
+            any::<u64>().prop_map(|amount| Convert { amount }),
+            (any::<u64>(), pubkey()).prop_map(|(minimum_balance, enforcement_authority)| {
+                SetMinimumBalance {
+                    minimum_balance,
+                    enforcement_authority,
+                }
+            }),
+            any::<u64>().prop_map(|snapshot_id| SnapshotBalance { snapshot_id }),
+            Just(CloseSnapshot),
+            (any::<u64>(), any::<u64>()).prop_map(|(amount, relayer_fee)| TransferWithRelayerFee {
+                amount,
+                relayer_fee,
+            }),
+            any::<u64>().prop_map(|nonce| VerifyOwnership { nonce }),
+            (any::<u64>(), any::<u64>()).prop_map(
+                |(max_outflow_per_instruction, timelock_slots)| {
+                    SetOutflowLimit {
+                        max_outflow_per_instruction,
+                        timelock_slots,
+                    }
+                }
+            ),
+            (pubkey(), any::<u64>(), any::<u64>()).prop_map(
+                |(minter, max_per_window, window_slots)| AddMinter {
+                    minter,
+                    max_per_window,
+                    window_slots,
+                }
+            ),
+            pubkey().prop_map(|minter| RemoveMinter { minter }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn instructions_round_trip(instruction in instruction()) {
+            prop_assert_eq!(TokenInstruction::unpack(&instruction.pack()).unwrap(), instruction);
+        }
+
+        #[test]
+        fn instructions_reject_short_and_long_data(
+            instruction in instruction(),
+            extra in vec(any::<u8>(), 1..8),
+        ) {
+            let packed = instruction.pack();
+            let mut long = packed.clone();
+            long.extend_from_slice(&extra);
+            prop_assert_eq!(TokenInstruction::unpack(&long), Err(ProgramError::InvalidInstructionData));
+
+            // Only a mint's unset maximum supply may be left out.
+            let short = TokenInstruction::unpack(&packed[..packed.len() - 1]);
+            match instruction {
+                TokenInstruction::InitializeMint { max_supply: COption::None, .. }
+                | TokenInstruction::InitializeMint2 { max_supply: COption::None, .. } => {
+                    prop_assert_eq!(short.unwrap(), instruction);
+                }
+                _ => prop_assert_eq!(short, Err(ProgramError::InvalidInstructionData)),
+            }
+        }
+
+        #[test]
+        fn arbitrary_instruction_data_never_panics(data in vec(any::<u8>(), 0..256)) {
+            let _ = TokenInstruction::unpack(&data);
+        }
+    }
+}
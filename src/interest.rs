@@ -0,0 +1,85 @@
+//! Continuously compounded interest of interest-bearing mints, in
+//! fixed-point arithmetic
+//!
+//! Values are WADs: `u128`s where [`WAD`] stands for 1.0. Every helper
+//! returns `None` instead of overflowing.
+
+/// 1.0 as a WAD.
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+
+/// Length of the year that rates are quoted over: 365.24 days.
+pub const SECONDS_PER_YEAR: i64 = 31_556_736;
+
+/// e as a WAD.
+const E: u128 = 2_718_281_828_459_045_235;
+
+/// Annual rates are in basis points.
+const BASIS_POINTS: i128 = 10_000;
+
+/// Growth of one unit over `elapsed` seconds at an annual `rate_bps`,
+/// compounded continuously: `e^(rate_bps / 10_000 * elapsed / SECONDS_PER_YEAR)`.
+pub fn growth_factor(rate_bps: i16, elapsed: i64) -> Option<u128> {
+    let exponent = (rate_bps as i128)
+        .checked_mul(elapsed as i128)?
+        .checked_mul(WAD as i128)?
+        / (BASIS_POINTS * SECONDS_PER_YEAR as i128);
+    let factor = exp(exponent.unsigned_abs());
+    if exponent < 0 {
+        // Decay too steep to represent rounds to zero.
+        Some(factor.map_or(0, |factor| WAD * WAD / factor))
+    } else {
+        factor
+    }
+}
+
+/// `a * b` for WADs `a` and `b`, truncated. Each operand is split into
+/// whole and fractional parts so no intermediate product overflows unless
+/// the result does.
+pub fn mul(a: u128, b: u128) -> Option<u128> {
+    let (a_whole, a_fraction) = (a / WAD, a % WAD);
+    let (b_whole, b_fraction) = (b / WAD, b % WAD);
+    a_whole
+        .checked_mul(b_whole)?
+        .checked_mul(WAD)?
+        .checked_add(a_whole.checked_mul(b_fraction)?)?
+        .checked_add(a_fraction.checked_mul(b_whole)?)?
+        .checked_add(a_fraction * b_fraction / WAD)
+}
+
+/// `e^x` for a non-negative WAD `x`: `e` raised to the whole part, times a
+/// Taylor series for the fractional part.
+fn exp(x: u128) -> Option<u128> {
+    let whole = x / WAD;
+    let fraction = x % WAD;
+
+    let mut term = WAD;
+    let mut result = WAD;
+    for k in 1..=30 {
+        term = term * fraction / (WAD * k);
+        if term == 0 {
+            break;
+        }
+        result += term;
+    }
+    // Overflows within a few dozen iterations for any `whole` that large.
+    for _ in 0..whole {
+        result = mul(result, E)?;
+    }
+    Some(result)
+}
+
+/// `amount` raw units scaled by the WAD `scale`, truncated.
+pub fn scale_amount(amount: u64, scale: u128) -> Option<u128> {
+    mul(amount as u128 * WAD, scale).map(|scaled| scaled / WAD)
+}
+
+/// Inverse of [`scale_amount`]: the raw amount worth `scaled_amount` at the
+/// WAD `scale`, truncated.
+pub fn unscale_amount(scaled_amount: u128, scale: u128) -> Option<u64> {
+    if scale == 0 {
+        return None;
+    }
+    let whole = (scaled_amount / scale).checked_mul(WAD)?;
+    let fraction = (scaled_amount % scale).checked_mul(WAD)? / scale;
+    u64::try_from(whole.checked_add(fraction)?).ok()
+}
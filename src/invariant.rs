@@ -0,0 +1,128 @@
+//! Token conservation checks
+//!
+//! Tokens only come into existence by minting and leave it by burning, so
+//! for every mint the tokens held across its accounts change by exactly as
+//! much as its supply does: not at all for a transfer, by the amount for a
+//! mint or burn. Held tokens include fees withheld on accounts and on the
+//! mint itself.
+//!
+//! A [`Ledger`] totals what a set of accounts holds per mint; comparing the
+//! ledgers taken before and after an instruction checks that it conserved
+//! tokens across those accounts. The integration tests use this as an
+//! oracle, and the `debug-invariants` feature runs the check around every
+//! instruction the processor executes.
+
+use std::{collections::BTreeMap, fmt};
+
+use solana_program::{
+    account_info::AccountInfo,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+
+use crate::{
+    extension::{MAX_ACCOUNT_LEN, MAX_MINT_LEN},
+    native_mint,
+    state::{Mint, Token},
+};
+
+/// Per-mint totals over a set of accounts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ledger {
+    mints: BTreeMap<Pubkey, Totals>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Totals {
+    /// Supply of the mint, zero when its account isn't in the set.
+    supply: u64,
+    /// Tokens held by the mint's accounts in the set, plus those withheld
+    /// on the mint.
+    held: u128,
+}
+
+impl Ledger {
+    /// Totals the initialized mints and token accounts owned by
+    /// `program_id` among `accounts`, in any layout and with any
+    /// extensions. An account passed more than once is counted once;
+    /// wrapped SOL, whose balance follows lamports, is left out.
+    pub fn capture(program_id: &Pubkey, accounts: &[AccountInfo]) -> Self {
+        // Version 2 accounts led with their version instead of a header.
+        const VERSION_2_TOKEN_LEN: usize = Token::LEN - 1;
+        let mut ledger = Ledger::default();
+        let mut seen = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            if account.owner != program_id || seen.contains(&account.key) {
+                continue;
+            }
+            seen.push(account.key);
+            let Ok(data) = account.try_borrow_data() else {
+                continue;
+            };
+            match data.len() {
+                Mint::LEN..=MAX_MINT_LEN | Mint::LEGACY_LEN | Mint::VERSION_2_LEN => {
+                    let Ok(mint) = Mint::unpack_any_version(&data) else {
+                        continue;
+                    };
+                    if mint.is_initialized {
+                        let totals = ledger.mints.entry(*account.key).or_default();
+                        totals.supply = mint.supply;
+                        totals.held += mint.withheld_amount as u128;
+                    }
+                }
+                Token::LEGACY_LEN | VERSION_2_TOKEN_LEN..=MAX_ACCOUNT_LEN => {
+                    let Ok(token) = Token::unpack_any_version(&data) else {
+                        continue;
+                    };
+                    if token.is_initialized()
+                        && !token.is_native()
+                        && token.mint != native_mint::id()
+                    {
+                        let totals = ledger.mints.entry(token.mint).or_default();
+                        totals.held += token.amount as u128;
+                        totals.held += token.withheld_amount.unwrap_or(0) as u128;
+                    }
+                }
+                _ => {}
+            }
+        }
+        ledger
+    }
+
+    /// Checks that every mint's held tokens changed by as much as its supply
+    /// between `self` and `after`, taken over the same accounts.
+    pub fn check(&self, after: &Ledger) -> Result<(), Violation> {
+        let keys = self.mints.keys().chain(after.mints.keys());
+        for mint in keys {
+            let before = self.mints.get(mint).copied().unwrap_or_default();
+            let after = after.mints.get(mint).copied().unwrap_or_default();
+            let violation = Violation {
+                mint: *mint,
+                supply_delta: after.supply as i128 - before.supply as i128,
+                held_delta: after.held as i128 - before.held as i128,
+            };
+            if violation.supply_delta != violation.held_delta {
+                return Err(violation);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A mint whose held tokens and supply moved apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub mint: Pubkey,
+    pub supply_delta: i128,
+    pub held_delta: i128,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "supply of mint {} changed by {} but its accounts by {}",
+            self.mint, self.supply_delta, self.held_delta
+        )
+    }
+}
@@ -1,127 +1,119 @@
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint,
-    entrypoint::ProgramResult,
-    msg,
-    program::{invoke, invoke_signed},
-    program_error::ProgramError,
-    program_option::COption,
-    program_pack::Sealed,
-    program_pack::{IsInitialized, Pack},
-    pubkey::Pubkey,
-    sysvar::{rent::Rent, Sysvar},
-};
-
-#[derive(Clone, Debug, Default, PartialEq)]
-pub struct Token {
-    pub mint: Pubkey,
-    pub owner: Pubkey,
-    pub amount: u64,
-}
-
-impl Sealed for Token {}
-
-impl IsInitialized for Token {
-    fn is_initialized(&self) -> bool {
-        self.amount > 0
-    }
-}
-
-impl Pack for Token {
-    const LEN: usize = 64;
-
-    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let amount = u64::from_le_bytes(src[0..8].try_into().unwrap());
-        let mint = Pubkey::new_from_array(src[8..40].try_into().unwrap());
-        let owner = Pubkey::new_from_array(src[40..72].try_into().unwrap());
-
-        Ok(Token {
-            mint,
-            owner,
-            amount,
-        })
-    }
-
-    fn pack_into_slice(&self, dst: &mut [u8]) {
-        let amount = self.amount.to_le_bytes();
-        let mint = self.mint.to_bytes();
-        let owner = self.owner.to_bytes();
-
-        dst[0..8].copy_from_slice(&amount);
-        dst[8..40].copy_from_slice(&mint);
-        dst[40..72].copy_from_slice(&owner);
-    }
-}
-
-entrypoint!(process_instruction);
-
+//! A token program for the Solana blockchain.
+//!
+//! The default `program` feature builds the on-chain program. Off-chain
+//! users can build with `--no-default-features --features client` for the
+//! state, instruction and error types, the instruction builders and account
+//! decoding, without the processor or entrypoint.
+
+#[cfg(feature = "program")]
+pub mod accounts;
+pub mod allowlist;
+#[cfg(feature = "anchor")]
+pub mod anchor;
+pub mod associated;
+pub mod converter;
+#[cfg(feature = "program")]
+pub mod cpi;
+#[cfg(feature = "client")]
+pub mod decode;
+#[cfg(feature = "program")]
+pub mod deprecation;
+pub mod distributor;
+pub mod error;
+pub mod escrow;
+pub mod event;
+pub mod extension;
+#[cfg(feature = "client")]
+pub mod fixtures;
+#[cfg(feature = "idl")]
+pub mod idl;
+pub mod instruction;
+pub mod interest;
+#[cfg(any(feature = "test-utils", feature = "debug-invariants"))]
+pub mod invariant;
+#[cfg(feature = "program")]
+pub mod log;
+pub mod math;
+pub mod memo;
+pub mod metadata;
+pub mod native_mint;
+pub mod permit;
+#[cfg(feature = "bytemuck")]
+pub mod pod;
+#[cfg(feature = "program")]
+pub mod processor;
+pub mod seeded;
+pub mod snapshot;
+pub mod state;
+pub mod transfer_hook;
+pub mod vesting;
+
+#[cfg(feature = "serde")]
+mod serde_helpers;
+
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+
+#[cfg(all(
+    feature = "program",
+    target_os = "solana",
+    not(feature = "no-entrypoint")
+))]
+mod entrypoint;
+
+pub use state::{AccountState, Escrow, Mint, Multisig, Token, TokenMetadata};
+
+use solana_program::program_error::ProgramError;
+#[cfg(feature = "program")]
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+/// Processes an instruction; kept as a free function for existing callers,
+/// equivalent to `processor::Processor::process`.
+#[cfg(feature = "program")]
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    let instruction = instruction_data[0];
-
-    match instruction {
-        0 => {
-            let amount = u64::from_le_bytes(instruction_data[1..9].try_into().unwrap());
-            mint_tokens(program_id, accounts, amount)
-        }
-        1 => {
-            let amount = u64::from_le_bytes(instruction_data[1..9].try_into().unwrap());
-            transfer_tokens(program_id, accounts, amount)
-        }
-        _ => Err(ProgramError::InvalidInstructionData),
-    }
+    processor::Processor::process(program_id, accounts, instruction_data)
 }
 
-fn mint_tokens(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let mint_account = next_account_info(account_info_iter)?;
-    let token_account = next_account_info(account_info_iter)?;
-
-    if token_account.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId);
-    }
-
-    let mut token_data = Token::unpack_unchecked(&token_account.try_borrow_data()?)?;
-    if !token_data.is_initialized() {
-        token_data.mint = *mint_account.key;
-        token_data.owner = *mint_account.key;
-        token_data.amount = amount;
-        Token::pack(token_data, &mut token_account.try_borrow_mut_data()?)?;
-    } else {
-        return Err(ProgramError::AccountAlreadyInitialized);
-    }
-
-    Ok(())
+/// Formats a raw amount with `decimals` fractional digits, dropping trailing
+/// zeros and a trailing decimal point.
+pub fn amount_to_ui_amount(amount: u64, decimals: u8) -> String {
+    format_ui_amount(amount as u128, decimals)
 }
 
-fn transfer_tokens(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let source_account = next_account_info(account_info_iter)?;
-    let destination_account = next_account_info(account_info_iter)?;
-    let authority_account = next_account_info(account_info_iter)?;
-
-    if !authority_account.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+/// [`amount_to_ui_amount`] for amounts that may exceed `u64`, such as
+/// interest-scaled ones.
+pub(crate) fn format_ui_amount(amount: u128, decimals: u8) -> String {
+    math::UiAmount { amount, decimals }.to_string()
+}
 
-    let mut source_data = Token::unpack(&source_account.try_borrow_data()?)?;
-    let mut destination_data = Token::unpack_unchecked(&destination_account.try_borrow_data()?)?;
+/// Parses a decimal string such as `"1.5"` into a raw amount with `decimals`
+/// fractional digits. Trailing fractional zeros are ignored; any other digit
+/// past `decimals`, an empty string, or a non-digit character is rejected.
+pub fn try_ui_amount_into_amount(ui_amount: &str, decimals: u8) -> Result<u64, ProgramError> {
+    u64::try_from(parse_ui_amount(ui_amount, decimals)?).map_err(|_| ProgramError::InvalidArgument)
+}
 
-    if source_data.amount < amount {
-        return Err(ProgramError::InsufficientFunds);
+/// [`try_ui_amount_into_amount`] for amounts that may exceed `u64`.
+pub(crate) fn parse_ui_amount(ui_amount: &str, decimals: u8) -> Result<u128, ProgramError> {
+    let decimals = decimals as usize;
+    let (whole, fraction) = ui_amount.split_once('.').unwrap_or((ui_amount, ""));
+    let fraction = fraction.trim_end_matches('0');
+    if (whole.is_empty() && fraction.is_empty())
+        || fraction.len() > decimals
+        || !whole
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(ProgramError::InvalidArgument);
     }
-
-    source_data.amount -= amount;
-    destination_data.amount += amount;
-
-    Token::pack(source_data, &mut source_account.try_borrow_mut_data()?)?;
-    Token::pack(
-        destination_data,
-        &mut destination_account.try_borrow_mut_data()?,
-    )?;
-
-    Ok(())
+    let mut digits = String::with_capacity(whole.len() + decimals);
+    digits.push_str(whole);
+    digits.push_str(fraction);
+    digits.extend(std::iter::repeat_n('0', decimals - fraction.len()));
+    digits.parse().map_err(|_| ProgramError::InvalidArgument)
 }
@@ -0,0 +1,26 @@
+//! Program logging, in two levels
+//!
+//! Events are what indexers consume, so [`log_event`] always logs them.
+//! Diagnostics describe the steps a handler took and why it failed; each
+//! `msg!` costs compute, so [`log_debug!`] only logs them in builds with the
+//! `verbose-logs` feature and compiles to nothing otherwise, its arguments
+//! included. Diagnostics show amounts as [`UiAmount`](crate::math::UiAmount)s
+//! when the handler has the mint's decimals; events keep raw amounts.
+
+use crate::event::TokenEvent;
+
+/// Logs a diagnostic with `msg!` in `verbose-logs` builds. The arguments
+/// are still type-checked in quiet builds, but never formatted.
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        if cfg!(feature = "verbose-logs") {
+            solana_program::msg!($($arg)+);
+        }
+    };
+}
+pub(crate) use log_debug;
+
+/// Logs `event` with `sol_log_data`, whatever the verbosity.
+pub fn log_event(event: &TokenEvent) {
+    event.emit();
+}
@@ -0,0 +1,147 @@
+//! Checked integer arithmetic shared by fees, royalties and amount scaling
+//!
+//! Every helper returns [`TokenError::MathOverflow`] instead of wrapping,
+//! truncating a result that doesn't fit, or dividing by zero. Intermediate
+//! products are computed in a [`PreciseNumber`], so only a final result too
+//! large for a `u64` fails.
+
+use crate::error::TokenError;
+use std::fmt;
+
+/// Basis points in a whole: 10_000 bps is 100%.
+pub const MAX_BASIS_POINTS: u16 = 10_000;
+
+/// A `u128`-backed integer for products of `u64`s, which never overflow it,
+/// before they are divided back down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PreciseNumber {
+    pub value: u128,
+}
+
+impl PreciseNumber {
+    pub fn new(value: u64) -> Self {
+        Self {
+            value: value as u128,
+        }
+    }
+
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self, TokenError> {
+        self.map(self.value.checked_add(rhs.value))
+    }
+
+    pub fn checked_sub(&self, rhs: &Self) -> Result<Self, TokenError> {
+        self.map(self.value.checked_sub(rhs.value))
+    }
+
+    pub fn checked_mul(&self, rhs: &Self) -> Result<Self, TokenError> {
+        self.map(self.value.checked_mul(rhs.value))
+    }
+
+    /// `self / rhs`, rounded down.
+    pub fn checked_div_floor(&self, rhs: &Self) -> Result<Self, TokenError> {
+        self.map(self.value.checked_div(rhs.value))
+    }
+
+    /// `self / rhs`, rounded up.
+    pub fn checked_div_ceil(&self, rhs: &Self) -> Result<Self, TokenError> {
+        let quotient = self.checked_div_floor(rhs)?;
+        if self.value.is_multiple_of(rhs.value) {
+            Ok(quotient)
+        } else {
+            quotient.checked_add(&Self::new(1))
+        }
+    }
+
+    /// The value as a `u64`, if it fits.
+    pub fn to_u64(&self) -> Result<u64, TokenError> {
+        u64::try_from(self.value).map_err(|_| TokenError::MathOverflow)
+    }
+
+    fn map(&self, value: Option<u128>) -> Result<Self, TokenError> {
+        value
+            .map(|value| Self { value })
+            .ok_or(TokenError::MathOverflow)
+    }
+}
+
+/// `amount * bps / 10_000`, rounded down: never more than the exact share.
+pub fn multiply_by_bps_floor(amount: u64, bps: u16) -> Result<u64, TokenError> {
+    PreciseNumber::new(amount)
+        .checked_mul(&PreciseNumber::new(bps as u64))?
+        .checked_div_floor(&PreciseNumber::new(MAX_BASIS_POINTS as u64))?
+        .to_u64()
+}
+
+/// `amount * bps / 10_000`, rounded up: never less than the exact share.
+pub fn multiply_by_bps_ceil(amount: u64, bps: u16) -> Result<u64, TokenError> {
+    PreciseNumber::new(amount)
+        .checked_mul(&PreciseNumber::new(bps as u64))?
+        .checked_div_ceil(&PreciseNumber::new(MAX_BASIS_POINTS as u64))?
+        .to_u64()
+}
+
+/// Re-expresses `amount` raw units of a mint with `from_decimals` as raw
+/// units with `to_decimals`, rounding down when digits are dropped.
+pub fn scale_by_decimals(
+    amount: u64,
+    from_decimals: u8,
+    to_decimals: u8,
+) -> Result<u64, TokenError> {
+    let amount = PreciseNumber::new(amount);
+    if to_decimals >= from_decimals {
+        let factor = power_of_ten(to_decimals - from_decimals).ok_or(TokenError::MathOverflow)?;
+        amount.checked_mul(&factor)?.to_u64()
+    } else {
+        match power_of_ten(from_decimals - to_decimals) {
+            Some(divisor) => amount.checked_div_floor(&divisor)?.to_u64(),
+            // Larger than any `u64`, so every digit is dropped.
+            None => Ok(0),
+        }
+    }
+}
+
+/// Displays a raw amount with `decimals` fractional digits, dropping
+/// trailing zeros and a trailing decimal point, as `amount_to_ui_amount`
+/// returns it. Writing it allocates nothing, so diagnostics can log one
+/// directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UiAmount {
+    pub amount: u128,
+    pub decimals: u8,
+}
+
+impl UiAmount {
+    pub fn new(amount: u64, decimals: u8) -> Self {
+        Self {
+            amount: amount as u128,
+            decimals,
+        }
+    }
+}
+
+impl fmt::Display for UiAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Past 38 decimals the divisor exceeds any `u128`: every digit is
+        // fractional.
+        let (whole, mut fraction) = match power_of_ten(self.decimals) {
+            Some(divisor) => (self.amount / divisor.value, self.amount % divisor.value),
+            None => (0, self.amount),
+        };
+        let mut width = self.decimals as usize;
+        while width > 0 && fraction % 10 == 0 {
+            fraction /= 10;
+            width -= 1;
+        }
+        if width == 0 {
+            write!(f, "{whole}")
+        } else {
+            write!(f, "{whole}.{fraction:0width$}")
+        }
+    }
+}
+
+fn power_of_ten(exponent: u8) -> Option<PreciseNumber> {
+    10u128
+        .checked_pow(exponent as u32)
+        .map(|value| PreciseNumber { value })
+}
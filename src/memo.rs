@@ -0,0 +1,14 @@
+//! Memo program ids, recognized when an account requires incoming transfers
+//! to carry a memo
+
+// The current SPL memo program.
+solana_program::declare_id!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// The first, deprecated version of the SPL memo program.
+pub const V1_ID: solana_program::pubkey::Pubkey =
+    solana_program::pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo");
+
+/// Whether `program_id` is either version of the memo program.
+pub fn is_memo_program(program_id: &solana_program::pubkey::Pubkey) -> bool {
+    *program_id == id() || *program_id == V1_ID
+}
@@ -0,0 +1,18 @@
+//! Token metadata account address derivation
+
+use solana_program::pubkey::Pubkey;
+
+/// Seed prefix of metadata account addresses.
+pub const METADATA_SEED: &[u8] = b"metadata";
+
+/// Derives the metadata account address for a mint.
+pub fn get_metadata_address(mint_address: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_metadata_address_and_bump_seed(mint_address, program_id).0
+}
+
+pub(crate) fn get_metadata_address_and_bump_seed(
+    mint_address: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[METADATA_SEED, &mint_address.to_bytes()], program_id)
+}
@@ -0,0 +1,8 @@
+//! The native mint, used to wrap SOL as a token
+
+/// Number of decimals of the native mint, matching lamports per SOL.
+pub const DECIMALS: u8 = 9;
+
+// There is no mint account behind this address; token accounts initialized
+// against it hold lamports and are treated as native.
+solana_program::declare_id!("2rV7g4gz95D69c1JM9Bvp2SkwZT1zZXrj8VdZFrFvvv2");
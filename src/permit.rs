@@ -0,0 +1,135 @@
+//! Transfers authorized by an owner's signature over a message, checked
+//! through an ed25519 program instruction earlier in the transaction
+//!
+//! The owner signs a [`Permit`] off-chain; anyone can then submit it with
+//! `PermitTransfer`, immediately preceded by the instruction returned by
+//! [`new_ed25519_instruction`]. The runtime fails the transaction unless
+//! the signature verifies, so the program only has to read which key signed
+//! which message.
+
+use solana_program::{
+    ed25519_program, instruction::Instruction, program_error::ProgramError, pubkey::Pubkey,
+};
+
+/// Size of a packed [`Permit`].
+pub const PERMIT_LEN: usize = 88;
+
+// Layout of an ed25519 program instruction verifying one signature, with
+// the key, signature and message all in its own data.
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+const DATA_START: usize = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN;
+const PUBKEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+/// Instruction index the offsets use for the verifying instruction itself.
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// The message an owner signs to let `amount` move from `source` to
+/// `destination` until `expiry_unix`.
+///
+/// Packed layout: `source (32) | destination (32) | amount (8) |
+/// expiry_unix (8) | nonce (8)`. A source account accepts each nonce once,
+/// and only in increasing order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Permit {
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub expiry_unix: i64,
+    pub nonce: u64,
+}
+
+impl Permit {
+    pub fn pack(&self) -> [u8; PERMIT_LEN] {
+        let mut dst = [0; PERMIT_LEN];
+        dst[0..32].copy_from_slice(self.source.as_ref());
+        dst[32..64].copy_from_slice(self.destination.as_ref());
+        dst[64..72].copy_from_slice(&self.amount.to_le_bytes());
+        dst[72..80].copy_from_slice(&self.expiry_unix.to_le_bytes());
+        dst[80..88].copy_from_slice(&self.nonce.to_le_bytes());
+        dst
+    }
+
+    /// Fails with `InvalidInstructionData` unless `src` is exactly
+    /// [`PERMIT_LEN`] bytes.
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != PERMIT_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(Permit {
+            source: Pubkey::new_from_array(src[0..32].try_into().unwrap()),
+            destination: Pubkey::new_from_array(src[32..64].try_into().unwrap()),
+            amount: u64::from_le_bytes(src[64..72].try_into().unwrap()),
+            expiry_unix: i64::from_le_bytes(src[72..80].try_into().unwrap()),
+            nonce: u64::from_le_bytes(src[80..88].try_into().unwrap()),
+        })
+    }
+}
+
+/// Creates the ed25519 program instruction verifying `signature`, made by
+/// `signer` over `message`, to place right before a `PermitTransfer`.
+pub fn new_ed25519_instruction(
+    signer: &Pubkey,
+    signature: &[u8; SIGNATURE_LEN],
+    message: &[u8],
+) -> Instruction {
+    let public_key_offset = DATA_START;
+    let signature_offset = public_key_offset + PUBKEY_LEN;
+    let message_data_offset = signature_offset + SIGNATURE_LEN;
+
+    let mut data = Vec::with_capacity(message_data_offset + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for field in [
+        signature_offset as u16,
+        CURRENT_INSTRUCTION,
+        public_key_offset as u16,
+        CURRENT_INSTRUCTION,
+        message_data_offset as u16,
+        message.len() as u16,
+        CURRENT_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: ed25519_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Returns the key and message of an ed25519 program instruction with
+/// `program_id` and `data` that verifies a single signature. Fails with
+/// `InvalidInstructionData` if it is any other instruction, verifies
+/// several signatures, or takes its key or message from another
+/// instruction, whose data this can't check.
+pub fn verified_message<'a>(
+    program_id: &Pubkey,
+    data: &'a [u8],
+) -> Result<(Pubkey, &'a [u8]), ProgramError> {
+    if *program_id != ed25519_program::id() || data.len() < DATA_START || data[0] != 1 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let field = |index: usize| {
+        let start = SIGNATURE_OFFSETS_START + 2 * index;
+        u16::from_le_bytes([data[start], data[start + 1]])
+    };
+    let (public_key_offset, public_key_instruction_index) = (field(2) as usize, field(3));
+    let (message_data_offset, message_data_size, message_instruction_index) =
+        (field(4) as usize, field(5) as usize, field(6));
+    if public_key_instruction_index != CURRENT_INSTRUCTION
+        || message_instruction_index != CURRENT_INSTRUCTION
+    {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let signer = data
+        .get(public_key_offset..public_key_offset + PUBKEY_LEN)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let message = data
+        .get(message_data_offset..message_data_offset + message_data_size)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((Pubkey::new_from_array(signer.try_into().unwrap()), message))
+}
@@ -0,0 +1,314 @@
+//! Zero-copy views of token and mint accounts
+//!
+//! [`PodToken`] and [`PodMint`] spell out the `Pack` layouts of [`Token`]
+//! and [`Mint`] as `#[repr(C)]` structs of bytes, so they have alignment one
+//! and cast from account data in place, without unpacking. Integers are kept
+//! little-endian in byte arrays and read through accessors. Only data that
+//! starts with the header of the current layout casts; legacy or other
+//! accounts go through `Pack`.
+
+use crate::state::{AccountState, AccountType, Mint, Token, ACCOUNT_VERSION};
+use bytemuck::{Pod, Zeroable};
+use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+
+/// A packed `COption<Pubkey>`: a four-byte tag, 1 if set, then the key.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct PodCOptionKey {
+    pub tag: [u8; 4],
+    pub key: Pubkey,
+}
+
+impl PodCOptionKey {
+    /// The key, if the tag is set; `None` for any other tag.
+    pub fn get(&self) -> Option<&Pubkey> {
+        (self.tag == [1, 0, 0, 0]).then_some(&self.key)
+    }
+
+    /// Whether the tag is one `Pack` reads.
+    pub fn is_valid(&self) -> bool {
+        matches!(self.tag, [0, 0, 0, 0] | [1, 0, 0, 0])
+    }
+}
+
+/// A packed `COption<u64>`: a four-byte tag, 1 if set, then the value.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct PodCOptionU64 {
+    pub tag: [u8; 4],
+    pub value: [u8; 8],
+}
+
+impl PodCOptionU64 {
+    /// The value, if the tag is set; `None` for any other tag.
+    pub fn get(&self) -> Option<u64> {
+        (self.tag == [1, 0, 0, 0]).then(|| u64::from_le_bytes(self.value))
+    }
+
+    /// Whether it is unset, as the all-zero bytes `Pack` writes for `None`.
+    pub fn is_none(&self) -> bool {
+        self.tag == [0; 4]
+    }
+}
+
+/// The [`Token`] layout, [`Token::LEN`] bytes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct PodToken {
+    pub account_type: u8,
+    pub version: u8,
+    pub amount: [u8; 8],
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub delegate: PodCOptionKey,
+    pub delegated_amount: [u8; 8],
+    pub state: u8,
+    pub is_native: PodCOptionU64,
+    pub withheld_amount: PodCOptionU64,
+    pub is_non_transferable: u8,
+    pub is_pausable: u8,
+    pub pending_owner: PodCOptionKey,
+    pub has_transfer_hook: u8,
+}
+
+impl PodToken {
+    /// Casts the first [`Token::LEN`] bytes of `data`, or returns `None` if
+    /// `data` is shorter, or isn't a token account of the current layout.
+    pub fn from_bytes(data: &[u8]) -> Option<&Self> {
+        let pod: &Self = bytemuck::try_from_bytes(data.get(..Token::LEN)?).ok()?;
+        pod.has_current_header().then_some(pod)
+    }
+
+    /// [`PodToken::from_bytes`], for changing the account in place.
+    pub fn from_bytes_mut(data: &mut [u8]) -> Option<&mut Self> {
+        let pod: &mut Self = bytemuck::try_from_bytes_mut(data.get_mut(..Token::LEN)?).ok()?;
+        pod.has_current_header().then_some(pod)
+    }
+
+    fn has_current_header(&self) -> bool {
+        self.account_type == AccountType::Token as u8 && self.version == ACCOUNT_VERSION
+    }
+
+    pub fn amount(&self) -> u64 {
+        u64::from_le_bytes(self.amount)
+    }
+
+    pub fn set_amount(&mut self, amount: u64) {
+        self.amount = amount.to_le_bytes();
+    }
+
+    pub fn delegated_amount(&self) -> u64 {
+        u64::from_le_bytes(self.delegated_amount)
+    }
+
+    /// Whether this is an initialized, unfrozen account with none of the
+    /// optional fields a transfer has to check, as `Pack` would read it:
+    /// no native balance, withheld fees, or set flags. A delegate or a
+    /// pending owner don't count, as long as their tags are valid.
+    pub fn is_plain(&self) -> bool {
+        self.state == AccountState::Initialized as u8
+            && self.delegate.is_valid()
+            && self.pending_owner.is_valid()
+            && self.is_native.is_none()
+            && self.withheld_amount.is_none()
+            && [
+                self.is_non_transferable,
+                self.is_pausable,
+                self.has_transfer_hook,
+            ] == [0; 3]
+    }
+}
+
+impl From<&Token> for PodToken {
+    fn from(token: &Token) -> Self {
+        let mut pod = Self::zeroed();
+        token.pack_into_slice(bytemuck::bytes_of_mut(&mut pod));
+        pod
+    }
+}
+
+impl TryFrom<&PodToken> for Token {
+    type Error = ProgramError;
+
+    fn try_from(pod: &PodToken) -> Result<Self, ProgramError> {
+        Token::unpack_from_slice(bytemuck::bytes_of(pod))
+    }
+}
+
+/// The [`Mint`] layout, [`Mint::LEN`] bytes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct PodMint {
+    pub account_type: u8,
+    pub version: u8,
+    pub mint_authority: PodCOptionKey,
+    pub supply: [u8; 8],
+    pub decimals: u8,
+    pub is_initialized: u8,
+    pub freeze_authority: PodCOptionKey,
+    pub max_supply: PodCOptionU64,
+    pub transfer_fee_authority: PodCOptionKey,
+    pub transfer_fee_tag: [u8; 4],
+    pub transfer_fee_basis_points: [u8; 2],
+    pub maximum_fee: [u8; 8],
+    pub withheld_amount: [u8; 8],
+    pub non_transferable: u8,
+    pub pause_authority: PodCOptionKey,
+    pub paused: u8,
+    pub permanent_delegate: PodCOptionKey,
+    pub transfer_hook_program: PodCOptionKey,
+    pub default_account_state: u8,
+    pub rate_authority: PodCOptionKey,
+    pub interest_rate_tag: [u8; 4],
+    pub current_rate_bps: [u8; 2],
+    pub last_update_timestamp: [u8; 8],
+    pub accrued_scale: [u8; 16],
+    pub close_authority: PodCOptionKey,
+}
+
+impl PodMint {
+    /// Casts the first [`Mint::LEN`] bytes of `data`, or returns `None` if
+    /// `data` is shorter, or isn't a mint of the current layout.
+    pub fn from_bytes(data: &[u8]) -> Option<&Self> {
+        let pod: &Self = bytemuck::try_from_bytes(data.get(..Mint::LEN)?).ok()?;
+        pod.has_current_header().then_some(pod)
+    }
+
+    /// [`PodMint::from_bytes`], for changing the mint in place.
+    pub fn from_bytes_mut(data: &mut [u8]) -> Option<&mut Self> {
+        let pod: &mut Self = bytemuck::try_from_bytes_mut(data.get_mut(..Mint::LEN)?).ok()?;
+        pod.has_current_header().then_some(pod)
+    }
+
+    fn has_current_header(&self) -> bool {
+        self.account_type == AccountType::Mint as u8 && self.version == ACCOUNT_VERSION
+    }
+
+    pub fn supply(&self) -> u64 {
+        u64::from_le_bytes(self.supply)
+    }
+
+    pub fn set_supply(&mut self, supply: u64) {
+        self.supply = supply.to_le_bytes();
+    }
+}
+
+impl From<&Mint> for PodMint {
+    fn from(mint: &Mint) -> Self {
+        let mut pod = Self::zeroed();
+        mint.pack_into_slice(bytemuck::bytes_of_mut(&mut pod));
+        pod
+    }
+}
+
+impl TryFrom<&PodMint> for Mint {
+    type Error = ProgramError;
+
+    fn try_from(pod: &PodMint) -> Result<Self, ProgramError> {
+        Mint::unpack_from_slice(bytemuck::bytes_of(pod))
+    }
+}
+
+const _: () = assert!(std::mem::size_of::<PodToken>() == Token::LEN);
+const _: () = assert!(std::mem::size_of::<PodMint>() == Mint::LEN);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::{mint, packed, token};
+    use proptest::{collection::vec, prelude::*};
+    use solana_program::program_option::COption;
+
+    proptest! {
+        #[test]
+        fn pod_views_cast_the_packed_layouts(
+            token in token(),
+            mint in mint(),
+            tail in vec(any::<u8>(), 0..32),
+        ) {
+            let mut buf = packed(&token);
+            buf.extend_from_slice(&tail);
+            let pod = PodToken::from_bytes(&buf).unwrap();
+            prop_assert_eq!(bytemuck::bytes_of(pod), &buf[..Token::LEN]);
+            prop_assert_eq!(*pod, PodToken::from(&token));
+            prop_assert_eq!(Token::try_from(pod), Ok(token.clone()));
+            prop_assert_eq!(pod.amount(), token.amount);
+            prop_assert_eq!(pod.delegated_amount(), token.delegated_amount);
+            prop_assert_eq!(pod.delegate.get().copied(), Option::from(token.delegate));
+            prop_assert_eq!(pod.is_native.get(), Option::from(token.is_native));
+
+            let mut buf = packed(&mint);
+            buf.extend_from_slice(&tail);
+            let pod = PodMint::from_bytes(&buf).unwrap();
+            prop_assert_eq!(bytemuck::bytes_of(pod), &buf[..Mint::LEN]);
+            prop_assert_eq!(*pod, PodMint::from(&mint));
+            prop_assert_eq!(Mint::try_from(pod), Ok(mint.clone()));
+            prop_assert_eq!(pod.supply(), mint.supply);
+            prop_assert_eq!(pod.max_supply.get(), Option::from(mint.max_supply));
+        }
+
+        #[test]
+        fn pod_writes_match_pack(token in token(), mint in mint(), amount in any::<u64>()) {
+            let mut in_place = packed(&token);
+            PodToken::from_bytes_mut(&mut in_place).unwrap().set_amount(amount);
+            let mut written = packed(&token);
+            Token::write_amount(&mut written, amount).unwrap();
+            prop_assert_eq!(&in_place, &written);
+
+            let mut in_place = packed(&mint);
+            PodMint::from_bytes_mut(&mut in_place).unwrap().set_supply(amount);
+            prop_assert_eq!(in_place, packed(&Mint { supply: amount, ..mint }));
+        }
+
+        #[test]
+        fn plain_pod_tokens_unpack_to_tokens_without_flags(token in token()) {
+            let pod = PodToken::from(&token);
+            let plain = token.state == AccountState::Initialized
+                && token.is_native.is_none()
+                && token.withheld_amount.is_none()
+                && !token.is_non_transferable
+                && !token.is_pausable
+                && !token.has_transfer_hook;
+            prop_assert_eq!(pod.is_plain(), plain);
+        }
+
+        #[test]
+        fn pod_views_reject_other_layouts(token in token(), mint in mint(), len in 0..Token::LEN) {
+            let buf = packed(&token);
+            prop_assert!(PodToken::from_bytes(&buf[..len]).is_none());
+            prop_assert!(PodMint::from_bytes(&buf).is_none());
+            prop_assert!(PodToken::from_bytes(&packed(&mint)).is_none());
+            // A version 2 account, which `Pack` still reads.
+            let mut version_2 = buf[1..].to_vec();
+            version_2[0] = 2;
+            prop_assert!(PodToken::from_bytes(&version_2).is_none());
+            prop_assert!(Token::unpack_any_version(&version_2).is_ok());
+            // An account that has never been written.
+            prop_assert!(PodToken::from_bytes(&[0; Token::LEN]).is_none());
+        }
+
+        #[test]
+        fn corrupted_plain_pod_tokens_are_not_plain(token in token(), byte in 1..=u8::MAX) {
+            let plain = Token {
+                state: AccountState::Initialized,
+                is_native: COption::None,
+                withheld_amount: COption::None,
+                is_non_transferable: false,
+                is_pausable: false,
+                has_transfer_hook: false,
+                ..token
+            };
+            let buf = packed(&plain);
+            prop_assert!(PodToken::from_bytes(&buf).unwrap().is_plain());
+            // Any byte `Pack` would reject, or that marks a field a transfer
+            // checks, makes the account one for the full path.
+            for index in [74, 118, 119, 131, 143, 144, 145, 181] {
+                let mut corrupted = buf.clone();
+                // Tag 1 sets a delegate or pending owner, and state 1 is
+                // `Initialized`: only higher bytes corrupt those.
+                corrupted[index] = if matches!(index, 74 | 118 | 145) { byte.max(2) } else { byte };
+                prop_assert!(!PodToken::from_bytes(&corrupted).unwrap().is_plain());
+            }
+        }
+    }
+}
@@ -0,0 +1,5665 @@
+//! Program state processor
+
+#[cfg(feature = "bytemuck")]
+use crate::pod::PodToken;
+use crate::{
+    accounts::{
+        check_account_owner, check_distinct, check_is_signer, check_payer, check_writable,
+        load_mint, load_signer, load_token_account, MintToAccounts, TransferAccounts,
+    },
+    allowlist::{get_allowlist_address_and_bump_seed, ALLOWLIST_SEED},
+    associated::{get_associated_token_address, get_associated_token_address_and_bump_seed},
+    converter::{get_converter_authority_and_bump_seed, CONVERTER_SEED},
+    deprecation::{self, CompatHandler, DeprecationTable, Disposition},
+    distributor::{
+        self, get_claim_address_and_bump_seed, get_distributor_authority_and_bump_seed, CLAIM_SEED,
+        DISTRIBUTOR_SEED,
+    },
+    error::TokenError,
+    escrow::{get_escrow_authority_and_bump_seed, ESCROW_SEED},
+    event::{
+        AuthorityChangedEvent, BurnEvent, ClawbackEvent, ConfigChange, ConfigChangedEvent,
+        MintEvent, MinterUsedEvent, OwnershipProvedEvent, RecoveryAction, RecoveryUsedEvent,
+        TokenEvent, TransferEvent,
+    },
+    extension::{
+        self, AccountLock, AllowlistEnforcement, CloseAuthority, Cooldown, Extension, HolderCount,
+        ImmutableOwner, MemoTransfer, MetadataPointer, MinTransferAmount, MinimumBalance,
+        MintActivity, MintExtension, MintSettings, Minter, Minters, OutflowLimit, PermitNonce,
+        RecoveryAuthority, Royalty, TransferCooldown, VestingReserve, MAX_MINTERS,
+    },
+    instruction::{
+        self, is_valid_signer_index, AuthorityType, TokenInstruction, AMOUNT_ALL, MAX_ALLOCATIONS,
+        MAX_FREEZE_BATCH, MAX_SIGNERS, MAX_TRANSFER_BATCH,
+    },
+    interest,
+    log::{log_debug, log_event},
+    math::UiAmount,
+    memo,
+    metadata::{get_metadata_address_and_bump_seed, METADATA_SEED},
+    native_mint,
+    permit::{self, Permit},
+    seeded::{get_seeded_token_address_and_bump_seed, SEEDED_TOKEN_SEED},
+    snapshot::{get_snapshot_address_and_bump_seed, SNAPSHOT_RETENTION_SLOTS, SNAPSHOT_SEED},
+    state::{
+        self, AccountState, AccountType, Allowlist, BalanceSnapshot, ClaimStatus, Converter,
+        Distributor, Escrow, InterestRate, Mint, Multisig, Token, TokenMetadata, TransferFee,
+        VestingSchedule,
+    },
+    transfer_hook,
+    vesting::{get_vesting_address_and_bump_seed, VESTING_SEED},
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::AccountMeta,
+    program::{invoke, invoke_signed, set_return_data},
+    program_error::ProgramError,
+    program_memory::sol_memcmp,
+    program_option::COption,
+    program_pack::{IsInitialized, Pack},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+    system_instruction, system_program,
+    sysvar::{
+        self,
+        clock::Clock,
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+        rent::Rent,
+        Sysvar,
+    },
+};
+
+/// Program state handler.
+pub struct Processor {}
+
+/// Compares two pubkeys with `sol_memcmp`, which costs fewer compute units
+/// on-chain than the derived `==`.
+pub fn cmp_pubkeys(a: &Pubkey, b: &Pubkey) -> bool {
+    sol_memcmp(a.as_ref(), b.as_ref(), PUBKEY_BYTES) == 0
+}
+
+/// Token balances collected over an instruction and written back once at
+/// its end, so an account passed in several roles sees the sum of its
+/// debits and credits instead of whichever write came last.
+struct Balances<'a, 'info> {
+    /// Each account with the balance it started with and its pending one.
+    balances: Vec<(&'a AccountInfo<'info>, u64, u64)>,
+}
+
+impl<'a, 'info> Balances<'a, 'info> {
+    fn new() -> Self {
+        Self {
+            balances: Vec::new(),
+        }
+    }
+
+    /// The pending balance of `account`, read from it the first time the
+    /// account is seen under any role.
+    fn balance(&mut self, account: &'a AccountInfo<'info>) -> Result<&mut u64, ProgramError> {
+        let index = match self
+            .balances
+            .iter()
+            .position(|(seen, _, _)| cmp_pubkeys(seen.key, account.key))
+        {
+            Some(index) => index,
+            None => {
+                let amount = Token::amount_from_slice(&account.try_borrow_data()?)?;
+                self.balances.push((account, amount, amount));
+                self.balances.len() - 1
+            }
+        };
+        Ok(&mut self.balances[index].2)
+    }
+
+    fn debit(&mut self, account: &'a AccountInfo<'info>, amount: u64) -> ProgramResult {
+        let balance = self.balance(account)?;
+        *balance = balance
+            .checked_sub(amount)
+            .ok_or(TokenError::InsufficientFunds)?;
+        Ok(())
+    }
+
+    fn credit(&mut self, account: &'a AccountInfo<'info>, amount: u64) -> ProgramResult {
+        let balance = self.balance(account)?;
+        *balance = balance.checked_add(amount).ok_or(TokenError::Overflow)?;
+        Ok(())
+    }
+
+    /// How many more of the accounts hold a balance than at the start.
+    fn holder_change(&self) -> i64 {
+        self.balances
+            .iter()
+            .map(|(_, start, pending)| holder_change(*start, *pending))
+            .sum()
+    }
+
+    /// Writes each balance back to its account, after any full repack of
+    /// the same accounts.
+    fn commit(self) -> ProgramResult {
+        for (account, _, amount) in self.balances {
+            Token::write_amount(&mut account.try_borrow_mut_data()?, amount)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether an account whose balance goes from `before` to `after` becomes a
+/// holder, 1, stops being one, -1, or neither.
+fn holder_change(before: u64, after: u64) -> i64 {
+    (after > 0) as i64 - (before > 0) as i64
+}
+
+/// The extensions a new token account copies from its mint; see
+/// [`Processor::apply_mint_config`].
+#[derive(Default)]
+struct MintConfig {
+    settings: Option<MintSettings>,
+    cooldown: Option<Cooldown>,
+}
+
+impl Processor {
+    /// Processes a [`TokenInstruction`].
+    pub fn process(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8],
+    ) -> ProgramResult {
+        Self::process_with_deprecations(
+            program_id,
+            accounts,
+            instruction_data,
+            &deprecation::RELEASE,
+        )
+    }
+
+    /// [`Processor::process`], dispatching by `deprecations` rather than
+    /// [`deprecation::RELEASE`].
+    pub fn process_with_deprecations(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8],
+        deprecations: &DeprecationTable,
+    ) -> ProgramResult {
+        #[cfg(feature = "debug-invariants")]
+        let ledger = crate::invariant::Ledger::capture(program_id, accounts);
+
+        let handler = match TokenInstruction::tag(instruction_data) {
+            Some(tag) => Self::handler_for(tag, deprecations)?,
+            None => Self::dispatch,
+        };
+        handler(program_id, accounts, instruction_data)?;
+
+        #[cfg(feature = "debug-invariants")]
+        if let Err(violation) =
+            ledger.check(&crate::invariant::Ledger::capture(program_id, accounts))
+        {
+            solana_program::msg!("Invariant violated: {}", violation);
+            return Err(TokenError::InvalidState.into());
+        }
+        Ok(())
+    }
+
+    /// The handler `deprecations` dispatches `tag` to: its compatibility
+    /// handler if it has one, otherwise the usual one. Fails with
+    /// `DeprecatedInstruction` if `deprecations` deprecates `tag`.
+    /// Deprecated tags, and those handled in compatibility, log their
+    /// replacement whatever the verbosity.
+    fn handler_for(
+        tag: u8,
+        deprecations: &DeprecationTable,
+    ) -> Result<CompatHandler, ProgramError> {
+        match deprecations.get(tag) {
+            Disposition::PassThrough => Ok(Self::dispatch),
+            Disposition::Compat {
+                replacement,
+                handler,
+            } => {
+                solana_program::msg!(
+                    "Instruction {} is deprecated; use instruction {}",
+                    tag,
+                    replacement
+                );
+                Ok(handler)
+            }
+            Disposition::Deprecated { replacement } => {
+                solana_program::msg!(
+                    "Instruction {} is no longer supported; use instruction {}",
+                    tag,
+                    replacement
+                );
+                Err(TokenError::DeprecatedInstruction.into())
+            }
+        }
+    }
+
+    /// The compatibility handler of `MintTokens` in [`deprecation::RELEASE`]:
+    /// logs that tag 0 now credits the destination's existing owner instead
+    /// of making the mint its owner, then mints as usual.
+    pub fn process_mint_tokens_compat(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8],
+    ) -> ProgramResult {
+        solana_program::msg!(
+            "MintTokens credits the destination's owner and no longer makes the mint its owner"
+        );
+        Self::dispatch(program_id, accounts, instruction_data)
+    }
+
+    fn dispatch(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8],
+    ) -> ProgramResult {
+        let instruction = TokenInstruction::unpack(instruction_data).inspect_err(|_| {
+            log_debug!(
+                "Could not unpack {} bytes of instruction data with tag {:?}",
+                instruction_data.len(),
+                instruction_data.first()
+            );
+        })?;
+        log_debug!("Instruction: {:?}", instruction);
+
+        match instruction {
+            TokenInstruction::MintTokens { amount } => {
+                Self::process_mint(program_id, accounts, amount, None)
+            }
+            TokenInstruction::TransferTokens { amount } => {
+                Self::process_transfer(program_id, accounts, amount, None)
+            }
+            TokenInstruction::Burn { amount } => {
+                Self::process_burn(program_id, accounts, amount, None)
+            }
+            TokenInstruction::InitializeMint {
+                decimals,
+                mint_authority,
+                freeze_authority,
+                max_supply,
+            } => Self::process_initialize_mint(
+                program_id,
+                accounts,
+                decimals,
+                mint_authority,
+                freeze_authority,
+                max_supply,
+                true,
+            ),
+            TokenInstruction::Approve { amount } => {
+                Self::process_approve(program_id, accounts, amount)
+            }
+            TokenInstruction::Revoke => Self::process_revoke(program_id, accounts),
+            TokenInstruction::InitializeAccount => {
+                Self::process_initialize_account(program_id, accounts, None, true)
+            }
+            TokenInstruction::InitializeAccount2 => {
+                Self::process_initialize_account(program_id, accounts, None, false)
+            }
+            TokenInstruction::SetAuthority {
+                authority_type,
+                new_authority,
+            } => Self::process_set_authority(program_id, accounts, authority_type, new_authority),
+            TokenInstruction::TransferChecked { amount, decimals } => {
+                Self::process_transfer(program_id, accounts, amount, Some(decimals))
+            }
+            TokenInstruction::MintToChecked { amount, decimals } => {
+                Self::process_mint(program_id, accounts, amount, Some(decimals))
+            }
+            TokenInstruction::BurnChecked { amount, decimals } => {
+                Self::process_burn(program_id, accounts, amount, Some(decimals))
+            }
+            TokenInstruction::CloseAccount => Self::process_close_account(program_id, accounts),
+            TokenInstruction::FreezeAccount => {
+                Self::process_toggle_freeze_account(program_id, accounts, AccountState::Frozen)
+            }
+            TokenInstruction::ThawAccount => {
+                Self::process_toggle_freeze_account(program_id, accounts, AccountState::Initialized)
+            }
+            TokenInstruction::CreateAssociatedAccount => {
+                Self::process_create_associated_account(program_id, accounts)
+            }
+            TokenInstruction::SyncNative => Self::process_sync_native(program_id, accounts),
+            TokenInstruction::InitializeMultisig { m } => {
+                Self::process_initialize_multisig(program_id, accounts, m, true)
+            }
+            TokenInstruction::TransferBatch { amounts } => {
+                Self::process_transfer_batch(program_id, accounts, &amounts)
+            }
+            TokenInstruction::CreateMetadata { name, symbol, uri } => {
+                Self::process_create_metadata(program_id, accounts, name, symbol, uri)
+            }
+            TokenInstruction::UpdateMetadata { name, symbol, uri } => {
+                Self::process_update_metadata(program_id, accounts, name, symbol, uri)
+            }
+            TokenInstruction::InitializeTransferFeeConfig {
+                transfer_fee_authority,
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => Self::process_initialize_transfer_fee_config(
+                program_id,
+                accounts,
+                transfer_fee_authority,
+                transfer_fee_basis_points,
+                maximum_fee,
+            ),
+            TokenInstruction::HarvestWithheldTokens => {
+                Self::process_harvest_withheld_tokens(program_id, accounts)
+            }
+            TokenInstruction::WithdrawWithheldTokens => {
+                Self::process_withdraw_withheld_tokens(program_id, accounts)
+            }
+            TokenInstruction::InitializeNonTransferableMint => {
+                Self::process_initialize_non_transferable_mint(program_id, accounts)
+            }
+            TokenInstruction::LockAccount { lock_until } => {
+                Self::process_lock_account(program_id, accounts, lock_until)
+            }
+            TokenInstruction::InitializeEscrow {
+                amount,
+                expected_amount,
+            } => Self::process_initialize_escrow(program_id, accounts, amount, expected_amount),
+            TokenInstruction::Exchange {
+                amount,
+                expected_amount,
+            } => Self::process_exchange(program_id, accounts, amount, expected_amount),
+            TokenInstruction::CancelEscrow => Self::process_cancel_escrow(program_id, accounts),
+            TokenInstruction::InitializePauseConfig { pause_authority } => {
+                Self::process_initialize_pause_config(program_id, accounts, pause_authority)
+            }
+            TokenInstruction::Pause => Self::process_toggle_pause(program_id, accounts, true),
+            TokenInstruction::Resume => Self::process_toggle_pause(program_id, accounts, false),
+            TokenInstruction::InitializePermanentDelegate { delegate } => {
+                Self::process_initialize_permanent_delegate(program_id, accounts, delegate)
+            }
+            TokenInstruction::ProposeOwner { new_owner } => {
+                Self::process_propose_owner(program_id, accounts, COption::Some(new_owner))
+            }
+            TokenInstruction::AcceptOwner => Self::process_accept_owner(program_id, accounts),
+            TokenInstruction::CancelPendingOwner => {
+                Self::process_propose_owner(program_id, accounts, COption::None)
+            }
+            TokenInstruction::GetAccountBalance => {
+                Self::process_get_account_balance(program_id, accounts)
+            }
+            TokenInstruction::GetMintSupply => Self::process_get_mint_supply(program_id, accounts),
+            TokenInstruction::AmountToUiAmount { amount } => {
+                Self::process_amount_to_ui_amount(program_id, accounts, amount)
+            }
+            TokenInstruction::UiAmountToAmount { ui_amount } => {
+                Self::process_ui_amount_to_amount(program_id, accounts, &ui_amount)
+            }
+            TokenInstruction::RequireMemo => {
+                Self::process_toggle_memo_required(program_id, accounts, true)
+            }
+            TokenInstruction::UnrequireMemo => {
+                Self::process_toggle_memo_required(program_id, accounts, false)
+            }
+            TokenInstruction::InitializeTransferHook {
+                program_id: hook_program_id,
+            } => Self::process_initialize_transfer_hook(program_id, accounts, hook_program_id),
+            TokenInstruction::InitializeDefaultAccountState { state } => {
+                Self::process_initialize_default_account_state(program_id, accounts, state)
+            }
+            TokenInstruction::SetDefaultAccountState { state } => {
+                Self::process_set_default_account_state(program_id, accounts, state)
+            }
+            TokenInstruction::InitializeImmutableOwner => {
+                Self::process_initialize_immutable_owner(program_id, accounts)
+            }
+            TokenInstruction::InitializeInterestBearingMint {
+                rate_authority,
+                rate_bps,
+            } => Self::process_initialize_interest_bearing_mint(
+                program_id,
+                accounts,
+                rate_authority,
+                rate_bps,
+            ),
+            TokenInstruction::UpdateRate { rate_bps } => {
+                Self::process_update_rate(program_id, accounts, rate_bps)
+            }
+            TokenInstruction::InitializeMintCloseAuthority { close_authority } => {
+                Self::process_initialize_mint_close_authority(program_id, accounts, close_authority)
+            }
+            TokenInstruction::CloseMint => Self::process_close_mint(program_id, accounts),
+            TokenInstruction::MigrateAccount => Self::process_migrate_account(program_id, accounts),
+            TokenInstruction::InitializeRoyalty {
+                basis_points,
+                destination,
+            } => Self::process_initialize_royalty(program_id, accounts, basis_points, destination),
+            TokenInstruction::InitializeAllowlist { authority } => {
+                Self::process_initialize_allowlist(program_id, accounts, authority)
+            }
+            TokenInstruction::AddToAllowlist { owner } => {
+                Self::process_add_to_allowlist(program_id, accounts, owner)
+            }
+            TokenInstruction::RemoveFromAllowlist { owner } => {
+                Self::process_remove_from_allowlist(program_id, accounts, owner)
+            }
+            TokenInstruction::InitializeMinTransferAmount {
+                min_transfer_amount,
+            } => Self::process_initialize_min_transfer_amount(
+                program_id,
+                accounts,
+                min_transfer_amount,
+            ),
+            TokenInstruction::UpdateMinTransferAmount {
+                min_transfer_amount,
+            } => {
+                Self::process_update_min_transfer_amount(program_id, accounts, min_transfer_amount)
+            }
+            TokenInstruction::TransferAccountOwnership => {
+                Self::process_transfer_account_ownership(program_id, accounts)
+            }
+            TokenInstruction::CreateDistribution {
+                merkle_root,
+                total_claimable,
+            } => Self::process_create_distribution(
+                program_id,
+                accounts,
+                merkle_root,
+                total_claimable,
+            ),
+            TokenInstruction::Claim {
+                index,
+                amount,
+                proof,
+            } => Self::process_claim(program_id, accounts, index, amount, &proof),
+            TokenInstruction::InitializeMint2 {
+                decimals,
+                mint_authority,
+                freeze_authority,
+                max_supply,
+            } => Self::process_initialize_mint(
+                program_id,
+                accounts,
+                decimals,
+                mint_authority,
+                freeze_authority,
+                max_supply,
+                false,
+            ),
+            TokenInstruction::InitializeAccount3 { owner } => {
+                Self::process_initialize_account(program_id, accounts, Some(&owner), false)
+            }
+            TokenInstruction::InitializeMultisig2 { m } => {
+                Self::process_initialize_multisig(program_id, accounts, m, false)
+            }
+            TokenInstruction::FreezeBatch { count } => {
+                Self::process_freeze_batch(program_id, accounts, count as usize)
+            }
+            TokenInstruction::InitializeMintWithAllocations {
+                decimals,
+                freeze_authority,
+                max_supply,
+                amounts,
+            } => Self::process_initialize_mint_with_allocations(
+                program_id,
+                accounts,
+                decimals,
+                freeze_authority,
+                max_supply,
+                &amounts,
+            ),
+            TokenInstruction::InitializeActivityTracking => {
+                Self::process_initialize_activity_tracking(program_id, accounts)
+            }
+            TokenInstruction::TransferWithFee { amount, fee } => {
+                Self::process_transfer_with_fee(program_id, accounts, amount, fee)
+            }
+            TokenInstruction::CreateTokenAccount { owner, seed } => {
+                Self::process_create_token_account(program_id, accounts, owner, seed)
+            }
+            TokenInstruction::InitializeTransferCooldown { cooldown_slots } => {
+                Self::process_initialize_transfer_cooldown(program_id, accounts, cooldown_slots)
+            }
+            TokenInstruction::InitializeHolderCount => {
+                Self::process_initialize_holder_count(program_id, accounts)
+            }
+            TokenInstruction::PermitTransfer => Self::process_permit_transfer(program_id, accounts),
+            TokenInstruction::CreateVesting {
+                total_amount,
+                start_ts,
+                end_ts,
+            } => Self::process_create_vesting(program_id, accounts, total_amount, start_ts, end_ts),
+            TokenInstruction::ClaimVested => Self::process_claim_vested(program_id, accounts),
+            TokenInstruction::RepairOwner { owner } => {
+                Self::process_repair_owner(program_id, accounts, &owner)
+            }
+            TokenInstruction::RecoverNested => Self::process_recover_nested(program_id, accounts),
+            TokenInstruction::TwoPartySwap { amount_a, amount_b } => {
+                Self::process_two_party_swap(program_id, accounts, amount_a, amount_b)
+            }
+            TokenInstruction::CreateAssociatedAccountIdempotent => {
+                Self::process_create_associated_account_idempotent(program_id, accounts)
+            }
+            TokenInstruction::InitializeRecoveryAuthority { recovery_authority } => {
+                Self::process_initialize_recovery_authority(
+                    program_id,
+                    accounts,
+                    recovery_authority,
+                )
+            }
+            TokenInstruction::InitializeMetadataPointer {
+                authority,
+                metadata_address,
+            } => Self::process_initialize_metadata_pointer(
+                program_id,
+                accounts,
+                authority,
+                metadata_address,
+            ),
+            TokenInstruction::UpdateMetadataPointer { metadata_address } => {
+                Self::process_update_metadata_pointer(program_id, accounts, metadata_address)
+            }
+            TokenInstruction::CreateConverter {
+                numerator,
+                denominator,
+            } => Self::process_create_converter(program_id, accounts, numerator, denominator),
+            TokenInstruction::Convert { amount } => {
+                Self::process_convert(program_id, accounts, amount)
+            }
+            TokenInstruction::SetMinimumBalance {
+                minimum_balance,
+                enforcement_authority,
+            } => Self::process_set_minimum_balance(
+                program_id,
+                accounts,
+                minimum_balance,
+                enforcement_authority,
+            ),
+            TokenInstruction::SnapshotBalance { snapshot_id } => {
+                Self::process_snapshot_balance(program_id, accounts, snapshot_id)
+            }
+            TokenInstruction::CloseSnapshot => Self::process_close_snapshot(program_id, accounts),
+            TokenInstruction::TransferWithRelayerFee {
+                amount,
+                relayer_fee,
+            } => Self::process_transfer_with_relayer_fee(program_id, accounts, amount, relayer_fee),
+            TokenInstruction::VerifyOwnership { nonce } => {
+                Self::process_verify_ownership(program_id, accounts, nonce)
+            }
+            TokenInstruction::SetOutflowLimit {
+                max_outflow_per_instruction,
+                timelock_slots,
+            } => Self::process_set_outflow_limit(
+                program_id,
+                accounts,
+                max_outflow_per_instruction,
+                timelock_slots,
+            ),
+            TokenInstruction::AddMinter {
+                minter,
+                max_per_window,
+                window_slots,
+            } => {
+                Self::process_add_minter(program_id, accounts, minter, max_per_window, window_slots)
+            }
+            TokenInstruction::RemoveMinter { minter } => {
+                Self::process_remove_minter(program_id, accounts, minter)
+            }
+        }
+    }
+
+    pub fn process_initialize_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        decimals: u8,
+        mint_authority: Pubkey,
+        freeze_authority: COption<Pubkey>,
+        max_supply: COption<u64>,
+        rent_sysvar_account: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let rent = &if rent_sysvar_account {
+            Rent::from_account_info(next_account_info(account_info_iter)?)?
+        } else {
+            Rent::get()?
+        };
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mut mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if !rent.is_exempt(mint_account.lamports(), mint_account.data_len()) {
+            return Err(TokenError::NotRentExempt.into());
+        }
+
+        mint_data.mint_authority = COption::Some(mint_authority);
+        mint_data.decimals = decimals;
+        mint_data.is_initialized = true;
+        mint_data.freeze_authority = freeze_authority;
+        mint_data.max_supply = max_supply;
+        if mint_data.default_account_state == AccountState::Frozen && freeze_authority.is_none() {
+            return Err(TokenError::MintCannotFreeze.into());
+        }
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_initialize_account(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        owner: Option<&Pubkey>,
+        rent_sysvar_account: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let owner = match owner {
+            Some(owner) => owner,
+            None => next_account_info(account_info_iter)?.key,
+        };
+        let rent = &if rent_sysvar_account {
+            Rent::from_account_info(next_account_info(account_info_iter)?)?
+        } else {
+            Rent::get()?
+        };
+
+        check_writable(token_account, "token")?;
+
+        let is_native_mint = *mint_account.key == native_mint::id();
+        check_account_owner(program_id, token_account)?;
+        if !is_native_mint {
+            check_account_owner(program_id, mint_account)?;
+        }
+
+        let mut token_data = Token::unpack_unchecked(&token_account.try_borrow_data()?)?;
+        if token_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        let rent_exempt_reserve = rent.minimum_balance(token_account.data_len());
+        if token_account.lamports() < rent_exempt_reserve {
+            return Err(TokenError::NotRentExempt.into());
+        }
+
+        token_data.mint = *mint_account.key;
+        token_data.owner = *owner;
+        token_data.state = AccountState::Initialized;
+        if is_native_mint {
+            token_data.is_native = COption::Some(rent_exempt_reserve);
+            token_data.amount = token_account.lamports() - rent_exempt_reserve;
+        }
+        let config = if is_native_mint {
+            MintConfig::default()
+        } else {
+            let mint_bytes = mint_account.try_borrow_data()?;
+            let mint_data = Mint::unpack(&mint_bytes)?;
+            token_data.is_native = COption::None;
+            token_data.amount = 0;
+            Self::apply_mint_config(&mut token_data, &mint_data, &mint_bytes)?
+        };
+        Token::pack(token_data, &mut token_account.try_borrow_mut_data()?)?;
+        Self::write_mint_config(token_account, accounts, config)
+    }
+
+    pub fn process_initialize_immutable_owner(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "token")?;
+
+        check_account_owner(program_id, token_account)?;
+
+        let token_data = Token::unpack_unchecked(&token_account.try_borrow_data()?)?;
+        if token_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Self::write_extension(token_account, accounts, &ImmutableOwner)
+    }
+
+    pub fn process_initialize_transfer_fee_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        transfer_fee_authority: COption<Pubkey>,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mut mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if transfer_fee_basis_points > TransferFee::MAX_BASIS_POINTS {
+            return Err(TokenError::TransferFeeExceedsMaximum.into());
+        }
+
+        mint_data.transfer_fee_authority = transfer_fee_authority;
+        mint_data.transfer_fee = COption::Some(TransferFee {
+            basis_points: transfer_fee_basis_points,
+            maximum_fee,
+        });
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_initialize_non_transferable_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mut mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        mint_data.non_transferable = true;
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_initialize_pause_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pause_authority: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mut mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        mint_data.pause_authority = COption::Some(pause_authority);
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_initialize_permanent_delegate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        delegate: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mut mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        mint_data.permanent_delegate = COption::Some(delegate);
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_initialize_recovery_authority(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        recovery_authority: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Self::write_mint_extension(
+            mint_account,
+            accounts,
+            &RecoveryAuthority { recovery_authority },
+        )
+    }
+
+    pub fn process_initialize_metadata_pointer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        authority: COption<Pubkey>,
+        metadata_address: COption<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Self::write_mint_extension(
+            mint_account,
+            accounts,
+            &MetadataPointer {
+                authority,
+                metadata_address,
+            },
+        )
+    }
+
+    pub fn process_update_metadata_pointer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        metadata_address: COption<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_bytes = mint_account.try_borrow_data()?;
+        Mint::unpack(&mint_bytes)?;
+        let Some(mut pointer) = extension::get_mint_extension::<MetadataPointer>(&mint_bytes)?
+        else {
+            return Err(TokenError::InvalidState.into());
+        };
+        drop(mint_bytes);
+        match pointer.authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                authority_account,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::AuthorityTypeNotSupported.into()),
+        }
+
+        pointer.metadata_address = metadata_address;
+        extension::init_mint_extension(&mut mint_account.try_borrow_mut_data()?, &pointer)
+    }
+
+    pub fn process_initialize_transfer_hook(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        hook_program_id: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+        // The runtime already rejects a hook that calls back into this
+        // program; one that *is* this program would be invoked directly.
+        if hook_program_id == *program_id {
+            return Err(TokenError::InvalidTransferHook.into());
+        }
+
+        let mut mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        mint_data.transfer_hook_program = COption::Some(hook_program_id);
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_initialize_default_account_state(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        state: AccountState,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+        if state == AccountState::Uninitialized {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        mint_data.default_account_state = state;
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_set_default_account_state(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        state: AccountState,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+        if state == AccountState::Uninitialized {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        match mint_data.freeze_authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                authority_account,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::MintCannotFreeze.into()),
+        }
+
+        mint_data.default_account_state = state;
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        log_event(&TokenEvent::ConfigChanged(ConfigChangedEvent {
+            account: *mint_account.key,
+            authority: *authority_account.key,
+            change: ConfigChange::DefaultAccountState(state),
+        }));
+
+        Ok(())
+    }
+
+    pub fn process_initialize_interest_bearing_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        rate_authority: COption<Pubkey>,
+        rate_bps: i16,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mut mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        mint_data.rate_authority = rate_authority;
+        mint_data.interest_rate = COption::Some(InterestRate {
+            current_rate_bps: rate_bps,
+            last_update_timestamp: Clock::get()?.unix_timestamp,
+            accrued_scale: interest::WAD,
+        });
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_update_rate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        rate_bps: i16,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mut mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        let COption::Some(interest_rate) = mint_data.interest_rate else {
+            return Err(TokenError::InvalidState.into());
+        };
+        match mint_data.rate_authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                authority_account,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::AuthorityTypeNotSupported.into()),
+        }
+
+        let unix_timestamp = Clock::get()?.unix_timestamp;
+        mint_data.interest_rate = COption::Some(InterestRate {
+            current_rate_bps: rate_bps,
+            last_update_timestamp: unix_timestamp,
+            accrued_scale: interest_rate.scale_at(unix_timestamp)?,
+        });
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        log_event(&TokenEvent::ConfigChanged(ConfigChangedEvent {
+            account: *mint_account.key,
+            authority: *authority_account.key,
+            change: ConfigChange::InterestRate(rate_bps),
+        }));
+
+        Ok(())
+    }
+
+    pub fn process_toggle_pause(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        paused: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mut mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        let recovery_authority = if paused {
+            None
+        } else {
+            Self::recovery_authority(mint_account, &mint_data.pause_authority, authority_account)?
+        };
+        let authority = match (recovery_authority, mint_data.pause_authority) {
+            (Some(recovery_authority), _) => recovery_authority,
+            (None, COption::Some(authority)) => authority,
+            (None, COption::None) => return Err(TokenError::MintCannotPause.into()),
+        };
+        Self::validate_owner(
+            program_id,
+            &authority,
+            authority_account,
+            account_info_iter.as_slice(),
+        )?;
+        if mint_data.paused == paused {
+            return Err(TokenError::InvalidState.into());
+        }
+
+        mint_data.paused = paused;
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        log_event(&TokenEvent::ConfigChanged(ConfigChangedEvent {
+            account: *mint_account.key,
+            authority: *authority_account.key,
+            change: if paused {
+                ConfigChange::Paused
+            } else {
+                ConfigChange::Resumed
+            },
+        }));
+        if let Some(recovery_authority) = recovery_authority {
+            log_event(&TokenEvent::RecoveryUsed(RecoveryUsedEvent {
+                account: *mint_account.key,
+                recovery_authority,
+                action: RecoveryAction::Resume,
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// The mint's recovery authority, if `authority_account` is it and the
+    /// mint's `primary` authority isn't the same key. Only thawing and
+    /// resuming accept it.
+    fn recovery_authority(
+        mint_account: &AccountInfo,
+        primary: &COption<Pubkey>,
+        authority_account: &AccountInfo,
+    ) -> Result<Option<Pubkey>, ProgramError> {
+        let recovery =
+            extension::get_mint_extension::<RecoveryAuthority>(&mint_account.try_borrow_data()?)?;
+        Ok(match recovery {
+            Some(RecoveryAuthority { recovery_authority })
+                if cmp_pubkeys(&recovery_authority, authority_account.key)
+                    && *primary != COption::Some(recovery_authority) =>
+            {
+                Some(recovery_authority)
+            }
+            _ => None,
+        })
+    }
+
+    pub fn process_harvest_withheld_tokens(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mut mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        // Each account is harvested once however often it is listed, and
+        // nothing is written until every account has been read.
+        let mut harvested: Vec<(&AccountInfo, Token)> = Vec::new();
+        for token_account in account_info_iter {
+            check_writable(token_account, "harvested")?;
+            check_distinct(token_account, &[mint_account])?;
+            if harvested
+                .iter()
+                .any(|(seen, _)| cmp_pubkeys(seen.key, token_account.key))
+            {
+                continue;
+            }
+            check_account_owner(program_id, token_account)?;
+            let mut token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+            if token_data.mint != *mint_account.key {
+                return Err(TokenError::MintMismatch.into());
+            }
+            if let COption::Some(withheld_amount) = token_data.withheld_amount {
+                mint_data.withheld_amount = mint_data
+                    .withheld_amount
+                    .checked_add(withheld_amount)
+                    .ok_or(TokenError::Overflow)?;
+                token_data.withheld_amount = COption::Some(0);
+                harvested.push((token_account, token_data));
+            }
+        }
+        for (token_account, token_data) in harvested {
+            Token::pack(token_data, &mut token_account.try_borrow_mut_data()?)?;
+        }
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_withdraw_withheld_tokens(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let destination_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+        check_writable(destination_account, "destination")?;
+
+        check_account_owner(program_id, mint_account)?;
+        check_account_owner(program_id, destination_account)?;
+
+        let mut mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        match mint_data.transfer_fee_authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                authority_account,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::AuthorityTypeNotSupported.into()),
+        }
+
+        let mut destination_data = Token::unpack(&destination_account.try_borrow_data()?)?;
+        if destination_data.mint != *mint_account.key {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if destination_data.state == AccountState::Frozen {
+            return Err(TokenError::AccountFrozen.into());
+        }
+
+        let starting_amount = destination_data.amount;
+        destination_data.amount = destination_data
+            .amount
+            .checked_add(mint_data.withheld_amount)
+            .ok_or(TokenError::Overflow)?;
+        mint_data.withheld_amount = 0;
+        Self::count_holders(
+            mint_account,
+            holder_change(starting_amount, destination_data.amount),
+        )?;
+
+        Token::pack(
+            destination_data,
+            &mut destination_account.try_borrow_mut_data()?,
+        )?;
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_initialize_multisig(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        m: u8,
+        rent_sysvar_account: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let multisig_account = next_account_info(account_info_iter)?;
+        let rent = &if rent_sysvar_account {
+            Rent::from_account_info(next_account_info(account_info_iter)?)?
+        } else {
+            Rent::get()?
+        };
+
+        check_writable(multisig_account, "multisig")?;
+
+        check_account_owner(program_id, multisig_account)?;
+
+        let mut multisig = Multisig::unpack_unchecked(&multisig_account.try_borrow_data()?)?;
+        if multisig.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if !rent.is_exempt(multisig_account.lamports(), multisig_account.data_len()) {
+            return Err(TokenError::NotRentExempt.into());
+        }
+
+        let signer_accounts = account_info_iter.as_slice();
+        if !is_valid_signer_index(signer_accounts.len()) {
+            return Err(TokenError::InvalidNumberOfProvidedSigners.into());
+        }
+        if !is_valid_signer_index(m as usize) || m as usize > signer_accounts.len() {
+            return Err(TokenError::InvalidNumberOfRequiredSigners.into());
+        }
+
+        multisig.m = m;
+        multisig.n = signer_accounts.len() as u8;
+        for (slot, signer_account) in multisig.signers.iter_mut().zip(signer_accounts) {
+            *slot = *signer_account.key;
+        }
+        multisig.is_initialized = true;
+        Multisig::pack(multisig, &mut multisig_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_create_associated_account(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_account = next_account_info(account_info_iter)?;
+        let associated_account = next_account_info(account_info_iter)?;
+        let wallet_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(payer_account, "payer")?;
+
+        let mint_data = if *mint_account.key == native_mint::id() {
+            None
+        } else {
+            check_account_owner(program_id, mint_account)?;
+            Some(Mint::unpack(&mint_account.try_borrow_data()?)?)
+        };
+        let token_data = Self::create_associated_token_account(
+            program_id,
+            payer_account,
+            associated_account,
+            wallet_account,
+            mint_account,
+            mint_data.as_ref(),
+            system_program_account,
+            &Rent::get()?,
+        )?;
+        Token::pack(token_data, &mut associated_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_create_associated_account_idempotent(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let _payer_account = next_account_info(account_info_iter)?;
+        let associated_account = next_account_info(account_info_iter)?;
+        let wallet_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+
+        if !cmp_pubkeys(associated_account.owner, program_id) {
+            return Self::process_create_associated_account(program_id, accounts);
+        }
+        let (associated_address, _) = get_associated_token_address_and_bump_seed(
+            wallet_account.key,
+            mint_account.key,
+            program_id,
+        );
+        if associated_address != *associated_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let token_data = Token::unpack(&associated_account.try_borrow_data()?)?;
+        if !cmp_pubkeys(&token_data.mint, mint_account.key) {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if !cmp_pubkeys(&token_data.owner, wallet_account.key) {
+            return Err(TokenError::OwnerMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Creates the associated token account of `wallet_account` for
+    /// `mint_account`, funded by `payer_account`, and returns its initial
+    /// state for the caller to adjust and pack. `mint_data` is `None` for
+    /// the native mint.
+    #[allow(clippy::too_many_arguments)]
+    fn create_associated_token_account<'a>(
+        program_id: &Pubkey,
+        payer_account: &AccountInfo<'a>,
+        associated_account: &AccountInfo<'a>,
+        wallet_account: &AccountInfo<'a>,
+        mint_account: &AccountInfo<'a>,
+        mint_data: Option<&Mint>,
+        system_program_account: &AccountInfo<'a>,
+        rent: &Rent,
+    ) -> Result<Token, ProgramError> {
+        check_writable(associated_account, "associated token")?;
+
+        let (associated_address, bump_seed) = get_associated_token_address_and_bump_seed(
+            wallet_account.key,
+            mint_account.key,
+            program_id,
+        );
+        if associated_address != *associated_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        // Lamports sent to the address beforehand don't stop its creation;
+        // only an account this program already owns does.
+        Self::create_program_account(
+            program_id,
+            &[
+                payer_account.clone(),
+                associated_account.clone(),
+                system_program_account.clone(),
+            ],
+            Token::LEN,
+            rent,
+            &[&[
+                &wallet_account.key.to_bytes(),
+                &program_id.to_bytes(),
+                &mint_account.key.to_bytes(),
+                &[bump_seed],
+            ]],
+        )?;
+
+        let mut token_data = Token {
+            mint: *mint_account.key,
+            owner: *wallet_account.key,
+            state: AccountState::Initialized,
+            ..Token::default()
+        };
+        match mint_data {
+            Some(mint_data) => {
+                let config = Self::apply_mint_config(
+                    &mut token_data,
+                    mint_data,
+                    &mint_account.try_borrow_data()?,
+                )?;
+                Self::write_mint_config(
+                    associated_account,
+                    &[payer_account.clone(), system_program_account.clone()],
+                    config,
+                )?;
+            }
+            None => {
+                let rent_exempt_reserve = rent.minimum_balance(Token::LEN);
+                token_data.is_native = COption::Some(rent_exempt_reserve);
+                token_data.amount = associated_account.lamports() - rent_exempt_reserve;
+            }
+        }
+        Ok(token_data)
+    }
+
+    pub fn process_create_token_account(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        owner: Pubkey,
+        seed: COption<u64>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_account = next_account_info(account_info_iter)?;
+        let token_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "token")?;
+        check_distinct(payer_account, &[token_account])?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let mint_data = if *mint_account.key == native_mint::id() {
+            None
+        } else {
+            check_account_owner(program_id, mint_account)?;
+            Some(Mint::unpack(&mint_account.try_borrow_data()?)?)
+        };
+
+        let rent = Rent::get()?;
+        let accounts = [
+            payer_account.clone(),
+            token_account.clone(),
+            system_program_account.clone(),
+        ];
+        match seed {
+            COption::Some(seed) => {
+                let (seeded_address, bump_seed) = get_seeded_token_address_and_bump_seed(
+                    &owner,
+                    mint_account.key,
+                    seed,
+                    program_id,
+                );
+                if seeded_address != *token_account.key {
+                    return Err(ProgramError::InvalidSeeds);
+                }
+                Self::create_program_account(
+                    program_id,
+                    &accounts,
+                    Token::LEN,
+                    &rent,
+                    &[&[
+                        SEEDED_TOKEN_SEED,
+                        &owner.to_bytes(),
+                        &mint_account.key.to_bytes(),
+                        &seed.to_le_bytes(),
+                        &[bump_seed],
+                    ]],
+                )?;
+            }
+            COption::None => {
+                check_is_signer(token_account, "token")?;
+                Self::create_program_account(program_id, &accounts, Token::LEN, &rent, &[])?;
+            }
+        }
+
+        let rent_exempt_reserve = rent.minimum_balance(Token::LEN);
+        let mut token_data = Token {
+            mint: *mint_account.key,
+            owner,
+            state: AccountState::Initialized,
+            ..Token::default()
+        };
+        let config = match mint_data {
+            Some(mint_data) => Self::apply_mint_config(
+                &mut token_data,
+                &mint_data,
+                &mint_account.try_borrow_data()?,
+            )?,
+            None => {
+                token_data.is_native = COption::Some(rent_exempt_reserve);
+                token_data.amount = token_account.lamports() - rent_exempt_reserve;
+                MintConfig::default()
+            }
+        };
+        Token::pack(token_data, &mut token_account.try_borrow_mut_data()?)?;
+        Self::write_mint_config(
+            token_account,
+            &[payer_account.clone(), system_program_account.clone()],
+            config,
+        )
+    }
+
+    /// Gives the second of `accounts`, the payer, account to create and
+    /// system program, `space` bytes owned by this program and funds it to
+    /// rent exemption from the payer. `create_account` refuses addresses
+    /// that already hold lamports, so those are topped up, allocated and
+    /// assigned instead. `signers_seeds` sign for a program address; a
+    /// keypair address signs the transaction and passes none, which makes
+    /// `invoke_signed` a plain `invoke`.
+    fn create_program_account(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo; 3],
+        space: usize,
+        rent: &Rent,
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let [payer_account, new_account, _] = accounts;
+        if *new_account.owner != system_program::id() || !new_account.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        let required_lamports = rent.minimum_balance(space);
+        if new_account.lamports() == 0 {
+            check_payer(payer_account, required_lamports)?;
+            return invoke_signed(
+                &system_instruction::create_account(
+                    payer_account.key,
+                    new_account.key,
+                    required_lamports,
+                    space as u64,
+                    program_id,
+                ),
+                accounts,
+                signers_seeds,
+            );
+        }
+
+        let shortfall = required_lamports.saturating_sub(new_account.lamports());
+        if shortfall > 0 {
+            check_payer(payer_account, shortfall)?;
+            invoke(
+                &system_instruction::transfer(payer_account.key, new_account.key, shortfall),
+                accounts,
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(new_account.key, space as u64),
+            accounts,
+            signers_seeds,
+        )?;
+        invoke_signed(
+            &system_instruction::assign(new_account.key, program_id),
+            accounts,
+            signers_seeds,
+        )
+    }
+
+    pub fn process_initialize_mint_with_allocations(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        decimals: u8,
+        freeze_authority: COption<Pubkey>,
+        max_supply: COption<u64>,
+        amounts: &[u64],
+    ) -> ProgramResult {
+        if amounts.len() > MAX_ALLOCATIONS {
+            return Err(TokenError::TooManyItems.into());
+        }
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let mint_authority = next_account_info(account_info_iter)?;
+        let payer_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+        // Each allocation passes its associated token account, then the
+        // wallet that will own it.
+        let allocation_accounts = account_info_iter.as_slice();
+        if allocation_accounts.len() < 2 * amounts.len() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        if allocation_accounts.len() > 2 * amounts.len() {
+            return Err(TokenError::TooManyAccounts.into());
+        }
+
+        check_is_signer(mint_authority, "mint authority")?;
+        check_writable(payer_account, "payer")?;
+
+        Self::process_initialize_mint(
+            program_id,
+            std::slice::from_ref(mint_account),
+            decimals,
+            *mint_authority.key,
+            freeze_authority,
+            max_supply,
+            false,
+        )?;
+        let mut mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        // The allowlist of a new mint is empty, so no one may hold it yet.
+        if extension::get_mint_extension::<AllowlistEnforcement>(&mint_account.try_borrow_data()?)?
+            .is_some()
+        {
+            return Err(TokenError::NotAllowlisted.into());
+        }
+        mint_data.supply = amounts
+            .iter()
+            .try_fold(0u64, |supply, amount| supply.checked_add(*amount))
+            .ok_or(TokenError::Overflow)?;
+        if let COption::Some(max_supply) = mint_data.max_supply {
+            if mint_data.supply > max_supply {
+                return Err(TokenError::SupplyCapExceeded.into());
+            }
+        }
+
+        let rent = Rent::get()?;
+        for (allocation, &amount) in allocation_accounts.chunks_exact(2).zip(amounts) {
+            let (associated_account, wallet_account) = (&allocation[0], &allocation[1]);
+            let mut token_data = Self::create_associated_token_account(
+                program_id,
+                payer_account,
+                associated_account,
+                wallet_account,
+                mint_account,
+                Some(&mint_data),
+                system_program_account,
+                &rent,
+            )?;
+            if token_data.state == AccountState::Frozen {
+                return Err(TokenError::AccountFrozen.into());
+            }
+            token_data.amount = amount;
+            Token::pack(token_data, &mut associated_account.try_borrow_mut_data()?)?;
+            Self::count_holders(mint_account, holder_change(0, amount))?;
+
+            log_event(&TokenEvent::Mint(MintEvent {
+                mint: *mint_account.key,
+                destination: *associated_account.key,
+                amount,
+            }));
+        }
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_mint(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        expected_decimals: Option<u8>,
+    ) -> ProgramResult {
+        let MintToAccounts {
+            mint: mint_account,
+            destination: token_account,
+            authority: mint_authority,
+            remaining,
+        } = MintToAccounts::load(accounts, program_id)?;
+
+        // The destination is checked before the authority, as spl-token does.
+        let (token_data, token_account) = load_token_account(token_account, program_id)?;
+        if !cmp_pubkeys(&token_data.mint, mint_account.key) {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if token_data.state == AccountState::Frozen {
+            return Err(TokenError::AccountFrozen.into());
+        }
+
+        let (mut mint_data, mint_account) = load_mint(mint_account, program_id)?;
+        let mut minters =
+            extension::get_mint_extension::<Minters>(&mint_account.try_borrow_data()?)?;
+        // A listed minter signs in place of the mint authority.
+        let minter_index = match mint_data.mint_authority {
+            COption::Some(authority) if cmp_pubkeys(&authority, mint_authority.key) => {
+                Self::validate_owner(program_id, &authority, mint_authority, remaining)?;
+                None
+            }
+            COption::Some(authority) => {
+                let index = minters.as_ref().and_then(|minters| {
+                    minters
+                        .minters
+                        .iter()
+                        .position(|minter| cmp_pubkeys(&minter.minter, mint_authority.key))
+                });
+                let expected = index.map_or(authority, |_| *mint_authority.key);
+                Self::validate_owner(program_id, &expected, mint_authority, remaining)?;
+                index
+            }
+            COption::None => return Err(TokenError::FixedSupply.into()),
+        };
+        if let Some(expected_decimals) = expected_decimals {
+            if expected_decimals != mint_data.decimals {
+                return Err(TokenError::MintDecimalsMismatch.into());
+            }
+        }
+        if mint_data.paused {
+            return Err(TokenError::MintPaused.into());
+        }
+        if extension::get_mint_extension::<AllowlistEnforcement>(&mint_account.try_borrow_data()?)?
+            .is_some()
+        {
+            Self::check_allowlisted(program_id, mint_account.key, &token_data.owner, remaining)?;
+        }
+
+        let token_amount = token_data
+            .amount
+            .checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+        mint_data.supply = mint_data
+            .supply
+            .checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+        Self::check_supply_cap(&mint_data, Self::vesting_reserved(mint_account)?)?;
+        if let Some((minters, index)) = minters.as_mut().zip(minter_index) {
+            let minter = &mut minters.minters[index];
+            let slot = if minter.window_slots == 0 {
+                0
+            } else {
+                Clock::get()?.slot
+            };
+            if !minter.record_mint(amount, slot) {
+                return Err(TokenError::MinterCapExceeded.into());
+            }
+        }
+
+        Self::record_activity(mint_account, false)?;
+        Self::count_holders(mint_account, holder_change(token_data.amount, token_amount))?;
+
+        log_debug!(
+            "Supply of mint {} is now {}",
+            mint_account.key,
+            UiAmount::new(mint_data.supply, mint_data.decimals)
+        );
+        Token::write_amount(&mut token_account.try_borrow_mut_data()?, token_amount)?;
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+        if let Some(minters) = minters.filter(|_| minter_index.is_some()) {
+            extension::init_mint_extension(&mut mint_account.try_borrow_mut_data()?, &minters)?;
+        }
+
+        log_event(&TokenEvent::Mint(MintEvent {
+            mint: *mint_account.key,
+            destination: *token_account.key,
+            amount,
+        }));
+        if minter_index.is_some() {
+            log_event(&TokenEvent::MinterUsed(MinterUsedEvent {
+                mint: *mint_account.key,
+                minter: *mint_authority.key,
+                amount,
+            }));
+        }
+
+        Ok(())
+    }
+
+    pub fn process_transfer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        expected_decimals: Option<u8>,
+    ) -> ProgramResult {
+        Self::transfer(program_id, accounts, amount, expected_decimals, false)
+    }
+
+    /// `process_transfer`, or, if `continues_transfer`, a further movement
+    /// from a source that has just sent one in the same instruction, which
+    /// neither a cooldown nor a minimum transfer amount applies to again.
+    fn transfer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        expected_decimals: Option<u8>,
+        continues_transfer: bool,
+    ) -> ProgramResult {
+        let TransferAccounts {
+            source: source_account,
+            mint: mint_account,
+            destination: destination_account,
+            authority: authority_account,
+            remaining: remaining_accounts,
+        } = TransferAccounts::load(accounts, program_id, expected_decimals.is_some())?;
+        let expected_mint_info = mint_account.zip(expected_decimals);
+        #[cfg(feature = "bytemuck")]
+        if expected_mint_info.is_none()
+            && Self::transfer_in_place(
+                program_id,
+                source_account,
+                destination_account,
+                authority_account,
+                amount,
+            )?
+        {
+            return Ok(());
+        }
+
+        let (mut source_data, source_account) = load_token_account(source_account, program_id)?;
+        let source_bytes = source_account.try_borrow_data()?;
+        if source_data.is_non_transferable {
+            return Err(TokenError::NonTransferable.into());
+        }
+        let amount = if amount == AMOUNT_ALL {
+            source_data.amount
+        } else {
+            amount
+        };
+        let mint_data = match expected_mint_info {
+            Some((mint_account, _)) => {
+                check_distinct(mint_account, &[source_account, destination_account])?;
+                if !cmp_pubkeys(mint_account.key, &source_data.mint) {
+                    return Err(TokenError::MintMismatch.into());
+                }
+                if source_data.is_native() {
+                    None
+                } else {
+                    Some(load_mint(mint_account, program_id)?.0)
+                }
+            }
+            None => None,
+        };
+        // The accounts are checked before the authority, as spl-token does.
+        let (mut destination_data, destination_account) =
+            load_token_account(destination_account, program_id)?;
+        if source_data.state == AccountState::Frozen
+            || destination_data.state == AccountState::Frozen
+        {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        if source_data.amount < amount {
+            return Err(TokenError::InsufficientFunds.into());
+        }
+        if !cmp_pubkeys(&source_data.mint, &destination_data.mint) {
+            return Err(TokenError::MintMismatch.into());
+        }
+        // Only `TransferChecked` loads the mint, so only it accepts the
+        // permanent delegate.
+        let is_permanent_delegate = mint_data.as_ref().is_some_and(|mint_data| {
+            mint_data.permanent_delegate == COption::Some(*authority_account.key)
+        });
+        let transfer_hook_program = mint_data
+            .as_ref()
+            .and_then(|mint_data| Option::from(mint_data.transfer_hook_program));
+        let royalty = match (expected_mint_info, &mint_data) {
+            (Some((mint_account, _)), Some(_)) => {
+                extension::get_mint_extension::<Royalty>(&mint_account.try_borrow_data()?)?
+            }
+            _ => None,
+        };
+        // The hook program and its extra accounts follow the signers.
+        let (signers, hook_accounts) = match transfer_hook_program {
+            Some(hook_program_id) => remaining_accounts.split_at(
+                remaining_accounts
+                    .iter()
+                    .position(|account| *account.key == hook_program_id)
+                    .ok_or(ProgramError::NotEnoughAccountKeys)?,
+            ),
+            None => (remaining_accounts, &[][..]),
+        };
+        let royalty_account = match royalty {
+            Some(ref royalty) => Some(
+                signers
+                    .iter()
+                    .find(|account| cmp_pubkeys(account.key, &royalty.destination))
+                    .ok_or(TokenError::RoyaltyAccountMissing)?,
+            ),
+            None => None,
+        };
+        let mut by_delegate = false;
+        match source_data.delegate {
+            _ if is_permanent_delegate => Self::validate_owner(
+                program_id,
+                authority_account.key,
+                authority_account,
+                signers,
+            )?,
+            COption::Some(ref delegate) if cmp_pubkeys(authority_account.key, delegate) => {
+                Self::validate_owner(program_id, delegate, authority_account, signers)?;
+                by_delegate = true;
+                source_data.delegated_amount = source_data
+                    .delegated_amount
+                    .checked_sub(amount)
+                    .ok_or(TokenError::InsufficientFunds)?;
+                if source_data.delegated_amount == 0 {
+                    source_data.delegate = COption::None;
+                }
+            }
+            _ => Self::validate_owner(program_id, &source_data.owner, authority_account, signers)?,
+        };
+        Self::check_unlocked(&source_bytes)?;
+        Self::check_minimum_balance(&source_bytes, source_data.amount, amount, accounts)?;
+        Self::check_outflow_limit(&source_bytes, amount)?;
+        let source_settings = extension::get_extension::<MintSettings>(&source_bytes)?;
+        let source_cooldown = extension::get_extension::<Cooldown>(&source_bytes)?;
+        drop(source_bytes);
+
+        let self_transfer = cmp_pubkeys(source_account.key, destination_account.key);
+        let destination_bytes = destination_account.try_borrow_data()?;
+        if Self::memo_required(&destination_bytes)? {
+            Self::check_memo_precedes(accounts)?;
+        }
+        let destination_settings = extension::get_extension::<MintSettings>(&destination_bytes)?;
+        drop(destination_bytes);
+        if destination_settings.is_some_and(|settings| settings.enforce_allowlist) {
+            Self::check_allowlisted(
+                program_id,
+                &destination_data.mint,
+                &destination_data.owner,
+                signers,
+            )?;
+        }
+        if let Some(royalty_account) = royalty_account {
+            check_writable(royalty_account, "royalty")?;
+            let (royalty_data, _) = load_token_account(royalty_account, program_id)?;
+            if !cmp_pubkeys(&royalty_data.mint, &source_data.mint) {
+                return Err(TokenError::MintMismatch.into());
+            }
+            if royalty_data.state == AccountState::Frozen {
+                return Err(TokenError::AccountFrozen.into());
+            }
+        }
+
+        let mut fee = 0;
+        let mut stamped_cooldown = None;
+        if let Some((mint_account, expected_decimals)) = expected_mint_info {
+            let decimals = match &mint_data {
+                Some(mint_data) => {
+                    if mint_data.paused {
+                        return Err(TokenError::MintPaused.into());
+                    }
+                    let mint_bytes = mint_account.try_borrow_data()?;
+                    if extension::get_mint_extension::<MintActivity>(&mint_bytes)?.is_some()
+                        || extension::get_mint_extension::<HolderCount>(&mint_bytes)?.is_some()
+                    {
+                        check_writable(mint_account, "mint")?;
+                    }
+                    drop(mint_bytes);
+                    if let Some(transfer_cooldown) =
+                        extension::get_mint_extension::<TransferCooldown>(
+                            &mint_account.try_borrow_data()?,
+                        )?
+                    {
+                        let mut cooldown = source_cooldown.ok_or(TokenError::InvalidState)?;
+                        let slot = Clock::get()?.slot;
+                        if !continues_transfer
+                            && cooldown.last_outgoing_slot > 0
+                            && slot
+                                < cooldown
+                                    .last_outgoing_slot
+                                    .saturating_add(transfer_cooldown.cooldown_slots)
+                        {
+                            return Err(TokenError::CooldownActive.into());
+                        }
+                        cooldown.last_outgoing_slot = slot;
+                        stamped_cooldown = Some(cooldown);
+                    }
+                    if let Some(minimum) = extension::get_mint_extension::<MinTransferAmount>(
+                        &mint_account.try_borrow_data()?,
+                    )? {
+                        // Emptying the source is always allowed, so accounts
+                        // can be closed.
+                        if !continues_transfer
+                            && (amount == 0
+                                || (amount < minimum.min_transfer_amount
+                                    && amount != source_data.amount))
+                        {
+                            return Err(TokenError::AmountBelowMinimum.into());
+                        }
+                    }
+                    if let COption::Some(transfer_fee) = mint_data.transfer_fee {
+                        fee = transfer_fee
+                            .calculate_fee(amount)
+                            .ok_or(TokenError::Overflow)?;
+                    }
+                    mint_data.decimals
+                }
+                None => native_mint::DECIMALS,
+            };
+            if expected_decimals != decimals {
+                return Err(TokenError::MintDecimalsMismatch.into());
+            }
+        } else if source_data.withheld_amount.is_some()
+            || source_data.is_pausable
+            || source_data.has_transfer_hook
+            || source_cooldown.is_some()
+            || source_settings.is_some_and(|settings| settings.requires_mint())
+        {
+            return Err(TokenError::MintRequiredForTransfer.into());
+        }
+        let royalty_amount = match royalty {
+            Some(royalty) => royalty
+                .calculate_royalty(amount - fee)
+                .ok_or(TokenError::Overflow)?,
+            None => 0,
+        };
+
+        source_data.amount = source_data
+            .amount
+            .checked_sub(amount)
+            .ok_or(TokenError::InsufficientFunds)?;
+
+        // Self-transfers are validated like any other transfer but leave the
+        // account untouched; repacking both copies would credit it twice.
+        if self_transfer {
+            return Ok(());
+        }
+
+        destination_data.amount = destination_data
+            .amount
+            .checked_add(amount - fee - royalty_amount)
+            .ok_or(TokenError::Overflow)?;
+        if fee > 0 {
+            let withheld_amount = match destination_data.withheld_amount {
+                COption::Some(withheld_amount) => withheld_amount,
+                COption::None => return Err(TokenError::InvalidState.into()),
+            };
+            destination_data.withheld_amount = COption::Some(
+                withheld_amount
+                    .checked_add(fee)
+                    .ok_or(TokenError::Overflow)?,
+            );
+        }
+
+        if source_data.is_native() {
+            let source_starting_lamports = source_account.lamports();
+            **source_account.try_borrow_mut_lamports()? = source_starting_lamports
+                .checked_sub(amount)
+                .ok_or(TokenError::Overflow)?;
+            let destination_starting_lamports = destination_account.lamports();
+            **destination_account.try_borrow_mut_lamports()? = destination_starting_lamports
+                .checked_add(amount)
+                .ok_or(TokenError::Overflow)?;
+        }
+
+        // The royalty account may also be the source or destination, so all
+        // three balances are read before anything is written.
+        let mut balances = Balances::new();
+        balances.debit(source_account, amount)?;
+        balances.credit(destination_account, amount - fee - royalty_amount)?;
+        if let Some(royalty_account) = royalty_account.filter(|_| royalty_amount > 0) {
+            balances.credit(royalty_account, royalty_amount)?;
+        }
+
+        // Unless a delegate spent its allowance, only the source's amount
+        // changes, and without a fee only the destination's.
+        let mint = source_data.mint;
+        if by_delegate {
+            Token::pack(source_data, &mut source_account.try_borrow_mut_data()?)?;
+        }
+        if let Some(cooldown) = stamped_cooldown {
+            extension::init_extension(&mut source_account.try_borrow_mut_data()?, &cooldown)?;
+        }
+        if fee > 0 {
+            Token::pack(
+                destination_data,
+                &mut destination_account.try_borrow_mut_data()?,
+            )?;
+        }
+        let holder_change = balances.holder_change();
+        balances.commit()?;
+        if let (Some((mint_account, _)), Some(_)) = (expected_mint_info, mint_data) {
+            Self::record_activity(mint_account, true)?;
+            Self::count_holders(mint_account, holder_change)?;
+        }
+        // Unchecked transfers don't pass the mint, so their amounts are
+        // logged raw.
+        let decimals = expected_mint_info.map_or(0, |(_, decimals)| decimals);
+        log_debug!(
+            "Moved {} of mint {}: {} withheld as fee, {} paid as royalty",
+            UiAmount::new(amount, decimals),
+            mint,
+            UiAmount::new(fee, decimals),
+            UiAmount::new(royalty_amount, decimals)
+        );
+
+        log_event(&TokenEvent::Transfer(TransferEvent {
+            source: *source_account.key,
+            destination: *destination_account.key,
+            authority: *authority_account.key,
+            amount,
+        }));
+        if let Some(hook_program_id) = transfer_hook_program {
+            Self::invoke_transfer_hook(
+                &hook_program_id,
+                source_account,
+                destination_account,
+                authority_account,
+                hook_accounts,
+                amount,
+            )?;
+        }
+        if is_permanent_delegate {
+            log_event(&TokenEvent::Clawback(ClawbackEvent {
+                mint,
+                source: *source_account.key,
+                permanent_delegate: *authority_account.key,
+                amount,
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Moves `amount` between two plain token accounts by rewriting their
+    /// amounts in place, without unpacking either. Returns `false`, having
+    /// changed nothing, for any transfer the full path has to handle:
+    /// accounts with extensions, set flags or an older layout, a delegate or
+    /// multisig authority, a self-transfer, or one that would fail.
+    #[cfg(feature = "bytemuck")]
+    fn transfer_in_place(
+        program_id: &Pubkey,
+        source_account: &AccountInfo,
+        destination_account: &AccountInfo,
+        authority_account: &AccountInfo,
+        amount: u64,
+    ) -> Result<bool, ProgramError> {
+        if cmp_pubkeys(source_account.key, destination_account.key)
+            || !authority_account.is_signer
+            || Self::is_multisig(program_id, authority_account)
+            || source_account.data_len() != Token::LEN
+            || destination_account.data_len() != Token::LEN
+        {
+            return Ok(false);
+        }
+        let mut source_bytes = source_account.try_borrow_mut_data()?;
+        let mut destination_bytes = destination_account.try_borrow_mut_data()?;
+        let (Some(source), Some(destination)) = (
+            PodToken::from_bytes_mut(&mut source_bytes),
+            PodToken::from_bytes_mut(&mut destination_bytes),
+        ) else {
+            return Ok(false);
+        };
+        if !source.is_plain()
+            || !destination.is_plain()
+            || !cmp_pubkeys(&source.owner, authority_account.key)
+            || source
+                .delegate
+                .get()
+                .is_some_and(|delegate| cmp_pubkeys(delegate, authority_account.key))
+            || !cmp_pubkeys(&source.mint, &destination.mint)
+        {
+            return Ok(false);
+        }
+        let amount = if amount == AMOUNT_ALL {
+            source.amount()
+        } else {
+            amount
+        };
+        let (Some(source_amount), Some(destination_amount)) = (
+            source.amount().checked_sub(amount),
+            destination.amount().checked_add(amount),
+        ) else {
+            return Ok(false);
+        };
+        source.set_amount(source_amount);
+        destination.set_amount(destination_amount);
+
+        log_debug!(
+            "Moved {} of mint {}: {} withheld as fee, {} paid as royalty",
+            UiAmount::new(amount, 0),
+            source.mint,
+            UiAmount::new(0, 0),
+            UiAmount::new(0, 0)
+        );
+        log_event(&TokenEvent::Transfer(TransferEvent {
+            source: *source_account.key,
+            destination: *destination_account.key,
+            authority: *authority_account.key,
+            amount,
+        }));
+        Ok(true)
+    }
+
+    pub fn process_transfer_batch(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amounts: &[u64],
+    ) -> ProgramResult {
+        if amounts.len() > MAX_TRANSFER_BATCH {
+            return Err(TokenError::TooManyItems.into());
+        }
+        let account_info_iter = &mut accounts.iter();
+        let source_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+        let mut destination_accounts = Vec::with_capacity(amounts.len());
+        for _ in amounts {
+            destination_accounts.push(next_account_info(account_info_iter)?);
+        }
+        // Besides signers, only the instructions sysvar, the mint's
+        // allowlist and the source's enforcement authority may follow.
+        Self::check_remaining_accounts(
+            program_id,
+            authority_account,
+            account_info_iter.as_slice(),
+            3,
+        )?;
+
+        check_writable(source_account, "source")?;
+        for destination_account in destination_accounts.iter() {
+            check_writable(destination_account, "destination")?;
+        }
+
+        check_account_owner(program_id, source_account)?;
+
+        let total = amounts
+            .iter()
+            .try_fold(0u64, |total, amount| total.checked_add(*amount))
+            .ok_or(TokenError::Overflow)?;
+
+        let source_bytes = source_account.try_borrow_data()?;
+        let mut source_data = Token::unpack(&source_bytes)?;
+        let mut by_delegate = false;
+        match source_data.delegate {
+            COption::Some(ref delegate) if cmp_pubkeys(authority_account.key, delegate) => {
+                Self::validate_owner(
+                    program_id,
+                    delegate,
+                    authority_account,
+                    account_info_iter.as_slice(),
+                )?;
+                by_delegate = true;
+                source_data.delegated_amount = source_data
+                    .delegated_amount
+                    .checked_sub(total)
+                    .ok_or(TokenError::InsufficientFunds)?;
+                if source_data.delegated_amount == 0 {
+                    source_data.delegate = COption::None;
+                }
+            }
+            _ => Self::validate_owner(
+                program_id,
+                &source_data.owner,
+                authority_account,
+                account_info_iter.as_slice(),
+            )?,
+        };
+        drop(source_bytes);
+
+        Self::transfer_authorized(
+            program_id,
+            accounts,
+            source_account,
+            source_data,
+            by_delegate,
+            authority_account.key,
+            &destination_accounts,
+            amounts,
+            account_info_iter.as_slice(),
+        )
+    }
+
+    /// The rest of a `TransferBatch` once `authority` has been checked, by
+    /// the delegate's allowance if `by_delegate`: checks the source can send
+    /// to every destination, then moves the amounts. Allowlist accounts
+    /// are looked for among `signers`, and the instructions sysvar among
+    /// `accounts`.
+    #[allow(clippy::too_many_arguments)]
+    fn transfer_authorized<'a>(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        source_account: &AccountInfo<'a>,
+        mut source_data: Token,
+        by_delegate: bool,
+        authority: &Pubkey,
+        destination_accounts: &[&AccountInfo<'a>],
+        amounts: &[u64],
+        signers: &[AccountInfo],
+    ) -> ProgramResult {
+        let total = amounts
+            .iter()
+            .try_fold(0u64, |total, amount| total.checked_add(*amount))
+            .ok_or(TokenError::Overflow)?;
+        Self::check_can_send_without_mint(source_account, &source_data, total, accounts)?;
+        source_data.amount = source_data
+            .amount
+            .checked_sub(total)
+            .ok_or(TokenError::InsufficientFunds)?;
+
+        // Validate every destination before anything is written.
+        let mut memo_required = false;
+        for destination_account in destination_accounts.iter() {
+            memo_required |= Self::check_can_receive(
+                program_id,
+                destination_account,
+                &source_data.mint,
+                signers,
+            )?;
+        }
+        if memo_required {
+            Self::check_memo_precedes(accounts)?;
+        }
+
+        // A destination listed more than once, or the source itself, is
+        // credited with the sum of its amounts.
+        let mut balances = Balances::new();
+        balances.debit(source_account, total)?;
+        for (destination_account, &amount) in destination_accounts.iter().zip(amounts) {
+            balances.credit(destination_account, amount)?;
+        }
+        let is_native = source_data.is_native();
+        if by_delegate {
+            Token::pack(source_data, &mut source_account.try_borrow_mut_data()?)?;
+        }
+        balances.commit()?;
+        if is_native {
+            let source_starting_lamports = source_account.lamports();
+            **source_account.try_borrow_mut_lamports()? = source_starting_lamports
+                .checked_sub(total)
+                .ok_or(TokenError::Overflow)?;
+        }
+
+        for (destination_account, &amount) in destination_accounts.iter().zip(amounts) {
+            if is_native {
+                let destination_starting_lamports = destination_account.lamports();
+                **destination_account.try_borrow_mut_lamports()? = destination_starting_lamports
+                    .checked_add(amount)
+                    .ok_or(TokenError::Overflow)?;
+            }
+
+            log_event(&TokenEvent::Transfer(TransferEvent {
+                source: *source_account.key,
+                destination: *destination_account.key,
+                authority: *authority,
+                amount,
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless `source_account`, holding `source_data`, can send
+    /// `amount` without the mint account: it isn't frozen, non-transferable
+    /// or locked, the debit keeps its minimum balance or is signed for among
+    /// `accounts`, stays within its outflow limit, and its mint has no
+    /// transfer feature that reads the mint.
+    fn check_can_send_without_mint(
+        source_account: &AccountInfo,
+        source_data: &Token,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        if source_data.state == AccountState::Frozen {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        if source_data.is_non_transferable {
+            return Err(TokenError::NonTransferable.into());
+        }
+        let source_bytes = source_account.try_borrow_data()?;
+        Self::check_unlocked(&source_bytes)?;
+        Self::check_minimum_balance(&source_bytes, source_data.amount, amount, accounts)?;
+        Self::check_outflow_limit(&source_bytes, amount)?;
+        let source_settings = extension::get_extension::<MintSettings>(&source_bytes)?;
+        let source_cooldown = extension::get_extension::<Cooldown>(&source_bytes)?;
+        drop(source_bytes);
+        if source_data.withheld_amount.is_some()
+            || source_data.is_pausable
+            || source_data.has_transfer_hook
+            || source_cooldown.is_some()
+            || source_settings.is_some_and(|settings| settings.requires_mint())
+        {
+            return Err(TokenError::MintRequiredForTransfer.into());
+        }
+        Ok(())
+    }
+
+    /// Fails unless `destination_account` is an unfrozen account of `mint`
+    /// that may receive, looking for the mint's allowlist among
+    /// `allowlist_accounts`; returns whether it requires a memo.
+    fn check_can_receive(
+        program_id: &Pubkey,
+        destination_account: &AccountInfo,
+        mint: &Pubkey,
+        allowlist_accounts: &[AccountInfo],
+    ) -> Result<bool, ProgramError> {
+        check_account_owner(program_id, destination_account)?;
+        let destination_bytes = destination_account.try_borrow_data()?;
+        let destination_data = Token::unpack(&destination_bytes)?;
+        if !cmp_pubkeys(&destination_data.mint, mint) {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if destination_data.state == AccountState::Frozen {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        let memo_required = Self::memo_required(&destination_bytes)?;
+        let destination_settings = extension::get_extension::<MintSettings>(&destination_bytes)?;
+        drop(destination_bytes);
+        if destination_settings.is_some_and(|settings| settings.enforce_allowlist) {
+            Self::check_allowlisted(
+                program_id,
+                &destination_data.mint,
+                &destination_data.owner,
+                allowlist_accounts,
+            )?;
+        }
+        Ok(memo_required)
+    }
+
+    pub fn process_two_party_swap(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount_a: u64,
+        amount_b: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let source_a = next_account_info(account_info_iter)?;
+        let destination_a = next_account_info(account_info_iter)?;
+        let owner_a = next_account_info(account_info_iter)?;
+        let source_b = next_account_info(account_info_iter)?;
+        let destination_b = next_account_info(account_info_iter)?;
+        let owner_b = next_account_info(account_info_iter)?;
+        let remaining = account_info_iter.as_slice();
+        let legs = [
+            (source_a, destination_a, owner_a, amount_a),
+            (source_b, destination_b, owner_b, amount_b),
+        ];
+
+        // Both legs are validated before either moves, and the balances of
+        // accounts in both are collected before any is written.
+        let mut memo_required = false;
+        let mut natives = [false; 2];
+        for (is_native, &(source_account, destination_account, owner_account, _)) in
+            natives.iter_mut().zip(&legs)
+        {
+            check_writable(source_account, "source")?;
+            check_writable(destination_account, "destination")?;
+            check_account_owner(program_id, source_account)?;
+            let source_data = Token::unpack(&source_account.try_borrow_data()?)?;
+            Self::validate_owner(program_id, &source_data.owner, owner_account, &[])?;
+            // A source in both legs is checked for both debits.
+            let debit = legs
+                .iter()
+                .filter(|(other, ..)| cmp_pubkeys(other.key, source_account.key))
+                .try_fold(0u64, |debit, &(.., amount)| debit.checked_add(amount))
+                .ok_or(TokenError::Overflow)?;
+            Self::check_can_send_without_mint(source_account, &source_data, debit, accounts)?;
+            memo_required |= Self::check_can_receive(
+                program_id,
+                destination_account,
+                &source_data.mint,
+                remaining,
+            )?;
+            *is_native = source_data.is_native();
+        }
+        if memo_required {
+            Self::check_memo_precedes(accounts)?;
+        }
+
+        let mut balances = Balances::new();
+        for &(source_account, destination_account, _, amount) in &legs {
+            balances.debit(source_account, amount)?;
+            balances.credit(destination_account, amount)?;
+        }
+        balances.commit()?;
+
+        for (is_native, &(source_account, destination_account, owner_account, amount)) in
+            natives.iter().zip(&legs)
+        {
+            if *is_native {
+                let source_starting_lamports = source_account.lamports();
+                **source_account.try_borrow_mut_lamports()? = source_starting_lamports
+                    .checked_sub(amount)
+                    .ok_or(TokenError::Overflow)?;
+                let destination_starting_lamports = destination_account.lamports();
+                **destination_account.try_borrow_mut_lamports()? = destination_starting_lamports
+                    .checked_add(amount)
+                    .ok_or(TokenError::Overflow)?;
+            }
+
+            log_event(&TokenEvent::Transfer(TransferEvent {
+                source: *source_account.key,
+                destination: *destination_account.key,
+                authority: *owner_account.key,
+                amount,
+            }));
+        }
+
+        Ok(())
+    }
+
+    pub fn process_permit_transfer<'a>(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let source_account = next_account_info(account_info_iter)?;
+        let destination_account = next_account_info(account_info_iter)?;
+        let instructions_account = next_account_info(account_info_iter)?;
+        let _payer_account = next_account_info(account_info_iter)?;
+        let _system_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(source_account, "source")?;
+        check_writable(destination_account, "destination")?;
+        check_account_owner(program_id, source_account)?;
+
+        // The runtime has already failed the transaction unless the ed25519
+        // program's signature checks pass, so what it checked is trusted.
+        let current_index = load_current_index_checked(instructions_account)? as usize;
+        let previous_index = current_index
+            .checked_sub(1)
+            .ok_or(TokenError::InvalidPermit)?;
+        let previous = load_instruction_at_checked(previous_index, instructions_account)?;
+        let (signer, message) = permit::verified_message(&previous.program_id, &previous.data)
+            .map_err(|_| TokenError::InvalidPermit)?;
+        let permit = Permit::unpack(message).map_err(|_| TokenError::InvalidPermit)?;
+        if !cmp_pubkeys(&permit.source, source_account.key)
+            || !cmp_pubkeys(&permit.destination, destination_account.key)
+        {
+            return Err(TokenError::InvalidPermit.into());
+        }
+
+        let source_bytes = source_account.try_borrow_data()?;
+        let source_data = Token::unpack(&source_bytes)?;
+        if !cmp_pubkeys(&signer, &source_data.owner) {
+            return Err(TokenError::OwnerMismatch.into());
+        }
+        if Clock::get()?.unix_timestamp > permit.expiry_unix {
+            return Err(TokenError::PermitExpired.into());
+        }
+        let next_nonce = extension::get_extension::<PermitNonce>(&source_bytes)?
+            .map_or(0, |nonce| nonce.next_nonce);
+        drop(source_bytes);
+        if permit.nonce < next_nonce {
+            return Err(TokenError::PermitNonceUsed.into());
+        }
+        let next_nonce = PermitNonce {
+            next_nonce: permit.nonce.checked_add(1).ok_or(TokenError::Overflow)?,
+        };
+        Self::write_extension(source_account, &accounts[..5], &next_nonce)?;
+
+        let owner = source_data.owner;
+        Self::transfer_authorized(
+            program_id,
+            accounts,
+            source_account,
+            source_data,
+            false,
+            &owner,
+            &[destination_account],
+            &[permit.amount],
+            account_info_iter.as_slice(),
+        )
+    }
+
+    pub fn process_create_vesting(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        total_amount: u64,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let funding_account = next_account_info(account_info_iter)?;
+        let vesting_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let beneficiary_account = next_account_info(account_info_iter)?;
+        let mint_authority = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(funding_account, "funding")?;
+        check_writable(vesting_account, "vesting")?;
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+        check_account_owner(program_id, beneficiary_account)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if total_amount == 0 || start_ts > end_ts {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        match mint_data.mint_authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                mint_authority,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::FixedSupply.into()),
+        }
+        let beneficiary_data = Token::unpack(&beneficiary_account.try_borrow_data()?)?;
+        if !cmp_pubkeys(&beneficiary_data.mint, mint_account.key) {
+            return Err(TokenError::MintMismatch.into());
+        }
+        let reserve = VestingReserve {
+            reserved: Self::vesting_reserved(mint_account)?
+                .checked_add(total_amount)
+                .ok_or(TokenError::Overflow)?,
+        };
+        Self::check_supply_cap(&mint_data, reserve.reserved)?;
+
+        let (vesting_address, bump_seed) = get_vesting_address_and_bump_seed(
+            mint_account.key,
+            beneficiary_account.key,
+            program_id,
+        );
+        if vesting_address != *vesting_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Self::create_program_account(
+            program_id,
+            &[
+                funding_account.clone(),
+                vesting_account.clone(),
+                system_program_account.clone(),
+            ],
+            VestingSchedule::LEN,
+            &Rent::get()?,
+            &[&[
+                VESTING_SEED,
+                mint_account.key.as_ref(),
+                beneficiary_account.key.as_ref(),
+                &[bump_seed],
+            ]],
+        )?;
+
+        let schedule = VestingSchedule {
+            is_initialized: true,
+            mint: *mint_account.key,
+            beneficiary: *beneficiary_account.key,
+            total_amount,
+            start_ts,
+            end_ts,
+            claimed_amount: 0,
+        };
+        VestingSchedule::pack(schedule, &mut vesting_account.try_borrow_mut_data()?)?;
+        Self::write_mint_extension(
+            mint_account,
+            &[funding_account.clone(), system_program_account.clone()],
+            &reserve,
+        )
+    }
+
+    pub fn process_claim_vested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let vesting_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let beneficiary_account = next_account_info(account_info_iter)?;
+
+        check_writable(vesting_account, "vesting")?;
+        check_writable(mint_account, "mint")?;
+        check_writable(beneficiary_account, "beneficiary")?;
+
+        check_account_owner(program_id, vesting_account)?;
+        check_account_owner(program_id, mint_account)?;
+        check_account_owner(program_id, beneficiary_account)?;
+
+        let mut schedule = VestingSchedule::unpack(&vesting_account.try_borrow_data()?)?;
+        if !cmp_pubkeys(&schedule.mint, mint_account.key)
+            || !cmp_pubkeys(&schedule.beneficiary, beneficiary_account.key)
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        if mint_data.paused {
+            return Err(TokenError::MintPaused.into());
+        }
+        let token_data = Token::unpack(&beneficiary_account.try_borrow_data()?)?;
+        if token_data.state == AccountState::Frozen {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        if extension::get_mint_extension::<AllowlistEnforcement>(&mint_account.try_borrow_data()?)?
+            .is_some()
+        {
+            Self::check_allowlisted(
+                program_id,
+                mint_account.key,
+                &token_data.owner,
+                account_info_iter.as_slice(),
+            )?;
+        }
+
+        // Vesting only grows with time, but the clock can repeat, so a
+        // claim may find nothing new.
+        let vested = schedule.vested_amount(Clock::get()?.unix_timestamp);
+        let amount = vested.saturating_sub(schedule.claimed_amount);
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let token_amount = token_data
+            .amount
+            .checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+        mint_data.supply = mint_data
+            .supply
+            .checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+        let reserve = VestingReserve {
+            reserved: Self::vesting_reserved(mint_account)?
+                .checked_sub(amount)
+                .ok_or(TokenError::InvalidState)?,
+        };
+        schedule.claimed_amount = vested;
+        Self::record_activity(mint_account, false)?;
+        Self::count_holders(mint_account, holder_change(token_data.amount, token_amount))?;
+
+        Token::write_amount(
+            &mut beneficiary_account.try_borrow_mut_data()?,
+            token_amount,
+        )?;
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+        extension::init_mint_extension(&mut mint_account.try_borrow_mut_data()?, &reserve)?;
+        VestingSchedule::pack(schedule, &mut vesting_account.try_borrow_mut_data()?)?;
+
+        log_event(&TokenEvent::Mint(MintEvent {
+            mint: *mint_account.key,
+            destination: *beneficiary_account.key,
+            amount,
+        }));
+
+        Ok(())
+    }
+
+    pub fn process_transfer_with_fee(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        fee: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let source_account = next_account_info(account_info_iter)?;
+        let destination_account = next_account_info(account_info_iter)?;
+        let fee_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_distinct(fee_account, &[source_account, destination_account])?;
+
+        // The accounts of the equivalent `TransferBatch`.
+        let mut batch_accounts = vec![
+            source_account.clone(),
+            authority_account.clone(),
+            destination_account.clone(),
+            fee_account.clone(),
+        ];
+        batch_accounts.extend(account_info_iter.cloned());
+        Self::process_transfer_batch(program_id, &batch_accounts, &[amount, fee])
+    }
+
+    pub fn process_transfer_with_relayer_fee(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        relayer_fee: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let source_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let destination_account = next_account_info(account_info_iter)?;
+        let relayer_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_distinct(relayer_account, &[source_account, destination_account])?;
+
+        check_account_owner(program_id, mint_account)?;
+        let decimals = Mint::unpack(&mint_account.try_borrow_data()?)?.decimals;
+        for token_account in [source_account, destination_account, relayer_account] {
+            check_account_owner(program_id, token_account)?;
+            let token = Token::unpack(&token_account.try_borrow_data()?)?;
+            if !cmp_pubkeys(&token.mint, mint_account.key) {
+                return Err(TokenError::MintMismatch.into());
+            }
+        }
+
+        // The accounts of the equivalent `TransferChecked`s, which apply the
+        // mint's fee, pause, hook and the rest to both movements.
+        let mut checked_accounts = vec![
+            source_account.clone(),
+            mint_account.clone(),
+            destination_account.clone(),
+            authority_account.clone(),
+        ];
+        checked_accounts.extend(account_info_iter.cloned());
+        Self::transfer(program_id, &checked_accounts, amount, Some(decimals), false)?;
+        if relayer_fee > 0 {
+            checked_accounts[2] = relayer_account.clone();
+            Self::transfer(
+                program_id,
+                &checked_accounts,
+                relayer_fee,
+                Some(decimals),
+                true,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn process_verify_ownership(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        nonce: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+
+        check_account_owner(program_id, token_account)?;
+        // Only the state and the owner matter: a frozen account is still
+        // its owner's.
+        let token = Token::unpack(&token_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &token.owner,
+            owner_account,
+            account_info_iter.as_slice(),
+        )?;
+
+        log_event(&TokenEvent::OwnershipProved(OwnershipProvedEvent {
+            token_account: *token_account.key,
+            owner: token.owner,
+            nonce,
+            unix_timestamp: Clock::get()?.unix_timestamp,
+        }));
+
+        Ok(())
+    }
+
+    pub fn process_burn(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        expected_decimals: Option<u8>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "source")?;
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, token_account)?;
+        check_account_owner(program_id, mint_account)?;
+
+        let token_bytes = token_account.try_borrow_data()?;
+        let mut token_data = Token::unpack(&token_bytes)?;
+        let amount = if amount == AMOUNT_ALL {
+            token_data.amount
+        } else {
+            amount
+        };
+
+        // The account is checked before the authority, as spl-token does.
+        if token_data.state == AccountState::Frozen {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        if token_data.amount < amount {
+            return Err(TokenError::InsufficientFunds.into());
+        }
+        if !cmp_pubkeys(mint_account.key, &token_data.mint) {
+            return Err(TokenError::MintMismatch.into());
+        }
+        let mut mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        // As with transfers, only the checked variant accepts the permanent
+        // delegate.
+        let is_permanent_delegate = expected_decimals.is_some()
+            && mint_data.permanent_delegate == COption::Some(*owner_account.key);
+        let mut by_delegate = false;
+        match token_data.delegate {
+            _ if is_permanent_delegate => Self::validate_owner(
+                program_id,
+                owner_account.key,
+                owner_account,
+                account_info_iter.as_slice(),
+            )?,
+            COption::Some(ref delegate) if cmp_pubkeys(owner_account.key, delegate) => {
+                Self::validate_owner(
+                    program_id,
+                    delegate,
+                    owner_account,
+                    account_info_iter.as_slice(),
+                )?;
+                by_delegate = true;
+                token_data.delegated_amount = token_data
+                    .delegated_amount
+                    .checked_sub(amount)
+                    .ok_or(TokenError::InsufficientFunds)?;
+                if token_data.delegated_amount == 0 {
+                    token_data.delegate = COption::None;
+                }
+            }
+            _ => Self::validate_owner(
+                program_id,
+                &token_data.owner,
+                owner_account,
+                account_info_iter.as_slice(),
+            )?,
+        };
+        Self::check_unlocked(&token_bytes)?;
+        Self::check_minimum_balance(&token_bytes, token_data.amount, amount, accounts)?;
+        Self::check_outflow_limit(&token_bytes, amount)?;
+        drop(token_bytes);
+
+        let starting_amount = token_data.amount;
+        token_data.amount = token_data
+            .amount
+            .checked_sub(amount)
+            .ok_or(TokenError::InsufficientFunds)?;
+
+        if let Some(expected_decimals) = expected_decimals {
+            if expected_decimals != mint_data.decimals {
+                return Err(TokenError::MintDecimalsMismatch.into());
+            }
+        }
+        if mint_data.paused {
+            return Err(TokenError::MintPaused.into());
+        }
+        mint_data.supply = mint_data
+            .supply
+            .checked_sub(amount)
+            .ok_or(TokenError::Overflow)?;
+        Self::record_activity(mint_account, false)?;
+        Self::count_holders(
+            mint_account,
+            holder_change(starting_amount, token_data.amount),
+        )?;
+
+        log_debug!(
+            "Supply of mint {} is now {}",
+            mint_account.key,
+            UiAmount::new(mint_data.supply, mint_data.decimals)
+        );
+        // Only a delegate's burn changes more of the account than its amount.
+        if by_delegate {
+            Token::pack(token_data, &mut token_account.try_borrow_mut_data()?)?;
+        } else {
+            Token::write_amount(&mut token_account.try_borrow_mut_data()?, token_data.amount)?;
+        }
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        log_event(&TokenEvent::Burn(BurnEvent {
+            mint: *mint_account.key,
+            source: *token_account.key,
+            authority: *owner_account.key,
+            amount,
+        }));
+        if is_permanent_delegate {
+            log_event(&TokenEvent::Clawback(ClawbackEvent {
+                mint: *mint_account.key,
+                source: *token_account.key,
+                permanent_delegate: *owner_account.key,
+                amount,
+            }));
+        }
+
+        Ok(())
+    }
+
+    pub fn process_approve(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let source_account = next_account_info(account_info_iter)?;
+        let delegate_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(source_account, "source")?;
+
+        check_account_owner(program_id, source_account)?;
+
+        let mut source_data = Token::unpack(&source_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &source_data.owner,
+            owner_account,
+            account_info_iter.as_slice(),
+        )?;
+
+        source_data.delegate = COption::Some(*delegate_account.key);
+        source_data.delegated_amount = amount;
+
+        Token::pack(source_data, &mut source_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_revoke(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let source_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(source_account, "source")?;
+
+        check_account_owner(program_id, source_account)?;
+
+        let mut source_data = Token::unpack(&source_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &source_data.owner,
+            owner_account,
+            account_info_iter.as_slice(),
+        )?;
+
+        source_data.delegate = COption::None;
+        source_data.delegated_amount = 0;
+
+        Token::pack(source_data, &mut source_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_close_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let source_account = next_account_info(account_info_iter)?;
+        let destination_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(source_account, "closed")?;
+        check_writable(destination_account, "destination")?;
+
+        check_account_owner(program_id, source_account)?;
+        if source_account.key == destination_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let source_data = Token::unpack(&source_account.try_borrow_data()?)?;
+        // The balance is checked before the authority, as spl-token does.
+        if !source_data.is_native() && source_data.amount != 0 {
+            return Err(TokenError::NonZeroBalance.into());
+        }
+        let close_authority =
+            extension::get_extension::<CloseAuthority>(&source_account.try_borrow_data()?)?
+                .and_then(|extension| Option::from(extension.close_authority))
+                .filter(|close_authority| cmp_pubkeys(owner_account.key, close_authority));
+        Self::validate_owner(
+            program_id,
+            close_authority.as_ref().unwrap_or(&source_data.owner),
+            owner_account,
+            account_info_iter.as_slice(),
+        )?;
+
+        if source_data.state == AccountState::Frozen {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        if let COption::Some(withheld_amount) = source_data.withheld_amount {
+            if withheld_amount != 0 {
+                return Err(TokenError::AccountHasWithheldTransferFees.into());
+            }
+        }
+
+        let destination_starting_lamports = destination_account.lamports();
+        **destination_account.try_borrow_mut_lamports()? = destination_starting_lamports
+            .checked_add(source_account.lamports())
+            .ok_or(TokenError::Overflow)?;
+        **source_account.try_borrow_mut_lamports()? = 0;
+        source_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    pub fn process_initialize_mint_close_authority(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        close_authority: COption<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mut mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        mint_data.close_authority = close_authority;
+        Mint::pack(mint_data, &mut mint_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_close_mint(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let destination_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+        check_writable(destination_account, "destination")?;
+
+        check_account_owner(program_id, mint_account)?;
+        if mint_account.key == destination_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        match mint_data.close_authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                authority_account,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::AuthorityTypeNotSupported.into()),
+        }
+
+        if mint_data.supply != 0 || Self::vesting_reserved(mint_account)? != 0 {
+            return Err(TokenError::MintHasSupply.into());
+        }
+        if mint_data.withheld_amount != 0 {
+            return Err(TokenError::AccountHasWithheldTransferFees.into());
+        }
+
+        let destination_starting_lamports = destination_account.lamports();
+        **destination_account.try_borrow_mut_lamports()? = destination_starting_lamports
+            .checked_add(mint_account.lamports())
+            .ok_or(TokenError::Overflow)?;
+        **mint_account.try_borrow_mut_lamports()? = 0;
+        mint_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    pub fn process_migrate_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let account_info = next_account_info(account_info_iter)?;
+        let payer_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(account_info, "migrated")?;
+
+        check_account_owner(program_id, account_info)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let data = account_info.try_borrow_data()?;
+        let migrated = match data.len() {
+            Token::LEGACY_LEN => {
+                let token = Token::unpack_any_version(&data)?;
+                let mut migrated = vec![0; Token::LEN];
+                token.pack_into_slice(&mut migrated);
+                migrated
+            }
+            Mint::LEGACY_LEN => {
+                let mint = Mint::unpack_any_version(&data)?;
+                let mut migrated = vec![0; Mint::LEN];
+                mint.pack_into_slice(&mut migrated);
+                migrated
+            }
+            _ => match state::add_account_type(&data) {
+                Some(migrated) => migrated,
+                None if AccountType::of(&data).is_some() => {
+                    return Err(TokenError::InvalidState.into())
+                }
+                None => return Err(ProgramError::InvalidAccountData),
+            },
+        };
+        drop(data);
+
+        let required_lamports = Rent::get()?
+            .minimum_balance(migrated.len())
+            .saturating_sub(account_info.lamports());
+        if required_lamports > 0 {
+            check_payer(payer_account, required_lamports)?;
+            invoke(
+                &system_instruction::transfer(
+                    payer_account.key,
+                    account_info.key,
+                    required_lamports,
+                ),
+                &[
+                    payer_account.clone(),
+                    account_info.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+        }
+
+        account_info.realloc(migrated.len(), false)?;
+        account_info
+            .try_borrow_mut_data()?
+            .copy_from_slice(&migrated);
+
+        Ok(())
+    }
+
+    pub fn process_initialize_royalty(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        basis_points: u16,
+        destination: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if basis_points > Royalty::MAX_BASIS_POINTS {
+            return Err(TokenError::RoyaltyExceedsMaximum.into());
+        }
+
+        Self::write_mint_extension(
+            mint_account,
+            accounts,
+            &Royalty {
+                basis_points,
+                destination,
+            },
+        )
+    }
+
+    pub fn process_initialize_allowlist(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        authority: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_account = next_account_info(account_info_iter)?;
+        let allowlist_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(payer_account, "payer")?;
+        check_writable(allowlist_account, "allowlist")?;
+        check_writable(mint_account, "mint")?;
+
+        let (allowlist_address, bump_seed) =
+            get_allowlist_address_and_bump_seed(mint_account.key, program_id);
+        if allowlist_address != *allowlist_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if allowlist_account.lamports() > 0 || *allowlist_account.owner != system_program::id() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        check_account_owner(program_id, mint_account)?;
+        let mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        let len = Allowlist::len_for(0);
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_account.key,
+                allowlist_account.key,
+                Rent::get()?.minimum_balance(len),
+                len as u64,
+                program_id,
+            ),
+            &[
+                payer_account.clone(),
+                allowlist_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[ALLOWLIST_SEED, &mint_account.key.to_bytes(), &[bump_seed]]],
+        )?;
+
+        let allowlist = Allowlist {
+            is_initialized: true,
+            mint: *mint_account.key,
+            authority,
+            owners: Vec::new(),
+        };
+        allowlist.pack(&mut allowlist_account.try_borrow_mut_data()?)?;
+        Self::write_mint_extension(
+            mint_account,
+            &[payer_account.clone(), system_program_account.clone()],
+            &AllowlistEnforcement,
+        )
+    }
+
+    pub fn process_add_to_allowlist(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        owner: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let allowlist_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+        // Besides signers, only the payer and system program may follow.
+        Self::check_remaining_accounts(
+            program_id,
+            authority_account,
+            account_info_iter.as_slice(),
+            2,
+        )?;
+
+        check_writable(allowlist_account, "allowlist")?;
+
+        check_account_owner(program_id, allowlist_account)?;
+        let mut allowlist = Allowlist::unpack(&allowlist_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &allowlist.authority,
+            authority_account,
+            account_info_iter.as_slice(),
+        )?;
+        if allowlist.contains(&owner) {
+            return Ok(());
+        }
+        if allowlist.owners.len() == Allowlist::MAX_OWNERS {
+            return Err(TokenError::AllowlistFull.into());
+        }
+
+        allowlist.owners.push(owner);
+        Self::grow_account(
+            allowlist_account,
+            accounts,
+            Allowlist::len_for(allowlist.owners.len()),
+        )?;
+        allowlist.pack(&mut allowlist_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_remove_from_allowlist(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        owner: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let allowlist_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(allowlist_account, "allowlist")?;
+
+        check_account_owner(program_id, allowlist_account)?;
+        let mut allowlist = Allowlist::unpack(&allowlist_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &allowlist.authority,
+            authority_account,
+            account_info_iter.as_slice(),
+        )?;
+        let index = allowlist
+            .owners
+            .iter()
+            .position(|listed| *listed == owner)
+            .ok_or(TokenError::NotAllowlisted)?;
+
+        // The rent the removed owner needed stays with the account.
+        allowlist.owners.swap_remove(index);
+        allowlist_account.realloc(Allowlist::len_for(allowlist.owners.len()), false)?;
+        allowlist.pack(&mut allowlist_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_initialize_min_transfer_amount(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        min_transfer_amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Self::write_mint_extension(
+            mint_account,
+            accounts,
+            &MinTransferAmount {
+                min_transfer_amount,
+            },
+        )
+    }
+
+    pub fn process_update_min_transfer_amount(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        min_transfer_amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        if extension::get_mint_extension::<MinTransferAmount>(&mint_account.try_borrow_data()?)?
+            .is_none()
+        {
+            return Err(TokenError::InvalidState.into());
+        }
+        match mint_data.mint_authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                authority_account,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::FixedSupply.into()),
+        }
+
+        extension::init_mint_extension(
+            &mut mint_account.try_borrow_mut_data()?,
+            &MinTransferAmount {
+                min_transfer_amount,
+            },
+        )?;
+
+        log_event(&TokenEvent::ConfigChanged(ConfigChangedEvent {
+            account: *mint_account.key,
+            authority: *authority_account.key,
+            change: ConfigChange::MinTransferAmount(min_transfer_amount),
+        }));
+
+        Ok(())
+    }
+
+    pub fn process_initialize_activity_tracking(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Self::write_mint_extension(mint_account, accounts, &MintActivity::default())
+    }
+
+    pub fn process_initialize_transfer_cooldown(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        cooldown_slots: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Self::write_mint_extension(mint_account, accounts, &TransferCooldown { cooldown_slots })
+    }
+
+    pub fn process_initialize_holder_count(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack_unchecked(&mint_account.try_borrow_data()?)?;
+        if mint_data.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Self::write_mint_extension(mint_account, accounts, &HolderCount::default())
+    }
+
+    /// Applies `change`, from [`holder_change`], to the [`HolderCount`] of
+    /// a mint that counts its holders.
+    fn count_holders(mint_account: &AccountInfo, change: i64) -> ProgramResult {
+        if change == 0 {
+            return Ok(());
+        }
+        let mut mint_bytes = mint_account.try_borrow_mut_data()?;
+        if let Some(mut count) = extension::get_mint_extension::<HolderCount>(&mint_bytes)? {
+            count.holders = count
+                .holders
+                .checked_add_signed(change)
+                .ok_or(TokenError::Overflow)?;
+            extension::init_mint_extension(&mut mint_bytes, &count)?;
+        }
+        Ok(())
+    }
+
+    /// Records a transfer, if `is_transfer`, or else a mint or burn, in the
+    /// [`MintActivity`] of a mint that tracks its activity.
+    fn record_activity(mint_account: &AccountInfo, is_transfer: bool) -> ProgramResult {
+        let mut mint_bytes = mint_account.try_borrow_mut_data()?;
+        if let Some(mut activity) = extension::get_mint_extension::<MintActivity>(&mint_bytes)? {
+            activity.record(Clock::get()?.unix_timestamp, is_transfer)?;
+            extension::init_mint_extension(&mut mint_bytes, &activity)?;
+        }
+        Ok(())
+    }
+
+    pub fn process_create_metadata(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_account = next_account_info(account_info_iter)?;
+        let metadata_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let mint_authority_account = next_account_info(account_info_iter)?;
+        let update_authority_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(payer_account, "payer")?;
+        check_writable(metadata_account, "metadata")?;
+
+        let (metadata_address, bump_seed) =
+            get_metadata_address_and_bump_seed(mint_account.key, program_id);
+        if metadata_address != *metadata_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if metadata_account.lamports() > 0 || *metadata_account.owner != system_program::id() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        check_account_owner(program_id, mint_account)?;
+        let mint_bytes = mint_account.try_borrow_data()?;
+        let mint_data = Mint::unpack(&mint_bytes)?;
+        if extension::get_mint_extension::<MetadataPointer>(&mint_bytes)?.is_some() {
+            return Err(TokenError::MetadataPointerSet.into());
+        }
+        drop(mint_bytes);
+        match mint_data.mint_authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                mint_authority_account,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::FixedSupply.into()),
+        }
+        TokenMetadata::check_lengths(&name, &symbol, &uri)?;
+
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_account.key,
+                metadata_account.key,
+                rent.minimum_balance(TokenMetadata::LEN),
+                TokenMetadata::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_account.clone(),
+                metadata_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[METADATA_SEED, &mint_account.key.to_bytes(), &[bump_seed]]],
+        )?;
+
+        let metadata = TokenMetadata {
+            is_initialized: true,
+            mint: *mint_account.key,
+            update_authority: *update_authority_account.key,
+            name,
+            symbol,
+            uri,
+        };
+        TokenMetadata::pack(metadata, &mut metadata_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_update_metadata(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let metadata_account = next_account_info(account_info_iter)?;
+        let update_authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(metadata_account, "metadata")?;
+
+        check_account_owner(program_id, metadata_account)?;
+
+        let mut metadata = TokenMetadata::unpack(&metadata_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &metadata.update_authority,
+            update_authority_account,
+            account_info_iter.as_slice(),
+        )?;
+        TokenMetadata::check_lengths(&name, &symbol, &uri)?;
+
+        metadata.name = name;
+        metadata.symbol = symbol;
+        metadata.uri = uri;
+        TokenMetadata::pack(metadata, &mut metadata_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_lock_account(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        lock_until: i64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "token")?;
+
+        check_account_owner(program_id, token_account)?;
+
+        let token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &token_data.owner,
+            owner_account,
+            account_info_iter.as_slice(),
+        )?;
+        if let Some(current) =
+            extension::get_extension::<AccountLock>(&token_account.try_borrow_data()?)?
+        {
+            if lock_until < current.lock_until {
+                return Err(TokenError::LockCannotBeShortened.into());
+            }
+        }
+
+        Self::write_extension(token_account, accounts, &AccountLock { lock_until })
+    }
+
+    pub fn process_set_minimum_balance(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        minimum_balance: u64,
+        enforcement_authority: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "token")?;
+
+        check_account_owner(program_id, token_account)?;
+
+        let token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &token_data.owner,
+            owner_account,
+            account_info_iter.as_slice(),
+        )?;
+        if let Some(current) =
+            extension::get_extension::<MinimumBalance>(&token_account.try_borrow_data()?)?
+        {
+            let loosened = minimum_balance < current.minimum_balance
+                || (current.minimum_balance > 0
+                    && !cmp_pubkeys(&enforcement_authority, &current.enforcement_authority));
+            if loosened && !Self::has_signed(&current.enforcement_authority, accounts) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+
+        Self::write_extension(
+            token_account,
+            accounts,
+            &MinimumBalance {
+                minimum_balance,
+                enforcement_authority,
+            },
+        )
+    }
+
+    pub fn process_set_outflow_limit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        max_outflow_per_instruction: u64,
+        timelock_slots: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "token")?;
+
+        check_account_owner(program_id, token_account)?;
+
+        let token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &token_data.owner,
+            owner_account,
+            account_info_iter.as_slice(),
+        )?;
+        if timelock_slots == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let slot = Clock::get()?.slot;
+        let limit =
+            match extension::get_extension::<OutflowLimit>(&token_account.try_borrow_data()?)? {
+                Some(current) => {
+                    if timelock_slots < current.timelock_slots {
+                        return Err(TokenError::TimelockCannotBeShortened.into());
+                    }
+                    if max_outflow_per_instruction <= current.max_outflow_at(slot) {
+                        OutflowLimit {
+                            max_outflow_per_instruction,
+                            timelock_slots,
+                            ..OutflowLimit::default()
+                        }
+                    } else {
+                        // The limit in force until then is the current one,
+                        // even if a pending one has already applied.
+                        OutflowLimit {
+                            max_outflow_per_instruction: current.max_outflow_at(slot),
+                            timelock_slots,
+                            pending_max_outflow_per_instruction: max_outflow_per_instruction,
+                            pending_effective_slot: slot.saturating_add(current.timelock_slots),
+                        }
+                    }
+                }
+                // Any limit is lower than none.
+                None => OutflowLimit {
+                    max_outflow_per_instruction,
+                    timelock_slots,
+                    ..OutflowLimit::default()
+                },
+            };
+
+        Self::write_extension(token_account, accounts, &limit)
+    }
+
+    pub fn process_add_minter(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        minter: Pubkey,
+        max_per_window: u64,
+        window_slots: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        match mint_data.mint_authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                authority_account,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::FixedSupply.into()),
+        }
+        if window_slots == 0 && max_per_window != 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let entry = Minter {
+            minter,
+            max_per_window,
+            window_slots,
+            window_start_slot: if window_slots == 0 {
+                0
+            } else {
+                Clock::get()?.slot
+            },
+            minted_in_window: 0,
+        };
+        let mut minters =
+            extension::get_mint_extension::<Minters>(&mint_account.try_borrow_data()?)?
+                .unwrap_or_default();
+        match minters
+            .minters
+            .iter()
+            .position(|listed| listed.minter == minter)
+        {
+            Some(index) => minters.minters[index] = entry,
+            None if minters.minters.len() == MAX_MINTERS => {
+                return Err(TokenError::TooManyMinters.into())
+            }
+            None => minters.minters.push(entry),
+        }
+
+        Self::write_mint_extension(mint_account, accounts, &minters)
+    }
+
+    pub fn process_remove_minter(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        minter: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(mint_account, "mint")?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        match mint_data.mint_authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                authority_account,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::FixedSupply.into()),
+        }
+
+        let mut minters =
+            extension::get_mint_extension::<Minters>(&mint_account.try_borrow_data()?)?
+                .unwrap_or_default();
+        let index = minters
+            .minters
+            .iter()
+            .position(|listed| listed.minter == minter)
+            .ok_or(TokenError::NotAMinter)?;
+        minters.minters.remove(index);
+
+        extension::init_mint_extension(&mut mint_account.try_borrow_mut_data()?, &minters)
+    }
+
+    /// Records `pending_owner` as the account's candidate owner, or clears
+    /// the candidate when it is `None`.
+    pub fn process_propose_owner(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        pending_owner: COption<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "token")?;
+
+        check_account_owner(program_id, token_account)?;
+
+        let mut token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &token_data.owner,
+            owner_account,
+            account_info_iter.as_slice(),
+        )?;
+        if token_data.state == AccountState::Frozen {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        if pending_owner.is_none() && token_data.pending_owner.is_none() {
+            return Err(TokenError::InvalidState.into());
+        }
+        if pending_owner.is_some() && Self::has_immutable_owner(token_account)? {
+            return Err(TokenError::ImmutableOwner.into());
+        }
+
+        token_data.pending_owner = pending_owner;
+        Token::pack(token_data, &mut token_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_accept_owner(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let pending_owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "token")?;
+
+        check_account_owner(program_id, token_account)?;
+
+        let mut token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+        let pending_owner = match token_data.pending_owner {
+            COption::Some(pending_owner) => pending_owner,
+            COption::None => return Err(TokenError::InvalidState.into()),
+        };
+        Self::validate_owner(
+            program_id,
+            &pending_owner,
+            pending_owner_account,
+            account_info_iter.as_slice(),
+        )?;
+        if token_data.state == AccountState::Frozen {
+            return Err(TokenError::AccountFrozen.into());
+        }
+
+        let old_owner = std::mem::replace(&mut token_data.owner, pending_owner);
+        token_data.pending_owner = COption::None;
+        token_data.delegate = COption::None;
+        token_data.delegated_amount = 0;
+        Token::pack(token_data, &mut token_account.try_borrow_mut_data()?)?;
+
+        log_event(&TokenEvent::AuthorityChanged(AuthorityChangedEvent {
+            account: *token_account.key,
+            authority_type: AuthorityType::AccountOwner,
+            old: COption::Some(old_owner),
+            new: COption::Some(pending_owner),
+        }));
+
+        Ok(())
+    }
+
+    pub fn process_repair_owner(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        owner: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let mint_authority = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "token")?;
+
+        check_account_owner(program_id, token_account)?;
+        check_account_owner(program_id, mint_account)?;
+
+        let mut token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+        if !cmp_pubkeys(&token_data.mint, mint_account.key) {
+            return Err(TokenError::MintMismatch.into());
+        }
+        let mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        match mint_data.mint_authority {
+            COption::Some(authority) => Self::validate_owner(
+                program_id,
+                &authority,
+                mint_authority,
+                account_info_iter.as_slice(),
+            )?,
+            COption::None => return Err(TokenError::FixedSupply.into()),
+        }
+        if !cmp_pubkeys(&token_data.owner, mint_account.key) {
+            return Err(TokenError::OwnerNotRepairable.into());
+        }
+        if Self::has_immutable_owner(token_account)? {
+            return Err(TokenError::ImmutableOwner.into());
+        }
+
+        let old_owner = std::mem::replace(&mut token_data.owner, *owner);
+        token_data.pending_owner = COption::None;
+        token_data.delegate = COption::None;
+        token_data.delegated_amount = 0;
+        Token::pack(token_data, &mut token_account.try_borrow_mut_data()?)?;
+
+        log_event(&TokenEvent::AuthorityChanged(AuthorityChangedEvent {
+            account: *token_account.key,
+            authority_type: AuthorityType::AccountOwner,
+            old: COption::Some(old_owner),
+            new: COption::Some(*owner),
+        }));
+
+        Ok(())
+    }
+
+    pub fn process_recover_nested<'a>(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let nested_account = next_account_info(account_info_iter)?;
+        let nested_mint_account = next_account_info(account_info_iter)?;
+        let destination_account = next_account_info(account_info_iter)?;
+        let owner_associated_account = next_account_info(account_info_iter)?;
+        let owner_mint_account = next_account_info(account_info_iter)?;
+        let wallet_account = next_account_info(account_info_iter)?;
+
+        check_writable(nested_account, "nested")?;
+        check_writable(destination_account, "destination")?;
+        check_writable(wallet_account, "wallet")?;
+        check_is_signer(wallet_account, "wallet")?;
+
+        // Every address must derive from the wallet and the two mints, so
+        // only accounts nested under the signer's own can be swept.
+        let owner_associated_address =
+            get_associated_token_address(wallet_account.key, owner_mint_account.key, program_id);
+        let nested_address = get_associated_token_address(
+            &owner_associated_address,
+            nested_mint_account.key,
+            program_id,
+        );
+        let destination_address =
+            get_associated_token_address(wallet_account.key, nested_mint_account.key, program_id);
+        if !cmp_pubkeys(owner_associated_account.key, &owner_associated_address)
+            || !cmp_pubkeys(nested_account.key, &nested_address)
+            || !cmp_pubkeys(destination_account.key, &destination_address)
+        {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        check_account_owner(program_id, owner_associated_account)?;
+        check_account_owner(program_id, nested_account)?;
+        let owner_associated_data = Token::unpack(&owner_associated_account.try_borrow_data()?)?;
+        if !cmp_pubkeys(&owner_associated_data.mint, owner_mint_account.key) {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if !cmp_pubkeys(&owner_associated_data.owner, wallet_account.key) {
+            return Err(TokenError::OwnerMismatch.into());
+        }
+        let nested_data = Token::unpack(&nested_account.try_borrow_data()?)?;
+        if !cmp_pubkeys(&nested_data.mint, nested_mint_account.key) {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if !cmp_pubkeys(&nested_data.owner, owner_associated_account.key) {
+            return Err(TokenError::OwnerMismatch.into());
+        }
+        if let COption::Some(withheld_amount) = nested_data.withheld_amount {
+            if withheld_amount != 0 {
+                return Err(TokenError::AccountHasWithheldTransferFees.into());
+            }
+        }
+
+        let amount = nested_data.amount;
+        Self::transfer_authorized(
+            program_id,
+            accounts,
+            nested_account,
+            nested_data,
+            false,
+            owner_associated_account.key,
+            &[destination_account],
+            &[amount],
+            account_info_iter.as_slice(),
+        )?;
+
+        let wallet_starting_lamports = wallet_account.lamports();
+        **wallet_account.try_borrow_mut_lamports()? = wallet_starting_lamports
+            .checked_add(nested_account.lamports())
+            .ok_or(TokenError::Overflow)?;
+        **nested_account.try_borrow_mut_lamports()? = 0;
+        nested_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    pub fn process_transfer_account_ownership(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+        let new_owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "token")?;
+
+        check_account_owner(program_id, token_account)?;
+
+        let mut token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &token_data.owner,
+            owner_account,
+            account_info_iter.as_slice(),
+        )?;
+        check_is_signer(new_owner_account, "new owner")?;
+        if token_data.state == AccountState::Frozen {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        if Self::has_immutable_owner(token_account)? {
+            return Err(TokenError::ImmutableOwner.into());
+        }
+
+        let old_owner = std::mem::replace(&mut token_data.owner, *new_owner_account.key);
+        token_data.pending_owner = COption::None;
+        token_data.delegate = COption::None;
+        token_data.delegated_amount = 0;
+        Token::pack(token_data, &mut token_account.try_borrow_mut_data()?)?;
+
+        log_event(&TokenEvent::AuthorityChanged(AuthorityChangedEvent {
+            account: *token_account.key,
+            authority_type: AuthorityType::AccountOwner,
+            old: COption::Some(old_owner),
+            new: COption::Some(*new_owner_account.key),
+        }));
+
+        Ok(())
+    }
+
+    pub fn process_get_account_balance(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+
+        check_account_owner(program_id, token_account)?;
+
+        let token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+        set_return_data(&token_data.amount.to_le_bytes());
+
+        Ok(())
+    }
+
+    pub fn process_get_mint_supply(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        check_account_owner(program_id, mint_account)?;
+
+        let mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        set_return_data(&mint_data.supply.to_le_bytes());
+
+        Ok(())
+    }
+
+    pub fn process_amount_to_ui_amount(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        let (decimals, scale) = Self::mint_ui_scale(program_id, mint_account)?;
+        let ui_amount = match scale {
+            Some(scale) => crate::format_ui_amount(
+                interest::scale_amount(amount, scale).ok_or(TokenError::Overflow)?,
+                decimals,
+            ),
+            None => crate::amount_to_ui_amount(amount, decimals),
+        };
+        set_return_data(ui_amount.as_bytes());
+
+        Ok(())
+    }
+
+    pub fn process_ui_amount_to_amount(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        ui_amount: &str,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+
+        let (decimals, scale) = Self::mint_ui_scale(program_id, mint_account)?;
+        let amount = match scale {
+            Some(scale) => {
+                interest::unscale_amount(crate::parse_ui_amount(ui_amount, decimals)?, scale)
+                    .ok_or(TokenError::Overflow)?
+            }
+            None => crate::try_ui_amount_into_amount(ui_amount, decimals)?,
+        };
+        set_return_data(&amount.to_le_bytes());
+
+        Ok(())
+    }
+
+    pub fn process_toggle_memo_required(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        memo_required: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account = next_account_info(account_info_iter)?;
+        let owner_account = next_account_info(account_info_iter)?;
+
+        check_writable(token_account, "token")?;
+
+        check_account_owner(program_id, token_account)?;
+
+        let token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+        Self::validate_owner(
+            program_id,
+            &token_data.owner,
+            owner_account,
+            account_info_iter.as_slice(),
+        )?;
+
+        // An account without the extension already doesn't require memos.
+        if !memo_required && !Self::memo_required(&token_account.try_borrow_data()?)? {
+            return Ok(());
+        }
+        Self::write_extension(
+            token_account,
+            accounts,
+            &MemoTransfer {
+                require_incoming_transfer_memos: memo_required,
+            },
+        )
+    }
+
+    /// Reads a mint's decimals and, for an interest-bearing mint, its
+    /// current interest scale; the native mint has no account to read.
+    fn mint_ui_scale(
+        program_id: &Pubkey,
+        mint_account: &AccountInfo,
+    ) -> Result<(u8, Option<u128>), ProgramError> {
+        if *mint_account.key == native_mint::id() {
+            return Ok((native_mint::DECIMALS, None));
+        }
+        check_account_owner(program_id, mint_account)?;
+        let mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        let scale = match mint_data.interest_rate {
+            COption::Some(interest_rate) => {
+                Some(interest_rate.scale_at(Clock::get()?.unix_timestamp)?)
+            }
+            COption::None => None,
+        };
+        Ok((mint_data.decimals, scale))
+    }
+
+    pub fn process_initialize_escrow(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        expected_amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_account = next_account_info(account_info_iter)?;
+        let source_account = next_account_info(account_info_iter)?;
+        let deposit_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+        let maker_receive_account = next_account_info(account_info_iter)?;
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(source_account, "source")?;
+        check_writable(deposit_account, "deposit")?;
+        check_writable(escrow_account, "escrow")?;
+
+        check_is_signer(maker_account, "maker")?;
+        if !cmp_pubkeys(token_program_account.key, program_id) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        check_account_owner(program_id, escrow_account)?;
+        check_account_owner(program_id, deposit_account)?;
+        check_account_owner(program_id, maker_receive_account)?;
+
+        let mut escrow = Escrow::unpack_unchecked(&escrow_account.try_borrow_data()?)?;
+        if escrow.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if !Rent::get()?.is_exempt(escrow_account.lamports(), escrow_account.data_len()) {
+            return Err(TokenError::NotRentExempt.into());
+        }
+
+        let (escrow_authority, _) =
+            get_escrow_authority_and_bump_seed(escrow_account.key, program_id);
+        let deposit_data = Token::unpack(&deposit_account.try_borrow_data()?)?;
+        if deposit_data.owner != escrow_authority {
+            return Err(TokenError::OwnerMismatch.into());
+        }
+        Token::unpack(&maker_receive_account.try_borrow_data()?)?;
+
+        invoke(
+            &instruction::transfer_tokens(
+                program_id,
+                source_account.key,
+                deposit_account.key,
+                maker_account.key,
+                &[],
+                amount,
+            )?,
+            &[
+                source_account.clone(),
+                deposit_account.clone(),
+                maker_account.clone(),
+                token_program_account.clone(),
+            ],
+        )?;
+
+        escrow.is_initialized = true;
+        escrow.maker = *maker_account.key;
+        escrow.deposit_account = *deposit_account.key;
+        escrow.maker_receive_account = *maker_receive_account.key;
+        escrow.expected_amount = expected_amount;
+        escrow.deposit_mint = deposit_data.mint;
+        escrow.deposit_amount = Token::unpack(&deposit_account.try_borrow_data()?)?.amount;
+        Escrow::pack(escrow, &mut escrow_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_exchange(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        expected_amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker_account = next_account_info(account_info_iter)?;
+        let taker_source_account = next_account_info(account_info_iter)?;
+        let taker_receive_account = next_account_info(account_info_iter)?;
+        let deposit_account = next_account_info(account_info_iter)?;
+        let maker_receive_account = next_account_info(account_info_iter)?;
+        let maker_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+        let escrow_authority_account = next_account_info(account_info_iter)?;
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(taker_source_account, "taker source")?;
+        check_writable(taker_receive_account, "taker receive")?;
+        check_writable(deposit_account, "deposit")?;
+        check_writable(maker_receive_account, "maker receive")?;
+        check_writable(maker_account, "maker")?;
+        check_writable(escrow_account, "escrow")?;
+
+        check_is_signer(taker_account, "taker")?;
+        let (escrow, bump_seed) = Self::unpack_escrow(
+            program_id,
+            escrow_account,
+            deposit_account,
+            escrow_authority_account,
+            token_program_account,
+        )?;
+        if escrow.maker != *maker_account.key
+            || escrow.maker_receive_account != *maker_receive_account.key
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if amount != escrow.expected_amount || expected_amount != escrow.deposit_amount {
+            return Err(TokenError::EscrowAmountMismatch.into());
+        }
+        // The deposit is released exactly as recorded, so one drained by a
+        // permanent delegate, or reopened under the same escrow with less,
+        // fails here rather than paying out whatever it holds now.
+        let deposit_data = Token::unpack(&deposit_account.try_borrow_data()?)?;
+        if deposit_data.mint != escrow.deposit_mint {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if deposit_data.amount != escrow.deposit_amount {
+            return Err(TokenError::EscrowAmountMismatch.into());
+        }
+
+        invoke(
+            &instruction::transfer_tokens(
+                program_id,
+                taker_source_account.key,
+                maker_receive_account.key,
+                taker_account.key,
+                &[],
+                amount,
+            )?,
+            &[
+                taker_source_account.clone(),
+                maker_receive_account.clone(),
+                taker_account.clone(),
+                token_program_account.clone(),
+            ],
+        )?;
+        Self::release_escrow(
+            program_id,
+            escrow_account,
+            deposit_account,
+            taker_receive_account,
+            maker_account,
+            escrow_authority_account,
+            token_program_account,
+            bump_seed,
+            escrow.deposit_amount,
+        )
+    }
+
+    pub fn process_cancel_escrow(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker_account = next_account_info(account_info_iter)?;
+        let deposit_account = next_account_info(account_info_iter)?;
+        let refund_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+        let escrow_authority_account = next_account_info(account_info_iter)?;
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(maker_account, "maker")?;
+        check_writable(deposit_account, "deposit")?;
+        check_writable(refund_account, "refund")?;
+        check_writable(escrow_account, "escrow")?;
+
+        let (escrow, bump_seed) = Self::unpack_escrow(
+            program_id,
+            escrow_account,
+            deposit_account,
+            escrow_authority_account,
+            token_program_account,
+        )?;
+        Self::validate_owner(program_id, &escrow.maker, maker_account, &[])?;
+
+        let deposit_amount = Token::unpack(&deposit_account.try_borrow_data()?)?.amount;
+        Self::release_escrow(
+            program_id,
+            escrow_account,
+            deposit_account,
+            refund_account,
+            maker_account,
+            escrow_authority_account,
+            token_program_account,
+            bump_seed,
+            deposit_amount,
+        )
+    }
+
+    /// Loads an escrow and checks the deposit and authority accounts passed
+    /// alongside it, returning the authority's bump seed.
+    fn unpack_escrow(
+        program_id: &Pubkey,
+        escrow_account: &AccountInfo,
+        deposit_account: &AccountInfo,
+        escrow_authority_account: &AccountInfo,
+        token_program_account: &AccountInfo,
+    ) -> Result<(Escrow, u8), ProgramError> {
+        if !cmp_pubkeys(token_program_account.key, program_id) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        check_account_owner(program_id, escrow_account)?;
+        check_account_owner(program_id, deposit_account)?;
+        let escrow = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        if escrow.deposit_account != *deposit_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (escrow_authority, bump_seed) =
+            get_escrow_authority_and_bump_seed(escrow_account.key, program_id);
+        if escrow_authority != *escrow_authority_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Ok((escrow, bump_seed))
+    }
+
+    /// Moves `deposit_amount`, all the deposit holds, to
+    /// `recipient_account`, then closes the deposit and escrow accounts into
+    /// the maker.
+    #[allow(clippy::too_many_arguments)]
+    fn release_escrow<'a>(
+        program_id: &Pubkey,
+        escrow_account: &AccountInfo<'a>,
+        deposit_account: &AccountInfo<'a>,
+        recipient_account: &AccountInfo<'a>,
+        maker_account: &AccountInfo<'a>,
+        escrow_authority_account: &AccountInfo<'a>,
+        token_program_account: &AccountInfo<'a>,
+        bump_seed: u8,
+        deposit_amount: u64,
+    ) -> ProgramResult {
+        let signer_seeds: &[&[u8]] = &[ESCROW_SEED, &escrow_account.key.to_bytes(), &[bump_seed]];
+
+        invoke_signed(
+            &instruction::transfer_tokens(
+                program_id,
+                deposit_account.key,
+                recipient_account.key,
+                escrow_authority_account.key,
+                &[],
+                deposit_amount,
+            )?,
+            &[
+                deposit_account.clone(),
+                recipient_account.clone(),
+                escrow_authority_account.clone(),
+                token_program_account.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+        invoke_signed(
+            &instruction::close_account(
+                program_id,
+                deposit_account.key,
+                maker_account.key,
+                escrow_authority_account.key,
+                &[],
+            )?,
+            &[
+                deposit_account.clone(),
+                maker_account.clone(),
+                escrow_authority_account.clone(),
+                token_program_account.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        let maker_starting_lamports = maker_account.lamports();
+        **maker_account.try_borrow_mut_lamports()? = maker_starting_lamports
+            .checked_add(escrow_account.lamports())
+            .ok_or(TokenError::Overflow)?;
+        **escrow_account.try_borrow_mut_lamports()? = 0;
+        escrow_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    pub fn process_create_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        merkle_root: [u8; 32],
+        total_claimable: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let creator_account = next_account_info(account_info_iter)?;
+        let source_account = next_account_info(account_info_iter)?;
+        let vault_account = next_account_info(account_info_iter)?;
+        let distributor_account = next_account_info(account_info_iter)?;
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(source_account, "source")?;
+        check_writable(vault_account, "vault")?;
+        check_writable(distributor_account, "distributor")?;
+
+        check_is_signer(creator_account, "creator")?;
+        if !cmp_pubkeys(token_program_account.key, program_id) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        check_account_owner(program_id, distributor_account)?;
+        check_account_owner(program_id, vault_account)?;
+
+        let mut distributor =
+            Distributor::unpack_unchecked(&distributor_account.try_borrow_data()?)?;
+        if distributor.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if !Rent::get()?.is_exempt(
+            distributor_account.lamports(),
+            distributor_account.data_len(),
+        ) {
+            return Err(TokenError::NotRentExempt.into());
+        }
+
+        let (distributor_authority, _) =
+            get_distributor_authority_and_bump_seed(distributor_account.key, program_id);
+        let vault_data = Token::unpack(&vault_account.try_borrow_data()?)?;
+        if vault_data.owner != distributor_authority {
+            return Err(TokenError::OwnerMismatch.into());
+        }
+
+        invoke(
+            &instruction::transfer_tokens(
+                program_id,
+                source_account.key,
+                vault_account.key,
+                creator_account.key,
+                &[],
+                total_claimable,
+            )?,
+            &[
+                source_account.clone(),
+                vault_account.clone(),
+                creator_account.clone(),
+                token_program_account.clone(),
+            ],
+        )?;
+
+        distributor.is_initialized = true;
+        distributor.mint = vault_data.mint;
+        distributor.vault = *vault_account.key;
+        distributor.merkle_root = merkle_root;
+        distributor.total_claimable = total_claimable;
+        Distributor::pack(distributor, &mut distributor_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_create_converter(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        numerator: u64,
+        denominator: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let converter_account = next_account_info(account_info_iter)?;
+        let from_mint_account = next_account_info(account_info_iter)?;
+        let to_mint_account = next_account_info(account_info_iter)?;
+
+        check_writable(converter_account, "converter")?;
+
+        check_account_owner(program_id, converter_account)?;
+        check_account_owner(program_id, from_mint_account)?;
+        check_account_owner(program_id, to_mint_account)?;
+
+        let converter = Converter::unpack_unchecked(&converter_account.try_borrow_data()?)?;
+        if converter.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if !Rent::get()?.is_exempt(converter_account.lamports(), converter_account.data_len()) {
+            return Err(TokenError::NotRentExempt.into());
+        }
+        if cmp_pubkeys(from_mint_account.key, to_mint_account.key)
+            || numerator == 0
+            || denominator == 0
+        {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let from_mint = Mint::unpack(&from_mint_account.try_borrow_data()?)?;
+        Mint::unpack(&to_mint_account.try_borrow_data()?)?;
+
+        let (conversion_authority, _) =
+            get_converter_authority_and_bump_seed(converter_account.key, program_id);
+        let converter = Converter {
+            is_initialized: true,
+            from_mint: *from_mint_account.key,
+            to_mint: *to_mint_account.key,
+            numerator,
+            denominator,
+            conversion_authority,
+        };
+        converter
+            .convert(from_mint.max_supply.unwrap_or(from_mint.supply))
+            .ok_or(TokenError::Overflow)?;
+        Converter::pack(converter, &mut converter_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_convert(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let owner_account = next_account_info(account_info_iter)?;
+        let source_account = next_account_info(account_info_iter)?;
+        let from_mint_account = next_account_info(account_info_iter)?;
+        let destination_account = next_account_info(account_info_iter)?;
+        let to_mint_account = next_account_info(account_info_iter)?;
+        let converter_account = next_account_info(account_info_iter)?;
+        let converter_authority_account = next_account_info(account_info_iter)?;
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        check_is_signer(owner_account, "owner")?;
+        if !cmp_pubkeys(token_program_account.key, program_id) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        check_account_owner(program_id, converter_account)?;
+        let converter = Converter::unpack(&converter_account.try_borrow_data()?)?;
+        if !cmp_pubkeys(&converter.from_mint, from_mint_account.key)
+            || !cmp_pubkeys(&converter.to_mint, to_mint_account.key)
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (conversion_authority, bump_seed) =
+            get_converter_authority_and_bump_seed(converter_account.key, program_id);
+        if conversion_authority != *converter_authority_account.key
+            || conversion_authority != converter.conversion_authority
+        {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let (burned, minted) = converter.convert(amount).ok_or(TokenError::Overflow)?;
+        if minted == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        invoke(
+            &instruction::burn(
+                program_id,
+                source_account.key,
+                from_mint_account.key,
+                owner_account.key,
+                &[],
+                burned,
+            )?,
+            &[
+                source_account.clone(),
+                from_mint_account.clone(),
+                owner_account.clone(),
+                token_program_account.clone(),
+            ],
+        )?;
+        invoke_signed(
+            &instruction::mint_tokens(
+                program_id,
+                to_mint_account.key,
+                destination_account.key,
+                converter_authority_account.key,
+                &[],
+                minted,
+            )?,
+            &[
+                to_mint_account.clone(),
+                destination_account.clone(),
+                converter_authority_account.clone(),
+                token_program_account.clone(),
+            ],
+            &[&[
+                CONVERTER_SEED,
+                &converter_account.key.to_bytes(),
+                &[bump_seed],
+            ]],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn process_snapshot_balance(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        snapshot_id: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_account = next_account_info(account_info_iter)?;
+        let snapshot_account = next_account_info(account_info_iter)?;
+        let token_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // The payer is recorded as the one who can close the snapshot, so
+        // it must sign even if the snapshot address is already funded.
+        check_is_signer(payer_account, "payer")?;
+        check_writable(payer_account, "payer")?;
+        check_writable(snapshot_account, "snapshot")?;
+
+        check_account_owner(program_id, token_account)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let token = Token::unpack(&token_account.try_borrow_data()?)?;
+
+        let (snapshot_address, bump_seed) =
+            get_snapshot_address_and_bump_seed(token_account.key, snapshot_id, program_id);
+        if snapshot_address != *snapshot_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Self::create_program_account(
+            program_id,
+            &[
+                payer_account.clone(),
+                snapshot_account.clone(),
+                system_program_account.clone(),
+            ],
+            BalanceSnapshot::LEN,
+            &Rent::get()?,
+            &[&[
+                SNAPSHOT_SEED,
+                token_account.key.as_ref(),
+                &snapshot_id.to_le_bytes(),
+                &[bump_seed],
+            ]],
+        )?;
+
+        let clock = Clock::get()?;
+        let snapshot = BalanceSnapshot {
+            is_initialized: true,
+            token_account: *token_account.key,
+            owner: token.owner,
+            amount: token.amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+            payer: *payer_account.key,
+        };
+        BalanceSnapshot::pack(snapshot, &mut snapshot_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_close_snapshot(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let snapshot_account = next_account_info(account_info_iter)?;
+        let destination_account = next_account_info(account_info_iter)?;
+        let payer_account = next_account_info(account_info_iter)?;
+
+        check_writable(snapshot_account, "snapshot")?;
+        check_writable(destination_account, "destination")?;
+        check_is_signer(payer_account, "payer")?;
+
+        check_account_owner(program_id, snapshot_account)?;
+        if snapshot_account.key == destination_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let snapshot = BalanceSnapshot::unpack(&snapshot_account.try_borrow_data()?)?;
+        if !cmp_pubkeys(&snapshot.payer, payer_account.key) {
+            return Err(TokenError::OwnerMismatch.into());
+        }
+        if Clock::get()?.slot < snapshot.slot.saturating_add(SNAPSHOT_RETENTION_SLOTS) {
+            return Err(TokenError::SnapshotRetained.into());
+        }
+
+        let destination_starting_lamports = destination_account.lamports();
+        **destination_account.try_borrow_mut_lamports()? = destination_starting_lamports
+            .checked_add(snapshot_account.lamports())
+            .ok_or(TokenError::Overflow)?;
+        **snapshot_account.try_borrow_mut_lamports()? = 0;
+        snapshot_account.try_borrow_mut_data()?.fill(0);
+
+        Ok(())
+    }
+
+    pub fn process_claim(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        index: u64,
+        amount: u64,
+        proof: &[[u8; 32]],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let claimant_account = next_account_info(account_info_iter)?;
+        let destination_account = next_account_info(account_info_iter)?;
+        let distributor_account = next_account_info(account_info_iter)?;
+        let vault_account = next_account_info(account_info_iter)?;
+        let distributor_authority_account = next_account_info(account_info_iter)?;
+        let claim_status_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+        let token_program_account = next_account_info(account_info_iter)?;
+
+        check_writable(claimant_account, "claimant")?;
+        check_writable(destination_account, "destination")?;
+        check_writable(distributor_account, "distributor")?;
+        check_writable(vault_account, "vault")?;
+        check_writable(claim_status_account, "claim status")?;
+
+        check_is_signer(claimant_account, "claimant")?;
+        if !cmp_pubkeys(token_program_account.key, program_id)
+            || *system_program_account.key != system_program::id()
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        check_account_owner(program_id, distributor_account)?;
+        check_account_owner(program_id, vault_account)?;
+        let mut distributor = Distributor::unpack(&distributor_account.try_borrow_data()?)?;
+        if distributor.vault != *vault_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (distributor_authority, authority_bump_seed) =
+            get_distributor_authority_and_bump_seed(distributor_account.key, program_id);
+        let (claim_address, claim_bump_seed) =
+            get_claim_address_and_bump_seed(distributor_account.key, index, program_id);
+        if distributor_authority != *distributor_authority_account.key
+            || claim_address != *claim_status_account.key
+        {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        // Only a claim status this program wrote marks the leaf claimed;
+        // lamports sent to the address beforehand don't.
+        if cmp_pubkeys(claim_status_account.owner, program_id)
+            && ClaimStatus::unpack(&claim_status_account.try_borrow_data()?).is_ok()
+        {
+            return Err(TokenError::AlreadyClaimed.into());
+        }
+
+        let leaf = distributor::claim_leaf(index, claimant_account.key, amount);
+        if !distributor::verify_proof(proof, &distributor.merkle_root, leaf) {
+            return Err(TokenError::InvalidMerkleProof.into());
+        }
+        distributor.total_claimed = distributor
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(TokenError::Overflow)?;
+        if distributor.total_claimed > distributor.total_claimable {
+            return Err(TokenError::InsufficientFunds.into());
+        }
+
+        Self::create_program_account(
+            program_id,
+            &[
+                claimant_account.clone(),
+                claim_status_account.clone(),
+                system_program_account.clone(),
+            ],
+            ClaimStatus::LEN,
+            &Rent::get()?,
+            &[&[
+                CLAIM_SEED,
+                &distributor_account.key.to_bytes(),
+                &index.to_le_bytes(),
+                &[claim_bump_seed],
+            ]],
+        )?;
+        let claim_status = ClaimStatus {
+            is_initialized: true,
+            claimant: *claimant_account.key,
+            amount,
+        };
+        ClaimStatus::pack(
+            claim_status,
+            &mut claim_status_account.try_borrow_mut_data()?,
+        )?;
+        Distributor::pack(distributor, &mut distributor_account.try_borrow_mut_data()?)?;
+
+        invoke_signed(
+            &instruction::transfer_tokens(
+                program_id,
+                vault_account.key,
+                destination_account.key,
+                distributor_authority_account.key,
+                &[],
+                amount,
+            )?,
+            &[
+                vault_account.clone(),
+                destination_account.clone(),
+                distributor_authority_account.clone(),
+                token_program_account.clone(),
+            ],
+            &[&[
+                DISTRIBUTOR_SEED,
+                &distributor_account.key.to_bytes(),
+                &[authority_bump_seed],
+            ]],
+        )
+    }
+
+    pub fn process_sync_native(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let native_account = next_account_info(account_info_iter)?;
+
+        check_writable(native_account, "native")?;
+
+        check_account_owner(program_id, native_account)?;
+
+        let mut native_data = Token::unpack(&native_account.try_borrow_data()?)?;
+        let rent_exempt_reserve = match native_data.is_native {
+            COption::Some(rent_exempt_reserve) => rent_exempt_reserve,
+            COption::None => return Err(TokenError::InvalidState.into()),
+        };
+        let new_amount = native_account
+            .lamports()
+            .checked_sub(rent_exempt_reserve)
+            .ok_or(TokenError::Overflow)?;
+        if new_amount < native_data.amount {
+            return Err(TokenError::InvalidState.into());
+        }
+        native_data.amount = new_amount;
+        Token::pack(native_data, &mut native_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    pub fn process_toggle_freeze_account(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        target_state: AccountState,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let source_account = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(source_account, "token")?;
+
+        check_account_owner(program_id, source_account)?;
+        check_account_owner(program_id, mint_account)?;
+
+        let mut source_data = Token::unpack(&source_account.try_borrow_data()?)?;
+        if source_data.mint != *mint_account.key {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if source_data.state == target_state {
+            return Err(TokenError::InvalidState.into());
+        }
+
+        let mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        let recovery_authority = if target_state == AccountState::Frozen {
+            None
+        } else {
+            Self::recovery_authority(mint_account, &mint_data.freeze_authority, authority_account)?
+        };
+        let authority = match (recovery_authority, mint_data.freeze_authority) {
+            (Some(recovery_authority), _) => recovery_authority,
+            (None, COption::Some(authority)) => authority,
+            (None, COption::None) => return Err(TokenError::MintCannotFreeze.into()),
+        };
+        Self::validate_owner(
+            program_id,
+            &authority,
+            authority_account,
+            account_info_iter.as_slice(),
+        )?;
+
+        source_data.state = target_state;
+        Token::pack(source_data, &mut source_account.try_borrow_mut_data()?)?;
+
+        log_event(&TokenEvent::ConfigChanged(ConfigChangedEvent {
+            account: *source_account.key,
+            authority: *authority_account.key,
+            change: if target_state == AccountState::Frozen {
+                ConfigChange::Frozen
+            } else {
+                ConfigChange::Thawed
+            },
+        }));
+        if let Some(recovery_authority) = recovery_authority {
+            log_event(&TokenEvent::RecoveryUsed(RecoveryUsedEvent {
+                account: *source_account.key,
+                recovery_authority,
+                action: RecoveryAction::Thaw,
+            }));
+        }
+
+        Ok(())
+    }
+
+    pub fn process_freeze_batch(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        count: usize,
+    ) -> ProgramResult {
+        if count > MAX_FREEZE_BATCH {
+            return Err(TokenError::TooManyItems.into());
+        }
+        let account_info_iter = &mut accounts.iter();
+        let mint_account = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+        if account_info_iter.len() < count {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let (token_accounts, signers) = account_info_iter.as_slice().split_at(count);
+        Self::check_remaining_accounts(program_id, authority_account, signers, 0)?;
+
+        check_account_owner(program_id, mint_account)?;
+        let mint_data = Mint::unpack(&mint_account.try_borrow_data()?)?;
+        match mint_data.freeze_authority {
+            COption::Some(authority) => {
+                Self::validate_owner(program_id, &authority, authority_account, signers)?
+            }
+            COption::None => return Err(TokenError::MintCannotFreeze.into()),
+        }
+
+        // Every account is checked before any is frozen, so that a batch
+        // with a stray account fails as a whole.
+        let mut frozen: Vec<(&AccountInfo, Token)> = Vec::with_capacity(count);
+        for token_account in token_accounts {
+            check_writable(token_account, "token")?;
+            check_account_owner(program_id, token_account)?;
+            let token_data = Token::unpack(&token_account.try_borrow_data()?)?;
+            if token_data.mint != *mint_account.key {
+                log_debug!(
+                    "The account {} belongs to mint {}, not {}",
+                    token_account.key,
+                    token_data.mint,
+                    mint_account.key
+                );
+                return Err(TokenError::MintMismatch.into());
+            }
+            if token_data.state != AccountState::Frozen
+                && !frozen
+                    .iter()
+                    .any(|(account, _)| cmp_pubkeys(account.key, token_account.key))
+            {
+                frozen.push((token_account, token_data));
+            }
+        }
+
+        for (token_account, mut token_data) in frozen {
+            token_data.state = AccountState::Frozen;
+            Token::pack(token_data, &mut token_account.try_borrow_mut_data()?)?;
+
+            log_event(&TokenEvent::ConfigChanged(ConfigChangedEvent {
+                account: *token_account.key,
+                authority: *authority_account.key,
+                change: ConfigChange::Frozen,
+            }));
+        }
+
+        Ok(())
+    }
+
+    pub fn process_set_authority(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        authority_type: AuthorityType,
+        new_authority: COption<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let account_info = next_account_info(account_info_iter)?;
+        let authority_account = next_account_info(account_info_iter)?;
+
+        check_writable(account_info, "owned")?;
+
+        check_account_owner(program_id, account_info)?;
+
+        let account_type = match &account_info.try_borrow_data()?[..] {
+            [0, 0, ..] => return Err(ProgramError::UninitializedAccount),
+            data => AccountType::of(data),
+        };
+        let old = if account_type == Some(AccountType::Token) {
+            let mut account = Token::unpack(&account_info.try_borrow_data()?)?;
+            if account.state == AccountState::Frozen {
+                return Err(TokenError::AccountFrozen.into());
+            }
+
+            let old = match authority_type {
+                AuthorityType::AccountOwner => {
+                    if Self::has_immutable_owner(account_info)? {
+                        return Err(TokenError::ImmutableOwner.into());
+                    }
+                    Self::validate_owner(
+                        program_id,
+                        &account.owner,
+                        authority_account,
+                        account_info_iter.as_slice(),
+                    )?;
+                    let new_owner = match new_authority {
+                        COption::Some(authority) => authority,
+                        COption::None => return Err(TokenError::InvalidInstruction.into()),
+                    };
+                    account.delegate = COption::None;
+                    account.delegated_amount = 0;
+                    account.pending_owner = COption::None;
+                    COption::Some(std::mem::replace(&mut account.owner, new_owner))
+                }
+                AuthorityType::CloseAccount => {
+                    Self::validate_owner(
+                        program_id,
+                        &account.owner,
+                        authority_account,
+                        account_info_iter.as_slice(),
+                    )?;
+                    let old = extension::get_extension::<CloseAuthority>(
+                        &account_info.try_borrow_data()?,
+                    )?;
+                    // Revoking an authority that was never set needs no entry.
+                    if old.is_some() || new_authority.is_some() {
+                        Self::write_extension(
+                            account_info,
+                            accounts,
+                            &CloseAuthority {
+                                close_authority: new_authority,
+                            },
+                        )?;
+                    }
+                    old.map_or(COption::None, |old| old.close_authority)
+                }
+                _ => return Err(TokenError::AuthorityTypeNotSupported.into()),
+            };
+            Token::pack(account, &mut account_info.try_borrow_mut_data()?)?;
+            old
+        } else if account_type == Some(AccountType::Mint) {
+            let mut mint = Mint::unpack(&account_info.try_borrow_data()?)?;
+
+            let old = match authority_type {
+                AuthorityType::MintTokens => {
+                    match mint.mint_authority {
+                        COption::Some(authority) => Self::validate_owner(
+                            program_id,
+                            &authority,
+                            authority_account,
+                            account_info_iter.as_slice(),
+                        )?,
+                        COption::None => return Err(TokenError::FixedSupply.into()),
+                    }
+                    std::mem::replace(&mut mint.mint_authority, new_authority)
+                }
+                AuthorityType::FreezeAccount => {
+                    match mint.freeze_authority {
+                        COption::Some(authority) => Self::validate_owner(
+                            program_id,
+                            &authority,
+                            authority_account,
+                            account_info_iter.as_slice(),
+                        )?,
+                        COption::None => return Err(TokenError::MintCannotFreeze.into()),
+                    }
+                    // New accounts would start frozen with no one to thaw them.
+                    if new_authority.is_none() && mint.default_account_state == AccountState::Frozen
+                    {
+                        return Err(TokenError::MintCannotFreeze.into());
+                    }
+                    std::mem::replace(&mut mint.freeze_authority, new_authority)
+                }
+                AuthorityType::TransferFeeConfig => {
+                    match mint.transfer_fee_authority {
+                        COption::Some(authority) => Self::validate_owner(
+                            program_id,
+                            &authority,
+                            authority_account,
+                            account_info_iter.as_slice(),
+                        )?,
+                        COption::None => return Err(TokenError::AuthorityTypeNotSupported.into()),
+                    }
+                    std::mem::replace(&mut mint.transfer_fee_authority, new_authority)
+                }
+                AuthorityType::CloseMint => {
+                    match mint.close_authority {
+                        COption::Some(authority) => Self::validate_owner(
+                            program_id,
+                            &authority,
+                            authority_account,
+                            account_info_iter.as_slice(),
+                        )?,
+                        COption::None => return Err(TokenError::AuthorityTypeNotSupported.into()),
+                    }
+                    std::mem::replace(&mut mint.close_authority, new_authority)
+                }
+                _ => return Err(TokenError::AuthorityTypeNotSupported.into()),
+            };
+            Mint::pack(mint, &mut account_info.try_borrow_mut_data()?)?;
+            old
+        } else {
+            return Err(ProgramError::InvalidArgument);
+        };
+
+        log_event(&TokenEvent::AuthorityChanged(AuthorityChangedEvent {
+            account: *account_info.key,
+            authority_type,
+            old,
+            new: new_authority,
+        }));
+
+        Ok(())
+    }
+
+    /// Copies onto a new token account the parts of its mint's configuration
+    /// that apply to it: flags that transfers without the mint still have to
+    /// enforce, and the mint's default account state. Returns the extensions
+    /// to write into the account as well, for those of the mint, with
+    /// `mint_bytes`, that it has.
+    fn apply_mint_config(
+        token: &mut Token,
+        mint: &Mint,
+        mint_bytes: &[u8],
+    ) -> Result<MintConfig, ProgramError> {
+        token.withheld_amount = if mint.transfer_fee.is_some() {
+            COption::Some(0)
+        } else {
+            COption::None
+        };
+        token.is_non_transferable = mint.non_transferable;
+        token.is_pausable = mint.pause_authority.is_some();
+        token.has_transfer_hook = mint.transfer_hook_program.is_some();
+        if mint.default_account_state == AccountState::Frozen {
+            token.state = AccountState::Frozen;
+        }
+        let settings = MintSettings {
+            holder_count: extension::get_mint_extension::<HolderCount>(mint_bytes)?.is_some(),
+            activity_tracking: extension::get_mint_extension::<MintActivity>(mint_bytes)?.is_some(),
+            min_transfer_amount: extension::get_mint_extension::<MinTransferAmount>(mint_bytes)?
+                .is_some(),
+            enforce_allowlist: extension::get_mint_extension::<AllowlistEnforcement>(mint_bytes)?
+                .is_some(),
+            royalty: extension::get_mint_extension::<Royalty>(mint_bytes)?.is_some(),
+        };
+        let cooldown = extension::get_mint_extension::<TransferCooldown>(mint_bytes)?
+            .map(|_| Cooldown::default());
+        Ok(MintConfig {
+            settings: (settings != MintSettings::default()).then_some(settings),
+            cooldown,
+        })
+    }
+
+    /// Writes each extension of `config` into `token_account`, growing it
+    /// as [`Self::write_extension`] does.
+    fn write_mint_config<'a>(
+        token_account: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+        config: MintConfig,
+    ) -> ProgramResult {
+        if let Some(settings) = config.settings {
+            Self::write_extension(token_account, accounts, &settings)?;
+        }
+        if let Some(cooldown) = config.cooldown {
+            Self::write_extension(token_account, accounts, &cooldown)?;
+        }
+        Ok(())
+    }
+
+    /// Invokes a mint's transfer hook for a transfer that has already been
+    /// applied. `hook_accounts` starts with the hook program, followed by the
+    /// extra accounts it was given.
+    fn invoke_transfer_hook<'a>(
+        hook_program_id: &Pubkey,
+        source_account: &AccountInfo<'a>,
+        destination_account: &AccountInfo<'a>,
+        authority_account: &AccountInfo<'a>,
+        hook_accounts: &[AccountInfo<'a>],
+        amount: u64,
+    ) -> ProgramResult {
+        let extra_account_metas: Vec<AccountMeta> = hook_accounts[1..]
+            .iter()
+            .map(|account| {
+                if account.is_writable {
+                    AccountMeta::new(*account.key, account.is_signer)
+                } else {
+                    AccountMeta::new_readonly(*account.key, account.is_signer)
+                }
+            })
+            .collect();
+        let mut account_infos = vec![
+            source_account.clone(),
+            destination_account.clone(),
+            authority_account.clone(),
+        ];
+        account_infos.extend_from_slice(hook_accounts);
+        invoke(
+            &transfer_hook::execute(
+                hook_program_id,
+                source_account.key,
+                destination_account.key,
+                authority_account.key,
+                &extra_account_metas,
+                amount,
+            ),
+            &account_infos,
+        )
+    }
+
+    /// Fails with `MemoRequired` unless the instruction just before the
+    /// current one is a memo. The instructions sysvar is looked up by key
+    /// among `accounts`.
+    fn check_memo_precedes(accounts: &[AccountInfo]) -> ProgramResult {
+        let instructions_account = accounts
+            .iter()
+            .find(|account| *account.key == sysvar::instructions::id())
+            .ok_or(TokenError::MemoRequired)?;
+        let current_index = load_current_index_checked(instructions_account)? as usize;
+        let previous_index = current_index
+            .checked_sub(1)
+            .ok_or(TokenError::MemoRequired)?;
+        let previous = load_instruction_at_checked(previous_index, instructions_account)?;
+        if !memo::is_memo_program(&previous.program_id) {
+            return Err(TokenError::MemoRequired.into());
+        }
+        Ok(())
+    }
+
+    /// Fails with `SupplyCapExceeded` if the supply of `mint_data`, with the
+    /// `reserved` tokens owed to vesting, exceeds its cap.
+    fn check_supply_cap(mint_data: &Mint, reserved: u64) -> ProgramResult {
+        if let COption::Some(max_supply) = mint_data.max_supply {
+            let committed = mint_data
+                .supply
+                .checked_add(reserved)
+                .ok_or(TokenError::Overflow)?;
+            if committed > max_supply {
+                return Err(TokenError::SupplyCapExceeded.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// The tokens the mint's [`VestingReserve`] holds back, if it has one.
+    fn vesting_reserved(mint_account: &AccountInfo) -> Result<u64, ProgramError> {
+        Ok(
+            extension::get_mint_extension::<VestingReserve>(&mint_account.try_borrow_data()?)?
+                .map_or(0, |reserve| reserve.reserved),
+        )
+    }
+
+    /// Fails with `AccountLocked` while the lock of the token account with
+    /// `data` hasn't expired.
+    fn check_unlocked(data: &[u8]) -> ProgramResult {
+        if let Some(lock) = extension::get_extension::<AccountLock>(data)? {
+            if Clock::get()?.unix_timestamp < lock.lock_until {
+                return Err(TokenError::AccountLocked.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails with `BelowMinimumBalance` if debiting `amount` from the token
+    /// account with `data`, holding `balance`, would leave it below its
+    /// minimum balance, unless its enforcement authority signed as one of
+    /// `accounts`. A debit of more than the balance is left to fail as such.
+    fn check_minimum_balance(
+        data: &[u8],
+        balance: u64,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        if let Some(minimum) = extension::get_extension::<MinimumBalance>(data)? {
+            let breached = amount > 0
+                && balance
+                    .checked_sub(amount)
+                    .is_some_and(|remaining| remaining < minimum.minimum_balance);
+            if breached && !Self::has_signed(&minimum.enforcement_authority, accounts) {
+                return Err(TokenError::BelowMinimumBalance.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails with `OutflowLimitExceeded` if `amount`, everything the instruction
+    /// debits from the token account with `data`, is over the account's
+    /// outflow limit.
+    fn check_outflow_limit(data: &[u8], amount: u64) -> ProgramResult {
+        if let Some(limit) = extension::get_extension::<OutflowLimit>(data)? {
+            if amount > limit.max_outflow_at(Clock::get()?.slot) {
+                return Err(TokenError::OutflowLimitExceeded.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `key` is one of `accounts`, and signed.
+    fn has_signed(key: &Pubkey, accounts: &[AccountInfo]) -> bool {
+        accounts
+            .iter()
+            .any(|account| account.is_signer && cmp_pubkeys(account.key, key))
+    }
+
+    fn memo_required(data: &[u8]) -> Result<bool, ProgramError> {
+        Ok(extension::get_extension::<MemoTransfer>(data)?
+            .is_some_and(|memo| memo.require_incoming_transfer_memos))
+    }
+
+    fn has_immutable_owner(token_account: &AccountInfo) -> Result<bool, ProgramError> {
+        Ok(
+            extension::get_extension::<ImmutableOwner>(&token_account.try_borrow_data()?)?
+                .is_some(),
+        )
+    }
+
+    /// Writes `extension` into `token_account`, first growing the account
+    /// by an entry if it doesn't hold one yet. Growing takes a payer, which
+    /// tops the account up to rent exemption, and the system program as the
+    /// last two of `accounts`.
+    fn write_extension<'a, T: Extension>(
+        token_account: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+        extension: &T,
+    ) -> ProgramResult {
+        let new_len = extension::len_with::<T>(&token_account.try_borrow_data()?)?;
+        Self::grow_account(token_account, accounts, new_len)?;
+        extension::init_extension(&mut token_account.try_borrow_mut_data()?, extension)
+    }
+
+    /// [`Self::write_extension`] for mints.
+    fn write_mint_extension<'a, T: MintExtension>(
+        mint_account: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+        extension: &T,
+    ) -> ProgramResult {
+        let new_len = extension::mint_len_with::<T>(&mint_account.try_borrow_data()?)?;
+        Self::grow_account(mint_account, accounts, new_len)?;
+        extension::init_mint_extension(&mut mint_account.try_borrow_mut_data()?, extension)
+    }
+
+    /// Grows `account` to `new_len` bytes, zeroing the new space, if it is
+    /// shorter. The last two `accounts` must then be a payer, which funds
+    /// the extra rent, and the system program.
+    fn grow_account<'a>(
+        account: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+        new_len: usize,
+    ) -> ProgramResult {
+        if new_len <= account.data_len() {
+            return Ok(());
+        }
+        let [.., payer_account, system_program_account] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let required_lamports = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(account.lamports());
+        if required_lamports > 0 {
+            check_payer(payer_account, required_lamports)?;
+            invoke(
+                &system_instruction::transfer(payer_account.key, account.key, required_lamports),
+                &[
+                    payer_account.clone(),
+                    account.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+        }
+        account.realloc(new_len, true)
+    }
+
+    /// Checks that `owner` is on the allowlist of `mint`, passed among
+    /// `accounts`.
+    fn check_allowlisted(
+        program_id: &Pubkey,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        // Only `InitializeAllowlist` writes an initialized allowlist, at the
+        // mint's derived address, so a matching `mint` identifies it.
+        let allowlist = accounts
+            .iter()
+            .filter(|account| cmp_pubkeys(account.owner, program_id))
+            .find_map(|account| {
+                let allowlist = Allowlist::unpack(&account.try_borrow_data().ok()?).ok()?;
+                cmp_pubkeys(&allowlist.mint, mint).then_some(allowlist)
+            })
+            .ok_or(TokenError::AllowlistAccountMissing)?;
+        if !allowlist.contains(owner) {
+            return Err(TokenError::NotAllowlisted.into());
+        }
+        Ok(())
+    }
+
+    /// Whether `authority` is laid out as one of this program's multisignature
+    /// accounts, whose signers then follow it.
+    fn is_multisig(program_id: &Pubkey, authority: &AccountInfo) -> bool {
+        cmp_pubkeys(authority.owner, program_id) && authority.data_len() == Multisig::LEN
+    }
+
+    /// Fails with `TooManyAccounts` if `remaining`, the accounts after an
+    /// instruction's fixed and counted ones, holds more than the signers
+    /// `authority` can use plus `optional` other accounts: the count in the
+    /// instruction data then disagrees with the accounts passed.
+    fn check_remaining_accounts(
+        program_id: &Pubkey,
+        authority: &AccountInfo,
+        remaining: &[AccountInfo],
+        optional: usize,
+    ) -> ProgramResult {
+        let signers = if Self::is_multisig(program_id, authority) {
+            MAX_SIGNERS
+        } else {
+            0
+        };
+        if remaining.len() > signers + optional {
+            return Err(TokenError::TooManyAccounts.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that `owner_account` is `expected_owner` and has signed, or, if
+    /// it is a multisignature account, that at least `m` of its signers are
+    /// among `signers` and have signed. A PDA owner has signed when its
+    /// program invoked this one with `invoke_signed` and the PDA's seeds.
+    pub fn validate_owner(
+        program_id: &Pubkey,
+        expected_owner: &Pubkey,
+        owner_account: &AccountInfo,
+        signers: &[AccountInfo],
+    ) -> ProgramResult {
+        if !cmp_pubkeys(expected_owner, owner_account.key) {
+            return Err(TokenError::OwnerMismatch.into());
+        }
+        if Self::is_multisig(program_id, owner_account) {
+            let multisig = Multisig::unpack(&owner_account.try_borrow_data()?)?;
+            let mut num_signers = 0;
+            let mut matched = [false; MAX_SIGNERS];
+            for signer in signers.iter() {
+                for (position, key) in multisig.signers[0..multisig.n as usize].iter().enumerate() {
+                    if cmp_pubkeys(key, signer.key) && !matched[position] {
+                        check_is_signer(signer, "multisig signer")?;
+                        matched[position] = true;
+                        num_signers += 1;
+                        break;
+                    }
+                }
+            }
+            if num_signers < multisig.m {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            return Ok(());
+        }
+        load_signer(owner_account)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{account_infos, AccountFixture};
+
+    const MINT: Pubkey = Pubkey::new_from_array([1; 32]);
+    const AUTHORITY: Pubkey = Pubkey::new_from_array([2; 32]);
+    const OWNER: Pubkey = Pubkey::new_from_array([3; 32]);
+    const SOURCE: Pubkey = Pubkey::new_from_array([4; 32]);
+    const DESTINATION: Pubkey = Pubkey::new_from_array([5; 32]);
+
+    /// A mint with authority `AUTHORITY`, `SOURCE` holding its whole
+    /// `supply` for `OWNER`, and an empty `DESTINATION`.
+    fn fixtures(program_id: Pubkey, supply: u64) -> Vec<AccountFixture> {
+        let mint = Mint {
+            mint_authority: COption::Some(AUTHORITY),
+            supply,
+            decimals: 6,
+            is_initialized: true,
+            ..Mint::default()
+        };
+        let token = |owner, amount| Token {
+            mint: MINT,
+            owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Token::default()
+        };
+        let rent = Rent::default();
+        vec![
+            AccountFixture::new(MINT)
+                .owner(program_id)
+                .lamports(rent.minimum_balance(Mint::LEN))
+                .state(&mint),
+            AccountFixture::new(SOURCE)
+                .owner(program_id)
+                .lamports(rent.minimum_balance(Token::LEN))
+                .state(&token(OWNER, supply)),
+            AccountFixture::new(DESTINATION)
+                .owner(program_id)
+                .lamports(rent.minimum_balance(Token::LEN))
+                .state(&token(Pubkey::new_unique(), 0)),
+            AccountFixture::new(AUTHORITY).signer(),
+            AccountFixture::new(OWNER).signer(),
+        ]
+    }
+
+    fn amount(account: &AccountFixture) -> u64 {
+        account.unpack::<Token>().unwrap().amount
+    }
+
+    #[test]
+    fn process_mint_credits_the_destination_and_the_supply() {
+        let program_id = Pubkey::new_unique();
+        let mut accounts = fixtures(program_id, 100);
+        {
+            let infos = account_infos(&mut accounts);
+            let mint_to = [infos[0].clone(), infos[2].clone(), infos[3].clone()];
+            Processor::process_mint(&program_id, &mint_to, 40, None).unwrap();
+            // Only the mint authority can mint.
+            let mint_to = [infos[0].clone(), infos[2].clone(), infos[4].clone()];
+            assert_eq!(
+                Processor::process_mint(&program_id, &mint_to, 40, None),
+                Err(TokenError::OwnerMismatch.into())
+            );
+        }
+        assert_eq!(accounts[0].unpack::<Mint>().unwrap().supply, 140);
+        assert_eq!(amount(&accounts[2]), 40);
+    }
+
+    #[test]
+    fn process_transfer_moves_tokens_from_the_owners_account() {
+        let program_id = Pubkey::new_unique();
+        let mut accounts = fixtures(program_id, 100);
+        {
+            let infos = account_infos(&mut accounts);
+            let transfer = [infos[1].clone(), infos[2].clone(), infos[4].clone()];
+            Processor::process_transfer(&program_id, &transfer, 30, None).unwrap();
+            assert_eq!(
+                Processor::process_transfer(&program_id, &transfer, 71, None),
+                Err(TokenError::InsufficientFunds.into())
+            );
+            // Only the source's owner can move its tokens.
+            let transfer = [infos[1].clone(), infos[2].clone(), infos[3].clone()];
+            assert_eq!(
+                Processor::process_transfer(&program_id, &transfer, 30, None),
+                Err(TokenError::OwnerMismatch.into())
+            );
+        }
+        assert_eq!(amount(&accounts[1]), 70);
+        assert_eq!(amount(&accounts[2]), 30);
+        assert_eq!(accounts[0].unpack::<Mint>().unwrap().supply, 100);
+    }
+}
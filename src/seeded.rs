@@ -0,0 +1,35 @@
+//! Seeded token account address derivation
+
+use solana_program::pubkey::Pubkey;
+
+/// Seed prefix of seeded token account addresses.
+pub const SEEDED_TOKEN_SEED: &[u8] = b"token";
+
+/// Derives the address of an owner's token account for a mint with the given
+/// seed. Unlike the associated token account, an owner can hold any number
+/// of these per mint, one for each seed.
+pub fn get_seeded_token_address(
+    owner_address: &Pubkey,
+    token_mint_address: &Pubkey,
+    seed: u64,
+    program_id: &Pubkey,
+) -> Pubkey {
+    get_seeded_token_address_and_bump_seed(owner_address, token_mint_address, seed, program_id).0
+}
+
+pub(crate) fn get_seeded_token_address_and_bump_seed(
+    owner_address: &Pubkey,
+    token_mint_address: &Pubkey,
+    seed: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEEDED_TOKEN_SEED,
+            &owner_address.to_bytes(),
+            &token_mint_address.to_bytes(),
+            &seed.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
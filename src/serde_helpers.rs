@@ -0,0 +1,87 @@
+//! Serde adapters for the state types, used with `#[serde(with = ...)]`
+//!
+//! Pubkeys are written as base58 strings and `COption`s as `Option`s, so the
+//! JSON form of an account reads like its RPC representation.
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use solana_program::{program_option::COption, pubkey::Pubkey};
+use std::str::FromStr;
+
+fn parse_pubkey<E: Error>(value: &str) -> Result<Pubkey, E> {
+    Pubkey::from_str(value).map_err(|_| E::custom(format!("invalid pubkey {value:?}")))
+}
+
+pub mod pubkey {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        parse_pubkey(&String::deserialize(deserializer)?)
+    }
+}
+
+pub mod coption_pubkey {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &COption<Pubkey>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            COption::Some(key) => serializer.serialize_some(&key.to_string()),
+            COption::None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<COption<Pubkey>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(key) => parse_pubkey(&key).map(COption::Some),
+            None => Ok(COption::None),
+        }
+    }
+}
+
+pub mod coption {
+    use super::*;
+
+    pub fn serialize<T: Serialize, S: Serializer>(
+        value: &COption<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Option::<&T>::from(value.as_ref()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<COption<T>, D::Error> {
+        Ok(Option::<T>::deserialize(deserializer)?.into())
+    }
+}
+
+pub mod pubkey_array {
+    use super::*;
+
+    pub fn serialize<const N: usize, S: Serializer>(
+        value: &[Pubkey; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(value.iter().map(Pubkey::to_string))
+    }
+
+    pub fn deserialize<'de, const N: usize, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[Pubkey; N], D::Error> {
+        let keys = Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|key| parse_pubkey(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let len = keys.len();
+        keys.try_into()
+            .map_err(|_| D::Error::invalid_length(len, &format!("{N} pubkeys").as_str()))
+    }
+}
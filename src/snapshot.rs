@@ -0,0 +1,41 @@
+//! Balance snapshot address derivation
+//!
+//! `SnapshotBalance` records a token account's balance, owner, slot and
+//! timestamp in an account derived from the token account and an id the
+//! caller picks, such as the slot, so a snapshot can be found and checked
+//! without trusting an indexer. Snapshots never change once written; their
+//! payer can close them with `CloseSnapshot` once
+//! [`SNAPSHOT_RETENTION_SLOTS`] have passed.
+
+use solana_program::pubkey::Pubkey;
+
+/// Seed prefix of balance snapshot addresses.
+pub const SNAPSHOT_SEED: &[u8] = b"snapshot";
+
+/// Slots a snapshot is kept before its payer can close it, about thirty
+/// days at 400ms slots.
+pub const SNAPSHOT_RETENTION_SLOTS: u64 = 6_480_000;
+
+/// Derives the address of the snapshot `snapshot_id` of `token_account`.
+pub fn get_snapshot_address(
+    token_account: &Pubkey,
+    snapshot_id: u64,
+    program_id: &Pubkey,
+) -> Pubkey {
+    get_snapshot_address_and_bump_seed(token_account, snapshot_id, program_id).0
+}
+
+pub(crate) fn get_snapshot_address_and_bump_seed(
+    token_account: &Pubkey,
+    snapshot_id: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SNAPSHOT_SEED,
+            &token_account.to_bytes(),
+            &snapshot_id.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
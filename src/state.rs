@@ -0,0 +1,2025 @@
+//! State transition types
+
+use crate::{error::TokenError, extension, instruction::MAX_SIGNERS, interest, math};
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use solana_program::{
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// Lifecycle state of a token account.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AccountState {
+    #[default]
+    Uninitialized,
+    Initialized,
+    Frozen,
+}
+
+/// Packed layout:
+/// `account_type (1) | version (1) | amount (8) | mint (32) | owner (32) | delegate (4 + 32) | delegated_amount (8) | state (1) |
+/// is_native (4 + 8) | withheld_amount (4 + 8) | is_non_transferable (1) | is_pausable (1) |
+/// pending_owner (4 + 32) | has_transfer_hook (1)`, followed by the account's
+/// [`extension`](crate::extension) entries, if any. `Pack` reads and writes
+/// the base layout of an account of any length, checking but preserving the
+/// entries after it.
+///
+/// `Pack` only accepts this, the current [`ACCOUNT_VERSION`], rejecting
+/// accounts of older layouts with `InvalidAccountData` until they are
+/// rewritten with `MigrateAccount`: version 2, without the account type,
+/// and the legacy `amount (8) | mint (32) | owner (32)` layout
+/// ([`Token::LEGACY_LEN`] bytes). [`Token::unpack_any_version`] reads all
+/// three.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner: Pubkey,
+    pub amount: u64,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::coption_pubkey")
+    )]
+    pub delegate: COption<Pubkey>,
+    pub delegated_amount: u64,
+    pub state: AccountState,
+    /// For accounts of the native mint, the rent-exempt reserve that is not
+    /// counted in `amount`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::coption"))]
+    pub is_native: COption<u64>,
+    /// For accounts of a mint with a transfer fee, fees withheld from
+    /// incoming transfers and not yet harvested to the mint.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::coption"))]
+    pub withheld_amount: COption<u64>,
+    /// Copied from the mint when the account is initialized.
+    pub is_non_transferable: bool,
+    /// Copied from the mint when the account is initialized; transfers out
+    /// of the account must then include the mint so its pause can be checked.
+    pub is_pausable: bool,
+    /// Owner proposed with `ProposeOwner`, waiting to accept.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::coption_pubkey")
+    )]
+    pub pending_owner: COption<Pubkey>,
+    /// Copied from the mint when the account is initialized; transfers out
+    /// of the account must then include the mint so its hook can be invoked.
+    pub has_transfer_hook: bool,
+}
+
+impl Token {
+    /// Size of the legacy, unversioned layout.
+    pub const LEGACY_LEN: usize = 72;
+
+    /// Unpacks the current, version 2 or legacy layout, without checking
+    /// that the account is initialized. A legacy account is `Initialized`
+    /// unless all of its data is zero.
+    pub fn unpack_any_version(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEGACY_LEN {
+            return match add_account_type(src) {
+                Some(typed) => Self::unpack_unchecked(&typed),
+                None => Self::unpack_unchecked(src),
+            };
+        }
+        let state = if src.iter().all(|&byte| byte == 0) {
+            AccountState::Uninitialized
+        } else {
+            AccountState::Initialized
+        };
+        Ok(Token {
+            amount: u64::from_le_bytes(src[0..8].try_into().unwrap()),
+            mint: Pubkey::new_from_array(src[8..40].try_into().unwrap()),
+            owner: Pubkey::new_from_array(src[40..72].try_into().unwrap()),
+            state,
+            ..Token::default()
+        })
+    }
+
+    /// Reads only the amount of a packed token account, without validating
+    /// the rest of it. Fails with `InvalidAccountData` if `src` is shorter
+    /// than [`Token::LEN`].
+    pub fn amount_from_slice(src: &[u8]) -> Result<u64, ProgramError> {
+        if src.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(u64::from_le_bytes(src[2..10].try_into().unwrap()))
+    }
+
+    /// Overwrites only the amount of a packed token account, leaving every
+    /// other byte of `dst` untouched. Fails with `InvalidAccountData` if
+    /// `dst` is shorter than [`Token::LEN`].
+    pub fn write_amount(dst: &mut [u8], amount: u64) -> Result<(), ProgramError> {
+        if dst.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        dst[2..10].copy_from_slice(&amount.to_le_bytes());
+        Ok(())
+    }
+
+    /// Whether this account wraps SOL.
+    pub fn is_native(&self) -> bool {
+        self.is_native.is_some()
+    }
+}
+
+impl Sealed for Token {}
+
+impl IsInitialized for Token {
+    fn is_initialized(&self) -> bool {
+        self.state != AccountState::Uninitialized
+    }
+}
+
+impl Pack for Token {
+    const LEN: usize = 182;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        check_account_type(input, AccountType::Token)?;
+        if input.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        extension::validate(input)?;
+        Self::unpack_from_slice(&input[..Self::LEN])
+    }
+
+    fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        src.pack_into_slice(&mut dst[..Self::LEN]);
+        Ok(())
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = unpack_header(src, AccountType::Token)?;
+        let amount = u64::from_le_bytes(src[0..8].try_into().unwrap());
+        let mint = Pubkey::new_from_array(src[8..40].try_into().unwrap());
+        let owner = Pubkey::new_from_array(src[40..72].try_into().unwrap());
+        let delegate = unpack_coption_key(src[72..108].try_into().unwrap())?;
+        let delegated_amount = u64::from_le_bytes(src[108..116].try_into().unwrap());
+        let state = AccountState::from_u8(src[116]).ok_or(ProgramError::InvalidAccountData)?;
+        let is_native = unpack_coption_u64(src[117..129].try_into().unwrap())?;
+        let withheld_amount = unpack_coption_u64(src[129..141].try_into().unwrap())?;
+        let is_non_transferable = match src[141] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let is_pausable = match src[142] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let pending_owner = unpack_coption_key(src[143..179].try_into().unwrap())?;
+        let has_transfer_hook = match src[179] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Token {
+            mint,
+            owner,
+            amount,
+            delegate,
+            delegated_amount,
+            state,
+            is_native,
+            withheld_amount,
+            is_non_transferable,
+            is_pausable,
+            pending_owner,
+            has_transfer_hook,
+        })
+    }
+
+    // `Pack::pack` passes exactly the first `LEN` bytes of `dst`, so the
+    // fixed offsets below are always in bounds.
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = pack_header(dst, AccountType::Token);
+        let amount = self.amount.to_le_bytes();
+        let mint = self.mint.to_bytes();
+        let owner = self.owner.to_bytes();
+
+        dst[0..8].copy_from_slice(&amount);
+        dst[8..40].copy_from_slice(&mint);
+        dst[40..72].copy_from_slice(&owner);
+        pack_coption_key(&self.delegate, (&mut dst[72..108]).try_into().unwrap());
+        dst[108..116].copy_from_slice(&self.delegated_amount.to_le_bytes());
+        dst[116] = self.state as u8;
+        pack_coption_u64(&self.is_native, (&mut dst[117..129]).try_into().unwrap());
+        pack_coption_u64(
+            &self.withheld_amount,
+            (&mut dst[129..141]).try_into().unwrap(),
+        );
+        dst[141] = self.is_non_transferable as u8;
+        dst[142] = self.is_pausable as u8;
+        pack_coption_key(
+            &self.pending_owner,
+            (&mut dst[143..179]).try_into().unwrap(),
+        );
+        dst[179] = self.has_transfer_hook as u8;
+    }
+}
+
+/// Packed layout: `account_type (1) | version (1) | mint_authority (4 + 32) | supply (8) | decimals (1) | is_initialized (1) |
+/// freeze_authority (4 + 32) | max_supply (4 + 8) | transfer_fee_authority (4 + 32) |
+/// transfer_fee (4 + 2 + 8) | withheld_amount (8) | non_transferable (1) |
+/// pause_authority (4 + 32) | paused (1) | permanent_delegate (4 + 32) |
+/// transfer_hook_program (4 + 32) | default_account_state (1) | rate_authority (4 + 32) |
+/// interest_rate (4 + 2 + 8 + 16) | close_authority (4 + 32)`,
+/// followed by the mint's [`extension`](crate::extension) entries, if any,
+/// which `Pack` checks but preserves as for [`Token`].
+///
+/// As with [`Token`], `Pack` rejects version 2 and the legacy
+/// `mint_authority (4 + 32) | supply (8) | decimals (1) | is_initialized (1)`
+/// layout ([`Mint::LEGACY_LEN`] bytes); [`Mint::unpack_any_version`] reads
+/// all three.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mint {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::coption_pubkey")
+    )]
+    pub mint_authority: COption<Pubkey>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::coption_pubkey")
+    )]
+    pub freeze_authority: COption<Pubkey>,
+    /// Hard cap on `supply`, fixed when the mint is initialized.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::coption"))]
+    pub max_supply: COption<u64>,
+    /// Authority that can withdraw withheld fees.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::coption_pubkey")
+    )]
+    pub transfer_fee_authority: COption<Pubkey>,
+    /// Fee charged on every transfer, fixed before the mint is initialized.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::coption"))]
+    pub transfer_fee: COption<TransferFee>,
+    /// Fees harvested from token accounts and not yet withdrawn.
+    pub withheld_amount: u64,
+    /// Balances of this mint can be minted and burned but never moved.
+    /// Fixed before the mint is initialized.
+    pub non_transferable: bool,
+    /// Authority that can pause and resume the mint. Fixed before the mint
+    /// is initialized; a mint without one can never be paused.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::coption_pubkey")
+    )]
+    pub pause_authority: COption<Pubkey>,
+    /// While set, no tokens of this mint can be transferred, minted or burned.
+    pub paused: bool,
+    /// Authority over every token account of this mint for checked transfers
+    /// and burns. Fixed before the mint is initialized.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::coption_pubkey")
+    )]
+    pub permanent_delegate: COption<Pubkey>,
+    /// Program invoked on every checked transfer of this mint; see
+    /// [`crate::transfer_hook`]. Fixed before the mint is initialized.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::coption_pubkey")
+    )]
+    pub transfer_hook_program: COption<Pubkey>,
+    /// State new token accounts of this mint start in. `Uninitialized`, the
+    /// zeroed value, behaves like `Initialized`; `Frozen` requires a freeze
+    /// authority.
+    pub default_account_state: AccountState,
+    /// Authority that can change `interest_rate`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::coption_pubkey")
+    )]
+    pub rate_authority: COption<Pubkey>,
+    /// Interest shown in UI amounts; raw balances never change. Fixed before
+    /// the mint is initialized.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::coption"))]
+    pub interest_rate: COption<InterestRate>,
+    /// Authority that can close the mint once its supply is zero.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::coption_pubkey")
+    )]
+    pub close_authority: COption<Pubkey>,
+}
+
+impl Mint {
+    /// Size of the legacy, unversioned layout.
+    pub const LEGACY_LEN: usize = 46;
+
+    /// Size of the version 2 layout, which led with its version alone.
+    pub const VERSION_2_LEN: usize = 366;
+
+    /// Unpacks the current, version 2 or legacy layout, without checking
+    /// that the mint is initialized.
+    pub fn unpack_any_version(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEGACY_LEN {
+            return match add_account_type(src) {
+                Some(typed) => Self::unpack_unchecked(&typed),
+                None => Self::unpack_unchecked(src),
+            };
+        }
+        Ok(Mint {
+            mint_authority: unpack_coption_key(src[0..36].try_into().unwrap())?,
+            supply: u64::from_le_bytes(src[36..44].try_into().unwrap()),
+            decimals: src[44],
+            is_initialized: match src[45] {
+                0 => false,
+                1 => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            ..Mint::default()
+        })
+    }
+}
+
+impl Sealed for Mint {}
+
+impl IsInitialized for Mint {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Mint {
+    const LEN: usize = 367;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        check_account_type(input, AccountType::Mint)?;
+        if input.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        extension::validate_mint(input)?;
+        Self::unpack_from_slice(&input[..Self::LEN])
+    }
+
+    fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        src.pack_into_slice(&mut dst[..Self::LEN]);
+        Ok(())
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = unpack_header(src, AccountType::Mint)?;
+        let mint_authority = unpack_coption_key(src[0..36].try_into().unwrap())?;
+        let supply = u64::from_le_bytes(src[36..44].try_into().unwrap());
+        let decimals = src[44];
+        let is_initialized = match src[45] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let freeze_authority = unpack_coption_key(src[46..82].try_into().unwrap())?;
+        let max_supply = unpack_coption_u64(src[82..94].try_into().unwrap())?;
+        let transfer_fee_authority = unpack_coption_key(src[94..130].try_into().unwrap())?;
+        let transfer_fee = match src[130..134] {
+            [0, 0, 0, 0] => COption::None,
+            [1, 0, 0, 0] => COption::Some(TransferFee {
+                basis_points: u16::from_le_bytes(src[134..136].try_into().unwrap()),
+                maximum_fee: u64::from_le_bytes(src[136..144].try_into().unwrap()),
+            }),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let withheld_amount = u64::from_le_bytes(src[144..152].try_into().unwrap());
+        let non_transferable = match src[152] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let pause_authority = unpack_coption_key(src[153..189].try_into().unwrap())?;
+        let paused = match src[189] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let permanent_delegate = unpack_coption_key(src[190..226].try_into().unwrap())?;
+        let transfer_hook_program = unpack_coption_key(src[226..262].try_into().unwrap())?;
+        let default_account_state =
+            AccountState::from_u8(src[262]).ok_or(ProgramError::InvalidAccountData)?;
+        let rate_authority = unpack_coption_key(src[263..299].try_into().unwrap())?;
+        let interest_rate = match src[299..303] {
+            [0, 0, 0, 0] => COption::None,
+            [1, 0, 0, 0] => COption::Some(InterestRate {
+                current_rate_bps: i16::from_le_bytes(src[303..305].try_into().unwrap()),
+                last_update_timestamp: i64::from_le_bytes(src[305..313].try_into().unwrap()),
+                accrued_scale: u128::from_le_bytes(src[313..329].try_into().unwrap()),
+            }),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let close_authority = unpack_coption_key(src[329..365].try_into().unwrap())?;
+
+        Ok(Mint {
+            mint_authority,
+            supply,
+            decimals,
+            is_initialized,
+            freeze_authority,
+            max_supply,
+            transfer_fee_authority,
+            transfer_fee,
+            withheld_amount,
+            non_transferable,
+            pause_authority,
+            paused,
+            permanent_delegate,
+            transfer_hook_program,
+            default_account_state,
+            rate_authority,
+            interest_rate,
+            close_authority,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = pack_header(dst, AccountType::Mint);
+        pack_coption_key(&self.mint_authority, (&mut dst[0..36]).try_into().unwrap());
+        dst[36..44].copy_from_slice(&self.supply.to_le_bytes());
+        dst[44] = self.decimals;
+        dst[45] = self.is_initialized as u8;
+        pack_coption_key(
+            &self.freeze_authority,
+            (&mut dst[46..82]).try_into().unwrap(),
+        );
+        pack_coption_u64(&self.max_supply, (&mut dst[82..94]).try_into().unwrap());
+        pack_coption_key(
+            &self.transfer_fee_authority,
+            (&mut dst[94..130]).try_into().unwrap(),
+        );
+        match self.transfer_fee {
+            COption::Some(fee) => {
+                dst[130..134].copy_from_slice(&[1, 0, 0, 0]);
+                dst[134..136].copy_from_slice(&fee.basis_points.to_le_bytes());
+                dst[136..144].copy_from_slice(&fee.maximum_fee.to_le_bytes());
+            }
+            COption::None => dst[130..144].fill(0),
+        }
+        dst[144..152].copy_from_slice(&self.withheld_amount.to_le_bytes());
+        dst[152] = self.non_transferable as u8;
+        pack_coption_key(
+            &self.pause_authority,
+            (&mut dst[153..189]).try_into().unwrap(),
+        );
+        dst[189] = self.paused as u8;
+        pack_coption_key(
+            &self.permanent_delegate,
+            (&mut dst[190..226]).try_into().unwrap(),
+        );
+        pack_coption_key(
+            &self.transfer_hook_program,
+            (&mut dst[226..262]).try_into().unwrap(),
+        );
+        dst[262] = self.default_account_state as u8;
+        pack_coption_key(
+            &self.rate_authority,
+            (&mut dst[263..299]).try_into().unwrap(),
+        );
+        match self.interest_rate {
+            COption::Some(rate) => {
+                dst[299..303].copy_from_slice(&[1, 0, 0, 0]);
+                dst[303..305].copy_from_slice(&rate.current_rate_bps.to_le_bytes());
+                dst[305..313].copy_from_slice(&rate.last_update_timestamp.to_le_bytes());
+                dst[313..329].copy_from_slice(&rate.accrued_scale.to_le_bytes());
+            }
+            COption::None => dst[299..329].fill(0),
+        }
+        pack_coption_key(
+            &self.close_authority,
+            (&mut dst[329..365]).try_into().unwrap(),
+        );
+    }
+}
+
+/// A fee of `basis_points` / 10_000 of each transferred amount, rounded up
+/// and capped at `maximum_fee`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransferFee {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+impl TransferFee {
+    /// Largest accepted `basis_points`, a fee of 100%.
+    pub const MAX_BASIS_POINTS: u16 = math::MAX_BASIS_POINTS;
+
+    /// Returns the fee withheld from a transfer of `amount`.
+    pub fn calculate_fee(&self, amount: u64) -> Option<u64> {
+        let fee = math::multiply_by_bps_ceil(amount, self.basis_points).ok()?;
+        Some(fee.min(self.maximum_fee))
+    }
+}
+
+/// An annual interest rate compounded continuously into UI amounts.
+///
+/// `accrued_scale` is the growth, as an [`interest::WAD`], of one raw unit
+/// up to `last_update_timestamp`; `current_rate_bps` applies from then on.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InterestRate {
+    pub current_rate_bps: i16,
+    pub last_update_timestamp: i64,
+    pub accrued_scale: u128,
+}
+
+impl InterestRate {
+    /// Returns the growth of one raw unit up to `unix_timestamp`, which may
+    /// not precede `last_update_timestamp`.
+    pub fn scale_at(&self, unix_timestamp: i64) -> Result<u128, TokenError> {
+        let elapsed = unix_timestamp
+            .checked_sub(self.last_update_timestamp)
+            .filter(|elapsed| *elapsed >= 0)
+            .ok_or(TokenError::InvalidTimestamp)?;
+        let growth =
+            interest::growth_factor(self.current_rate_bps, elapsed).ok_or(TokenError::Overflow)?;
+        interest::mul(self.accrued_scale, growth).ok_or(TokenError::Overflow)
+    }
+}
+
+/// Packed layout: `account_type (1) | version (1) | m (1) | n (1) | is_initialized (1) | signers (32 * MAX_SIGNERS)`.
+///
+/// Only the first `n` entries of `signers` are meaningful.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Multisig {
+    /// Number of signers required
+    pub m: u8,
+    /// Number of valid signers
+    pub n: u8,
+    pub is_initialized: bool,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::pubkey_array"))]
+    pub signers: [Pubkey; MAX_SIGNERS],
+}
+
+impl Sealed for Multisig {}
+
+impl IsInitialized for Multisig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Multisig {
+    const LEN: usize = 357;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        unpack_sized(input, AccountType::Multisig)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = unpack_header(src, AccountType::Multisig)?;
+        let m = src[0];
+        let n = src[1];
+        if n as usize > MAX_SIGNERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let is_initialized = match src[2] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let mut signers = [Pubkey::default(); MAX_SIGNERS];
+        for (signer, chunk) in signers.iter_mut().zip(src[3..].chunks_exact(32)) {
+            *signer = Pubkey::new_from_array(chunk.try_into().unwrap());
+        }
+
+        Ok(Multisig {
+            m,
+            n,
+            is_initialized,
+            signers,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = pack_header(dst, AccountType::Multisig);
+        dst[0] = self.m;
+        dst[1] = self.n;
+        dst[2] = self.is_initialized as u8;
+        for (chunk, signer) in dst[3..].chunks_exact_mut(32).zip(self.signers.iter()) {
+            chunk.copy_from_slice(signer.as_ref());
+        }
+    }
+}
+
+/// Packed layout: `account_type (1) | version (1) | is_initialized (1) | mint (32) | update_authority (32) | name (4 + 32) |
+/// symbol (4 + 10) | uri (4 + 200)`.
+///
+/// Each string is stored as a `u32` byte length followed by a zero-padded
+/// buffer of its maximum length.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TokenMetadata {
+    pub is_initialized: bool,
+    pub mint: Pubkey,
+    pub update_authority: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+impl TokenMetadata {
+    /// Maximum length of `name`, in bytes.
+    pub const MAX_NAME_LEN: usize = 32;
+    /// Maximum length of `symbol`, in bytes.
+    pub const MAX_SYMBOL_LEN: usize = 10;
+    /// Maximum length of `uri`, in bytes.
+    pub const MAX_URI_LEN: usize = 200;
+
+    /// Fails with `MetadataTooLong` if any field doesn't fit its buffer.
+    /// Values are never truncated to fit.
+    pub fn check_lengths(name: &str, symbol: &str, uri: &str) -> Result<(), TokenError> {
+        if name.len() > Self::MAX_NAME_LEN
+            || symbol.len() > Self::MAX_SYMBOL_LEN
+            || uri.len() > Self::MAX_URI_LEN
+        {
+            return Err(TokenError::MetadataTooLong);
+        }
+        Ok(())
+    }
+}
+
+impl Sealed for TokenMetadata {}
+
+impl IsInitialized for TokenMetadata {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for TokenMetadata {
+    const LEN: usize = 321;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        unpack_sized(input, AccountType::TokenMetadata)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = unpack_header(src, AccountType::TokenMetadata)?;
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let mint = Pubkey::new_from_array(src[1..33].try_into().unwrap());
+        let update_authority = Pubkey::new_from_array(src[33..65].try_into().unwrap());
+        let name = unpack_string(&src[65..101])?;
+        let symbol = unpack_string(&src[101..115])?;
+        let uri = unpack_string(&src[115..])?;
+
+        Ok(TokenMetadata {
+            is_initialized,
+            mint,
+            update_authority,
+            name,
+            symbol,
+            uri,
+        })
+    }
+
+    // Callers must have passed the strings through `check_lengths`; a field
+    // longer than its buffer is a bug and panics here.
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = pack_header(dst, AccountType::TokenMetadata);
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(self.mint.as_ref());
+        dst[33..65].copy_from_slice(self.update_authority.as_ref());
+        pack_string(&self.name, &mut dst[65..101]);
+        pack_string(&self.symbol, &mut dst[101..115]);
+        pack_string(&self.uri, &mut dst[115..]);
+    }
+}
+
+/// Packed layout: `account_type (1) | version (1) | is_initialized (1) | maker (32) | deposit_account (32) |
+/// maker_receive_account (32) | expected_amount (8) | deposit_mint (32) | deposit_amount (8)`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Escrow {
+    pub is_initialized: bool,
+    /// The account that created the escrow and receives its rent back.
+    pub maker: Pubkey,
+    /// Token account holding the maker's deposit, owned by the escrow
+    /// authority.
+    pub deposit_account: Pubkey,
+    /// Token account that receives the taker's payment.
+    pub maker_receive_account: Pubkey,
+    /// Amount the taker must pay into `maker_receive_account`.
+    pub expected_amount: u64,
+    /// Mint of the deposit, recorded when the escrow was opened.
+    pub deposit_mint: Pubkey,
+    /// Amount the deposit held once the maker's tokens arrived; an exchange
+    /// releases exactly this much.
+    pub deposit_amount: u64,
+}
+
+impl Escrow {
+    /// Size of the layout before escrows recorded their deposit. Escrows
+    /// migrated from it have an empty deposit mint and can only be cancelled.
+    pub const PRE_DEPOSIT_LEN: usize = 107;
+}
+
+impl Sealed for Escrow {}
+
+impl IsInitialized for Escrow {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Escrow {
+    const LEN: usize = 147;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        unpack_sized(input, AccountType::Escrow)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = unpack_header(src, AccountType::Escrow)?;
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let maker = Pubkey::new_from_array(src[1..33].try_into().unwrap());
+        let deposit_account = Pubkey::new_from_array(src[33..65].try_into().unwrap());
+        let maker_receive_account = Pubkey::new_from_array(src[65..97].try_into().unwrap());
+        let expected_amount = u64::from_le_bytes(src[97..105].try_into().unwrap());
+        let deposit_mint = Pubkey::new_from_array(src[105..137].try_into().unwrap());
+        let deposit_amount = u64::from_le_bytes(src[137..145].try_into().unwrap());
+
+        Ok(Escrow {
+            is_initialized,
+            maker,
+            deposit_account,
+            maker_receive_account,
+            expected_amount,
+            deposit_mint,
+            deposit_amount,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = pack_header(dst, AccountType::Escrow);
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(self.maker.as_ref());
+        dst[33..65].copy_from_slice(self.deposit_account.as_ref());
+        dst[65..97].copy_from_slice(self.maker_receive_account.as_ref());
+        dst[97..105].copy_from_slice(&self.expected_amount.to_le_bytes());
+        dst[105..137].copy_from_slice(self.deposit_mint.as_ref());
+        dst[137..145].copy_from_slice(&self.deposit_amount.to_le_bytes());
+    }
+}
+
+/// Packed layout: `account_type (1) | version (1) | is_initialized (1) | mint (32) | vault (32) | merkle_root (32) |
+/// total_claimable (8) | total_claimed (8)`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Distributor {
+    pub is_initialized: bool,
+    pub mint: Pubkey,
+    /// Token account holding the unclaimed tokens, owned by the distributor
+    /// authority.
+    pub vault: Pubkey,
+    /// Root of the merkle tree of `distributor::claim_leaf`s.
+    pub merkle_root: [u8; 32],
+    /// Amount deposited into the vault when the distribution was created.
+    pub total_claimable: u64,
+    /// Amount paid out by claims so far.
+    pub total_claimed: u64,
+}
+
+impl Sealed for Distributor {}
+
+impl IsInitialized for Distributor {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Distributor {
+    const LEN: usize = 115;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        unpack_sized(input, AccountType::Distributor)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = unpack_header(src, AccountType::Distributor)?;
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let mint = Pubkey::new_from_array(src[1..33].try_into().unwrap());
+        let vault = Pubkey::new_from_array(src[33..65].try_into().unwrap());
+        let merkle_root = src[65..97].try_into().unwrap();
+        let total_claimable = u64::from_le_bytes(src[97..105].try_into().unwrap());
+        let total_claimed = u64::from_le_bytes(src[105..113].try_into().unwrap());
+
+        Ok(Distributor {
+            is_initialized,
+            mint,
+            vault,
+            merkle_root,
+            total_claimable,
+            total_claimed,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = pack_header(dst, AccountType::Distributor);
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(self.mint.as_ref());
+        dst[33..65].copy_from_slice(self.vault.as_ref());
+        dst[65..97].copy_from_slice(&self.merkle_root);
+        dst[97..105].copy_from_slice(&self.total_claimable.to_le_bytes());
+        dst[105..113].copy_from_slice(&self.total_claimed.to_le_bytes());
+    }
+}
+
+/// Packed layout: `account_type (1) | version (1) | is_initialized (1) | claimant (32) | amount (8)`.
+///
+/// Records that one leaf of a distribution has been claimed; it lives at
+/// `distributor::get_claim_address` of the distributor and leaf index.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClaimStatus {
+    pub is_initialized: bool,
+    pub claimant: Pubkey,
+    pub amount: u64,
+}
+
+impl Sealed for ClaimStatus {}
+
+impl IsInitialized for ClaimStatus {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ClaimStatus {
+    const LEN: usize = 43;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        unpack_sized(input, AccountType::ClaimStatus)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = unpack_header(src, AccountType::ClaimStatus)?;
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let claimant = Pubkey::new_from_array(src[1..33].try_into().unwrap());
+        let amount = u64::from_le_bytes(src[33..41].try_into().unwrap());
+
+        Ok(ClaimStatus {
+            is_initialized,
+            claimant,
+            amount,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = pack_header(dst, AccountType::ClaimStatus);
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(self.claimant.as_ref());
+        dst[33..41].copy_from_slice(&self.amount.to_le_bytes());
+    }
+}
+
+/// Packed layout: `account_type (1) | version (1) | is_initialized (1) | mint (32) | beneficiary (32) | total_amount (8) |
+/// start_ts (8) | end_ts (8) | claimed_amount (8)`.
+///
+/// Tokens of `mint` that become claimable by the `beneficiary` token
+/// account linearly between `start_ts` and `end_ts`; it lives at
+/// `vesting::get_vesting_address` of the mint and beneficiary.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VestingSchedule {
+    pub is_initialized: bool,
+    pub mint: Pubkey,
+    pub beneficiary: Pubkey,
+    pub total_amount: u64,
+    /// Unix timestamp before which nothing has vested.
+    pub start_ts: i64,
+    /// Unix timestamp from which everything has vested.
+    pub end_ts: i64,
+    /// Amount minted to the beneficiary by claims so far.
+    pub claimed_amount: u64,
+}
+
+impl VestingSchedule {
+    /// Returns the amount vested by `unix_timestamp`.
+    pub fn vested_amount(&self, unix_timestamp: i64) -> u64 {
+        if unix_timestamp < self.start_ts {
+            0
+        } else if unix_timestamp >= self.end_ts {
+            self.total_amount
+        } else {
+            let elapsed = (unix_timestamp as i128 - self.start_ts as i128) as u128;
+            let duration = (self.end_ts as i128 - self.start_ts as i128) as u128;
+            // Less than `total_amount`, since `elapsed < duration`.
+            (self.total_amount as u128 * elapsed / duration) as u64
+        }
+    }
+}
+
+impl Sealed for VestingSchedule {}
+
+impl IsInitialized for VestingSchedule {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for VestingSchedule {
+    const LEN: usize = 99;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        unpack_sized(input, AccountType::VestingSchedule)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = unpack_header(src, AccountType::VestingSchedule)?;
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let mint = Pubkey::new_from_array(src[1..33].try_into().unwrap());
+        let beneficiary = Pubkey::new_from_array(src[33..65].try_into().unwrap());
+        let total_amount = u64::from_le_bytes(src[65..73].try_into().unwrap());
+        let start_ts = i64::from_le_bytes(src[73..81].try_into().unwrap());
+        let end_ts = i64::from_le_bytes(src[81..89].try_into().unwrap());
+        let claimed_amount = u64::from_le_bytes(src[89..97].try_into().unwrap());
+
+        Ok(VestingSchedule {
+            is_initialized,
+            mint,
+            beneficiary,
+            total_amount,
+            start_ts,
+            end_ts,
+            claimed_amount,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = pack_header(dst, AccountType::VestingSchedule);
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(self.mint.as_ref());
+        dst[33..65].copy_from_slice(self.beneficiary.as_ref());
+        dst[65..73].copy_from_slice(&self.total_amount.to_le_bytes());
+        dst[73..81].copy_from_slice(&self.start_ts.to_le_bytes());
+        dst[81..89].copy_from_slice(&self.end_ts.to_le_bytes());
+        dst[89..97].copy_from_slice(&self.claimed_amount.to_le_bytes());
+    }
+}
+
+/// Packed layout: `account_type (1) | version (1) | is_initialized (1) | mint (32) | authority (32) | owners_len (4) |
+/// owners (32 * owners_len)`.
+///
+/// Owners whose token accounts of `mint` may receive tokens, when the mint
+/// enforces its allowlist. The account is exactly as long as its owners
+/// need, growing and shrinking as they are added and removed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Allowlist {
+    pub is_initialized: bool,
+    pub mint: Pubkey,
+    /// Authority that can add and remove owners.
+    pub authority: Pubkey,
+    pub owners: Vec<Pubkey>,
+}
+
+impl Allowlist {
+    /// Size of the layout before the owners.
+    pub const HEADER_LEN: usize = 71;
+    /// Maximum number of owners on a list.
+    pub const MAX_OWNERS: usize = 256;
+
+    /// Size of an allowlist holding `owners` owners.
+    pub const fn len_for(owners: usize) -> usize {
+        Self::HEADER_LEN + 32 * owners
+    }
+
+    /// Whether `owner` is on the list.
+    pub fn contains(&self, owner: &Pubkey) -> bool {
+        self.owners.iter().any(|listed| listed == owner)
+    }
+
+    /// Unpacks an initialized allowlist, failing with `InvalidAccountData`
+    /// unless `src` is exactly as long as its owners need.
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        check_account_type(src, AccountType::Allowlist)?;
+        let header = src
+            .get(..Self::HEADER_LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let header = unpack_header(header, AccountType::Allowlist)?;
+        if header[0] != 1 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let owners_len = u32::from_le_bytes(header[65..69].try_into().unwrap()) as usize;
+        if owners_len > Self::MAX_OWNERS || src.len() != Self::len_for(owners_len) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Allowlist {
+            is_initialized: true,
+            mint: Pubkey::new_from_array(header[1..33].try_into().unwrap()),
+            authority: Pubkey::new_from_array(header[33..65].try_into().unwrap()),
+            owners: src[Self::HEADER_LEN..]
+                .chunks_exact(32)
+                .map(|owner| Pubkey::new_from_array(owner.try_into().unwrap()))
+                .collect(),
+        })
+    }
+
+    /// Packs the allowlist into `dst`, which must be exactly
+    /// [`Allowlist::len_for`] its owners long.
+    pub fn pack(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if self.owners.len() > Self::MAX_OWNERS || dst.len() != Self::len_for(self.owners.len()) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let dst = pack_header(dst, AccountType::Allowlist);
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(self.mint.as_ref());
+        dst[33..65].copy_from_slice(self.authority.as_ref());
+        dst[65..69].copy_from_slice(&(self.owners.len() as u32).to_le_bytes());
+        for (chunk, owner) in dst[Self::HEADER_LEN - ACCOUNT_HEADER_LEN..]
+            .chunks_exact_mut(32)
+            .zip(&self.owners)
+        {
+            chunk.copy_from_slice(owner.as_ref());
+        }
+        Ok(())
+    }
+}
+
+/// Packed layout: `account_type (1) | version (1) | is_initialized (1) | from_mint (32) | to_mint (32) | numerator (8) |
+/// denominator (8) | conversion_authority (32)`.
+///
+/// A fixed exchange rate from `from_mint` to `to_mint`: `Convert` burns
+/// tokens of the one and mints `numerator / denominator` as many of the
+/// other, signing as `conversion_authority`, which must be `to_mint`'s mint
+/// authority.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Converter {
+    pub is_initialized: bool,
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub numerator: u64,
+    pub denominator: u64,
+    /// `converter::get_converter_authority` of the converter.
+    pub conversion_authority: Pubkey,
+}
+
+impl Converter {
+    /// Returns the amounts burned and minted by converting up to `amount`:
+    /// the minted amount rounds down, and only as much as it takes is
+    /// burned, so the remainder stays unconverted. `None` if the minted
+    /// amount overflows, or the rate divides by zero.
+    pub fn convert(&self, amount: u64) -> Option<(u64, u64)> {
+        let (numerator, denominator) = (self.numerator as u128, self.denominator as u128);
+        let minted = (amount as u128 * numerator).checked_div(denominator)?;
+        // At most `amount`, the least that still mints `minted`.
+        let burned = (minted * denominator).div_ceil(numerator);
+        Some((burned as u64, u64::try_from(minted).ok()?))
+    }
+}
+
+impl Sealed for Converter {}
+
+impl IsInitialized for Converter {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Converter {
+    const LEN: usize = 115;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        unpack_sized(input, AccountType::Converter)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = unpack_header(src, AccountType::Converter)?;
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let from_mint = Pubkey::new_from_array(src[1..33].try_into().unwrap());
+        let to_mint = Pubkey::new_from_array(src[33..65].try_into().unwrap());
+        let numerator = u64::from_le_bytes(src[65..73].try_into().unwrap());
+        let denominator = u64::from_le_bytes(src[73..81].try_into().unwrap());
+        let conversion_authority = Pubkey::new_from_array(src[81..113].try_into().unwrap());
+
+        Ok(Converter {
+            is_initialized,
+            from_mint,
+            to_mint,
+            numerator,
+            denominator,
+            conversion_authority,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = pack_header(dst, AccountType::Converter);
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(self.from_mint.as_ref());
+        dst[33..65].copy_from_slice(self.to_mint.as_ref());
+        dst[65..73].copy_from_slice(&self.numerator.to_le_bytes());
+        dst[73..81].copy_from_slice(&self.denominator.to_le_bytes());
+        dst[81..113].copy_from_slice(self.conversion_authority.as_ref());
+    }
+}
+
+/// Packed layout: `account_type (1) | version (1) | is_initialized (1) | token_account (32) | owner (32) | amount (8) |
+/// slot (8) | unix_timestamp (8) | payer (32)`.
+///
+/// The balance and owner of `token_account` as of `slot`, written once by
+/// `SnapshotBalance`; it lives at `snapshot::get_snapshot_address` of the
+/// token account and the snapshot id. `payer` funded it and is the only one
+/// who can close it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BalanceSnapshot {
+    pub is_initialized: bool,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+    pub payer: Pubkey,
+}
+
+impl Sealed for BalanceSnapshot {}
+
+impl IsInitialized for BalanceSnapshot {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for BalanceSnapshot {
+    const LEN: usize = 123;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        unpack_sized(input, AccountType::BalanceSnapshot)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let src = unpack_header(src, AccountType::BalanceSnapshot)?;
+        let is_initialized = match src[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let token_account = Pubkey::new_from_array(src[1..33].try_into().unwrap());
+        let owner = Pubkey::new_from_array(src[33..65].try_into().unwrap());
+        let amount = u64::from_le_bytes(src[65..73].try_into().unwrap());
+        let slot = u64::from_le_bytes(src[73..81].try_into().unwrap());
+        let unix_timestamp = i64::from_le_bytes(src[81..89].try_into().unwrap());
+        let payer = Pubkey::new_from_array(src[89..121].try_into().unwrap());
+
+        Ok(BalanceSnapshot {
+            is_initialized,
+            token_account,
+            owner,
+            amount,
+            slot,
+            unix_timestamp,
+            payer,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = pack_header(dst, AccountType::BalanceSnapshot);
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(self.token_account.as_ref());
+        dst[33..65].copy_from_slice(self.owner.as_ref());
+        dst[65..73].copy_from_slice(&self.amount.to_le_bytes());
+        dst[73..81].copy_from_slice(&self.slot.to_le_bytes());
+        dst[81..89].copy_from_slice(&self.unix_timestamp.to_le_bytes());
+        dst[89..121].copy_from_slice(self.payer.as_ref());
+    }
+}
+
+/// The type of a program account, written as its first byte.
+///
+/// Every layout starts with `account_type (1) | version (1)`, so an account
+/// of one type passed where another is expected fails with
+/// `InvalidAccountType` rather than having its bytes read as the wrong
+/// fields. Types start at 3, above the version byte of 2 that began version
+/// 2 [`Token`] and [`Mint`] accounts and the `is_initialized` flag that began
+/// older allowlists, so those are never mistaken for current accounts.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive)]
+pub enum AccountType {
+    Token = 3,
+    Mint,
+    Multisig,
+    TokenMetadata,
+    Escrow,
+    Distributor,
+    ClaimStatus,
+    VestingSchedule,
+    Allowlist,
+    Converter,
+    BalanceSnapshot,
+}
+
+impl AccountType {
+    /// The type written in `src`, if it starts with the header of a current
+    /// layout.
+    pub fn of(src: &[u8]) -> Option<Self> {
+        match src {
+            [account_type, ACCOUNT_VERSION, ..] => Self::from_u8(*account_type),
+            _ => None,
+        }
+    }
+}
+
+/// Version of the current layouts, written after their [`AccountType`].
+/// Version 2 [`Token`] and [`Mint`] accounts started with their version and
+/// had no account type; the legacy layouts had neither.
+pub const ACCOUNT_VERSION: u8 = 3;
+
+/// Size of the `account_type (1) | version (1)` header of every layout.
+pub const ACCOUNT_HEADER_LEN: usize = 2;
+
+/// Fails with `InvalidAccountType` if `src` is an account of a type other
+/// than `account_type`.
+fn check_account_type(src: &[u8], account_type: AccountType) -> Result<(), ProgramError> {
+    match AccountType::of(src) {
+        Some(found) if found != account_type => Err(TokenError::InvalidAccountType.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Splits off the header of an account of `account_type`, also accepting
+/// zeros, the header of an account that has never been written.
+fn unpack_header(src: &[u8], account_type: AccountType) -> Result<&[u8], ProgramError> {
+    check_account_type(src, account_type)?;
+    match src {
+        [0, 0, rest @ ..] => Ok(rest),
+        [found, ACCOUNT_VERSION, rest @ ..] if *found == account_type as u8 => Ok(rest),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+fn pack_header(dst: &mut [u8], account_type: AccountType) -> &mut [u8] {
+    dst[0] = account_type as u8;
+    dst[1] = ACCOUNT_VERSION;
+    &mut dst[ACCOUNT_HEADER_LEN..]
+}
+
+/// `Pack::unpack_unchecked` for fixed-size types, checking the account type
+/// before the size so that another type fails with `InvalidAccountType`
+/// however long it is.
+fn unpack_sized<T: Pack>(input: &[u8], account_type: AccountType) -> Result<T, ProgramError> {
+    check_account_type(input, account_type)?;
+    if input.len() != T::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    T::unpack_from_slice(input)
+}
+
+/// Rewrites an account written before [`AccountType`]s, or an escrow written
+/// before it recorded its deposit, into the current layout, or returns
+/// `None` if `src` isn't one. Only the header changes: version 2 [`Token`]
+/// and [`Mint`] accounts trade their version byte for it, the other types
+/// gain it, and each old account's size tells its type. Escrows also gain
+/// whichever of the newer fields they lack, unset.
+/// The legacy layouts, which have no version byte, are read by
+/// [`Token::unpack_any_version`] and [`Mint::unpack_any_version`] instead.
+pub fn add_account_type(src: &[u8]) -> Option<Vec<u8>> {
+    const VERSION_2: u8 = 2;
+    let untyped_len = |len: usize| len - ACCOUNT_HEADER_LEN;
+    let untyped_owners = |len: usize| len.checked_sub(untyped_len(Allowlist::HEADER_LEN));
+    let (account_type, body) = match (src.len(), src.first()) {
+        (len, Some(&VERSION_2)) if (Token::LEN - 1..extension::MAX_ACCOUNT_LEN).contains(&len) => {
+            (AccountType::Token, &src[1..])
+        }
+        (Mint::VERSION_2_LEN, Some(&VERSION_2)) => (AccountType::Mint, &src[1..]),
+        (len, _) if len == untyped_len(Multisig::LEN) => (AccountType::Multisig, src),
+        (len, _) if len == untyped_len(TokenMetadata::LEN) => (AccountType::TokenMetadata, src),
+        (len, _) if len == untyped_len(Escrow::PRE_DEPOSIT_LEN) => (AccountType::Escrow, src),
+        (Escrow::PRE_DEPOSIT_LEN, _) if AccountType::of(src) == Some(AccountType::Escrow) => {
+            (AccountType::Escrow, &src[ACCOUNT_HEADER_LEN..])
+        }
+        (len, _) if len == untyped_len(Distributor::LEN) => (AccountType::Distributor, src),
+        (len, _) if len == untyped_len(ClaimStatus::LEN) => (AccountType::ClaimStatus, src),
+        (len, _) if len == untyped_len(VestingSchedule::LEN) => (AccountType::VestingSchedule, src),
+        // Allowlists were always initialized, which tells them from current
+        // accounts of the same size.
+        (len, Some(&1)) if untyped_owners(len).is_some_and(|owners| owners % 32 == 0) => {
+            (AccountType::Allowlist, src)
+        }
+        _ => return None,
+    };
+    let mut migrated = vec![account_type as u8, ACCOUNT_VERSION];
+    migrated.extend_from_slice(body);
+    if account_type == AccountType::Escrow {
+        migrated.resize(Escrow::LEN, 0);
+    }
+    Some(migrated)
+}
+
+fn pack_string(src: &str, dst: &mut [u8]) {
+    let (len, buf) = dst.split_at_mut(4);
+    len.copy_from_slice(&(src.len() as u32).to_le_bytes());
+    buf.fill(0);
+    buf[..src.len()].copy_from_slice(src.as_bytes());
+}
+
+fn unpack_string(src: &[u8]) -> Result<String, ProgramError> {
+    let (len, buf) = src.split_at(4);
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    let bytes = buf.get(..len).ok_or(ProgramError::InvalidAccountData)?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+pub(crate) fn pack_coption_key(src: &COption<Pubkey>, dst: &mut [u8; 36]) {
+    match src {
+        COption::Some(key) => {
+            dst[0..4].copy_from_slice(&[1, 0, 0, 0]);
+            dst[4..36].copy_from_slice(key.as_ref());
+        }
+        COption::None => {
+            dst.fill(0);
+        }
+    }
+}
+
+pub(crate) fn unpack_coption_key(src: &[u8; 36]) -> Result<COption<Pubkey>, ProgramError> {
+    match src[0..4] {
+        [0, 0, 0, 0] => Ok(COption::None),
+        [1, 0, 0, 0] => Ok(COption::Some(Pubkey::new_from_array(
+            src[4..36].try_into().unwrap(),
+        ))),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+fn pack_coption_u64(src: &COption<u64>, dst: &mut [u8; 12]) {
+    match src {
+        COption::Some(value) => {
+            dst[0..4].copy_from_slice(&[1, 0, 0, 0]);
+            dst[4..12].copy_from_slice(&value.to_le_bytes());
+        }
+        COption::None => {
+            dst.fill(0);
+        }
+    }
+}
+
+fn unpack_coption_u64(src: &[u8; 12]) -> Result<COption<u64>, ProgramError> {
+    match src[0..4] {
+        [0, 0, 0, 0] => Ok(COption::None),
+        [1, 0, 0, 0] => Ok(COption::Some(u64::from_le_bytes(
+            src[4..12].try_into().unwrap(),
+        ))),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// With the `borsh` feature, accounts (de)serialize to exactly their `Pack`
+/// layout, so each account type has a single wire format.
+#[cfg(feature = "borsh")]
+macro_rules! impl_borsh_via_pack {
+    ($($account:ty),*) => {$(
+        impl borsh::BorshSerialize for $account {
+            fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+                let mut buf = [0; <$account>::LEN];
+                self.pack_into_slice(&mut buf);
+                writer.write_all(&buf)
+            }
+        }
+
+        impl borsh::BorshDeserialize for $account {
+            fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+                let mut buf = [0; <$account>::LEN];
+                reader.read_exact(&mut buf)?;
+                Self::unpack_from_slice(&buf).map_err(|error| {
+                    borsh::io::Error::new(borsh::io::ErrorKind::InvalidData, error.to_string())
+                })
+            }
+        }
+    )*};
+}
+
+#[cfg(feature = "borsh")]
+impl_borsh_via_pack!(Token, Mint);
+
+const _: () = assert!(Token::LEN == 2 + 8 + 32 + 32 + 36 + 8 + 1 + 12 + 12 + 1 + 1 + 36 + 1);
+const _: () = assert!(
+    Mint::LEN
+        == 2 + 36 + 8 + 1 + 1 + 36 + 12 + 36 + 14 + 8 + 1 + 36 + 1 + 36 + 36 + 1 + 36 + 30 + 36
+);
+const _: () = assert!(Multisig::LEN == 2 + 3 + 32 * MAX_SIGNERS);
+const _: () = assert!(Escrow::LEN == 2 + 1 + 32 + 32 + 32 + 8 + 32 + 8);
+const _: () = assert!(VestingSchedule::LEN == 2 + 1 + 32 + 32 + 8 + 8 + 8 + 8);
+const _: () = assert!(Allowlist::HEADER_LEN == 2 + 1 + 32 + 32 + 4);
+const _: () = assert!(Converter::LEN == 2 + 1 + 32 + 32 + 8 + 8 + 32);
+const _: () = assert!(BalanceSnapshot::LEN == 2 + 1 + 32 + 32 + 8 + 8 + 8 + 32);
+const _: () = assert!(
+    TokenMetadata::LEN
+        == 2 + 1
+            + 32
+            + 32
+            + (4 + TokenMetadata::MAX_NAME_LEN)
+            + (4 + TokenMetadata::MAX_SYMBOL_LEN)
+            + (4 + TokenMetadata::MAX_URI_LEN)
+);
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use proptest::{collection::vec, option, prelude::*};
+
+    pub(crate) fn pubkey() -> impl Strategy<Value = Pubkey> {
+        any::<[u8; 32]>().prop_map(Pubkey::new_from_array)
+    }
+
+    pub(crate) fn coption<T: std::fmt::Debug>(
+        strategy: impl Strategy<Value = T>,
+    ) -> impl Strategy<Value = COption<T>> {
+        option::of(strategy).prop_map(COption::from)
+    }
+
+    pub(crate) fn account_state() -> impl Strategy<Value = AccountState> {
+        prop_oneof![
+            Just(AccountState::Uninitialized),
+            Just(AccountState::Initialized),
+            Just(AccountState::Frozen),
+        ]
+    }
+
+    prop_compose! {
+        pub(crate) fn token()(
+            mint in pubkey(),
+            owner in pubkey(),
+            amount in any::<u64>(),
+            delegate in coption(pubkey()),
+            delegated_amount in any::<u64>(),
+            state in account_state(),
+            is_native in coption(any::<u64>()),
+            withheld_amount in coption(any::<u64>()),
+            is_non_transferable in any::<bool>(),
+            is_pausable in any::<bool>(),
+            pending_owner in coption(pubkey()),
+            has_transfer_hook in any::<bool>(),
+        ) -> Token {
+            Token {
+                mint,
+                owner,
+                amount,
+                delegate,
+                delegated_amount,
+                state,
+                is_native,
+                withheld_amount,
+                is_non_transferable,
+                is_pausable,
+                pending_owner,
+                has_transfer_hook,
+            }
+        }
+    }
+
+    prop_compose! {
+        pub(crate) fn mint()(
+            (mint_authority, supply, decimals, is_initialized, freeze_authority, max_supply) in (
+                coption(pubkey()),
+                any::<u64>(),
+                any::<u8>(),
+                any::<bool>(),
+                coption(pubkey()),
+                coption(any::<u64>()),
+            ),
+            (transfer_fee_authority, transfer_fee, withheld_amount, non_transferable) in (
+                coption(pubkey()),
+                coption((any::<u16>(), any::<u64>()).prop_map(|(basis_points, maximum_fee)| {
+                    TransferFee { basis_points, maximum_fee }
+                })),
+                any::<u64>(),
+                any::<bool>(),
+            ),
+            (pause_authority, paused, permanent_delegate, transfer_hook_program, default_account_state) in (
+                coption(pubkey()),
+                any::<bool>(),
+                coption(pubkey()),
+                coption(pubkey()),
+                account_state(),
+            ),
+            (rate_authority, interest_rate, close_authority) in (
+                coption(pubkey()),
+                coption((any::<i16>(), any::<i64>(), any::<u128>()).prop_map(
+                    |(current_rate_bps, last_update_timestamp, accrued_scale)| InterestRate {
+                        current_rate_bps,
+                        last_update_timestamp,
+                        accrued_scale,
+                    },
+                )),
+                coption(pubkey()),
+            ),
+        ) -> Mint {
+            Mint {
+                mint_authority,
+                supply,
+                decimals,
+                is_initialized,
+                freeze_authority,
+                max_supply,
+                transfer_fee_authority,
+                transfer_fee,
+                withheld_amount,
+                non_transferable,
+                pause_authority,
+                paused,
+                permanent_delegate,
+                transfer_hook_program,
+                default_account_state,
+                rate_authority,
+                interest_rate,
+                close_authority,
+            }
+        }
+    }
+
+    prop_compose! {
+        fn multisig()(
+            m in any::<u8>(),
+            n in 0..=MAX_SIGNERS as u8,
+            is_initialized in any::<bool>(),
+            signers in vec(pubkey(), MAX_SIGNERS),
+        ) -> Multisig {
+            Multisig {
+                m,
+                n,
+                is_initialized,
+                signers: signers.try_into().unwrap(),
+            }
+        }
+    }
+
+    prop_compose! {
+        fn metadata()(
+            is_initialized in any::<bool>(),
+            mint in pubkey(),
+            update_authority in pubkey(),
+            name in ".{0,8}",
+            symbol in "[A-Z]{0,10}",
+            uri in "[ -~]{0,200}",
+        ) -> TokenMetadata {
+            TokenMetadata { is_initialized, mint, update_authority, name, symbol, uri }
+        }
+    }
+
+    prop_compose! {
+        fn escrow()(
+            is_initialized in any::<bool>(),
+            maker in pubkey(),
+            deposit_account in pubkey(),
+            maker_receive_account in pubkey(),
+            expected_amount in any::<u64>(),
+            deposit_mint in pubkey(),
+            deposit_amount in any::<u64>(),
+        ) -> Escrow {
+            Escrow {
+                is_initialized,
+                maker,
+                deposit_account,
+                maker_receive_account,
+                expected_amount,
+                deposit_mint,
+                deposit_amount,
+            }
+        }
+    }
+
+    prop_compose! {
+        fn distributor()(
+            is_initialized in any::<bool>(),
+            mint in pubkey(),
+            vault in pubkey(),
+            merkle_root in any::<[u8; 32]>(),
+            total_claimable in any::<u64>(),
+            total_claimed in any::<u64>(),
+        ) -> Distributor {
+            Distributor { is_initialized, mint, vault, merkle_root, total_claimable, total_claimed }
+        }
+    }
+
+    prop_compose! {
+        fn converter()(
+            is_initialized in any::<bool>(),
+            from_mint in pubkey(),
+            to_mint in pubkey(),
+            numerator in any::<u64>(),
+            denominator in any::<u64>(),
+            conversion_authority in pubkey(),
+        ) -> Converter {
+            Converter { is_initialized, from_mint, to_mint, numerator, denominator, conversion_authority }
+        }
+    }
+
+    prop_compose! {
+        fn balance_snapshot()(
+            is_initialized in any::<bool>(),
+            token_account in pubkey(),
+            owner in pubkey(),
+            amount in any::<u64>(),
+            slot in any::<u64>(),
+            unix_timestamp in any::<i64>(),
+            payer in pubkey(),
+        ) -> BalanceSnapshot {
+            BalanceSnapshot { is_initialized, token_account, owner, amount, slot, unix_timestamp, payer }
+        }
+    }
+
+    prop_compose! {
+        fn claim_status()(
+            is_initialized in any::<bool>(),
+            claimant in pubkey(),
+            amount in any::<u64>(),
+        ) -> ClaimStatus {
+            ClaimStatus { is_initialized, claimant, amount }
+        }
+    }
+
+    prop_compose! {
+        fn vesting_schedule()(
+            is_initialized in any::<bool>(),
+            mint in pubkey(),
+            beneficiary in pubkey(),
+            total_amount in any::<u64>(),
+            start_ts in any::<i64>(),
+            end_ts in any::<i64>(),
+            claimed_amount in any::<u64>(),
+        ) -> VestingSchedule {
+            VestingSchedule {
+                is_initialized,
+                mint,
+                beneficiary,
+                total_amount,
+                start_ts,
+                end_ts,
+                claimed_amount,
+            }
+        }
+    }
+
+    prop_compose! {
+        fn allowlist()(
+            mint in pubkey(),
+            authority in pubkey(),
+            owners in vec(pubkey(), 0..=8),
+        ) -> Allowlist {
+            Allowlist { is_initialized: true, mint, authority, owners }
+        }
+    }
+
+    fn round_trip<T: Pack + PartialEq + std::fmt::Debug + Clone>(value: T) {
+        let mut buf = vec![0; T::LEN];
+        value.pack_into_slice(&mut buf);
+        assert_eq!(T::unpack_from_slice(&buf).unwrap(), value);
+    }
+
+    /// Unpacking must return, never panic, whatever one byte is changed to.
+    fn corrupt_byte<T: Pack>(mut buf: Vec<u8>, index: usize, byte: u8) {
+        let index = index % buf.len();
+        buf[index] = byte;
+        let _ = T::unpack_from_slice(&buf);
+    }
+
+    pub(crate) fn packed<T: Pack>(value: &T) -> Vec<u8> {
+        let mut buf = vec![0; T::LEN];
+        value.pack_into_slice(&mut buf);
+        buf
+    }
+
+    /// Unpacks `data` as each account type, in `AccountType` order.
+    fn unpack_as_each_type(data: &[u8]) -> [Result<(), ProgramError>; 11] {
+        [
+            Token::unpack_unchecked(data).map(drop),
+            Mint::unpack_unchecked(data).map(drop),
+            Multisig::unpack_unchecked(data).map(drop),
+            TokenMetadata::unpack_unchecked(data).map(drop),
+            Escrow::unpack_unchecked(data).map(drop),
+            Distributor::unpack_unchecked(data).map(drop),
+            ClaimStatus::unpack_unchecked(data).map(drop),
+            VestingSchedule::unpack_unchecked(data).map(drop),
+            Allowlist::unpack(data).map(drop),
+            Converter::unpack_unchecked(data).map(drop),
+            BalanceSnapshot::unpack_unchecked(data).map(drop),
+        ]
+    }
+
+    /// Buffers of any length other than `LEN` are rejected.
+    fn wrong_length<T: Pack>(len: usize) {
+        if len != T::LEN {
+            assert!(T::unpack_from_slice(&vec![0; len]).is_err());
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn token_round_trips(token in token()) {
+            round_trip(token);
+        }
+
+        #[test]
+        fn mint_round_trips(mint in mint()) {
+            round_trip(mint);
+        }
+
+        #[test]
+        fn multisig_round_trips(multisig in multisig()) {
+            round_trip(multisig);
+        }
+
+        #[test]
+        fn metadata_round_trips(metadata in metadata()) {
+            round_trip(metadata);
+        }
+
+        #[test]
+        fn escrow_round_trips(escrow in escrow()) {
+            round_trip(escrow);
+        }
+
+        #[test]
+        fn distributor_round_trips(distributor in distributor()) {
+            round_trip(distributor);
+        }
+
+        #[test]
+        fn converter_round_trips(converter in converter()) {
+            round_trip(converter);
+        }
+
+        #[test]
+        fn balance_snapshot_round_trips(snapshot in balance_snapshot()) {
+            round_trip(snapshot);
+        }
+
+        #[test]
+        fn conversions_round_down_and_burn_no_more_than_they_use(
+            converter in converter(),
+            amount in any::<u64>(),
+        ) {
+            // `CreateConverter` rejects zero rates.
+            prop_assume!(converter.numerator > 0 && converter.denominator > 0);
+            let exact = amount as u128 * converter.numerator as u128 / converter.denominator as u128;
+            match converter.convert(amount) {
+                Some((burned, minted)) => {
+                    prop_assert_eq!(minted as u128, exact);
+                    prop_assert!(burned <= amount);
+                    // Burning one less would mint less.
+                    if let Some(short) = burned.checked_sub(1) {
+                        prop_assert!(converter.convert(short).unwrap().1 < minted);
+                    }
+                    prop_assert_eq!(converter.convert(burned), Some((burned, minted)));
+                }
+                None => prop_assert!(exact > u64::MAX as u128),
+            }
+        }
+
+        #[test]
+        fn claim_status_round_trips(claim_status in claim_status()) {
+            round_trip(claim_status);
+        }
+
+        #[test]
+        fn vesting_schedule_round_trips(schedule in vesting_schedule()) {
+            round_trip(schedule);
+        }
+
+        #[test]
+        fn vesting_grows_from_nothing_to_the_total(
+            schedule in vesting_schedule(),
+            a in any::<i64>(),
+            b in any::<i64>(),
+        ) {
+            // `CreateVesting` rejects schedules that end before they start.
+            prop_assume!(schedule.start_ts <= schedule.end_ts);
+            let (earlier, later) = (a.min(b), a.max(b));
+            let vested = schedule.vested_amount(earlier);
+            prop_assert!(vested <= schedule.vested_amount(later));
+            prop_assert!(vested <= schedule.total_amount);
+            if earlier < schedule.start_ts {
+                prop_assert_eq!(vested, 0);
+            }
+            if earlier >= schedule.end_ts {
+                prop_assert_eq!(vested, schedule.total_amount);
+            }
+        }
+
+        #[test]
+        fn allowlist_round_trips(allowlist in allowlist()) {
+            let mut buf = vec![0; Allowlist::len_for(allowlist.owners.len())];
+            allowlist.pack(&mut buf).unwrap();
+            prop_assert_eq!(Allowlist::unpack(&buf).unwrap(), allowlist.clone());
+            // The account must be exactly as long as its owners need.
+            let mut longer = buf.clone();
+            longer.extend_from_slice(&[0; 32]);
+            prop_assert!(Allowlist::unpack(&longer).is_err());
+            prop_assert!(Allowlist::unpack(&buf[..buf.len() - 1]).is_err());
+            prop_assert!(allowlist.pack(&mut longer).is_err());
+        }
+
+        #[test]
+        fn corrupted_allowlists_never_panic(
+            allowlist in allowlist(),
+            index in any::<usize>(),
+            byte in any::<u8>(),
+        ) {
+            let mut buf = vec![0; Allowlist::len_for(allowlist.owners.len())];
+            allowlist.pack(&mut buf).unwrap();
+            let index = index % buf.len();
+            buf[index] = byte;
+            let _ = Allowlist::unpack(&buf);
+        }
+
+        #[test]
+        fn accounts_only_unpack_as_their_own_type(
+            token in token(),
+            mint in mint(),
+            multisig in multisig(),
+            metadata in metadata(),
+            escrow in escrow(),
+            distributor in distributor(),
+            claim_status in claim_status(),
+            schedule in vesting_schedule(),
+            allowlist in allowlist(),
+            converter in converter(),
+            snapshot in balance_snapshot(),
+        ) {
+            let mut allowlist_data = vec![0; Allowlist::len_for(allowlist.owners.len())];
+            allowlist.pack(&mut allowlist_data).unwrap();
+            let accounts = [
+                packed(&token),
+                packed(&mint),
+                packed(&multisig),
+                packed(&metadata),
+                packed(&escrow),
+                packed(&distributor),
+                packed(&claim_status),
+                packed(&schedule),
+                allowlist_data,
+                packed(&converter),
+                packed(&snapshot),
+            ];
+            for (account_type, data) in accounts.iter().enumerate() {
+                for (expected, unpacked) in unpack_as_each_type(data).into_iter().enumerate() {
+                    if expected != account_type {
+                        prop_assert_eq!(unpacked, Err(TokenError::InvalidAccountType.into()));
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn corrupted_accounts_never_panic(
+            data in vec(any::<u8>(), Mint::LEN.max(Multisig::LEN)),
+            index in any::<usize>(),
+            byte in any::<u8>(),
+        ) {
+            corrupt_byte::<Token>(data[..Token::LEN].to_vec(), index, byte);
+            corrupt_byte::<Mint>(data[..Mint::LEN].to_vec(), index, byte);
+            corrupt_byte::<Multisig>(data[..Multisig::LEN].to_vec(), index, byte);
+            corrupt_byte::<TokenMetadata>(data[..TokenMetadata::LEN].to_vec(), index, byte);
+            corrupt_byte::<Escrow>(data[..Escrow::LEN].to_vec(), index, byte);
+            corrupt_byte::<Distributor>(data[..Distributor::LEN].to_vec(), index, byte);
+            corrupt_byte::<ClaimStatus>(data[..ClaimStatus::LEN].to_vec(), index, byte);
+            corrupt_byte::<Converter>(data[..Converter::LEN].to_vec(), index, byte);
+            corrupt_byte::<BalanceSnapshot>(data[..BalanceSnapshot::LEN].to_vec(), index, byte);
+        }
+
+        #[test]
+        fn wrong_lengths_are_rejected(len in 0..2 * Multisig::LEN) {
+            wrong_length::<Token>(len);
+            wrong_length::<Mint>(len);
+            wrong_length::<Multisig>(len);
+            wrong_length::<TokenMetadata>(len);
+            wrong_length::<Escrow>(len);
+            wrong_length::<Distributor>(len);
+            wrong_length::<ClaimStatus>(len);
+            wrong_length::<Converter>(len);
+            wrong_length::<BalanceSnapshot>(len);
+        }
+
+        #[test]
+        fn amount_is_read_from_the_packed_layout(token in token(), tail in vec(any::<u8>(), 0..32)) {
+            let mut buf = vec![0; Token::LEN];
+            token.pack_into_slice(&mut buf);
+            buf.extend_from_slice(&tail);
+            prop_assert_eq!(Token::amount_from_slice(&buf), Ok(token.amount));
+        }
+
+        #[test]
+        fn write_amount_touches_only_the_amount(
+            token in token(),
+            data in vec(any::<u8>(), Token::LEN..Token::LEN + 32),
+            amount in any::<u64>(),
+        ) {
+            let mut written = data.clone();
+            Token::write_amount(&mut written, amount).unwrap();
+            prop_assert_eq!(Token::amount_from_slice(&written), Ok(amount));
+            prop_assert_eq!(&written[..2], &data[..2]);
+            prop_assert_eq!(&written[10..], &data[10..]);
+
+            let mut buf = vec![0; Token::LEN];
+            token.pack_into_slice(&mut buf);
+            Token::write_amount(&mut buf, amount).unwrap();
+            prop_assert_eq!(Token::unpack_from_slice(&buf).unwrap(), Token { amount, ..token });
+        }
+
+        #[test]
+        fn amount_helpers_reject_short_buffers(len in 0..Token::LEN, amount in any::<u64>()) {
+            let mut buf = vec![0; len];
+            prop_assert_eq!(
+                Token::amount_from_slice(&buf),
+                Err(ProgramError::InvalidAccountData)
+            );
+            prop_assert_eq!(
+                Token::write_amount(&mut buf, amount),
+                Err(ProgramError::InvalidAccountData)
+            );
+            prop_assert!(buf.iter().all(|&byte| byte == 0));
+        }
+
+        #[test]
+        fn invalid_token_tags_are_rejected(token in token(), byte in 2..=u8::MAX) {
+            let mut buf = vec![0; Token::LEN];
+            token.pack_into_slice(&mut buf);
+            // The account type and version; delegate, is_native, withheld_amount
+            // and pending_owner option tags; state and the three flags.
+            for index in [0, 1, 74, 119, 131, 145, 118, 143, 144, 181] {
+                let mut corrupted = buf.clone();
+                corrupted[index] = if matches!(index, 0 | 1 | 118) { byte.max(4) } else { byte };
+                prop_assert!(Token::unpack_from_slice(&corrupted).is_err());
+            }
+        }
+
+        #[test]
+        fn invalid_mint_tags_are_rejected(mint in mint(), byte in 2..=u8::MAX) {
+            let mut buf = vec![0; Mint::LEN];
+            mint.pack_into_slice(&mut buf);
+            // The account type and version, option tags, then the is_initialized,
+            // non_transferable and paused flags.
+            for index in [0, 1, 2, 48, 84, 96, 132, 155, 192, 47, 154, 191] {
+                let mut corrupted = buf.clone();
+                corrupted[index] = if index < 2 { byte.max(5) } else { byte };
+                prop_assert!(Mint::unpack_from_slice(&corrupted).is_err());
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Owned account buffers for driving the processor without a runtime.
+//!
+//! Enabled by the `test-utils` feature; used by the integration tests and the
+//! fuzz targets to hand synthetic accounts straight to
+//! [`Processor::process`](crate::processor::Processor::process).
+//!
+//! ```ignore
+//! let mut source = AccountFixture::new(key)
+//!     .owner(program_id)
+//!     .lamports(1)
+//!     .state(&token);
+//! let mut owner = AccountFixture::new(token.owner).signer();
+//! Processor::process(&program_id, &[source.account_info(), owner.account_info()], &data)?;
+//! let token: Token = source.unpack()?;
+//! ```
+
+use crate::extension::{self, Extension, MintExtension};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Epoch,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// An account whose key, owner, lamports, data and flags are all owned, so
+/// that an [`AccountInfo`] can be borrowed over it.
+///
+/// New fixtures are writable, hold no lamports or data, and are owned by the
+/// system program; the builder methods change one field at a time.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccountFixture {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+}
+
+impl AccountFixture {
+    pub fn new(key: Pubkey) -> Self {
+        AccountFixture {
+            key,
+            is_writable: true,
+            ..AccountFixture::default()
+        }
+    }
+
+    /// A read-only fixture of a sysvar, with `sysvar` serialized into its
+    /// data as the runtime would.
+    pub fn sysvar<S: Sysvar>(sysvar: &S) -> Self {
+        let mut fixture = AccountFixture::new(S::id())
+            .owner(solana_program::sysvar::id())
+            .readonly()
+            .data_len(S::size_of());
+        sysvar
+            .to_account_info(&mut fixture.account_info())
+            .expect("sysvar fits its own size");
+        fixture
+    }
+
+    pub fn owner(mut self, owner: Pubkey) -> Self {
+        self.owner = owner;
+        self
+    }
+
+    pub fn lamports(mut self, lamports: u64) -> Self {
+        self.lamports = lamports;
+        self
+    }
+
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    pub fn readonly(mut self) -> Self {
+        self.is_writable = false;
+        self
+    }
+
+    pub fn executable(mut self) -> Self {
+        self.executable = true;
+        self
+    }
+
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Zero-filled data of `len` bytes, as left by `create_account`.
+    pub fn data_len(self, len: usize) -> Self {
+        self.data(vec![0; len])
+    }
+
+    /// Data holding `state` packed at its full length, e.g. a [`Token`] or
+    /// [`Mint`](crate::state::Mint).
+    ///
+    /// [`Token`]: crate::state::Token
+    pub fn state<T: Pack>(self, state: &T) -> Self {
+        let mut data = vec![0; T::LEN];
+        state.pack_into_slice(&mut data);
+        self.data(data)
+    }
+
+    /// Data grown by an entry holding `extension`, as the processor writes
+    /// it into a token account.
+    pub fn extension<T: Extension>(mut self, extension: &T) -> Self {
+        let len = extension::len_with::<T>(&self.data).expect("valid token account data");
+        self.data.resize(len, 0);
+        extension::init_extension(&mut self.data, extension).expect("room for the entry");
+        self
+    }
+
+    /// [`AccountFixture::extension`] for mints.
+    pub fn mint_extension<T: MintExtension>(mut self, extension: &T) -> Self {
+        let len = extension::mint_len_with::<T>(&self.data).expect("valid mint data");
+        self.data.resize(len, 0);
+        extension::init_mint_extension(&mut self.data, extension).expect("room for the entry");
+        self
+    }
+
+    /// Unpacks the current data as an initialized `T`.
+    pub fn unpack<T: Pack + IsInitialized>(&self) -> Result<T, ProgramError> {
+        T::unpack(&self.data)
+    }
+
+    /// Borrows the account as an [`AccountInfo`]; lamport and data changes
+    /// made by the processor land back in `self`.
+    pub fn account_info(&mut self) -> AccountInfo<'_> {
+        let AccountFixture {
+            key,
+            owner,
+            lamports,
+            data,
+            is_signer,
+            is_writable,
+            executable,
+        } = self;
+        AccountInfo::new(
+            key,
+            *is_signer,
+            *is_writable,
+            lamports,
+            data,
+            owner,
+            *executable,
+            Epoch::default(),
+        )
+    }
+}
+
+/// Borrows every fixture in `accounts`, in order.
+///
+/// To pass the same account twice, clone its [`AccountInfo`]: clones share
+/// their lamports and data, as duplicate accounts do in the runtime.
+pub fn account_infos(accounts: &mut [AccountFixture]) -> Vec<AccountInfo<'_>> {
+    accounts
+        .iter_mut()
+        .map(AccountFixture::account_info)
+        .collect()
+}
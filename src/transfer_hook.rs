@@ -0,0 +1,60 @@
+//! Interface between this program and the transfer hook a mint can name
+
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Creates the instruction a mint's transfer hook receives on every checked
+/// transfer of the mint, after the balances have been updated. An error from
+/// the hook fails the transfer. The data is the transferred `amount` as 8
+/// little-endian bytes.
+///
+///   0. `[]` The source account.
+///   1. `[]` The destination account.
+///   2. `[]` The source account owner or delegate.
+///   3. ..3+N `[]` N extra accounts, passed through from the transfer.
+pub fn execute(
+    hook_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    extra_account_metas: &[AccountMeta],
+    amount: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*source_pubkey, false),
+        AccountMeta::new_readonly(*destination_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+    ];
+    accounts.extend_from_slice(extra_account_metas);
+
+    Instruction {
+        program_id: *hook_program_id,
+        accounts,
+        data: amount.to_le_bytes().to_vec(),
+    }
+}
+
+/// Decodes the `amount` of an [`execute`] instruction, for use in hook
+/// programs.
+pub fn unpack_execute(input: &[u8]) -> Result<u64, ProgramError> {
+    input
+        .try_into()
+        .map(u64::from_le_bytes)
+        .map_err(|_| ProgramError::InvalidInstructionData)
+}
+
+/// Appends the hook program and its extra accounts to a `TransferChecked`
+/// instruction, after any signer accounts.
+pub fn add_hook_accounts(
+    instruction: &mut Instruction,
+    hook_program_id: &Pubkey,
+    extra_account_metas: &[AccountMeta],
+) {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*hook_program_id, false));
+    instruction.accounts.extend_from_slice(extra_account_metas);
+}
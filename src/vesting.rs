@@ -0,0 +1,28 @@
+//! Vesting schedule address derivation
+//!
+//! A mint authority sets tokens aside for a beneficiary token account with
+//! `CreateVesting`; `ClaimVested` then mints whatever has vested since the
+//! last claim. The schedule's address is derived from the mint and the
+//! beneficiary, so each beneficiary has at most one schedule per mint.
+
+use solana_program::pubkey::Pubkey;
+
+/// Seed prefix of vesting schedule addresses.
+pub const VESTING_SEED: &[u8] = b"vesting";
+
+/// Derives the address of the vesting schedule of `beneficiary`, a token
+/// account of `mint`.
+pub fn get_vesting_address(mint: &Pubkey, beneficiary: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_vesting_address_and_bump_seed(mint, beneficiary, program_id).0
+}
+
+pub(crate) fn get_vesting_address_and_bump_seed(
+    mint: &Pubkey,
+    beneficiary: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[VESTING_SEED, &mint.to_bytes(), &beneficiary.to_bytes()],
+        program_id,
+    )
+}
@@ -0,0 +1,181 @@
+//! Checks that the account loaders reject short, misordered and foreign
+//! account lists with an error instead of reading the wrong state.
+
+use solana_contracts::{
+    accounts::{load_mint, load_signer, load_token_account, MintToAccounts, TransferAccounts},
+    error::TokenError,
+    instruction, process_instruction,
+    test_utils::{account_infos, AccountFixture},
+    AccountState, Mint, Token,
+};
+use solana_program::{
+    program_error::ProgramError, program_option::COption, pubkey::Pubkey, system_program,
+};
+
+fn program_id() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+const MINT: Pubkey = Pubkey::new_from_array([1; 32]);
+const AUTHORITY: Pubkey = Pubkey::new_from_array([2; 32]);
+const SOURCE: Pubkey = Pubkey::new_from_array([3; 32]);
+const DESTINATION: Pubkey = Pubkey::new_from_array([4; 32]);
+
+/// A mint, two of its accounts owned by `AUTHORITY`, and the authority as
+/// a signer, in the order of `TransferChecked`.
+fn fixtures() -> Vec<AccountFixture> {
+    let mint = Mint {
+        mint_authority: COption::Some(AUTHORITY),
+        supply: 10,
+        decimals: 2,
+        is_initialized: true,
+        ..Mint::default()
+    };
+    let token = |amount| Token {
+        mint: MINT,
+        owner: AUTHORITY,
+        amount,
+        state: AccountState::Initialized,
+        ..Token::default()
+    };
+    vec![
+        AccountFixture::new(SOURCE)
+            .owner(program_id())
+            .state(&token(10)),
+        AccountFixture::new(MINT).owner(program_id()).state(&mint),
+        AccountFixture::new(DESTINATION)
+            .owner(program_id())
+            .state(&token(0)),
+        AccountFixture::new(AUTHORITY).signer(),
+    ]
+}
+
+#[test]
+fn loaders_return_the_unpacked_state() {
+    let mut accounts = fixtures();
+    let infos = account_infos(&mut accounts);
+    let (token, info) = load_token_account(&infos[0], &program_id()).unwrap();
+    assert_eq!((token.amount, *info.key), (10, SOURCE));
+    let (mint, info) = load_mint(&infos[1], &program_id()).unwrap();
+    assert_eq!((mint.supply, *info.key), (10, MINT));
+    assert_eq!(load_signer(&infos[3]).unwrap().key, &AUTHORITY);
+
+    let loaded = TransferAccounts::load(&infos, &program_id(), true).unwrap();
+    assert_eq!(loaded.source.key, &SOURCE);
+    assert_eq!(loaded.mint.unwrap().key, &MINT);
+    assert_eq!(loaded.destination.key, &DESTINATION);
+    assert_eq!(loaded.authority.key, &AUTHORITY);
+    assert!(loaded.remaining.is_empty());
+}
+
+#[test]
+fn loaders_reject_the_wrong_kind_of_account() {
+    let mut accounts = fixtures();
+    let infos = account_infos(&mut accounts);
+    assert_eq!(
+        load_mint(&infos[0], &program_id()).unwrap_err(),
+        TokenError::InvalidAccountType.into()
+    );
+    assert_eq!(
+        load_token_account(&infos[1], &program_id()).unwrap_err(),
+        TokenError::InvalidAccountType.into()
+    );
+    assert_eq!(
+        load_token_account(&infos[3], &program_id()).unwrap_err(),
+        ProgramError::IncorrectProgramId
+    );
+    assert_eq!(
+        load_signer(&infos[0]).unwrap_err(),
+        ProgramError::MissingRequiredSignature
+    );
+}
+
+#[test]
+fn short_account_lists_fail_with_not_enough_account_keys() {
+    let mut accounts = fixtures();
+    let infos = account_infos(&mut accounts);
+    for len in 0..4 {
+        assert_eq!(
+            TransferAccounts::load(&infos[..len], &program_id(), true).err(),
+            Some(ProgramError::NotEnoughAccountKeys),
+            "{len} accounts"
+        );
+    }
+    let unchecked = [infos[0].clone(), infos[2].clone(), infos[3].clone()];
+    for len in 0..3 {
+        assert_eq!(
+            TransferAccounts::load(&unchecked[..len], &program_id(), false).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(
+            MintToAccounts::load(&unchecked[..len], &program_id()).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+}
+
+#[test]
+fn misordered_accounts_are_rejected_before_their_state_is_used() {
+    let mut accounts = fixtures();
+    let infos = account_infos(&mut accounts);
+    // The authority where the source belongs isn't a program account.
+    let authority_first = [
+        infos[3].clone(),
+        infos[1].clone(),
+        infos[2].clone(),
+        infos[0].clone(),
+    ];
+    assert_eq!(
+        TransferAccounts::load(&authority_first, &program_id(), true).err(),
+        Some(ProgramError::IncorrectProgramId)
+    );
+    drop(infos);
+
+    // Swapping the mint and destination of a mint passes the structural
+    // checks but not their account types.
+    let mut ix =
+        instruction::mint_tokens(&program_id(), &MINT, &DESTINATION, &AUTHORITY, &[], 5).unwrap();
+    ix.accounts.swap(0, 1);
+    let infos = account_infos(&mut accounts);
+    let ordered: Vec<_> = ix
+        .accounts
+        .iter()
+        .map(|meta| {
+            infos
+                .iter()
+                .find(|info| *info.key == meta.pubkey)
+                .unwrap()
+                .clone()
+        })
+        .collect();
+    assert_eq!(
+        process_instruction(&program_id(), &ordered, &ix.data),
+        Err(TokenError::InvalidAccountType.into())
+    );
+}
+
+#[test]
+fn accounts_of_other_programs_or_read_only_are_rejected() {
+    for (index, owner) in [(0, system_program::id()), (2, Pubkey::new_unique())] {
+        let mut accounts = fixtures();
+        accounts[index].owner = owner;
+        let infos = account_infos(&mut accounts);
+        assert_eq!(
+            TransferAccounts::load(&infos, &program_id(), true).err(),
+            Some(ProgramError::IncorrectProgramId)
+        );
+    }
+
+    let mut accounts = fixtures();
+    accounts[2].is_writable = false;
+    let infos = account_infos(&mut accounts);
+    assert_eq!(
+        TransferAccounts::load(&infos, &program_id(), true).err(),
+        Some(TokenError::AccountNotWritable.into())
+    );
+    let mint_to = [infos[1].clone(), infos[2].clone(), infos[3].clone()];
+    assert_eq!(
+        MintToAccounts::load(&mint_to, &program_id()).err(),
+        Some(TokenError::AccountNotWritable.into())
+    );
+}
@@ -0,0 +1,386 @@
+#![cfg(feature = "anchor")]
+
+use solana_contracts::{
+    anchor::{self, DISCRIMINATORS},
+    instruction::{AuthorityType, TokenInstruction},
+    AccountState,
+};
+use solana_program::{
+    hash::hashv, program_error::ProgramError, program_option::COption, pubkey::Pubkey,
+};
+
+/// One instruction of each kind, in order of tag.
+fn instructions() -> Vec<TokenInstruction> {
+    let key = Pubkey::new_from_array([7; 32]);
+    vec![
+        TokenInstruction::MintTokens { amount: 1 },
+        TokenInstruction::TransferTokens { amount: 2 },
+        TokenInstruction::Burn { amount: 3 },
+        TokenInstruction::InitializeMint {
+            decimals: 6,
+            mint_authority: key,
+            freeze_authority: COption::Some(key),
+            max_supply: COption::Some(1_000),
+        },
+        TokenInstruction::Approve { amount: 4 },
+        TokenInstruction::Revoke,
+        TokenInstruction::InitializeAccount,
+        TokenInstruction::CloseAccount,
+        TokenInstruction::FreezeAccount,
+        TokenInstruction::ThawAccount,
+        TokenInstruction::InitializeAccount2,
+        TokenInstruction::SetAuthority {
+            authority_type: AuthorityType::CloseMint,
+            new_authority: COption::None,
+        },
+        TokenInstruction::TransferChecked {
+            amount: 5,
+            decimals: 6,
+        },
+        TokenInstruction::MintToChecked {
+            amount: 6,
+            decimals: 6,
+        },
+        TokenInstruction::BurnChecked {
+            amount: 7,
+            decimals: 6,
+        },
+        TokenInstruction::CreateAssociatedAccount,
+        TokenInstruction::SyncNative,
+        TokenInstruction::InitializeMultisig { m: 2 },
+        TokenInstruction::TransferBatch {
+            amounts: vec![1, 2, 3],
+        },
+        TokenInstruction::CreateMetadata {
+            name: "Token".to_string(),
+            symbol: "TKN".to_string(),
+            uri: "https://example.com".to_string(),
+        },
+        TokenInstruction::UpdateMetadata {
+            name: "Token 2".to_string(),
+            symbol: "TKN2".to_string(),
+            uri: String::new(),
+        },
+        TokenInstruction::InitializeTransferFeeConfig {
+            transfer_fee_authority: COption::Some(key),
+            transfer_fee_basis_points: 50,
+            maximum_fee: 10,
+        },
+        TokenInstruction::HarvestWithheldTokens,
+        TokenInstruction::WithdrawWithheldTokens,
+        TokenInstruction::InitializeNonTransferableMint,
+        TokenInstruction::LockAccount { lock_until: -1 },
+        TokenInstruction::InitializeEscrow {
+            amount: 8,
+            expected_amount: 9,
+        },
+        TokenInstruction::Exchange {
+            amount: 9,
+            expected_amount: 8,
+        },
+        TokenInstruction::CancelEscrow,
+        TokenInstruction::InitializePauseConfig {
+            pause_authority: key,
+        },
+        TokenInstruction::Pause,
+        TokenInstruction::Resume,
+        TokenInstruction::InitializePermanentDelegate { delegate: key },
+        TokenInstruction::ProposeOwner { new_owner: key },
+        TokenInstruction::AcceptOwner,
+        TokenInstruction::CancelPendingOwner,
+        TokenInstruction::GetAccountBalance,
+        TokenInstruction::GetMintSupply,
+        TokenInstruction::AmountToUiAmount { amount: 10 },
+        TokenInstruction::UiAmountToAmount {
+            ui_amount: "1.5".to_string(),
+        },
+        TokenInstruction::RequireMemo,
+        TokenInstruction::UnrequireMemo,
+        TokenInstruction::InitializeTransferHook { program_id: key },
+        TokenInstruction::InitializeDefaultAccountState {
+            state: AccountState::Frozen,
+        },
+        TokenInstruction::SetDefaultAccountState {
+            state: AccountState::Initialized,
+        },
+        TokenInstruction::InitializeImmutableOwner,
+        TokenInstruction::InitializeInterestBearingMint {
+            rate_authority: COption::Some(key),
+            rate_bps: -5,
+        },
+        TokenInstruction::UpdateRate { rate_bps: 5 },
+        TokenInstruction::InitializeMintCloseAuthority {
+            close_authority: COption::None,
+        },
+        TokenInstruction::CloseMint,
+        TokenInstruction::MigrateAccount,
+        TokenInstruction::InitializeRoyalty {
+            basis_points: 100,
+            destination: key,
+        },
+        TokenInstruction::InitializeAllowlist { authority: key },
+        TokenInstruction::AddToAllowlist { owner: key },
+        TokenInstruction::RemoveFromAllowlist { owner: key },
+        TokenInstruction::InitializeMinTransferAmount {
+            min_transfer_amount: 11,
+        },
+        TokenInstruction::UpdateMinTransferAmount {
+            min_transfer_amount: 12,
+        },
+        TokenInstruction::TransferAccountOwnership,
+        TokenInstruction::CreateDistribution {
+            merkle_root: [8; 32],
+            total_claimable: 13,
+        },
+        TokenInstruction::Claim {
+            index: 14,
+            amount: 15,
+            proof: vec![[9; 32], [10; 32]],
+        },
+        TokenInstruction::InitializeMint2 {
+            decimals: 9,
+            mint_authority: key,
+            freeze_authority: COption::None,
+            max_supply: COption::Some(16),
+        },
+        TokenInstruction::InitializeAccount3 { owner: key },
+        TokenInstruction::InitializeMultisig2 { m: 2 },
+        TokenInstruction::FreezeBatch { count: 5 },
+        TokenInstruction::InitializeMintWithAllocations {
+            decimals: 6,
+            freeze_authority: COption::Some(key),
+            max_supply: COption::None,
+            amounts: vec![1, 2, 3],
+        },
+        TokenInstruction::InitializeActivityTracking,
+        TokenInstruction::TransferWithFee { amount: 9, fee: 1 },
+        TokenInstruction::CreateTokenAccount {
+            owner: key,
+            seed: COption::Some(17),
+        },
+        TokenInstruction::InitializeTransferCooldown { cooldown_slots: 18 },
+        TokenInstruction::InitializeHolderCount,
+        TokenInstruction::PermitTransfer,
+        TokenInstruction::CreateVesting {
+            total_amount: 19,
+            start_ts: -20,
+            end_ts: 21,
+        },
+        TokenInstruction::ClaimVested,
+        TokenInstruction::RepairOwner { owner: key },
+        TokenInstruction::RecoverNested,
+        TokenInstruction::TwoPartySwap {
+            amount_a: 20,
+            amount_b: 21,
+        },
+        TokenInstruction::CreateAssociatedAccountIdempotent,
+        TokenInstruction::InitializeRecoveryAuthority {
+            recovery_authority: key,
+        },
+        TokenInstruction::InitializeMetadataPointer {
+            authority: COption::Some(key),
+            metadata_address: COption::None,
+        },
+        TokenInstruction::UpdateMetadataPointer {
+            metadata_address: COption::Some(key),
+        },
+        TokenInstruction::CreateConverter {
+            numerator: 1_000,
+            denominator: 1,
+        },
+        TokenInstruction::Convert { amount: 7 },
+        TokenInstruction::SetMinimumBalance {
+            minimum_balance: 500,
+            enforcement_authority: key,
+        },
+        TokenInstruction::SnapshotBalance { snapshot_id: 9 },
+        TokenInstruction::CloseSnapshot,
+        TokenInstruction::TransferWithRelayerFee {
+            amount: 5,
+            relayer_fee: 1,
+        },
+        TokenInstruction::VerifyOwnership { nonce: 3 },
+        TokenInstruction::SetOutflowLimit {
+            max_outflow_per_instruction: 100,
+            timelock_slots: 10,
+        },
+        TokenInstruction::AddMinter {
+            minter: key,
+            max_per_window: 1_000,
+            window_slots: 100,
+        },
+        TokenInstruction::RemoveMinter { minter: key },
+    ]
+}
+
+/// The snake-case name of `instruction`'s variant.
+fn snake_case_name(instruction: &TokenInstruction) -> String {
+    let debug = format!("{instruction:?}");
+    let variant = debug.split([' ', '(', '{']).next().unwrap();
+    let mut name = String::new();
+    for (i, c) in variant.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+#[test]
+fn discriminators_hash_each_instruction_name() {
+    let instructions = instructions();
+    assert_eq!(instructions.len(), DISCRIMINATORS.len());
+    for (tag, instruction) in instructions.iter().enumerate() {
+        assert_eq!(instruction.pack()[0] as usize, tag);
+        let (name, discriminator) = DISCRIMINATORS[tag];
+        assert_eq!(name, snake_case_name(instruction));
+        let hash = hashv(&[b"global:", name.as_bytes()]);
+        assert_eq!(discriminator, hash.to_bytes()[..8]);
+        assert_eq!(anchor::tag(&discriminator), Some(tag as u8));
+    }
+}
+
+#[test]
+fn every_instruction_unpacks_from_both_layouts() {
+    for instruction in instructions() {
+        let legacy = instruction.pack();
+        let anchor = anchor::pack(&instruction);
+        assert_eq!(TokenInstruction::unpack(&legacy).unwrap(), instruction);
+        assert_eq!(TokenInstruction::unpack(&anchor).unwrap(), instruction);
+        assert_eq!(anchor[..8], DISCRIMINATORS[legacy[0] as usize].1);
+        let mut long = anchor.clone();
+        long.push(0);
+        assert_eq!(
+            TokenInstruction::unpack(&long),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        if !matches!(
+            instruction,
+            TokenInstruction::TransferBatch { .. }
+                | TokenInstruction::Claim { .. }
+                | TokenInstruction::InitializeMintWithAllocations { .. }
+        ) {
+            assert_eq!(anchor[8..], legacy[1..]);
+        }
+    }
+}
+
+#[test]
+fn anchor_transfer_batch_counts_amounts_with_a_u32() {
+    let mut data = DISCRIMINATORS[18].1.to_vec();
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&5u64.to_le_bytes());
+    data.extend_from_slice(&6u64.to_le_bytes());
+    assert_eq!(
+        anchor::pack(&TokenInstruction::TransferBatch {
+            amounts: vec![5, 6]
+        }),
+        data
+    );
+    assert_eq!(
+        TokenInstruction::unpack(&data).unwrap(),
+        TokenInstruction::TransferBatch {
+            amounts: vec![5, 6]
+        }
+    );
+
+    for count in [0u32, 17, u32::MAX] {
+        let mut data = DISCRIMINATORS[18].1.to_vec();
+        data.extend_from_slice(&count.to_le_bytes());
+        data.extend_from_slice(&[0; 17 * 8]);
+        assert_eq!(
+            TokenInstruction::unpack(&data),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+}
+
+#[test]
+fn anchor_claim_counts_proof_nodes_with_a_u32() {
+    let claim = TokenInstruction::Claim {
+        index: 1,
+        amount: 2,
+        proof: vec![[3; 32]],
+    };
+    let mut data = DISCRIMINATORS[59].1.to_vec();
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&2u64.to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&[3; 32]);
+    assert_eq!(anchor::pack(&claim), data);
+    assert_eq!(TokenInstruction::unpack(&data).unwrap(), claim);
+
+    data[24..28].copy_from_slice(&33u32.to_le_bytes());
+    data.resize(28 + 33 * 32, 0);
+    assert_eq!(
+        TokenInstruction::unpack(&data),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}
+
+#[test]
+fn discriminators_take_precedence_over_legacy_tags() {
+    // `cancel_pending_owner` starts with 0, the `MintTokens` tag: a legacy
+    // `MintTokens` whose amount continues it is read as the Anchor form,
+    // which takes no arguments, so its last byte is left over.
+    let (_, cancel_pending_owner) = DISCRIMINATORS[35];
+    assert_eq!(cancel_pending_owner[0], 0);
+    let mut amount = [0xff; 8];
+    amount[..7].copy_from_slice(&cancel_pending_owner[1..]);
+    let mut data = vec![0];
+    data.extend_from_slice(&amount);
+    assert_eq!(
+        TokenInstruction::unpack(&data),
+        Err(ProgramError::InvalidInstructionData)
+    );
+    assert_eq!(
+        TokenInstruction::unpack(&cancel_pending_owner).unwrap(),
+        TokenInstruction::CancelPendingOwner
+    );
+
+    // Likewise `initialize_account2` starts with the `FreezeAccount` tag,
+    // which takes no arguments either.
+    let (_, initialize_account2) = DISCRIMINATORS[10];
+    assert_eq!(initialize_account2[0], 8);
+    assert_eq!(
+        TokenInstruction::unpack(&initialize_account2).unwrap(),
+        TokenInstruction::InitializeAccount2
+    );
+    assert_eq!(
+        TokenInstruction::unpack(&initialize_account2[..7]),
+        Err(ProgramError::InvalidInstructionData)
+    );
+
+    // Any other amount is a legacy `MintTokens`.
+    amount[0] ^= 1;
+    data[1..].copy_from_slice(&amount);
+    assert_eq!(
+        TokenInstruction::unpack(&data).unwrap(),
+        TokenInstruction::MintTokens {
+            amount: u64::from_le_bytes(amount)
+        }
+    );
+}
+
+#[test]
+fn truncated_anchor_data_does_not_fall_back_to_the_legacy_layout() {
+    // A discriminator with missing arguments is an error, not a legacy
+    // instruction with trailing bytes.
+    let (_, mint_tokens) = DISCRIMINATORS[0];
+    assert_eq!(
+        TokenInstruction::unpack(&mint_tokens),
+        Err(ProgramError::InvalidInstructionData)
+    );
+    let (_, initialize_mint) = DISCRIMINATORS[3];
+    let mut data = initialize_mint.to_vec();
+    data.push(6);
+    assert_eq!(
+        TokenInstruction::unpack(&data),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}
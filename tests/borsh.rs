@@ -0,0 +1,96 @@
+#![cfg(feature = "borsh")]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_contracts::{
+    instruction::{AuthorityType, TokenInstruction},
+    state::AccountState,
+    Mint, Token,
+};
+use solana_program::{program_option::COption, program_pack::Pack, pubkey::Pubkey};
+
+fn instructions() -> Vec<TokenInstruction> {
+    vec![
+        TokenInstruction::MintTokens { amount: 7 },
+        TokenInstruction::InitializeMint {
+            decimals: 6,
+            mint_authority: Pubkey::new_unique(),
+            freeze_authority: COption::Some(Pubkey::new_unique()),
+            max_supply: COption::Some(1_000),
+        },
+        TokenInstruction::SetAuthority {
+            authority_type: AuthorityType::AccountOwner,
+            new_authority: COption::None,
+        },
+        TokenInstruction::TransferChecked {
+            amount: u64::MAX,
+            decimals: 9,
+        },
+        TokenInstruction::TransferBatch {
+            amounts: vec![1, 2, 3],
+        },
+        TokenInstruction::CreateMetadata {
+            name: "Token".to_string(),
+            symbol: "TKN".to_string(),
+            uri: "https://example.com".to_string(),
+        },
+        TokenInstruction::LockAccount { lock_until: -1 },
+        TokenInstruction::UiAmountToAmount {
+            ui_amount: "1.5".to_string(),
+        },
+    ]
+}
+
+#[test]
+fn instruction_borsh_encoding_is_the_packed_encoding() {
+    for instruction in instructions() {
+        let encoded = borsh::to_vec(&instruction).unwrap();
+        assert_eq!(encoded, instruction.pack());
+        assert_eq!(TokenInstruction::unpack(&encoded).unwrap(), instruction);
+        assert_eq!(
+            TokenInstruction::try_from_slice(&encoded).unwrap(),
+            instruction
+        );
+    }
+}
+
+#[test]
+fn instruction_borsh_rejects_invalid_data() {
+    assert!(TokenInstruction::try_from_slice(&[]).is_err());
+    assert!(TokenInstruction::try_from_slice(&[u8::MAX]).is_err());
+}
+
+#[test]
+fn account_borsh_encoding_is_the_packed_layout() {
+    let token = Token {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount: 42,
+        delegate: COption::Some(Pubkey::new_unique()),
+        delegated_amount: 2,
+        state: AccountState::Frozen,
+        withheld_amount: COption::Some(100),
+        ..Token::default()
+    };
+    let mut packed = vec![0; Token::LEN];
+    Token::pack(token.clone(), &mut packed).unwrap();
+    let mut encoded = Vec::new();
+    token.serialize(&mut encoded).unwrap();
+    assert_eq!(encoded, packed);
+    assert_eq!(Token::try_from_slice(&encoded).unwrap(), token);
+
+    let mint = Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply: 1_000,
+        decimals: 6,
+        is_initialized: true,
+        max_supply: COption::Some(5_000),
+        paused: true,
+        ..Mint::default()
+    };
+    let mut packed = vec![0; Mint::LEN];
+    Mint::pack(mint.clone(), &mut packed).unwrap();
+    assert_eq!(borsh::to_vec(&mint).unwrap(), packed);
+    assert_eq!(Mint::try_from_slice(&packed).unwrap(), mint);
+
+    assert!(Mint::try_from_slice(&packed[..Mint::LEN - 1]).is_err());
+}
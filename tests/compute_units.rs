@@ -0,0 +1,371 @@
+//! Compute units used by each instruction of the SBF build of the program.
+//!
+//! Builtin processors aren't metered, so these tests need the shared object
+//! and are ignored by plain `cargo test`:
+//! `cargo test-sbf --test compute_units -- --ignored --nocapture` builds it
+//! and prints the units each instruction used. A test that is run without
+//! the shared object it measures fails rather than passing unmeasured.
+//!
+//! Each budget is a ceiling a regression would cross, not a measurement; a
+//! change that crosses one fails with the budget and the units it now uses,
+//! and raises the budget in the same change if the cost is intended.
+//! The transfer path is the one batched transactions hit hardest; it
+//! compares pubkeys with `sol_memcmp`, borrows each account's data once
+//! while validating, and writes only the two amounts, so its budget is held
+//! below that of `InitializeAccount`.
+//!
+//! Diagnostics are only logged by builds with the `verbose-logs` feature.
+//! To compare the two, also build that variant under another name:
+//! `cargo build-sbf --features verbose-logs`, then copy
+//! `target/deploy/solana_contracts.so` to `solana_contracts_verbose.so` in
+//! the same directory before the plain build overwrites it. Builds with the
+//! `bytemuck` feature, which move a plain transfer's amounts in place, are
+//! compared the same way, as `solana_contracts_zero_copy.so`.
+
+use solana_contracts::{
+    instruction::{self, AuthorityType},
+    Mint, Token,
+};
+use solana_program::{instruction::Instruction, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::{find_file, BanksClient, ProgramTest};
+use solana_sdk::{
+    hash::Hash,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+const INITIALIZE_MINT_BUDGET: u64 = 6_000;
+const INITIALIZE_ACCOUNT_BUDGET: u64 = 8_000;
+const MINT_TOKENS_BUDGET: u64 = 7_000;
+const TRANSFER_BUDGET: u64 = 6_500;
+const TRANSFER_CHECKED_BUDGET: u64 = 9_000;
+const MINT_TO_CHECKED_BUDGET: u64 = 8_000;
+const BURN_BUDGET: u64 = 7_000;
+const BURN_CHECKED_BUDGET: u64 = 8_000;
+const APPROVE_BUDGET: u64 = 5_000;
+const REVOKE_BUDGET: u64 = 4_500;
+const FREEZE_ACCOUNT_BUDGET: u64 = 6_000;
+const THAW_ACCOUNT_BUDGET: u64 = 6_000;
+const CLOSE_ACCOUNT_BUDGET: u64 = 5_500;
+const SET_AUTHORITY_BUDGET: u64 = 5_500;
+/// The least a verbose transfer should cost over a quiet one: it logs two
+/// diagnostics, and every `msg!` costs at least 100 CU.
+const VERBOSE_TRANSFER_OVERHEAD: u64 = 200;
+/// The least a zero-copy transfer should save over an unpacked one: it skips
+/// unpacking and validating two full token accounts.
+const ZERO_COPY_TRANSFER_SAVINGS: u64 = 500;
+
+struct Meter {
+    banks_client: BanksClient,
+    payer: Keypair,
+    recent_blockhash: Hash,
+    program_id: Pubkey,
+}
+
+impl Meter {
+    /// Starts a bank running the SBF build.
+    async fn start() -> Self {
+        Self::start_program("solana_contracts").await
+    }
+
+    /// Starts a bank running the shared object `name`.so, panicking if there
+    /// is none.
+    async fn start_program(name: &str) -> Self {
+        assert!(
+            find_file(&format!("{name}.so")).is_some(),
+            "{name}.so not found; build it with `cargo build-sbf` or run `cargo test-sbf`"
+        );
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::default();
+        program_test.prefer_bpf(true);
+        program_test.add_program(name, program_id, None);
+        let (banks_client, payer, recent_blockhash) = program_test.start().await;
+        Meter {
+            banks_client,
+            payer,
+            recent_blockhash,
+            program_id,
+        }
+    }
+
+    /// Processes `instruction` alone and returns the units it consumed.
+    async fn measure(&mut self, instruction: Instruction, signers: &[&Keypair]) -> u64 {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.recent_blockhash,
+        );
+        let result = self
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        result.result.unwrap();
+        result.metadata.unwrap().compute_units_consumed
+    }
+
+    async fn create_account(&mut self, account: &Keypair, len: usize) {
+        let rent = self.banks_client.get_rent().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::create_account(
+                &self.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(len),
+                len as u64,
+                &self.program_id,
+            )],
+            Some(&self.payer.pubkey()),
+            &[&self.payer, account],
+            self.recent_blockhash,
+        );
+        self.banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+}
+
+/// Sets up a mint and two funded accounts, then returns the units a
+/// transfer between them used.
+async fn measure_transfer(meter: &mut Meter) -> u64 {
+    let program_id = meter.program_id;
+    let authority = Keypair::new();
+    let mint = Keypair::new();
+    let (source, destination) = (Keypair::new(), Keypair::new());
+
+    meter.create_account(&mint, Mint::LEN).await;
+    meter
+        .measure(
+            instruction::initialize_mint(
+                &program_id,
+                &mint.pubkey(),
+                &authority.pubkey(),
+                None,
+                6,
+                None,
+            )
+            .unwrap(),
+            &[],
+        )
+        .await;
+    for account in [&source, &destination] {
+        meter.create_account(account, Token::LEN).await;
+        meter
+            .measure(
+                instruction::initialize_account(
+                    &program_id,
+                    &account.pubkey(),
+                    &mint.pubkey(),
+                    &authority.pubkey(),
+                )
+                .unwrap(),
+                &[],
+            )
+            .await;
+    }
+    meter
+        .measure(
+            instruction::mint_tokens(
+                &program_id,
+                &mint.pubkey(),
+                &source.pubkey(),
+                &authority.pubkey(),
+                &[],
+                1_000,
+            )
+            .unwrap(),
+            &[&authority],
+        )
+        .await;
+    meter
+        .measure(
+            instruction::transfer_tokens(
+                &program_id,
+                &source.pubkey(),
+                &destination.pubkey(),
+                &authority.pubkey(),
+                &[],
+                100,
+            )
+            .unwrap(),
+            &[&authority],
+        )
+        .await
+}
+
+/// Prints the units `name` used, failing with its budget and the units if
+/// they went over.
+fn check(name: &str, units: u64, budget: u64) {
+    println!("{name}: {units} CU (budget {budget})");
+    assert!(
+        units <= budget,
+        "{name} used {units} CU, {} over its budget of {budget} CU; raise the budget only if \
+         the cost is intended",
+        units - budget
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires cargo test-sbf"]
+async fn instructions_stay_within_their_compute_budgets() {
+    let mut meter = Meter::start().await;
+    let program_id = meter.program_id;
+    let authority = Keypair::new();
+    let mint = Keypair::new();
+    let (source, destination, empty) = (Keypair::new(), Keypair::new(), Keypair::new());
+
+    meter.create_account(&mint, Mint::LEN).await;
+    let units = meter
+        .measure(
+            instruction::initialize_mint(
+                &program_id,
+                &mint.pubkey(),
+                &authority.pubkey(),
+                Some(&authority.pubkey()),
+                6,
+                None,
+            )
+            .unwrap(),
+            &[],
+        )
+        .await;
+    check("InitializeMint", units, INITIALIZE_MINT_BUDGET);
+
+    for account in [&source, &destination, &empty] {
+        meter.create_account(account, Token::LEN).await;
+        let units = meter
+            .measure(
+                instruction::initialize_account(
+                    &program_id,
+                    &account.pubkey(),
+                    &mint.pubkey(),
+                    &authority.pubkey(),
+                )
+                .unwrap(),
+                &[],
+            )
+            .await;
+        check("InitializeAccount", units, INITIALIZE_ACCOUNT_BUDGET);
+    }
+
+    // Measured in order, each signed by `authority`, which holds every
+    // role: the later steps rely on the balances the earlier ones leave.
+    let (mint, source, destination) = (mint.pubkey(), source.pubkey(), destination.pubkey());
+    let key = authority.pubkey();
+    let steps = [
+        (
+            "MintTokens",
+            instruction::mint_tokens(&program_id, &mint, &source, &key, &[], 1_000),
+            MINT_TOKENS_BUDGET,
+        ),
+        (
+            "MintToChecked",
+            instruction::mint_to_checked(&program_id, &mint, &source, &key, &[], 1_000, 6),
+            MINT_TO_CHECKED_BUDGET,
+        ),
+        (
+            "TransferTokens",
+            instruction::transfer_tokens(&program_id, &source, &destination, &key, &[], 100),
+            TRANSFER_BUDGET,
+        ),
+        (
+            "TransferChecked",
+            instruction::transfer_checked(
+                &program_id,
+                &source,
+                &mint,
+                &destination,
+                &key,
+                &[],
+                100,
+                6,
+            ),
+            TRANSFER_CHECKED_BUDGET,
+        ),
+        (
+            "Burn",
+            instruction::burn(&program_id, &source, &mint, &key, &[], 100),
+            BURN_BUDGET,
+        ),
+        (
+            "BurnChecked",
+            instruction::burn_checked(&program_id, &source, &mint, &key, &[], 100, 6),
+            BURN_CHECKED_BUDGET,
+        ),
+        (
+            "Approve",
+            instruction::approve(&program_id, &source, &destination, &key, &[], 50),
+            APPROVE_BUDGET,
+        ),
+        (
+            "Revoke",
+            instruction::revoke(&program_id, &source, &key, &[]),
+            REVOKE_BUDGET,
+        ),
+        (
+            "FreezeAccount",
+            instruction::freeze_account(&program_id, &destination, &mint, &key, &[]),
+            FREEZE_ACCOUNT_BUDGET,
+        ),
+        (
+            "ThawAccount",
+            instruction::thaw_account(&program_id, &destination, &mint, &key, &[]),
+            THAW_ACCOUNT_BUDGET,
+        ),
+        (
+            "CloseAccount",
+            instruction::close_account(&program_id, &empty.pubkey(), &key, &key, &[]),
+            CLOSE_ACCOUNT_BUDGET,
+        ),
+        (
+            "SetAuthority",
+            instruction::set_authority(
+                &program_id,
+                &mint,
+                Some(&Pubkey::new_unique()),
+                AuthorityType::FreezeAccount,
+                &key,
+                &[],
+            ),
+            SET_AUTHORITY_BUDGET,
+        ),
+    ];
+    for (name, instruction, budget) in steps {
+        let units = meter.measure(instruction.unwrap(), &[&authority]).await;
+        check(name, units, budget);
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires cargo test-sbf"]
+async fn verbose_logs_cost_more_than_quiet_ones() {
+    let mut quiet = Meter::start().await;
+    let mut verbose = Meter::start_program("solana_contracts_verbose").await;
+    let quiet_units = measure_transfer(&mut quiet).await;
+    let verbose_units = measure_transfer(&mut verbose).await;
+    check("TransferTokens (quiet)", quiet_units, TRANSFER_BUDGET);
+    println!("TransferTokens (verbose): {verbose_units} CU");
+    assert!(
+        verbose_units >= quiet_units + VERBOSE_TRANSFER_OVERHEAD,
+        "verbose transfer used {verbose_units} CU, quiet {quiet_units} CU"
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires cargo test-sbf"]
+async fn zero_copy_transfers_cost_less_than_unpacked_ones() {
+    let mut unpacked = Meter::start().await;
+    let mut zero_copy = Meter::start_program("solana_contracts_zero_copy").await;
+    let unpacked_units = measure_transfer(&mut unpacked).await;
+    let zero_copy_units = measure_transfer(&mut zero_copy).await;
+    check("TransferTokens (unpacked)", unpacked_units, TRANSFER_BUDGET);
+    println!("TransferTokens (zero-copy): {zero_copy_units} CU");
+    assert!(
+        zero_copy_units + ZERO_COPY_TRANSFER_SAVINGS <= unpacked_units,
+        "zero-copy transfer used {zero_copy_units} CU, unpacked {unpacked_units} CU"
+    );
+}
@@ -0,0 +1,236 @@
+#![cfg(feature = "client")]
+
+//! Decodes account data as returned base64-encoded by `getAccountInfo`.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use proptest::{collection::vec, prelude::*};
+use solana_contracts::{
+    decode::{parse_mint, parse_token_account, DecodeError, Layout, TokenAccountFlags},
+    extension::{self, ExtensionType, MetadataPointer, MintExtension, Minter, Minters},
+    state::{AccountState, TransferFee},
+    Mint,
+};
+use solana_program::{program_option::COption, program_pack::Pack, pubkey::Pubkey};
+
+/// A legacy token account holding 1_000_000 of mint `[1; 32]` for owner
+/// `[2; 32]`.
+const LEGACY_TOKEN: &str = concat!(
+    "QEIPAAAAAAABAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQICAgICAgICAgICAgIC",
+    "AgICAgICAgICAgICAgICAgIC",
+);
+
+/// A frozen, pausable token account of the same mint and owner, with a
+/// delegate `[4; 32]` of 250 and 7 withheld.
+const TOKEN: &str = concat!(
+    "AwNAQg8AAAAAAAEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAgICAgICAgICAgIC",
+    "AgICAgICAgICAgICAgICAgICAgIBAAAABAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE",
+    "BAT6AAAAAAAAAAIAAAAAAAAAAAAAAAABAAAABwAAAAAAAAAAAQAAAAAAAAAAAAAAAAAAAAAA",
+    "AAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+);
+
+/// `TOKEN` with a memo requirement, then a lock until 1_700_000_000.
+const TOKEN_WITH_EXTENSIONS: &str = concat!(
+    "AwNAQg8AAAAAAAEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAgICAgICAgICAgIC",
+    "AgICAgICAgICAgICAgICAgICAgIBAAAABAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE",
+    "BAT6AAAAAAAAAAIAAAAAAAAAAAAAAAABAAAABwAAAAAAAAAAAQAAAAAAAAAAAAAAAAAAAAAA",
+    "AAAAAAAAAAAAAAAAAAAAAAAAAAABAAEAAQIACAAA8VNlAAAAAA==",
+);
+
+/// A legacy mint of 5_000_000 with 6 decimals and authority `[3; 32]`.
+const LEGACY_MINT: &str = "AQAAAAMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDQEtMAAAAAAAGAQ==";
+
+/// The same mint in the current layout, capped at 10_000_000 and charging
+/// 50 basis points up to 5_000, with 12 withheld.
+const MINT: &str = concat!(
+    "BAMBAAAAAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwNAS0wAAAAAAAYBAAAAAAAA",
+    "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQAAAICWmAAAAAAAAAAAAAAAAAAAAAAA",
+    "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQAAADIAiBMAAAAAAAAMAAAAAAAAAAAAAAAAAAAA",
+    "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA",
+    "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA",
+    "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA",
+    "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA==",
+);
+fn bytes(fixture: &str) -> Vec<u8> {
+    STANDARD.decode(fixture).unwrap()
+}
+
+fn key(byte: u8) -> Pubkey {
+    Pubkey::new_from_array([byte; 32])
+}
+
+#[test]
+fn legacy_token_accounts_decode() {
+    let view = parse_token_account(&bytes(LEGACY_TOKEN)).unwrap();
+    assert_eq!(view.layout, Layout::Legacy);
+    assert_eq!(
+        (view.mint, view.owner, view.amount),
+        (key(1), key(2), 1_000_000)
+    );
+    assert_eq!(view.state, AccountState::Initialized);
+    assert_eq!(view.delegate, None);
+    assert_eq!(view.flags, TokenAccountFlags::default());
+    assert!(view.extensions.is_empty());
+}
+
+#[test]
+fn current_token_accounts_decode() {
+    let view = parse_token_account(&bytes(TOKEN)).unwrap();
+    assert_eq!(view.layout, Layout::Current);
+    assert_eq!(
+        (view.mint, view.owner, view.amount),
+        (key(1), key(2), 1_000_000)
+    );
+    assert_eq!(view.state, AccountState::Frozen);
+    assert_eq!((view.delegate, view.delegated_amount), (Some(key(4)), 250));
+    assert_eq!(view.withheld_amount, Some(7));
+    assert_eq!((view.is_native, view.pending_owner), (None, None));
+    assert_eq!(
+        view.flags,
+        TokenAccountFlags {
+            pausable: true,
+            ..TokenAccountFlags::default()
+        }
+    );
+    assert!(view.extensions.is_empty());
+}
+
+#[test]
+fn extensions_are_listed_in_order() {
+    let view = parse_token_account(&bytes(TOKEN_WITH_EXTENSIONS)).unwrap();
+    assert_eq!(
+        view.extensions,
+        [ExtensionType::MemoTransfer, ExtensionType::AccountLock]
+    );
+    assert_eq!(view.amount, 1_000_000);
+}
+
+#[test]
+fn mints_of_both_layouts_decode() {
+    let legacy = parse_mint(&bytes(LEGACY_MINT)).unwrap();
+    assert_eq!(legacy.layout, Layout::Legacy);
+    assert_eq!(legacy.mint_authority, Some(key(3)));
+    assert_eq!((legacy.supply, legacy.decimals), (5_000_000, 6));
+    assert_eq!((legacy.max_supply, legacy.transfer_fee), (None, None));
+
+    let current = parse_mint(&bytes(MINT)).unwrap();
+    assert_eq!(current.layout, Layout::Current);
+    assert_eq!(
+        (current.mint_authority, current.supply, current.decimals),
+        (legacy.mint_authority, legacy.supply, legacy.decimals)
+    );
+    assert_eq!(current.max_supply, Some(10_000_000));
+    assert_eq!(
+        current.transfer_fee,
+        Some(TransferFee {
+            basis_points: 50,
+            maximum_fee: 5_000
+        })
+    );
+    assert_eq!(current.withheld_amount, 12);
+    assert!(!current.paused && !current.non_transferable);
+    assert_eq!(current.metadata_pointer, None);
+}
+
+#[test]
+fn metadata_pointers_decode() {
+    let pointer = MetadataPointer {
+        authority: COption::None,
+        metadata_address: COption::Some(key(9)),
+    };
+    let mut data = vec![0; Mint::LEN + extension::HEADER_LEN + MetadataPointer::LEN];
+    Mint {
+        is_initialized: true,
+        ..Mint::default()
+    }
+    .pack_into_slice(&mut data[..Mint::LEN]);
+    extension::init_mint_extension(&mut data, &pointer).unwrap();
+    assert_eq!(parse_mint(&data).unwrap().metadata_pointer, Some(pointer));
+}
+
+#[test]
+fn minters_decode() {
+    let mut data = vec![0; Mint::LEN + extension::HEADER_LEN + Minters::LEN];
+    Mint {
+        is_initialized: true,
+        ..Mint::default()
+    }
+    .pack_into_slice(&mut data[..Mint::LEN]);
+    assert!(parse_mint(&data).is_err());
+    let minters = Minters {
+        minters: vec![
+            Minter {
+                minter: key(5),
+                ..Minter::default()
+            },
+            Minter {
+                minter: key(6),
+                max_per_window: 100,
+                window_slots: 10,
+                ..Minter::default()
+            },
+        ],
+    };
+    extension::init_mint_extension(&mut data, &minters).unwrap();
+    assert_eq!(parse_mint(&data).unwrap().minters, [key(5), key(6)]);
+    assert_eq!(
+        parse_mint(&data[..Mint::LEN]).unwrap().minters,
+        Vec::<Pubkey>::new()
+    );
+}
+
+#[test]
+fn truncated_and_mistyped_data_is_rejected() {
+    // Data cut to the end of a legacy layout, the base layout or a whole
+    // extension entry is still a valid account.
+    let whole = [72, bytes(TOKEN).len(), bytes(TOKEN).len() + 5];
+    for fixture in [LEGACY_TOKEN, TOKEN, TOKEN_WITH_EXTENSIONS] {
+        let data = bytes(fixture);
+        for len in (0..data.len()).filter(|len| !whole.contains(len)) {
+            assert!(parse_token_account(&data[..len]).is_err(), "{len} bytes");
+        }
+    }
+    for fixture in [LEGACY_MINT, MINT] {
+        let data = bytes(fixture);
+        for len in (0..data.len()).filter(|len| *len != 46) {
+            assert!(parse_mint(&data[..len]).is_err(), "{len} bytes");
+        }
+    }
+    assert_eq!(
+        parse_mint(&bytes(TOKEN)),
+        Err(DecodeError::UnexpectedLength(bytes(TOKEN).len()))
+    );
+    assert_eq!(
+        parse_token_account(&bytes(MINT)),
+        Err(DecodeError::UnexpectedLength(bytes(MINT).len()))
+    );
+}
+
+#[test]
+fn malformed_and_uninitialized_data_is_rejected() {
+    let mut data = bytes(TOKEN);
+    data[0] = 9;
+    assert_eq!(parse_token_account(&data), Err(DecodeError::InvalidData));
+    // An extension entry with an unknown type.
+    let mut data = bytes(TOKEN_WITH_EXTENSIONS);
+    let entry = bytes(TOKEN).len();
+    data[entry] = 0xff;
+    assert_eq!(parse_token_account(&data), Err(DecodeError::InvalidData));
+
+    assert_eq!(
+        parse_token_account(&vec![0; bytes(TOKEN).len()]),
+        Err(DecodeError::Uninitialized)
+    );
+    assert_eq!(
+        parse_token_account(&[0; 72]),
+        Err(DecodeError::Uninitialized)
+    );
+    assert_eq!(parse_mint(&[0; 46]), Err(DecodeError::Uninitialized));
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_data_never_panics(data in vec(any::<u8>(), 0..512)) {
+        let _ = parse_token_account(&data);
+        let _ = parse_mint(&data);
+    }
+}
@@ -0,0 +1,307 @@
+use solana_contracts::{
+    deprecation::{
+        self, DeprecationTable, Disposition, MINT_TOKENS_REPLACEMENT, TRANSFER_TOKENS_REPLACEMENT,
+    },
+    error::TokenError,
+    instruction::{self, MAX_TAG},
+    processor::Processor,
+    Mint, Token,
+};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::InstructionError, msg,
+    program_pack::Pack, pubkey::Pubkey, system_instruction,
+};
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    hash::Hash,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use std::sync::Mutex;
+
+/// The table the program under test dispatches with, changed between
+/// transactions to stage a sunset.
+static STAGE: Mutex<DeprecationTable> = Mutex::new(DeprecationTable::PASS_THROUGH);
+
+/// The compatibility handler the staged sunset gives `TransferTokens`: it
+/// logs that it ran, then handles the transfer as before.
+fn transfer_tokens_compat(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    msg!("Handling TransferTokens in compatibility");
+    Processor::process_with_deprecations(
+        program_id,
+        accounts,
+        data,
+        &DeprecationTable::PASS_THROUGH,
+    )
+}
+
+fn process_staged(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let deprecations = *STAGE.lock().unwrap();
+    Processor::process_with_deprecations(program_id, accounts, data, &deprecations)
+}
+
+struct Env {
+    banks_client: BanksClient,
+    payer: Keypair,
+    recent_blockhash: Hash,
+}
+
+impl Env {
+    /// Processes `instructions`, returning the result and the program's
+    /// log lines.
+    async fn process(
+        &mut self,
+        instructions: &[solana_program::instruction::Instruction],
+        signers: &[&Keypair],
+    ) -> (Result<(), TransactionError>, Vec<String>) {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.recent_blockhash,
+        );
+        let outcome = self
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        let logs = outcome
+            .metadata
+            .map(|metadata| metadata.log_messages)
+            .unwrap_or_default();
+        (outcome.result, logs)
+    }
+
+    async fn create_account(&mut self, account: &Keypair, len: usize, owner: &Pubkey) {
+        let rent = self.banks_client.get_rent().await.unwrap();
+        let create = system_instruction::create_account(
+            &self.payer.pubkey(),
+            &account.pubkey(),
+            rent.minimum_balance(len),
+            len as u64,
+            owner,
+        );
+        self.process(&[create], &[account]).await.0.unwrap();
+    }
+
+    async fn amount(&mut self, account: &Pubkey) -> u64 {
+        let account = self.banks_client.get_account(*account).await.unwrap();
+        Token::unpack(&account.unwrap().data).unwrap().amount
+    }
+}
+
+#[test]
+fn this_release_handles_only_mint_tokens_in_compatibility() {
+    // Staging a sunset changes this test along with `RELEASE`.
+    assert!(matches!(
+        deprecation::RELEASE.get(0),
+        Disposition::Compat {
+            replacement: MINT_TOKENS_REPLACEMENT,
+            ..
+        }
+    ));
+    for tag in 1..=u8::MAX {
+        assert!(matches!(
+            deprecation::RELEASE.get(tag),
+            Disposition::PassThrough
+        ));
+    }
+}
+
+#[tokio::test]
+async fn this_release_mints_with_tag_0_after_logging_the_new_behaviour() {
+    let program_id = Pubkey::new_unique();
+    let program_test = ProgramTest::new(
+        "solana_contracts",
+        program_id,
+        processor!(Processor::process),
+    );
+    let (banks_client, payer, recent_blockhash) = program_test.start().await;
+    let mut env = Env {
+        banks_client,
+        payer,
+        recent_blockhash,
+    };
+    let authority = env.payer.pubkey();
+    let (mint, owner, account) = (Keypair::new(), Keypair::new(), Keypair::new());
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let initialize_mint =
+        instruction::initialize_mint(&program_id, &mint.pubkey(), &authority, None, 2, None)
+            .unwrap();
+    env.process(&[initialize_mint], &[]).await.0.unwrap();
+    env.create_account(&account, Token::LEN, &program_id).await;
+    let initialize = instruction::initialize_account(
+        &program_id,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &owner.pubkey(),
+    )
+    .unwrap();
+    env.process(&[initialize], &[]).await.0.unwrap();
+
+    let mint_tokens = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &account.pubkey(),
+        &authority,
+        &[],
+        100,
+    )
+    .unwrap();
+    let (result, logs) = env.process(&[mint_tokens], &[]).await;
+    result.unwrap();
+    let notice = logs
+        .iter()
+        .position(|line| line == "Program log: Instruction 0 is deprecated; use instruction 13");
+    let behaviour = logs.iter().position(|line| {
+        line == "Program log: MintTokens credits the destination's owner and no longer makes \
+                 the mint its owner"
+    });
+    assert!(
+        matches!((notice, behaviour), (Some(notice), Some(behaviour)) if notice < behaviour),
+        "{logs:#?}"
+    );
+    let token = env
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap();
+    let token = Token::unpack(&token.unwrap().data).unwrap();
+    assert_eq!((token.owner, token.amount), (owner.pubkey(), 100));
+}
+
+#[test]
+fn tables_set_only_the_tags_they_are_given() {
+    let table = DeprecationTable::PASS_THROUGH.with(1, Disposition::Deprecated { replacement: 12 });
+    assert!(matches!(
+        table.get(1),
+        Disposition::Deprecated { replacement: 12 }
+    ));
+    for tag in [0, MAX_TAG, MAX_TAG + 1] {
+        assert!(matches!(table.get(tag), Disposition::PassThrough));
+    }
+}
+
+#[tokio::test]
+async fn staged_sunsets_log_the_replacement_then_fail() {
+    let program_id = Pubkey::new_unique();
+    let program_test = ProgramTest::new("solana_contracts", program_id, processor!(process_staged));
+    let (banks_client, payer, recent_blockhash) = program_test.start().await;
+    let mut env = Env {
+        banks_client,
+        payer,
+        recent_blockhash,
+    };
+    let authority = env.payer.pubkey();
+    let (mint, owner) = (Keypair::new(), Keypair::new());
+    let (source, destination) = (Keypair::new(), Keypair::new());
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let initialize_mint =
+        instruction::initialize_mint(&program_id, &mint.pubkey(), &authority, None, 2, None)
+            .unwrap();
+    env.process(&[initialize_mint], &[]).await.0.unwrap();
+    for account in [&source, &destination] {
+        env.create_account(account, Token::LEN, &program_id).await;
+        let initialize = instruction::initialize_account(
+            &program_id,
+            &account.pubkey(),
+            &mint.pubkey(),
+            &owner.pubkey(),
+        )
+        .unwrap();
+        env.process(&[initialize], &[]).await.0.unwrap();
+    }
+    let mint_tokens = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &authority,
+        &[],
+        100,
+    )
+    .unwrap();
+    env.process(&[mint_tokens], &[]).await.0.unwrap();
+
+    // Distinct amounts, so no two transfers are the same transaction.
+    let transfer = |amount| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    let notice = "Program log: Instruction 1 is deprecated; use instruction 12";
+    let rejection = "Program log: Instruction 1 is no longer supported; use instruction 12";
+    let compat = "Program log: Handling TransferTokens in compatibility";
+
+    let (result, logs) = env.process(&[transfer(1)], &[&owner]).await;
+    result.unwrap();
+    assert!(!logs.iter().any(|line| line.contains("deprecated")));
+
+    // In compatibility, the compatibility handler takes the transfer
+    // through, after a notice.
+    *STAGE.lock().unwrap() = DeprecationTable::PASS_THROUGH.with(
+        1,
+        Disposition::Compat {
+            replacement: TRANSFER_TOKENS_REPLACEMENT,
+            handler: transfer_tokens_compat,
+        },
+    );
+    let (result, logs) = env.process(&[transfer(2)], &[&owner]).await;
+    result.unwrap();
+    let notice_at = logs.iter().position(|line| line == notice);
+    let handled_at = logs.iter().position(|line| line == compat);
+    assert!(
+        matches!((notice_at, handled_at), (Some(notice_at), Some(handled_at)) if notice_at < handled_at),
+        "{logs:#?}"
+    );
+    assert_eq!(env.amount(&destination.pubkey()).await, 3);
+
+    // Once deprecated, it fails and moves nothing, while its replacement
+    // still works.
+    *STAGE.lock().unwrap() = DeprecationTable::PASS_THROUGH.with(
+        1,
+        Disposition::Deprecated {
+            replacement: TRANSFER_TOKENS_REPLACEMENT,
+        },
+    );
+    let (result, logs) = env.process(&[transfer(4)], &[&owner]).await;
+    assert!(!logs.iter().any(|line| line == compat), "{logs:#?}");
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TokenError::DeprecatedInstruction as u32)
+        )
+    );
+    assert!(logs.iter().any(|line| line == rejection), "{logs:#?}");
+    assert_eq!(env.amount(&destination.pubkey()).await, 3);
+
+    let transfer_checked = instruction::transfer_checked(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        4,
+        2,
+    )
+    .unwrap();
+    let (result, logs) = env.process(&[transfer_checked], &[&owner]).await;
+    result.unwrap();
+    assert!(!logs.iter().any(|line| line.contains("deprecated")));
+    assert_eq!(env.amount(&destination.pubkey()).await, 7);
+}
@@ -0,0 +1,74 @@
+//! Holds the `TokenError` codes clients map to messages to a committed
+//! table, `idl/errors.json`.
+
+use num_traits::FromPrimitive;
+use serde_json::{json, Value};
+use solana_contracts::error::{TokenError, ERRORS};
+use std::{fs, path::Path};
+
+const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/idl/errors.json");
+
+fn to_json() -> String {
+    let errors: Vec<Value> = ERRORS
+        .iter()
+        .map(|error| json!({ "code": error.code, "name": error.name, "message": error.message }))
+        .collect();
+    serde_json::to_string_pretty(&errors).unwrap() + "\n"
+}
+
+fn golden() -> Vec<Value> {
+    fs::read_to_string(GOLDEN)
+        .map(|golden| serde_json::from_str(&golden).unwrap())
+        .unwrap_or_default()
+}
+
+#[test]
+fn table_lists_every_error_by_code() {
+    for (position, error) in ERRORS.iter().enumerate() {
+        assert_eq!(error.code as usize, position);
+        let variant = TokenError::from_u32(error.code).unwrap();
+        assert_eq!(format!("{variant:?}"), error.name);
+        assert_eq!(variant.to_string(), error.message);
+    }
+    assert_eq!(TokenError::from_u32(ERRORS.len() as u32), None);
+}
+
+/// Fails if a committed code now names another variant, even when updating:
+/// renumbering an error changes what deployed clients report.
+fn check_committed_codes() {
+    for committed in golden() {
+        let code = committed["code"].as_u64().unwrap() as usize;
+        let error = ERRORS
+            .get(code)
+            .unwrap_or_else(|| panic!("error code {code} was removed"));
+        assert_eq!(
+            committed["name"], error.name,
+            "error code {code} changed variant; append new variants instead"
+        );
+    }
+}
+
+#[test]
+fn committed_codes_keep_their_variant() {
+    check_committed_codes();
+}
+
+/// Writes the table to `target/errors.json` for client codegen and compares
+/// it with the committed copy; run with `UPDATE_ERRORS=1` to accept a change.
+#[test]
+fn table_matches_the_committed_copy() {
+    let json = to_json();
+    let target = Path::new(env!("CARGO_MANIFEST_DIR")).join("target");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(target.join("errors.json"), &json).unwrap();
+    if std::env::var_os("UPDATE_ERRORS").is_some() {
+        check_committed_codes();
+        fs::write(GOLDEN, &json).unwrap();
+        return;
+    }
+    let golden = fs::read_to_string(GOLDEN).unwrap_or_default();
+    assert!(
+        golden == json,
+        "the error table changed; review it and rerun with UPDATE_ERRORS=1 to update {GOLDEN}"
+    );
+}
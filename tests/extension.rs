@@ -0,0 +1,548 @@
+//! Round trips of token accounts holding every combination of extensions,
+//! and of mints holding theirs.
+
+use proptest::{option, prelude::*};
+use solana_contracts::{
+    extension::{
+        self, AccountLock, CloseAuthority, Cooldown, Extension, ExtensionType, ImmutableOwner,
+        MemoTransfer, MinimumBalance, MintExtension, MintSettings, Minter, Minters, OutflowLimit,
+        PermitNonce, HEADER_LEN, MAX_ACCOUNT_LEN, MAX_MINTERS,
+    },
+    state::AccountState,
+    Mint, Token,
+};
+use solana_program::{
+    program_error::ProgramError, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+};
+use std::sync::OnceLock;
+
+fn base() -> Token {
+    Token {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount: 7,
+        state: AccountState::Initialized,
+        ..Token::default()
+    }
+}
+
+/// Grows `data` as the processor does, then writes `extension`.
+fn enable<T: Extension>(data: &mut Vec<u8>, extension: &T) {
+    let len = extension::len_with::<T>(data).unwrap();
+    data.resize(len, 0);
+    extension::init_extension(data, extension).unwrap();
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Values {
+    memo: MemoTransfer,
+    lock: AccountLock,
+    nonce: PermitNonce,
+    minimum: MinimumBalance,
+    close: CloseAuthority,
+    outflow: OutflowLimit,
+    settings: MintSettings,
+    cooldown: Cooldown,
+}
+
+fn enable_type(data: &mut Vec<u8>, extension_type: ExtensionType, values: Values) {
+    match extension_type {
+        ExtensionType::MemoTransfer => enable(data, &values.memo),
+        ExtensionType::AccountLock => enable(data, &values.lock),
+        ExtensionType::ImmutableOwner => enable(data, &ImmutableOwner),
+        ExtensionType::PermitNonce => enable(data, &values.nonce),
+        ExtensionType::MinimumBalance => enable(data, &values.minimum),
+        ExtensionType::CloseAuthority => enable(data, &values.close),
+        ExtensionType::OutflowLimit => enable(data, &values.outflow),
+        ExtensionType::MintSettings => enable(data, &values.settings),
+        ExtensionType::Cooldown => enable(data, &values.cooldown),
+    }
+}
+
+/// Every ordered selection of distinct extension types an account's owner
+/// can enable, including none, built once. The entries copied from the
+/// mint are all written at initialization, so they are added after any
+/// ordering instead.
+fn orderings() -> &'static [Vec<ExtensionType>] {
+    static ORDERINGS: OnceLock<Vec<Vec<ExtensionType>>> = OnceLock::new();
+    ORDERINGS.get_or_init(build_orderings)
+}
+
+fn build_orderings() -> Vec<Vec<ExtensionType>> {
+    let all = [
+        ExtensionType::MemoTransfer,
+        ExtensionType::AccountLock,
+        ExtensionType::ImmutableOwner,
+        ExtensionType::PermitNonce,
+        ExtensionType::MinimumBalance,
+        ExtensionType::CloseAuthority,
+        ExtensionType::OutflowLimit,
+    ];
+    let mut orderings = vec![vec![]];
+    let mut last = vec![vec![]];
+    for _ in 0..all.len() {
+        last = last
+            .iter()
+            .flat_map(|prefix: &Vec<ExtensionType>| {
+                all.iter()
+                    .filter(|extension_type| !prefix.contains(extension_type))
+                    .map(|&extension_type| {
+                        let mut ordering = prefix.clone();
+                        ordering.push(extension_type);
+                        ordering
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        orderings.extend(last.iter().cloned());
+    }
+    orderings
+}
+
+fn account(token: &Token, ordering: &[ExtensionType], values: Values) -> Vec<u8> {
+    let mut data = vec![0; Token::LEN];
+    Token::pack(token.clone(), &mut data).unwrap();
+    for &extension_type in ordering {
+        enable_type(&mut data, extension_type, values);
+    }
+    data
+}
+
+fn entry_len(extension_type: ExtensionType) -> usize {
+    HEADER_LEN
+        + match extension_type {
+            ExtensionType::MemoTransfer => MemoTransfer::LEN,
+            ExtensionType::AccountLock => AccountLock::LEN,
+            ExtensionType::ImmutableOwner => ImmutableOwner::LEN,
+            ExtensionType::PermitNonce => PermitNonce::LEN,
+            ExtensionType::MinimumBalance => MinimumBalance::LEN,
+            ExtensionType::CloseAuthority => CloseAuthority::LEN,
+            ExtensionType::OutflowLimit => OutflowLimit::LEN,
+            ExtensionType::MintSettings => MintSettings::LEN,
+            ExtensionType::Cooldown => Cooldown::LEN,
+        }
+}
+
+#[test]
+fn every_ordering_is_covered() {
+    assert_eq!(
+        orderings().len(),
+        1 + 7 + 42 + 210 + 840 + 2520 + 5040 + 5040
+    );
+}
+
+proptest! {
+    // Each case already covers every ordering.
+    #![proptest_config(ProptestConfig::with_cases(8))]
+
+    #[test]
+    fn accounts_with_any_extensions_round_trip(
+        require_incoming_transfer_memos in any::<bool>(),
+        lock_until in any::<i64>(),
+        next_nonce in any::<u64>(),
+        minimum_balance in any::<u64>(),
+        close_authority in option::of(any::<[u8; 32]>().prop_map(Pubkey::new_from_array)),
+        outflow in any::<[u64; 4]>(),
+        holder_count in any::<bool>(),
+        activity_tracking in any::<bool>(),
+        min_transfer_amount in any::<bool>(),
+        enforce_allowlist in any::<bool>(),
+        royalty in any::<bool>(),
+        with_settings in any::<bool>(),
+        last_outgoing_slot in option::of(any::<u64>()),
+        amount in any::<u64>(),
+    ) {
+        let values = Values {
+            memo: MemoTransfer { require_incoming_transfer_memos },
+            lock: AccountLock { lock_until },
+            nonce: PermitNonce { next_nonce },
+            minimum: MinimumBalance {
+                minimum_balance,
+                enforcement_authority: Pubkey::new_unique(),
+            },
+            close: CloseAuthority { close_authority: close_authority.into() },
+            outflow: OutflowLimit {
+                max_outflow_per_instruction: outflow[0],
+                timelock_slots: outflow[1],
+                pending_max_outflow_per_instruction: outflow[2],
+                pending_effective_slot: outflow[3],
+            },
+            settings: MintSettings {
+                holder_count,
+                activity_tracking,
+                min_transfer_amount,
+                enforce_allowlist,
+                royalty,
+            },
+            cooldown: Cooldown { last_outgoing_slot: last_outgoing_slot.unwrap_or_default() },
+        };
+        let token = base();
+        for ordering in orderings() {
+            let mut ordering = ordering.clone();
+            if with_settings {
+                ordering.push(ExtensionType::MintSettings);
+            }
+            if last_outgoing_slot.is_some() {
+                ordering.push(ExtensionType::Cooldown);
+            }
+            let ordering = &ordering;
+            let data = account(&token, ordering, values);
+            let len: usize = ordering.iter().map(|&t| entry_len(t)).sum();
+            prop_assert_eq!(data.len(), Token::LEN + len);
+            prop_assert!(data.len() <= MAX_ACCOUNT_LEN);
+
+            prop_assert_eq!(Token::unpack(&data).unwrap(), token.clone());
+            prop_assert_eq!(extension::get_extension_types(&data).unwrap(), ordering.clone());
+            prop_assert_eq!(
+                extension::get_extension::<MemoTransfer>(&data).unwrap(),
+                ordering.contains(&ExtensionType::MemoTransfer).then_some(values.memo)
+            );
+            prop_assert_eq!(
+                extension::get_extension::<AccountLock>(&data).unwrap(),
+                ordering.contains(&ExtensionType::AccountLock).then_some(values.lock)
+            );
+            prop_assert_eq!(
+                extension::get_extension::<ImmutableOwner>(&data).unwrap(),
+                ordering.contains(&ExtensionType::ImmutableOwner).then_some(ImmutableOwner)
+            );
+            prop_assert_eq!(
+                extension::get_extension::<PermitNonce>(&data).unwrap(),
+                ordering.contains(&ExtensionType::PermitNonce).then_some(values.nonce)
+            );
+            prop_assert_eq!(
+                extension::get_extension::<MinimumBalance>(&data).unwrap(),
+                ordering.contains(&ExtensionType::MinimumBalance).then_some(values.minimum)
+            );
+            prop_assert_eq!(
+                extension::get_extension::<CloseAuthority>(&data).unwrap(),
+                ordering.contains(&ExtensionType::CloseAuthority).then_some(values.close)
+            );
+            prop_assert_eq!(
+                extension::get_extension::<OutflowLimit>(&data).unwrap(),
+                ordering.contains(&ExtensionType::OutflowLimit).then_some(values.outflow)
+            );
+            prop_assert_eq!(
+                extension::get_extension::<MintSettings>(&data).unwrap(),
+                ordering.contains(&ExtensionType::MintSettings).then_some(values.settings)
+            );
+            prop_assert_eq!(
+                extension::get_extension::<Cooldown>(&data).unwrap(),
+                ordering.contains(&ExtensionType::Cooldown).then_some(values.cooldown)
+            );
+
+            // Repacking the base leaves the extensions as they were.
+            let mut repacked = data.clone();
+            let updated = Token { amount, ..token.clone() };
+            Token::pack(updated.clone(), &mut repacked).unwrap();
+            prop_assert_eq!(Token::unpack(&repacked).unwrap(), updated);
+            prop_assert_eq!(&repacked[Token::LEN..], &data[Token::LEN..]);
+        }
+    }
+
+    #[test]
+    fn enabling_an_existing_extension_overwrites_it(
+        first in any::<i64>(),
+        second in any::<i64>(),
+    ) {
+        let values = |lock_until| Values {
+            memo: MemoTransfer::default(),
+            lock: AccountLock { lock_until },
+            nonce: PermitNonce::default(),
+            minimum: MinimumBalance::default(),
+            close: CloseAuthority::default(),
+            outflow: OutflowLimit::default(),
+            settings: MintSettings::default(),
+            cooldown: Cooldown::default(),
+        };
+        for ordering in orderings() {
+            if !ordering.contains(&ExtensionType::AccountLock) {
+                continue;
+            }
+            let mut data = account(&base(), ordering, values(first));
+            let len = data.len();
+            enable(&mut data, &AccountLock { lock_until: second });
+            prop_assert_eq!(data.len(), len);
+            let expected = account(&base_of(&data), ordering, values(second));
+            prop_assert_eq!(data, expected);
+        }
+    }
+
+}
+
+proptest! {
+    #[test]
+    fn corrupted_extensions_never_panic(
+        ordering in 0..13700usize,
+        index in any::<usize>(),
+        byte in any::<u8>(),
+    ) {
+        let values = Values {
+            memo: MemoTransfer { require_incoming_transfer_memos: true },
+            lock: AccountLock { lock_until: -1 },
+            nonce: PermitNonce { next_nonce: u64::MAX },
+            minimum: MinimumBalance {
+                minimum_balance: u64::MAX,
+                enforcement_authority: Pubkey::new_unique(),
+            },
+            close: CloseAuthority { close_authority: COption::Some(Pubkey::new_unique()) },
+            outflow: OutflowLimit {
+                max_outflow_per_instruction: 1,
+                timelock_slots: u64::MAX,
+                pending_max_outflow_per_instruction: u64::MAX,
+                pending_effective_slot: 1,
+            },
+            settings: MintSettings {
+                holder_count: true,
+                activity_tracking: true,
+                min_transfer_amount: true,
+                enforce_allowlist: true,
+                royalty: true,
+            },
+            cooldown: Cooldown { last_outgoing_slot: u64::MAX },
+        };
+        let mut ordering = orderings()[ordering].clone();
+        ordering.extend([ExtensionType::MintSettings, ExtensionType::Cooldown]);
+        let mut data = account(&base(), &ordering, values);
+        let tail = data.len() - Token::LEN;
+        if tail > 0 {
+            data[Token::LEN + index % tail] = byte;
+        }
+        let _ = Token::unpack(&data);
+        let _ = extension::get_extension::<MemoTransfer>(&data);
+        let _ = extension::get_extension::<AccountLock>(&data);
+        let _ = extension::get_extension::<PermitNonce>(&data);
+        let _ = extension::get_extension::<MinimumBalance>(&data);
+        let _ = extension::get_extension::<CloseAuthority>(&data);
+        let _ = extension::get_extension::<OutflowLimit>(&data);
+        let _ = extension::get_extension::<MintSettings>(&data);
+        let _ = extension::get_extension::<Cooldown>(&data);
+        let _ = extension::init_extension(&mut data, &ImmutableOwner);
+    }
+
+    #[test]
+    fn truncated_extensions_are_rejected(
+        ordering in 0..13700usize,
+        cut in 1..=HEADER_LEN + MinimumBalance::LEN,
+    ) {
+        let values = Values {
+            memo: MemoTransfer::default(),
+            lock: AccountLock::default(),
+            nonce: PermitNonce::default(),
+            minimum: MinimumBalance::default(),
+            close: CloseAuthority::default(),
+            outflow: OutflowLimit::default(),
+            settings: MintSettings::default(),
+            cooldown: Cooldown::default(),
+        };
+        let data = account(&base(), &orderings()[ordering], values);
+        let last = orderings()[ordering].last().map_or(0, |&t| entry_len(t));
+        if cut < last {
+            let truncated = &data[..data.len() - cut];
+            prop_assert_eq!(Token::unpack(truncated), Err(ProgramError::InvalidAccountData));
+        }
+    }
+}
+
+fn base_of(data: &[u8]) -> Token {
+    Token::unpack_from_slice(&data[..Token::LEN]).unwrap()
+}
+
+fn with_entry(extension_type: u16, value: &[u8]) -> Vec<u8> {
+    let mut data = vec![0; Token::LEN];
+    Token::pack(base(), &mut data).unwrap();
+    data.extend_from_slice(&extension_type.to_le_bytes());
+    data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    data.extend_from_slice(value);
+    data
+}
+
+#[test]
+fn hand_built_entries_match_the_typed_extensions() {
+    let data = with_entry(2, &(-3i64).to_le_bytes());
+    assert_eq!(
+        extension::get_extension::<AccountLock>(&data).unwrap(),
+        Some(AccountLock { lock_until: -3 })
+    );
+    assert_eq!(Token::unpack(&data).unwrap().amount, 7);
+    assert_eq!(
+        extension::get_extension::<ImmutableOwner>(&with_entry(3, &[])).unwrap(),
+        Some(ImmutableOwner)
+    );
+}
+
+#[test]
+fn unknown_extension_types_are_rejected() {
+    for extension_type in [0, 10, 0x100, u16::MAX] {
+        let data = with_entry(extension_type, &[1]);
+        assert_eq!(Token::unpack(&data), Err(ProgramError::InvalidAccountData));
+        assert_eq!(
+            extension::get_extension::<MemoTransfer>(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
+
+#[test]
+fn malformed_entries_are_rejected() {
+    let mut duplicated = with_entry(1, &[1]);
+    duplicated.extend_from_slice(&with_entry(1, &[0])[Token::LEN..]);
+    let cases = [
+        // Wrong lengths for the type.
+        with_entry(1, &[]),
+        with_entry(1, &[1, 0]),
+        with_entry(2, &[0; 7]),
+        with_entry(3, &[0]),
+        with_entry(4, &[0; 9]),
+        with_entry(5, &[0; 32]),
+        with_entry(6, &[0; 32]),
+        with_entry(7, &[0; 36]),
+        // The same type twice.
+        duplicated,
+        // Zeroed space that was never filled in.
+        vec![0; Token::LEN + HEADER_LEN],
+    ];
+    for data in cases {
+        assert_eq!(Token::unpack(&data), Err(ProgramError::InvalidAccountData));
+    }
+    assert_eq!(
+        extension::get_extension_types(&[0; Token::LEN - 1]),
+        Err(ProgramError::InvalidAccountData)
+    );
+
+    // A well-formed entry holding an invalid flag fails only when read.
+    let data = with_entry(1, &[2]);
+    assert!(Token::unpack(&data).is_ok());
+    assert_eq!(
+        extension::get_extension::<MemoTransfer>(&data),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+fn init_extension_only_fills_zeroed_space() {
+    let mut data = vec![0; Token::LEN];
+    Token::pack(base(), &mut data).unwrap();
+    // No room for the entry.
+    assert_eq!(
+        extension::init_extension(&mut data, &ImmutableOwner),
+        Err(ProgramError::InvalidAccountData)
+    );
+
+    data.resize(Token::LEN + HEADER_LEN, 0xff);
+    assert_eq!(
+        extension::init_extension(&mut data, &ImmutableOwner),
+        Err(ProgramError::InvalidAccountData)
+    );
+
+    data[Token::LEN..].fill(0);
+    extension::init_extension(&mut data, &ImmutableOwner).unwrap();
+    assert_eq!(
+        extension::get_extension_types(&data).unwrap(),
+        [ExtensionType::ImmutableOwner]
+    );
+}
+
+fn mint_with_entry(extension_type: u16, value: &[u8]) -> Vec<u8> {
+    let mut data = vec![0; Mint::LEN];
+    Mint {
+        is_initialized: true,
+        ..Mint::default()
+    }
+    .pack_into_slice(&mut data);
+    data.extend_from_slice(&extension_type.to_le_bytes());
+    data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    data.extend_from_slice(value);
+    data
+}
+
+#[test]
+fn mints_round_trip_their_minters() {
+    let minters = Minters {
+        minters: (0..MAX_MINTERS as u64)
+            .map(|i| Minter {
+                minter: Pubkey::new_unique(),
+                max_per_window: i * 100,
+                window_slots: i,
+                window_start_slot: i + 1,
+                minted_in_window: i + 2,
+            })
+            .collect(),
+    };
+    let mut data = vec![0; Mint::LEN];
+    let mint = Mint {
+        supply: 9,
+        is_initialized: true,
+        ..Mint::default()
+    };
+    Mint::pack(mint.clone(), &mut data).unwrap();
+    let len = extension::mint_len_with::<Minters>(&data).unwrap();
+    assert_eq!(len, Mint::LEN + HEADER_LEN + Minters::LEN);
+    data.resize(len, 0);
+    extension::init_mint_extension(&mut data, &minters).unwrap();
+    assert_eq!(extension::mint_len_with::<Minters>(&data), Ok(len));
+    assert_eq!(
+        extension::get_mint_extension::<Minters>(&data),
+        Ok(Some(minters))
+    );
+    assert_eq!(Mint::unpack(&data), Ok(mint));
+
+    // Minting packs the base layout without touching the entry.
+    let mint = Mint {
+        supply: 10,
+        ..Mint::unpack(&data).unwrap()
+    };
+    let entry = data[Mint::LEN..].to_vec();
+    Mint::pack(mint, &mut data).unwrap();
+    assert_eq!(data[Mint::LEN..], entry[..]);
+}
+
+#[test]
+fn malformed_mint_entries_are_rejected() {
+    // Zeroed space that was never filled in.
+    let mut unfilled = mint_with_entry(1, &[]);
+    unfilled[Mint::LEN..].fill(0);
+    for data in [
+        // A type no mint extension has.
+        mint_with_entry(0xff, &[0; 8]),
+        mint_with_entry(1, &[0; Minters::LEN - 1]),
+        unfilled,
+    ] {
+        assert_eq!(Mint::unpack(&data), Err(ProgramError::InvalidAccountData));
+    }
+    let mut too_many = vec![0; Minters::LEN];
+    too_many[0] = MAX_MINTERS as u8 + 1;
+    let data = mint_with_entry(1, &too_many);
+    assert!(Mint::unpack(&data).is_ok());
+    assert_eq!(
+        extension::get_mint_extension::<Minters>(&data),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+fn minter_caps_refill_each_window() {
+    let mut minter = Minter {
+        max_per_window: 100,
+        window_slots: 10,
+        window_start_slot: 5,
+        ..Minter::default()
+    };
+    assert!(minter.record_mint(60, 5));
+    assert!(!minter.record_mint(41, 14));
+    assert!(minter.record_mint(40, 14));
+    assert_eq!(
+        (minter.window_start_slot, minter.minted_in_window),
+        (5, 100)
+    );
+    // The next window starts at the first mint after this one ends.
+    assert!(minter.record_mint(100, 30));
+    assert_eq!(
+        (minter.window_start_slot, minter.minted_in_window),
+        (30, 100)
+    );
+    assert!(!minter.record_mint(u64::MAX, 100));
+
+    let mut uncapped = Minter::default();
+    assert!(uncapped.record_mint(u64::MAX, 0));
+    assert!(uncapped.record_mint(u64::MAX, 0));
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "client")]
+
+use solana_contracts::{
+    decode,
+    fixtures::{self, fixture_key},
+    state::{AccountState, Mint, Multisig, Token},
+};
+use solana_program::{program_error::ProgramError, program_pack::Pack};
+use std::{collections::BTreeSet, fs, path::Path};
+
+const DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+
+/// Regenerates the fixtures and compares them with the committed copies;
+/// run with `UPDATE_FIXTURES=1` to accept a change.
+#[test]
+fn fixtures_match_the_committed_copies() {
+    let files = fixtures::files();
+    if std::env::var_os("UPDATE_FIXTURES").is_some() {
+        let _ = fs::remove_dir_all(DIR);
+        fs::create_dir_all(DIR).unwrap();
+        for (name, contents) in &files {
+            fs::write(Path::new(DIR).join(name), contents).unwrap();
+        }
+        return;
+    }
+    for (name, contents) in &files {
+        let committed = fs::read(Path::new(DIR).join(name)).unwrap_or_default();
+        assert!(
+            &committed == contents,
+            "{name} changed; review it and rerun with UPDATE_FIXTURES=1 to update {DIR}"
+        );
+    }
+    let committed: BTreeSet<_> = fs::read_dir(DIR)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    let generated: BTreeSet<_> = files.into_iter().map(|(name, _)| name).collect();
+    assert_eq!(committed, generated, "{DIR} has stale fixtures");
+}
+
+#[test]
+fn fixtures_read_back_as_described() {
+    let fixtures = fixtures::fixtures();
+    let data = |name| &fixtures.iter().find(|f| f.name == name).unwrap().data;
+    for fixture in &fixtures {
+        let unpacked = match fixture.account_type {
+            "Mint" => Mint::unpack(&fixture.data).map(drop),
+            "Token" => Token::unpack(&fixture.data).map(drop),
+            "Multisig" => Multisig::unpack(&fixture.data).map(drop),
+            other => panic!("unknown account type {other}"),
+        };
+        assert_eq!(unpacked.is_ok(), fixture.valid, "{}", fixture.name);
+    }
+
+    let mint = Mint::unpack(data("mint_with_transfer_fee")).unwrap();
+    assert_eq!(mint.transfer_fee.unwrap().basis_points, 50);
+    let frozen = Token::unpack(data("frozen_token_account")).unwrap();
+    assert_eq!(frozen.state, AccountState::Frozen);
+    assert_eq!(frozen.mint, fixture_key("mint"));
+    let multisig = Multisig::unpack(data("multisig_2_of_3")).unwrap();
+    assert_eq!((multisig.m, multisig.n), (2, 3));
+
+    let legacy = data("legacy_token_account");
+    assert_eq!(legacy.len(), Token::LEGACY_LEN);
+    let migrated = Token::unpack_any_version(legacy).unwrap();
+    assert_eq!(migrated.amount, 42_000);
+    assert_eq!(migrated.owner, fixture_key("owner"));
+    assert_eq!(migrated.state, AccountState::Initialized);
+
+    let malformed = data("malformed_token_account");
+    assert_eq!(
+        Token::unpack(malformed).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+    assert!(decode::parse_token_account(malformed).is_err());
+}
+
+#[test]
+fn the_manifest_lists_every_fixture() {
+    let fixtures = fixtures::fixtures();
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fixtures::manifest_json(&fixtures)).unwrap();
+    let listed = manifest["fixtures"].as_array().unwrap();
+    assert_eq!(listed.len(), fixtures.len());
+    for (entry, fixture) in listed.iter().zip(&fixtures) {
+        assert_eq!(entry["file"], format!("{}.bin", fixture.name));
+        assert_eq!(entry["address"], fixture.address.to_string());
+        assert_eq!(entry["data_len"], fixture.data.len());
+        assert_eq!(entry["valid"], fixture.valid);
+    }
+}
@@ -0,0 +1,201 @@
+#![cfg(feature = "idl")]
+
+use solana_contracts::{
+    idl::{self, IdlType},
+    instruction::{self, AuthorityType, TokenInstruction},
+    AccountState,
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use std::{fs, path::Path};
+
+const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/idl/solana_contracts.json");
+
+/// Writes the IDL to `target/idl.json` and compares it with the committed
+/// copy; run with `UPDATE_IDL=1` to accept a change.
+#[test]
+fn idl_matches_the_committed_copy() {
+    let json = idl::to_json();
+    let target = Path::new(env!("CARGO_MANIFEST_DIR")).join("target");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(target.join("idl.json"), &json).unwrap();
+    if std::env::var_os("UPDATE_IDL").is_some() {
+        fs::write(GOLDEN, &json).unwrap();
+        return;
+    }
+    let golden = fs::read_to_string(GOLDEN).unwrap_or_default();
+    assert!(
+        golden == json,
+        "the IDL changed; review it and rerun with UPDATE_IDL=1 to update {GOLDEN}"
+    );
+}
+
+/// One instruction of each kind, built with a single-signer authority.
+fn samples() -> Vec<Instruction> {
+    let [pid, a, b, c, d, e, f, g] = [(); 8].map(|_| Pubkey::new_unique());
+    let (pid, a, b, c, d, e, f, g) = (&pid, &a, &b, &c, &d, &e, &f, &g);
+    vec![
+        instruction::mint_tokens(pid, a, b, c, &[], 1).unwrap(),
+        instruction::transfer_tokens(pid, a, b, c, &[], 1).unwrap(),
+        instruction::burn(pid, a, b, c, &[], 1).unwrap(),
+        instruction::initialize_mint(pid, a, b, Some(c), 6, Some(100)).unwrap(),
+        instruction::approve(pid, a, b, c, &[], 1).unwrap(),
+        instruction::revoke(pid, a, b, &[]).unwrap(),
+        instruction::initialize_account(pid, a, b, c).unwrap(),
+        instruction::close_account(pid, a, b, c, &[]).unwrap(),
+        instruction::freeze_account(pid, a, b, c, &[]).unwrap(),
+        instruction::thaw_account(pid, a, b, c, &[]).unwrap(),
+        instruction::initialize_account2(pid, a, b, c).unwrap(),
+        instruction::set_authority(pid, a, Some(b), AuthorityType::CloseMint, c, &[]).unwrap(),
+        instruction::transfer_checked(pid, a, b, c, d, &[], 1, 6).unwrap(),
+        instruction::mint_to_checked(pid, a, b, c, &[], 1, 6).unwrap(),
+        instruction::burn_checked(pid, a, b, c, &[], 1, 6).unwrap(),
+        instruction::create_associated_account(pid, a, b, c).unwrap(),
+        instruction::sync_native(pid, a).unwrap(),
+        instruction::initialize_multisig(pid, a, &[b, c], 1).unwrap(),
+        instruction::transfer_batch(pid, a, b, &[], &[(c, 1), (d, 2)]).unwrap(),
+        instruction::create_metadata(pid, a, b, c, d, &[], "Name", "SYM", "uri").unwrap(),
+        instruction::update_metadata(pid, a, b, &[], "Name", "SYM", "uri").unwrap(),
+        instruction::initialize_transfer_fee_config(pid, a, Some(b), 50, 10).unwrap(),
+        instruction::harvest_withheld_tokens(pid, a, &[b, c]).unwrap(),
+        instruction::withdraw_withheld_tokens(pid, a, b, c, &[]).unwrap(),
+        instruction::initialize_non_transferable_mint(pid, a).unwrap(),
+        instruction::lock_account(pid, a, b, &[], c, 1).unwrap(),
+        instruction::initialize_escrow(pid, a, b, c, d, e, 1, 2).unwrap(),
+        instruction::exchange(pid, a, b, c, d, e, f, g, 2, 3).unwrap(),
+        instruction::cancel_escrow(pid, a, b, c, d).unwrap(),
+        instruction::initialize_pause_config(pid, a, b).unwrap(),
+        instruction::pause(pid, a, b, &[]).unwrap(),
+        instruction::resume(pid, a, b, &[]).unwrap(),
+        instruction::initialize_permanent_delegate(pid, a, b).unwrap(),
+        instruction::propose_owner(pid, a, b, &[], c).unwrap(),
+        instruction::accept_owner(pid, a, b, &[]).unwrap(),
+        instruction::cancel_pending_owner(pid, a, b, &[]).unwrap(),
+        instruction::get_account_balance(pid, a).unwrap(),
+        instruction::get_mint_supply(pid, a).unwrap(),
+        instruction::amount_to_ui_amount(pid, a, 1).unwrap(),
+        instruction::ui_amount_to_amount(pid, a, "1.5").unwrap(),
+        instruction::require_memo(pid, a, b, &[], c).unwrap(),
+        instruction::unrequire_memo(pid, a, b, &[]).unwrap(),
+        instruction::initialize_transfer_hook(pid, a, b).unwrap(),
+        instruction::initialize_default_account_state(pid, a, AccountState::Frozen).unwrap(),
+        instruction::set_default_account_state(pid, a, b, &[], AccountState::Initialized).unwrap(),
+        instruction::initialize_immutable_owner(pid, a, b).unwrap(),
+        instruction::initialize_interest_bearing_mint(pid, a, Some(b), -5).unwrap(),
+        instruction::update_rate(pid, a, b, &[], 5).unwrap(),
+        instruction::initialize_mint_close_authority(pid, a, Some(b)).unwrap(),
+        instruction::close_mint(pid, a, b, c, &[]).unwrap(),
+        instruction::migrate_account(pid, a, b).unwrap(),
+        instruction::initialize_royalty(pid, a, b, 100, c).unwrap(),
+        instruction::initialize_allowlist(pid, a, b, c).unwrap(),
+        instruction::add_to_allowlist(pid, a, b, &[], c, d).unwrap(),
+        instruction::remove_from_allowlist(pid, a, b, &[], c).unwrap(),
+        instruction::initialize_min_transfer_amount(pid, a, b, 10).unwrap(),
+        instruction::update_min_transfer_amount(pid, a, b, &[], 10).unwrap(),
+        instruction::transfer_account_ownership(pid, a, b, c, &[]).unwrap(),
+        instruction::create_distribution(pid, a, b, c, d, [1; 32], 100).unwrap(),
+        instruction::claim(pid, a, b, c, d, 0, 10, &[[2; 32], [3; 32]]).unwrap(),
+        instruction::initialize_mint2(pid, a, b, Some(c), 6, Some(1_000)).unwrap(),
+        instruction::initialize_account3(pid, a, b, c).unwrap(),
+        instruction::initialize_multisig2(pid, a, &[b, c], 1).unwrap(),
+        instruction::freeze_batch(pid, a, b, &[], &[c, d]).unwrap(),
+        instruction::initialize_mint_with_allocations(pid, a, b, c, None, 6, Some(10), &[(d, 5)])
+            .unwrap(),
+        instruction::initialize_activity_tracking(pid, a, b).unwrap(),
+        instruction::transfer_with_fee(pid, a, b, c, d, &[], 5, 1).unwrap(),
+        instruction::create_seeded_token_account(pid, a, b, c, 7).unwrap(),
+        instruction::initialize_transfer_cooldown(pid, a, b, 8).unwrap(),
+        instruction::initialize_holder_count(pid, a, b).unwrap(),
+        instruction::permit_transfer(pid, a, b, c).unwrap(),
+        instruction::create_vesting(pid, a, b, c, d, &[], 1, 2, 3).unwrap(),
+        instruction::claim_vested(pid, a, b).unwrap(),
+        instruction::repair_owner(pid, a, b, c, &[], d).unwrap(),
+        instruction::recover_nested(pid, a, b, c).unwrap(),
+        instruction::two_party_swap(pid, a, b, c, d, e, f, 1, 2).unwrap(),
+        instruction::create_associated_account_idempotent(pid, a, b, c).unwrap(),
+        instruction::initialize_recovery_authority(pid, a, b, c).unwrap(),
+        instruction::initialize_metadata_pointer(pid, a, b, Some(c), None).unwrap(),
+        instruction::update_metadata_pointer(pid, a, b, &[], Some(c)).unwrap(),
+        instruction::create_converter(pid, a, b, c, 1_000, 1).unwrap(),
+        instruction::convert(pid, a, b, c, d, e, f, 7).unwrap(),
+        instruction::set_minimum_balance(pid, a, b, &[], c, 500, d, None).unwrap(),
+        instruction::snapshot_balance(pid, a, b, 9).unwrap(),
+        instruction::close_snapshot(pid, a, b, c).unwrap(),
+        instruction::transfer_with_relayer_fee(pid, a, b, c, d, e, &[], 5, 1).unwrap(),
+        instruction::verify_ownership(pid, a, b, &[], 3).unwrap(),
+        instruction::set_outflow_limit(pid, a, b, &[], c, 100, 10).unwrap(),
+        instruction::add_minter(pid, a, b, &[], c, d, 1_000, 100).unwrap(),
+        instruction::remove_minter(pid, a, b, &[], c).unwrap(),
+    ]
+}
+
+/// Reads one value of `ty` from the front of `data`.
+fn skip_arg<'a>(ty: &IdlType, data: &'a [u8]) -> &'a [u8] {
+    match ty {
+        IdlType::U8 | IdlType::Defined(_) => &data[1..],
+        IdlType::U16 | IdlType::I16 => &data[2..],
+        IdlType::U64 | IdlType::I64 => &data[8..],
+        IdlType::PublicKey => &data[32..],
+        IdlType::String => {
+            let len = u32::from_le_bytes(data[..4].try_into().unwrap()) as usize;
+            &data[4 + len..]
+        }
+        IdlType::Option(inner) => match data[0] {
+            0 => &data[1..],
+            1 => skip_arg(inner, &data[1..]),
+            tag => panic!("invalid option tag {tag}"),
+        },
+        IdlType::Vec(inner) => (0..data[0]).fold(&data[1..], |rest, _| skip_arg(inner, rest)),
+        IdlType::Array(inner, len) => (0..*len).fold(data, |rest, _| skip_arg(inner, rest)),
+    }
+}
+
+#[test]
+fn idl_describes_every_builder() {
+    let samples = samples();
+    assert_eq!(samples.len(), idl::instructions().len());
+    for (sample, entry) in samples.iter().zip(idl::instructions()) {
+        let decoded = TokenInstruction::unpack(&sample.data).unwrap();
+        assert_eq!(idl::instruction(&decoded), entry);
+        assert_eq!(sample.data[0], entry.discriminant, "{}", entry.name);
+
+        let rest = entry
+            .args
+            .iter()
+            .fold(&sample.data[1..], |rest, arg| skip_arg(&arg.ty, rest));
+        assert!(rest.is_empty(), "{} args don't cover its data", entry.name);
+
+        assert!(
+            sample.accounts.len() >= entry.accounts.len(),
+            "{} has fewer accounts than described",
+            entry.name
+        );
+        for (meta, account) in sample.accounts.iter().zip(entry.accounts) {
+            assert_eq!(
+                (meta.is_writable, meta.is_signer),
+                (account.is_mut, account.is_signer),
+                "{}.{}",
+                entry.name,
+                account.name
+            );
+        }
+        if sample.accounts.len() > entry.accounts.len() {
+            assert!(
+                entry.remaining_accounts.is_some(),
+                "{} passes undescribed accounts",
+                entry.name
+            );
+        }
+    }
+}
+
+#[test]
+fn discriminants_are_dense_and_names_unique() {
+    let instructions = idl::instructions();
+    for (i, entry) in instructions.iter().enumerate() {
+        assert_eq!(entry.discriminant as usize, i);
+        assert!(instructions[..i]
+            .iter()
+            .all(|other| other.name != entry.name));
+    }
+    assert!(TokenInstruction::unpack(&[instructions.len() as u8]).is_err());
+}
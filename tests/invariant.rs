@@ -0,0 +1,416 @@
+//! Applies random sequences of instructions to a small bank of fixture
+//! accounts and checks after every step that each mint's supply still
+//! accounts for every token its accounts hold.
+//!
+//! A failing case prints the instructions applied up to the violation, in
+//! order, so that it can be replayed by hand.
+
+use proptest::{collection::vec, prelude::*};
+use solana_contracts::{
+    instruction,
+    invariant::Ledger,
+    process_instruction,
+    state::TransferFee,
+    test_utils::{account_infos, AccountFixture},
+    AccountState, Mint, Token,
+};
+use solana_program::{
+    entrypoint::ProgramResult, instruction::Instruction, program_option::COption,
+    program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+
+const DECIMALS: u8 = 2;
+const NUM_MINTS: usize = 2;
+const NUM_OWNERS: usize = 3;
+const NUM_ACCOUNTS: usize = NUM_MINTS * NUM_OWNERS;
+
+fn program_id() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+/// The first mint is plain; the second withholds a transfer fee.
+fn mint_key(mint: usize) -> Pubkey {
+    Pubkey::new_from_array([1 + mint as u8; 32])
+}
+
+fn authority() -> Pubkey {
+    Pubkey::new_from_array([9; 32])
+}
+
+fn owner_key(owner: usize) -> Pubkey {
+    Pubkey::new_from_array([10 + owner as u8; 32])
+}
+
+/// Token account `account` belongs to mint `account % NUM_MINTS`.
+fn account_key(account: usize) -> Pubkey {
+    Pubkey::new_from_array([20 + account as u8; 32])
+}
+
+fn mint_of(account: usize) -> usize {
+    account % NUM_MINTS
+}
+
+fn owner_of(account: usize) -> usize {
+    account / NUM_MINTS
+}
+
+fn bank() -> Vec<AccountFixture> {
+    let mut accounts = Vec::new();
+    for mint in 0..NUM_MINTS {
+        let has_fee = mint == 1;
+        let state = Mint {
+            mint_authority: COption::Some(authority()),
+            decimals: DECIMALS,
+            is_initialized: true,
+            freeze_authority: COption::Some(authority()),
+            transfer_fee_authority: if has_fee {
+                COption::Some(authority())
+            } else {
+                COption::None
+            },
+            transfer_fee: if has_fee {
+                COption::Some(TransferFee {
+                    basis_points: 250,
+                    maximum_fee: 40,
+                })
+            } else {
+                COption::None
+            },
+            ..Mint::default()
+        };
+        accounts.push(
+            AccountFixture::new(mint_key(mint))
+                .owner(program_id())
+                .lamports(Rent::default().minimum_balance(Mint::LEN))
+                .state(&state),
+        );
+    }
+    for account in 0..NUM_ACCOUNTS {
+        let state = Token {
+            mint: mint_key(mint_of(account)),
+            owner: owner_key(owner_of(account)),
+            state: AccountState::Initialized,
+            withheld_amount: if mint_of(account) == 1 {
+                COption::Some(0)
+            } else {
+                COption::None
+            },
+            ..Token::default()
+        };
+        accounts.push(
+            AccountFixture::new(account_key(account))
+                .owner(program_id())
+                .lamports(Rent::default().minimum_balance(Token::LEN))
+                .state(&state),
+        );
+    }
+    accounts.push(AccountFixture::new(authority()).signer());
+    for owner in 0..NUM_OWNERS {
+        accounts.push(AccountFixture::new(owner_key(owner)).signer());
+    }
+    accounts
+}
+
+/// An instruction over the bank, by account and owner index.
+#[derive(Clone, Debug)]
+enum Op {
+    MintTo {
+        account: usize,
+        amount: u64,
+    },
+    Burn {
+        account: usize,
+        amount: u64,
+    },
+    Transfer {
+        from: usize,
+        to: usize,
+        amount: u64,
+    },
+    TransferChecked {
+        from: usize,
+        to: usize,
+        amount: u64,
+    },
+    TransferBatch {
+        from: usize,
+        to: Vec<(usize, u64)>,
+    },
+    Approve {
+        account: usize,
+        delegate: usize,
+        amount: u64,
+    },
+    DelegatedTransfer {
+        from: usize,
+        to: usize,
+        delegate: usize,
+        amount: u64,
+    },
+    Freeze {
+        account: usize,
+    },
+    Thaw {
+        account: usize,
+    },
+    Harvest {
+        accounts: Vec<usize>,
+    },
+    WithdrawWithheld {
+        to: usize,
+    },
+}
+
+impl Op {
+    fn instruction(&self) -> Instruction {
+        let program_id = &program_id();
+        let mint = |account: usize| mint_key(mint_of(account));
+        let owner = |account: usize| owner_key(owner_of(account));
+        match self {
+            Op::MintTo { account, amount } => instruction::mint_tokens(
+                program_id,
+                &mint(*account),
+                &account_key(*account),
+                &authority(),
+                &[],
+                *amount,
+            ),
+            Op::Burn { account, amount } => instruction::burn(
+                program_id,
+                &account_key(*account),
+                &mint(*account),
+                &owner(*account),
+                &[],
+                *amount,
+            ),
+            Op::Transfer { from, to, amount } => instruction::transfer_tokens(
+                program_id,
+                &account_key(*from),
+                &account_key(*to),
+                &owner(*from),
+                &[],
+                *amount,
+            ),
+            Op::TransferChecked { from, to, amount } => instruction::transfer_checked(
+                program_id,
+                &account_key(*from),
+                &mint(*from),
+                &account_key(*to),
+                &owner(*from),
+                &[],
+                *amount,
+                DECIMALS,
+            ),
+            Op::TransferBatch { from, to } => {
+                let keys: Vec<Pubkey> = to.iter().map(|(to, _)| account_key(*to)).collect();
+                let transfers: Vec<(&Pubkey, u64)> = keys
+                    .iter()
+                    .zip(to)
+                    .map(|(key, (_, amount))| (key, *amount))
+                    .collect();
+                instruction::transfer_batch(
+                    program_id,
+                    &account_key(*from),
+                    &owner(*from),
+                    &[],
+                    &transfers,
+                )
+            }
+            Op::Approve {
+                account,
+                delegate,
+                amount,
+            } => instruction::approve(
+                program_id,
+                &account_key(*account),
+                &owner_key(*delegate),
+                &owner(*account),
+                &[],
+                *amount,
+            ),
+            Op::DelegatedTransfer {
+                from,
+                to,
+                delegate,
+                amount,
+            } => instruction::transfer_checked(
+                program_id,
+                &account_key(*from),
+                &mint(*from),
+                &account_key(*to),
+                &owner_key(*delegate),
+                &[],
+                *amount,
+                DECIMALS,
+            ),
+            Op::Freeze { account } => instruction::freeze_account(
+                program_id,
+                &account_key(*account),
+                &mint(*account),
+                &authority(),
+                &[],
+            ),
+            Op::Thaw { account } => instruction::thaw_account(
+                program_id,
+                &account_key(*account),
+                &mint(*account),
+                &authority(),
+                &[],
+            ),
+            Op::Harvest { accounts } => {
+                let keys: Vec<Pubkey> = accounts
+                    .iter()
+                    .map(|account| account_key(*account))
+                    .collect();
+                let sources: Vec<&Pubkey> = keys.iter().collect();
+                instruction::harvest_withheld_tokens(program_id, &mint_key(1), &sources)
+            }
+            Op::WithdrawWithheld { to } => instruction::withdraw_withheld_tokens(
+                program_id,
+                &mint_key(1),
+                &account_key(*to),
+                &authority(),
+                &[],
+            ),
+        }
+        .unwrap()
+    }
+}
+
+fn amount() -> impl Strategy<Value = u64> {
+    prop_oneof![4 => 0..2_000u64, 1 => any::<u64>()]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let account = || 0..NUM_ACCOUNTS;
+    let owner = || 0..NUM_OWNERS;
+    prop_oneof![
+        (account(), amount()).prop_map(|(account, amount)| Op::MintTo { account, amount }),
+        (account(), amount()).prop_map(|(account, amount)| Op::Burn { account, amount }),
+        (account(), account(), amount()).prop_map(|(from, to, amount)| Op::Transfer {
+            from,
+            to,
+            amount
+        }),
+        (account(), account(), amount()).prop_map(|(from, to, amount)| Op::TransferChecked {
+            from,
+            to,
+            amount
+        }),
+        (account(), vec((account(), amount()), 1..4))
+            .prop_map(|(from, to)| Op::TransferBatch { from, to }),
+        (account(), owner(), amount()).prop_map(|(account, delegate, amount)| Op::Approve {
+            account,
+            delegate,
+            amount
+        }),
+        (account(), account(), owner(), amount()).prop_map(|(from, to, delegate, amount)| {
+            Op::DelegatedTransfer {
+                from,
+                to,
+                delegate,
+                amount,
+            }
+        }),
+        account().prop_map(|account| Op::Freeze { account }),
+        account().prop_map(|account| Op::Thaw { account }),
+        vec(account(), 0..4).prop_map(|accounts| Op::Harvest { accounts }),
+        account().prop_map(|to| Op::WithdrawWithheld { to }),
+    ]
+}
+
+/// Runs `instruction` over the bank, undoing any partial writes if it fails
+/// as the runtime would.
+fn apply(instruction: &Instruction, bank: &mut Vec<AccountFixture>) -> ProgramResult {
+    let snapshot = bank.clone();
+    let result = {
+        let infos = account_infos(bank);
+        let ordered: Vec<_> = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                infos
+                    .iter()
+                    .find(|info| *info.key == meta.pubkey)
+                    .expect("a fixture for every account")
+                    .clone()
+            })
+            .collect();
+        process_instruction(&instruction.program_id, &ordered, &instruction.data)
+    };
+    if result.is_err() {
+        *bank = snapshot;
+    }
+    result
+}
+
+fn ledger(bank: &mut [AccountFixture]) -> Ledger {
+    Ledger::capture(&program_id(), &account_infos(bank))
+}
+
+proptest! {
+    #[test]
+    fn every_instruction_sequence_conserves_tokens(ops in vec(op(), 1..40)) {
+        let mut bank = bank();
+        for (step, op) in ops.iter().enumerate() {
+            let before = ledger(&mut bank);
+            let _ = apply(&op.instruction(), &mut bank);
+            let after = ledger(&mut bank);
+            if let Err(violation) = before.check(&after) {
+                prop_assert!(
+                    false,
+                    "{}\nafter applying, in order:\n{:#?}",
+                    violation,
+                    &ops[..=step]
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn transfers_move_tokens_without_changing_the_totals() {
+    let mut bank = bank();
+    let start = ledger(&mut bank);
+    for op in [
+        Op::MintTo {
+            account: 1,
+            amount: 1_000,
+        },
+        Op::TransferChecked {
+            from: 1,
+            to: 3,
+            amount: 800,
+        },
+        Op::Harvest {
+            accounts: vec![1, 3],
+        },
+        Op::Burn {
+            account: 3,
+            amount: 100,
+        },
+    ] {
+        apply(&op.instruction(), &mut bank).unwrap();
+    }
+    let end = ledger(&mut bank);
+    assert_eq!(start.check(&end), Ok(()));
+    // 1_000 minted less 100 burned: 200 with Alice, 680 with Bob, 20
+    // harvested to the mint.
+    let token = |account: usize| bank[NUM_MINTS + account].unpack::<Token>().unwrap();
+    assert_eq!(token(1).amount, 200);
+    assert_eq!(token(3).amount, 680);
+    assert_eq!(bank[1].unpack::<Mint>().unwrap().withheld_amount, 20);
+}
+
+#[test]
+fn ledger_flags_tokens_that_appear_without_minting() {
+    let mut bank = bank();
+    let before = ledger(&mut bank);
+    let key = account_key(0);
+    let fixture = bank.iter_mut().find(|fixture| fixture.key == key).unwrap();
+    Token::write_amount(&mut fixture.data, 5).unwrap();
+    let violation = before.check(&ledger(&mut bank)).unwrap_err();
+    assert_eq!(violation.mint, mint_key(0));
+    assert_eq!(violation.supply_delta, 0);
+    assert_eq!(violation.held_delta, 5);
+}
@@ -0,0 +1,140 @@
+use proptest::prelude::*;
+use solana_contracts::{
+    error::TokenError,
+    math::{
+        multiply_by_bps_ceil, multiply_by_bps_floor, scale_by_decimals, PreciseNumber, UiAmount,
+        MAX_BASIS_POINTS,
+    },
+    try_ui_amount_into_amount,
+};
+
+#[test]
+fn zero_bps_is_always_zero() {
+    for amount in [0, 1, 9_999, u64::MAX] {
+        assert_eq!(multiply_by_bps_floor(amount, 0), Ok(0));
+        assert_eq!(multiply_by_bps_ceil(amount, 0), Ok(0));
+    }
+}
+
+#[test]
+fn max_bps_is_the_whole_amount() {
+    for amount in [0, 1, 9_999, u64::MAX] {
+        assert_eq!(multiply_by_bps_floor(amount, MAX_BASIS_POINTS), Ok(amount));
+        assert_eq!(multiply_by_bps_ceil(amount, MAX_BASIS_POINTS), Ok(amount));
+    }
+}
+
+#[test]
+fn u64_max_amounts_do_not_overflow_the_product() {
+    assert_eq!(multiply_by_bps_floor(u64::MAX, 5_000), Ok(u64::MAX / 2));
+    assert_eq!(multiply_by_bps_ceil(u64::MAX, 5_000), Ok(u64::MAX / 2 + 1));
+    assert_eq!(multiply_by_bps_floor(u64::MAX, 1), Ok(u64::MAX / 10_000));
+    assert_eq!(multiply_by_bps_ceil(u64::MAX, 1), Ok(u64::MAX / 10_000 + 1));
+    // Above 100% the result no longer fits.
+    assert_eq!(
+        multiply_by_bps_floor(u64::MAX, MAX_BASIS_POINTS + 1),
+        Err(TokenError::MathOverflow)
+    );
+    assert_eq!(
+        multiply_by_bps_ceil(u64::MAX, u16::MAX),
+        Err(TokenError::MathOverflow)
+    );
+}
+
+#[test]
+fn rounding_directions_on_small_amounts() {
+    // 1 * 1 / 10_000 is a ten-thousandth of a unit.
+    assert_eq!(multiply_by_bps_floor(1, 1), Ok(0));
+    assert_eq!(multiply_by_bps_ceil(1, 1), Ok(1));
+    assert_eq!(multiply_by_bps_floor(7, 3_333), Ok(2));
+    assert_eq!(multiply_by_bps_ceil(7, 3_333), Ok(3));
+    // Exact shares are not rounded either way.
+    assert_eq!(multiply_by_bps_floor(200, 250), Ok(5));
+    assert_eq!(multiply_by_bps_ceil(200, 250), Ok(5));
+}
+
+#[test]
+fn scale_by_decimals_multiplies_and_floors() {
+    assert_eq!(scale_by_decimals(15, 1, 3), Ok(1_500));
+    assert_eq!(scale_by_decimals(1_599, 3, 1), Ok(15));
+    assert_eq!(scale_by_decimals(u64::MAX, 9, 9), Ok(u64::MAX));
+    assert_eq!(
+        scale_by_decimals(u64::MAX, 19, 0),
+        Ok(u64::MAX / 10u64.pow(19))
+    );
+    assert_eq!(
+        scale_by_decimals(u64::MAX, 0, 1),
+        Err(TokenError::MathOverflow)
+    );
+    assert_eq!(scale_by_decimals(0, 0, 255), Err(TokenError::MathOverflow));
+    assert_eq!(scale_by_decimals(u64::MAX, 255, 0), Ok(0));
+}
+
+#[test]
+fn precise_number_checks_every_operation() {
+    let max = PreciseNumber::new(u64::MAX);
+    let product = max.checked_mul(&max).unwrap();
+    assert_eq!(product.value, u64::MAX as u128 * u64::MAX as u128);
+    assert_eq!(product.to_u64(), Err(TokenError::MathOverflow));
+    assert_eq!(
+        product.checked_div_floor(&max).unwrap().to_u64(),
+        Ok(u64::MAX)
+    );
+    assert_eq!(product.checked_mul(&product), Err(TokenError::MathOverflow));
+    assert_eq!(
+        PreciseNumber::new(0).checked_sub(&PreciseNumber::new(1)),
+        Err(TokenError::MathOverflow)
+    );
+    assert_eq!(
+        max.checked_div_floor(&PreciseNumber::new(0)),
+        Err(TokenError::MathOverflow)
+    );
+    assert_eq!(
+        max.checked_div_ceil(&PreciseNumber::new(0)),
+        Err(TokenError::MathOverflow)
+    );
+    assert_eq!(
+        PreciseNumber::new(7)
+            .checked_div_ceil(&PreciseNumber::new(2))
+            .unwrap(),
+        PreciseNumber::new(4)
+    );
+}
+
+#[test]
+fn ui_amounts_place_the_decimal_point_and_trim_zeros() {
+    let ui = |amount, decimals| UiAmount::new(amount, decimals).to_string();
+    assert_eq!(ui(0, 0), "0");
+    assert_eq!(ui(1_000, 0), "1000");
+    assert_eq!(ui(0, 6), "0");
+    assert_eq!(ui(1, 6), "0.000001");
+    assert_eq!(ui(1_000_000, 6), "1");
+    assert_eq!(ui(1_230_000, 6), "1.23");
+    assert_eq!(ui(10_000_001, 6), "10.000001");
+    assert_eq!(ui(1, 9), "0.000000001");
+    assert_eq!(ui(1_500_000_000, 9), "1.5");
+    assert_eq!(ui(20_000_000_000, 9), "20");
+    assert_eq!(ui(u64::MAX, 9), "18446744073.709551615");
+    // Decimals past what a `u128` divisor can hold leave no whole part.
+    assert_eq!(ui(5, 40), format!("0.{}5", "0".repeat(39)));
+}
+
+proptest! {
+    #[test]
+    fn ui_amounts_parse_back(amount in any::<u64>(), decimals in 0..=20u8) {
+        let ui_amount = UiAmount::new(amount, decimals).to_string();
+        prop_assert_eq!(try_ui_amount_into_amount(&ui_amount, decimals), Ok(amount));
+    }
+
+    #[test]
+    fn floor_and_ceil_bracket_the_exact_share(amount in any::<u64>(), bps in 0..=MAX_BASIS_POINTS) {
+        let exact = amount as u128 * bps as u128;
+        let floor = multiply_by_bps_floor(amount, bps).unwrap() as u128;
+        let ceil = multiply_by_bps_ceil(amount, bps).unwrap() as u128;
+        prop_assert!(floor * 10_000 <= exact);
+        prop_assert!(ceil * 10_000 >= exact);
+        prop_assert!(ceil - floor <= 1);
+        prop_assert_eq!(ceil == floor, exact.is_multiple_of(10_000));
+        prop_assert!(ceil <= amount as u128);
+    }
+}
@@ -0,0 +1,83 @@
+use proptest::prelude::*;
+use solana_contracts::permit::{self, Permit, PERMIT_LEN};
+use solana_program::{ed25519_program, program_error::ProgramError, pubkey::Pubkey};
+
+fn sample() -> Permit {
+    Permit {
+        source: Pubkey::new_unique(),
+        destination: Pubkey::new_unique(),
+        amount: 5,
+        expiry_unix: -1,
+        nonce: 9,
+    }
+}
+
+#[test]
+fn permits_round_trip_and_reject_other_lengths() {
+    let permit = sample();
+    let packed = permit.pack();
+    assert_eq!(Permit::unpack(&packed), Ok(permit));
+    assert_eq!(&packed[64..72], &5u64.to_le_bytes());
+    for len in [0, PERMIT_LEN - 1, PERMIT_LEN + 1] {
+        let mut data = packed.to_vec();
+        data.resize(len, 0);
+        assert_eq!(
+            Permit::unpack(&data),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+}
+
+#[test]
+fn built_instructions_yield_their_key_and_message() {
+    let signer = Pubkey::new_unique();
+    let message = sample().pack();
+    let verify = permit::new_ed25519_instruction(&signer, &[3; 64], &message);
+    assert_eq!(verify.program_id, ed25519_program::id());
+    assert!(verify.accounts.is_empty());
+    assert_eq!(
+        permit::verified_message(&verify.program_id, &verify.data),
+        Ok((signer, &message[..]))
+    );
+
+    // Only the ed25519 program's instructions are trusted.
+    assert_eq!(
+        permit::verified_message(&Pubkey::new_unique(), &verify.data),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}
+
+#[test]
+fn unsupported_signature_layouts_are_rejected() {
+    let verify = permit::new_ed25519_instruction(&Pubkey::new_unique(), &[3; 64], &[1, 2, 3]);
+    let rejected = |data: &[u8]| {
+        permit::verified_message(&ed25519_program::id(), data)
+            == Err(ProgramError::InvalidInstructionData)
+    };
+
+    // Several signatures, or none.
+    for count in [0, 2] {
+        let mut data = verify.data.clone();
+        data[0] = count;
+        assert!(rejected(&data));
+    }
+    // A key or message taken from another instruction.
+    for field in [3, 6] {
+        let mut data = verify.data.clone();
+        data[2 + 2 * field..4 + 2 * field].copy_from_slice(&0u16.to_le_bytes());
+        assert!(rejected(&data));
+    }
+    // A message running past the data.
+    let mut data = verify.data.clone();
+    data[12..14].copy_from_slice(&4u16.to_le_bytes());
+    assert!(rejected(&data));
+    assert!(rejected(&verify.data[..verify.data.len() - 1]));
+    assert!(rejected(&verify.data[..15]));
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_ed25519_data_never_panics(data in proptest::collection::vec(any::<u8>(), 0..256)) {
+        let _ = permit::verified_message(&ed25519_program::id(), &data);
+    }
+}
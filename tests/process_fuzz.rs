@@ -0,0 +1,272 @@
+//! Feeds arbitrary instruction data and synthetic accounts straight into the
+//! processor and checks that it only ever returns, never panics.
+//!
+//! This is a host-side counterpart to the `fuzz/` targets, small enough to
+//! run with the rest of the tests. The named tests at the bottom pin the
+//! inputs that used to panic.
+
+use proptest::{collection::vec, prelude::*};
+use solana_contracts::{
+    error::TokenError,
+    idl,
+    instruction::{self, MAX_SIGNERS},
+    process_instruction,
+    test_utils::{account_infos, AccountFixture},
+    AccountState, Escrow, Mint, Multisig, Token, TokenMetadata,
+};
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_option::COption,
+    program_pack::Pack, pubkey::Pubkey,
+};
+
+const NUM_KEYS: usize = 6;
+
+fn program_id() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+fn key(index: usize) -> Pubkey {
+    Pubkey::new_from_array([index as u8 + 1; 32])
+}
+
+/// Account data shaped like one of the program's accounts, either packed
+/// from a valid state or filled with arbitrary bytes of a plausible length.
+fn account_data() -> impl Strategy<Value = Vec<u8>> {
+    let key_index = || 0..NUM_KEYS;
+    prop_oneof![
+        prop_oneof![
+            Just(0),
+            Just(1),
+            Just(Token::LEN),
+            Just(Mint::LEN),
+            Just(Multisig::LEN),
+            Just(TokenMetadata::LEN),
+            Just(Escrow::LEN),
+        ]
+        .prop_flat_map(|len| vec(any::<u8>(), len)),
+        (key_index(), key_index(), any::<u64>(), 0u8..3).prop_map(
+            |(mint, owner, amount, state)| {
+                let token = Token {
+                    mint: key(mint),
+                    owner: key(owner),
+                    amount,
+                    state: match state {
+                        0 => AccountState::Uninitialized,
+                        1 => AccountState::Initialized,
+                        _ => AccountState::Frozen,
+                    },
+                    ..Token::default()
+                };
+                let mut data = vec![0; Token::LEN];
+                token.pack_into_slice(&mut data);
+                data
+            }
+        ),
+        (key_index(), any::<u64>(), any::<u8>(), any::<bool>()).prop_map(
+            |(authority, supply, decimals, is_initialized)| {
+                let mint = Mint {
+                    mint_authority: COption::Some(key(authority)),
+                    supply,
+                    decimals,
+                    is_initialized,
+                    ..Mint::default()
+                };
+                let mut data = vec![0; Mint::LEN];
+                mint.pack_into_slice(&mut data);
+                data
+            }
+        ),
+        (any::<u8>(), any::<u8>(), vec(key_index(), MAX_SIGNERS)).prop_map(|(m, n, signers)| {
+            let mut data = vec![0; Multisig::LEN];
+            data[0] = m;
+            data[1] = n;
+            data[2] = 1;
+            for (chunk, signer) in data[3..].chunks_exact_mut(32).zip(signers) {
+                chunk.copy_from_slice(key(signer).as_ref());
+            }
+            data
+        }),
+    ]
+}
+
+prop_compose! {
+    fn test_account(index: usize)(
+        owner in prop_oneof![Just(program_id()), Just(Pubkey::default()), (0..NUM_KEYS).prop_map(key)],
+        lamports in any::<u64>(),
+        data in account_data(),
+        is_signer in any::<bool>(),
+        is_writable in any::<bool>(),
+    ) -> AccountFixture {
+        AccountFixture {
+            is_signer,
+            is_writable,
+            ..AccountFixture::new(key(index)).owner(owner).lamports(lamports).data(data)
+        }
+    }
+}
+
+fn test_accounts() -> impl Strategy<Value = Vec<AccountFixture>> {
+    (0..NUM_KEYS).map(test_account).collect::<Vec<_>>()
+}
+
+/// Instruction data that mostly starts with a known tag, so decoding gets
+/// past the first byte.
+fn instruction_data() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        vec(any::<u8>(), 0..64),
+        (0u8..48, vec(any::<u8>(), 0..128)).prop_map(|(tag, rest)| {
+            let mut data = vec![tag];
+            data.extend(rest);
+            data
+        }),
+    ]
+}
+
+/// Runs `data` against `accounts` in the order given by `indices`; repeated
+/// indices pass the same account twice, sharing its lamports and data.
+fn process(
+    accounts: &mut [AccountFixture],
+    indices: &[usize],
+    data: &[u8],
+) -> Result<(), ProgramError> {
+    let infos = account_infos(accounts);
+    let ordered: Vec<AccountInfo> = indices
+        .iter()
+        .map(|index| infos[index % infos.len()].clone())
+        .collect();
+    process_instruction(&program_id(), &ordered, data)
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_input_never_panics(
+        mut accounts in test_accounts(),
+        indices in vec(0..NUM_KEYS, 0..16),
+        data in instruction_data(),
+    ) {
+        let _ = process(&mut accounts, &indices, &data);
+    }
+}
+
+fn initialized_token(index: usize, mint: usize, owner: usize, amount: u64) -> AccountFixture {
+    AccountFixture::new(key(index))
+        .owner(program_id())
+        .lamports(1)
+        .state(&Token {
+            mint: key(mint),
+            owner: key(owner),
+            amount,
+            state: AccountState::Initialized,
+            ..Token::default()
+        })
+}
+
+#[test]
+fn empty_instruction_data_is_rejected() {
+    assert_eq!(
+        process(&mut [], &[], &[]),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}
+
+#[test]
+fn unknown_tags_are_rejected() {
+    assert_eq!(
+        process(&mut [], &[], &[instruction::MAX_TAG + 1]),
+        Err(ProgramError::InvalidInstructionData)
+    );
+    assert_eq!(
+        process(&mut [], &[], &[u8::MAX, 0, 0]),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}
+
+#[test]
+fn reserved_tags_are_unsupported() {
+    let unsupported = Err(TokenError::UnsupportedInstruction.into());
+    // The tag after the last one defined.
+    let next_tag = idl::instructions().len() as u8;
+    assert_eq!(process(&mut [], &[], &[next_tag]), unsupported);
+    assert_eq!(
+        process(&mut [], &[], &[instruction::MAX_TAG, 1, 2]),
+        unsupported
+    );
+}
+
+#[test]
+fn short_payloads_of_known_tags_are_rejected() {
+    let data = instruction::TokenInstruction::TransferChecked {
+        amount: 1,
+        decimals: 2,
+    }
+    .pack();
+    for len in 1..data.len() {
+        assert_eq!(
+            process(&mut [], &[], &data[..len]),
+            Err(ProgramError::InvalidInstructionData),
+            "{len} bytes"
+        );
+    }
+}
+
+#[test]
+fn missing_accounts_are_rejected() {
+    let data = instruction::TokenInstruction::TransferTokens { amount: 1 }.pack();
+    assert_eq!(
+        process(&mut [], &[], &data),
+        Err(ProgramError::NotEnoughAccountKeys)
+    );
+}
+
+#[test]
+fn short_account_data_is_rejected() {
+    let mut accounts = [
+        AccountFixture::new(key(0))
+            .owner(program_id())
+            .data(vec![1; Token::LEN - 1]),
+        initialized_token(1, 3, 2, 0),
+        AccountFixture::new(key(2)).signer(),
+    ];
+    let data = instruction::TokenInstruction::TransferTokens { amount: 1 }.pack();
+    assert_eq!(
+        process(&mut accounts, &[0, 1, 2], &data),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+fn duplicate_source_and_destination_leave_balance_unchanged() {
+    let mut accounts = [
+        initialized_token(0, 3, 2, 10),
+        AccountFixture::new(key(2)).signer(),
+    ];
+    let data = instruction::TokenInstruction::TransferTokens { amount: 4 }.pack();
+    let _ = process(&mut accounts, &[0, 0, 1], &data);
+    assert_eq!(Token::unpack(&accounts[0].data).unwrap().amount, 10);
+}
+
+#[test]
+fn multisig_with_too_many_signers_is_rejected() {
+    let mut multisig = vec![0; Multisig::LEN];
+    multisig[0] = 1;
+    multisig[1] = MAX_SIGNERS as u8 + 1;
+    multisig[2] = 1;
+    assert_eq!(
+        Multisig::unpack(&multisig),
+        Err(ProgramError::InvalidAccountData)
+    );
+
+    let mut accounts = [
+        initialized_token(0, 3, 2, 10),
+        initialized_token(1, 3, 4, 0),
+        AccountFixture::new(key(2))
+            .owner(program_id())
+            .data(multisig),
+        AccountFixture::new(key(4)).signer(),
+    ];
+    let data = instruction::TokenInstruction::TransferTokens { amount: 1 }.pack();
+    assert_eq!(
+        process(&mut accounts, &[0, 1, 2, 3], &data),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
@@ -0,0 +1,1755 @@
+//! Drives individual processor paths directly over account fixtures, without
+//! a bank.
+
+use solana_contracts::{
+    associated::get_associated_token_address,
+    error::TokenError,
+    extension::{RecoveryAuthority, Royalty},
+    instruction::{
+        self, AuthorityType, MAX_ALLOCATIONS, MAX_FREEZE_BATCH, MAX_SIGNERS, MAX_TRANSFER_BATCH,
+    },
+    process_instruction,
+    processor::Processor,
+    state::{
+        AccountType, Allowlist, BalanceSnapshot, ClaimStatus, Converter, Distributor,
+        VestingSchedule,
+    },
+    test_utils::{account_infos, AccountFixture},
+    AccountState, Escrow, Mint, Multisig, Token, TokenMetadata,
+};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+};
+
+const DECIMALS: u8 = 2;
+
+fn program_id() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+fn key(seed: u8) -> Pubkey {
+    Pubkey::new_from_array([seed; 32])
+}
+
+/// Runs `instruction`, passing for each of its account metas the fixture
+/// with the same key. Signer flags come from the fixtures, so a test can
+/// withhold a signature the builder asked for.
+fn process(instruction: &Instruction, accounts: &mut [AccountFixture]) -> ProgramResult {
+    let infos = account_infos(accounts);
+    let ordered: Vec<AccountInfo> = instruction
+        .accounts
+        .iter()
+        .map(|meta| {
+            infos
+                .iter()
+                .find(|info| *info.key == meta.pubkey)
+                .expect("a fixture for every account")
+                .clone()
+        })
+        .collect();
+    process_instruction(&instruction.program_id, &ordered, &instruction.data)
+}
+
+fn rent_exempt(len: usize) -> u64 {
+    Rent::default().minimum_balance(len)
+}
+
+fn mint_fixture(key: Pubkey, mint: Mint) -> AccountFixture {
+    AccountFixture::new(key)
+        .owner(program_id())
+        .lamports(rent_exempt(Mint::LEN))
+        .state(&mint)
+}
+
+fn token_fixture(key: Pubkey, token: Token) -> AccountFixture {
+    AccountFixture::new(key)
+        .owner(program_id())
+        .lamports(rent_exempt(Token::LEN))
+        .state(&token)
+}
+
+/// Keys shared by the fixtures below: a mint with authority `authority`,
+/// and two of its accounts owned by `alice` and `bob`.
+struct Keys {
+    mint: Pubkey,
+    authority: Pubkey,
+    alice: Pubkey,
+    alice_account: Pubkey,
+    bob: Pubkey,
+    bob_account: Pubkey,
+}
+
+const KEYS: Keys = Keys {
+    mint: Pubkey::new_from_array([1; 32]),
+    authority: Pubkey::new_from_array([2; 32]),
+    alice: Pubkey::new_from_array([3; 32]),
+    alice_account: Pubkey::new_from_array([4; 32]),
+    bob: Pubkey::new_from_array([5; 32]),
+    bob_account: Pubkey::new_from_array([6; 32]),
+};
+
+fn mint(supply: u64) -> Mint {
+    Mint {
+        mint_authority: COption::Some(KEYS.authority),
+        supply,
+        decimals: DECIMALS,
+        is_initialized: true,
+        ..Mint::default()
+    }
+}
+
+fn token(owner: Pubkey, amount: u64) -> Token {
+    Token {
+        mint: KEYS.mint,
+        owner,
+        amount,
+        state: AccountState::Initialized,
+        ..Token::default()
+    }
+}
+
+/// A mint with `supply` held entirely by Alice, an empty account for Bob,
+/// and the three owners as signers.
+fn fixtures(supply: u64) -> Vec<AccountFixture> {
+    vec![
+        mint_fixture(KEYS.mint, mint(supply)),
+        token_fixture(KEYS.alice_account, token(KEYS.alice, supply)),
+        token_fixture(KEYS.bob_account, token(KEYS.bob, 0)),
+        AccountFixture::new(KEYS.authority).signer(),
+        AccountFixture::new(KEYS.alice).signer(),
+        AccountFixture::new(KEYS.bob).signer(),
+    ]
+}
+
+fn find(accounts: &[AccountFixture], key: Pubkey) -> &AccountFixture {
+    accounts.iter().find(|account| account.key == key).unwrap()
+}
+
+fn find_mut(accounts: &mut [AccountFixture], key: Pubkey) -> &mut AccountFixture {
+    accounts
+        .iter_mut()
+        .find(|account| account.key == key)
+        .unwrap()
+}
+
+fn balance(accounts: &[AccountFixture], key: Pubkey) -> u64 {
+    find(accounts, key).unpack::<Token>().unwrap().amount
+}
+
+fn supply(accounts: &[AccountFixture]) -> u64 {
+    find(accounts, KEYS.mint).unpack::<Mint>().unwrap().supply
+}
+
+fn initialize_mint_ix(max_supply: Option<u64>) -> Instruction {
+    instruction::initialize_mint(
+        &program_id(),
+        &KEYS.mint,
+        &KEYS.authority,
+        None,
+        DECIMALS,
+        max_supply,
+    )
+    .unwrap()
+}
+
+fn mint_ix(amount: u64) -> Instruction {
+    instruction::mint_tokens(
+        &program_id(),
+        &KEYS.mint,
+        &KEYS.bob_account,
+        &KEYS.authority,
+        &[],
+        amount,
+    )
+    .unwrap()
+}
+
+fn transfer_ix(amount: u64) -> Instruction {
+    instruction::transfer_tokens(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.bob_account,
+        &KEYS.alice,
+        &[],
+        amount,
+    )
+    .unwrap()
+}
+
+#[test]
+fn initialize_mint_sets_authority_and_decimals() {
+    let mut accounts = vec![
+        AccountFixture::new(KEYS.mint)
+            .owner(program_id())
+            .lamports(rent_exempt(Mint::LEN))
+            .data_len(Mint::LEN),
+        AccountFixture::sysvar(&Rent::default()),
+    ];
+    process(&initialize_mint_ix(Some(100)), &mut accounts).unwrap();
+
+    let mint: Mint = accounts[0].unpack().unwrap();
+    assert_eq!(mint.mint_authority, COption::Some(KEYS.authority));
+    assert_eq!(mint.decimals, DECIMALS);
+    assert_eq!(mint.supply, 0);
+    assert_eq!(mint.max_supply, COption::Some(100));
+
+    assert_eq!(
+        process(&initialize_mint_ix(None), &mut accounts),
+        Err(ProgramError::AccountAlreadyInitialized)
+    );
+}
+
+#[test]
+fn initialize_mint_requires_rent_exemption_and_program_ownership() {
+    let mut accounts = vec![
+        AccountFixture::new(KEYS.mint)
+            .owner(program_id())
+            .lamports(rent_exempt(Mint::LEN) - 1)
+            .data_len(Mint::LEN),
+        AccountFixture::sysvar(&Rent::default()),
+    ];
+    assert_eq!(
+        process(&initialize_mint_ix(None), &mut accounts),
+        Err(TokenError::NotRentExempt.into())
+    );
+
+    accounts[0] = AccountFixture::new(KEYS.mint)
+        .lamports(rent_exempt(Mint::LEN))
+        .data_len(Mint::LEN);
+    assert_eq!(
+        process(&initialize_mint_ix(None), &mut accounts),
+        Err(ProgramError::IncorrectProgramId)
+    );
+}
+
+#[test]
+fn initialize_account_copies_mint_and_owner() {
+    let mut accounts = vec![
+        mint_fixture(KEYS.mint, mint(0)),
+        AccountFixture::new(KEYS.bob_account)
+            .owner(program_id())
+            .lamports(rent_exempt(Token::LEN))
+            .data_len(Token::LEN),
+        AccountFixture::new(KEYS.bob),
+        AccountFixture::sysvar(&Rent::default()),
+    ];
+    let ix =
+        instruction::initialize_account(&program_id(), &KEYS.bob_account, &KEYS.mint, &KEYS.bob)
+            .unwrap();
+    process(&ix, &mut accounts).unwrap();
+
+    let token: Token = accounts[1].unpack().unwrap();
+    assert_eq!(token, self::token(KEYS.bob, 0));
+}
+
+#[test]
+fn mint_to_credits_account_and_supply() {
+    let mut accounts = fixtures(0);
+    process(&mint_ix(42), &mut accounts).unwrap();
+    assert_eq!(balance(&accounts, KEYS.bob_account), 42);
+    assert_eq!(supply(&accounts), 42);
+}
+
+#[test]
+fn mint_to_checks_authority() {
+    let mut accounts = fixtures(0);
+    let ix = instruction::mint_tokens(
+        &program_id(),
+        &KEYS.mint,
+        &KEYS.bob_account,
+        &KEYS.bob,
+        &[],
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::OwnerMismatch.into())
+    );
+
+    find_mut(&mut accounts, KEYS.authority).is_signer = false;
+    assert_eq!(
+        process(&mint_ix(1), &mut accounts),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+    assert_eq!(supply(&accounts), 0);
+}
+
+#[test]
+fn mint_to_rejects_fixed_supply_and_cap() {
+    let mut accounts = fixtures(0);
+    *find_mut(&mut accounts, KEYS.mint) = mint_fixture(
+        KEYS.mint,
+        Mint {
+            max_supply: COption::Some(10),
+            ..mint(0)
+        },
+    );
+    assert_eq!(
+        process(&mint_ix(11), &mut accounts),
+        Err(TokenError::SupplyCapExceeded.into())
+    );
+    process(&mint_ix(10), &mut accounts).unwrap();
+
+    *find_mut(&mut accounts, KEYS.mint) = mint_fixture(
+        KEYS.mint,
+        Mint {
+            mint_authority: COption::None,
+            ..mint(10)
+        },
+    );
+    assert_eq!(
+        process(&mint_ix(1), &mut accounts),
+        Err(TokenError::FixedSupply.into())
+    );
+}
+
+#[test]
+fn mint_to_checked_rejects_wrong_decimals() {
+    let mut accounts = fixtures(0);
+    let ix = instruction::mint_to_checked(
+        &program_id(),
+        &KEYS.mint,
+        &KEYS.bob_account,
+        &KEYS.authority,
+        &[],
+        1,
+        DECIMALS + 1,
+    )
+    .unwrap();
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::MintDecimalsMismatch.into())
+    );
+}
+
+#[test]
+fn mint_to_rejects_frozen_and_foreign_accounts() {
+    let mut accounts = fixtures(0);
+    *find_mut(&mut accounts, KEYS.bob_account) = token_fixture(
+        KEYS.bob_account,
+        Token {
+            state: AccountState::Frozen,
+            ..token(KEYS.bob, 0)
+        },
+    );
+    assert_eq!(
+        process(&mint_ix(1), &mut accounts),
+        Err(TokenError::AccountFrozen.into())
+    );
+
+    *find_mut(&mut accounts, KEYS.bob_account) = token_fixture(
+        KEYS.bob_account,
+        Token {
+            mint: key(9),
+            ..token(KEYS.bob, 0)
+        },
+    );
+    assert_eq!(
+        process(&mint_ix(1), &mut accounts),
+        Err(TokenError::MintMismatch.into())
+    );
+}
+
+/// Initialization is the account's state, not its amount: minting nothing
+/// neither initializes an account nor leaves one open to re-initialization.
+#[test]
+fn zero_amount_mints_cannot_replace_the_owner() {
+    let mut accounts = fixtures(0);
+    *find_mut(&mut accounts, KEYS.bob_account) = AccountFixture::new(KEYS.bob_account)
+        .owner(program_id())
+        .lamports(rent_exempt(Token::LEN))
+        .data_len(Token::LEN);
+    assert_eq!(
+        process(&mint_ix(0), &mut accounts),
+        Err(ProgramError::UninitializedAccount)
+    );
+    assert!(find(&accounts, KEYS.bob_account)
+        .data
+        .iter()
+        .all(|byte| *byte == 0));
+
+    let mut accounts = fixtures(0);
+    process(&mint_ix(0), &mut accounts).unwrap();
+    let bob_account: Token = find(&accounts, KEYS.bob_account).unpack().unwrap();
+    assert_eq!(bob_account, token(KEYS.bob, 0));
+
+    // A second mint, with the same authority, can't claim the account...
+    let other_mint = key(9);
+    accounts.push(mint_fixture(other_mint, mint(0)));
+    let mint_other = instruction::mint_tokens(
+        &program_id(),
+        &other_mint,
+        &KEYS.bob_account,
+        &KEYS.authority,
+        &[],
+        0,
+    )
+    .unwrap();
+    assert_eq!(
+        process(&mint_other, &mut accounts),
+        Err(TokenError::MintMismatch.into())
+    );
+
+    // ...nor can re-initializing it hand it to another owner.
+    accounts.push(AccountFixture::sysvar(&Rent::default()));
+    let reinitialize =
+        instruction::initialize_account(&program_id(), &KEYS.bob_account, &other_mint, &KEYS.alice)
+            .unwrap();
+    assert_eq!(
+        process(&reinitialize, &mut accounts),
+        Err(ProgramError::AccountAlreadyInitialized)
+    );
+    let bob_account: Token = find(&accounts, KEYS.bob_account).unpack().unwrap();
+    assert_eq!(bob_account, token(KEYS.bob, 0));
+}
+
+#[test]
+fn transfer_moves_tokens() {
+    let mut accounts = fixtures(100);
+    process(&transfer_ix(30), &mut accounts).unwrap();
+    assert_eq!(balance(&accounts, KEYS.alice_account), 70);
+    assert_eq!(balance(&accounts, KEYS.bob_account), 30);
+    assert_eq!(supply(&accounts), 100);
+}
+
+#[test]
+fn plain_transfers_change_only_the_two_amounts() {
+    // Built with `bytemuck`, the program moves these amounts in place.
+    let alice = Token {
+        delegate: COption::Some(KEYS.bob),
+        delegated_amount: 10,
+        pending_owner: COption::Some(key(9)),
+        ..token(KEYS.alice, 100)
+    };
+    for (amount, moved) in [(30, 30), (instruction::AMOUNT_ALL, 100)] {
+        let mut accounts = fixtures(100);
+        *find_mut(&mut accounts, KEYS.alice_account) =
+            token_fixture(KEYS.alice_account, alice.clone());
+        process(&transfer_ix(amount), &mut accounts).unwrap();
+        let expected = token_fixture(
+            KEYS.alice_account,
+            Token {
+                amount: 100 - moved,
+                ..alice.clone()
+            },
+        );
+        assert_eq!(find(&accounts, KEYS.alice_account).data, expected.data);
+        let expected = token_fixture(KEYS.bob_account, token(KEYS.bob, moved));
+        assert_eq!(find(&accounts, KEYS.bob_account).data, expected.data);
+    }
+}
+
+#[test]
+fn transfer_rejects_overdraft_and_missing_signature() {
+    let mut accounts = fixtures(100);
+    assert_eq!(
+        process(&transfer_ix(101), &mut accounts),
+        Err(TokenError::InsufficientFunds.into())
+    );
+
+    find_mut(&mut accounts, KEYS.alice).is_signer = false;
+    assert_eq!(
+        process(&transfer_ix(1), &mut accounts),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+    assert_eq!(balance(&accounts, KEYS.alice_account), 100);
+}
+
+#[test]
+fn transfer_rejects_wrong_owner() {
+    let mut accounts = fixtures(100);
+    let ix = instruction::transfer_tokens(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.bob_account,
+        &KEYS.bob,
+        &[],
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::OwnerMismatch.into())
+    );
+}
+
+#[test]
+fn transfer_rejects_frozen_and_mismatched_accounts() {
+    let mut accounts = fixtures(100);
+    *find_mut(&mut accounts, KEYS.bob_account) = token_fixture(
+        KEYS.bob_account,
+        Token {
+            state: AccountState::Frozen,
+            ..token(KEYS.bob, 0)
+        },
+    );
+    assert_eq!(
+        process(&transfer_ix(1), &mut accounts),
+        Err(TokenError::AccountFrozen.into())
+    );
+
+    *find_mut(&mut accounts, KEYS.bob_account) = token_fixture(
+        KEYS.bob_account,
+        Token {
+            mint: key(9),
+            ..token(KEYS.bob, 0)
+        },
+    );
+    assert_eq!(
+        process(&transfer_ix(1), &mut accounts),
+        Err(TokenError::MintMismatch.into())
+    );
+
+    find_mut(&mut accounts, KEYS.bob_account).owner = key(9);
+    assert_eq!(
+        process(&transfer_ix(1), &mut accounts),
+        Err(ProgramError::IncorrectProgramId)
+    );
+}
+
+#[test]
+fn delegate_transfer_spends_allowance() {
+    let mut accounts = fixtures(100);
+    *find_mut(&mut accounts, KEYS.alice_account) = token_fixture(
+        KEYS.alice_account,
+        Token {
+            delegate: COption::Some(KEYS.bob),
+            delegated_amount: 10,
+            ..token(KEYS.alice, 100)
+        },
+    );
+    let ix = |amount| {
+        instruction::transfer_tokens(
+            &program_id(),
+            &KEYS.alice_account,
+            &KEYS.bob_account,
+            &KEYS.bob,
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        process(&ix(11), &mut accounts),
+        Err(TokenError::InsufficientFunds.into())
+    );
+    process(&ix(10), &mut accounts).unwrap();
+
+    let alice: Token = find(&accounts, KEYS.alice_account).unpack().unwrap();
+    assert_eq!(alice.amount, 90);
+    assert_eq!(alice.delegate, COption::None);
+    assert_eq!(alice.delegated_amount, 0);
+}
+
+/// Alice's account of `fixtures(100)`, with Bob as delegate of 10.
+fn delegated_fixtures() -> Vec<AccountFixture> {
+    let mut accounts = fixtures(100);
+    *find_mut(&mut accounts, KEYS.alice_account) = token_fixture(
+        KEYS.alice_account,
+        Token {
+            delegate: COption::Some(KEYS.bob),
+            delegated_amount: 10,
+            ..token(KEYS.alice, 100)
+        },
+    );
+    accounts
+}
+
+fn burn_ix(authority: Pubkey, amount: u64) -> Instruction {
+    instruction::burn(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.mint,
+        &authority,
+        &[],
+        amount,
+    )
+    .unwrap()
+}
+
+#[test]
+fn delegate_burn_spends_allowance() {
+    let mut accounts = delegated_fixtures();
+    process(&burn_ix(KEYS.bob, 4), &mut accounts).unwrap();
+    let alice: Token = find(&accounts, KEYS.alice_account).unpack().unwrap();
+    assert_eq!((alice.amount, alice.delegated_amount), (96, 6));
+    assert_eq!(supply(&accounts), 96);
+
+    assert_eq!(
+        process(&burn_ix(KEYS.bob, 7), &mut accounts),
+        Err(TokenError::InsufficientFunds.into())
+    );
+    assert_eq!(supply(&accounts), 96);
+
+    process(&burn_ix(KEYS.bob, 6), &mut accounts).unwrap();
+    let alice: Token = find(&accounts, KEYS.alice_account).unpack().unwrap();
+    assert_eq!(alice.amount, 90);
+    assert_eq!(alice.delegate, COption::None);
+    assert_eq!(alice.delegated_amount, 0);
+    assert_eq!(supply(&accounts), 90);
+}
+
+#[test]
+fn owner_and_permanent_delegate_burns_leave_the_delegation_alone() {
+    let mut accounts = delegated_fixtures();
+    *find_mut(&mut accounts, KEYS.mint) = mint_fixture(
+        KEYS.mint,
+        Mint {
+            permanent_delegate: COption::Some(KEYS.authority),
+            ..mint(100)
+        },
+    );
+    process(&burn_ix(KEYS.alice, 50), &mut accounts).unwrap();
+    let burn_checked = instruction::burn_checked(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.mint,
+        &KEYS.authority,
+        &[],
+        30,
+        DECIMALS,
+    )
+    .unwrap();
+    process(&burn_checked, &mut accounts).unwrap();
+
+    let alice: Token = find(&accounts, KEYS.alice_account).unpack().unwrap();
+    assert_eq!(alice.amount, 20);
+    assert_eq!(alice.delegate, COption::Some(KEYS.bob));
+    assert_eq!(alice.delegated_amount, 10);
+    assert_eq!(supply(&accounts), 20);
+}
+
+#[test]
+fn transfer_checked_validates_mint() {
+    let mut accounts = fixtures(100);
+    let ix = |decimals| {
+        instruction::transfer_checked(
+            &program_id(),
+            &KEYS.alice_account,
+            &KEYS.mint,
+            &KEYS.bob_account,
+            &KEYS.alice,
+            &[],
+            5,
+            decimals,
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        process(&ix(DECIMALS + 1), &mut accounts),
+        Err(TokenError::MintDecimalsMismatch.into())
+    );
+    process(&ix(DECIMALS), &mut accounts).unwrap();
+    assert_eq!(balance(&accounts, KEYS.bob_account), 5);
+}
+
+#[test]
+fn multisig_owner_needs_m_signatures() {
+    let multisig_key = key(20);
+    let signers = [key(21), key(22), key(23)];
+    let mut multisig = Multisig {
+        m: 2,
+        n: 3,
+        is_initialized: true,
+        signers: [Pubkey::default(); MAX_SIGNERS],
+    };
+    multisig.signers[..3].copy_from_slice(&signers);
+
+    let mut accounts = fixtures(100);
+    *find_mut(&mut accounts, KEYS.alice_account) =
+        token_fixture(KEYS.alice_account, token(multisig_key, 100));
+    accounts.push(
+        AccountFixture::new(multisig_key)
+            .owner(program_id())
+            .state(&multisig),
+    );
+    accounts.extend(signers.iter().map(|signer| AccountFixture::new(*signer)));
+
+    let ix = instruction::transfer_tokens(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.bob_account,
+        &multisig_key,
+        &[&signers[0], &signers[2]],
+        7,
+    )
+    .unwrap();
+    find_mut(&mut accounts, signers[0]).is_signer = true;
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+
+    find_mut(&mut accounts, signers[2]).is_signer = true;
+    process(&ix, &mut accounts).unwrap();
+    assert_eq!(balance(&accounts, KEYS.bob_account), 7);
+}
+
+#[test]
+fn transfers_to_uninitialized_accounts_are_rejected() {
+    let mut accounts = fixtures(100);
+    *find_mut(&mut accounts, KEYS.bob_account) = AccountFixture::new(KEYS.bob_account)
+        .owner(program_id())
+        .lamports(rent_exempt(Token::LEN))
+        .data_len(Token::LEN);
+
+    let transfer_checked = instruction::transfer_checked(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.mint,
+        &KEYS.bob_account,
+        &KEYS.alice,
+        &[],
+        1,
+        DECIMALS,
+    )
+    .unwrap();
+    let transfer_batch = instruction::transfer_batch(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.alice,
+        &[],
+        &[(&KEYS.bob_account, 1)],
+    )
+    .unwrap();
+    for ix in [transfer_ix(1), transfer_checked, transfer_batch, mint_ix(1)] {
+        assert_eq!(
+            process(&ix, &mut accounts),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+    assert_eq!(balance(&accounts, KEYS.alice_account), 100);
+    assert_eq!(supply(&accounts), 100);
+    assert!(find(&accounts, KEYS.bob_account)
+        .data
+        .iter()
+        .all(|byte| *byte == 0));
+}
+
+fn transfer_checked_ix(source: Pubkey, mint: Pubkey, destination: Pubkey) -> Instruction {
+    instruction::transfer_checked(
+        &program_id(),
+        &source,
+        &mint,
+        &destination,
+        &KEYS.alice,
+        &[],
+        100,
+        DECIMALS,
+    )
+    .unwrap()
+}
+
+#[test]
+fn transfer_with_an_account_in_two_roles() {
+    // Passing the source as the destination is a checked no-op.
+    let mut accounts = fixtures(1_000);
+    let ix = transfer_checked_ix(KEYS.alice_account, KEYS.mint, KEYS.alice_account);
+    process(&ix, &mut accounts).unwrap();
+    assert_eq!(balance(&accounts, KEYS.alice_account), 1_000);
+
+    // The mint can't double as either token account.
+    let ix = transfer_checked_ix(KEYS.alice_account, KEYS.mint, KEYS.mint);
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::DuplicateAccount.into())
+    );
+    let ix = transfer_checked_ix(KEYS.mint, KEYS.mint, KEYS.bob_account);
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::InvalidAccountType.into())
+    );
+    // Nor can a token account sign for itself unless it owns itself.
+    let mut ix = transfer_checked_ix(KEYS.alice_account, KEYS.mint, KEYS.bob_account);
+    ix.accounts[3].pubkey = KEYS.alice_account;
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::OwnerMismatch.into())
+    );
+    assert_eq!(balance(&accounts, KEYS.alice_account), 1_000);
+    assert_eq!(balance(&accounts, KEYS.bob_account), 0);
+}
+
+#[test]
+fn royalty_paid_to_the_source_or_destination_is_combined() {
+    for (royalty_destination, alice, bob) in
+        [(KEYS.alice_account, 910, 90), (KEYS.bob_account, 900, 100)]
+    {
+        let mut accounts = fixtures(1_000);
+        let royalty = Royalty {
+            basis_points: 1_000,
+            destination: royalty_destination,
+        };
+        *find_mut(&mut accounts, KEYS.mint) =
+            mint_fixture(KEYS.mint, mint(1_000)).mint_extension(&royalty);
+        let mut ix = transfer_checked_ix(KEYS.alice_account, KEYS.mint, KEYS.bob_account);
+        ix.accounts
+            .push(AccountMeta::new(royalty_destination, false));
+        process(&ix, &mut accounts).unwrap();
+        assert_eq!(balance(&accounts, KEYS.alice_account), alice);
+        assert_eq!(balance(&accounts, KEYS.bob_account), bob);
+    }
+}
+
+#[test]
+fn transfer_batch_combines_repeated_destinations() {
+    let mut accounts = fixtures(100);
+    let ix = instruction::transfer_batch(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.alice,
+        &[],
+        &[
+            (&KEYS.bob_account, 1),
+            (&KEYS.bob_account, 2),
+            (&KEYS.alice_account, 3),
+        ],
+    )
+    .unwrap();
+    process(&ix, &mut accounts).unwrap();
+    assert_eq!(balance(&accounts, KEYS.alice_account), 97);
+    assert_eq!(balance(&accounts, KEYS.bob_account), 3);
+
+    // A delegate's spend repacks the source, which must not drop the credit
+    // it receives back.
+    let mut accounts = fixtures(100);
+    *find_mut(&mut accounts, KEYS.alice_account) = token_fixture(
+        KEYS.alice_account,
+        Token {
+            delegate: COption::Some(KEYS.bob),
+            delegated_amount: 10,
+            ..token(KEYS.alice, 100)
+        },
+    );
+    let ix = instruction::transfer_batch(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.bob,
+        &[],
+        &[(&KEYS.alice_account, 4), (&KEYS.bob_account, 2)],
+    )
+    .unwrap();
+    process(&ix, &mut accounts).unwrap();
+    let alice = find(&accounts, KEYS.alice_account)
+        .unpack::<Token>()
+        .unwrap();
+    assert_eq!(alice.amount, 98);
+    assert_eq!(alice.delegated_amount, 4);
+    assert_eq!(balance(&accounts, KEYS.bob_account), 2);
+}
+
+#[test]
+fn transfer_batch_accounts_must_match_the_amounts() {
+    let mut accounts = fixtures(100);
+    let ix = instruction::transfer_batch(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.alice,
+        &[],
+        &[(&KEYS.bob_account, 1), (&KEYS.bob_account, 2)],
+    )
+    .unwrap();
+
+    let mut short = ix.clone();
+    short.accounts.pop();
+    assert_eq!(
+        process(&short, &mut accounts),
+        Err(ProgramError::NotEnoughAccountKeys)
+    );
+    // Three more accounts may be the instructions sysvar, the mint's
+    // allowlist and the source's enforcement authority; four more can't.
+    let mut long = ix;
+    for _ in 0..4 {
+        long.accounts
+            .push(AccountMeta::new(KEYS.bob_account, false));
+    }
+    assert_eq!(
+        process(&long, &mut accounts),
+        Err(TokenError::TooManyAccounts.into())
+    );
+    assert_eq!(balance(&accounts, KEYS.bob_account), 0);
+
+    assert_eq!(
+        Processor::process_transfer_batch(&program_id(), &[], &[1; MAX_TRANSFER_BATCH + 1]),
+        Err(TokenError::TooManyItems.into())
+    );
+}
+
+#[test]
+fn freeze_batch_accounts_must_match_the_count() {
+    let mut accounts = fixtures(100);
+    *find_mut(&mut accounts, KEYS.mint) = mint_fixture(
+        KEYS.mint,
+        Mint {
+            freeze_authority: COption::Some(KEYS.authority),
+            ..mint(100)
+        },
+    );
+    let ix = |token_accounts: &[&Pubkey]| {
+        instruction::freeze_batch(
+            &program_id(),
+            &KEYS.mint,
+            &KEYS.authority,
+            &[],
+            token_accounts,
+        )
+        .unwrap()
+    };
+
+    let mut short = ix(&[&KEYS.alice_account, &KEYS.bob_account]);
+    short.accounts.pop();
+    assert_eq!(
+        process(&short, &mut accounts),
+        Err(ProgramError::NotEnoughAccountKeys)
+    );
+    let mut long = ix(&[&KEYS.alice_account]);
+    long.accounts
+        .push(AccountMeta::new(KEYS.bob_account, false));
+    assert_eq!(
+        process(&long, &mut accounts),
+        Err(TokenError::TooManyAccounts.into())
+    );
+    for account in [KEYS.alice_account, KEYS.bob_account] {
+        let state = find(&accounts, account).unpack::<Token>().unwrap().state;
+        assert_eq!(state, AccountState::Initialized);
+    }
+
+    // A multisignature authority is followed by at most `MAX_SIGNERS`.
+    let multisig_key = key(20);
+    let signers: Vec<Pubkey> = (0..=MAX_SIGNERS as u8).map(|i| key(30 + i)).collect();
+    let mut multisig = Multisig {
+        m: 1,
+        n: MAX_SIGNERS as u8,
+        is_initialized: true,
+        signers: [Pubkey::default(); MAX_SIGNERS],
+    };
+    multisig.signers.copy_from_slice(&signers[..MAX_SIGNERS]);
+    *find_mut(&mut accounts, KEYS.mint) = mint_fixture(
+        KEYS.mint,
+        Mint {
+            freeze_authority: COption::Some(multisig_key),
+            ..mint(100)
+        },
+    );
+    accounts.push(
+        AccountFixture::new(multisig_key)
+            .owner(program_id())
+            .state(&multisig),
+    );
+    accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountFixture::new(*signer).signer()),
+    );
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+    let ix = |signers: &[&Pubkey]| {
+        instruction::freeze_batch(
+            &program_id(),
+            &KEYS.mint,
+            &multisig_key,
+            signers,
+            &[&KEYS.alice_account],
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        process(&ix(&signer_refs), &mut accounts),
+        Err(TokenError::TooManyAccounts.into())
+    );
+    process(&ix(&signer_refs[..MAX_SIGNERS]), &mut accounts).unwrap();
+    let alice = find(&accounts, KEYS.alice_account)
+        .unpack::<Token>()
+        .unwrap();
+    assert_eq!(alice.state, AccountState::Frozen);
+
+    assert_eq!(
+        Processor::process_freeze_batch(&program_id(), &[], MAX_FREEZE_BATCH + 1),
+        Err(TokenError::TooManyItems.into())
+    );
+}
+
+#[test]
+fn mint_allocations_must_match_the_accounts() {
+    let allocation = get_associated_token_address(&KEYS.alice, &KEYS.mint, &program_id());
+    let mut ix = instruction::initialize_mint_with_allocations(
+        &program_id(),
+        &KEYS.mint,
+        &KEYS.authority,
+        &KEYS.bob,
+        None,
+        DECIMALS,
+        None,
+        &[(&KEYS.alice, 10)],
+    )
+    .unwrap();
+    let mut accounts = vec![
+        AccountFixture::new(KEYS.mint)
+            .owner(program_id())
+            .lamports(rent_exempt(Mint::LEN))
+            .data(vec![0; Mint::LEN]),
+        AccountFixture::new(KEYS.authority).signer(),
+        AccountFixture::new(KEYS.bob).signer(),
+        AccountFixture::new(system_program::id()),
+        AccountFixture::new(allocation),
+        AccountFixture::new(KEYS.alice),
+    ];
+
+    ix.accounts.push(AccountMeta::new(KEYS.alice, false));
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::TooManyAccounts.into())
+    );
+    ix.accounts.truncate(ix.accounts.len() - 2);
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(ProgramError::NotEnoughAccountKeys)
+    );
+    assert_eq!(find(&accounts, KEYS.mint).data, vec![0; Mint::LEN]);
+
+    assert_eq!(
+        Processor::process_initialize_mint_with_allocations(
+            &program_id(),
+            &[],
+            DECIMALS,
+            COption::None,
+            COption::None,
+            &[1; MAX_ALLOCATIONS + 1],
+        ),
+        Err(TokenError::TooManyItems.into())
+    );
+}
+
+/// `fixtures(100)` plus a second mint, of which Bob holds 50 and Alice
+/// none, and a swap of Alice's `amount_a` for Bob's `amount_b`.
+fn swap_fixtures(amount_a: u64, amount_b: u64) -> (Vec<AccountFixture>, Instruction) {
+    let [other_mint, alice_other, bob_other] = [key(40), key(41), key(42)];
+    let mut accounts = fixtures(100);
+    accounts.push(mint_fixture(other_mint, mint(50)));
+    for (account, owner, amount) in [(alice_other, KEYS.alice, 0), (bob_other, KEYS.bob, 50)] {
+        accounts.push(token_fixture(
+            account,
+            Token {
+                mint: other_mint,
+                ..token(owner, amount)
+            },
+        ));
+    }
+    let ix = instruction::two_party_swap(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.bob_account,
+        &KEYS.alice,
+        &bob_other,
+        &alice_other,
+        &KEYS.bob,
+        amount_a,
+        amount_b,
+    )
+    .unwrap();
+    (accounts, ix)
+}
+
+#[test]
+fn two_party_swap_moves_both_legs_or_neither() {
+    let (mut accounts, ix) = swap_fixtures(30, 20);
+    process(&ix, &mut accounts).unwrap();
+    assert_eq!(balance(&accounts, KEYS.alice_account), 70);
+    assert_eq!(balance(&accounts, KEYS.bob_account), 30);
+    assert_eq!(balance(&accounts, key(41)), 20);
+    assert_eq!(balance(&accounts, key(42)), 30);
+
+    // Bob's signature is missing, or his leg overdraws: nothing moves.
+    let (mut accounts, ix) = swap_fixtures(30, 20);
+    find_mut(&mut accounts, KEYS.bob).is_signer = false;
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+    let (mut accounts, ix) = swap_fixtures(30, 51);
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::InsufficientFunds.into())
+    );
+    assert_eq!(balance(&accounts, KEYS.alice_account), 100);
+    assert_eq!(balance(&accounts, key(42)), 50);
+
+    // Each leg stays within one mint.
+    let (mut accounts, mut ix) = swap_fixtures(30, 20);
+    ix.accounts[1].pubkey = key(41);
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::MintMismatch.into())
+    );
+}
+
+#[test]
+fn two_party_swap_sums_accounts_in_both_legs() {
+    // Bob swaps back part of what he receives: his account is the first
+    // leg's destination and the second's source.
+    let (mut accounts, mut ix) = swap_fixtures(30, 10);
+    ix.accounts[3].pubkey = KEYS.bob_account;
+    ix.accounts[4].pubkey = KEYS.alice_account;
+    process(&ix, &mut accounts).unwrap();
+    assert_eq!(balance(&accounts, KEYS.alice_account), 80);
+    assert_eq!(balance(&accounts, KEYS.bob_account), 20);
+
+    // He can't send more than he had plus what he received.
+    let (mut accounts, mut ix) = swap_fixtures(30, 31);
+    ix.accounts[3].pubkey = KEYS.bob_account;
+    ix.accounts[4].pubkey = KEYS.alice_account;
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::InsufficientFunds.into())
+    );
+    assert_eq!(balance(&accounts, KEYS.bob_account), 0);
+}
+
+#[test]
+fn harvest_counts_each_account_once() {
+    let withheld = |owner, amount| Token {
+        withheld_amount: COption::Some(amount),
+        ..token(owner, 0)
+    };
+    let mut accounts = fixtures(0);
+    *find_mut(&mut accounts, KEYS.alice_account) =
+        token_fixture(KEYS.alice_account, withheld(KEYS.alice, 5));
+    *find_mut(&mut accounts, KEYS.bob_account) =
+        token_fixture(KEYS.bob_account, withheld(KEYS.bob, 7));
+
+    let ix = instruction::harvest_withheld_tokens(
+        &program_id(),
+        &KEYS.mint,
+        &[&KEYS.alice_account, &KEYS.mint],
+    )
+    .unwrap();
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::DuplicateAccount.into())
+    );
+
+    let ix = instruction::harvest_withheld_tokens(
+        &program_id(),
+        &KEYS.mint,
+        &[&KEYS.alice_account, &KEYS.bob_account, &KEYS.alice_account],
+    )
+    .unwrap();
+    process(&ix, &mut accounts).unwrap();
+    let mint = find(&accounts, KEYS.mint).unpack::<Mint>().unwrap();
+    assert_eq!(mint.withheld_amount, 12);
+    for key in [KEYS.alice_account, KEYS.bob_account] {
+        let token = find(&accounts, key).unpack::<Token>().unwrap();
+        assert_eq!(token.withheld_amount, COption::Some(0));
+    }
+}
+
+#[test]
+fn handlers_reject_read_only_accounts_they_modify() {
+    let uninitialized_mint = key(20);
+    let uninitialized_account = key(21);
+    let associated_account = get_associated_token_address(&KEYS.bob, &KEYS.mint, &program_id());
+    let pid = &program_id();
+    let cases: Vec<(Instruction, Pubkey)> = vec![
+        (mint_ix(1), KEYS.mint),
+        (mint_ix(1), KEYS.bob_account),
+        (transfer_ix(1), KEYS.alice_account),
+        (transfer_ix(1), KEYS.bob_account),
+        (
+            transfer_checked_ix(KEYS.alice_account, KEYS.mint, KEYS.bob_account),
+            KEYS.bob_account,
+        ),
+        (
+            instruction::transfer_batch(
+                pid,
+                &KEYS.alice_account,
+                &KEYS.alice,
+                &[],
+                &[(&KEYS.bob_account, 1)],
+            )
+            .unwrap(),
+            KEYS.bob_account,
+        ),
+        (
+            instruction::burn(pid, &KEYS.alice_account, &KEYS.mint, &KEYS.alice, &[], 1).unwrap(),
+            KEYS.mint,
+        ),
+        (
+            instruction::approve(pid, &KEYS.alice_account, &KEYS.bob, &KEYS.alice, &[], 1).unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::revoke(pid, &KEYS.alice_account, &KEYS.alice, &[]).unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::close_account(pid, &KEYS.bob_account, &KEYS.bob, &KEYS.bob, &[]).unwrap(),
+            KEYS.bob,
+        ),
+        (
+            instruction::freeze_account(pid, &KEYS.alice_account, &KEYS.mint, &KEYS.authority, &[])
+                .unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::set_authority(
+                pid,
+                &KEYS.mint,
+                None,
+                instruction::AuthorityType::MintTokens,
+                &KEYS.authority,
+                &[],
+            )
+            .unwrap(),
+            KEYS.mint,
+        ),
+        (
+            instruction::propose_owner(pid, &KEYS.alice_account, &KEYS.alice, &[], &KEYS.bob)
+                .unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::initialize_mint(
+                pid,
+                &uninitialized_mint,
+                &KEYS.authority,
+                None,
+                DECIMALS,
+                None,
+            )
+            .unwrap(),
+            uninitialized_mint,
+        ),
+        (
+            instruction::initialize_account(pid, &uninitialized_account, &KEYS.mint, &KEYS.bob)
+                .unwrap(),
+            uninitialized_account,
+        ),
+        (
+            instruction::create_associated_account(pid, &KEYS.authority, &KEYS.bob, &KEYS.mint)
+                .unwrap(),
+            KEYS.authority,
+        ),
+        (
+            instruction::harvest_withheld_tokens(pid, &KEYS.mint, &[&KEYS.bob_account]).unwrap(),
+            KEYS.bob_account,
+        ),
+    ];
+
+    for (ix, read_only) in cases {
+        let mut accounts = fixtures(100);
+        *find_mut(&mut accounts, KEYS.mint) = mint_fixture(
+            KEYS.mint,
+            Mint {
+                freeze_authority: COption::Some(KEYS.authority),
+                ..mint(100)
+            },
+        );
+        accounts.extend([
+            AccountFixture::new(uninitialized_mint)
+                .owner(program_id())
+                .lamports(rent_exempt(Mint::LEN))
+                .data_len(Mint::LEN),
+            AccountFixture::new(uninitialized_account)
+                .owner(program_id())
+                .lamports(rent_exempt(Token::LEN))
+                .data_len(Token::LEN),
+            AccountFixture::new(associated_account),
+            AccountFixture::new(system_program::id()).readonly(),
+            AccountFixture::sysvar(&Rent::default()),
+        ]);
+        let fixture = find_mut(&mut accounts, read_only);
+        *fixture = fixture.clone().readonly();
+        let before = accounts.clone();
+
+        assert_eq!(
+            process(&ix, &mut accounts),
+            Err(TokenError::AccountNotWritable.into()),
+            "{ix:?} with {read_only} read-only"
+        );
+        assert_eq!(accounts, before);
+    }
+}
+
+#[test]
+fn handlers_reject_state_accounts_owned_by_other_programs() {
+    let uninitialized_mint = key(20);
+    let uninitialized_account = key(21);
+    let uninitialized_multisig = key(22);
+    let pid = &program_id();
+    let cases: Vec<(Instruction, Pubkey)> = vec![
+        (mint_ix(1), KEYS.mint),
+        (mint_ix(1), KEYS.bob_account),
+        (transfer_ix(1), KEYS.alice_account),
+        (transfer_ix(1), KEYS.bob_account),
+        (
+            transfer_checked_ix(KEYS.alice_account, KEYS.mint, KEYS.bob_account),
+            KEYS.mint,
+        ),
+        (
+            instruction::transfer_batch(
+                pid,
+                &KEYS.alice_account,
+                &KEYS.alice,
+                &[],
+                &[(&KEYS.bob_account, 1)],
+            )
+            .unwrap(),
+            KEYS.bob_account,
+        ),
+        (
+            instruction::burn(pid, &KEYS.alice_account, &KEYS.mint, &KEYS.alice, &[], 1).unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::burn(pid, &KEYS.alice_account, &KEYS.mint, &KEYS.alice, &[], 1).unwrap(),
+            KEYS.mint,
+        ),
+        (
+            instruction::approve(pid, &KEYS.alice_account, &KEYS.bob, &KEYS.alice, &[], 1).unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::revoke(pid, &KEYS.alice_account, &KEYS.alice, &[]).unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::close_account(pid, &KEYS.bob_account, &KEYS.bob, &KEYS.bob, &[]).unwrap(),
+            KEYS.bob_account,
+        ),
+        (
+            instruction::freeze_account(pid, &KEYS.alice_account, &KEYS.mint, &KEYS.authority, &[])
+                .unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::thaw_account(pid, &KEYS.alice_account, &KEYS.mint, &KEYS.authority, &[])
+                .unwrap(),
+            KEYS.mint,
+        ),
+        (
+            instruction::set_authority(
+                pid,
+                &KEYS.mint,
+                None,
+                instruction::AuthorityType::MintTokens,
+                &KEYS.authority,
+                &[],
+            )
+            .unwrap(),
+            KEYS.mint,
+        ),
+        (
+            instruction::set_authority(
+                pid,
+                &KEYS.alice_account,
+                Some(&KEYS.bob),
+                instruction::AuthorityType::AccountOwner,
+                &KEYS.alice,
+                &[],
+            )
+            .unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::propose_owner(pid, &KEYS.alice_account, &KEYS.alice, &[], &KEYS.bob)
+                .unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::transfer_account_ownership(
+                pid,
+                &KEYS.alice_account,
+                &KEYS.alice,
+                &KEYS.bob,
+                &[],
+            )
+            .unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::get_account_balance(pid, &KEYS.alice_account).unwrap(),
+            KEYS.alice_account,
+        ),
+        (
+            instruction::get_mint_supply(pid, &KEYS.mint).unwrap(),
+            KEYS.mint,
+        ),
+        (
+            instruction::amount_to_ui_amount(pid, &KEYS.mint, 1).unwrap(),
+            KEYS.mint,
+        ),
+        (
+            instruction::harvest_withheld_tokens(pid, &KEYS.mint, &[&KEYS.bob_account]).unwrap(),
+            KEYS.mint,
+        ),
+        (
+            instruction::harvest_withheld_tokens(pid, &KEYS.mint, &[&KEYS.bob_account]).unwrap(),
+            KEYS.bob_account,
+        ),
+        (
+            instruction::initialize_mint(
+                pid,
+                &uninitialized_mint,
+                &KEYS.authority,
+                None,
+                DECIMALS,
+                None,
+            )
+            .unwrap(),
+            uninitialized_mint,
+        ),
+        (
+            instruction::initialize_account(pid, &uninitialized_account, &KEYS.mint, &KEYS.bob)
+                .unwrap(),
+            uninitialized_account,
+        ),
+        (
+            instruction::initialize_account(pid, &uninitialized_account, &KEYS.mint, &KEYS.bob)
+                .unwrap(),
+            KEYS.mint,
+        ),
+        (
+            instruction::initialize_multisig(pid, &uninitialized_multisig, &[&KEYS.alice], 1)
+                .unwrap(),
+            uninitialized_multisig,
+        ),
+    ];
+
+    for (ix, lookalike) in cases {
+        let mut accounts = fixtures(100);
+        *find_mut(&mut accounts, KEYS.mint) = mint_fixture(
+            KEYS.mint,
+            Mint {
+                freeze_authority: COption::Some(KEYS.authority),
+                ..mint(100)
+            },
+        );
+        accounts.extend([
+            AccountFixture::new(uninitialized_mint)
+                .owner(program_id())
+                .lamports(rent_exempt(Mint::LEN))
+                .data_len(Mint::LEN),
+            AccountFixture::new(uninitialized_account)
+                .owner(program_id())
+                .lamports(rent_exempt(Token::LEN))
+                .data_len(Token::LEN),
+            AccountFixture::new(uninitialized_multisig)
+                .owner(program_id())
+                .lamports(rent_exempt(Multisig::LEN))
+                .data_len(Multisig::LEN),
+            AccountFixture::sysvar(&Rent::default()),
+        ]);
+        // Same bytes, but any program could have written them.
+        let fixture = find_mut(&mut accounts, lookalike);
+        *fixture = fixture.clone().owner(system_program::id());
+        let before = accounts.clone();
+
+        assert_eq!(
+            process(&ix, &mut accounts),
+            Err(ProgramError::IncorrectProgramId),
+            "{ix:?} with {lookalike} owned by the system program"
+        );
+        assert_eq!(accounts, before);
+    }
+}
+
+#[test]
+fn transfer_account_ownership_rejects_frozen_accounts() {
+    let mut accounts = fixtures(100);
+    *find_mut(&mut accounts, KEYS.alice_account) = token_fixture(
+        KEYS.alice_account,
+        Token {
+            state: AccountState::Frozen,
+            ..token(KEYS.alice, 100)
+        },
+    );
+    let ix = instruction::transfer_account_ownership(
+        &program_id(),
+        &KEYS.alice_account,
+        &KEYS.alice,
+        &KEYS.bob,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        process(&ix, &mut accounts),
+        Err(TokenError::AccountFrozen.into())
+    );
+    let alice = find(&accounts, KEYS.alice_account)
+        .unpack::<Token>()
+        .unwrap();
+    assert_eq!(alice.owner, KEYS.alice);
+}
+
+fn packed<T: Pack>(state: &T) -> Vec<u8> {
+    let mut data = vec![0; T::LEN];
+    state.pack_into_slice(&mut data);
+    data
+}
+
+/// Initialized data of an account of each type.
+fn account_of_each_type() -> Vec<(AccountType, Vec<u8>)> {
+    let allowlist = Allowlist {
+        is_initialized: true,
+        mint: KEYS.mint,
+        authority: KEYS.authority,
+        owners: vec![KEYS.alice],
+    };
+    let mut allowlist_data = vec![0; Allowlist::len_for(1)];
+    allowlist.pack(&mut allowlist_data).unwrap();
+    vec![
+        (AccountType::Token, packed(&token(KEYS.alice, 100))),
+        (AccountType::Mint, packed(&mint(100))),
+        (
+            AccountType::Multisig,
+            packed(&Multisig {
+                m: 1,
+                n: 1,
+                is_initialized: true,
+                ..Multisig::default()
+            }),
+        ),
+        (
+            AccountType::TokenMetadata,
+            packed(&TokenMetadata {
+                is_initialized: true,
+                mint: KEYS.mint,
+                ..TokenMetadata::default()
+            }),
+        ),
+        (
+            AccountType::Escrow,
+            packed(&Escrow {
+                is_initialized: true,
+                ..Escrow::default()
+            }),
+        ),
+        (
+            AccountType::Distributor,
+            packed(&Distributor {
+                is_initialized: true,
+                mint: KEYS.mint,
+                ..Distributor::default()
+            }),
+        ),
+        (
+            AccountType::ClaimStatus,
+            packed(&ClaimStatus {
+                is_initialized: true,
+                ..ClaimStatus::default()
+            }),
+        ),
+        (
+            AccountType::VestingSchedule,
+            packed(&VestingSchedule {
+                is_initialized: true,
+                mint: KEYS.mint,
+                ..VestingSchedule::default()
+            }),
+        ),
+        (AccountType::Allowlist, allowlist_data),
+        (
+            AccountType::Converter,
+            packed(&Converter {
+                is_initialized: true,
+                from_mint: KEYS.mint,
+                ..Converter::default()
+            }),
+        ),
+        (
+            AccountType::BalanceSnapshot,
+            packed(&BalanceSnapshot {
+                is_initialized: true,
+                token_account: KEYS.alice_account,
+                ..BalanceSnapshot::default()
+            }),
+        ),
+    ]
+}
+
+#[test]
+fn handlers_reject_accounts_of_other_types() {
+    let pid = program_id();
+    let (alice, bob) = (KEYS.alice_account, KEYS.bob_account);
+    // Each instruction, with the index of an account it reads and that
+    // account's type.
+    let slots = [
+        (
+            instruction::transfer_tokens(&pid, &alice, &bob, &KEYS.alice, &[], 1).unwrap(),
+            vec![(0, AccountType::Token), (1, AccountType::Token)],
+        ),
+        (
+            transfer_checked_ix(alice, KEYS.mint, bob),
+            vec![
+                (0, AccountType::Token),
+                (1, AccountType::Mint),
+                (2, AccountType::Token),
+            ],
+        ),
+        (
+            instruction::mint_tokens(&pid, &KEYS.mint, &bob, &KEYS.authority, &[], 1).unwrap(),
+            vec![(0, AccountType::Mint), (1, AccountType::Token)],
+        ),
+        (
+            instruction::burn(&pid, &alice, &KEYS.mint, &KEYS.alice, &[], 1).unwrap(),
+            vec![(0, AccountType::Token), (1, AccountType::Mint)],
+        ),
+        (
+            instruction::approve(&pid, &alice, &KEYS.bob, &KEYS.alice, &[], 1).unwrap(),
+            vec![(0, AccountType::Token)],
+        ),
+        (
+            instruction::revoke(&pid, &alice, &KEYS.alice, &[]).unwrap(),
+            vec![(0, AccountType::Token)],
+        ),
+        (
+            instruction::close_account(&pid, &bob, &KEYS.bob, &KEYS.bob, &[]).unwrap(),
+            vec![(0, AccountType::Token)],
+        ),
+    ];
+    for (ix, expected_types) in slots {
+        for (index, expected) in expected_types {
+            let key = ix.accounts[index].pubkey;
+            for (account_type, data) in account_of_each_type() {
+                if account_type == expected {
+                    continue;
+                }
+                let mut accounts = fixtures(100);
+                *find_mut(&mut accounts, key) = AccountFixture::new(key)
+                    .owner(pid)
+                    .lamports(rent_exempt(data.len()))
+                    .data(data);
+                assert_eq!(
+                    process(&ix, &mut accounts),
+                    Err(TokenError::InvalidAccountType.into()),
+                    "tag {} given a {account_type:?} as account {index}",
+                    ix.data[0]
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn only_thaw_and_resume_accept_the_recovery_authority() {
+    let pid = program_id();
+    let recovery = key(42);
+    let (alice, bob) = (KEYS.alice_account, KEYS.bob_account);
+    let mut accounts = fixtures(100);
+    *find_mut(&mut accounts, KEYS.mint) = mint_fixture(
+        KEYS.mint,
+        Mint {
+            freeze_authority: COption::Some(KEYS.authority),
+            pause_authority: COption::Some(KEYS.authority),
+            close_authority: COption::Some(KEYS.authority),
+            transfer_fee_authority: COption::Some(KEYS.authority),
+            ..mint(100)
+        },
+    )
+    .mint_extension(&RecoveryAuthority {
+        recovery_authority: recovery,
+    });
+    accounts.push(AccountFixture::new(recovery).signer());
+
+    let rejected = vec![
+        instruction::mint_tokens(&pid, &KEYS.mint, &bob, &recovery, &[], 1).unwrap(),
+        instruction::mint_to_checked(&pid, &KEYS.mint, &bob, &recovery, &[], 1, DECIMALS).unwrap(),
+        instruction::transfer_tokens(&pid, &alice, &bob, &recovery, &[], 1).unwrap(),
+        instruction::transfer_checked(&pid, &alice, &KEYS.mint, &bob, &recovery, &[], 1, DECIMALS)
+            .unwrap(),
+        instruction::burn(&pid, &alice, &KEYS.mint, &recovery, &[], 1).unwrap(),
+        instruction::approve(&pid, &alice, &recovery, &recovery, &[], 1).unwrap(),
+        instruction::revoke(&pid, &alice, &recovery, &[]).unwrap(),
+        instruction::close_account(&pid, &bob, &recovery, &recovery, &[]).unwrap(),
+        instruction::set_authority(
+            &pid,
+            &alice,
+            Some(&recovery),
+            AuthorityType::AccountOwner,
+            &recovery,
+            &[],
+        )
+        .unwrap(),
+        instruction::freeze_account(&pid, &alice, &KEYS.mint, &recovery, &[]).unwrap(),
+        instruction::freeze_batch(&pid, &KEYS.mint, &recovery, &[], &[&alice]).unwrap(),
+        instruction::pause(&pid, &KEYS.mint, &recovery, &[]).unwrap(),
+        instruction::set_default_account_state(
+            &pid,
+            &KEYS.mint,
+            &recovery,
+            &[],
+            AccountState::Frozen,
+        )
+        .unwrap(),
+    ]
+    .into_iter()
+    .chain(
+        [
+            AuthorityType::MintTokens,
+            AuthorityType::FreezeAccount,
+            AuthorityType::TransferFeeConfig,
+            AuthorityType::CloseMint,
+        ]
+        .map(|authority_type| {
+            instruction::set_authority(
+                &pid,
+                &KEYS.mint,
+                Some(&recovery),
+                authority_type,
+                &recovery,
+                &[],
+            )
+            .unwrap()
+        }),
+    );
+    let before: Vec<_> = accounts
+        .iter()
+        .map(|account| account.data.clone())
+        .collect();
+    for ix in rejected {
+        assert_eq!(
+            process(&ix, &mut accounts),
+            Err(TokenError::OwnerMismatch.into()),
+            "tag {}",
+            ix.data[0]
+        );
+        let after: Vec<_> = accounts
+            .iter()
+            .map(|account| account.data.clone())
+            .collect();
+        assert_eq!(after, before, "tag {}", ix.data[0]);
+    }
+
+    // What the freeze and pause authorities do, the recovery authority undoes.
+    let freeze = instruction::freeze_account(&pid, &alice, &KEYS.mint, &KEYS.authority, &[]);
+    let pause = instruction::pause(&pid, &KEYS.mint, &KEYS.authority, &[]);
+    process(&freeze.unwrap(), &mut accounts).unwrap();
+    process(&pause.unwrap(), &mut accounts).unwrap();
+    let thaw = instruction::thaw_account(&pid, &alice, &KEYS.mint, &recovery, &[]);
+    let resume = instruction::resume(&pid, &KEYS.mint, &recovery, &[]);
+    process(&thaw.unwrap(), &mut accounts).unwrap();
+    process(&resume.unwrap(), &mut accounts).unwrap();
+    assert_eq!(
+        find(&accounts, alice).unpack::<Token>().unwrap().state,
+        AccountState::Initialized
+    );
+    assert!(!find(&accounts, KEYS.mint).unpack::<Mint>().unwrap().paused);
+
+    // Withholding its signature still fails.
+    let freeze = instruction::freeze_account(&pid, &alice, &KEYS.mint, &KEYS.authority, &[]);
+    process(&freeze.unwrap(), &mut accounts).unwrap();
+    find_mut(&mut accounts, recovery).is_signer = false;
+    let thaw = instruction::thaw_account(&pid, &alice, &KEYS.mint, &recovery, &[]);
+    assert_eq!(
+        process(&thaw.unwrap(), &mut accounts),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+}
@@ -0,0 +1,9552 @@
+use base64::prelude::{Engine, BASE64_STANDARD};
+use proptest::{prelude::*, strategy::ValueTree, test_runner::TestRunner};
+use solana_contracts::{
+    allowlist, amount_to_ui_amount,
+    associated::get_associated_token_address,
+    converter::get_converter_authority,
+    distributor::{self, get_claim_address, get_distributor_authority},
+    error::TokenError,
+    escrow::get_escrow_authority,
+    event::{
+        self, AuthorityChangedEvent, BurnEvent, ClawbackEvent, ConfigChange, ConfigChangedEvent,
+        EventDiscriminator, MintEvent, MinterUsedEvent, OwnershipProvedEvent, RecoveryAction,
+        RecoveryUsedEvent, TokenEvent, TransferEvent,
+    },
+    extension::{
+        self, AccountLock, AllowlistEnforcement, CloseAuthority, Cooldown, Extension,
+        ExtensionType, HolderCount, ImmutableOwner, MemoTransfer, MetadataPointer,
+        MinTransferAmount, MinimumBalance, MintActivity, MintExtension, MintSettings, Minter,
+        Minters, OutflowLimit, PermitNonce, RecoveryAuthority, Royalty, TransferCooldown,
+        VestingReserve,
+    },
+    instruction, interest, memo,
+    metadata::get_metadata_address,
+    native_mint,
+    permit::{self, Permit},
+    process_instruction,
+    seeded::get_seeded_token_address,
+    snapshot::{get_snapshot_address, SNAPSHOT_RETENTION_SLOTS},
+    state::{
+        AccountType, Allowlist, BalanceSnapshot, ClaimStatus, Converter, Distributor, InterestRate,
+        VestingSchedule,
+    },
+    transfer_hook, try_ui_amount_into_amount,
+    vesting::get_vesting_address,
+    AccountState, Escrow, Mint, Multisig, Token, TokenMetadata,
+};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    decode_error::DecodeError,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    program_stubs::{self, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, sysvar,
+};
+use solana_program_test::{processor, BanksClient, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use std::{collections::HashMap, sync::Once};
+
+struct Env {
+    program_id: Pubkey,
+    banks_client: BanksClient,
+    payer: Keypair,
+    recent_blockhash: Hash,
+    context: ProgramTestContext,
+}
+
+async fn start() -> Env {
+    start_with(|_| {}).await
+}
+
+/// Like `start`, letting the caller add programs and accounts first.
+async fn start_with(configure: impl FnOnce(&mut ProgramTest)) -> Env {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new(
+        "solana_contracts",
+        program_id,
+        processor!(process_instruction),
+    );
+    configure(&mut program_test);
+    let context = program_test.start_with_context().await;
+    LOG_DATA_STUBS.call_once(|| {
+        let inner = program_stubs::set_syscall_stubs(Box::new(LogDataStubs(None)));
+        program_stubs::set_syscall_stubs(Box::new(LogDataStubs(Some(inner))));
+    });
+    Env {
+        program_id,
+        banks_client: context.banks_client.clone(),
+        payer: context.payer.insecure_clone(),
+        recent_blockhash: context.last_blockhash,
+        context,
+    }
+}
+
+static LOG_DATA_STUBS: Once = Once::new();
+
+/// Wraps the program-test syscalls, which print `sol_log_data` fields to
+/// stdout, to log them as the runtime does. The line goes through
+/// `sol_log`, so it reads `Program log: Program data: ...`.
+struct LogDataStubs(Option<Box<dyn SyscallStubs>>);
+
+impl LogDataStubs {
+    fn inner(&self) -> &dyn SyscallStubs {
+        self.0.as_deref().unwrap()
+    }
+}
+
+impl SyscallStubs for LogDataStubs {
+    fn sol_log(&self, message: &str) {
+        self.inner().sol_log(message)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields: Vec<_> = fields.iter().map(|f| BASE64_STANDARD.encode(f)).collect();
+        self.inner()
+            .sol_log(&format!("Program data: {}", fields.join(" ")))
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.inner()
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_epoch_rewards_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_last_restart_slot(var_addr)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.inner().sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.inner().sol_set_return_data(data)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.inner().sol_get_stack_height()
+    }
+}
+
+impl Env {
+    async fn process(
+        &mut self,
+        instructions: &[solana_program::instruction::Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.recent_blockhash,
+        );
+        self.banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|e| e.unwrap())
+    }
+
+    /// Processes `instructions` and decodes the events they logged, in order.
+    async fn process_events(
+        &mut self,
+        instructions: &[solana_program::instruction::Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Vec<TokenEvent>, TransactionError> {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.recent_blockhash,
+        );
+        let processed = self
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        processed.result?;
+        Ok(processed
+            .metadata
+            .unwrap()
+            .log_messages
+            .iter()
+            .filter_map(|line| line.strip_prefix("Program log: Program data: "))
+            .map(|data| TokenEvent::unpack(&BASE64_STANDARD.decode(data).unwrap()).unwrap())
+            .collect())
+    }
+
+    /// Processes `instructions` and returns their log messages, with event
+    /// data on lines of its own, as the runtime logs it.
+    async fn process_logs(
+        &mut self,
+        instructions: &[solana_program::instruction::Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Vec<String>, TransactionError> {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.recent_blockhash,
+        );
+        let processed = self
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        processed.result?;
+        Ok(processed
+            .metadata
+            .unwrap()
+            .log_messages
+            .iter()
+            .map(|line| line.replace("Program log: Program data: ", "Program data: "))
+            .collect())
+    }
+
+    /// Simulates `instruction` and decodes the amount it returned.
+    async fn simulate_returned_amount(
+        &mut self,
+        instruction: solana_program::instruction::Instruction,
+    ) -> Result<u64, TransactionError> {
+        let data = self.simulate_return_data(instruction).await?;
+        Ok(instruction::decode_returned_amount(&data).unwrap())
+    }
+
+    /// Simulates `instruction` and returns the data it set.
+    async fn simulate_return_data(
+        &mut self,
+        instruction: solana_program::instruction::Instruction,
+    ) -> Result<Vec<u8>, TransactionError> {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            self.recent_blockhash,
+        );
+        let simulation = self
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        simulation.result.unwrap()?;
+        let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+        assert_eq!(return_data.program_id, self.program_id);
+        Ok(return_data.data)
+    }
+
+    async fn create_account(&mut self, account: &Keypair, len: usize, owner: &Pubkey) {
+        let rent = self.banks_client.get_rent().await.unwrap();
+        let instruction = system_instruction::create_account(
+            &self.payer.pubkey(),
+            &account.pubkey(),
+            rent.minimum_balance(len),
+            len as u64,
+            owner,
+        );
+        self.process(&[instruction], &[account]).await.unwrap();
+    }
+
+    async fn create_mint(&mut self, mint: &Keypair, authority: &Pubkey) {
+        self.create_capped_mint(mint, authority, None).await;
+    }
+
+    async fn create_capped_mint(
+        &mut self,
+        mint: &Keypair,
+        authority: &Pubkey,
+        max_supply: Option<u64>,
+    ) {
+        let program_id = self.program_id;
+        self.create_account(mint, Mint::LEN, &program_id).await;
+        let instruction = instruction::initialize_mint(
+            &program_id,
+            &mint.pubkey(),
+            authority,
+            None,
+            6,
+            max_supply,
+        )
+        .unwrap();
+        self.process(&[instruction], &[]).await.unwrap();
+    }
+
+    async fn create_token_account(&mut self, account: &Keypair, mint: &Pubkey, owner: &Pubkey) {
+        let program_id = self.program_id;
+        self.create_account(account, Token::LEN, &program_id).await;
+        let mut instruction =
+            instruction::initialize_account(&program_id, &account.pubkey(), mint, owner).unwrap();
+        // For mints whose settings grow their accounts.
+        instruction.accounts.extend([
+            AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ]);
+        self.process(&[instruction], &[]).await.unwrap();
+    }
+
+    async fn create_native_account(&mut self, account: &Keypair, owner: &Pubkey, wrapped: u64) {
+        let program_id = self.program_id;
+        let rent = self.banks_client.get_rent().await.unwrap();
+        let create = system_instruction::create_account(
+            &self.payer.pubkey(),
+            &account.pubkey(),
+            rent.minimum_balance(Token::LEN) + wrapped,
+            Token::LEN as u64,
+            &program_id,
+        );
+        let initialize = instruction::initialize_account(
+            &program_id,
+            &account.pubkey(),
+            &native_mint::id(),
+            owner,
+        )
+        .unwrap();
+        self.process(&[create, initialize], &[account])
+            .await
+            .unwrap();
+    }
+
+    async fn set_unix_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock = self.banks_client.get_sysvar::<Clock>().await.unwrap();
+        clock.unix_timestamp = unix_timestamp;
+        self.context.set_sysvar(&clock);
+    }
+
+    async fn get_account(&mut self, pubkey: &Pubkey) -> Account {
+        self.banks_client
+            .get_account(*pubkey)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    async fn get_token(&mut self, pubkey: &Pubkey) -> Token {
+        Token::unpack(&self.get_account(pubkey).await.data).unwrap()
+    }
+
+    async fn get_mint(&mut self, pubkey: &Pubkey) -> Mint {
+        Mint::unpack(&self.get_account(pubkey).await.data).unwrap()
+    }
+
+    async fn get_extension<T: Extension>(&mut self, pubkey: &Pubkey) -> Option<T> {
+        extension::get_extension(&self.get_account(pubkey).await.data).unwrap()
+    }
+
+    async fn get_mint_extension<T: MintExtension>(&mut self, pubkey: &Pubkey) -> Option<T> {
+        extension::get_mint_extension(&self.get_account(pubkey).await.data).unwrap()
+    }
+}
+
+/// Token balances rebuilt from nothing but the events a session logged, to
+/// check that each balance change is logged, with the right fields.
+#[derive(Default)]
+struct EventReplay {
+    balances: HashMap<Pubkey, u64>,
+}
+
+impl EventReplay {
+    /// Processes `instructions` like [`Env::process`], folding the events they
+    /// logged into the replayed balances.
+    async fn process(
+        &mut self,
+        env: &mut Env,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        for event in env.process_events(instructions, signers).await? {
+            self.apply(&event);
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, event: &TokenEvent) {
+        match event {
+            TokenEvent::Mint(event) => self.credit(&event.destination, event.amount),
+            TokenEvent::Transfer(event) => {
+                self.debit(&event.source, event.amount);
+                self.credit(&event.destination, event.amount);
+            }
+            TokenEvent::Burn(event) => self.debit(&event.source, event.amount),
+            // A clawback is also logged as the transfer or burn it made.
+            TokenEvent::Clawback(_)
+            | TokenEvent::AuthorityChanged(_)
+            | TokenEvent::ConfigChanged(_)
+            | TokenEvent::RecoveryUsed(_)
+            | TokenEvent::OwnershipProved(_)
+            | TokenEvent::MinterUsed(_) => {}
+        }
+    }
+
+    fn credit(&mut self, account: &Pubkey, amount: u64) {
+        let balance = self.balances.entry(*account).or_default();
+        *balance = balance.checked_add(amount).unwrap();
+    }
+
+    fn debit(&mut self, account: &Pubkey, amount: u64) {
+        let balance = self.balances.get_mut(account).unwrap_or_else(|| {
+            panic!("{account} was debited {amount} before any event credited it")
+        });
+        *balance = balance.checked_sub(amount).unwrap_or_else(|| {
+            panic!("{account} was debited {amount} past its replayed balance {balance}")
+        });
+    }
+
+    /// Asserts that each of `accounts` holds its replayed balance, or, once
+    /// closed, that its balance replayed to zero.
+    async fn assert_matches(&self, env: &mut Env, accounts: &[Pubkey]) {
+        for account in accounts {
+            let replayed = self.balances.get(account).copied().unwrap_or(0);
+            match env.banks_client.get_account(*account).await.unwrap() {
+                Some(on_chain) => assert_eq!(
+                    Token::unpack(&on_chain.data).unwrap().amount,
+                    replayed,
+                    "balance of {account}"
+                ),
+                None => assert_eq!(replayed, 0, "balance of closed {account}"),
+            }
+        }
+    }
+}
+
+struct Fixture {
+    env: Env,
+    mint: Keypair,
+    mint_authority: Keypair,
+    owner: Keypair,
+    source: Keypair,
+    destination: Keypair,
+}
+
+async fn setup(initial_amount: u64) -> Fixture {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let owner = Keypair::new();
+    let source = Keypair::new();
+    let destination = Keypair::new();
+
+    env.create_mint(&mint, &mint_authority.pubkey()).await;
+    env.create_token_account(&source, &mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_token_account(&destination, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+
+    let instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        initial_amount,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&mint_authority])
+        .await
+        .unwrap();
+
+    Fixture {
+        env,
+        mint,
+        mint_authority,
+        owner,
+        source,
+        destination,
+    }
+}
+
+fn custom(error: TokenError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+}
+
+#[tokio::test]
+async fn mint_and_transfer() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(1_000).await;
+    let program_id = env.program_id;
+
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 1_000);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 1_000);
+
+    let instruction = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        400,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&owner]).await.unwrap();
+
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 600);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 400);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 1_000);
+}
+
+#[tokio::test]
+async fn emptied_accounts_stay_initialized() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        owner,
+        source,
+        destination,
+    } = setup(100).await;
+    let program_id = env.program_id;
+
+    let instruction = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&owner]).await.unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.amount, 0);
+    assert_eq!(token.state, AccountState::Initialized);
+
+    let reinitialize = instruction::initialize_account(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[reinitialize], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+    let instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        5,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&mint_authority])
+        .await
+        .unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.owner, owner.pubkey());
+    assert_eq!(token.amount, 5);
+}
+
+#[tokio::test]
+async fn accounts_sized_at_len_round_trip() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        ..
+    } = setup(1).await;
+
+    let account = env.get_account(&source.pubkey()).await;
+    assert_eq!(account.data.len(), Token::LEN);
+    let token = Token::unpack(&account.data).unwrap();
+    assert_eq!(token.mint, mint.pubkey());
+    assert_eq!(token.owner, owner.pubkey());
+}
+
+#[test]
+fn tokens_pack_into_exactly_len_bytes() {
+    let token = Token {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount: u64::MAX,
+        delegate: COption::Some(Pubkey::new_unique()),
+        delegated_amount: 3,
+        state: AccountState::Frozen,
+        ..Token::default()
+    };
+    let mut data = vec![0; Token::LEN];
+    Token::pack(token.clone(), &mut data).unwrap();
+    assert_eq!(Token::unpack(&data), Ok(token.clone()));
+
+    // Shorter buffers, including the 64 bytes the first layout declared,
+    // are rejected rather than overrun.
+    for len in [0, 64, Token::LEGACY_LEN, Token::LEN - 1] {
+        let mut short = vec![0; len];
+        assert_eq!(
+            Token::pack(token.clone(), &mut short),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert!(Token::unpack(&data[..len]).is_err());
+    }
+    assert!(Token::unpack_any_version(&data[..64]).is_err());
+}
+
+#[tokio::test]
+async fn truncated_token_accounts_fail_without_panicking() {
+    for len in [0, 8, Token::LEN - 1, Token::LEN + 1] {
+        assert_eq!(
+            Token::unpack_from_slice(&vec![0; len]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let account = env.get_account(&source.pubkey()).await;
+    for len in [0, 8, Token::LEN - 1, Token::LEN + 1] {
+        let mut data = account.data.clone();
+        data.resize(len, 0);
+        let truncated = Account {
+            data,
+            ..account.clone()
+        };
+        env.context.set_account(&source.pubkey(), &truncated.into());
+        let instruction = instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            len as u64,
+        )
+        .unwrap();
+        // Each amount differs, so no transaction repeats another.
+        assert_eq!(
+            env.process(&[instruction], &[&owner]).await.unwrap_err(),
+            TransactionError::InstructionError(0, InstructionError::InvalidAccountData),
+            "{len} bytes"
+        );
+    }
+}
+
+#[tokio::test]
+async fn initializers_reject_accounts_that_are_not_rent_exempt() {
+    let Fixture { mut env, mint, .. } = setup(0).await;
+    let program_id = env.program_id;
+    let rent = env.banks_client.get_rent().await.unwrap();
+    let owner = Pubkey::new_unique();
+    let accounts = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let initializers = [
+        instruction::initialize_account(&program_id, &accounts[0].pubkey(), &mint.pubkey(), &owner),
+        instruction::initialize_account2(
+            &program_id,
+            &accounts[1].pubkey(),
+            &mint.pubkey(),
+            &owner,
+        ),
+        instruction::initialize_mint(&program_id, &accounts[2].pubkey(), &owner, None, 6, None),
+    ];
+
+    for ((account, initialize), len) in
+        accounts
+            .iter()
+            .zip(initializers)
+            .zip([Token::LEN, Token::LEN, Mint::LEN])
+    {
+        // The account is initialized in the transaction that funds it, as
+        // the runtime would reject it on its own.
+        let create = system_instruction::create_account(
+            &env.payer.pubkey(),
+            &account.pubkey(),
+            rent.minimum_balance(len) - 1,
+            len as u64,
+            &program_id,
+        );
+        assert_eq!(
+            env.process(&[create, initialize.unwrap()], &[account])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(TokenError::NotRentExempt as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn initializers_without_the_rent_sysvar_match_the_originals() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let authority = Pubkey::new_unique();
+    let freeze_authority = Pubkey::new_unique();
+
+    let mints = [Keypair::new(), Keypair::new()];
+    for mint in &mints {
+        env.create_account(mint, Mint::LEN, &program_id).await;
+    }
+    let initialize_mint = instruction::initialize_mint(
+        &program_id,
+        &mints[0].pubkey(),
+        &authority,
+        Some(&freeze_authority),
+        9,
+        Some(1_000),
+    )
+    .unwrap();
+    let initialize_mint2 = instruction::initialize_mint2(
+        &program_id,
+        &mints[1].pubkey(),
+        &authority,
+        Some(&freeze_authority),
+        9,
+        Some(1_000),
+    )
+    .unwrap();
+    assert_eq!(initialize_mint2.accounts.len(), 1);
+    env.process(&[initialize_mint, initialize_mint2], &[])
+        .await
+        .unwrap();
+    let mint = env.get_account(&mints[0].pubkey()).await.data;
+    assert_eq!(env.get_account(&mints[1].pubkey()).await.data, mint);
+    assert!(Mint::unpack(&mint).unwrap().is_initialized);
+
+    let owner = Pubkey::new_unique();
+    let accounts = [Keypair::new(), Keypair::new(), Keypair::new()];
+    for account in &accounts {
+        env.create_account(account, Token::LEN, &program_id).await;
+    }
+    let mint_pubkey = mints[0].pubkey();
+    let initialize_accounts = [
+        instruction::initialize_account(&program_id, &accounts[0].pubkey(), &mint_pubkey, &owner)
+            .unwrap(),
+        instruction::initialize_account2(&program_id, &accounts[1].pubkey(), &mint_pubkey, &owner)
+            .unwrap(),
+        instruction::initialize_account3(&program_id, &accounts[2].pubkey(), &mint_pubkey, &owner)
+            .unwrap(),
+    ];
+    assert_eq!(initialize_accounts[2].accounts.len(), 2);
+    env.process(&initialize_accounts, &[]).await.unwrap();
+    let token = env.get_account(&accounts[0].pubkey()).await.data;
+    for account in &accounts[1..] {
+        assert_eq!(env.get_account(&account.pubkey()).await.data, token);
+    }
+    assert_eq!(Token::unpack(&token).unwrap().owner, owner);
+
+    let signers = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let signer_refs: Vec<_> = signers.iter().collect();
+    let multisigs = [Keypair::new(), Keypair::new()];
+    for multisig in &multisigs {
+        env.create_account(multisig, Multisig::LEN, &program_id)
+            .await;
+    }
+    let initialize_multisig =
+        instruction::initialize_multisig(&program_id, &multisigs[0].pubkey(), &signer_refs, 2)
+            .unwrap();
+    let initialize_multisig2 =
+        instruction::initialize_multisig2(&program_id, &multisigs[1].pubkey(), &signer_refs, 2)
+            .unwrap();
+    env.process(&[initialize_multisig, initialize_multisig2], &[])
+        .await
+        .unwrap();
+    let multisig = env.get_account(&multisigs[0].pubkey()).await.data;
+    assert_eq!(env.get_account(&multisigs[1].pubkey()).await.data, multisig);
+    assert_eq!(Multisig::unpack(&multisig).unwrap().m, 2);
+}
+
+#[test]
+fn mints_pack_into_exactly_len_bytes() {
+    let mint = Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply: u64::MAX,
+        decimals: 9,
+        is_initialized: true,
+        freeze_authority: COption::Some(Pubkey::new_unique()),
+        ..Mint::default()
+    };
+    let mut data = vec![0; Mint::LEN];
+    Mint::pack(mint.clone(), &mut data).unwrap();
+    assert_eq!(Mint::unpack(&data), Ok(mint.clone()));
+    let mut short = vec![0; Mint::LEN - 1];
+    assert_eq!(
+        Mint::pack(mint, &mut short),
+        Err(ProgramError::InvalidAccountData)
+    );
+    assert!(Mint::unpack(&data[..Mint::LEN - 1]).is_err());
+}
+
+#[tokio::test]
+async fn mints_past_the_maximum_supply_overflow() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        source,
+        destination,
+        ..
+    } = setup(u64::MAX).await;
+    let program_id = env.program_id;
+
+    // The supply overflows even where the destination's balance wouldn't.
+    let instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &destination.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[instruction], &[&mint_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::Overflow)
+    );
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 0);
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, u64::MAX);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, u64::MAX);
+}
+
+#[tokio::test]
+async fn initialize_account_starts_empty_and_runs_once() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        ..
+    } = setup(0).await;
+    let program_id = env.program_id;
+    let account = Keypair::new();
+    let owner = Pubkey::new_unique();
+    env.create_account(&account, Token::LEN, &program_id).await;
+
+    let mint_to = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &account.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(std::slice::from_ref(&mint_to), &[&mint_authority])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::UninitializedAccount)
+    );
+
+    let initialize =
+        instruction::initialize_account(&program_id, &account.pubkey(), &mint.pubkey(), &owner)
+            .unwrap();
+    env.process(&[initialize], &[]).await.unwrap();
+    let token = env.get_token(&account.pubkey()).await;
+    assert_eq!(token.mint, mint.pubkey());
+    assert_eq!(token.owner, owner);
+    assert_eq!(token.amount, 0);
+    assert_eq!(token.state, AccountState::Initialized);
+
+    let reinitialize = instruction::initialize_account(
+        &program_id,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &Pubkey::new_unique(),
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[reinitialize], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    env.recent_blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    env.process(&[mint_to], &[&mint_authority]).await.unwrap();
+    let token = env.get_token(&account.pubkey()).await;
+    assert_eq!(token.owner, owner);
+    assert_eq!(token.amount, 10);
+}
+
+#[tokio::test]
+async fn repeated_mints_accumulate_balance_and_supply() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+
+    for (amount, balance) in [(50, 150), (50, 200), (25, 225)] {
+        let instruction = instruction::mint_tokens(
+            &program_id,
+            &mint.pubkey(),
+            &source.pubkey(),
+            &mint_authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        env.recent_blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+        env.process(&[instruction], &[&mint_authority])
+            .await
+            .unwrap();
+        assert_eq!(env.get_token(&source.pubkey()).await.amount, balance);
+        assert_eq!(env.get_mint(&mint.pubkey()).await.supply, balance);
+    }
+
+    // Minting to a second holder adds to the same supply.
+    let instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &destination.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        75,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&mint_authority])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 75);
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 225);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 300);
+}
+
+#[test]
+fn builders_encode_the_instruction_and_the_processors_account_order() {
+    let program_id = Pubkey::new_unique();
+    let [mint, source, destination, authority, signer] = [(); 5].map(|_| Pubkey::new_unique());
+
+    let mint_to =
+        instruction::mint_tokens(&program_id, &mint, &destination, &authority, &[], 7).unwrap();
+    assert_eq!(mint_to.program_id, program_id);
+    assert_eq!(
+        instruction::TokenInstruction::unpack(&mint_to.data),
+        Ok(instruction::TokenInstruction::MintTokens { amount: 7 })
+    );
+    assert_eq!(
+        mint_to.accounts,
+        [
+            AccountMeta::new(mint, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(authority, true),
+        ]
+    );
+
+    // A multisignature authority doesn't sign; its signers follow it.
+    let transfer = instruction::transfer_tokens(
+        &program_id,
+        &source,
+        &destination,
+        &authority,
+        &[&signer],
+        9,
+    )
+    .unwrap();
+    assert_eq!(
+        instruction::TokenInstruction::unpack(&transfer.data),
+        Ok(instruction::TokenInstruction::TransferTokens { amount: 9 })
+    );
+    assert_eq!(
+        transfer.accounts,
+        [
+            AccountMeta::new(source, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(authority, false),
+            AccountMeta::new_readonly(signer, true),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn empty_and_truncated_instruction_data_is_rejected() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        source,
+        ..
+    } = setup(10).await;
+    let program_id = env.program_id;
+
+    let instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        5,
+    )
+    .unwrap();
+    for len in [0, 1, instruction.data.len() - 1] {
+        let truncated = Instruction {
+            data: instruction.data[..len].to_vec(),
+            ..instruction.clone()
+        };
+        assert_eq!(
+            env.process(&[truncated], &[&mint_authority])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+        );
+    }
+    let mut extended = instruction.clone();
+    extended.data.push(0);
+    assert_eq!(
+        env.process(&[extended], &[&mint_authority])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+
+    env.process(&[instruction], &[&mint_authority])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 15);
+}
+
+#[tokio::test]
+async fn failures_decode_to_their_token_errors() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let other_mint = Keypair::new();
+    let other_account = Keypair::new();
+    env.create_mint(&other_mint, &Pubkey::new_unique()).await;
+    env.create_token_account(&other_account, &other_mint.pubkey(), &owner.pubkey())
+        .await;
+
+    let impostor = Keypair::new();
+    let transfer = |destination: &Keypair, authority: &Keypair, amount| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    let failures = [
+        (
+            transfer(&destination, &owner, 101),
+            &owner,
+            TokenError::InsufficientFunds,
+        ),
+        (
+            transfer(&destination, &impostor, 1),
+            &impostor,
+            TokenError::OwnerMismatch,
+        ),
+        (
+            transfer(&other_account, &owner, 1),
+            &owner,
+            TokenError::MintMismatch,
+        ),
+    ];
+    for (instruction, signer, error) in failures {
+        let code = match env.process(&[instruction], &[signer]).await.unwrap_err() {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            other => panic!("expected {error:?}, got {other:?}"),
+        };
+        let decoded = TokenError::decode_custom_error_to_enum(code);
+        assert_eq!(decoded.as_ref(), Some(&error));
+        assert!(!decoded.unwrap().to_string().is_empty());
+    }
+}
+
+#[tokio::test]
+async fn mints_need_the_mint_authority_to_sign() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        source,
+        ..
+    } = setup(1).await;
+    let program_id = env.program_id;
+
+    let mut instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    instruction.accounts[2].is_signer = false;
+    assert_eq!(
+        env.process(&[instruction], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    let impostor = Keypair::new();
+    let instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &impostor.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[instruction], &[&impostor]).await.unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 1);
+
+    let instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&mint_authority])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 11);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 11);
+}
+
+#[tokio::test]
+async fn transfers_need_the_owner_to_sign() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+
+    let mut instruction = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    instruction.accounts[2].is_signer = false;
+    assert_eq!(
+        env.process(&[instruction], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    let stranger = Keypair::new();
+    let instruction = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &stranger.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[instruction], &[&stranger]).await.unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 100);
+
+    let instruction = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 90);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 10);
+}
+
+#[tokio::test]
+async fn transfer_insufficient_funds_fails() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+
+    let instruction = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        101,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[instruction], &[&owner]).await.unwrap_err(),
+        custom(TokenError::InsufficientFunds)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 100);
+}
+
+#[tokio::test]
+async fn self_transfers_are_checked_no_ops() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let stranger = Keypair::new();
+    let transfer = |authority: &Keypair, amount| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &source.pubkey(),
+            &authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+
+    env.process(&[transfer(&owner, 40)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 100);
+
+    // It still needs the balance and the owner's signature.
+    assert_eq!(
+        env.process(&[transfer(&owner, 101)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::InsufficientFunds)
+    );
+    assert_eq!(
+        env.process(&[transfer(&stranger, 40)], &[&stranger])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    let mut unsigned = transfer(&owner, 40);
+    unsigned.accounts[2].is_signer = false;
+    assert_eq!(
+        env.process(&[unsigned], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 100);
+}
+
+#[tokio::test]
+async fn transfers_that_would_overflow_the_destination_fail() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+
+    // No mint's supply allows balances this large, so write one directly.
+    let mut account = env.get_account(&destination.pubkey()).await;
+    Token::write_amount(&mut account.data, u64::MAX - 5).unwrap();
+    env.context
+        .set_account(&destination.pubkey(), &account.into());
+
+    let transfer = |amount| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        env.process(&[transfer(6)], &[&owner]).await.unwrap_err(),
+        custom(TokenError::Overflow)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 100);
+    assert_eq!(
+        env.get_token(&destination.pubkey()).await.amount,
+        u64::MAX - 5
+    );
+
+    env.process(&[transfer(5)], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, u64::MAX);
+}
+
+#[tokio::test]
+async fn approve_and_revoke_set_and_clear_the_delegate() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let delegate = Keypair::new();
+    let approve = |amount| {
+        instruction::approve(
+            &program_id,
+            &source.pubkey(),
+            &delegate.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    let spend = |amount| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &delegate.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+
+    env.process(&[approve(50)], &[&owner]).await.unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.delegate, COption::Some(delegate.pubkey()));
+    assert_eq!(token.delegated_amount, 50);
+
+    env.process(&[spend(20)], &[&delegate]).await.unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.amount, 80);
+    assert_eq!(token.delegated_amount, 30);
+    assert_eq!(
+        env.process(&[spend(31)], &[&delegate]).await.unwrap_err(),
+        custom(TokenError::InsufficientFunds)
+    );
+    env.process(&[spend(30)], &[&delegate]).await.unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.amount, 50);
+    assert_eq!(token.delegated_amount, 0);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 50);
+
+    env.process(&[approve(40)], &[&owner]).await.unwrap();
+    let revoke = instruction::revoke(&program_id, &source.pubkey(), &owner.pubkey(), &[]).unwrap();
+    env.process(&[revoke], &[&owner]).await.unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.delegate, COption::None);
+    assert_eq!(token.delegated_amount, 0);
+    assert_eq!(
+        env.process(&[spend(1)], &[&delegate]).await.unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+}
+
+#[tokio::test]
+async fn delegations_clear_once_spent_and_survive_owner_transfers() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let delegate = Keypair::new();
+    let transfer = |authority: &Keypair, amount| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    let approve = instruction::approve(
+        &program_id,
+        &source.pubkey(),
+        &delegate.pubkey(),
+        &owner.pubkey(),
+        &[],
+        30,
+    )
+    .unwrap();
+    env.process(&[approve], &[&owner]).await.unwrap();
+
+    // The owner's own transfers leave the delegation alone.
+    env.process(&[transfer(&owner, 10)], &[&owner])
+        .await
+        .unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.delegate, COption::Some(delegate.pubkey()));
+    assert_eq!(token.delegated_amount, 30);
+
+    assert_eq!(
+        env.process(&[transfer(&delegate, 31)], &[&delegate])
+            .await
+            .unwrap_err(),
+        custom(TokenError::InsufficientFunds)
+    );
+    env.process(&[transfer(&delegate, 30)], &[&delegate])
+        .await
+        .unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.amount, 60);
+    assert_eq!(token.delegate, COption::None);
+    assert_eq!(token.delegated_amount, 0);
+    assert_eq!(
+        env.process(&[transfer(&delegate, 1)], &[&delegate])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+}
+
+#[tokio::test]
+async fn transfer_to_account_owned_by_other_program_fails() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+
+    let foreign = Keypair::new();
+    env.create_account(&foreign, Token::LEN, &solana_program::system_program::id())
+        .await;
+
+    let instruction = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &foreign.pubkey(),
+        &owner.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[instruction], &[&owner]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+}
+
+#[tokio::test]
+async fn transfers_need_both_accounts_initialized_for_one_mint() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let uninitialized = Keypair::new();
+    let other_mint = Keypair::new();
+    let other_account = Keypair::new();
+    env.create_account(&uninitialized, Token::LEN, &program_id)
+        .await;
+    env.create_mint(&other_mint, &Pubkey::new_unique()).await;
+    env.create_token_account(&other_account, &other_mint.pubkey(), &owner.pubkey())
+        .await;
+    // A copy of the source that another program owns.
+    let copy = Pubkey::new_unique();
+    let mut foreign = env.get_account(&source.pubkey()).await;
+    foreign.owner = solana_program::system_program::id();
+    env.context.set_account(&copy, &foreign.into());
+
+    let transfer = |source: &Pubkey, destination: &Pubkey| {
+        instruction::transfer_tokens(&program_id, source, destination, &owner.pubkey(), &[], 10)
+            .unwrap()
+    };
+    for (instruction, error) in [
+        (
+            transfer(&source.pubkey(), &uninitialized.pubkey()),
+            InstructionError::UninitializedAccount,
+        ),
+        (
+            transfer(&source.pubkey(), &other_account.pubkey()),
+            InstructionError::Custom(TokenError::MintMismatch as u32),
+        ),
+        (
+            transfer(&copy, &destination.pubkey()),
+            InstructionError::IncorrectProgramId,
+        ),
+    ] {
+        assert_eq!(
+            env.process(&[instruction], &[&owner]).await.unwrap_err(),
+            TransactionError::InstructionError(0, error)
+        );
+    }
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 100);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 0);
+}
+
+#[tokio::test]
+async fn set_authority_rotates_each_authority_and_disables_minting_for_good() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let authority = Keypair::new();
+    let owner = Keypair::new();
+    let account = Keypair::new();
+    let destination = Keypair::new();
+    let new_authority = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &authority.pubkey(),
+        Some(&authority.pubkey()),
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[initialize], &[]).await.unwrap();
+    env.create_token_account(&account, &mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_token_account(&destination, &mint.pubkey(), &owner.pubkey())
+        .await;
+    let set_authority =
+        |owned: &Keypair, authority_type, new_authority: Option<&Pubkey>, current: &Keypair| {
+            instruction::set_authority(
+                &program_id,
+                &owned.pubkey(),
+                new_authority,
+                authority_type,
+                &current.pubkey(),
+                &[],
+            )
+            .unwrap()
+        };
+    let mint_to = |authority: &Keypair| {
+        instruction::mint_tokens(
+            &program_id,
+            &mint.pubkey(),
+            &account.pubkey(),
+            &authority.pubkey(),
+            &[],
+            10,
+        )
+        .unwrap()
+    };
+    let transfer = |authority: &Keypair| {
+        instruction::transfer_tokens(
+            &program_id,
+            &account.pubkey(),
+            &destination.pubkey(),
+            &authority.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap()
+    };
+    let freeze = |authority: &Keypair| {
+        instruction::freeze_account(
+            &program_id,
+            &destination.pubkey(),
+            &mint.pubkey(),
+            &authority.pubkey(),
+            &[],
+        )
+        .unwrap()
+    };
+
+    // Each authority type passes from the old authority to the new one.
+    for (owned, authority_type, current, uses) in [
+        (
+            &mint,
+            instruction::AuthorityType::MintTokens,
+            &authority,
+            &mint_to as &dyn Fn(&Keypair) -> Instruction,
+        ),
+        (
+            &account,
+            instruction::AuthorityType::AccountOwner,
+            &owner,
+            &transfer,
+        ),
+        (
+            &mint,
+            instruction::AuthorityType::FreezeAccount,
+            &authority,
+            &freeze,
+        ),
+    ] {
+        let rotate = set_authority(
+            owned,
+            authority_type,
+            Some(&new_authority.pubkey()),
+            current,
+        );
+        env.process(&[rotate], &[current]).await.unwrap();
+        assert_eq!(
+            env.process(&[uses(current)], &[current]).await.unwrap_err(),
+            custom(TokenError::OwnerMismatch)
+        );
+        env.process(&[uses(&new_authority)], &[&new_authority])
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        env.get_token(&destination.pubkey()).await.state,
+        AccountState::Frozen
+    );
+
+    // Clearing the mint authority fixes the supply, for good.
+    let disable = set_authority(
+        &mint,
+        instruction::AuthorityType::MintTokens,
+        None,
+        &new_authority,
+    );
+    env.process(&[disable], &[&new_authority]).await.unwrap();
+    assert_eq!(
+        env.get_mint(&mint.pubkey()).await.mint_authority,
+        COption::None
+    );
+    env.recent_blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        env.process(&[mint_to(&new_authority)], &[&new_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::FixedSupply)
+    );
+    let restore = set_authority(
+        &mint,
+        instruction::AuthorityType::MintTokens,
+        Some(&new_authority.pubkey()),
+        &new_authority,
+    );
+    assert_eq!(
+        env.process(&[restore], &[&new_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::FixedSupply)
+    );
+}
+
+#[tokio::test]
+async fn transfer_checked_verifies_the_mint_and_decimals() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let other_mint = Keypair::new();
+    env.create_mint(&other_mint, &Pubkey::new_unique()).await;
+    let transfer_checked = |mint: &Keypair, decimals| {
+        instruction::transfer_checked(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            10,
+            decimals,
+        )
+        .unwrap()
+    };
+
+    assert_eq!(
+        env.process(&[transfer_checked(&mint, 9)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::MintDecimalsMismatch)
+    );
+    assert_eq!(
+        env.process(&[transfer_checked(&other_mint, 6)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::MintMismatch)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 100);
+
+    env.process(&[transfer_checked(&mint, 6)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 90);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 10);
+}
+
+#[tokio::test]
+async fn checked_mints_and_burns_verify_decimals() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        owner,
+        source,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let mint_to_checked = |authority: &Keypair, amount, decimals| {
+        instruction::mint_to_checked(
+            &program_id,
+            &mint.pubkey(),
+            &source.pubkey(),
+            &authority.pubkey(),
+            &[],
+            amount,
+            decimals,
+        )
+        .unwrap()
+    };
+    let burn_checked = |authority: &Keypair, amount, decimals| {
+        instruction::burn_checked(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &authority.pubkey(),
+            &[],
+            amount,
+            decimals,
+        )
+        .unwrap()
+    };
+
+    for (instruction, signer) in [
+        (mint_to_checked(&mint_authority, 10, 5), &mint_authority),
+        (burn_checked(&owner, 10, 7), &owner),
+    ] {
+        assert_eq!(
+            env.process(&[instruction], &[signer]).await.unwrap_err(),
+            custom(TokenError::MintDecimalsMismatch)
+        );
+    }
+    // Authority and balance failures match the unchecked instructions'.
+    assert_eq!(
+        env.process(&[mint_to_checked(&owner, 10, 6)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    assert_eq!(
+        env.process(&[burn_checked(&owner, 101, 6)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::InsufficientFunds)
+    );
+
+    env.process(
+        &[mint_to_checked(&mint_authority, 10, 6)],
+        &[&mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 110);
+    env.process(&[burn_checked(&owner, 30, 6)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 80);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 80);
+}
+
+#[tokio::test]
+async fn wrap_transfer_sync_and_unwrap_native() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let owner = Keypair::new();
+    let source = Keypair::new();
+    let destination = Keypair::new();
+    let rent = env.banks_client.get_rent().await.unwrap();
+    let reserve = rent.minimum_balance(Token::LEN);
+
+    env.create_native_account(&source, &owner.pubkey(), 5_000)
+        .await;
+    env.create_native_account(&destination, &Pubkey::new_unique(), 0)
+        .await;
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.mint, native_mint::id());
+    assert_eq!(token.amount, 5_000);
+    assert_eq!(token.is_native, COption::Some(reserve));
+
+    let instruction = instruction::transfer_checked(
+        &program_id,
+        &source.pubkey(),
+        &native_mint::id(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        2_000,
+        native_mint::DECIMALS,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 3_000);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 2_000);
+    assert_eq!(
+        env.get_account(&source.pubkey()).await.lamports,
+        reserve + 3_000
+    );
+    assert_eq!(
+        env.get_account(&destination.pubkey()).await.lamports,
+        reserve + 2_000
+    );
+
+    let deposit = system_instruction::transfer(&env.payer.pubkey(), &source.pubkey(), 700);
+    let sync = instruction::sync_native(&program_id, &source.pubkey()).unwrap();
+    env.process(&[deposit, sync], &[]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 3_700);
+
+    let recipient = Pubkey::new_unique();
+    let instruction = instruction::close_account(
+        &program_id,
+        &source.pubkey(),
+        &recipient,
+        &owner.pubkey(),
+        &[],
+    )
+    .unwrap();
+    env.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(env.get_account(&recipient).await.lamports, reserve + 3_700);
+}
+
+#[tokio::test]
+async fn sync_native_rejects_non_native_account() {
+    let Fixture {
+        mut env, source, ..
+    } = setup(1).await;
+    let program_id = env.program_id;
+
+    let instruction = instruction::sync_native(&program_id, &source.pubkey()).unwrap();
+    assert_eq!(
+        env.process(&[instruction], &[]).await.unwrap_err(),
+        custom(TokenError::InvalidState)
+    );
+}
+
+async fn setup_multisig_mint(m: u8) -> (Env, Keypair, Keypair, Vec<Keypair>) {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let multisig = Keypair::new();
+    let signers = vec![Keypair::new(), Keypair::new(), Keypair::new()];
+    let signer_pubkeys = signers.iter().map(|s| s.pubkey()).collect::<Vec<_>>();
+
+    env.create_account(&multisig, Multisig::LEN, &program_id)
+        .await;
+    let instruction = instruction::initialize_multisig(
+        &program_id,
+        &multisig.pubkey(),
+        &signer_pubkeys.iter().collect::<Vec<_>>(),
+        m,
+    )
+    .unwrap();
+    env.process(&[instruction], &[]).await.unwrap();
+
+    let mint = Keypair::new();
+    env.create_mint(&mint, &multisig.pubkey()).await;
+    (env, multisig, mint, signers)
+}
+
+async fn mint_with_multisig(
+    env: &mut Env,
+    multisig: &Keypair,
+    mint: &Keypair,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let program_id = env.program_id;
+    let account = Keypair::new();
+    env.create_token_account(&account, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    let signer_pubkeys = signers.iter().map(|s| s.pubkey()).collect::<Vec<_>>();
+    let instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &account.pubkey(),
+        &multisig.pubkey(),
+        &signer_pubkeys.iter().collect::<Vec<_>>(),
+        10,
+    )
+    .unwrap();
+    env.process(&[instruction], signers).await
+}
+
+#[tokio::test]
+async fn multisig_accepts_exactly_m_signatures() {
+    let (mut env, multisig, mint, signers) = setup_multisig_mint(2).await;
+
+    let state = Multisig::unpack(&env.get_account(&multisig.pubkey()).await.data).unwrap();
+    assert_eq!((state.m, state.n), (2, 3));
+
+    mint_with_multisig(&mut env, &multisig, &mint, &[&signers[0], &signers[2]])
+        .await
+        .unwrap();
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 10);
+}
+
+#[tokio::test]
+async fn multisig_rejects_fewer_than_m_signatures() {
+    let (mut env, multisig, mint, signers) = setup_multisig_mint(2).await;
+
+    assert_eq!(
+        mint_with_multisig(&mut env, &multisig, &mint, &[&signers[1]])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn multisig_ignores_non_member_and_repeated_signers() {
+    let (mut env, multisig, mint, signers) = setup_multisig_mint(2).await;
+    let outsider = Keypair::new();
+
+    assert_eq!(
+        mint_with_multisig(&mut env, &multisig, &mint, &[&signers[0], &outsider])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    let program_id = env.program_id;
+    let account = Keypair::new();
+    env.create_token_account(&account, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    let signer = signers[0].pubkey();
+    let instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &account.pubkey(),
+        &multisig.pubkey(),
+        &[&signer, &signer],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[instruction], &[&signers[0]])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn initialize_multisig_rejects_m_greater_than_n() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let multisig = Keypair::new();
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    env.create_account(&multisig, Multisig::LEN, &program_id)
+        .await;
+    let mut instruction =
+        instruction::initialize_multisig(&program_id, &multisig.pubkey(), &[&first, &second], 2)
+            .unwrap();
+    instruction.accounts.pop();
+    assert_eq!(
+        env.process(&[instruction], &[]).await.unwrap_err(),
+        custom(TokenError::InvalidNumberOfRequiredSigners)
+    );
+}
+
+#[tokio::test]
+async fn mint_respects_supply_cap() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let owner = Keypair::new();
+    let account = Keypair::new();
+
+    env.create_capped_mint(&mint, &mint_authority.pubkey(), Some(100))
+        .await;
+    env.create_token_account(&account, &mint.pubkey(), &owner.pubkey())
+        .await;
+    let mint_to = |amount| {
+        instruction::mint_tokens(
+            &program_id,
+            &mint.pubkey(),
+            &account.pubkey(),
+            &mint_authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+
+    env.process(&[mint_to(100)], &[&mint_authority])
+        .await
+        .unwrap();
+    let capped = env.get_mint(&mint.pubkey()).await;
+    assert_eq!(capped.supply, 100);
+    assert_eq!(capped.max_supply, COption::Some(100));
+
+    assert_eq!(
+        env.process(&[mint_to(1)], &[&mint_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::SupplyCapExceeded)
+    );
+
+    let burn = instruction::burn(
+        &program_id,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &owner.pubkey(),
+        &[],
+        30,
+    )
+    .unwrap();
+    env.process(&[burn], &[&owner]).await.unwrap();
+    env.process(&[mint_to(30)], &[&mint_authority])
+        .await
+        .unwrap();
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 100);
+    assert_eq!(env.get_token(&account.pubkey()).await.amount, 100);
+}
+
+async fn transfer_batch_to_new_accounts(count: usize) {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        ..
+    } = setup(10_000).await;
+    let program_id = env.program_id;
+
+    let destinations = (0..count).map(|_| Keypair::new()).collect::<Vec<_>>();
+    for destination in destinations.iter() {
+        env.create_token_account(destination, &mint.pubkey(), &Pubkey::new_unique())
+            .await;
+    }
+    let destination_pubkeys = destinations.iter().map(|d| d.pubkey()).collect::<Vec<_>>();
+    let transfers = destination_pubkeys
+        .iter()
+        .enumerate()
+        .map(|(i, pubkey)| (pubkey, 10 * (i as u64 + 1)))
+        .collect::<Vec<_>>();
+
+    let instruction = instruction::transfer_batch(
+        &program_id,
+        &source.pubkey(),
+        &owner.pubkey(),
+        &[],
+        &transfers,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&owner]).await.unwrap();
+
+    let total = transfers.iter().map(|(_, amount)| amount).sum::<u64>();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 10_000 - total);
+    for (pubkey, amount) in transfers {
+        assert_eq!(env.get_token(pubkey).await.amount, amount);
+    }
+}
+
+#[tokio::test]
+async fn transfer_batch_single_destination() {
+    transfer_batch_to_new_accounts(1).await;
+}
+
+#[tokio::test]
+async fn transfer_batch_three_destinations() {
+    transfer_batch_to_new_accounts(3).await;
+}
+
+#[tokio::test]
+async fn transfer_batch_max_destinations() {
+    transfer_batch_to_new_accounts(instruction::MAX_TRANSFER_BATCH).await;
+}
+
+#[tokio::test]
+async fn transfer_batch_count_mismatch_fails() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+
+    let destination_pubkey = destination.pubkey();
+    let mut instruction = instruction::transfer_batch(
+        &program_id,
+        &source.pubkey(),
+        &owner.pubkey(),
+        &[],
+        &[(&destination_pubkey, 10), (&destination_pubkey, 20)],
+    )
+    .unwrap();
+    instruction.accounts.pop();
+    assert_eq!(
+        env.process(&[instruction], &[&owner]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 100);
+}
+
+#[tokio::test]
+async fn transfer_batch_invalid_destination_applies_nothing() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+
+    let other_mint = Keypair::new();
+    let other = Keypair::new();
+    env.create_mint(&other_mint, &Pubkey::new_unique()).await;
+    env.create_token_account(&other, &other_mint.pubkey(), &Pubkey::new_unique())
+        .await;
+
+    let (destination_pubkey, other_pubkey) = (destination.pubkey(), other.pubkey());
+    let instruction = instruction::transfer_batch(
+        &program_id,
+        &source.pubkey(),
+        &owner.pubkey(),
+        &[],
+        &[(&destination_pubkey, 10), (&other_pubkey, 20)],
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[instruction], &[&owner]).await.unwrap_err(),
+        custom(TokenError::MintMismatch)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 100);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 0);
+}
+
+#[tokio::test]
+async fn transfer_amount_all_drains_source() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(750).await;
+    let program_id = env.program_id;
+
+    let instruction = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        instruction::AMOUNT_ALL,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 0);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 750);
+
+    // With nothing left the sentinel is a successful zero-amount transfer.
+    let instruction = instruction::transfer_checked(
+        &program_id,
+        &source.pubkey(),
+        &env.get_token(&source.pubkey()).await.mint,
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        instruction::AMOUNT_ALL,
+        6,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 0);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 750);
+}
+
+#[tokio::test]
+async fn burn_amount_all_burns_balance() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        ..
+    } = setup(300).await;
+    let program_id = env.program_id;
+
+    let instruction = instruction::burn(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &owner.pubkey(),
+        &[],
+        instruction::AMOUNT_ALL,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 0);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 0);
+}
+
+#[tokio::test]
+async fn close_account_moves_the_rent_and_wipes_the_account() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let recipient = Pubkey::new_unique();
+    let close = |account: &Keypair, destination: &Pubkey| {
+        instruction::close_account(
+            &program_id,
+            &account.pubkey(),
+            destination,
+            &owner.pubkey(),
+            &[],
+        )
+        .unwrap()
+    };
+
+    assert_eq!(
+        env.process(&[close(&source, &recipient)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::NonZeroBalance)
+    );
+    let instruction = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(
+        env.process(&[close(&source, &source.pubkey())], &[&owner])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    let rent = env.get_account(&source.pubkey()).await.lamports;
+    env.recent_blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    env.process(&[close(&source, &recipient)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.get_account(&recipient).await.lamports, rent);
+    assert_eq!(
+        env.banks_client.get_account(source.pubkey()).await.unwrap(),
+        None
+    );
+
+    // Frozen accounts can't be closed, even when empty.
+    let freeze_authority = Keypair::new();
+    let frozen_mint = Keypair::new();
+    let frozen = Keypair::new();
+    env.create_account(&frozen_mint, Mint::LEN, &program_id)
+        .await;
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &frozen_mint.pubkey(),
+        &Pubkey::new_unique(),
+        Some(&freeze_authority.pubkey()),
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[initialize], &[]).await.unwrap();
+    env.create_token_account(&frozen, &frozen_mint.pubkey(), &owner.pubkey())
+        .await;
+    let freeze = instruction::freeze_account(
+        &program_id,
+        &frozen.pubkey(),
+        &frozen_mint.pubkey(),
+        &freeze_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    env.process(&[freeze], &[&freeze_authority]).await.unwrap();
+    assert_eq!(
+        env.process(&[close(&frozen, &recipient)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::AccountFrozen)
+    );
+}
+
+#[tokio::test]
+async fn frozen_accounts_cannot_move_tokens_until_thawed() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let authority = Keypair::new();
+    let owner = Keypair::new();
+    let frozen = Keypair::new();
+    let other = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &authority.pubkey(),
+        Some(&authority.pubkey()),
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[initialize], &[]).await.unwrap();
+    for account in [&frozen, &other] {
+        env.create_token_account(account, &mint.pubkey(), &owner.pubkey())
+            .await;
+        let instruction = instruction::mint_tokens(
+            &program_id,
+            &mint.pubkey(),
+            &account.pubkey(),
+            &authority.pubkey(),
+            &[],
+            100,
+        )
+        .unwrap();
+        env.process(&[instruction], &[&authority]).await.unwrap();
+    }
+
+    let stranger = Keypair::new();
+    let freeze = |freeze_authority: &Keypair| {
+        instruction::freeze_account(
+            &program_id,
+            &frozen.pubkey(),
+            &mint.pubkey(),
+            &freeze_authority.pubkey(),
+            &[],
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        env.process(&[freeze(&stranger)], &[&stranger])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    env.process(&[freeze(&authority)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.get_token(&frozen.pubkey()).await.state,
+        AccountState::Frozen
+    );
+
+    let transfer = |source: &Keypair, destination: &Keypair| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            10,
+        )
+        .unwrap()
+    };
+    let burn = instruction::burn(
+        &program_id,
+        &frozen.pubkey(),
+        &mint.pubkey(),
+        &owner.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    for instruction in [transfer(&frozen, &other), transfer(&other, &frozen), burn] {
+        assert_eq!(
+            env.process(&[instruction], &[&owner]).await.unwrap_err(),
+            custom(TokenError::AccountFrozen)
+        );
+    }
+
+    let thaw = instruction::thaw_account(
+        &program_id,
+        &frozen.pubkey(),
+        &mint.pubkey(),
+        &authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    env.process(&[thaw], &[&authority]).await.unwrap();
+    env.recent_blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    env.process(&[transfer(&frozen, &other)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&frozen.pubkey()).await.amount, 90);
+    assert_eq!(env.get_token(&other.pubkey()).await.amount, 110);
+}
+
+#[tokio::test]
+async fn mints_without_a_freeze_authority_cannot_freeze() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        source,
+        ..
+    } = setup(1).await;
+    let program_id = env.program_id;
+
+    let freeze = instruction::freeze_account(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[freeze], &[&mint_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::MintCannotFreeze)
+    );
+    assert_eq!(
+        env.get_token(&source.pubkey()).await.state,
+        AccountState::Initialized
+    );
+}
+
+#[tokio::test]
+async fn burns_partial_and_full_balances_but_not_past_zero() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        ..
+    } = setup(300).await;
+    let program_id = env.program_id;
+    let burn = |amount| {
+        instruction::burn(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+
+    let mut unsigned = burn(100);
+    unsigned.accounts[2].is_signer = false;
+    assert_eq!(
+        env.process(&[unsigned], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    env.process(&[burn(100)], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 200);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 200);
+
+    assert_eq!(
+        env.process(&[burn(201)], &[&owner]).await.unwrap_err(),
+        custom(TokenError::InsufficientFunds)
+    );
+    env.process(&[burn(200)], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 0);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 0);
+
+    assert_eq!(
+        env.process(&[burn(1)], &[&owner]).await.unwrap_err(),
+        custom(TokenError::InsufficientFunds)
+    );
+}
+
+#[test]
+fn metadata_pack_round_trip() {
+    let metadata = TokenMetadata {
+        is_initialized: true,
+        mint: Pubkey::new_unique(),
+        update_authority: Pubkey::new_unique(),
+        name: "Wrapped Ünicode".to_string(),
+        symbol: "WUNI".to_string(),
+        uri: "https://example.com/token.json".to_string(),
+    };
+    let mut data = vec![0; TokenMetadata::LEN];
+    TokenMetadata::pack(metadata.clone(), &mut data).unwrap();
+    assert_eq!(TokenMetadata::unpack(&data).unwrap(), metadata);
+
+    // Corrupt the first name byte into an invalid UTF-8 lead byte.
+    data[69] = 0xff;
+    assert_eq!(
+        TokenMetadata::unpack(&data).unwrap_err(),
+        solana_program::program_error::ProgramError::InvalidAccountData
+    );
+}
+
+#[test]
+fn metadata_longer_than_buffer_is_rejected() {
+    let name = "n".repeat(TokenMetadata::MAX_NAME_LEN + 1);
+    assert_eq!(
+        TokenMetadata::check_lengths(&name, "SYM", "uri"),
+        Err(TokenError::MetadataTooLong)
+    );
+    assert!(instruction::update_metadata(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &[],
+        &name,
+        "SYM",
+        "uri",
+    )
+    .is_err());
+}
+
+#[tokio::test]
+async fn create_and_update_metadata() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        ..
+    } = setup(1).await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let update_authority = Keypair::new();
+
+    let instruction = instruction::create_metadata(
+        &program_id,
+        &payer,
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        &update_authority.pubkey(),
+        &[],
+        "Example",
+        "EXM",
+        "https://example.com/a.json",
+    )
+    .unwrap();
+    env.process(&[instruction], &[&mint_authority])
+        .await
+        .unwrap();
+
+    let address = get_metadata_address(&mint.pubkey(), &program_id);
+    let get_metadata = |account: Account| TokenMetadata::unpack(&account.data).unwrap();
+    let metadata = get_metadata(env.get_account(&address).await);
+    assert_eq!(metadata.mint, mint.pubkey());
+    assert_eq!(metadata.update_authority, update_authority.pubkey());
+    assert_eq!(metadata.name, "Example");
+    assert_eq!(metadata.symbol, "EXM");
+
+    let update = |uri| {
+        instruction::update_metadata(
+            &program_id,
+            &mint.pubkey(),
+            &update_authority.pubkey(),
+            &[],
+            "Example",
+            "EXM",
+            uri,
+        )
+        .unwrap()
+    };
+    env.process(
+        &[update("https://example.com/b.json")],
+        &[&update_authority],
+    )
+    .await
+    .unwrap();
+    let metadata = get_metadata(env.get_account(&address).await);
+    assert_eq!(metadata.uri, "https://example.com/b.json");
+
+    let mut instruction = update("https://example.com/c.json");
+    instruction.accounts[1].pubkey = mint_authority.pubkey();
+    assert_eq!(
+        env.process(&[instruction], &[&mint_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+}
+
+#[tokio::test]
+async fn metadata_pointers_update_clear_and_rule_out_metadata_accounts() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let pointer_authority = Keypair::new();
+    let external = Pubkey::new_unique();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure = instruction::initialize_metadata_pointer(
+        &program_id,
+        &mint.pubkey(),
+        &payer,
+        Some(&pointer_authority.pubkey()),
+        Some(&external),
+    )
+    .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        None,
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure.clone(), initialize], &[])
+        .await
+        .unwrap();
+    let pointer = |metadata_address| {
+        Some(MetadataPointer {
+            authority: COption::Some(pointer_authority.pubkey()),
+            metadata_address,
+        })
+    };
+    assert_eq!(
+        env.get_mint_extension(&mint.pubkey()).await,
+        pointer(COption::Some(external))
+    );
+    assert_eq!(
+        env.process(&[configure], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    // Only the pointer authority repoints it, or clears it.
+    let update = |metadata_address: Option<&Pubkey>, authority: &Keypair| {
+        instruction::update_metadata_pointer(
+            &program_id,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            &[],
+            metadata_address,
+        )
+        .unwrap()
+    };
+    let repointed = Pubkey::new_unique();
+    assert_eq!(
+        env.process(
+            &[update(Some(&repointed), &mint_authority)],
+            &[&mint_authority]
+        )
+        .await
+        .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    env.process(
+        &[update(Some(&repointed), &pointer_authority)],
+        &[&pointer_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.get_mint_extension(&mint.pubkey()).await,
+        pointer(COption::Some(repointed))
+    );
+    env.process(&[update(None, &pointer_authority)], &[&pointer_authority])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.get_mint_extension(&mint.pubkey()).await,
+        pointer(COption::None)
+    );
+
+    // The pointer takes precedence over a metadata account, even cleared.
+    let create_metadata = instruction::create_metadata(
+        &program_id,
+        &payer,
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        &pointer_authority.pubkey(),
+        &[],
+        "Example",
+        "EXM",
+        "https://example.com/a.json",
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[create_metadata], &[&mint_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::MetadataPointerSet)
+    );
+    let address = get_metadata_address(&mint.pubkey(), &program_id);
+    assert!(env
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .is_none());
+
+    // A mint without a pointer has none to update, and a pointer without an
+    // authority is fixed.
+    let plain = Keypair::new();
+    env.create_mint(&plain, &mint_authority.pubkey()).await;
+    let fixed = Keypair::new();
+    env.create_account(&fixed, Mint::LEN, &program_id).await;
+    let configure =
+        instruction::initialize_metadata_pointer(&program_id, &fixed.pubkey(), &payer, None, None)
+            .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &fixed.pubkey(),
+        &mint_authority.pubkey(),
+        None,
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    for (other, error) in [
+        (&plain, TokenError::InvalidState),
+        (&fixed, TokenError::AuthorityTypeNotSupported),
+    ] {
+        let mut instruction = update(Some(&repointed), &mint_authority);
+        instruction.accounts[0].pubkey = other.pubkey();
+        assert_eq!(
+            env.process(&[instruction], &[&mint_authority])
+                .await
+                .unwrap_err(),
+            custom(error)
+        );
+    }
+}
+
+struct FeeFixture {
+    env: Env,
+    mint: Keypair,
+    fee_authority: Keypair,
+    owner: Keypair,
+    source: Keypair,
+    destination: Keypair,
+}
+
+// 1% fee capped at 50 units.
+async fn setup_fee_mint(initial_amount: u64) -> FeeFixture {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let fee_authority = Keypair::new();
+    let owner = Keypair::new();
+    let source = Keypair::new();
+    let destination = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure = instruction::initialize_transfer_fee_config(
+        &program_id,
+        &mint.pubkey(),
+        Some(&fee_authority.pubkey()),
+        100,
+        50,
+    )
+    .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        None,
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    env.create_token_account(&source, &mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_token_account(&destination, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+
+    let instruction = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        initial_amount,
+    )
+    .unwrap();
+    env.process(&[instruction], &[&mint_authority])
+        .await
+        .unwrap();
+
+    FeeFixture {
+        env,
+        mint,
+        fee_authority,
+        owner,
+        source,
+        destination,
+    }
+}
+
+impl FeeFixture {
+    async fn transfer(&mut self, amount: u64) -> Result<(), TransactionError> {
+        let instruction = instruction::transfer_checked(
+            &self.env.program_id,
+            &self.source.pubkey(),
+            &self.mint.pubkey(),
+            &self.destination.pubkey(),
+            &self.owner.pubkey(),
+            &[],
+            amount,
+            6,
+        )
+        .unwrap();
+        self.env.process(&[instruction], &[&self.owner]).await
+    }
+}
+
+#[tokio::test]
+async fn transfer_fee_is_withheld_on_destination() {
+    let mut fixture = setup_fee_mint(100_000).await;
+    let (source, destination) = (fixture.source.pubkey(), fixture.destination.pubkey());
+
+    // 1% of 1_000 is exactly 10.
+    fixture.transfer(1_000).await.unwrap();
+    let token = fixture.env.get_token(&destination).await;
+    assert_eq!(token.amount, 990);
+    assert_eq!(token.withheld_amount, COption::Some(10));
+
+    // A single unit still pays a rounded-up fee of one.
+    fixture.transfer(1).await.unwrap();
+    let token = fixture.env.get_token(&destination).await;
+    assert_eq!(token.amount, 990);
+    assert_eq!(token.withheld_amount, COption::Some(11));
+
+    // 1% of 50_000 is clamped to the maximum fee.
+    fixture.transfer(50_000).await.unwrap();
+    let token = fixture.env.get_token(&destination).await;
+    assert_eq!(token.amount, 990 + 49_950);
+    assert_eq!(token.withheld_amount, COption::Some(61));
+
+    fixture.transfer(0).await.unwrap();
+    assert_eq!(
+        fixture.env.get_token(&destination).await.withheld_amount,
+        COption::Some(61)
+    );
+    assert_eq!(
+        fixture.env.get_token(&source).await.amount,
+        100_000 - 51_001
+    );
+}
+
+#[tokio::test]
+async fn transfer_fee_mint_requires_checked_transfer() {
+    let mut fixture = setup_fee_mint(100).await;
+    let program_id = fixture.env.program_id;
+
+    let instruction = instruction::transfer_tokens(
+        &program_id,
+        &fixture.source.pubkey(),
+        &fixture.destination.pubkey(),
+        &fixture.owner.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        fixture
+            .env
+            .process(&[instruction], &[&fixture.owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::MintRequiredForTransfer)
+    );
+}
+
+#[tokio::test]
+async fn harvest_and_withdraw_withheld_fees() {
+    let mut fixture = setup_fee_mint(10_000).await;
+    let program_id = fixture.env.program_id;
+    let (mint, source, destination) = (
+        fixture.mint.pubkey(),
+        fixture.source.pubkey(),
+        fixture.destination.pubkey(),
+    );
+
+    fixture.transfer(2_000).await.unwrap();
+    let harvest =
+        instruction::harvest_withheld_tokens(&program_id, &mint, &[&destination]).unwrap();
+    fixture.env.process(&[harvest], &[]).await.unwrap();
+    assert_eq!(
+        fixture.env.get_token(&destination).await.withheld_amount,
+        COption::Some(0)
+    );
+    assert_eq!(fixture.env.get_mint(&mint).await.withheld_amount, 20);
+
+    // Rotate the fee authority; only the new one can withdraw.
+    let new_fee_authority = Keypair::new();
+    let rotate = instruction::set_authority(
+        &program_id,
+        &mint,
+        Some(&new_fee_authority.pubkey()),
+        instruction::AuthorityType::TransferFeeConfig,
+        &fixture.fee_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    fixture
+        .env
+        .process(&[rotate], &[&fixture.fee_authority])
+        .await
+        .unwrap();
+
+    let withdraw = |authority: &Keypair| {
+        instruction::withdraw_withheld_tokens(&program_id, &mint, &source, &authority.pubkey(), &[])
+            .unwrap()
+    };
+    let old_authority = fixture.fee_authority.insecure_clone();
+    assert_eq!(
+        fixture
+            .env
+            .process(&[withdraw(&old_authority)], &[&old_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    fixture
+        .env
+        .process(&[withdraw(&new_fee_authority)], &[&new_fee_authority])
+        .await
+        .unwrap();
+    assert_eq!(fixture.env.get_mint(&mint).await.withheld_amount, 0);
+    assert_eq!(fixture.env.get_token(&source).await.amount, 8_000 + 20);
+}
+
+#[tokio::test]
+async fn non_transferable_mint_blocks_every_transfer() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let owner = Keypair::new();
+    let source = Keypair::new();
+    let destination = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure =
+        instruction::initialize_non_transferable_mint(&program_id, &mint.pubkey()).unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        None,
+        0,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    assert!(env.get_mint(&mint.pubkey()).await.non_transferable);
+
+    let reconfigure =
+        instruction::initialize_non_transferable_mint(&program_id, &mint.pubkey()).unwrap();
+    assert_eq!(
+        env.process(&[reconfigure], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    env.create_token_account(&source, &mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_token_account(&destination, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    let mint_to = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    env.process(&[mint_to], &[&mint_authority]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 1);
+
+    let destination_pubkey = destination.pubkey();
+    let transfers = [
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination_pubkey,
+            &owner.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap(),
+        instruction::transfer_checked(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &destination_pubkey,
+            &owner.pubkey(),
+            &[],
+            1,
+            0,
+        )
+        .unwrap(),
+        instruction::transfer_batch(
+            &program_id,
+            &source.pubkey(),
+            &owner.pubkey(),
+            &[],
+            &[(&destination_pubkey, 1)],
+        )
+        .unwrap(),
+    ];
+    for transfer in transfers {
+        assert_eq!(
+            env.process(&[transfer], &[&owner]).await.unwrap_err(),
+            custom(TokenError::NonTransferable)
+        );
+    }
+
+    let burn = instruction::burn(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &owner.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    env.process(&[burn], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 0);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 0);
+}
+
+#[tokio::test]
+async fn locked_account_cannot_send_until_expiry() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    env.set_unix_timestamp(1_000).await;
+
+    let lock = |lock_until| {
+        instruction::lock_account(
+            &program_id,
+            &source.pubkey(),
+            &owner.pubkey(),
+            &[],
+            &payer,
+            lock_until,
+        )
+        .unwrap()
+    };
+    env.process(&[lock(2_000)], &[&owner]).await.unwrap();
+    assert_eq!(
+        env.get_extension::<AccountLock>(&source.pubkey()).await,
+        Some(AccountLock { lock_until: 2_000 })
+    );
+    assert_eq!(
+        env.process(&[lock(1_500)], &[&owner]).await.unwrap_err(),
+        custom(TokenError::LockCannotBeShortened)
+    );
+
+    let transfer = |amount| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    let burn = |amount| {
+        instruction::burn(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        env.process(&[transfer(10)], &[&owner]).await.unwrap_err(),
+        custom(TokenError::AccountLocked)
+    );
+    assert_eq!(
+        env.process(&[burn(5)], &[&owner]).await.unwrap_err(),
+        custom(TokenError::AccountLocked)
+    );
+
+    // The locked account can still receive.
+    let sender = Keypair::new();
+    env.create_token_account(&sender, &mint.pubkey(), &owner.pubkey())
+        .await;
+    let mint_to = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &sender.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        20,
+    )
+    .unwrap();
+    let incoming = instruction::transfer_tokens(
+        &program_id,
+        &sender.pubkey(),
+        &source.pubkey(),
+        &owner.pubkey(),
+        &[],
+        20,
+    )
+    .unwrap();
+    env.process(&[mint_to, incoming], &[&mint_authority, &owner])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 120);
+
+    // Identical transactions would be deduplicated, so use new amounts.
+    env.set_unix_timestamp(2_000).await;
+    env.process(&[transfer(15)], &[&owner]).await.unwrap();
+    env.process(&[burn(6)], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 99);
+}
+
+struct EscrowFixture {
+    env: Env,
+    maker: Keypair,
+    maker_source: Keypair,
+    maker_receive: Keypair,
+    taker: Keypair,
+    taker_source: Keypair,
+    taker_receive: Keypair,
+    deposit: Keypair,
+    escrow: Keypair,
+}
+
+/// Maker holds 100 of mint A and wants 40 of mint B; taker holds 50 of mint
+/// B. The escrow and its deposit account are created but not initialized.
+async fn setup_escrow() -> EscrowFixture {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint_authority = Keypair::new();
+    let mint_a = Keypair::new();
+    let mint_b = Keypair::new();
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let maker_source = Keypair::new();
+    let maker_receive = Keypair::new();
+    let taker_source = Keypair::new();
+    let taker_receive = Keypair::new();
+    let deposit = Keypair::new();
+    let escrow = Keypair::new();
+
+    env.create_mint(&mint_a, &mint_authority.pubkey()).await;
+    env.create_mint(&mint_b, &mint_authority.pubkey()).await;
+    env.create_token_account(&maker_source, &mint_a.pubkey(), &maker.pubkey())
+        .await;
+    env.create_token_account(&maker_receive, &mint_b.pubkey(), &maker.pubkey())
+        .await;
+    env.create_token_account(&taker_source, &mint_b.pubkey(), &taker.pubkey())
+        .await;
+    env.create_token_account(&taker_receive, &mint_a.pubkey(), &taker.pubkey())
+        .await;
+    let escrow_authority = get_escrow_authority(&escrow.pubkey(), &program_id);
+    env.create_token_account(&deposit, &mint_a.pubkey(), &escrow_authority)
+        .await;
+    env.create_account(&escrow, Escrow::LEN, &program_id).await;
+
+    let mint_a_to_maker = instruction::mint_tokens(
+        &program_id,
+        &mint_a.pubkey(),
+        &maker_source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    let mint_b_to_taker = instruction::mint_tokens(
+        &program_id,
+        &mint_b.pubkey(),
+        &taker_source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        50,
+    )
+    .unwrap();
+    env.process(&[mint_a_to_maker, mint_b_to_taker], &[&mint_authority])
+        .await
+        .unwrap();
+
+    EscrowFixture {
+        env,
+        maker,
+        maker_source,
+        maker_receive,
+        taker,
+        taker_source,
+        taker_receive,
+        deposit,
+        escrow,
+    }
+}
+
+impl EscrowFixture {
+    async fn initialize(&mut self, amount: u64, expected_amount: u64) {
+        let instruction = instruction::initialize_escrow(
+            &self.env.program_id,
+            &self.maker.pubkey(),
+            &self.maker_source.pubkey(),
+            &self.deposit.pubkey(),
+            &self.escrow.pubkey(),
+            &self.maker_receive.pubkey(),
+            amount,
+            expected_amount,
+        )
+        .unwrap();
+        self.env
+            .process(&[instruction], &[&self.maker])
+            .await
+            .unwrap();
+    }
+
+    async fn exchange(
+        &mut self,
+        amount: u64,
+        expected_amount: u64,
+    ) -> Result<(), TransactionError> {
+        let instruction = instruction::exchange(
+            &self.env.program_id,
+            &self.taker.pubkey(),
+            &self.taker_source.pubkey(),
+            &self.taker_receive.pubkey(),
+            &self.deposit.pubkey(),
+            &self.maker_receive.pubkey(),
+            &self.maker.pubkey(),
+            &self.escrow.pubkey(),
+            amount,
+            expected_amount,
+        )
+        .unwrap();
+        self.env.process(&[instruction], &[&self.taker]).await
+    }
+
+    async fn cancel(&mut self) -> Result<(), TransactionError> {
+        let instruction = instruction::cancel_escrow(
+            &self.env.program_id,
+            &self.maker.pubkey(),
+            &self.deposit.pubkey(),
+            &self.maker_source.pubkey(),
+            &self.escrow.pubkey(),
+        )
+        .unwrap();
+        self.env.process(&[instruction], &[&self.maker]).await
+    }
+
+    async fn is_closed(&mut self, pubkey: &Pubkey) -> bool {
+        self.env
+            .banks_client
+            .get_account(*pubkey)
+            .await
+            .unwrap()
+            .is_none()
+    }
+}
+
+#[tokio::test]
+async fn escrow_exchange_swaps_both_sides() {
+    let mut fixture = setup_escrow().await;
+    fixture.initialize(60, 40).await;
+
+    let escrow_pubkey = fixture.escrow.pubkey();
+    let escrow = Escrow::unpack(&fixture.env.get_account(&escrow_pubkey).await.data).unwrap();
+    assert_eq!(escrow.maker, fixture.maker.pubkey());
+    assert_eq!(escrow.expected_amount, 40);
+    assert_eq!(escrow.deposit_amount, 60);
+    let deposit_pubkey = fixture.deposit.pubkey();
+    assert_eq!(fixture.env.get_token(&deposit_pubkey).await.amount, 60);
+
+    fixture.exchange(40, 60).await.unwrap();
+
+    let env = &mut fixture.env;
+    assert_eq!(
+        env.get_token(&fixture.maker_source.pubkey()).await.amount,
+        40
+    );
+    assert_eq!(
+        env.get_token(&fixture.maker_receive.pubkey()).await.amount,
+        40
+    );
+    assert_eq!(
+        env.get_token(&fixture.taker_source.pubkey()).await.amount,
+        10
+    );
+    assert_eq!(
+        env.get_token(&fixture.taker_receive.pubkey()).await.amount,
+        60
+    );
+    assert!(fixture.is_closed(&deposit_pubkey).await);
+    assert!(fixture.is_closed(&escrow_pubkey).await);
+    let maker_pubkey = fixture.maker.pubkey();
+    assert!(fixture.env.get_account(&maker_pubkey).await.lamports > 0);
+}
+
+#[tokio::test]
+async fn escrow_exchange_rejects_taker_offering_too_little() {
+    let mut fixture = setup_escrow().await;
+    fixture.initialize(60, 40).await;
+
+    assert_eq!(
+        fixture.exchange(39, 60).await.unwrap_err(),
+        custom(TokenError::EscrowAmountMismatch)
+    );
+
+    let env = &mut fixture.env;
+    assert_eq!(env.get_token(&fixture.deposit.pubkey()).await.amount, 60);
+    assert_eq!(
+        env.get_token(&fixture.taker_source.pubkey()).await.amount,
+        50
+    );
+    assert_eq!(
+        env.get_token(&fixture.maker_receive.pubkey()).await.amount,
+        0
+    );
+}
+
+#[tokio::test]
+async fn escrow_exchange_rejects_a_deposit_reopened_smaller() {
+    let mut fixture = setup_escrow().await;
+    fixture.initialize(60, 40).await;
+    fixture.cancel().await.unwrap();
+
+    // The maker reopens the same escrow and deposit accounts with a tenth
+    // of the deposit before the taker's exchange lands.
+    fixture.env.recent_blockhash = fixture
+        .env
+        .context
+        .get_new_latest_blockhash()
+        .await
+        .unwrap();
+    let program_id = fixture.env.program_id;
+    let escrow_authority = get_escrow_authority(&fixture.escrow.pubkey(), &program_id);
+    let mint = fixture
+        .env
+        .get_token(&fixture.maker_source.pubkey())
+        .await
+        .mint;
+    fixture
+        .env
+        .create_token_account(&fixture.deposit, &mint, &escrow_authority)
+        .await;
+    fixture
+        .env
+        .create_account(&fixture.escrow, Escrow::LEN, &program_id)
+        .await;
+    fixture.initialize(6, 40).await;
+
+    assert_eq!(
+        fixture.exchange(40, 60).await.unwrap_err(),
+        custom(TokenError::EscrowAmountMismatch)
+    );
+    let env = &mut fixture.env;
+    assert_eq!(
+        env.get_token(&fixture.taker_source.pubkey()).await.amount,
+        50
+    );
+    fixture.exchange(40, 6).await.unwrap();
+    let taker_receive = fixture.taker_receive.pubkey();
+    assert_eq!(fixture.env.get_token(&taker_receive).await.amount, 6);
+}
+
+#[tokio::test]
+async fn escrow_cancel_after_partial_setup() {
+    let mut fixture = setup_escrow().await;
+
+    // The accounts exist but InitializeEscrow never ran.
+    assert_eq!(
+        fixture.cancel().await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::UninitializedAccount)
+    );
+
+    fixture.initialize(60, 40).await;
+    assert_eq!(
+        fixture.exchange(30, 60).await.unwrap_err(),
+        custom(TokenError::EscrowAmountMismatch)
+    );
+    // The retried cancel is otherwise identical to the failed one above.
+    fixture.env.recent_blockhash = fixture
+        .env
+        .context
+        .get_new_latest_blockhash()
+        .await
+        .unwrap();
+    fixture.cancel().await.unwrap();
+
+    let maker_source = fixture.maker_source.pubkey();
+    assert_eq!(fixture.env.get_token(&maker_source).await.amount, 100);
+    let (deposit, escrow) = (fixture.deposit.pubkey(), fixture.escrow.pubkey());
+    assert!(fixture.is_closed(&deposit).await);
+    assert!(fixture.is_closed(&escrow).await);
+}
+
+#[tokio::test]
+async fn merkle_distribution_pays_each_leaf_once() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let creator = Keypair::new();
+    let source = Keypair::new();
+    let vault = Keypair::new();
+    let distributor = Keypair::new();
+    env.create_mint(&mint, &mint_authority.pubkey()).await;
+    env.create_token_account(&source, &mint.pubkey(), &creator.pubkey())
+        .await;
+    let authority = get_distributor_authority(&distributor.pubkey(), &program_id);
+    env.create_token_account(&vault, &mint.pubkey(), &authority)
+        .await;
+    env.create_account(&distributor, Distributor::LEN, &program_id)
+        .await;
+    let mint_to_creator = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    env.process(&[mint_to_creator], &[&mint_authority])
+        .await
+        .unwrap();
+
+    // Four claimants, each with a wallet to pay for its claim status and a
+    // token account to receive into.
+    let mut claimants = Vec::new();
+    for _ in 0..4 {
+        let (claimant, account) = (Keypair::new(), Keypair::new());
+        let fund =
+            system_instruction::transfer(&env.payer.pubkey(), &claimant.pubkey(), 10_000_000);
+        env.process(&[fund], &[]).await.unwrap();
+        env.create_token_account(&account, &mint.pubkey(), &claimant.pubkey())
+            .await;
+        claimants.push((claimant, account));
+    }
+    let amounts = [10, 20, 30, 40];
+    let leaves: Vec<_> = claimants
+        .iter()
+        .zip(amounts)
+        .enumerate()
+        .map(|(index, ((claimant, _), amount))| {
+            distributor::claim_leaf(index as u64, &claimant.pubkey(), amount)
+        })
+        .collect();
+    let left = distributor::hash_pair(&leaves[0], &leaves[1]);
+    let right = distributor::hash_pair(&leaves[2], &leaves[3]);
+    let root = distributor::hash_pair(&left, &right);
+    let proofs = [
+        [leaves[1], right],
+        [leaves[0], right],
+        [leaves[3], left],
+        [leaves[2], left],
+    ];
+
+    let create = instruction::create_distribution(
+        &program_id,
+        &creator.pubkey(),
+        &source.pubkey(),
+        &vault.pubkey(),
+        &distributor.pubkey(),
+        root,
+        100,
+    )
+    .unwrap();
+    env.process(&[create], &[&creator]).await.unwrap();
+    assert_eq!(env.get_token(&vault.pubkey()).await.amount, 100);
+
+    let claim = |index: usize, claimant: &Keypair, amount: u64, proof: &[[u8; 32]]| {
+        instruction::claim(
+            &program_id,
+            &claimant.pubkey(),
+            &claimants[index].1.pubkey(),
+            &distributor.pubkey(),
+            &vault.pubkey(),
+            index as u64,
+            amount,
+            proof,
+        )
+        .unwrap()
+    };
+    for index in [0, 2] {
+        let claimant = &claimants[index].0;
+        let instruction = claim(index, claimant, amounts[index], &proofs[index]);
+        env.process(&[instruction], &[claimant]).await.unwrap();
+        let account = claimants[index].1.pubkey();
+        assert_eq!(env.get_token(&account).await.amount, amounts[index]);
+    }
+    assert_eq!(env.get_token(&vault.pubkey()).await.amount, 60);
+    let distributor_data =
+        Distributor::unpack(&env.get_account(&distributor.pubkey()).await.data).unwrap();
+    assert_eq!(distributor_data.merkle_root, root);
+    assert_eq!(distributor_data.total_claimed, 40);
+    let claim_address = get_claim_address(&distributor.pubkey(), 2, &program_id);
+    let claim_status = ClaimStatus::unpack(&env.get_account(&claim_address).await.data).unwrap();
+    assert_eq!(claim_status.claimant, claimants[2].0.pubkey());
+    assert_eq!(claim_status.amount, 30);
+
+    // Replaying the first claim, in a fresh transaction, fails.
+    env.recent_blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    let replay = claim(0, &claimants[0].0, 10, &proofs[0]);
+    assert_eq!(
+        env.process(&[replay], &[&claimants[0].0])
+            .await
+            .unwrap_err(),
+        custom(TokenError::AlreadyClaimed)
+    );
+
+    // Neither a larger amount nor another claimant's key matches a leaf.
+    let inflated = claim(1, &claimants[1].0, 50, &proofs[1]);
+    assert_eq!(
+        env.process(&[inflated], &[&claimants[1].0])
+            .await
+            .unwrap_err(),
+        custom(TokenError::InvalidMerkleProof)
+    );
+    let stolen = claim(3, &claimants[0].0, 40, &proofs[3]);
+    assert_eq!(
+        env.process(&[stolen], &[&claimants[0].0])
+            .await
+            .unwrap_err(),
+        custom(TokenError::InvalidMerkleProof)
+    );
+    assert_eq!(env.get_token(&vault.pubkey()).await.amount, 60);
+
+    // Lamports sent to a claim status address beforehand don't count as a
+    // claim.
+    let claim_address = get_claim_address(&distributor.pubkey(), 1, &program_id);
+    let rent = env.banks_client.get_rent().await.unwrap();
+    let prefund =
+        system_instruction::transfer(&env.payer.pubkey(), &claim_address, rent.minimum_balance(0));
+    env.process(&[prefund], &[]).await.unwrap();
+    let honest = claim(1, &claimants[1].0, 20, &proofs[1]);
+    env.process(&[honest], &[&claimants[1].0]).await.unwrap();
+    assert_eq!(env.get_token(&vault.pubkey()).await.amount, 40);
+    let claim_status = env.get_account(&claim_address).await;
+    assert_eq!(claim_status.owner, program_id);
+    assert_eq!(
+        claim_status.lamports,
+        rent.minimum_balance(ClaimStatus::LEN)
+    );
+}
+
+#[tokio::test]
+async fn pause_blocks_movement_until_resumed() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let pause_authority = Keypair::new();
+    let owner = Keypair::new();
+    let source = Keypair::new();
+    let destination = Keypair::new();
+    let empty = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure = instruction::initialize_pause_config(
+        &program_id,
+        &mint.pubkey(),
+        &pause_authority.pubkey(),
+    )
+    .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        None,
+        0,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    env.create_token_account(&source, &mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_token_account(&destination, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    env.create_token_account(&empty, &mint.pubkey(), &owner.pubkey())
+        .await;
+    assert!(env.get_token(&source.pubkey()).await.is_pausable);
+
+    let mint_to = |amount| {
+        instruction::mint_tokens(
+            &program_id,
+            &mint.pubkey(),
+            &source.pubkey(),
+            &mint_authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    let transfer = |amount| {
+        instruction::transfer_checked(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+            0,
+        )
+        .unwrap()
+    };
+    let burn = |amount| {
+        instruction::burn(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    env.process(&[mint_to(100)], &[&mint_authority])
+        .await
+        .unwrap();
+
+    // Without the mint the pause can't be checked, so unchecked transfers
+    // are always rejected.
+    let unchecked = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[unchecked], &[&owner]).await.unwrap_err(),
+        custom(TokenError::MintRequiredForTransfer)
+    );
+
+    let pause =
+        instruction::pause(&program_id, &mint.pubkey(), &pause_authority.pubkey(), &[]).unwrap();
+    env.process(&[pause], &[&pause_authority]).await.unwrap();
+    assert!(env.get_mint(&mint.pubkey()).await.paused);
+
+    assert_eq!(
+        env.process(&[transfer(10)], &[&owner]).await.unwrap_err(),
+        custom(TokenError::MintPaused)
+    );
+    assert_eq!(
+        env.process(&[mint_to(10)], &[&mint_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::MintPaused)
+    );
+    assert_eq!(
+        env.process(&[burn(10)], &[&owner]).await.unwrap_err(),
+        custom(TokenError::MintPaused)
+    );
+
+    // Empty accounts can still be closed while paused.
+    let close = instruction::close_account(
+        &program_id,
+        &empty.pubkey(),
+        &owner.pubkey(),
+        &owner.pubkey(),
+        &[],
+    )
+    .unwrap();
+    env.process(&[close], &[&owner]).await.unwrap();
+
+    let resume =
+        instruction::resume(&program_id, &mint.pubkey(), &pause_authority.pubkey(), &[]).unwrap();
+    env.process(&[resume], &[&pause_authority]).await.unwrap();
+    assert!(!env.get_mint(&mint.pubkey()).await.paused);
+
+    // Identical transactions would be deduplicated, so use new amounts.
+    env.process(&[transfer(12)], &[&owner]).await.unwrap();
+    env.process(&[mint_to(5)], &[&mint_authority])
+        .await
+        .unwrap();
+    env.process(&[burn(5)], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 88);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 12);
+}
+
+#[tokio::test]
+async fn mint_without_pause_authority_cannot_be_paused() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+
+    let pause =
+        instruction::pause(&program_id, &mint.pubkey(), &mint_authority.pubkey(), &[]).unwrap();
+    assert_eq!(
+        env.process(&[pause], &[&mint_authority]).await.unwrap_err(),
+        custom(TokenError::MintCannotPause)
+    );
+    assert!(!env.get_mint(&mint.pubkey()).await.paused);
+
+    // The pause authority can't be added once the mint is initialized.
+    let configure =
+        instruction::initialize_pause_config(&program_id, &mint.pubkey(), &mint_authority.pubkey())
+            .unwrap();
+    assert_eq!(
+        env.process(&[configure], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[tokio::test]
+async fn permanent_delegate_claws_back_from_any_account() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let permanent_delegate = Keypair::new();
+    let stranger = Keypair::new();
+    let source = Keypair::new();
+    let treasury = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure = instruction::initialize_permanent_delegate(
+        &program_id,
+        &mint.pubkey(),
+        &permanent_delegate.pubkey(),
+    )
+    .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        None,
+        0,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    env.create_token_account(&source, &mint.pubkey(), &stranger.pubkey())
+        .await;
+    env.create_token_account(&treasury, &mint.pubkey(), &permanent_delegate.pubkey())
+        .await;
+    let mint_to = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    env.process(&[mint_to], &[&mint_authority]).await.unwrap();
+
+    let transfer = instruction::transfer_checked(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &treasury.pubkey(),
+        &permanent_delegate.pubkey(),
+        &[],
+        60,
+        0,
+    )
+    .unwrap();
+    env.process(&[transfer], &[&permanent_delegate])
+        .await
+        .unwrap();
+
+    let burn = instruction::burn_checked(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &permanent_delegate.pubkey(),
+        &[],
+        40,
+        0,
+    )
+    .unwrap();
+    env.process(&[burn], &[&permanent_delegate]).await.unwrap();
+
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 0);
+    assert_eq!(env.get_token(&treasury.pubkey()).await.amount, 60);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 60);
+}
+
+#[tokio::test]
+async fn permanent_delegate_acts_only_through_checked_instructions() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let permanent_delegate = Keypair::new();
+    let (source, treasury) = (Keypair::new(), Keypair::new());
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure = instruction::initialize_permanent_delegate(
+        &program_id,
+        &mint.pubkey(),
+        &permanent_delegate.pubkey(),
+    )
+    .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        None,
+        0,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    env.create_token_account(&source, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    env.create_token_account(&treasury, &mint.pubkey(), &permanent_delegate.pubkey())
+        .await;
+    let mint_to = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    env.process(&[mint_to], &[&mint_authority]).await.unwrap();
+
+    // Unchecked, the permanent delegate is just another stranger.
+    let transfer = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &treasury.pubkey(),
+        &permanent_delegate.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[transfer], &[&permanent_delegate])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    let burn = instruction::burn(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &permanent_delegate.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[burn], &[&permanent_delegate])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 100);
+
+    let transfer = instruction::transfer_checked(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &treasury.pubkey(),
+        &permanent_delegate.pubkey(),
+        &[],
+        10,
+        0,
+    )
+    .unwrap();
+    let burn = instruction::burn_checked(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &permanent_delegate.pubkey(),
+        &[],
+        10,
+        0,
+    )
+    .unwrap();
+    env.process(&[transfer, burn], &[&permanent_delegate])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 80);
+    assert_eq!(env.get_token(&treasury.pubkey()).await.amount, 10);
+}
+
+#[tokio::test]
+async fn mint_without_permanent_delegate_rejects_strangers() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let stranger = Keypair::new();
+    assert_eq!(
+        env.get_mint(&mint.pubkey()).await.permanent_delegate,
+        COption::None
+    );
+
+    let transfer = instruction::transfer_checked(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &destination.pubkey(),
+        &stranger.pubkey(),
+        &[],
+        10,
+        6,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[transfer], &[&stranger]).await.unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    let burn = instruction::burn(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &stranger.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[burn], &[&stranger]).await.unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+
+    let transfer = instruction::transfer_checked(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        10,
+        6,
+    )
+    .unwrap();
+    env.process(&[transfer], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 90);
+}
+
+#[test]
+fn clawback_event_round_trip() {
+    let event = TokenEvent::Clawback(ClawbackEvent {
+        mint: Pubkey::new_unique(),
+        source: Pubkey::new_unique(),
+        permanent_delegate: Pubkey::new_unique(),
+        amount: 42,
+    });
+    let packed = event.pack();
+    assert_eq!(packed.len(), 1 + 32 + 32 + 32 + 8);
+    assert_eq!(TokenEvent::unpack(&packed).unwrap(), event);
+}
+
+#[test]
+fn authority_and_config_events_round_trip() {
+    let account = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    for (old, new) in [
+        (
+            COption::Some(authority),
+            COption::Some(Pubkey::new_unique()),
+        ),
+        (COption::Some(authority), COption::None),
+        (COption::None, COption::Some(authority)),
+    ] {
+        let event = TokenEvent::AuthorityChanged(AuthorityChangedEvent {
+            account,
+            authority_type: instruction::AuthorityType::FreezeAccount,
+            old,
+            new,
+        });
+        let packed = event.pack();
+        assert_eq!(packed.len(), 1 + 32 + 1 + 33 + 33);
+        assert_eq!(packed[0], EventDiscriminator::AuthorityChanged as u8);
+        assert_eq!(TokenEvent::unpack(&packed).unwrap(), event);
+    }
+
+    for change in [
+        ConfigChange::Frozen,
+        ConfigChange::Thawed,
+        ConfigChange::Paused,
+        ConfigChange::Resumed,
+        ConfigChange::InterestRate(-250),
+        ConfigChange::DefaultAccountState(AccountState::Frozen),
+        ConfigChange::MinTransferAmount(u64::MAX),
+    ] {
+        let event = TokenEvent::ConfigChanged(ConfigChangedEvent {
+            account,
+            authority,
+            change,
+        });
+        let packed = event.pack();
+        assert_eq!(packed.len(), 1 + 32 + 32 + 1 + 8);
+        assert_eq!(packed[0], EventDiscriminator::ConfigChanged as u8);
+        assert_eq!(TokenEvent::unpack(&packed).unwrap(), event);
+    }
+}
+
+#[test]
+fn malformed_authority_and_config_events_are_rejected() {
+    let authority_changed = TokenEvent::AuthorityChanged(AuthorityChangedEvent {
+        account: Pubkey::new_unique(),
+        authority_type: instruction::AuthorityType::MintTokens,
+        old: COption::None,
+        new: COption::Some(Pubkey::new_unique()),
+    })
+    .pack();
+    let config_changed = TokenEvent::ConfigChanged(ConfigChangedEvent {
+        account: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        change: ConfigChange::Paused,
+    })
+    .pack();
+
+    let corrupt = |packed: &[u8], index: usize, byte: u8| {
+        let mut packed = packed.to_vec();
+        packed[index] = byte;
+        TokenEvent::unpack(&packed)
+    };
+    // An unknown authority type, an unset authority with a key and an
+    // unknown option tag.
+    assert!(corrupt(&authority_changed, 33, 6).is_err());
+    assert!(corrupt(&authority_changed, 35, 1).is_err());
+    assert!(corrupt(&authority_changed, 67, 2).is_err());
+    // An unknown change, and a value on a change that takes none.
+    assert!(corrupt(&config_changed, 65, 7).is_err());
+    assert!(corrupt(&config_changed, 66, 1).is_err());
+    // An interest rate out of range, and an unknown account state.
+    let mut rate = config_changed.clone();
+    rate[65] = 4;
+    rate[66..].copy_from_slice(&(i16::MAX as u64 + 1).to_le_bytes());
+    assert!(TokenEvent::unpack(&rate).is_err());
+    let mut state = config_changed.clone();
+    state[65] = 5;
+    state[66] = 3;
+    assert!(TokenEvent::unpack(&state).is_err());
+
+    for packed in [&authority_changed, &config_changed] {
+        assert!(TokenEvent::unpack(&packed[..packed.len() - 1]).is_err());
+        let mut trailing = packed.clone();
+        trailing.push(0);
+        assert!(TokenEvent::unpack(&trailing).is_err());
+    }
+    assert!(TokenEvent::unpack(&[9]).is_err());
+}
+
+#[test]
+fn mint_and_transfer_events_round_trip() {
+    let mint = TokenEvent::Mint(MintEvent {
+        mint: Pubkey::new_unique(),
+        destination: Pubkey::new_unique(),
+        amount: 7,
+    });
+    let transfer = TokenEvent::Transfer(TransferEvent {
+        source: Pubkey::new_unique(),
+        destination: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        amount: u64::MAX,
+    });
+    for (event, discriminator, len) in [
+        (mint, EventDiscriminator::Mint, 1 + 32 + 32 + 8),
+        (transfer, EventDiscriminator::Transfer, 1 + 32 + 32 + 32 + 8),
+    ] {
+        let packed = event.pack();
+        assert_eq!(packed.len(), len);
+        assert_eq!(packed[0], discriminator as u8);
+        assert_eq!(TokenEvent::unpack(&packed).unwrap(), event);
+        assert!(TokenEvent::unpack(&packed[..len - 1]).is_err());
+    }
+}
+
+#[test]
+fn burn_event_round_trip() {
+    let event = TokenEvent::Burn(BurnEvent {
+        mint: Pubkey::new_unique(),
+        source: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        amount: 42,
+    });
+    let packed = event.pack();
+    assert_eq!(packed.len(), 1 + 32 + 32 + 32 + 8);
+    assert_eq!(packed[0], EventDiscriminator::Burn as u8);
+    assert_eq!(TokenEvent::unpack(&packed).unwrap(), event);
+}
+
+#[test]
+fn recovery_events_round_trip() {
+    for action in [RecoveryAction::Thaw, RecoveryAction::Resume] {
+        let event = TokenEvent::RecoveryUsed(RecoveryUsedEvent {
+            account: Pubkey::new_unique(),
+            recovery_authority: Pubkey::new_unique(),
+            action,
+        });
+        let packed = event.pack();
+        assert_eq!(packed.len(), 1 + 32 + 32 + 1);
+        assert_eq!(packed[0], EventDiscriminator::RecoveryUsed as u8);
+        assert_eq!(TokenEvent::unpack(&packed).unwrap(), event);
+
+        let mut unknown = packed.clone();
+        unknown[65] = 2;
+        assert!(TokenEvent::unpack(&unknown).is_err());
+        assert!(TokenEvent::unpack(&packed[..65]).is_err());
+    }
+}
+
+#[tokio::test]
+async fn recovery_authority_thaws_and_resumes_with_an_audit_event() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let authority = Keypair::new();
+    let recovery = Keypair::new();
+    let account = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure_recovery = instruction::initialize_recovery_authority(
+        &program_id,
+        &mint.pubkey(),
+        &env.payer.pubkey(),
+        &recovery.pubkey(),
+    )
+    .unwrap();
+    let configure_pause =
+        instruction::initialize_pause_config(&program_id, &mint.pubkey(), &authority.pubkey())
+            .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &authority.pubkey(),
+        Some(&authority.pubkey()),
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure_recovery, configure_pause, initialize], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.get_mint_extension::<RecoveryAuthority>(&mint.pubkey())
+            .await,
+        Some(RecoveryAuthority {
+            recovery_authority: recovery.pubkey()
+        })
+    );
+    env.create_token_account(&account, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+
+    let freeze = instruction::freeze_account(
+        &program_id,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    let pause = instruction::pause(&program_id, &mint.pubkey(), &authority.pubkey(), &[]).unwrap();
+    env.process(&[freeze, pause], &[&authority]).await.unwrap();
+
+    // The recovery authority can't do what it undoes.
+    let freeze_again = instruction::freeze_account(
+        &program_id,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &recovery.pubkey(),
+        &[],
+    )
+    .unwrap();
+    let thaw = instruction::thaw_account(
+        &program_id,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &recovery.pubkey(),
+        &[],
+    )
+    .unwrap();
+    let resume = instruction::resume(&program_id, &mint.pubkey(), &recovery.pubkey(), &[]).unwrap();
+    assert_eq!(
+        env.process_events(&[thaw.clone(), freeze_again], &[&recovery])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(TokenError::OwnerMismatch as u32)
+        )
+    );
+
+    let changed = |account: &Keypair, change| {
+        TokenEvent::ConfigChanged(ConfigChangedEvent {
+            account: account.pubkey(),
+            authority: recovery.pubkey(),
+            change,
+        })
+    };
+    let recovered = |account: &Keypair, action| {
+        TokenEvent::RecoveryUsed(RecoveryUsedEvent {
+            account: account.pubkey(),
+            recovery_authority: recovery.pubkey(),
+            action,
+        })
+    };
+    assert_eq!(
+        env.process_events(&[thaw, resume], &[&recovery])
+            .await
+            .unwrap(),
+        vec![
+            changed(&account, ConfigChange::Thawed),
+            recovered(&account, RecoveryAction::Thaw),
+            changed(&mint, ConfigChange::Resumed),
+            recovered(&mint, RecoveryAction::Resume),
+        ]
+    );
+    assert_eq!(
+        env.get_token(&account.pubkey()).await.state,
+        AccountState::Initialized
+    );
+    assert!(!env.get_mint(&mint.pubkey()).await.paused);
+
+    // The freeze and pause authorities still log no recovery.
+    let freeze = instruction::freeze_account(
+        &program_id,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    let thaw = instruction::thaw_account(
+        &program_id,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    let events = env
+        .process_events(&[freeze, thaw], &[&authority])
+        .await
+        .unwrap();
+    assert!(!events
+        .iter()
+        .any(|event| matches!(event, TokenEvent::RecoveryUsed(_))));
+}
+
+/// A step of [`replayed_events_rebuild_every_balance`], on one of its
+/// accounts; moved and burned amounts are percentages of the balance.
+#[derive(Clone, Debug)]
+enum ReplayStep {
+    Mint {
+        to: usize,
+        amount: u64,
+    },
+    Transfer {
+        from: usize,
+        to: usize,
+        percent: u64,
+    },
+    Burn {
+        from: usize,
+        percent: u64,
+    },
+    Close {
+        account: usize,
+    },
+}
+
+const REPLAY_ACCOUNTS: usize = 4;
+
+fn replay_step() -> impl Strategy<Value = ReplayStep> {
+    let account = 0..REPLAY_ACCOUNTS;
+    prop_oneof![
+        3 => (account.clone(), 1..1_000u64).prop_map(|(to, amount)| ReplayStep::Mint { to, amount }),
+        4 => (account.clone(), account.clone(), 1..=100u64)
+            .prop_map(|(from, to, percent)| ReplayStep::Transfer { from, to, percent }),
+        2 => (account.clone(), 1..=100u64).prop_map(|(from, percent)| ReplayStep::Burn { from, percent }),
+        1 => account.prop_map(|account| ReplayStep::Close { account }),
+    ]
+}
+
+#[tokio::test]
+async fn replayed_events_rebuild_every_balance() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    env.create_mint(&mint, &mint_authority.pubkey()).await;
+    let owners = (0..REPLAY_ACCOUNTS)
+        .map(|_| Keypair::new())
+        .collect::<Vec<_>>();
+    let accounts = (0..REPLAY_ACCOUNTS)
+        .map(|_| Keypair::new())
+        .collect::<Vec<_>>();
+    for (account, owner) in accounts.iter().zip(&owners) {
+        env.create_token_account(account, &mint.pubkey(), &owner.pubkey())
+            .await;
+    }
+    let keys = accounts.iter().map(|a| a.pubkey()).collect::<Vec<_>>();
+
+    let steps = proptest::collection::vec(replay_step(), 60)
+        .new_tree(&mut TestRunner::deterministic())
+        .unwrap()
+        .current();
+    let mut replay = EventReplay::default();
+    let mut closed = [false; REPLAY_ACCOUNTS];
+    for (slot, step) in (1..).zip(steps) {
+        let involved = match step {
+            ReplayStep::Mint { to, .. } | ReplayStep::Close { account: to } => [to, to],
+            ReplayStep::Transfer { from, to, .. } => [from, to],
+            ReplayStep::Burn { from, .. } => [from, from],
+        };
+        if involved.iter().any(|&i| closed[i]) {
+            continue;
+        }
+        let (instructions, signer) = match step {
+            ReplayStep::Mint { to, amount } => (
+                vec![instruction::mint_tokens(
+                    &program_id,
+                    &mint.pubkey(),
+                    &keys[to],
+                    &mint_authority.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap()],
+                &mint_authority,
+            ),
+            ReplayStep::Transfer { from, to, percent } => {
+                let amount = env.get_token(&keys[from]).await.amount * percent / 100;
+                if amount == 0 {
+                    continue;
+                }
+                (
+                    vec![instruction::transfer_tokens(
+                        &program_id,
+                        &keys[from],
+                        &keys[to],
+                        &owners[from].pubkey(),
+                        &[],
+                        amount,
+                    )
+                    .unwrap()],
+                    &owners[from],
+                )
+            }
+            ReplayStep::Burn { from, percent } => {
+                let amount = env.get_token(&keys[from]).await.amount * percent / 100;
+                if amount == 0 {
+                    continue;
+                }
+                (
+                    vec![instruction::burn(
+                        &program_id,
+                        &keys[from],
+                        &mint.pubkey(),
+                        &owners[from].pubkey(),
+                        &[],
+                        amount,
+                    )
+                    .unwrap()],
+                    &owners[from],
+                )
+            }
+            ReplayStep::Close { account } => {
+                // Whatever the account still holds is burned first.
+                let amount = env.get_token(&keys[account]).await.amount;
+                let owner = owners[account].pubkey();
+                let mut instructions = vec![];
+                if amount > 0 {
+                    instructions.push(
+                        instruction::burn(
+                            &program_id,
+                            &keys[account],
+                            &mint.pubkey(),
+                            &owner,
+                            &[],
+                            amount,
+                        )
+                        .unwrap(),
+                    );
+                }
+                instructions.push(
+                    instruction::close_account(&program_id, &keys[account], &owner, &owner, &[])
+                        .unwrap(),
+                );
+                closed[account] = true;
+                (instructions, &owners[account])
+            }
+        };
+        // A later slot, so that identical steps are distinct transactions.
+        env.context.warp_to_slot(slot).unwrap();
+        env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+        replay
+            .process(&mut env, &instructions, &[signer])
+            .await
+            .unwrap();
+    }
+
+    assert!(closed.iter().any(|&closed| closed));
+    replay.assert_matches(&mut env, &keys).await;
+    let supply = env.get_mint(&mint.pubkey()).await.supply;
+    assert_eq!(replay.balances.values().sum::<u64>(), supply);
+}
+
+#[tokio::test]
+async fn set_authority_and_accept_owner_log_authority_changes() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        owner,
+        source,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let new_mint_authority = Keypair::new();
+    let candidate = Keypair::new();
+
+    let set_mint_authority = instruction::set_authority(
+        &program_id,
+        &mint.pubkey(),
+        Some(&new_mint_authority.pubkey()),
+        instruction::AuthorityType::MintTokens,
+        &mint_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    let revoke_mint_authority = instruction::set_authority(
+        &program_id,
+        &mint.pubkey(),
+        None,
+        instruction::AuthorityType::MintTokens,
+        &new_mint_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        env.process_events(
+            &[set_mint_authority, revoke_mint_authority],
+            &[&mint_authority, &new_mint_authority]
+        )
+        .await
+        .unwrap(),
+        vec![
+            TokenEvent::AuthorityChanged(AuthorityChangedEvent {
+                account: mint.pubkey(),
+                authority_type: instruction::AuthorityType::MintTokens,
+                old: COption::Some(mint_authority.pubkey()),
+                new: COption::Some(new_mint_authority.pubkey()),
+            }),
+            TokenEvent::AuthorityChanged(AuthorityChangedEvent {
+                account: mint.pubkey(),
+                authority_type: instruction::AuthorityType::MintTokens,
+                old: COption::Some(new_mint_authority.pubkey()),
+                new: COption::None,
+            }),
+        ]
+    );
+
+    let propose = instruction::propose_owner(
+        &program_id,
+        &source.pubkey(),
+        &owner.pubkey(),
+        &[],
+        &candidate.pubkey(),
+    )
+    .unwrap();
+    let accept =
+        instruction::accept_owner(&program_id, &source.pubkey(), &candidate.pubkey(), &[]).unwrap();
+    assert_eq!(
+        env.process_events(&[propose, accept], &[&owner, &candidate])
+            .await
+            .unwrap(),
+        vec![TokenEvent::AuthorityChanged(AuthorityChangedEvent {
+            account: source.pubkey(),
+            authority_type: instruction::AuthorityType::AccountOwner,
+            old: COption::Some(owner.pubkey()),
+            new: COption::Some(candidate.pubkey()),
+        })]
+    );
+
+    // A rejected change logs nothing.
+    let set_owner = instruction::set_authority(
+        &program_id,
+        &source.pubkey(),
+        Some(&owner.pubkey()),
+        instruction::AuthorityType::AccountOwner,
+        &owner.pubkey(),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        env.process_events(&[set_owner], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+}
+
+#[tokio::test]
+async fn config_handlers_log_config_changes() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let mint = Keypair::new();
+    let authority = Keypair::new();
+    let account = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure_pause =
+        instruction::initialize_pause_config(&program_id, &mint.pubkey(), &authority.pubkey())
+            .unwrap();
+    let configure_rate = instruction::initialize_interest_bearing_mint(
+        &program_id,
+        &mint.pubkey(),
+        Some(&authority.pubkey()),
+        100,
+    )
+    .unwrap();
+    let configure_minimum =
+        instruction::initialize_min_transfer_amount(&program_id, &mint.pubkey(), &payer, 1)
+            .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &authority.pubkey(),
+        Some(&authority.pubkey()),
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(
+        &[
+            configure_pause,
+            configure_rate,
+            configure_minimum,
+            initialize,
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    env.create_token_account(&account, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+
+    let changed = |account: &Keypair, change| {
+        TokenEvent::ConfigChanged(ConfigChangedEvent {
+            account: account.pubkey(),
+            authority: authority.pubkey(),
+            change,
+        })
+    };
+    let instructions = [
+        instruction::freeze_account(
+            &program_id,
+            &account.pubkey(),
+            &mint.pubkey(),
+            &authority.pubkey(),
+            &[],
+        )
+        .unwrap(),
+        instruction::thaw_account(
+            &program_id,
+            &account.pubkey(),
+            &mint.pubkey(),
+            &authority.pubkey(),
+            &[],
+        )
+        .unwrap(),
+        instruction::pause(&program_id, &mint.pubkey(), &authority.pubkey(), &[]).unwrap(),
+        instruction::resume(&program_id, &mint.pubkey(), &authority.pubkey(), &[]).unwrap(),
+        instruction::update_rate(&program_id, &mint.pubkey(), &authority.pubkey(), &[], -40)
+            .unwrap(),
+        instruction::set_default_account_state(
+            &program_id,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            &[],
+            AccountState::Frozen,
+        )
+        .unwrap(),
+        instruction::update_min_transfer_amount(
+            &program_id,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            &[],
+            500,
+        )
+        .unwrap(),
+    ];
+    assert_eq!(
+        env.process_events(&instructions, &[&authority])
+            .await
+            .unwrap(),
+        vec![
+            changed(&account, ConfigChange::Frozen),
+            changed(&account, ConfigChange::Thawed),
+            changed(&mint, ConfigChange::Paused),
+            changed(&mint, ConfigChange::Resumed),
+            changed(&mint, ConfigChange::InterestRate(-40)),
+            changed(
+                &mint,
+                ConfigChange::DefaultAccountState(AccountState::Frozen)
+            ),
+            changed(&mint, ConfigChange::MinTransferAmount(500)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn quiet_builds_log_events_but_no_diagnostics() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let transfer = instruction::transfer_tokens(
+        &env.program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        40,
+    )
+    .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[transfer],
+        Some(&env.payer.pubkey()),
+        &[&env.payer, &owner],
+        env.recent_blockhash,
+    );
+    let processed = env
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    processed.result.unwrap();
+    let program_logs: Vec<String> = processed
+        .metadata
+        .unwrap()
+        .log_messages
+        .into_iter()
+        .filter(|line| line.starts_with("Program log: "))
+        .collect();
+    let events = program_logs
+        .iter()
+        .filter(|line| line.starts_with("Program log: Program data: "))
+        .count();
+    assert_eq!(events, 1, "{program_logs:#?}");
+    // `verbose-logs` builds add their diagnostics alongside the event.
+    if cfg!(feature = "verbose-logs") {
+        assert!(program_logs.len() > 1, "{program_logs:#?}");
+    } else {
+        assert_eq!(program_logs.len(), 1, "{program_logs:#?}");
+    }
+}
+
+#[tokio::test]
+async fn freeze_batch_freezes_every_account_or_none() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let other_mint = Keypair::new();
+    let authority = Keypair::new();
+    let owner = Keypair::new();
+    let destination = Keypair::new();
+
+    for mint in [&mint, &other_mint] {
+        env.create_account(mint, Mint::LEN, &program_id).await;
+        let initialize = instruction::initialize_mint(
+            &program_id,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            Some(&authority.pubkey()),
+            6,
+            None,
+        )
+        .unwrap();
+        env.process(&[initialize], &[]).await.unwrap();
+    }
+    let accounts: Vec<Keypair> = (0..5).map(|_| Keypair::new()).collect();
+    for account in &accounts {
+        env.create_token_account(account, &mint.pubkey(), &owner.pubkey())
+            .await;
+        let mint_to = instruction::mint_tokens(
+            &program_id,
+            &mint.pubkey(),
+            &account.pubkey(),
+            &authority.pubkey(),
+            &[],
+            10,
+        )
+        .unwrap();
+        env.process(&[mint_to], &[&authority]).await.unwrap();
+    }
+    env.create_token_account(&destination, &mint.pubkey(), &owner.pubkey())
+        .await;
+    let stray = Keypair::new();
+    env.create_token_account(&stray, &other_mint.pubkey(), &owner.pubkey())
+        .await;
+
+    let keys: Vec<Pubkey> = accounts.iter().map(Keypair::pubkey).collect();
+    let mut key_refs: Vec<&Pubkey> = keys.iter().collect();
+    let stray_key = stray.pubkey();
+    key_refs.push(&stray_key);
+    let with_stray = instruction::freeze_batch(
+        &program_id,
+        &mint.pubkey(),
+        &authority.pubkey(),
+        &[],
+        &key_refs,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[with_stray], &[&authority]).await.unwrap_err(),
+        custom(TokenError::MintMismatch)
+    );
+    for key in &keys {
+        assert_eq!(env.get_token(key).await.state, AccountState::Initialized);
+    }
+
+    key_refs.pop();
+    let freeze = instruction::freeze_batch(
+        &program_id,
+        &mint.pubkey(),
+        &authority.pubkey(),
+        &[],
+        &key_refs,
+    )
+    .unwrap();
+    let events = env.process_events(&[freeze], &[&authority]).await.unwrap();
+    assert_eq!(
+        events,
+        keys.iter()
+            .map(|key| TokenEvent::ConfigChanged(ConfigChangedEvent {
+                account: *key,
+                authority: authority.pubkey(),
+                change: ConfigChange::Frozen,
+            }))
+            .collect::<Vec<_>>()
+    );
+    for key in &keys {
+        assert_eq!(env.get_token(key).await.state, AccountState::Frozen);
+        let transfer = instruction::transfer_tokens(
+            &program_id,
+            key,
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap();
+        assert_eq!(
+            env.process(&[transfer], &[&owner]).await.unwrap_err(),
+            custom(TokenError::AccountFrozen)
+        );
+    }
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 0);
+}
+
+#[tokio::test]
+async fn launch_credits_every_allocation_in_one_transaction() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let authority = Keypair::new();
+    let wallets: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let payer = env.payer.pubkey();
+    let rent = env.banks_client.get_rent().await.unwrap();
+    let create_mint = system_instruction::create_account(
+        &payer,
+        &mint.pubkey(),
+        rent.minimum_balance(Mint::LEN),
+        Mint::LEN as u64,
+        &program_id,
+    );
+    let launch = |max_supply| {
+        instruction::initialize_mint_with_allocations(
+            &program_id,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            &payer,
+            None,
+            6,
+            max_supply,
+            &[(&wallets[0], 500), (&wallets[1], 300), (&wallets[2], 200)],
+        )
+        .unwrap()
+    };
+    let associated: Vec<Pubkey> = wallets
+        .iter()
+        .map(|wallet| get_associated_token_address(wallet, &mint.pubkey(), &program_id))
+        .collect();
+
+    // Over the cap, nothing is created.
+    assert_eq!(
+        env.process(
+            &[create_mint.clone(), launch(Some(999))],
+            &[&mint, &authority]
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(TokenError::SupplyCapExceeded as u32)
+        )
+    );
+    // Dropping the last wallet leaves its allocation without accounts.
+    let mut short = launch(None);
+    short.accounts.pop();
+    assert_eq!(
+        env.process(&[create_mint.clone(), short], &[&mint, &authority])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(1, InstructionError::NotEnoughAccountKeys)
+    );
+    for key in associated.iter().chain([&mint.pubkey()]) {
+        assert!(env.banks_client.get_account(*key).await.unwrap().is_none());
+    }
+
+    env.process(&[create_mint, launch(Some(1_000))], &[&mint, &authority])
+        .await
+        .unwrap();
+    let mint_data = env.get_mint(&mint.pubkey()).await;
+    assert_eq!(mint_data.supply, 1_000);
+    assert_eq!(mint_data.mint_authority, COption::Some(authority.pubkey()));
+    assert!(mint_data.is_initialized);
+    for ((key, wallet), amount) in associated.iter().zip(&wallets).zip([500, 300, 200]) {
+        let token = env.get_token(key).await;
+        assert_eq!(token.owner, *wallet);
+        assert_eq!(token.mint, mint.pubkey());
+        assert_eq!(token.amount, amount);
+    }
+}
+
+#[tokio::test]
+async fn create_token_account_funds_and_initializes_in_one_instruction() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let owner = Pubkey::new_unique();
+    env.create_mint(&mint, &Pubkey::new_unique()).await;
+    let payer = env.payer.pubkey();
+    let rent = env.banks_client.get_rent().await.unwrap();
+    let reserve = rent.minimum_balance(Token::LEN);
+
+    // A fresh keypair address is created, and must sign.
+    let account = Keypair::new();
+    let create = instruction::create_token_account(
+        &program_id,
+        &payer,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &owner,
+    )
+    .unwrap();
+    let mut unsigned = create.clone();
+    unsigned.accounts[1].is_signer = false;
+    assert_eq!(
+        env.process(&[unsigned], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    env.process(std::slice::from_ref(&create), &[&account])
+        .await
+        .unwrap();
+    let created = env.get_account(&account.pubkey()).await;
+    assert_eq!(created.owner, program_id);
+    assert_eq!(created.lamports, reserve);
+    let token = env.get_token(&account.pubkey()).await;
+    assert_eq!(token.mint, mint.pubkey());
+    assert_eq!(token.owner, owner);
+    assert_eq!(token.state, AccountState::Initialized);
+    assert_eq!(token.amount, 0);
+
+    // Creating it again fails.
+    env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+    assert_eq!(
+        env.process(&[create], &[&account]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    // An address already holding lamports is topped up to the reserve.
+    let prefunded = Keypair::new();
+    env.process(
+        &[system_instruction::transfer(
+            &payer,
+            &prefunded.pubkey(),
+            rent.minimum_balance(0),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let create = instruction::create_token_account(
+        &program_id,
+        &payer,
+        &prefunded.pubkey(),
+        &mint.pubkey(),
+        &owner,
+    )
+    .unwrap();
+    env.process(&[create], &[&prefunded]).await.unwrap();
+    let created = env.get_account(&prefunded.pubkey()).await;
+    assert_eq!(created.owner, program_id);
+    assert_eq!(created.lamports, reserve);
+    assert_eq!(env.get_token(&prefunded.pubkey()).await.owner, owner);
+}
+
+#[tokio::test]
+async fn create_token_account_signs_for_seeded_addresses() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let owner = Pubkey::new_unique();
+    env.create_mint(&mint, &Pubkey::new_unique()).await;
+    let payer = env.payer.pubkey();
+    let reserve = env
+        .banks_client
+        .get_rent()
+        .await
+        .unwrap()
+        .minimum_balance(Token::LEN);
+
+    // Each seed is a distinct account of the owner.
+    for seed in [0, 1] {
+        let create = instruction::create_seeded_token_account(
+            &program_id,
+            &payer,
+            &owner,
+            &mint.pubkey(),
+            seed,
+        )
+        .unwrap();
+        let address = get_seeded_token_address(&owner, &mint.pubkey(), seed, &program_id);
+        assert_eq!(create.accounts[1].pubkey, address);
+        env.process(&[create], &[]).await.unwrap();
+        let token = env.get_token(&address).await;
+        assert_eq!(token.owner, owner);
+        assert_eq!(token.mint, mint.pubkey());
+    }
+
+    // The address must match the seed.
+    let mut mismatched =
+        instruction::create_seeded_token_account(&program_id, &payer, &owner, &mint.pubkey(), 2)
+            .unwrap();
+    mismatched.accounts[1].pubkey =
+        get_seeded_token_address(&owner, &mint.pubkey(), 3, &program_id);
+    assert_eq!(
+        env.process(&[mismatched], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+
+    // A pre-funded native account wraps the lamports above the reserve.
+    let address = get_seeded_token_address(&owner, &native_mint::id(), 0, &program_id);
+    env.process(
+        &[system_instruction::transfer(
+            &payer,
+            &address,
+            reserve + 500,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let create = instruction::create_seeded_token_account(
+        &program_id,
+        &payer,
+        &owner,
+        &native_mint::id(),
+        0,
+    )
+    .unwrap();
+    env.process(&[create], &[]).await.unwrap();
+    let created = env.get_account(&address).await;
+    assert_eq!(created.owner, program_id);
+    assert_eq!(created.lamports, reserve + 500);
+    let token = env.get_token(&address).await;
+    assert_eq!(token.is_native, COption::Some(reserve));
+    assert_eq!(token.amount, 500);
+}
+
+#[tokio::test]
+async fn propose_then_accept_owner() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let first_candidate = Keypair::new();
+    let candidate = Keypair::new();
+
+    let propose = |new_owner: &Pubkey| {
+        instruction::propose_owner(
+            &program_id,
+            &source.pubkey(),
+            &owner.pubkey(),
+            &[],
+            new_owner,
+        )
+        .unwrap()
+    };
+    env.process(&[propose(&first_candidate.pubkey())], &[&owner])
+        .await
+        .unwrap();
+    // A later proposal replaces the earlier one.
+    env.process(&[propose(&candidate.pubkey())], &[&owner])
+        .await
+        .unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.owner, owner.pubkey());
+    assert_eq!(token.pending_owner, COption::Some(candidate.pubkey()));
+
+    let accept = |pending_owner: &Pubkey| {
+        instruction::accept_owner(&program_id, &source.pubkey(), pending_owner, &[]).unwrap()
+    };
+    assert_eq!(
+        env.process(&[accept(&first_candidate.pubkey())], &[&first_candidate])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+
+    env.process(&[accept(&candidate.pubkey())], &[&candidate])
+        .await
+        .unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.owner, candidate.pubkey());
+    assert_eq!(token.pending_owner, COption::None);
+}
+
+#[tokio::test]
+async fn transfer_account_ownership_needs_both_owners_and_clears_delegation() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let delegate = Keypair::new();
+    let candidate = Keypair::new();
+    let buyer = Keypair::new();
+
+    let approve = instruction::approve(
+        &program_id,
+        &source.pubkey(),
+        &delegate.pubkey(),
+        &owner.pubkey(),
+        &[],
+        40,
+    )
+    .unwrap();
+    let propose = instruction::propose_owner(
+        &program_id,
+        &source.pubkey(),
+        &owner.pubkey(),
+        &[],
+        &candidate.pubkey(),
+    )
+    .unwrap();
+    env.process(&[approve, propose], &[&owner]).await.unwrap();
+
+    let hand_over = instruction::transfer_account_ownership(
+        &program_id,
+        &source.pubkey(),
+        &owner.pubkey(),
+        &buyer.pubkey(),
+        &[],
+    )
+    .unwrap();
+    // Each signature is required; the other party signing alone isn't enough.
+    for (unsigned, signer) in [(1, &buyer), (2, &owner)] {
+        let mut unsigned_hand_over = hand_over.clone();
+        unsigned_hand_over.accounts[unsigned].is_signer = false;
+        assert_eq!(
+            env.process(&[unsigned_hand_over], &[signer])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+        );
+    }
+    assert_eq!(env.get_token(&source.pubkey()).await.owner, owner.pubkey());
+
+    assert_eq!(
+        env.process_events(&[hand_over], &[&owner, &buyer])
+            .await
+            .unwrap(),
+        vec![TokenEvent::AuthorityChanged(AuthorityChangedEvent {
+            account: source.pubkey(),
+            authority_type: instruction::AuthorityType::AccountOwner,
+            old: COption::Some(owner.pubkey()),
+            new: COption::Some(buyer.pubkey()),
+        })]
+    );
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.owner, buyer.pubkey());
+    assert_eq!(token.amount, 100);
+    assert_eq!(token.delegate, COption::None);
+    assert_eq!(token.delegated_amount, 0);
+    assert_eq!(token.pending_owner, COption::None);
+
+    // Neither the old delegate nor the old pending owner keeps any hold.
+    let spend = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &source.pubkey(),
+        &delegate.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[spend], &[&delegate]).await.unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    let accept =
+        instruction::accept_owner(&program_id, &source.pubkey(), &candidate.pubkey(), &[]).unwrap();
+    assert_eq!(
+        env.process(&[accept], &[&candidate]).await.unwrap_err(),
+        custom(TokenError::InvalidState)
+    );
+}
+
+#[tokio::test]
+async fn pending_owner_survives_transfers_and_can_be_cancelled() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        owner,
+        source,
+        destination,
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let candidate = Keypair::new();
+
+    let propose = instruction::propose_owner(
+        &program_id,
+        &source.pubkey(),
+        &owner.pubkey(),
+        &[],
+        &candidate.pubkey(),
+    )
+    .unwrap();
+    env.process(&[propose], &[&owner]).await.unwrap();
+
+    let transfer_out = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        30,
+    )
+    .unwrap();
+    let mint_in = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        5,
+    )
+    .unwrap();
+    env.process(&[transfer_out, mint_in], &[&owner, &mint_authority])
+        .await
+        .unwrap();
+    let token = env.get_token(&source.pubkey()).await;
+    assert_eq!(token.amount, 75);
+    assert_eq!(token.pending_owner, COption::Some(candidate.pubkey()));
+
+    let cancel =
+        instruction::cancel_pending_owner(&program_id, &source.pubkey(), &owner.pubkey(), &[])
+            .unwrap();
+    env.process(&[cancel], &[&owner]).await.unwrap();
+    assert_eq!(
+        env.get_token(&source.pubkey()).await.pending_owner,
+        COption::None
+    );
+
+    let accept =
+        instruction::accept_owner(&program_id, &source.pubkey(), &candidate.pubkey(), &[]).unwrap();
+    assert_eq!(
+        env.process(&[accept], &[&candidate]).await.unwrap_err(),
+        custom(TokenError::InvalidState)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.owner, owner.pubkey());
+}
+
+#[tokio::test]
+async fn balance_and_supply_are_returned() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(1_000).await;
+    let program_id = env.program_id;
+
+    let transfer = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        250,
+    )
+    .unwrap();
+    env.process(&[transfer], &[&owner]).await.unwrap();
+
+    let get_balance =
+        |account: &Pubkey| instruction::get_account_balance(&program_id, account).unwrap();
+    assert_eq!(
+        env.simulate_returned_amount(get_balance(&source.pubkey()))
+            .await,
+        Ok(750)
+    );
+    assert_eq!(
+        env.simulate_returned_amount(get_balance(&destination.pubkey()))
+            .await,
+        Ok(250)
+    );
+    let get_supply = instruction::get_mint_supply(&program_id, &mint.pubkey()).unwrap();
+    assert_eq!(env.simulate_returned_amount(get_supply).await, Ok(1_000));
+}
+
+#[tokio::test]
+async fn balance_of_uninitialized_account_fails() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let account = Keypair::new();
+    env.create_account(&account, Token::LEN, &program_id).await;
+
+    let get_balance = instruction::get_account_balance(&program_id, &account.pubkey()).unwrap();
+    assert_eq!(
+        env.simulate_returned_amount(get_balance).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::UninitializedAccount
+        ))
+    );
+}
+
+#[test]
+fn amount_to_ui_amount_formats() {
+    assert_eq!(amount_to_ui_amount(0, 0), "0");
+    assert_eq!(amount_to_ui_amount(0, 9), "0");
+    assert_eq!(amount_to_ui_amount(1, 9), "0.000000001");
+    assert_eq!(amount_to_ui_amount(1_500_000_000, 9), "1.5");
+    assert_eq!(amount_to_ui_amount(2_000_000_000, 9), "2");
+    assert_eq!(amount_to_ui_amount(42, 0), "42");
+    assert_eq!(amount_to_ui_amount(u64::MAX, 0), "18446744073709551615");
+    assert_eq!(amount_to_ui_amount(u64::MAX, 9), "18446744073.709551615");
+}
+
+#[test]
+fn ui_amount_into_amount_parses() {
+    assert_eq!(try_ui_amount_into_amount("0", 0), Ok(0));
+    assert_eq!(try_ui_amount_into_amount("0", 9), Ok(0));
+    assert_eq!(try_ui_amount_into_amount(".5", 9), Ok(500_000_000));
+    assert_eq!(try_ui_amount_into_amount("1.", 9), Ok(1_000_000_000));
+    assert_eq!(
+        try_ui_amount_into_amount("1.500000000000", 9),
+        Ok(1_500_000_000)
+    );
+    assert_eq!(try_ui_amount_into_amount("7.000", 0), Ok(7));
+    assert_eq!(
+        try_ui_amount_into_amount("18446744073709551615", 0),
+        Ok(u64::MAX)
+    );
+    assert_eq!(
+        try_ui_amount_into_amount("18446744073.709551615", 9),
+        Ok(u64::MAX)
+    );
+
+    for (ui_amount, decimals) in [
+        ("", 9),
+        (".", 9),
+        ("1.5", 0),
+        ("0.0000000001", 9),
+        ("18446744073709551616", 0),
+        ("18446744073.709551616", 9),
+        ("-1", 9),
+        ("+1", 9),
+        ("1.2.3", 9),
+        ("1,5", 9),
+    ] {
+        assert_eq!(
+            try_ui_amount_into_amount(ui_amount, decimals),
+            Err(ProgramError::InvalidArgument),
+            "{ui_amount:?} with {decimals} decimals"
+        );
+    }
+
+    for amount in [0, 1, 1_500_000_000, u64::MAX] {
+        for decimals in [0, 6, 9] {
+            let ui_amount = amount_to_ui_amount(amount, decimals);
+            assert_eq!(try_ui_amount_into_amount(&ui_amount, decimals), Ok(amount));
+        }
+    }
+}
+
+#[tokio::test]
+async fn ui_amount_conversions_use_mint_decimals() {
+    let Fixture { mut env, mint, .. } = setup(0).await;
+    let program_id = env.program_id;
+
+    let to_ui = instruction::amount_to_ui_amount(&program_id, &mint.pubkey(), 1_250_000).unwrap();
+    assert_eq!(env.simulate_return_data(to_ui).await.unwrap(), b"1.25");
+
+    let from_ui = instruction::ui_amount_to_amount(&program_id, &mint.pubkey(), "1.25").unwrap();
+    assert_eq!(env.simulate_returned_amount(from_ui).await, Ok(1_250_000));
+
+    let too_precise =
+        instruction::ui_amount_to_amount(&program_id, &mint.pubkey(), "0.0000001").unwrap();
+    assert_eq!(
+        env.simulate_returned_amount(too_precise).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+}
+
+#[tokio::test]
+async fn memo_required_account_rejects_transfers_without_memo() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let exchange = Keypair::new();
+    let deposit = Keypair::new();
+    env.create_token_account(&deposit, &mint.pubkey(), &exchange.pubkey())
+        .await;
+    let payer = env.payer.pubkey();
+    env.process(
+        &[instruction::require_memo(
+            &program_id,
+            &deposit.pubkey(),
+            &exchange.pubkey(),
+            &[],
+            &payer,
+        )
+        .unwrap()],
+        &[&exchange],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.get_extension::<MemoTransfer>(&deposit.pubkey()).await,
+        Some(MemoTransfer {
+            require_incoming_transfer_memos: true
+        })
+    );
+
+    let transfer = |amount, with_sysvar| {
+        let mut ix = instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &deposit.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        if with_sysvar {
+            ix.accounts
+                .push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+        }
+        ix
+    };
+    let memo_ix = |text: &str| Instruction {
+        program_id: memo::id(),
+        accounts: vec![],
+        data: text.as_bytes().to_vec(),
+    };
+    let memo_required = |index| {
+        TransactionError::InstructionError(
+            index,
+            InstructionError::Custom(TokenError::MemoRequired as u32),
+        )
+    };
+
+    assert_eq!(
+        env.process(&[transfer(1, false)], &[&owner])
+            .await
+            .unwrap_err(),
+        memo_required(0)
+    );
+    assert_eq!(
+        env.process(&[transfer(1, true)], &[&owner])
+            .await
+            .unwrap_err(),
+        memo_required(0)
+    );
+    // The memo must come immediately before the transfer.
+    assert_eq!(
+        env.process(
+            &[
+                memo_ix("deposit 1"),
+                instruction::get_account_balance(&program_id, &source.pubkey()).unwrap(),
+                transfer(2, true),
+            ],
+            &[&owner]
+        )
+        .await
+        .unwrap_err(),
+        memo_required(2)
+    );
+
+    env.process(&[memo_ix("deposit 2"), transfer(3, true)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&deposit.pubkey()).await.amount, 3);
+
+    env.process(
+        &[
+            instruction::unrequire_memo(&program_id, &deposit.pubkey(), &exchange.pubkey(), &[])
+                .unwrap(),
+        ],
+        &[&exchange],
+    )
+    .await
+    .unwrap();
+    env.process(&[transfer(4, false)], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&deposit.pubkey()).await.amount, 7);
+}
+
+/// Example transfer hook: rejects transfers above the `u64` threshold held
+/// by its one extra account.
+fn threshold_hook(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let amount = transfer_hook::unpack_execute(instruction_data)?;
+    let threshold_account = accounts.get(3).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let threshold = u64::from_le_bytes(
+        threshold_account.data.borrow()[..8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if amount > threshold {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn transfer_hook_can_reject_transfers() {
+    let hook_program_id = Pubkey::new_unique();
+    let threshold = Pubkey::new_unique();
+    let mut env = start_with(|program_test| {
+        program_test.add_program(
+            "threshold_hook",
+            hook_program_id,
+            processor!(threshold_hook),
+        );
+        program_test.add_account(
+            threshold,
+            Account {
+                lamports: 1_000_000,
+                data: 50u64.to_le_bytes().to_vec(),
+                owner: hook_program_id,
+                ..Account::default()
+            },
+        );
+    })
+    .await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let owner = Keypair::new();
+    let source = Keypair::new();
+    let destination = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    assert_eq!(
+        env.process(
+            &[
+                instruction::initialize_transfer_hook(&program_id, &mint.pubkey(), &program_id)
+                    .unwrap()
+            ],
+            &[]
+        )
+        .await
+        .unwrap_err(),
+        custom(TokenError::InvalidTransferHook)
+    );
+    let configure =
+        instruction::initialize_transfer_hook(&program_id, &mint.pubkey(), &hook_program_id)
+            .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        None,
+        0,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    assert_eq!(
+        env.get_mint(&mint.pubkey()).await.transfer_hook_program,
+        COption::Some(hook_program_id)
+    );
+    env.create_token_account(&source, &mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_token_account(&destination, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    assert!(env.get_token(&source.pubkey()).await.has_transfer_hook);
+    let mint_to = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        200,
+    )
+    .unwrap();
+    env.process(&[mint_to], &[&mint_authority]).await.unwrap();
+
+    let transfer = |amount, with_hook| {
+        let mut ix = instruction::transfer_checked(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+            0,
+        )
+        .unwrap();
+        if with_hook {
+            transfer_hook::add_hook_accounts(
+                &mut ix,
+                &hook_program_id,
+                &[AccountMeta::new_readonly(threshold, false)],
+            );
+        }
+        ix
+    };
+
+    env.process(&[transfer(50, true)], &[&owner]).await.unwrap();
+    assert_eq!(
+        env.process(&[transfer(51, true)], &[&owner])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+    assert_eq!(
+        env.process(&[transfer(10, false)], &[&owner])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+    let unchecked = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[unchecked], &[&owner]).await.unwrap_err(),
+        custom(TokenError::MintRequiredForTransfer)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 150);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 50);
+}
+
+#[tokio::test]
+async fn default_frozen_mint_creates_frozen_accounts() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let freeze_authority = Keypair::new();
+    let first = Keypair::new();
+    let wallet = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure = instruction::initialize_default_account_state(
+        &program_id,
+        &mint.pubkey(),
+        AccountState::Frozen,
+    )
+    .unwrap();
+    let initialize = |freeze_authority: Option<&Pubkey>| {
+        instruction::initialize_mint(
+            &program_id,
+            &mint.pubkey(),
+            &mint_authority.pubkey(),
+            freeze_authority,
+            0,
+            None,
+        )
+        .unwrap()
+    };
+    // Accounts would be bricked without a freeze authority to thaw them.
+    assert_eq!(
+        env.process(&[configure.clone(), initialize(None)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(TokenError::MintCannotFreeze as u32)
+        )
+    );
+    env.process(
+        &[configure, initialize(Some(&freeze_authority.pubkey()))],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    env.create_token_account(&first, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    let create_associated = instruction::create_associated_account(
+        &program_id,
+        &env.payer.pubkey(),
+        &wallet.pubkey(),
+        &mint.pubkey(),
+    )
+    .unwrap();
+    let associated = create_associated.accounts[1].pubkey;
+    env.process(&[create_associated], &[]).await.unwrap();
+    assert_eq!(
+        env.get_token(&first.pubkey()).await.state,
+        AccountState::Frozen
+    );
+    assert_eq!(env.get_token(&associated).await.state, AccountState::Frozen);
+
+    let thaw = instruction::thaw_account(
+        &program_id,
+        &first.pubkey(),
+        &mint.pubkey(),
+        &freeze_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    env.process(&[thaw], &[&freeze_authority]).await.unwrap();
+    assert_eq!(
+        env.get_token(&first.pubkey()).await.state,
+        AccountState::Initialized
+    );
+
+    let remove_freeze_authority = instruction::set_authority(
+        &program_id,
+        &mint.pubkey(),
+        None,
+        instruction::AuthorityType::FreezeAccount,
+        &freeze_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[remove_freeze_authority], &[&freeze_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::MintCannotFreeze)
+    );
+
+    let set_default = |authority: &Pubkey| {
+        instruction::set_default_account_state(
+            &program_id,
+            &mint.pubkey(),
+            authority,
+            &[],
+            AccountState::Initialized,
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        env.process(&[set_default(&mint_authority.pubkey())], &[&mint_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    env.process(
+        &[set_default(&freeze_authority.pubkey())],
+        &[&freeze_authority],
+    )
+    .await
+    .unwrap();
+    let second = Keypair::new();
+    env.create_token_account(&second, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    assert_eq!(
+        env.get_token(&second.pubkey()).await.state,
+        AccountState::Initialized
+    );
+    // Existing accounts keep their state.
+    assert_eq!(env.get_token(&associated).await.state, AccountState::Frozen);
+}
+
+#[tokio::test]
+async fn immutable_owner_blocks_every_owner_change() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        ..
+    } = setup(0).await;
+    let program_id = env.program_id;
+    let deposit = Keypair::new();
+    let new_owner = Keypair::new();
+
+    env.create_account(&deposit, Token::LEN, &program_id).await;
+    let payer = env.payer.pubkey();
+    let configure =
+        instruction::initialize_immutable_owner(&program_id, &deposit.pubkey(), &payer).unwrap();
+    let initialize = instruction::initialize_account(
+        &program_id,
+        &deposit.pubkey(),
+        &mint.pubkey(),
+        &owner.pubkey(),
+    )
+    .unwrap();
+    env.process(&[configure.clone(), initialize], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.get_extension::<ImmutableOwner>(&deposit.pubkey()).await,
+        Some(ImmutableOwner)
+    );
+    assert_eq!(
+        env.process(&[configure], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    let set_owner = |account: &Pubkey| {
+        instruction::set_authority(
+            &program_id,
+            account,
+            Some(&new_owner.pubkey()),
+            instruction::AuthorityType::AccountOwner,
+            &owner.pubkey(),
+            &[],
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        env.process(&[set_owner(&deposit.pubkey())], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::ImmutableOwner)
+    );
+    let propose = instruction::propose_owner(
+        &program_id,
+        &deposit.pubkey(),
+        &owner.pubkey(),
+        &[],
+        &new_owner.pubkey(),
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[propose], &[&owner]).await.unwrap_err(),
+        custom(TokenError::ImmutableOwner)
+    );
+    let hand_over = instruction::transfer_account_ownership(
+        &program_id,
+        &deposit.pubkey(),
+        &owner.pubkey(),
+        &new_owner.pubkey(),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[hand_over], &[&owner, &new_owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::ImmutableOwner)
+    );
+    assert_eq!(env.get_token(&deposit.pubkey()).await.owner, owner.pubkey());
+
+    // An ordinary account of the same owner is unaffected.
+    env.process(&[set_owner(&source.pubkey())], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.get_token(&source.pubkey()).await.owner,
+        new_owner.pubkey()
+    );
+}
+
+#[tokio::test]
+async fn enabling_extensions_grows_the_account_once() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let rent = env.banks_client.get_rent().await.unwrap();
+    let require_memo =
+        instruction::require_memo(&program_id, &source.pubkey(), &owner.pubkey(), &[], &payer)
+            .unwrap();
+    let lock = |lock_until| {
+        instruction::lock_account(
+            &program_id,
+            &source.pubkey(),
+            &owner.pubkey(),
+            &[],
+            &payer,
+            lock_until,
+        )
+        .unwrap()
+    };
+
+    // Growing needs the payer and the system program as the last accounts.
+    let mut without_payer = require_memo.clone();
+    without_payer.accounts.truncate(2);
+    assert_eq!(
+        env.process(&[without_payer], &[&owner]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+
+    env.process(&[require_memo], &[&owner]).await.unwrap();
+    env.process(&[lock(-1)], &[&owner]).await.unwrap();
+    let account = env.get_account(&source.pubkey()).await;
+    let len = Token::LEN
+        + extension::HEADER_LEN
+        + MemoTransfer::LEN
+        + extension::HEADER_LEN
+        + AccountLock::LEN;
+    assert_eq!(account.data.len(), len);
+    assert!(rent.is_exempt(account.lamports, len));
+    assert_eq!(
+        extension::get_extension_types(&account.data).unwrap(),
+        [ExtensionType::MemoTransfer, ExtensionType::AccountLock]
+    );
+
+    // Updating an extension the account holds doesn't grow it again.
+    env.process(&[lock(0)], &[&owner]).await.unwrap();
+    env.process(
+        &[
+            instruction::unrequire_memo(&program_id, &source.pubkey(), &owner.pubkey(), &[])
+                .unwrap(),
+        ],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.get_account(&source.pubkey()).await.data.len(), len);
+    assert_eq!(
+        env.get_extension::<MemoTransfer>(&source.pubkey()).await,
+        Some(MemoTransfer::default())
+    );
+
+    // The extended account works as before.
+    env.process(
+        &[instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            40,
+        )
+        .unwrap()],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 60);
+    assert_eq!(
+        env.get_extension::<AccountLock>(&source.pubkey()).await,
+        Some(AccountLock { lock_until: 0 })
+    );
+}
+
+#[test]
+fn interest_scale_compounds_continuously() {
+    let year = interest::SECONDS_PER_YEAR;
+    let rate = |current_rate_bps| InterestRate {
+        current_rate_bps,
+        last_update_timestamp: 1_000,
+        accrued_scale: interest::WAD,
+    };
+    let close_to = |scale: u128, expected: u128| scale.abs_diff(expected) < 1_000_000;
+
+    assert_eq!(rate(0).scale_at(1_000), Ok(interest::WAD));
+    assert_eq!(rate(0).scale_at(1_000 + 100 * year), Ok(interest::WAD));
+    assert!(close_to(
+        rate(10_000).scale_at(1_000 + year).unwrap(),
+        2_718_281_828_459_045_235
+    ));
+    assert!(close_to(
+        rate(-10_000).scale_at(1_000 + year).unwrap(),
+        367_879_441_171_442_321
+    ));
+    // e^(3.2767 * 10) is still representable; growth is monotonic.
+    let large = rate(i16::MAX).scale_at(1_000 + 10 * year).unwrap();
+    assert!(large > rate(i16::MAX).scale_at(1_000 + 9 * year).unwrap());
+    assert!(large / interest::WAD > 10u128.pow(14));
+    assert_eq!(rate(i16::MIN).scale_at(1_000 + 100 * year), Ok(0));
+    assert_eq!(
+        rate(i16::MAX).scale_at(1_000 + 100 * year),
+        Err(TokenError::Overflow)
+    );
+    assert_eq!(
+        rate(i16::MAX).scale_at(i64::MIN),
+        Err(TokenError::InvalidTimestamp)
+    );
+    assert_eq!(rate(0).scale_at(999), Err(TokenError::InvalidTimestamp));
+}
+
+#[tokio::test]
+async fn interest_bearing_mint_scales_ui_amounts() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let rate_authority = Keypair::new();
+    let start_time = 1_700_000_000;
+    let year = interest::SECONDS_PER_YEAR;
+
+    env.set_unix_timestamp(start_time).await;
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure = instruction::initialize_interest_bearing_mint(
+        &program_id,
+        &mint.pubkey(),
+        Some(&rate_authority.pubkey()),
+        10_000,
+    )
+    .unwrap();
+    let initialize =
+        instruction::initialize_mint(&program_id, &mint.pubkey(), &mint.pubkey(), None, 6, None)
+            .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+
+    let to_ui =
+        |amount| instruction::amount_to_ui_amount(&program_id, &mint.pubkey(), amount).unwrap();
+    let from_ui =
+        |ui_amount| instruction::ui_amount_to_amount(&program_id, &mint.pubkey(), ui_amount);
+    assert_eq!(
+        env.simulate_return_data(to_ui(1_000_000)).await.unwrap(),
+        b"1"
+    );
+
+    env.set_unix_timestamp(start_time + year).await;
+    assert_eq!(
+        env.simulate_return_data(to_ui(1_000_000)).await.unwrap(),
+        b"2.718281"
+    );
+    assert_eq!(
+        env.simulate_returned_amount(from_ui("2.718282").unwrap())
+            .await,
+        Ok(1_000_000)
+    );
+
+    // Interest already accrued is kept at the new rate.
+    let update = instruction::update_rate(
+        &program_id,
+        &mint.pubkey(),
+        &rate_authority.pubkey(),
+        &[],
+        0,
+    )
+    .unwrap();
+    env.process(&[update], &[&rate_authority]).await.unwrap();
+    env.set_unix_timestamp(start_time + 10 * year).await;
+    assert_eq!(
+        env.simulate_return_data(to_ui(1_000_000)).await.unwrap(),
+        b"2.718281"
+    );
+
+    // A clock behind the last update is rejected.
+    env.set_unix_timestamp(start_time).await;
+    assert_eq!(
+        env.simulate_return_data(to_ui(1_000_000)).await,
+        Err(custom(TokenError::InvalidTimestamp))
+    );
+
+    let wrong_authority =
+        instruction::update_rate(&program_id, &mint.pubkey(), &env.payer.pubkey(), &[], 100)
+            .unwrap();
+    assert_eq!(
+        env.process(&[wrong_authority], &[]).await.unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+}
+
+/// Creates a mint with a close authority and a token account holding
+/// `amount` of it, owned by `owner`.
+async fn setup_closable_mint(
+    env: &mut Env,
+    close_authority: &Pubkey,
+    owner: &Keypair,
+    amount: u64,
+) -> (Keypair, Keypair) {
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let account = Keypair::new();
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure = instruction::initialize_mint_close_authority(
+        &program_id,
+        &mint.pubkey(),
+        Some(close_authority),
+    )
+    .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &env.payer.pubkey(),
+        None,
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    env.create_token_account(&account, &mint.pubkey(), &owner.pubkey())
+        .await;
+    let mint_tokens = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &account.pubkey(),
+        &env.payer.pubkey(),
+        &[],
+        amount,
+    )
+    .unwrap();
+    env.process(&[mint_tokens], &[]).await.unwrap();
+    (mint, account)
+}
+
+#[tokio::test]
+async fn close_mint_with_supply_is_rejected() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let close_authority = Keypair::new();
+    let owner = Keypair::new();
+    let (mint, _account) =
+        setup_closable_mint(&mut env, &close_authority.pubkey(), &owner, 50).await;
+
+    let close = instruction::close_mint(
+        &program_id,
+        &mint.pubkey(),
+        &env.payer.pubkey(),
+        &close_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[close], &[&close_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::MintHasSupply)
+    );
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 50);
+}
+
+#[tokio::test]
+async fn burned_out_mint_can_be_closed() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let close_authority = Keypair::new();
+    let new_close_authority = Keypair::new();
+    let owner = Keypair::new();
+    let destination = Pubkey::new_unique();
+    let (mint, account) =
+        setup_closable_mint(&mut env, &close_authority.pubkey(), &owner, 50).await;
+
+    let rotate = instruction::set_authority(
+        &program_id,
+        &mint.pubkey(),
+        Some(&new_close_authority.pubkey()),
+        instruction::AuthorityType::CloseMint,
+        &close_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    env.process(&[rotate], &[&close_authority]).await.unwrap();
+    let burn = instruction::burn(
+        &program_id,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &owner.pubkey(),
+        &[],
+        instruction::AMOUNT_ALL,
+    )
+    .unwrap();
+    env.process(&[burn], &[&owner]).await.unwrap();
+
+    let close = |close_authority: &Pubkey| {
+        instruction::close_mint(
+            &program_id,
+            &mint.pubkey(),
+            &destination,
+            close_authority,
+            &[],
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        env.process(&[close(&close_authority.pubkey())], &[&close_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    let mint_lamports = env.get_account(&mint.pubkey()).await.lamports;
+    env.process(
+        &[close(&new_close_authority.pubkey())],
+        &[&new_close_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.get_account(&destination).await.lamports, mint_lamports);
+    assert!(env
+        .banks_client
+        .get_account(mint.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+
+    let late_account = Keypair::new();
+    env.create_account(&late_account, Token::LEN, &program_id)
+        .await;
+    let initialize = instruction::initialize_account(
+        &program_id,
+        &late_account.pubkey(),
+        &mint.pubkey(),
+        &owner.pubkey(),
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[initialize], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+}
+
+#[test]
+fn legacy_layouts_unpack_only_through_any_version() {
+    let mint = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut legacy = 500u64.to_le_bytes().to_vec();
+    legacy.extend_from_slice(mint.as_ref());
+    legacy.extend_from_slice(owner.as_ref());
+
+    assert_eq!(
+        Token::unpack(&legacy),
+        Err(ProgramError::InvalidAccountData)
+    );
+    let token = Token::unpack_any_version(&legacy).unwrap();
+    assert_eq!(
+        (token.amount, token.mint, token.owner, token.state),
+        (500, mint, owner, AccountState::Initialized)
+    );
+    assert_eq!(
+        Token::unpack_any_version(&[0; Token::LEGACY_LEN])
+            .unwrap()
+            .state,
+        AccountState::Uninitialized
+    );
+
+    let mut data = vec![0; Token::LEN];
+    token.pack_into_slice(&mut data);
+    assert_eq!(
+        data[..2],
+        [
+            AccountType::Token as u8,
+            solana_contracts::state::ACCOUNT_VERSION
+        ]
+    );
+    assert_eq!(Token::unpack_any_version(&data), Ok(token));
+}
+
+#[tokio::test]
+async fn migrate_account_rewrites_legacy_layouts() {
+    let mint = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let owner = Keypair::new();
+    let mint_authority = Keypair::new();
+    let rent = Rent::default();
+
+    let mut legacy_mint = vec![1, 0, 0, 0];
+    legacy_mint.extend_from_slice(mint_authority.pubkey().as_ref());
+    legacy_mint.extend_from_slice(&500u64.to_le_bytes());
+    legacy_mint.extend_from_slice(&[6, 1]);
+    let mut legacy_token = 500u64.to_le_bytes().to_vec();
+    legacy_token.extend_from_slice(mint.as_ref());
+    legacy_token.extend_from_slice(owner.pubkey().as_ref());
+
+    let program_id = Pubkey::new_unique();
+    let mut env = {
+        let mut program_test = ProgramTest::new(
+            "solana_contracts",
+            program_id,
+            processor!(process_instruction),
+        );
+        for (pubkey, data) in [(mint, legacy_mint), (account, legacy_token)] {
+            program_test.add_account(
+                pubkey,
+                Account {
+                    lamports: rent.minimum_balance(data.len()),
+                    data,
+                    owner: program_id,
+                    ..Account::default()
+                },
+            );
+        }
+        let context = program_test.start_with_context().await;
+        Env {
+            program_id,
+            banks_client: context.banks_client.clone(),
+            payer: context.payer.insecure_clone(),
+            recent_blockhash: context.last_blockhash,
+            context,
+        }
+    };
+
+    let destination = Keypair::new();
+    let transfer = instruction::transfer_tokens(
+        &program_id,
+        &account,
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    let approve = instruction::approve(
+        &program_id,
+        &account,
+        &Pubkey::new_unique(),
+        &owner.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[approve], &[&owner]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    let payer = env.payer.pubkey();
+    env.process(
+        &[
+            instruction::migrate_account(&program_id, &mint, &payer).unwrap(),
+            instruction::migrate_account(&program_id, &account, &payer).unwrap(),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let migrated_mint = env.get_account(&mint).await;
+    assert_eq!(migrated_mint.data.len(), Mint::LEN);
+    assert_eq!(migrated_mint.lamports, rent.minimum_balance(Mint::LEN));
+    let migrated_mint = env.get_mint(&mint).await;
+    assert_eq!(
+        migrated_mint.mint_authority,
+        COption::Some(mint_authority.pubkey())
+    );
+    assert_eq!((migrated_mint.supply, migrated_mint.decimals), (500, 6));
+    let token = env.get_token(&account).await;
+    assert_eq!(env.get_account(&account).await.data.len(), Token::LEN);
+    assert_eq!(
+        (token.amount, token.mint, token.owner, token.state),
+        (500, mint, owner.pubkey(), AccountState::Initialized)
+    );
+
+    // Migrated accounts work as usual and can't be migrated again.
+    env.create_token_account(&destination, &mint, &Pubkey::new_unique())
+        .await;
+    env.process(&[transfer], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&account).await.amount, 400);
+    assert_eq!(
+        env.process(
+            &[instruction::migrate_account(&program_id, &account, &payer).unwrap()],
+            &[]
+        )
+        .await
+        .unwrap_err(),
+        custom(TokenError::InvalidState)
+    );
+}
+
+#[tokio::test]
+async fn a_separate_payer_funds_accounts_for_an_owner_without_lamports() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    env.create_mint(&mint, &Pubkey::new_unique()).await;
+    let rent = Rent::default();
+    let funder = Keypair::new();
+    let owner = Keypair::new();
+    let payer = env.payer.pubkey();
+    env.process(
+        &[system_instruction::transfer(
+            &payer,
+            &funder.pubkey(),
+            10 * rent.minimum_balance(extension::MAX_ACCOUNT_LEN),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // The owner signs only to require memos, and holds nothing.
+    let account = Keypair::new();
+    env.process(
+        &[
+            instruction::create_token_account(
+                &program_id,
+                &funder.pubkey(),
+                &account.pubkey(),
+                &mint.pubkey(),
+                &owner.pubkey(),
+            )
+            .unwrap(),
+            instruction::create_associated_account(
+                &program_id,
+                &funder.pubkey(),
+                &owner.pubkey(),
+                &mint.pubkey(),
+            )
+            .unwrap(),
+            instruction::require_memo(
+                &program_id,
+                &account.pubkey(),
+                &owner.pubkey(),
+                &[],
+                &funder.pubkey(),
+            )
+            .unwrap(),
+        ],
+        &[&funder, &account, &owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.get_token(&account.pubkey()).await.owner, owner.pubkey());
+    let associated = get_associated_token_address(&owner.pubkey(), &mint.pubkey(), &program_id);
+    assert_eq!(env.get_token(&associated).await.owner, owner.pubkey());
+    assert_eq!(
+        env.get_extension::<MemoTransfer>(&account.pubkey()).await,
+        Some(MemoTransfer {
+            require_incoming_transfer_memos: true
+        })
+    );
+    assert!(env
+        .banks_client
+        .get_account(owner.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+
+    // A payer short of the rent, or that didn't sign, fails before the
+    // system program is asked to move anything.
+    let poor = Keypair::new();
+    env.process(
+        &[system_instruction::transfer(
+            &payer,
+            &poor.pubkey(),
+            rent.minimum_balance(0),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let wallet = Pubkey::new_unique();
+    assert_eq!(
+        env.process(
+            &[instruction::create_associated_account(
+                &program_id,
+                &poor.pubkey(),
+                &wallet,
+                &mint.pubkey(),
+            )
+            .unwrap()],
+            &[&poor],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InsufficientFunds)
+    );
+    let mut unsigned = instruction::create_associated_account(
+        &program_id,
+        &funder.pubkey(),
+        &wallet,
+        &mint.pubkey(),
+    )
+    .unwrap();
+    unsigned.accounts[0].is_signer = false;
+    assert_eq!(
+        env.process(&[unsigned], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn migrate_account_adds_account_types() {
+    let account = Pubkey::new_unique();
+    let escrow = Pubkey::new_unique();
+    let pre_deposit_escrow = Pubkey::new_unique();
+    let token = Token {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount: 500,
+        state: AccountState::Initialized,
+        ..Token::default()
+    };
+    let escrow_state = Escrow {
+        is_initialized: true,
+        maker: Pubkey::new_unique(),
+        expected_amount: 7,
+        ..Escrow::default()
+    };
+
+    // A version 2 token account led with its version instead of a header;
+    // other accounts had no header at all.
+    let mut current = vec![0; Token::LEN];
+    token.pack_into_slice(&mut current);
+    let mut version_2_token = vec![2];
+    version_2_token.extend_from_slice(&current[2..]);
+    let mut current = vec![0; Escrow::LEN];
+    escrow_state.pack_into_slice(&mut current);
+    let untyped_escrow = current[2..Escrow::PRE_DEPOSIT_LEN].to_vec();
+    // So did escrows from before they recorded their deposit.
+    let pre_deposit_escrow_data = current[..Escrow::PRE_DEPOSIT_LEN].to_vec();
+
+    let program_id = Pubkey::new_unique();
+    let rent = Rent::default();
+    let mut env = {
+        let mut program_test = ProgramTest::new(
+            "solana_contracts",
+            program_id,
+            processor!(process_instruction),
+        );
+        for (pubkey, data) in [
+            (account, version_2_token),
+            (escrow, untyped_escrow),
+            (pre_deposit_escrow, pre_deposit_escrow_data),
+        ] {
+            program_test.add_account(
+                pubkey,
+                Account {
+                    lamports: rent.minimum_balance(data.len()),
+                    data,
+                    owner: program_id,
+                    ..Account::default()
+                },
+            );
+        }
+        let context = program_test.start_with_context().await;
+        Env {
+            program_id,
+            banks_client: context.banks_client.clone(),
+            payer: context.payer.insecure_clone(),
+            recent_blockhash: context.last_blockhash,
+            context,
+        }
+    };
+
+    let payer = env.payer.pubkey();
+    env.process(
+        &[
+            instruction::migrate_account(&program_id, &account, &payer).unwrap(),
+            instruction::migrate_account(&program_id, &escrow, &payer).unwrap(),
+            instruction::migrate_account(&program_id, &pre_deposit_escrow, &payer).unwrap(),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.get_account(&account).await.data.len(), Token::LEN);
+    assert_eq!(env.get_token(&account).await, token);
+    for escrow in [escrow, pre_deposit_escrow] {
+        let migrated = env.get_account(&escrow).await;
+        assert_eq!(migrated.lamports, rent.minimum_balance(Escrow::LEN));
+        assert_eq!(Escrow::unpack(&migrated.data), Ok(escrow_state.clone()));
+    }
+    assert_eq!(
+        env.process(
+            &[instruction::migrate_account(&program_id, &escrow, &payer).unwrap()],
+            &[]
+        )
+        .await
+        .unwrap_err(),
+        custom(TokenError::InvalidState)
+    );
+}
+
+#[tokio::test]
+async fn migrate_account_keeps_version_2_extension_entries() {
+    let token = Token {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount: 500,
+        state: AccountState::Initialized,
+        ..Token::default()
+    };
+    let mut plain_token = vec![0; Token::LEN];
+    token.pack_into_slice(&mut plain_token);
+    let mut extended_token = plain_token.clone();
+    let memo = MemoTransfer {
+        require_incoming_transfer_memos: true,
+    };
+    extended_token.resize(
+        extension::len_with::<MemoTransfer>(&extended_token).unwrap(),
+        0,
+    );
+    extension::init_extension(&mut extended_token, &memo).unwrap();
+    let mint_state = Mint {
+        supply: 9,
+        is_initialized: true,
+        ..Mint::default()
+    };
+    let mut current_mint = vec![0; Mint::LEN];
+    mint_state.pack_into_slice(&mut current_mint);
+
+    // Version 2 accounts led with their version alone, so a token
+    // account's extension entries started a byte before they do now.
+    let version_2 = |current: &[u8]| {
+        let mut data = vec![2];
+        data.extend_from_slice(&current[2..]);
+        data
+    };
+    let accounts: Vec<_> = [&plain_token, &extended_token, &current_mint]
+        .into_iter()
+        .map(|current| (Pubkey::new_unique(), version_2(current), current.clone()))
+        .collect();
+
+    let program_id = Pubkey::new_unique();
+    let rent = Rent::default();
+    let mut env = {
+        let mut program_test = ProgramTest::new(
+            "solana_contracts",
+            program_id,
+            processor!(process_instruction),
+        );
+        for (pubkey, data, _) in &accounts {
+            program_test.add_account(
+                *pubkey,
+                Account {
+                    lamports: rent.minimum_balance(data.len()),
+                    data: data.clone(),
+                    owner: program_id,
+                    ..Account::default()
+                },
+            );
+        }
+        let context = program_test.start_with_context().await;
+        Env {
+            program_id,
+            banks_client: context.banks_client.clone(),
+            payer: context.payer.insecure_clone(),
+            recent_blockhash: context.last_blockhash,
+            context,
+        }
+    };
+
+    let payer = env.payer.pubkey();
+    for (pubkey, _, migrated) in &accounts {
+        env.process(
+            &[instruction::migrate_account(&program_id, pubkey, &payer).unwrap()],
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(&env.get_account(pubkey).await.data, migrated);
+    }
+    assert_eq!(
+        extension::get_extension::<MemoTransfer>(&env.get_account(&accounts[1].0).await.data),
+        Ok(Some(memo))
+    );
+}
+
+#[test]
+fn royalty_rounds_down_in_favor_of_the_recipient() {
+    let royalty = |basis_points| Royalty {
+        basis_points,
+        destination: Pubkey::new_unique(),
+    };
+    assert_eq!(royalty(250).calculate_royalty(0), Some(0));
+    assert_eq!(royalty(250).calculate_royalty(1), Some(0));
+    assert_eq!(royalty(9_999).calculate_royalty(1), Some(0));
+    assert_eq!(royalty(10_000).calculate_royalty(1), Some(1));
+    assert_eq!(royalty(250).calculate_royalty(999), Some(24));
+    assert_eq!(royalty(3_333).calculate_royalty(3), Some(0));
+    assert_eq!(royalty(3_333).calculate_royalty(7), Some(2));
+    assert_eq!(royalty(10_000).calculate_royalty(999), Some(999));
+    assert_eq!(royalty(10_000).calculate_royalty(u64::MAX), Some(u64::MAX));
+    assert_eq!(
+        royalty(5_000).calculate_royalty(u64::MAX),
+        Some(u64::MAX / 2)
+    );
+}
+
+struct RoyaltyFixture {
+    mint: Keypair,
+    owner: Keypair,
+    source: Keypair,
+    destination: Keypair,
+    royalty: Keypair,
+}
+
+async fn setup_royalty_mint(env: &mut Env, basis_points: u16, amount: u64) -> RoyaltyFixture {
+    let program_id = env.program_id;
+    let fixture = RoyaltyFixture {
+        mint: Keypair::new(),
+        owner: Keypair::new(),
+        source: Keypair::new(),
+        destination: Keypair::new(),
+        royalty: Keypair::new(),
+    };
+    env.create_account(&fixture.mint, Mint::LEN, &program_id)
+        .await;
+    let configure = instruction::initialize_royalty(
+        &program_id,
+        &fixture.mint.pubkey(),
+        &env.payer.pubkey(),
+        basis_points,
+        &fixture.royalty.pubkey(),
+    )
+    .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &fixture.mint.pubkey(),
+        &env.payer.pubkey(),
+        None,
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    let mint = fixture.mint.pubkey();
+    env.create_token_account(&fixture.source, &mint, &fixture.owner.pubkey())
+        .await;
+    for account in [&fixture.destination, &fixture.royalty] {
+        env.create_token_account(account, &mint, &Pubkey::new_unique())
+            .await;
+    }
+    let mint_tokens = instruction::mint_tokens(
+        &program_id,
+        &mint,
+        &fixture.source.pubkey(),
+        &env.payer.pubkey(),
+        &[],
+        amount,
+    )
+    .unwrap();
+    env.process(&[mint_tokens], &[]).await.unwrap();
+    fixture
+}
+
+impl RoyaltyFixture {
+    fn transfer(&self, program_id: &Pubkey, amount: u64, royalty: Option<&Pubkey>) -> Instruction {
+        let mut instruction = instruction::transfer_checked(
+            program_id,
+            &self.source.pubkey(),
+            &self.mint.pubkey(),
+            &self.destination.pubkey(),
+            &self.owner.pubkey(),
+            &[],
+            amount,
+            6,
+        )
+        .unwrap();
+        if let Some(royalty) = royalty {
+            instruction.accounts.push(AccountMeta::new(*royalty, false));
+        }
+        instruction
+    }
+}
+
+#[tokio::test]
+async fn checked_transfers_split_off_the_royalty() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    // (basis points, amount transferred, royalty paid)
+    for (basis_points, amount, expected) in [
+        (250, 1, 0),
+        (250, 999, 24),
+        (3_333, 7, 2),
+        (10_000, 1, 1),
+        (10_000, 501, 501),
+        (250, 0, 0),
+    ] {
+        let fixture = setup_royalty_mint(&mut env, basis_points, 1_000).await;
+        assert_eq!(
+            env.get_extension::<MintSettings>(&fixture.source.pubkey())
+                .await,
+            Some(MintSettings {
+                royalty: true,
+                ..MintSettings::default()
+            })
+        );
+        let transfer = fixture.transfer(&program_id, amount, Some(&fixture.royalty.pubkey()));
+        env.process(&[transfer], &[&fixture.owner]).await.unwrap();
+
+        assert_eq!(
+            env.get_token(&fixture.source.pubkey()).await.amount,
+            1_000 - amount
+        );
+        assert_eq!(
+            env.get_token(&fixture.destination.pubkey()).await.amount,
+            amount - expected
+        );
+        assert_eq!(
+            env.get_token(&fixture.royalty.pubkey()).await.amount,
+            expected
+        );
+        assert_eq!(env.get_mint(&fixture.mint.pubkey()).await.supply, 1_000);
+    }
+}
+
+#[tokio::test]
+async fn royalty_mint_requires_its_royalty_account() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let fixture = setup_royalty_mint(&mut env, 500, 100).await;
+
+    let missing = fixture.transfer(&program_id, 10, None);
+    assert_eq!(
+        env.process(&[missing], &[&fixture.owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::RoyaltyAccountMissing)
+    );
+    let wrong = fixture.transfer(&program_id, 10, Some(&fixture.destination.pubkey()));
+    assert_eq!(
+        env.process(&[wrong], &[&fixture.owner]).await.unwrap_err(),
+        custom(TokenError::RoyaltyAccountMissing)
+    );
+
+    let unchecked = instruction::transfer_tokens(
+        &program_id,
+        &fixture.source.pubkey(),
+        &fixture.destination.pubkey(),
+        &fixture.owner.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[unchecked], &[&fixture.owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::MintRequiredForTransfer)
+    );
+    assert_eq!(env.get_token(&fixture.source.pubkey()).await.amount, 100);
+
+    let mint = Keypair::new();
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure = instruction::initialize_royalty(
+        &program_id,
+        &mint.pubkey(),
+        &env.payer.pubkey(),
+        10_001,
+        &Pubkey::new_unique(),
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[configure], &[]).await.unwrap_err(),
+        custom(TokenError::RoyaltyExceedsMaximum)
+    );
+}
+
+#[tokio::test]
+async fn allowlist_gates_mints_and_transfers() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let authority = Keypair::new();
+    let (sender, recipient) = (Keypair::new(), Keypair::new());
+    let (source, destination) = (Keypair::new(), Keypair::new());
+    let allowlist_address = allowlist::get_allowlist_address(&mint.pubkey(), &program_id);
+    let payer = env.payer.pubkey();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure =
+        instruction::initialize_allowlist(&program_id, &payer, &mint.pubkey(), &authority.pubkey())
+            .unwrap();
+    let initialize =
+        instruction::initialize_mint(&program_id, &mint.pubkey(), &payer, None, 6, None).unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    assert_eq!(
+        env.get_mint_extension(&mint.pubkey()).await,
+        Some(AllowlistEnforcement)
+    );
+    env.create_token_account(&source, &mint.pubkey(), &sender.pubkey())
+        .await;
+    env.create_token_account(&destination, &mint.pubkey(), &recipient.pubkey())
+        .await;
+    assert_eq!(
+        env.get_extension::<MintSettings>(&destination.pubkey())
+            .await,
+        Some(MintSettings {
+            enforce_allowlist: true,
+            ..MintSettings::default()
+        })
+    );
+
+    let add = |owner: &Pubkey| {
+        instruction::add_to_allowlist(
+            &program_id,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            &[],
+            &payer,
+            owner,
+        )
+        .unwrap()
+    };
+    // Adding an owner twice leaves a single entry.
+    env.process(
+        &[add(&sender.pubkey()), add(&recipient.pubkey())],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    env.process(&[add(&recipient.pubkey())], &[&authority])
+        .await
+        .unwrap();
+    let allowlist_data = env.get_account(&allowlist_address).await.data;
+    assert_eq!(allowlist_data.len(), Allowlist::len_for(2));
+    let allowlist = Allowlist::unpack(&allowlist_data).unwrap();
+    assert_eq!(allowlist.owners, [sender.pubkey(), recipient.pubkey()]);
+    assert_eq!(allowlist.mint, mint.pubkey());
+
+    let with_allowlist = |mut instruction: Instruction| {
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(allowlist_address, false));
+        instruction
+    };
+    let mint_tokens = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &payer,
+        &[],
+        100,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(std::slice::from_ref(&mint_tokens), &[])
+            .await
+            .unwrap_err(),
+        custom(TokenError::AllowlistAccountMissing)
+    );
+    env.process(&[with_allowlist(mint_tokens)], &[])
+        .await
+        .unwrap();
+
+    let transfer = |amount| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &sender.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    env.process(&[with_allowlist(transfer(30))], &[&sender])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 30);
+    assert_eq!(
+        env.process(&[transfer(31)], &[&sender]).await.unwrap_err(),
+        custom(TokenError::AllowlistAccountMissing)
+    );
+
+    let remove = instruction::remove_from_allowlist(
+        &program_id,
+        &mint.pubkey(),
+        &authority.pubkey(),
+        &[],
+        &recipient.pubkey(),
+    )
+    .unwrap();
+    env.process(&[remove], &[&authority]).await.unwrap();
+    assert_eq!(
+        env.get_account(&allowlist_address).await.data.len(),
+        Allowlist::len_for(1)
+    );
+    assert_eq!(
+        env.process(&[with_allowlist(transfer(32))], &[&sender])
+            .await
+            .unwrap_err(),
+        custom(TokenError::NotAllowlisted)
+    );
+    let batch = instruction::transfer_batch(
+        &program_id,
+        &source.pubkey(),
+        &sender.pubkey(),
+        &[],
+        &[(&destination.pubkey(), 5)],
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[with_allowlist(batch)], &[&sender])
+            .await
+            .unwrap_err(),
+        custom(TokenError::NotAllowlisted)
+    );
+    let remove_unlisted = instruction::remove_from_allowlist(
+        &program_id,
+        &mint.pubkey(),
+        &authority.pubkey(),
+        &[],
+        &Pubkey::new_unique(),
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[remove_unlisted], &[&authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::NotAllowlisted)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 70);
+
+    // Only the allowlist authority can change the list.
+    let add_by_sender = instruction::add_to_allowlist(
+        &program_id,
+        &mint.pubkey(),
+        &sender.pubkey(),
+        &[],
+        &payer,
+        &recipient.pubkey(),
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[add_by_sender], &[&sender]).await.unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+}
+
+#[tokio::test]
+async fn mints_without_an_allowlist_need_no_allowlist_account() {
+    let mut fixture = setup(100).await;
+    let program_id = fixture.env.program_id;
+    assert_eq!(
+        fixture
+            .env
+            .get_mint_extension::<AllowlistEnforcement>(&fixture.mint.pubkey())
+            .await,
+        None
+    );
+
+    let transfer = instruction::transfer_tokens(
+        &program_id,
+        &fixture.source.pubkey(),
+        &fixture.destination.pubkey(),
+        &fixture.owner.pubkey(),
+        &[],
+        40,
+    )
+    .unwrap();
+    fixture
+        .env
+        .process(&[transfer], &[&fixture.owner])
+        .await
+        .unwrap();
+    assert_eq!(
+        fixture
+            .env
+            .get_token(&fixture.destination.pubkey())
+            .await
+            .amount,
+        40
+    );
+}
+
+#[tokio::test]
+async fn transfers_below_the_minimum_fail_unless_they_drain() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let mint = Keypair::new();
+    let owner = Keypair::new();
+    let (source, destination) = (Keypair::new(), Keypair::new());
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure =
+        instruction::initialize_min_transfer_amount(&program_id, &mint.pubkey(), &payer, 10)
+            .unwrap();
+    let initialize =
+        instruction::initialize_mint(&program_id, &mint.pubkey(), &payer, None, 6, None).unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    env.create_token_account(&source, &mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_token_account(&destination, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    let mint_tokens = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &payer,
+        &[],
+        25,
+    )
+    .unwrap();
+    env.process(&[mint_tokens], &[]).await.unwrap();
+    assert_eq!(
+        env.get_extension::<MintSettings>(&source.pubkey()).await,
+        Some(MintSettings {
+            min_transfer_amount: true,
+            ..MintSettings::default()
+        })
+    );
+
+    let transfer = |amount| {
+        instruction::transfer_checked(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+            6,
+        )
+        .unwrap()
+    };
+    for amount in [0, 1, 9] {
+        assert_eq!(
+            env.process(&[transfer(amount)], &[&owner])
+                .await
+                .unwrap_err(),
+            custom(TokenError::AmountBelowMinimum)
+        );
+    }
+    env.process(&[transfer(10)], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 10);
+
+    let unchecked = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[unchecked], &[&owner]).await.unwrap_err(),
+        custom(TokenError::MintRequiredForTransfer)
+    );
+
+    // The minimum rises above the remaining 15, which can still be drained.
+    let update =
+        instruction::update_min_transfer_amount(&program_id, &mint.pubkey(), &payer, &[], 20)
+            .unwrap();
+    env.process(&[update], &[]).await.unwrap();
+    assert_eq!(
+        env.get_mint_extension::<MinTransferAmount>(&mint.pubkey())
+            .await,
+        Some(MinTransferAmount {
+            min_transfer_amount: 20
+        })
+    );
+    assert_eq!(
+        env.process(&[transfer(14)], &[&owner]).await.unwrap_err(),
+        custom(TokenError::AmountBelowMinimum)
+    );
+    env.process(&[transfer(15)], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 0);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 25);
+    // A zero transfer is rejected even from an empty account.
+    assert_eq!(
+        env.process(&[transfer(instruction::AMOUNT_ALL)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::AmountBelowMinimum)
+    );
+}
+
+#[tokio::test]
+async fn min_transfer_amount_is_updated_only_by_the_mint_authority() {
+    let mut fixture = setup(10).await;
+    let program_id = fixture.env.program_id;
+    let mint = fixture.mint.pubkey();
+    let authority = fixture.mint_authority.pubkey();
+
+    // A mint configured without a minimum can't gain one later.
+    let update =
+        instruction::update_min_transfer_amount(&program_id, &mint, &authority, &[], 5).unwrap();
+    assert_eq!(
+        fixture
+            .env
+            .process(&[update], &[&fixture.mint_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::InvalidState)
+    );
+
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let mint = Keypair::new();
+    let stranger = Keypair::new();
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure =
+        instruction::initialize_min_transfer_amount(&program_id, &mint.pubkey(), &payer, 3)
+            .unwrap();
+    let initialize =
+        instruction::initialize_mint(&program_id, &mint.pubkey(), &authority, None, 6, None)
+            .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    let update = instruction::update_min_transfer_amount(
+        &program_id,
+        &mint.pubkey(),
+        &stranger.pubkey(),
+        &[],
+        5,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[update], &[&stranger]).await.unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    assert_eq!(
+        env.get_mint_extension::<MinTransferAmount>(&mint.pubkey())
+            .await,
+        Some(MinTransferAmount {
+            min_transfer_amount: 3
+        })
+    );
+}
+
+#[tokio::test]
+async fn activity_tracking_counts_successful_transfers_at_the_clock_time() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let mint = Keypair::new();
+    let owner = Keypair::new();
+    let (source, destination) = (Keypair::new(), Keypair::new());
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure =
+        instruction::initialize_activity_tracking(&program_id, &mint.pubkey(), &payer).unwrap();
+    let initialize =
+        instruction::initialize_mint(&program_id, &mint.pubkey(), &payer, None, 6, None).unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    env.create_token_account(&source, &mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_token_account(&destination, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    assert_eq!(
+        env.get_extension::<MintSettings>(&source.pubkey()).await,
+        Some(MintSettings {
+            activity_tracking: true,
+            ..MintSettings::default()
+        })
+    );
+    let activity = |data: Vec<u8>| match extension::get_mint_extension::<MintActivity>(&data) {
+        Ok(Some(activity)) => (activity.transfer_count, activity.last_activity_unix),
+        _ => panic!("the mint tracks its activity"),
+    };
+
+    env.set_unix_timestamp(1_000).await;
+    let mint_tokens = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &payer,
+        &[],
+        100,
+    )
+    .unwrap();
+    env.process(&[mint_tokens], &[]).await.unwrap();
+    assert_eq!(
+        activity(env.get_account(&mint.pubkey()).await.data),
+        (0, 1_000)
+    );
+
+    let transfer = |amount| {
+        let mut transfer = instruction::transfer_checked(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+            6,
+        )
+        .unwrap();
+        transfer.accounts[1].is_writable = true;
+        transfer
+    };
+    env.set_unix_timestamp(2_000).await;
+    env.process(&[transfer(10)], &[&owner]).await.unwrap();
+    env.set_unix_timestamp(3_000).await;
+    env.process(&[transfer(20)], &[&owner]).await.unwrap();
+    assert_eq!(
+        activity(env.get_account(&mint.pubkey()).await.data),
+        (2, 3_000)
+    );
+
+    // Failed transfers leave the activity as it was.
+    env.set_unix_timestamp(4_000).await;
+    assert_eq!(
+        env.process(&[transfer(1_000)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::InsufficientFunds)
+    );
+    let mut read_only = transfer(5);
+    read_only.accounts[1].is_writable = false;
+    assert_eq!(
+        env.process(&[read_only], &[&owner]).await.unwrap_err(),
+        custom(TokenError::AccountNotWritable)
+    );
+    let unchecked = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        5,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[unchecked], &[&owner]).await.unwrap_err(),
+        custom(TokenError::MintRequiredForTransfer)
+    );
+    assert_eq!(
+        activity(env.get_account(&mint.pubkey()).await.data),
+        (2, 3_000)
+    );
+
+    // Burns stamp the time without counting a transfer.
+    let burn = instruction::burn(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &owner.pubkey(),
+        &[],
+        5,
+    )
+    .unwrap();
+    env.process(&[burn], &[&owner]).await.unwrap();
+    assert_eq!(
+        activity(env.get_account(&mint.pubkey()).await.data),
+        (2, 4_000)
+    );
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 65);
+}
+
+#[tokio::test]
+async fn mints_without_activity_tracking_keep_read_only_transfers() {
+    let mut fixture = setup(100).await;
+    let (mint, source) = (fixture.mint.pubkey(), fixture.source.pubkey());
+    let transfer = instruction::transfer_checked(
+        &fixture.env.program_id,
+        &source,
+        &mint,
+        &fixture.destination.pubkey(),
+        &fixture.owner.pubkey(),
+        &[],
+        10,
+        6,
+    )
+    .unwrap();
+    assert!(!transfer.accounts[1].is_writable);
+    fixture
+        .env
+        .process(&[transfer], &[&fixture.owner])
+        .await
+        .unwrap();
+    assert_eq!(
+        fixture.env.get_mint_extension::<MintActivity>(&mint).await,
+        None
+    );
+    assert_eq!(
+        fixture.env.get_extension::<MintSettings>(&source).await,
+        None
+    );
+}
+
+#[tokio::test]
+async fn transfer_with_fee_pays_the_destination_and_fee_account_together() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        owner,
+        source,
+        destination,
+    } = setup(1_000).await;
+    let program_id = env.program_id;
+    let fee_account = Keypair::new();
+    env.create_token_account(&fee_account, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    let transfer = |fee_account: &Pubkey, amount, fee| {
+        instruction::transfer_with_fee(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            fee_account,
+            &owner.pubkey(),
+            &[],
+            amount,
+            fee,
+        )
+        .unwrap()
+    };
+    let accounts = [source.pubkey(), destination.pubkey(), fee_account.pubkey()];
+
+    env.process(&[transfer(&fee_account.pubkey(), 600, 25)], &[&owner])
+        .await
+        .unwrap();
+    let mut amounts = Vec::new();
+    for account in &accounts {
+        amounts.push(env.get_token(account).await.amount);
+    }
+    assert_eq!(amounts, [375, 600, 25]);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 1_000);
+
+    for (amount, fee, error) in [
+        (376, 25, TokenError::InsufficientFunds),
+        (u64::MAX, 1, TokenError::Overflow),
+    ] {
+        assert_eq!(
+            env.process(&[transfer(&fee_account.pubkey(), amount, fee)], &[&owner])
+                .await
+                .unwrap_err(),
+            custom(error)
+        );
+    }
+
+    // A fee account of another mint, or the destination again, is rejected
+    // and nothing moves.
+    let other_mint = Keypair::new();
+    let foreign_fee_account = Keypair::new();
+    env.create_mint(&other_mint, &mint_authority.pubkey()).await;
+    env.create_token_account(
+        &foreign_fee_account,
+        &other_mint.pubkey(),
+        &Pubkey::new_unique(),
+    )
+    .await;
+    assert_eq!(
+        env.process(
+            &[transfer(&foreign_fee_account.pubkey(), 100, 5)],
+            &[&owner]
+        )
+        .await
+        .unwrap_err(),
+        custom(TokenError::MintMismatch)
+    );
+    assert_eq!(
+        env.process(&[transfer(&destination.pubkey(), 100, 5)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::DuplicateAccount)
+    );
+    let mut amounts = Vec::new();
+    for account in &accounts {
+        amounts.push(env.get_token(account).await.amount);
+    }
+    assert_eq!(amounts, [375, 600, 25]);
+}
+
+#[tokio::test]
+async fn relayer_fees_are_paid_from_the_source_with_the_transfer() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        owner,
+        source,
+        destination,
+    } = setup(1_000).await;
+    let program_id = env.program_id;
+    let relayer = Keypair::new();
+    env.create_token_account(&relayer, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+    let transfer = |mint: &Pubkey, relayer: &Pubkey, amount, relayer_fee| {
+        instruction::transfer_with_relayer_fee(
+            &program_id,
+            &source.pubkey(),
+            mint,
+            &destination.pubkey(),
+            relayer,
+            &owner.pubkey(),
+            &[],
+            amount,
+            relayer_fee,
+        )
+        .unwrap()
+    };
+    let accounts = [source.pubkey(), destination.pubkey(), relayer.pubkey()];
+
+    // With a fee, and without one.
+    env.process(
+        &[
+            transfer(&mint.pubkey(), &relayer.pubkey(), 600, 25),
+            transfer(&mint.pubkey(), &relayer.pubkey(), 100, 0),
+        ],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let mut amounts = Vec::new();
+    for account in &accounts {
+        amounts.push(env.get_token(account).await.amount);
+    }
+    assert_eq!(amounts, [275, 700, 25]);
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 1_000);
+
+    assert_eq!(
+        env.process(
+            &[transfer(&mint.pubkey(), &relayer.pubkey(), 250, 26)],
+            &[&owner]
+        )
+        .await
+        .unwrap_err(),
+        custom(TokenError::InsufficientFunds)
+    );
+
+    // A relayer account of another mint, or every account named with the
+    // other mint, is rejected and nothing moves.
+    let other_mint = Keypair::new();
+    let foreign_relayer = Keypair::new();
+    env.create_mint(&other_mint, &mint_authority.pubkey()).await;
+    env.create_token_account(
+        &foreign_relayer,
+        &other_mint.pubkey(),
+        &Pubkey::new_unique(),
+    )
+    .await;
+    for (mint, relayer) in [
+        (mint.pubkey(), foreign_relayer.pubkey()),
+        (other_mint.pubkey(), relayer.pubkey()),
+    ] {
+        assert_eq!(
+            env.process(&[transfer(&mint, &relayer, 100, 5)], &[&owner])
+                .await
+                .unwrap_err(),
+            custom(TokenError::MintMismatch)
+        );
+    }
+    assert_eq!(
+        env.process(
+            &[transfer(&mint.pubkey(), &destination.pubkey(), 100, 5)],
+            &[&owner]
+        )
+        .await
+        .unwrap_err(),
+        custom(TokenError::DuplicateAccount)
+    );
+    let mut amounts = Vec::new();
+    for account in &accounts {
+        amounts.push(env.get_token(account).await.amount);
+    }
+    assert_eq!(amounts, [275, 700, 25]);
+}
+
+#[tokio::test]
+async fn relayer_fees_pay_the_mints_transfer_fee() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let owner = Keypair::new();
+    let source = Keypair::new();
+    let destination = Keypair::new();
+    let relayer = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure = instruction::initialize_transfer_fee_config(
+        &program_id,
+        &mint.pubkey(),
+        Some(&Pubkey::new_unique()),
+        100,
+        50,
+    )
+    .unwrap();
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &mint_authority.pubkey(),
+        None,
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    for account in [&source, &destination, &relayer] {
+        env.create_token_account(account, &mint.pubkey(), &owner.pubkey())
+            .await;
+    }
+    let mint_to = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &mint_authority.pubkey(),
+        &[],
+        1_000,
+    )
+    .unwrap();
+    env.process(&[mint_to], &[&mint_authority]).await.unwrap();
+
+    // Both movements withhold the 1% fee at their destination.
+    let transfer = instruction::transfer_with_relayer_fee(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &destination.pubkey(),
+        &relayer.pubkey(),
+        &owner.pubkey(),
+        &[],
+        600,
+        200,
+    )
+    .unwrap();
+    env.process(&[transfer], &[&owner]).await.unwrap();
+    let mut balances = Vec::new();
+    for account in [&source, &destination, &relayer] {
+        let token = env.get_token(&account.pubkey()).await;
+        balances.push((token.amount, token.withheld_amount));
+    }
+    assert_eq!(
+        balances,
+        [
+            (200, COption::Some(0)),
+            (594, COption::Some(6)),
+            (198, COption::Some(2))
+        ]
+    );
+}
+
+#[tokio::test]
+async fn transfer_cooldown_blocks_sending_until_the_slots_pass() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let mint = Keypair::new();
+    let owner = Keypair::new();
+    let (source, destination) = (Keypair::new(), Keypair::new());
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure =
+        instruction::initialize_transfer_cooldown(&program_id, &mint.pubkey(), &payer, 10).unwrap();
+    let initialize =
+        instruction::initialize_mint(&program_id, &mint.pubkey(), &payer, None, 6, None).unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    env.create_token_account(&source, &mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_token_account(&destination, &mint.pubkey(), &owner.pubkey())
+        .await;
+    assert_eq!(
+        env.get_mint_extension::<TransferCooldown>(&mint.pubkey())
+            .await,
+        Some(TransferCooldown { cooldown_slots: 10 })
+    );
+    assert_eq!(
+        env.get_extension::<Cooldown>(&source.pubkey()).await,
+        Some(Cooldown::default())
+    );
+    let last_outgoing_slot = |data: Vec<u8>| match extension::get_extension::<Cooldown>(&data) {
+        Ok(Some(cooldown)) => cooldown.last_outgoing_slot,
+        _ => panic!("the account has a cooldown"),
+    };
+    let mint_tokens = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &payer,
+        &[],
+        100,
+    )
+    .unwrap();
+    env.process(&[mint_tokens], &[]).await.unwrap();
+
+    let transfer = |from: &Keypair, to: &Keypair, amount| {
+        instruction::transfer_checked(
+            &program_id,
+            &from.pubkey(),
+            &mint.pubkey(),
+            &to.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+            6,
+        )
+        .unwrap()
+    };
+    let slot = env.banks_client.get_sysvar::<Clock>().await.unwrap().slot;
+    env.process(&[transfer(&source, &destination, 10)], &[&owner])
+        .await
+        .unwrap();
+    let sent_at = last_outgoing_slot(env.get_account(&source.pubkey()).await.data);
+    assert!(sent_at >= slot);
+
+    // The source can't send again yet, checked or not, but can still
+    // receive and burn.
+    assert_eq!(
+        env.process(&[transfer(&source, &destination, 11)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::CooldownActive)
+    );
+    let unchecked = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        11,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[unchecked], &[&owner]).await.unwrap_err(),
+        custom(TokenError::MintRequiredForTransfer)
+    );
+    env.process(&[transfer(&destination, &source, 5)], &[&owner])
+        .await
+        .unwrap();
+    let burn = instruction::burn(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &owner.pubkey(),
+        &[],
+        5,
+    )
+    .unwrap();
+    env.process(&[burn], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 90);
+    assert_eq!(
+        last_outgoing_slot(env.get_account(&source.pubkey()).await.data),
+        sent_at
+    );
+
+    // One slot short of the cooldown it is still blocked; then it passes.
+    env.context.warp_to_slot(sent_at + 9).unwrap();
+    env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+    assert_eq!(
+        env.process(&[transfer(&source, &destination, 12)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::CooldownActive)
+    );
+    env.context.warp_to_slot(sent_at + 10).unwrap();
+    env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+    env.process(&[transfer(&source, &destination, 12)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 78);
+    assert_eq!(
+        last_outgoing_slot(env.get_account(&source.pubkey()).await.data),
+        sent_at + 10
+    );
+}
+
+#[tokio::test]
+async fn holder_count_follows_balances_to_and_from_zero() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let mint = Keypair::new();
+    let owner = Keypair::new();
+    let (first, second) = (Keypair::new(), Keypair::new());
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let configure =
+        instruction::initialize_holder_count(&program_id, &mint.pubkey(), &payer).unwrap();
+    let initialize =
+        instruction::initialize_mint(&program_id, &mint.pubkey(), &payer, None, 6, None).unwrap();
+    env.process(&[configure, initialize], &[]).await.unwrap();
+    env.create_token_account(&first, &mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_token_account(&second, &mint.pubkey(), &owner.pubkey())
+        .await;
+    assert_eq!(
+        env.get_extension::<MintSettings>(&first.pubkey()).await,
+        Some(MintSettings {
+            holder_count: true,
+            ..MintSettings::default()
+        })
+    );
+    let holders = |data: Vec<u8>| match extension::get_mint_extension::<HolderCount>(&data) {
+        Ok(Some(count)) => count.holders,
+        _ => panic!("the mint counts its holders"),
+    };
+    assert_eq!(holders(env.get_account(&mint.pubkey()).await.data), 0);
+
+    let mint_tokens = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &first.pubkey(),
+        &payer,
+        &[],
+        100,
+    )
+    .unwrap();
+    env.process(&[mint_tokens], &[]).await.unwrap();
+    assert_eq!(holders(env.get_account(&mint.pubkey()).await.data), 1);
+
+    let transfer = |from: &Keypair, to: &Keypair, amount| {
+        let mut transfer = instruction::transfer_checked(
+            &program_id,
+            &from.pubkey(),
+            &mint.pubkey(),
+            &to.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+            6,
+        )
+        .unwrap();
+        transfer.accounts[1].is_writable = true;
+        transfer
+    };
+    // Emptying the source into a fresh destination leaves the count as is.
+    env.process(&[transfer(&first, &second, 100)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(holders(env.get_account(&mint.pubkey()).await.data), 1);
+
+    // Refilling and emptying the first account moves the count each time.
+    for amount in 1..=3 {
+        env.process(&[transfer(&second, &first, amount)], &[&owner])
+            .await
+            .unwrap();
+        assert_eq!(holders(env.get_account(&mint.pubkey()).await.data), 2);
+        env.process(&[transfer(&first, &second, amount)], &[&owner])
+            .await
+            .unwrap();
+        assert_eq!(holders(env.get_account(&mint.pubkey()).await.data), 1);
+    }
+
+    // Transfers that can't update the count are rejected.
+    let mut read_only = transfer(&second, &first, 5);
+    read_only.accounts[1].is_writable = false;
+    assert_eq!(
+        env.process(&[read_only], &[&owner]).await.unwrap_err(),
+        custom(TokenError::AccountNotWritable)
+    );
+    let unchecked = instruction::transfer_tokens(
+        &program_id,
+        &second.pubkey(),
+        &first.pubkey(),
+        &owner.pubkey(),
+        &[],
+        5,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[unchecked], &[&owner]).await.unwrap_err(),
+        custom(TokenError::MintRequiredForTransfer)
+    );
+
+    // Burning a partial balance keeps the holder; burning the rest drops it,
+    // and closing the empty account leaves the count alone.
+    let burn = |amount| {
+        instruction::burn(
+            &program_id,
+            &second.pubkey(),
+            &mint.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    env.process(&[burn(40)], &[&owner]).await.unwrap();
+    assert_eq!(holders(env.get_account(&mint.pubkey()).await.data), 1);
+    env.process(&[burn(60)], &[&owner]).await.unwrap();
+    assert_eq!(holders(env.get_account(&mint.pubkey()).await.data), 0);
+    let close =
+        instruction::close_account(&program_id, &second.pubkey(), &payer, &owner.pubkey(), &[])
+            .unwrap();
+    env.process(&[close], &[&owner]).await.unwrap();
+    assert_eq!(holders(env.get_account(&mint.pubkey()).await.data), 0);
+}
+
+#[tokio::test]
+async fn permit_transfers_need_the_owners_signature_once_per_nonce() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let mint = Keypair::new();
+    let owner = Keypair::new();
+    let (source, destination, other) = (Keypair::new(), Keypair::new(), Keypair::new());
+    env.create_mint(&mint, &payer).await;
+    for account in [&source, &destination, &other] {
+        env.create_token_account(account, &mint.pubkey(), &owner.pubkey())
+            .await;
+    }
+    let mint_tokens = instruction::mint_tokens(
+        &program_id,
+        &mint.pubkey(),
+        &source.pubkey(),
+        &payer,
+        &[],
+        100,
+    )
+    .unwrap();
+    env.process(&[mint_tokens], &[]).await.unwrap();
+
+    let now = env
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+    let permit = |amount, nonce| Permit {
+        source: source.pubkey(),
+        destination: destination.pubkey(),
+        amount,
+        expiry_unix: now + 100,
+        nonce,
+    };
+    let signed = |permit: &Permit, signer: &Keypair| {
+        let message = permit.pack();
+        let signature = signer.sign_message(&message);
+        let verify = permit::new_ed25519_instruction(
+            &signer.pubkey(),
+            signature.as_ref().try_into().unwrap(),
+            &message,
+        );
+        let transfer = instruction::permit_transfer(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &payer,
+        )
+        .unwrap();
+        [verify, transfer]
+    };
+    let permit_error = |error: TokenError| {
+        TransactionError::InstructionError(1, InstructionError::Custom(error as u32))
+    };
+
+    // Only the payer signs the transaction.
+    env.process(&signed(&permit(10, 0), &owner), &[])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 90);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 10);
+    let source_data = env.get_account(&source.pubkey()).await.data;
+    assert_eq!(
+        extension::get_extension::<PermitNonce>(&source_data).unwrap(),
+        Some(PermitNonce { next_nonce: 1 })
+    );
+
+    // Replaying the permit fails, in a transaction of its own.
+    env.context.warp_to_slot(10).unwrap();
+    env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+    assert_eq!(
+        env.process(&signed(&permit(10, 0), &owner), &[])
+            .await
+            .unwrap_err(),
+        permit_error(TokenError::PermitNonceUsed)
+    );
+
+    // Nonces may be skipped, but not gone back to.
+    env.process(&signed(&permit(5, 7), &owner), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.process(&signed(&permit(5, 3), &owner), &[])
+            .await
+            .unwrap_err(),
+        permit_error(TokenError::PermitNonceUsed)
+    );
+    let source_data = env.get_account(&source.pubkey()).await.data;
+    assert_eq!(
+        extension::get_extension::<PermitNonce>(&source_data).unwrap(),
+        Some(PermitNonce { next_nonce: 8 })
+    );
+
+    let expired = Permit {
+        expiry_unix: now - 1,
+        ..permit(1, 8)
+    };
+    assert_eq!(
+        env.process(&signed(&expired, &owner), &[])
+            .await
+            .unwrap_err(),
+        permit_error(TokenError::PermitExpired)
+    );
+    assert_eq!(
+        env.process(&signed(&permit(1, 8), &Keypair::new()), &[])
+            .await
+            .unwrap_err(),
+        permit_error(TokenError::OwnerMismatch)
+    );
+    let elsewhere = Permit {
+        destination: other.pubkey(),
+        ..permit(1, 8)
+    };
+    assert_eq!(
+        env.process(&signed(&elsewhere, &owner), &[])
+            .await
+            .unwrap_err(),
+        permit_error(TokenError::InvalidPermit)
+    );
+
+    // A message changed after signing fails the ed25519 program's check,
+    // which the runtime reports as this error, before anything runs.
+    let mut tampered = signed(&permit(1, 8), &owner);
+    let amount_start = tampered[0].data.len() - permit::PERMIT_LEN + 64;
+    tampered[0].data[amount_start] = 90;
+    assert_eq!(
+        env.process(&tampered, &[]).await.unwrap_err(),
+        TransactionError::InvalidAccountIndex
+    );
+
+    // Without the signature check right before, there is no permit.
+    let [_, transfer] = signed(&permit(1, 8), &owner);
+    assert_eq!(
+        env.process(std::slice::from_ref(&transfer), &[])
+            .await
+            .unwrap_err(),
+        custom(TokenError::InvalidPermit)
+    );
+    let memo = Instruction {
+        program_id: memo::id(),
+        accounts: vec![],
+        data: b"not a signature check".to_vec(),
+    };
+    assert_eq!(
+        env.process(&[memo, transfer], &[]).await.unwrap_err(),
+        permit_error(TokenError::InvalidPermit)
+    );
+
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 85);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 15);
+}
+
+#[tokio::test]
+async fn vested_tokens_are_minted_as_the_clock_passes() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let mint = Keypair::new();
+    let (beneficiary, other) = (Keypair::new(), Keypair::new());
+    let owner = Pubkey::new_unique();
+    env.create_capped_mint(&mint, &payer, Some(1_000)).await;
+    env.create_token_account(&beneficiary, &mint.pubkey(), &owner)
+        .await;
+    env.create_token_account(&other, &mint.pubkey(), &owner)
+        .await;
+
+    let create_vesting = |beneficiary: &Keypair, authority: &Pubkey, total_amount| {
+        instruction::create_vesting(
+            &program_id,
+            &payer,
+            &mint.pubkey(),
+            &beneficiary.pubkey(),
+            authority,
+            &[],
+            total_amount,
+            2_000,
+            3_000,
+        )
+        .unwrap()
+    };
+    let stranger = Keypair::new();
+    assert_eq!(
+        env.process(
+            &[create_vesting(&beneficiary, &stranger.pubkey(), 600)],
+            &[&stranger]
+        )
+        .await
+        .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    env.process(&[create_vesting(&beneficiary, &payer, 600)], &[])
+        .await
+        .unwrap();
+    let vesting = get_vesting_address(&mint.pubkey(), &beneficiary.pubkey(), &program_id);
+    let schedule = VestingSchedule::unpack(&env.get_account(&vesting).await.data).unwrap();
+    assert_eq!(schedule.total_amount, 600);
+    assert_eq!(schedule.claimed_amount, 0);
+    assert_eq!(
+        env.get_mint_extension::<VestingReserve>(&mint.pubkey())
+            .await,
+        Some(VestingReserve { reserved: 600 })
+    );
+
+    // The reservation counts against the cap, for mints as for schedules.
+    let mint_to = |amount| {
+        instruction::mint_tokens(
+            &program_id,
+            &mint.pubkey(),
+            &other.pubkey(),
+            &payer,
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        env.process(&[mint_to(401)], &[]).await.unwrap_err(),
+        custom(TokenError::SupplyCapExceeded)
+    );
+    env.process(&[mint_to(300)], &[]).await.unwrap();
+    assert_eq!(
+        env.process(&[create_vesting(&other, &payer, 101)], &[])
+            .await
+            .unwrap_err(),
+        custom(TokenError::SupplyCapExceeded)
+    );
+    assert_eq!(
+        env.process(&[create_vesting(&beneficiary, &payer, 1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    let claim =
+        instruction::claim_vested(&program_id, &mint.pubkey(), &beneficiary.pubkey()).unwrap();
+    let claims = [
+        // Nothing vests before the start, nor at it.
+        (1_500, 0),
+        (2_000, 0),
+        (2_250, 150),
+        // A second claim at the same time finds nothing new.
+        (2_250, 150),
+        (2_500, 300),
+        (2_999, 599),
+        // After the end, the rest.
+        (5_000, 600),
+        (6_000, 600),
+    ];
+    for (i, (unix_timestamp, balance)) in claims.into_iter().enumerate() {
+        // A later slot, so that identical claims are distinct transactions.
+        env.context.warp_to_slot(10 * (i as u64 + 1)).unwrap();
+        env.set_unix_timestamp(unix_timestamp).await;
+        env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+        env.process(std::slice::from_ref(&claim), &[])
+            .await
+            .unwrap();
+        assert_eq!(env.get_token(&beneficiary.pubkey()).await.amount, balance);
+        let schedule = VestingSchedule::unpack(&env.get_account(&vesting).await.data).unwrap();
+        assert_eq!(schedule.claimed_amount, balance);
+        assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 300 + balance);
+        assert_eq!(
+            env.get_mint_extension::<VestingReserve>(&mint.pubkey())
+                .await,
+            Some(VestingReserve {
+                reserved: 600 - balance
+            })
+        );
+    }
+
+    // Fully claimed, the schedule no longer holds back the cap.
+    env.process(&[mint_to(100)], &[]).await.unwrap();
+    assert_eq!(env.get_mint(&mint.pubkey()).await.supply, 1_000);
+}
+
+#[tokio::test]
+async fn repair_owner_gives_mint_owned_accounts_a_real_owner() {
+    let mint = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let mint_authority = Keypair::new();
+    let owner = Keypair::new();
+    let rent = Rent::default();
+
+    // An account as the original `MintTokens` created it, owned by its mint.
+    let mut legacy_mint = vec![1, 0, 0, 0];
+    legacy_mint.extend_from_slice(mint_authority.pubkey().as_ref());
+    legacy_mint.extend_from_slice(&500u64.to_le_bytes());
+    legacy_mint.extend_from_slice(&[6, 1]);
+    let mut legacy_token = 500u64.to_le_bytes().to_vec();
+    legacy_token.extend_from_slice(mint.as_ref());
+    legacy_token.extend_from_slice(mint.as_ref());
+
+    let program_id = Pubkey::new_unique();
+    let mut env = {
+        let mut program_test = ProgramTest::new(
+            "solana_contracts",
+            program_id,
+            processor!(process_instruction),
+        );
+        for (pubkey, data) in [(mint, legacy_mint), (account, legacy_token)] {
+            program_test.add_account(
+                pubkey,
+                Account {
+                    lamports: rent.minimum_balance(data.len()),
+                    data,
+                    owner: program_id,
+                    ..Account::default()
+                },
+            );
+        }
+        let context = program_test.start_with_context().await;
+        Env {
+            program_id,
+            banks_client: context.banks_client.clone(),
+            payer: context.payer.insecure_clone(),
+            recent_blockhash: context.last_blockhash,
+            context,
+        }
+    };
+    let payer = env.payer.pubkey();
+    env.process(
+        &[
+            instruction::migrate_account(&program_id, &mint, &payer).unwrap(),
+            instruction::migrate_account(&program_id, &account, &payer).unwrap(),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let destination = Keypair::new();
+    env.create_token_account(&destination, &mint, &Pubkey::new_unique())
+        .await;
+    let transfer = instruction::transfer_tokens(
+        &program_id,
+        &account,
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(std::slice::from_ref(&transfer), &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+
+    // Only the mint authority can repair it, and only once.
+    let repair = |authority: &Pubkey| {
+        instruction::repair_owner(
+            &program_id,
+            &account,
+            &mint,
+            authority,
+            &[],
+            &owner.pubkey(),
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        env.process(&[repair(&owner.pubkey())], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    env.process(&[repair(&mint_authority.pubkey())], &[&mint_authority])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&account).await.owner, owner.pubkey());
+    // A later slot, so that the rejected transfer can be sent again.
+    env.context.warp_to_slot(10).unwrap();
+    env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+    env.process(&[transfer], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&account).await.amount, 400);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 100);
+    assert_eq!(
+        env.process(&[repair(&mint_authority.pubkey())], &[&mint_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerNotRepairable)
+    );
+
+    // Accounts minted to today already belong to their owner.
+    let fresh = Keypair::new();
+    env.create_token_account(&fresh, &mint, &owner.pubkey())
+        .await;
+    env.process(
+        &[instruction::mint_tokens(
+            &program_id,
+            &mint,
+            &fresh.pubkey(),
+            &mint_authority.pubkey(),
+            &[],
+            50,
+        )
+        .unwrap()],
+        &[&mint_authority],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instruction::transfer_tokens(
+            &program_id,
+            &fresh.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            50,
+        )
+        .unwrap()],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 150);
+}
+
+#[tokio::test]
+async fn idempotent_creation_accepts_an_existing_associated_account() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    env.create_mint(&mint, &Pubkey::new_unique()).await;
+    let wallet = Pubkey::new_unique();
+    let payer = env.payer.pubkey();
+    let create = instruction::create_associated_account_idempotent(
+        &program_id,
+        &payer,
+        &wallet,
+        &mint.pubkey(),
+    )
+    .unwrap();
+    let associated = create.accounts[1].pubkey;
+
+    env.process(&[create.clone(), create.clone()], &[])
+        .await
+        .unwrap();
+    let token = env.get_token(&associated).await;
+    assert_eq!((token.mint, token.owner), (mint.pubkey(), wallet));
+    // The plain instruction still refuses an existing account.
+    assert_eq!(
+        env.process(
+            &[
+                instruction::create_associated_account(
+                    &program_id,
+                    &payer,
+                    &wallet,
+                    &mint.pubkey()
+                )
+                .unwrap()
+            ],
+            &[]
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    // An account at the address that doesn't match is an error, not a
+    // no-op.
+    let mut account = env.get_account(&associated).await;
+    for (slot, (mismatched, error)) in [10, 20].into_iter().zip([
+        (
+            Token {
+                mint: Pubkey::new_unique(),
+                ..token.clone()
+            },
+            TokenError::MintMismatch,
+        ),
+        (
+            Token {
+                owner: Pubkey::new_unique(),
+                ..token.clone()
+            },
+            TokenError::OwnerMismatch,
+        ),
+    ]) {
+        Token::pack(mismatched, &mut account.data).unwrap();
+        env.context
+            .set_account(&associated, &account.clone().into());
+        env.context.warp_to_slot(slot).unwrap();
+        env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+        assert_eq!(
+            env.process(std::slice::from_ref(&create), &[])
+                .await
+                .unwrap_err(),
+            custom(error)
+        );
+    }
+}
+
+#[tokio::test]
+async fn associated_accounts_are_created_only_at_the_canonical_address() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    env.create_mint(&mint, &Pubkey::new_unique()).await;
+    let wallet = Pubkey::new_unique();
+    let payer = env.payer.pubkey();
+    let create =
+        instruction::create_associated_account(&program_id, &payer, &wallet, &mint.pubkey())
+            .unwrap();
+    let mint_pubkey = mint.pubkey();
+    let seeds = [wallet.as_ref(), program_id.as_ref(), mint_pubkey.as_ref()];
+    let (associated, bump_seed) = Pubkey::find_program_address(&seeds, &program_id);
+    assert_eq!(create.accounts[1].pubkey, associated);
+
+    // A program address from a lower bump seed, or another wallet's
+    // address, isn't the wallet's associated account.
+    let non_canonical = (0..bump_seed)
+        .rev()
+        .find_map(|bump_seed| {
+            let bump_seed = [bump_seed];
+            let seeds = [&seeds[..], &[&bump_seed[..]]].concat();
+            Pubkey::create_program_address(&seeds, &program_id).ok()
+        })
+        .unwrap();
+    let other_wallets = get_associated_token_address(&payer, &mint.pubkey(), &program_id);
+    for address in [non_canonical, other_wallets] {
+        let mut misdirected = create.clone();
+        misdirected.accounts[1].pubkey = address;
+        assert_eq!(
+            env.process(&[misdirected], &[]).await.unwrap_err(),
+            TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+        );
+    }
+
+    let rent = env.banks_client.get_rent().await.unwrap();
+    let balance = env.get_account(&payer).await.lamports;
+    env.process(&[create], &[]).await.unwrap();
+    let account = env.get_account(&associated).await;
+    assert_eq!(account.owner, program_id);
+    assert_eq!(account.lamports, rent.minimum_balance(Token::LEN));
+    // The payer funded it, through the system program.
+    assert!(balance - env.get_account(&payer).await.lamports >= account.lamports);
+    assert_eq!(env.get_token(&associated).await.owner, wallet);
+}
+
+#[tokio::test]
+async fn prefunded_associated_addresses_can_still_be_created() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    env.create_mint(&mint, &Pubkey::new_unique()).await;
+    let wallet = Pubkey::new_unique();
+    let payer = env.payer.pubkey();
+    let associated = get_associated_token_address(&wallet, &mint.pubkey(), &program_id);
+    let (expected, _) = Pubkey::find_program_address(
+        &[wallet.as_ref(), program_id.as_ref(), mint.pubkey().as_ref()],
+        &program_id,
+    );
+    assert_eq!(associated, expected);
+
+    // Lamports sent ahead of time no longer block the account.
+    let rent = env.banks_client.get_rent().await.unwrap();
+    let prefund = system_instruction::transfer(&payer, &associated, rent.minimum_balance(0));
+    env.process(&[prefund], &[]).await.unwrap();
+    let create =
+        instruction::create_associated_account(&program_id, &payer, &wallet, &mint.pubkey())
+            .unwrap();
+    env.process(std::slice::from_ref(&create), &[])
+        .await
+        .unwrap();
+    let account = env.get_account(&associated).await;
+    assert_eq!(account.owner, program_id);
+    assert_eq!(account.lamports, rent.minimum_balance(Token::LEN));
+    let token = env.get_token(&associated).await;
+    assert_eq!(
+        (token.mint, token.owner, token.amount),
+        (mint.pubkey(), wallet, 0)
+    );
+
+    env.recent_blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        env.process(&[create], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[tokio::test]
+async fn recover_nested_sweeps_tokens_sent_to_an_associated_accounts_address() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let wallet = Keypair::new();
+    let owner_mint = Keypair::new();
+    let nested_mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    env.create_mint(&owner_mint, &mint_authority.pubkey()).await;
+    env.create_mint(&nested_mint, &mint_authority.pubkey())
+        .await;
+
+    // The wallet's account for `owner_mint` is mistaken for a wallet, and
+    // tokens of `nested_mint` are sent to its associated account.
+    let payer = env.payer.pubkey();
+    let owner_associated =
+        get_associated_token_address(&wallet.pubkey(), &owner_mint.pubkey(), &program_id);
+    let nested =
+        get_associated_token_address(&owner_associated, &nested_mint.pubkey(), &program_id);
+    let destination =
+        get_associated_token_address(&wallet.pubkey(), &nested_mint.pubkey(), &program_id);
+    env.process(
+        &[
+            instruction::create_associated_account(
+                &program_id,
+                &payer,
+                &wallet.pubkey(),
+                &owner_mint.pubkey(),
+            )
+            .unwrap(),
+            instruction::create_associated_account(
+                &program_id,
+                &payer,
+                &owner_associated,
+                &nested_mint.pubkey(),
+            )
+            .unwrap(),
+            instruction::create_associated_account(
+                &program_id,
+                &payer,
+                &wallet.pubkey(),
+                &nested_mint.pubkey(),
+            )
+            .unwrap(),
+            instruction::mint_tokens(
+                &program_id,
+                &nested_mint.pubkey(),
+                &nested,
+                &mint_authority.pubkey(),
+                &[],
+                75,
+            )
+            .unwrap(),
+        ],
+        &[&mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.get_token(&nested).await.owner, owner_associated);
+    let nested_lamports = env.get_account(&nested).await.lamports;
+
+    let recover = instruction::recover_nested(
+        &program_id,
+        &wallet.pubkey(),
+        &owner_mint.pubkey(),
+        &nested_mint.pubkey(),
+    )
+    .unwrap();
+    assert_eq!(recover.accounts[0].pubkey, nested);
+
+    // Only the wallet the addresses derive from can recover them.
+    let thief = Keypair::new();
+    let mut stolen = recover.clone();
+    stolen.accounts[5] = AccountMeta::new(thief.pubkey(), true);
+    stolen.accounts[2] = AccountMeta::new(
+        get_associated_token_address(&thief.pubkey(), &nested_mint.pubkey(), &program_id),
+        false,
+    );
+    assert_eq!(
+        env.process(&[stolen], &[&thief]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+    // Nor can the wallet send the tokens anywhere but its own account.
+    let mut redirected = recover.clone();
+    redirected.accounts[2] = AccountMeta::new(owner_associated, false);
+    assert_eq!(
+        env.process(&[redirected], &[&wallet]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+
+    env.process(&[recover], &[&wallet]).await.unwrap();
+    assert_eq!(env.get_token(&destination).await.amount, 75);
+    assert!(env
+        .banks_client
+        .get_account(nested)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        env.get_account(&wallet.pubkey()).await.lamports,
+        nested_lamports
+    );
+    assert_eq!(
+        env.get_token(&owner_associated).await.owner,
+        wallet.pubkey()
+    );
+}
+
+#[tokio::test]
+async fn converters_burn_at_a_fixed_rate_and_respect_the_target_cap() {
+    let Fixture {
+        mut env,
+        mint: from_mint,
+        owner,
+        source,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let converter = Keypair::new();
+    let authority = get_converter_authority(&converter.pubkey(), &program_id);
+    let (to_mint, destination) = (Keypair::new(), Keypair::new());
+    env.create_capped_mint(&to_mint, &authority, Some(60)).await;
+    env.create_token_account(&destination, &to_mint.pubkey(), &owner.pubkey())
+        .await;
+    env.create_account(&converter, Converter::LEN, &program_id)
+        .await;
+
+    let create = |to_mint: &Pubkey, numerator, denominator| {
+        instruction::create_converter(
+            &program_id,
+            &converter.pubkey(),
+            &from_mint.pubkey(),
+            to_mint,
+            numerator,
+            denominator,
+        )
+        .unwrap()
+    };
+    let invalid = TransactionError::InstructionError(0, InstructionError::InvalidArgument);
+    for rejected in [
+        create(&to_mint.pubkey(), 0, 3),
+        create(&to_mint.pubkey(), 2, 0),
+        create(&from_mint.pubkey(), 2, 3),
+    ] {
+        assert_eq!(env.process(&[rejected], &[]).await.unwrap_err(), invalid);
+    }
+    // Converting the whole uncapped supply of 100 would overflow.
+    assert_eq!(
+        env.process(&[create(&to_mint.pubkey(), u64::MAX, 1)], &[])
+            .await
+            .unwrap_err(),
+        custom(TokenError::Overflow)
+    );
+    env.process(&[create(&to_mint.pubkey(), 2, 3)], &[])
+        .await
+        .unwrap();
+    let state = Converter::unpack(&env.get_account(&converter.pubkey()).await.data).unwrap();
+    assert_eq!(
+        (
+            state.numerator,
+            state.denominator,
+            state.conversion_authority
+        ),
+        (2, 3, authority)
+    );
+
+    let convert = |amount| {
+        instruction::convert(
+            &program_id,
+            &owner.pubkey(),
+            &source.pubkey(),
+            &from_mint.pubkey(),
+            &destination.pubkey(),
+            &to_mint.pubkey(),
+            &converter.pubkey(),
+            amount,
+        )
+        .unwrap()
+    };
+    async fn balances(env: &mut Env, tokens: [&Keypair; 2], mints: [&Keypair; 2]) -> [u64; 4] {
+        [
+            env.get_token(&tokens[0].pubkey()).await.amount,
+            env.get_token(&tokens[1].pubkey()).await.amount,
+            env.get_mint(&mints[0].pubkey()).await.supply,
+            env.get_mint(&mints[1].pubkey()).await.supply,
+        ]
+    }
+    let (tokens, mints) = ([&source, &destination], [&from_mint, &to_mint]);
+
+    // An exact multiple burns all of it.
+    env.process(&[convert(30)], &[&owner]).await.unwrap();
+    assert_eq!(balances(&mut env, tokens, mints).await, [70, 20, 70, 20]);
+    // A remainder rounds down, and the tokens it didn't use stay put.
+    env.process(&[convert(10)], &[&owner]).await.unwrap();
+    assert_eq!(balances(&mut env, tokens, mints).await, [61, 26, 61, 26]);
+    assert_eq!(
+        env.process(&[convert(1)], &[&owner]).await.unwrap_err(),
+        invalid
+    );
+
+    // Past the target cap of 60, nothing is burned either.
+    assert_eq!(
+        env.process(&[convert(61)], &[&owner]).await.unwrap_err(),
+        custom(TokenError::SupplyCapExceeded)
+    );
+    assert_eq!(balances(&mut env, tokens, mints).await, [61, 26, 61, 26]);
+    env.process(&[convert(51)], &[&owner]).await.unwrap();
+    assert_eq!(balances(&mut env, tokens, mints).await, [10, 60, 10, 60]);
+}
+
+#[tokio::test]
+async fn minimum_balances_hold_unless_the_enforcement_authority_cosigns() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let enforcer = Keypair::new();
+
+    let set_minimum = |minimum_balance, authority: &Keypair, cosigner: Option<&Keypair>| {
+        instruction::set_minimum_balance(
+            &program_id,
+            &source.pubkey(),
+            &owner.pubkey(),
+            &[],
+            &payer,
+            minimum_balance,
+            &authority.pubkey(),
+            cosigner.map(|cosigner| cosigner.pubkey()).as_ref(),
+        )
+        .unwrap()
+    };
+    env.process(&[set_minimum(60, &enforcer, None)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.get_extension::<MinimumBalance>(&source.pubkey()).await,
+        Some(MinimumBalance {
+            minimum_balance: 60,
+            enforcement_authority: enforcer.pubkey(),
+        })
+    );
+
+    // Co-signing is passing the enforcement authority after the signers.
+    let cosigned = |mut instruction: Instruction| {
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(enforcer.pubkey(), true));
+        instruction
+    };
+    let transfer = |amount| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    let burn = |amount| {
+        instruction::burn(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    let batch = |amount| {
+        instruction::transfer_batch(
+            &program_id,
+            &source.pubkey(),
+            &owner.pubkey(),
+            &[],
+            &[(&destination.pubkey(), amount)],
+        )
+        .unwrap()
+    };
+
+    // The excess moves freely, down to the floor.
+    env.process(&[transfer(30), burn(5), batch(5)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 60);
+    for breach in [transfer(1), burn(1), batch(1)] {
+        assert_eq!(
+            env.process(&[breach], &[&owner]).await.unwrap_err(),
+            custom(TokenError::BelowMinimumBalance)
+        );
+    }
+    assert_eq!(
+        env.process(&[transfer(0)], &[&owner]).await,
+        Ok(()),
+        "an empty debit breaches nothing"
+    );
+
+    // The enforcement authority's signature lets debits through the floor.
+    env.process(
+        &[cosigned(transfer(1)), cosigned(burn(2)), cosigned(batch(3))],
+        &[&owner, &enforcer],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 54);
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 39);
+
+    // The owner can raise the floor alone, but not lower, clear or hand it
+    // to another authority.
+    env.process(&[set_minimum(70, &enforcer, None)], &[&owner])
+        .await
+        .unwrap();
+    for loosened in [
+        set_minimum(10, &enforcer, None),
+        set_minimum(0, &enforcer, None),
+        set_minimum(70, &owner, None),
+    ] {
+        assert_eq!(
+            env.process(&[loosened], &[&owner]).await.unwrap_err(),
+            TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+        );
+    }
+    env.process(
+        &[set_minimum(0, &enforcer, Some(&enforcer))],
+        &[&owner, &enforcer],
+    )
+    .await
+    .unwrap();
+    env.process(&[transfer(54)], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 93);
+}
+
+#[tokio::test]
+async fn close_authorities_only_close_empty_accounts() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(10).await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let (back_office, replacement) = (Keypair::new(), Keypair::new());
+
+    let set_close_authority = |close_authority: Option<&Keypair>, authority: &Keypair| {
+        instruction::set_close_authority(
+            &program_id,
+            &source.pubkey(),
+            close_authority.map(Keypair::pubkey).as_ref(),
+            &authority.pubkey(),
+            &[],
+            &payer,
+        )
+        .unwrap()
+    };
+    // Each close goes to a fresh collector, so no two transactions match.
+    let close = |authority: &Keypair, collector: &Pubkey| {
+        instruction::close_account(
+            &program_id,
+            &source.pubkey(),
+            collector,
+            &authority.pubkey(),
+            &[],
+        )
+        .unwrap()
+    };
+    let owner_mismatch = custom(TokenError::OwnerMismatch);
+
+    env.process(
+        &[set_close_authority(Some(&back_office), &owner)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.get_extension::<CloseAuthority>(&source.pubkey()).await,
+        Some(CloseAuthority {
+            close_authority: COption::Some(back_office.pubkey()),
+        })
+    );
+
+    // It can't move, burn or hand on the tokens, nor close before they go.
+    let transfer = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &back_office.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    let burn = instruction::burn(
+        &program_id,
+        &source.pubkey(),
+        &mint.pubkey(),
+        &back_office.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    for rejected in [
+        transfer,
+        burn,
+        set_close_authority(Some(&back_office), &back_office),
+    ] {
+        assert_eq!(
+            env.process(&[rejected], &[&back_office]).await.unwrap_err(),
+            owner_mismatch
+        );
+    }
+    assert_eq!(
+        env.process(
+            &[close(&back_office, &Pubkey::new_unique())],
+            &[&back_office]
+        )
+        .await
+        .unwrap_err(),
+        custom(TokenError::NonZeroBalance)
+    );
+
+    let empty = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        10,
+    )
+    .unwrap();
+    env.process(&[empty], &[&owner]).await.unwrap();
+
+    // The owner replaces and revokes it at will.
+    env.process(
+        &[set_close_authority(Some(&replacement), &owner)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.process(
+            &[close(&back_office, &Pubkey::new_unique())],
+            &[&back_office]
+        )
+        .await
+        .unwrap_err(),
+        owner_mismatch
+    );
+    env.process(&[set_close_authority(None, &owner)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.process(
+            &[close(&replacement, &Pubkey::new_unique())],
+            &[&replacement]
+        )
+        .await
+        .unwrap_err(),
+        owner_mismatch
+    );
+
+    // Setting it back repeats the first transaction, so it needs a new
+    // blockhash.
+    env.recent_blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    env.process(
+        &[set_close_authority(Some(&back_office), &owner)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let lamports = env.get_account(&source.pubkey()).await.lamports;
+    let collector = Pubkey::new_unique();
+    env.process(&[close(&back_office, &collector)], &[&back_office])
+        .await
+        .unwrap();
+    assert!(env
+        .banks_client
+        .get_account(source.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(env.get_account(&collector).await.lamports, lamports);
+}
+
+#[tokio::test]
+async fn snapshots_keep_their_balance_until_their_payer_closes_them() {
+    let Fixture {
+        mut env,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(100).await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let snapshot = |snapshot_id| {
+        instruction::snapshot_balance(&program_id, &payer, &source.pubkey(), snapshot_id).unwrap()
+    };
+    let first = get_snapshot_address(&source.pubkey(), 1, &program_id);
+    let second = get_snapshot_address(&source.pubkey(), 2, &program_id);
+
+    env.process(&[snapshot(1)], &[]).await.unwrap();
+    let clock = env.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let taken = BalanceSnapshot::unpack(&env.get_account(&first).await.data).unwrap();
+    assert_eq!(
+        taken,
+        BalanceSnapshot {
+            is_initialized: true,
+            token_account: source.pubkey(),
+            owner: owner.pubkey(),
+            amount: 100,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+            payer,
+        }
+    );
+
+    let transfer = instruction::transfer_tokens(
+        &program_id,
+        &source.pubkey(),
+        &destination.pubkey(),
+        &owner.pubkey(),
+        &[],
+        40,
+    )
+    .unwrap();
+    env.process(&[transfer], &[&owner]).await.unwrap();
+    env.context.warp_to_slot(clock.slot + 10).unwrap();
+    env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+    env.process(&[snapshot(2)], &[]).await.unwrap();
+    let retaken = BalanceSnapshot::unpack(&env.get_account(&second).await.data).unwrap();
+    assert_eq!((retaken.amount, retaken.slot), (60, clock.slot + 10));
+
+    // The first snapshot is unchanged, and can't be taken again.
+    assert_eq!(
+        BalanceSnapshot::unpack(&env.get_account(&first).await.data).unwrap(),
+        taken
+    );
+    assert_eq!(
+        env.process(&[snapshot(1)], &[]).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    // Only the payer can close a snapshot, and only after the retention
+    // period.
+    let collector = Pubkey::new_unique();
+    let stranger = Keypair::new();
+    let close = |snapshot: &Pubkey, payer: &Pubkey| {
+        instruction::close_snapshot(&program_id, snapshot, &collector, payer).unwrap()
+    };
+    assert_eq!(
+        env.process(&[close(&first, &stranger.pubkey())], &[&stranger])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    assert_eq!(
+        env.process(&[close(&first, &payer)], &[])
+            .await
+            .unwrap_err(),
+        custom(TokenError::SnapshotRetained)
+    );
+
+    env.context
+        .warp_to_slot(taken.slot + SNAPSHOT_RETENTION_SLOTS)
+        .unwrap();
+    env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+    let lamports = env.get_account(&first).await.lamports;
+    env.process(&[close(&first, &payer)], &[]).await.unwrap();
+    assert!(env.banks_client.get_account(first).await.unwrap().is_none());
+    assert_eq!(env.get_account(&collector).await.lamports, lamports);
+    assert_eq!(
+        env.process(&[close(&second, &payer)], &[])
+            .await
+            .unwrap_err(),
+        custom(TokenError::SnapshotRetained)
+    );
+}
+
+#[tokio::test]
+async fn owners_prove_control_of_accounts_without_moving_funds() {
+    let mut env = start().await;
+    let program_id = env.program_id;
+    let mint = Keypair::new();
+    let freeze_authority = Keypair::new();
+    let owner = Keypair::new();
+    let account = Keypair::new();
+
+    env.create_account(&mint, Mint::LEN, &program_id).await;
+    let initialize = instruction::initialize_mint(
+        &program_id,
+        &mint.pubkey(),
+        &freeze_authority.pubkey(),
+        Some(&freeze_authority.pubkey()),
+        6,
+        None,
+    )
+    .unwrap();
+    env.process(&[initialize], &[]).await.unwrap();
+    env.create_token_account(&account, &mint.pubkey(), &owner.pubkey())
+        .await;
+    let verify = |authority: &Keypair, nonce| {
+        instruction::verify_ownership(
+            &program_id,
+            &account.pubkey(),
+            &authority.pubkey(),
+            &[],
+            nonce,
+        )
+        .unwrap()
+    };
+
+    let logs = env
+        .process_logs(&[verify(&owner, 7)], &[&owner])
+        .await
+        .unwrap();
+    let clock = env.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let proof = OwnershipProvedEvent {
+        token_account: account.pubkey(),
+        owner: owner.pubkey(),
+        nonce: 7,
+        unix_timestamp: clock.unix_timestamp,
+    };
+    assert_eq!(
+        event::find_ownership_proof(&logs, &program_id, &account.pubkey(), 7),
+        Some(proof.clone())
+    );
+    // Another nonce, account or program proves nothing.
+    assert_eq!(
+        event::find_ownership_proof(&logs, &program_id, &account.pubkey(), 8),
+        None
+    );
+    assert_eq!(
+        event::find_ownership_proof(&logs, &program_id, &mint.pubkey(), 7),
+        None
+    );
+    assert_eq!(
+        event::find_ownership_proof(&logs, &Pubkey::new_unique(), &account.pubkey(), 7),
+        None
+    );
+
+    // Nor does the same event logged by another program.
+    let impostor = Pubkey::new_unique();
+    let data = BASE64_STANDARD.encode(TokenEvent::OwnershipProved(proof.clone()).pack());
+    let forged = [
+        format!("Program {program_id} invoke [1]"),
+        format!("Program {impostor} invoke [2]"),
+        format!("Program data: {data}"),
+        format!("Program {impostor} success"),
+        format!("Program {program_id} success"),
+    ]
+    .to_vec();
+    assert_eq!(
+        event::find_ownership_proof(&forged, &program_id, &account.pubkey(), 7),
+        None
+    );
+    assert_eq!(
+        event::find_ownership_proof(&forged, &impostor, &account.pubkey(), 7),
+        Some(proof)
+    );
+
+    let impostor_owner = Keypair::new();
+    assert_eq!(
+        env.process(&[verify(&impostor_owner, 9)], &[&impostor_owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+
+    // A frozen account is still its owner's to prove, and stays untouched.
+    let freeze = instruction::freeze_account(
+        &program_id,
+        &account.pubkey(),
+        &mint.pubkey(),
+        &freeze_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    env.process(&[freeze], &[&freeze_authority]).await.unwrap();
+    let frozen = env.get_account(&account.pubkey()).await;
+    let logs = env
+        .process_logs(&[verify(&owner, 10)], &[&owner])
+        .await
+        .unwrap();
+    assert!(event::find_ownership_proof(&logs, &program_id, &account.pubkey(), 10).is_some());
+    assert_eq!(env.get_account(&account.pubkey()).await, frozen);
+
+    let uninitialized = Keypair::new();
+    env.create_account(&uninitialized, Token::LEN, &program_id)
+        .await;
+    let verify_uninitialized = instruction::verify_ownership(
+        &program_id,
+        &uninitialized.pubkey(),
+        &owner.pubkey(),
+        &[],
+        11,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[verify_uninitialized], &[&owner])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::UninitializedAccount)
+    );
+}
+
+#[tokio::test]
+async fn outflow_limits_cap_each_instruction_and_loosen_only_after_a_timelock() {
+    let Fixture {
+        mut env,
+        mint,
+        owner,
+        source,
+        destination,
+        ..
+    } = setup(1_000).await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let other = Keypair::new();
+    env.create_token_account(&other, &mint.pubkey(), &Pubkey::new_unique())
+        .await;
+
+    let set_limit = |max_outflow_per_instruction, timelock_slots| {
+        instruction::set_outflow_limit(
+            &program_id,
+            &source.pubkey(),
+            &owner.pubkey(),
+            &[],
+            &payer,
+            max_outflow_per_instruction,
+            timelock_slots,
+        )
+        .unwrap()
+    };
+    let transfer = |authority: &Keypair, amount| {
+        instruction::transfer_tokens(
+            &program_id,
+            &source.pubkey(),
+            &destination.pubkey(),
+            &authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    let batch = |amounts: [u64; 2]| {
+        instruction::transfer_batch(
+            &program_id,
+            &source.pubkey(),
+            &owner.pubkey(),
+            &[],
+            &[
+                (&destination.pubkey(), amounts[0]),
+                (&other.pubkey(), amounts[1]),
+            ],
+        )
+        .unwrap()
+    };
+    let burn = |amount| {
+        instruction::burn(
+            &program_id,
+            &source.pubkey(),
+            &mint.pubkey(),
+            &owner.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+
+    env.process(&[set_limit(100, 50)], &[&owner]).await.unwrap();
+    assert_eq!(
+        env.get_extension::<OutflowLimit>(&source.pubkey()).await,
+        Some(OutflowLimit {
+            max_outflow_per_instruction: 100,
+            timelock_slots: 50,
+            pending_max_outflow_per_instruction: 0,
+            pending_effective_slot: 0,
+        })
+    );
+    let exceeded = custom(TokenError::OutflowLimitExceeded);
+    assert_eq!(
+        env.process(&[transfer(&owner, 101)], &[&owner])
+            .await
+            .unwrap_err(),
+        exceeded
+    );
+    assert_eq!(
+        env.process(&[burn(101)], &[&owner]).await.unwrap_err(),
+        exceeded
+    );
+    // Each leg is under the cap, but not their sum.
+    assert_eq!(
+        env.process(&[batch([60, 60])], &[&owner])
+            .await
+            .unwrap_err(),
+        exceeded
+    );
+    // Whoever signs.
+    let delegate = Keypair::new();
+    let approve = instruction::approve(
+        &program_id,
+        &source.pubkey(),
+        &delegate.pubkey(),
+        &owner.pubkey(),
+        &[],
+        500,
+    )
+    .unwrap();
+    env.process(&[approve], &[&owner]).await.unwrap();
+    assert_eq!(
+        env.process(&[transfer(&delegate, 150)], &[&delegate])
+            .await
+            .unwrap_err(),
+        exceeded
+    );
+    env.process(&[transfer(&owner, 100)], &[&owner])
+        .await
+        .unwrap();
+    env.process(&[batch([50, 50])], &[&owner]).await.unwrap();
+    env.process(&[burn(100)], &[&owner]).await.unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 700);
+
+    // Only the owner sets the limit, and the timelock only grows.
+    let stranger = Keypair::new();
+    let by_stranger = instruction::set_outflow_limit(
+        &program_id,
+        &source.pubkey(),
+        &stranger.pubkey(),
+        &[],
+        &payer,
+        u64::MAX,
+        50,
+    )
+    .unwrap();
+    assert_eq!(
+        env.process(&[by_stranger], &[&stranger]).await.unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    assert_eq!(
+        env.process(&[set_limit(100, 49)], &[&owner])
+            .await
+            .unwrap_err(),
+        custom(TokenError::TimelockCannotBeShortened)
+    );
+
+    // A higher limit waits out the timelock.
+    env.process(&[set_limit(300, 50)], &[&owner]).await.unwrap();
+    let raised = env
+        .get_extension::<OutflowLimit>(&source.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(
+        (
+            raised.max_outflow_per_instruction,
+            raised.pending_max_outflow_per_instruction
+        ),
+        (100, 300)
+    );
+    let effective_slot = raised.pending_effective_slot;
+    assert_eq!(
+        effective_slot,
+        env.banks_client.get_sysvar::<Clock>().await.unwrap().slot + 50
+    );
+    env.context.warp_to_slot(effective_slot - 1).unwrap();
+    env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+    assert_eq!(
+        env.process(&[transfer(&owner, 200)], &[&owner])
+            .await
+            .unwrap_err(),
+        exceeded
+    );
+    env.context.warp_to_slot(effective_slot).unwrap();
+    env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+    env.process(&[transfer(&owner, 200)], &[&owner])
+        .await
+        .unwrap();
+
+    // A lower one applies at once and cancels a pending raise.
+    env.process(&[set_limit(1_000, 50)], &[&owner])
+        .await
+        .unwrap();
+    env.process(&[set_limit(20, 60)], &[&owner]).await.unwrap();
+    assert_eq!(
+        env.get_extension::<OutflowLimit>(&source.pubkey()).await,
+        Some(OutflowLimit {
+            max_outflow_per_instruction: 20,
+            timelock_slots: 60,
+            pending_max_outflow_per_instruction: 0,
+            pending_effective_slot: 0,
+        })
+    );
+    assert_eq!(
+        env.process(&[transfer(&owner, 21)], &[&owner])
+            .await
+            .unwrap_err(),
+        exceeded
+    );
+    env.process(&[transfer(&owner, 20)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.get_token(&source.pubkey()).await.amount, 480);
+}
+
+#[tokio::test]
+async fn listed_minters_mint_within_their_caps_until_removed() {
+    let Fixture {
+        mut env,
+        mint,
+        mint_authority,
+        destination,
+        ..
+    } = setup(1_000).await;
+    let program_id = env.program_id;
+    let payer = env.payer.pubkey();
+    let (regional, capped, stranger) = (Keypair::new(), Keypair::new(), Keypair::new());
+
+    let add_minter = |authority: &Keypair, minter: &Keypair, max_per_window, window_slots| {
+        instruction::add_minter(
+            &program_id,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            &[],
+            &payer,
+            &minter.pubkey(),
+            max_per_window,
+            window_slots,
+        )
+        .unwrap()
+    };
+    let remove_minter = |minter: &Keypair| {
+        instruction::remove_minter(
+            &program_id,
+            &mint.pubkey(),
+            &mint_authority.pubkey(),
+            &[],
+            &minter.pubkey(),
+        )
+        .unwrap()
+    };
+    let mint_by = |authority: &Keypair, amount| {
+        instruction::mint_tokens(
+            &program_id,
+            &mint.pubkey(),
+            &destination.pubkey(),
+            &authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    let minters = |data: &[u8]| {
+        extension::get_mint_extension::<Minters>(data)
+            .unwrap()
+            .unwrap()
+            .minters
+    };
+
+    // Only the mint authority adds minters, and a cap needs a window.
+    assert_eq!(
+        env.process(&[add_minter(&stranger, &regional, 0, 0)], &[&stranger])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    assert_eq!(
+        env.process(
+            &[add_minter(&mint_authority, &regional, 100, 0)],
+            &[&mint_authority]
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+    env.process(
+        &[add_minter(&mint_authority, &regional, 0, 0)],
+        &[&mint_authority],
+    )
+    .await
+    .unwrap();
+    let data = env.get_account(&mint.pubkey()).await.data;
+    assert_eq!(data.len(), Mint::LEN + extension::HEADER_LEN + Minters::LEN);
+    assert_eq!(
+        minters(&data),
+        [Minter {
+            minter: regional.pubkey(),
+            ..Minter::default()
+        }]
+    );
+
+    // A minter mints unsigned by the mint authority, and says so.
+    let logs = env
+        .process_logs(&[mint_by(&regional, 500)], &[&regional])
+        .await
+        .unwrap();
+    assert_eq!(
+        event::events_from_logs(&logs, &program_id),
+        [
+            TokenEvent::Mint(MintEvent {
+                mint: mint.pubkey(),
+                destination: destination.pubkey(),
+                amount: 500,
+            }),
+            TokenEvent::MinterUsed(MinterUsedEvent {
+                mint: mint.pubkey(),
+                minter: regional.pubkey(),
+                amount: 500,
+            }),
+        ]
+    );
+    let logs = env
+        .process_logs(&[mint_by(&mint_authority, 1)], &[&mint_authority])
+        .await
+        .unwrap();
+    assert_eq!(event::events_from_logs(&logs, &program_id).len(), 1);
+    assert_eq!(
+        env.process(&[mint_by(&stranger, 1)], &[&stranger])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    assert_eq!(env.get_token(&destination.pubkey()).await.amount, 501);
+
+    // A capped minter's window refills once it has passed.
+    env.process(
+        &[add_minter(&mint_authority, &capped, 100, 20)],
+        &[&mint_authority],
+    )
+    .await
+    .unwrap();
+    let window_start_slot =
+        minters(&env.get_account(&mint.pubkey()).await.data)[1].window_start_slot;
+    env.process(&[mint_by(&capped, 60)], &[&capped])
+        .await
+        .unwrap();
+    let exceeded = custom(TokenError::MinterCapExceeded);
+    assert_eq!(
+        env.process(&[mint_by(&capped, 41)], &[&capped])
+            .await
+            .unwrap_err(),
+        exceeded
+    );
+    env.process(&[mint_by(&capped, 40)], &[&capped])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.process(&[mint_by(&capped, 1)], &[&capped])
+            .await
+            .unwrap_err(),
+        exceeded
+    );
+    env.context.warp_to_slot(window_start_slot + 20).unwrap();
+    env.recent_blockhash = env.banks_client.get_latest_blockhash().await.unwrap();
+    env.process(&[mint_by(&capped, 100)], &[&capped])
+        .await
+        .unwrap();
+    let data = env.get_account(&mint.pubkey()).await.data;
+    assert_eq!(
+        minters(&data)[1],
+        Minter {
+            minter: capped.pubkey(),
+            max_per_window: 100,
+            window_slots: 20,
+            window_start_slot: window_start_slot + 20,
+            minted_in_window: 100,
+        }
+    );
+    assert_eq!(Mint::unpack(&data).unwrap().supply, 1_701);
+
+    // A removed minter is a stranger again.
+    env.process(&[remove_minter(&regional)], &[&mint_authority])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.process(&[mint_by(&regional, 2)], &[&regional])
+            .await
+            .unwrap_err(),
+        custom(TokenError::OwnerMismatch)
+    );
+    env.recent_blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        env.process(&[remove_minter(&regional)], &[&mint_authority])
+            .await
+            .unwrap_err(),
+        custom(TokenError::NotAMinter)
+    );
+    let data = env.get_account(&mint.pubkey()).await.data;
+    assert_eq!(data.len(), Mint::LEN + extension::HEADER_LEN + Minters::LEN);
+    assert_eq!(minters(&data).len(), 1);
+
+    // Nor can minters mint once the supply is fixed.
+    let fix_supply = instruction::set_authority(
+        &program_id,
+        &mint.pubkey(),
+        None,
+        instruction::AuthorityType::MintTokens,
+        &mint_authority.pubkey(),
+        &[],
+    )
+    .unwrap();
+    env.process(&[fix_supply], &[&mint_authority])
+        .await
+        .unwrap();
+    env.recent_blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        env.process(&[mint_by(&capped, 1)], &[&capped])
+            .await
+            .unwrap_err(),
+        custom(TokenError::FixedSupply)
+    );
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "serde")]
+
+use serde_json::json;
+use solana_contracts::{
+    instruction::MAX_SIGNERS,
+    state::{AccountState, TransferFee},
+    Mint, Multisig, Token,
+};
+use solana_program::{program_option::COption, pubkey::Pubkey};
+
+#[test]
+fn token_json_round_trip() {
+    let token = Token {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount: 42,
+        delegate: COption::Some(Pubkey::new_unique()),
+        delegated_amount: 2,
+        state: AccountState::Frozen,
+        withheld_amount: COption::Some(5),
+        ..Token::default()
+    };
+    let value = serde_json::to_value(&token).unwrap();
+    assert_eq!(value["mint"], json!(token.mint.to_string()));
+    assert_eq!(
+        value["delegate"],
+        json!(token.delegate.unwrap().to_string())
+    );
+    assert_eq!(value["state"], json!("Frozen"));
+    assert_eq!(value["is_native"], json!(null));
+    assert_eq!(value["withheld_amount"], json!(5));
+    assert_eq!(serde_json::from_value::<Token>(value).unwrap(), token);
+}
+
+#[test]
+fn mint_json_round_trip() {
+    let mint = Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply: u64::MAX,
+        decimals: 9,
+        is_initialized: true,
+        max_supply: COption::Some(u64::MAX),
+        transfer_fee: COption::Some(TransferFee {
+            basis_points: 100,
+            maximum_fee: 50,
+        }),
+        ..Mint::default()
+    };
+    let json = serde_json::to_string(&mint).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["freeze_authority"], json!(null));
+    assert_eq!(value["supply"], json!(u64::MAX));
+    assert_eq!(
+        value["transfer_fee"],
+        json!({ "basis_points": 100, "maximum_fee": 50 })
+    );
+    assert_eq!(serde_json::from_str::<Mint>(&json).unwrap(), mint);
+}
+
+#[test]
+fn multisig_json_round_trip() {
+    let mut signers = [Pubkey::default(); MAX_SIGNERS];
+    signers[0] = Pubkey::new_unique();
+    signers[1] = Pubkey::new_unique();
+    let multisig = Multisig {
+        m: 1,
+        n: 2,
+        is_initialized: true,
+        signers,
+    };
+    let value = serde_json::to_value(&multisig).unwrap();
+    assert_eq!(value["signers"][1], json!(signers[1].to_string()));
+    assert_eq!(serde_json::from_value::<Multisig>(value).unwrap(), multisig);
+}
+
+#[test]
+fn invalid_pubkeys_and_signer_counts_are_rejected() {
+    let mut value = serde_json::to_value(Token::default()).unwrap();
+    value["owner"] = json!("not a pubkey");
+    assert!(serde_json::from_value::<Token>(value).is_err());
+
+    let mut value = serde_json::to_value(Multisig::default()).unwrap();
+    value["signers"] = json!([Pubkey::default().to_string()]);
+    assert!(serde_json::from_value::<Multisig>(value).is_err());
+}
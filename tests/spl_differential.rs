@@ -0,0 +1,580 @@
+//! Runs the same random operation sequences against this program and the
+//! spl-token program `solana-program-test` deploys, and requires the two to
+//! agree on every balance, on the supply and on how each operation fails.
+//!
+//! Only the instructions both programs share are drawn: initialize account,
+//! mint, transfer, burn, approve and close. Transfers and burns never use
+//! [`AMOUNT_ALL`], which this program reads as the whole balance. Error codes
+//! differ between the programs, so failures are compared by [`Outcome`]. A
+//! divergence fails with the shrunk sequence that still reproduces it.
+
+use proptest::{
+    prelude::*,
+    test_runner::{Config, TestError, TestRunner},
+};
+use solana_contracts::{
+    error::TokenError, instruction, instruction::AMOUNT_ALL, process_instruction, Mint, Token,
+};
+use solana_program::{
+    instruction::{Instruction, InstructionError},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use solana_program_test::{processor, BanksClient, BanksClientError, ProgramTest};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use spl_token::error::TokenError as SplError;
+
+/// Token accounts per program. The first two start initialized.
+const ACCOUNTS: usize = 3;
+/// Signers operations are drawn from: the mint authority, two owners, a
+/// delegate and a key with no role at all.
+const ACTORS: usize = 5;
+const MINT_AUTHORITY: usize = 0;
+const DELEGATE: usize = 3;
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    InitializeAccount {
+        account: usize,
+        owner: usize,
+    },
+    MintTo {
+        account: usize,
+        amount: u64,
+        authority: usize,
+    },
+    Transfer {
+        source: usize,
+        destination: usize,
+        amount: u64,
+        authority: usize,
+    },
+    Burn {
+        account: usize,
+        amount: u64,
+        authority: usize,
+    },
+    /// Approves the delegate actor.
+    Approve {
+        account: usize,
+        amount: u64,
+        owner: usize,
+    },
+    Close {
+        account: usize,
+        owner: usize,
+    },
+}
+
+/// How an operation ended, in terms both programs' errors map to.
+#[derive(Clone, Debug, PartialEq)]
+enum Outcome {
+    Succeeded,
+    InsufficientFunds,
+    OwnerMismatch,
+    MintMismatch,
+    Overflow,
+    NonZeroBalance,
+    AlreadyInitialized,
+    /// Uninitialized, closed, or not the program's at all. The programs
+    /// check ownership and initialization in different orders, so these
+    /// are one outcome.
+    UnusableAccount,
+    /// An error with no counterpart; never equal to the other program's.
+    Unmapped(&'static str, TransactionError),
+}
+
+/// What one token account holds, or that it is gone.
+#[derive(Clone, Debug, PartialEq)]
+enum Holding {
+    Closed,
+    Uninitialized,
+    Initialized {
+        owner: Pubkey,
+        amount: u64,
+        delegate: Option<Pubkey>,
+        delegated_amount: u64,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Snapshot {
+    supply: u64,
+    holdings: Vec<Holding>,
+}
+
+fn amount() -> impl Strategy<Value = u64> {
+    prop_oneof![4 => 0..1_000u64, 1 => Just(u64::MAX / 2 + 1)]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let account = 0..ACCOUNTS;
+    let actor = 0..ACTORS;
+    prop_oneof![
+        (account.clone(), actor.clone())
+            .prop_map(|(account, owner)| Op::InitializeAccount { account, owner }),
+        (account.clone(), amount(), actor.clone()).prop_map(|(account, amount, authority)| {
+            Op::MintTo {
+                account,
+                amount,
+                authority,
+            }
+        }),
+        (account.clone(), account.clone(), amount(), actor.clone()).prop_map(
+            |(source, destination, amount, authority)| Op::Transfer {
+                source,
+                destination,
+                amount,
+                authority,
+            }
+        ),
+        (account.clone(), amount(), actor.clone()).prop_map(|(account, amount, authority)| {
+            Op::Burn {
+                account,
+                amount,
+                authority,
+            }
+        }),
+        (account.clone(), amount(), actor.clone()).prop_map(|(account, amount, owner)| {
+            Op::Approve {
+                account,
+                amount,
+                owner,
+            }
+        }),
+        (account, actor).prop_map(|(account, owner)| Op::Close { account, owner }),
+    ]
+}
+
+/// One program's mint and token accounts.
+struct Deployment {
+    name: &'static str,
+    program_id: Pubkey,
+    mint: Pubkey,
+    accounts: Vec<Pubkey>,
+}
+
+impl Deployment {
+    fn is_ours(&self) -> bool {
+        self.program_id != spl_token::id()
+    }
+
+    fn instruction(&self, op: Op, actors: &[Keypair]) -> Instruction {
+        let (id, mint) = (&self.program_id, &self.mint);
+        let account = |i: usize| &self.accounts[i];
+        let actor = |i: usize| actors[i].pubkey();
+        let built = match (op, self.is_ours()) {
+            (Op::InitializeAccount { account: i, owner }, true) => {
+                instruction::initialize_account(id, account(i), mint, &actor(owner))
+            }
+            (Op::InitializeAccount { account: i, owner }, false) => {
+                spl_token::instruction::initialize_account(id, account(i), mint, &actor(owner))
+            }
+            (
+                Op::MintTo {
+                    account: i,
+                    amount,
+                    authority,
+                },
+                true,
+            ) => instruction::mint_tokens(id, mint, account(i), &actor(authority), &[], amount),
+            (
+                Op::MintTo {
+                    account: i,
+                    amount,
+                    authority,
+                },
+                false,
+            ) => spl_token::instruction::mint_to(
+                id,
+                mint,
+                account(i),
+                &actor(authority),
+                &[],
+                amount,
+            ),
+            (
+                Op::Transfer {
+                    source,
+                    destination,
+                    amount,
+                    authority,
+                },
+                true,
+            ) => instruction::transfer_tokens(
+                id,
+                account(source),
+                account(destination),
+                &actor(authority),
+                &[],
+                amount,
+            ),
+            (
+                Op::Transfer {
+                    source,
+                    destination,
+                    amount,
+                    authority,
+                },
+                false,
+            ) => spl_token::instruction::transfer(
+                id,
+                account(source),
+                account(destination),
+                &actor(authority),
+                &[],
+                amount,
+            ),
+            (
+                Op::Burn {
+                    account: i,
+                    amount,
+                    authority,
+                },
+                true,
+            ) => instruction::burn(id, account(i), mint, &actor(authority), &[], amount),
+            (
+                Op::Burn {
+                    account: i,
+                    amount,
+                    authority,
+                },
+                false,
+            ) => spl_token::instruction::burn(id, account(i), mint, &actor(authority), &[], amount),
+            (
+                Op::Approve {
+                    account: i,
+                    amount,
+                    owner,
+                },
+                true,
+            ) => instruction::approve(id, account(i), &actor(DELEGATE), &actor(owner), &[], amount),
+            (
+                Op::Approve {
+                    account: i,
+                    amount,
+                    owner,
+                },
+                false,
+            ) => spl_token::instruction::approve(
+                id,
+                account(i),
+                &actor(DELEGATE),
+                &actor(owner),
+                &[],
+                amount,
+            ),
+            (Op::Close { account: i, owner }, true) => {
+                instruction::close_account(id, account(i), &rent_collector(), &actor(owner), &[])
+            }
+            (Op::Close { account: i, owner }, false) => spl_token::instruction::close_account(
+                id,
+                account(i),
+                &rent_collector(),
+                &actor(owner),
+                &[],
+            ),
+        };
+        built.unwrap()
+    }
+
+    fn outcome(&self, result: Result<(), TransactionError>) -> Outcome {
+        let error = match result {
+            Ok(()) => return Outcome::Succeeded,
+            Err(error) => error,
+        };
+        // The operation is the second instruction, after the fee price.
+        let outcome = match &error {
+            TransactionError::InstructionError(1, InstructionError::Custom(code)) => {
+                if self.is_ours() {
+                    ours(*code)
+                } else {
+                    theirs(*code)
+                }
+            }
+            TransactionError::InstructionError(1, error) => match error {
+                InstructionError::AccountAlreadyInitialized => Some(Outcome::AlreadyInitialized),
+                InstructionError::UninitializedAccount
+                | InstructionError::IncorrectProgramId
+                | InstructionError::InvalidAccountData
+                | InstructionError::InvalidAccountOwner => Some(Outcome::UnusableAccount),
+                _ => None,
+            },
+            _ => None,
+        };
+        outcome.unwrap_or(Outcome::Unmapped(self.name, error))
+    }
+}
+
+fn ours(code: u32) -> Option<Outcome> {
+    [
+        (TokenError::InsufficientFunds, Outcome::InsufficientFunds),
+        (TokenError::OwnerMismatch, Outcome::OwnerMismatch),
+        (TokenError::MintMismatch, Outcome::MintMismatch),
+        (TokenError::Overflow, Outcome::Overflow),
+        (TokenError::NonZeroBalance, Outcome::NonZeroBalance),
+    ]
+    .into_iter()
+    .find(|(error, _)| error.clone() as u32 == code)
+    .map(|(_, outcome)| outcome)
+}
+
+fn theirs(code: u32) -> Option<Outcome> {
+    [
+        (SplError::InsufficientFunds, Outcome::InsufficientFunds),
+        (SplError::OwnerMismatch, Outcome::OwnerMismatch),
+        (SplError::MintMismatch, Outcome::MintMismatch),
+        (SplError::Overflow, Outcome::Overflow),
+        (SplError::NonNativeHasBalance, Outcome::NonZeroBalance),
+        (SplError::AlreadyInUse, Outcome::AlreadyInitialized),
+        (SplError::UninitializedState, Outcome::UnusableAccount),
+    ]
+    .into_iter()
+    .find(|(error, _)| error.clone() as u32 == code)
+    .map(|(_, outcome)| outcome)
+}
+
+/// Where closed accounts' lamports go; never read back, since the two
+/// programs' accounts differ in length and so in rent.
+fn rent_collector() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+struct Env {
+    banks_client: BanksClient,
+    payer: Keypair,
+    recent_blockhash: Hash,
+    actors: Vec<Keypair>,
+    /// Priced into each operation's transaction, so repeating an operation
+    /// never repeats a transaction.
+    nonce: u64,
+}
+
+impl Env {
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.recent_blockhash,
+        );
+        self.banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|error| match error {
+                BanksClientError::TransactionError(error) => error,
+                error => panic!("{error:?}"),
+            })
+    }
+
+    async fn deploy(&mut self, name: &'static str, program_id: Pubkey) -> Deployment {
+        let (mint_len, account_len) = if program_id == spl_token::id() {
+            (spl_token::state::Mint::LEN, spl_token::state::Account::LEN)
+        } else {
+            (Mint::LEN, Token::LEN)
+        };
+        let rent = self.banks_client.get_rent().await.unwrap();
+        let mint = Keypair::new();
+        let accounts: Vec<Keypair> = (0..ACCOUNTS).map(|_| Keypair::new()).collect();
+        let mut instructions = vec![system_instruction::create_account(
+            &self.payer.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(mint_len),
+            mint_len as u64,
+            &program_id,
+        )];
+        instructions.extend(accounts.iter().map(|account| {
+            system_instruction::create_account(
+                &self.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(account_len),
+                account_len as u64,
+                &program_id,
+            )
+        }));
+        let authority = self.actors[MINT_AUTHORITY].pubkey();
+        instructions.push(if program_id == spl_token::id() {
+            spl_token::instruction::initialize_mint(
+                &program_id,
+                &mint.pubkey(),
+                &authority,
+                None,
+                2,
+            )
+            .unwrap()
+        } else {
+            instruction::initialize_mint(&program_id, &mint.pubkey(), &authority, None, 2, None)
+                .unwrap()
+        });
+        let mut signers = vec![&mint];
+        signers.extend(accounts.iter());
+        self.process(&instructions, &signers).await.unwrap();
+
+        let deployment = Deployment {
+            name,
+            program_id,
+            mint: mint.pubkey(),
+            accounts: accounts.iter().map(Keypair::pubkey).collect(),
+        };
+        for (account, owner) in [(0, 1), (1, 2)] {
+            let outcome = self
+                .apply(&deployment, Op::InitializeAccount { account, owner })
+                .await;
+            assert_eq!(outcome, Outcome::Succeeded);
+        }
+        deployment
+    }
+
+    async fn apply(&mut self, deployment: &Deployment, op: Op) -> Outcome {
+        self.nonce += 1;
+        let signer = match op {
+            Op::InitializeAccount { .. } => None,
+            Op::MintTo { authority, .. }
+            | Op::Transfer { authority, .. }
+            | Op::Burn { authority, .. } => Some(authority),
+            Op::Approve { owner, .. } | Op::Close { owner, .. } => Some(owner),
+        };
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_price(self.nonce),
+            deployment.instruction(op, &self.actors),
+        ];
+        let actors = std::mem::take(&mut self.actors);
+        let signers: Vec<&Keypair> = signer.map(|i| &actors[i]).into_iter().collect();
+        let result = self.process(&instructions, &signers).await;
+        self.actors = actors;
+        deployment.outcome(result)
+    }
+
+    async fn snapshot(&mut self, deployment: &Deployment) -> Snapshot {
+        let mint = self
+            .banks_client
+            .get_account(deployment.mint)
+            .await
+            .unwrap()
+            .unwrap();
+        let supply = if deployment.is_ours() {
+            Mint::unpack(&mint.data).unwrap().supply
+        } else {
+            spl_token::state::Mint::unpack(&mint.data).unwrap().supply
+        };
+        let mut holdings = Vec::with_capacity(ACCOUNTS);
+        for address in &deployment.accounts {
+            let account = self.banks_client.get_account(*address).await.unwrap();
+            let data = match account {
+                Some(account) if account.lamports > 0 && !account.data.is_empty() => account.data,
+                _ => {
+                    holdings.push(Holding::Closed);
+                    continue;
+                }
+            };
+            holdings.push(if deployment.is_ours() {
+                let token = Token::unpack_unchecked(&data).unwrap();
+                if token.state == solana_contracts::AccountState::Uninitialized {
+                    Holding::Uninitialized
+                } else {
+                    Holding::Initialized {
+                        owner: token.owner,
+                        amount: token.amount,
+                        delegate: option(token.delegate),
+                        delegated_amount: token.delegated_amount,
+                    }
+                }
+            } else {
+                let account = spl_token::state::Account::unpack_unchecked(&data).unwrap();
+                if account.state == spl_token::state::AccountState::Uninitialized {
+                    Holding::Uninitialized
+                } else {
+                    Holding::Initialized {
+                        owner: account.owner,
+                        amount: account.amount,
+                        delegate: option(account.delegate),
+                        delegated_amount: account.delegated_amount,
+                    }
+                }
+            });
+        }
+        Snapshot { supply, holdings }
+    }
+}
+
+fn option(value: COption<Pubkey>) -> Option<Pubkey> {
+    match value {
+        COption::Some(value) => Some(value),
+        COption::None => None,
+    }
+}
+
+/// Applies `ops` to both programs, failing at the first operation they
+/// disagree on.
+async fn run(ops: &[Op]) -> Result<(), TestCaseError> {
+    let program_id = Pubkey::new_unique();
+    let program_test = ProgramTest::new(
+        "solana_contracts",
+        program_id,
+        processor!(process_instruction),
+    );
+    let (banks_client, payer, recent_blockhash) = program_test.start().await;
+    let mut env = Env {
+        banks_client,
+        payer,
+        recent_blockhash,
+        actors: (0..ACTORS).map(|_| Keypair::new()).collect(),
+        nonce: 0,
+    };
+    let ours = env.deploy("solana_contracts", program_id).await;
+    let theirs = env.deploy("spl-token", spl_token::id()).await;
+
+    for (step, &op) in ops.iter().enumerate() {
+        let (ours_outcome, theirs_outcome) =
+            (env.apply(&ours, op).await, env.apply(&theirs, op).await);
+        prop_assert_eq!(
+            &ours_outcome,
+            &theirs_outcome,
+            "outcomes diverge at step {} ({:?})",
+            step,
+            op
+        );
+        let (ours_state, theirs_state) = (env.snapshot(&ours).await, env.snapshot(&theirs).await);
+        prop_assert_eq!(
+            ours_state,
+            theirs_state,
+            "state diverges at step {} ({:?})",
+            step,
+            op
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn shared_instructions_match_spl_token() {
+    assert_ne!(u64::MAX / 2 + 1, AMOUNT_ALL);
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut runner = TestRunner::new(Config {
+        cases: 16,
+        failure_persistence: None,
+        ..Config::default()
+    });
+    let ops = proptest::collection::vec(op(), 1..24);
+    if let Err(error) = runner.run(&ops, |ops| runtime.block_on(run(&ops))) {
+        match error {
+            TestError::Fail(reason, ops) => {
+                panic!("{reason}\nminimized sequence:\n{ops:#?}")
+            }
+            TestError::Abort(reason) => panic!("{reason}"),
+        }
+    }
+}